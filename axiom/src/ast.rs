@@ -1,20 +1,28 @@
 /// Complete AST definitions for Axiom language — Final Maturation
+use serde::Serialize;
+
 use crate::errors::Span;
 
 // ---------------------------------------------------------------------------
 // Top-level items
 // ---------------------------------------------------------------------------
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub enum Item {
     FunctionDecl {
         name: String,
         params: Vec<String>,
+        /// Optional `: Type` per parameter, parallel to `params`.
+        param_types: Vec<Option<TypeAnnotation>>,
+        /// Optional `-> Type` after the parameter list.
+        ret_type: Option<TypeAnnotation>,
         body: Vec<Stmt>,
         span: Span,
     },
     ClassDecl {
         name: String,
         parent: Option<String>,
+        /// Interfaces named in an `impl A, B` clause
+        interfaces: Vec<String>,
         body: Vec<ClassMember>,
         span: Span,
     },
@@ -23,20 +31,47 @@ pub enum Item {
         variants: Vec<EnumVariant>,
         span: Span,
     },
+    InterfaceDecl {
+        name: String,
+        methods: Vec<InterfaceMethod>,
+        span: Span,
+    },
+    /// `loc utils` binds the module's exports as the map `utils`; `loc utils as u`
+    /// names the map `u`; `loc utils { f, g }` binds only `f` and `g`.
+    /// `loc plot if "plotting"` only imports when that package feature is on.
     LocImport {
         name: String,
+        alias: Option<String>,
+        names: Option<Vec<String>>,
+        feature: Option<String>,
+        span: Span,
+    },
+    /// `std mth as m` or `std mth { sqrt, pow }`: rename an intrinsic module
+    /// or bring some of its functions into scope by their own names.
+    StdImport {
+        module: String,
+        alias: Option<String>,
+        names: Option<Vec<String>>,
         span: Span,
     },
     LibDecl {
         name: String,
         span: Span,
     },
+    /// `exp a, b`: the names a module makes visible to files that `load` it.
+    /// A module without any `exp` exports every top-level name.
+    Export {
+        names: Vec<String>,
+        span: Span,
+    },
     LoadStmt {
         path: String,
         is_lib: bool,  // true if loading from @user/lib format
         /// Optional short alias registered alongside the full path.
         /// Syntax: load "path"; //alias: shortname
         alias: Option<String>,
+        /// `load "path" if "feature"`: the package feature the load needs
+        feature: Option<String>,
         span: Span,
     },
     Statement(Stmt),
@@ -45,57 +80,158 @@ pub enum Item {
 // ---------------------------------------------------------------------------
 // Class members
 // ---------------------------------------------------------------------------
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub enum ClassMember {
     Method {
         name: String,
         params: Vec<String>,
+        param_types: Vec<Option<TypeAnnotation>>,
+        ret_type: Option<TypeAnnotation>,
         body: Vec<Stmt>,
+        /// `static fun`: called on the class, without `self`
+        is_static: bool,
         span: Span,
     },
     Field {
         name: String,
+        ty: Option<TypeAnnotation>,
         default: Option<Expr>,
+        /// `static let`: one value stored on the class, not per instance
+        is_static: bool,
+        span: Span,
+    },
+    /// `get name() { }` runs on `obj.name`; `set name(v) { }` on `obj.name = v`
+    Accessor {
+        name: String,
+        kind: AccessorKind,
+        params: Vec<String>,
+        body: Vec<Stmt>,
         span: Span,
     },
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum AccessorKind {
+    Get,
+    Set,
+}
+
+impl ClassMember {
+    pub fn is_static(&self) -> bool {
+        match self {
+            ClassMember::Method { is_static, .. } | ClassMember::Field { is_static, .. } => *is_static,
+            ClassMember::Accessor { .. } => false,
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Type annotations
+// ---------------------------------------------------------------------------
+/// Optional static type written by the user: `Num`, `List<Str>`, `Point`.
+/// Only `axiom chk` reads these; they are erased at runtime.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TypeAnnotation {
+    pub name: String,
+    pub args: Vec<TypeAnnotation>,
+    pub span: Span,
+}
+
 // ---------------------------------------------------------------------------
 // Enum variants
 // ---------------------------------------------------------------------------
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct EnumVariant {
     pub name: String,
     pub has_data: bool, // true if Variant(inner)
     pub span: Span,
 }
 
+// ---------------------------------------------------------------------------
+// Interface methods
+// ---------------------------------------------------------------------------
+/// A method an `ifc` requires implementing classes to define.
+#[derive(Debug, Clone, Serialize)]
+pub struct InterfaceMethod {
+    pub name: String,
+    pub params: Vec<String>,
+    pub span: Span,
+}
+
 // ---------------------------------------------------------------------------
 // Match arm
 // ---------------------------------------------------------------------------
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct MatchArm {
     pub pattern: MatchPattern,
+    /// `pattern if cond =>`: the arm is taken only when `cond` holds
+    pub guard: Option<Expr>,
     pub body: Vec<Stmt>,
     pub span: Span,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub enum MatchPattern {
     Identifier(String),
     EnumVariant { enum_name: Option<String>, variant: String, binding: Option<String> },
     Literal(Expr),
     Wildcard,
+    /// `a | b | c`: matches when any alternative does
+    Or(Vec<MatchPattern>),
+    /// `lo..hi`: a number (or string) with `lo <= value < hi`
+    Range { start: Expr, end: Expr },
+    /// `[a, b]` matches a list of exactly those items; `[a, ..rest]` one of
+    /// at least that many, with `rest` (a name or `_`) bound to the tail
+    List { items: Vec<MatchPattern>, rest: Option<Box<MatchPattern>> },
+}
+
+impl MatchPattern {
+    /// Whether this pattern matches every value.
+    pub fn is_catch_all(&self) -> bool {
+        match self {
+            MatchPattern::Wildcard | MatchPattern::Identifier(_) => true,
+            MatchPattern::Or(alts) => alts.iter().any(MatchPattern::is_catch_all),
+            MatchPattern::EnumVariant { .. } | MatchPattern::Literal(_)
+            | MatchPattern::Range { .. } | MatchPattern::List { .. } => false,
+        }
+    }
+
+    /// The variables a match binds, first occurrence first: `true` for a
+    /// variant's payload, `false` for the matched value itself (inside a
+    /// list pattern, for the element it matched).
+    pub fn bindings(&self) -> Vec<(&str, bool)> {
+        let mut out: Vec<(&str, bool)> = Vec::new();
+        self.collect_bindings(&mut out);
+        out
+    }
+
+    fn collect_bindings<'a>(&'a self, out: &mut Vec<(&'a str, bool)>) {
+        let found = match self {
+            MatchPattern::Identifier(name) => (name.as_str(), false),
+            MatchPattern::EnumVariant { binding: Some(name), .. } => (name.as_str(), true),
+            MatchPattern::Or(alts) => {
+                for alt in alts { alt.collect_bindings(out); }
+                return;
+            }
+            MatchPattern::List { items, rest } => {
+                for item in items.iter().chain(rest.as_deref()) { item.collect_bindings(out); }
+                return;
+            }
+            _ => return,
+        };
+        if !out.iter().any(|(name, _)| *name == found.0) { out.push(found); }
+    }
 }
 
 // ---------------------------------------------------------------------------
 // Statements
 // ---------------------------------------------------------------------------
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub enum Stmt {
     Expr(Expr),
     Let {
         name: String,
+        ty: Option<TypeAnnotation>,
         value: Expr,
         span: Span,
     },
@@ -139,13 +275,19 @@ pub enum Stmt {
 // ---------------------------------------------------------------------------
 // Expressions
 // ---------------------------------------------------------------------------
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub enum Expr {
     Number { value: f64, span: Span },
     String { value: String, span: Span },
     Boolean { value: bool, span: Span },
     Identifier { name: String, span: Span },
     SelfRef { span: Span },
+    /// `super.method(args)`: the parent class's `method`, run on the current `self`
+    SuperCall {
+        method: String,
+        arguments: Vec<Expr>,
+        span: Span,
+    },
     List { items: Vec<Expr>, span: Span },
     BinaryOp {
         left: Box<Expr>,
@@ -198,13 +340,51 @@ pub enum Expr {
         body: Vec<Stmt>,
         span: Span,
     },
+    /// `match` in expression position: the value of the taken arm's last
+    /// expression statement, nil if no arm matches
+    Match {
+        expr: Box<Expr>,
+        arms: Vec<MatchArm>,
+        span: Span,
+    },
 }
 
 /// Parts of an interpolated string: literal text or embedded expression.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub enum StringPart {
     Literal(String),
     Expr(Expr),
+    /// `@(expr:spec)`: the value formatted as `str.fmt` formats `{:spec}`;
+    /// the parser has checked that the spec parses
+    Formatted { expr: Expr, spec: String },
+}
+
+impl StringPart {
+    /// The embedded expression, formatted or not.
+    pub fn expr(&self) -> Option<&Expr> {
+        match self {
+            StringPart::Literal(_) => None,
+            StringPart::Expr(e) | StringPart::Formatted { expr: e, .. } => Some(e),
+        }
+    }
+}
+
+impl Stmt {
+    /// Where the statement is; a bare block takes its first statement's span.
+    pub fn span(&self) -> Span {
+        match self {
+            Stmt::Expr(e) => e.span(),
+            Stmt::Block(stmts) => stmts.first().map(Stmt::span).unwrap_or_default(),
+            Stmt::Let { span, .. }
+            | Stmt::Return { span, .. }
+            | Stmt::If { span, .. }
+            | Stmt::While { span, .. }
+            | Stmt::For { span, .. }
+            | Stmt::GoSpawn { span, .. }
+            | Stmt::Match { span, .. }
+            | Stmt::Out { span, .. } => *span,
+        }
+    }
 }
 
 impl Expr {
@@ -215,6 +395,7 @@ impl Expr {
             | Expr::Boolean { span, .. }
             | Expr::Identifier { span, .. }
             | Expr::SelfRef { span }
+            | Expr::SuperCall { span, .. }
             | Expr::List { span, .. }
             | Expr::BinaryOp { span, .. }
             | Expr::UnaryOp { span, .. }
@@ -225,7 +406,8 @@ impl Expr {
             | Expr::Assign { span, .. }
             | Expr::New { span, .. }
             | Expr::InterpolatedString { span, .. }
-            | Expr::Lambda { span, .. } => *span,
+            | Expr::Lambda { span, .. }
+            | Expr::Match { span, .. } => *span,
         }
     }
 }
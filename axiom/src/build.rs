@@ -0,0 +1,231 @@
+//! Project Builds (`axiom build`)
+//!
+//! Starting from the entry point named in a project's Axiomite.toml, parses
+//! and checks every module the program reaches through `load` and `loc`, and
+//! bundles their sources into one `.axc` file that `axiom run` accepts.
+//! Modules outside the project (installed packages, `AXIOM_PATH`) are left
+//! out and resolved as usual when the bundle runs.
+
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::ast::Item;
+use crate::errors::{Diagnostic, DiagnosticLevel, ParserError};
+use crate::pkg::AxiomiteConfig;
+
+/// A built project: the sources of all its modules, keyed by their path
+/// from the project root, and which of them the program starts from.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Bundle {
+    pub format: u32,
+    pub package: String,
+    pub version: String,
+    pub entry: String,
+    pub modules: BTreeMap<String, String>,
+}
+
+impl Bundle {
+    /// Bumped whenever the layout changes; `read` rejects other versions.
+    pub const FORMAT: u32 = 1;
+
+    pub fn read(path: &Path) -> Result<Bundle, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("Cannot read '{}': {}", path.display(), e))?;
+        let bundle: Bundle = serde_json::from_str(&text).map_err(|e| format!("'{}' is not an Axiom bundle: {}", path.display(), e))?;
+        if bundle.format != Self::FORMAT {
+            return Err(format!("'{}' is bundle format {}; this axiom reads format {}", path.display(), bundle.format, Self::FORMAT));
+        }
+        Ok(bundle)
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), String> {
+        let json = serde_json::to_string(self).map_err(|e| e.to_string())?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| format!("Cannot create '{}': {}", dir.display(), e))?;
+        }
+        std::fs::write(path, json).map_err(|e| format!("Cannot write '{}': {}", path.display(), e))
+    }
+
+    /// Write every module under `dir`, recreating the project layout, and
+    /// return the entry script's path.
+    pub fn unpack(&self, dir: &Path) -> Result<PathBuf, String> {
+        for (rel, source) in &self.modules {
+            let file = dir.join(rel);
+            if let Some(parent) = file.parent() {
+                std::fs::create_dir_all(parent).map_err(|e| format!("Cannot create '{}': {}", parent.display(), e))?;
+            }
+            std::fs::write(&file, source).map_err(|e| format!("Cannot write '{}': {}", file.display(), e))?;
+        }
+        Ok(dir.join(&self.entry))
+    }
+}
+
+/// One module of a build and what checking it found.
+pub struct ModuleReport {
+    /// Path from the project root, with `/` separators
+    pub path: String,
+    pub source: String,
+    pub parse_errors: Vec<ParserError>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// The outcome of `build_project`: the manifest, every module reached from
+/// the entry point (entry first), and the imports left to resolve at run time.
+pub struct BuildReport {
+    pub root: PathBuf,
+    pub config: AxiomiteConfig,
+    pub modules: Vec<ModuleReport>,
+    pub external: Vec<String>,
+}
+
+impl BuildReport {
+    pub fn errors(&self) -> usize {
+        self.modules.iter()
+            .map(|m| m.parse_errors.len() + m.diagnostics.iter().filter(|d| matches!(d.level, DiagnosticLevel::Error)).count())
+            .sum()
+    }
+
+    pub fn warnings(&self) -> usize {
+        self.modules.iter()
+            .map(|m| m.diagnostics.iter().filter(|d| matches!(d.level, DiagnosticLevel::Warning)).count())
+            .sum()
+    }
+
+    pub fn bundle(&self) -> Bundle {
+        Bundle {
+            format: Bundle::FORMAT,
+            package: self.config.package.name.clone(),
+            version: self.config.package.version.clone(),
+            entry: self.config.build.entry.clone(),
+            modules: self.modules.iter().map(|m| (m.path.clone(), m.source.clone())).collect(),
+        }
+    }
+
+    /// Where the bundle goes: `<target>/<package>.axc` under the root.
+    pub fn bundle_path(&self) -> PathBuf {
+        self.root.join(&self.config.build.target).join(format!("{}.axc", self.config.package.name))
+    }
+}
+
+/// Read `root/Axiomite.toml` and parse and check each module reachable from
+/// its entry point. Imports resolve as when the entry is run from `root`.
+pub fn build_project(root: &Path) -> Result<BuildReport, String> {
+    let manifest = root.join("Axiomite.toml");
+    let config = AxiomiteConfig::from_file(&manifest).map_err(|e| format!("Cannot read '{}': {}", manifest.display(), e))?;
+    let canonical_root = root.canonicalize().map_err(|e| format!("Cannot open '{}': {}", root.display(), e))?;
+    let entry = canonical_root.join(&config.build.entry);
+    if !entry.is_file() {
+        return Err(format!("Entry point '{}' not found", entry.display()));
+    }
+
+    let mut modules = Vec::new();
+    let mut external = Vec::new();
+    let mut seen = HashSet::from([entry.clone()]);
+    let mut queue = VecDeque::from([entry]);
+    while let Some(file) = queue.pop_front() {
+        let source = std::fs::read_to_string(&file).map_err(|e| format!("Cannot read '{}': {}", file.display(), e))?;
+        let (items, parse_errors) = crate::Parser::new(&source, 0).parse_recovering();
+        let is_entry = modules.is_empty();
+        // The entry's `loc`s start at the root, a module's at its own directory
+        let dir = if is_entry { canonical_root.clone() } else { file.parent().unwrap_or(&canonical_root).to_path_buf() };
+        let mut diagnostics = Vec::new();
+        if parse_errors.is_empty() {
+            let mut chk = crate::SemanticAnalyzer::new();
+            chk.set_module_dir(&dir);
+            diagnostics = chk.check(&items);
+            for import in imports(&items, &dir, &canonical_root) {
+                match import {
+                    Ok(found) if !found.starts_with(&canonical_root) => external.push(found.display().to_string()),
+                    Ok(found) => if seen.insert(found.clone()) { queue.push_back(found) },
+                    Err(spec) => external.push(spec),
+                }
+            }
+        }
+        let path = file.strip_prefix(&canonical_root).unwrap_or(&file)
+            .components().map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/");
+        modules.push(ModuleReport { path, source, parse_errors, diagnostics });
+    }
+    external.sort();
+    external.dedup();
+    Ok(BuildReport { root: root.to_path_buf(), config, modules, external })
+}
+
+/// The files a module's `load`s and `loc`s name, canonicalized, or the
+/// import as written when it cannot be found here (a package, or a module
+/// `chk` has already reported missing).
+fn imports(items: &[Item], dir: &Path, root: &Path) -> Vec<Result<PathBuf, String>> {
+    items.iter().filter_map(|item| {
+        let found = match item {
+            Item::LoadStmt { path, is_lib: true, .. } => return Some(Err(path.clone())),
+            Item::LoadStmt { path, .. } => Ok(root.join(path)),
+            Item::LocImport { name, .. } => crate::loader::resolve_loc(name, dir).map_err(|_| name.clone()),
+            _ => return None,
+        };
+        Some(found.and_then(|file| file.canonicalize().map_err(|_| file.display().to_string())))
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn project(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("axiom-build-{}-{}", name, std::process::id()));
+        for (rel, source) in files {
+            let file = root.join(rel);
+            std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+            std::fs::write(file, source).unwrap();
+        }
+        root
+    }
+
+    #[test]
+    fn test_build_bundles_reachable_modules() {
+        let root = project("ok", &[
+            ("Axiomite.toml", "[package]\nname = \"app\"\nversion = \"0.2.0\"\n\n[build]\nentry = \"src/main.ax\"\n"),
+            ("src/main.ax", "loc src.net.auth as auth\nout auth.check(2);\n"),
+            ("src/net/auth.ax", "exp check\nloc ..util { twice }\nfun check(x) { ret twice(x) + 1 }\n"),
+            ("src/util.ax", "exp twice\nfun twice(x) { ret x * 2 }\n"),
+            ("src/unused.ax", "out \"never\";\n"),
+        ]);
+        let report = build_project(&root).unwrap();
+        let bundle = report.bundle();
+        let unpacked = root.join("unpacked");
+        let entry = bundle.unpack(&unpacked).unwrap();
+        let source = std::fs::read_to_string(&entry).unwrap();
+        let mut rt = crate::Runtime::new();
+        rt.set_module_root(&unpacked);
+        let items = crate::Parser::new(&source, 0).parse().unwrap();
+        let (result, output) = rt.run_captured(items);
+        std::fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(report.errors(), 0);
+        assert_eq!(bundle.entry, "src/main.ax");
+        assert_eq!(bundle.modules.keys().collect::<Vec<_>>(), ["src/main.ax", "src/net/auth.ax", "src/util.ax"]);
+        assert_eq!(report.bundle_path(), root.join("target/app.axc"));
+        result.unwrap();
+        assert_eq!(output, "5\n");
+    }
+
+    #[test]
+    fn test_build_reports_diagnostics_per_module() {
+        let root = project("errors", &[
+            ("Axiomite.toml", "[package]\nname = \"bad\"\nversion = \"0.1.0\"\n"),
+            ("main.ax", "loc helpers\nloc broken\nload @someone/pkg\nout helpers.f();\n"),
+            ("helpers.ax", "fun f() { ret missing }\n"),
+            ("broken.ax", "loc never\nfun (\n"),
+        ]);
+        let report = build_project(&root).unwrap();
+        std::fs::remove_dir_all(&root).unwrap();
+
+        let module = |path: &str| report.modules.iter().find(|m| m.path == path).unwrap();
+        assert_eq!(report.modules.len(), 3);
+        assert!(module("helpers.ax").diagnostics.iter().any(|d| d.message.contains("missing")));
+        // A module with syntax errors is not followed into its imports
+        assert!(!module("broken.ax").parse_errors.is_empty());
+        assert_eq!(report.external, ["@someone/pkg"]);
+        assert!(report.errors() >= 2);
+        assert!(build_project(Path::new("/nonexistent-axiom-project")).is_err());
+    }
+}
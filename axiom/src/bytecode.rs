@@ -10,12 +10,17 @@
 /// Register 0..N are locals + temporaries in the current frame.
 /// Register 255 is a scratch/result accumulator.
 ///
-/// SUPERINSTRUCTIONS (compound opcodes for hot patterns):
-///   AddIntImm   = LoadInt(r) + Add(dst, src, r)     → dst = src + imm
+/// SUPERINSTRUCTIONS (compound opcodes for hot patterns, made by the
+/// optimizer's fusion pass; sC is the C byte read as a signed i8):
+///   AddIntImm   = LoadInt(r) + Add(dst, src, r)     → dst = src + sC
+///   CmpLtJmp    = Lt(r, x, y) + JumpFalse(r)        → if !(x < y): ip += sC
+///   Return src  = Move(r, src) + Return(r)
 ///   IncrLocal   = dst = dst + 1                     → common loop counter
-///   LoadNilRet  = LoadNil + Return                  → empty function return
-///   CmpJmpTrue  = Eq/Lt/Le + JumpIfFalse            → loop condition
-///   CallNoRet   = Call where result ignored
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::intern::Sym;
 
 #[repr(u8)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -93,8 +98,8 @@ pub enum Op {
     CallNative = 48, // A, B, C  — A=ret, B=native_idx, C=argc; args in R[A+1..A+C]
 
     // ── Property Access with Inline Cache ─────────────────────────────────────
-    GetProp    = 49, // A, B, Bx → R[A] = R[B].S[Bx]  (IC site)
-    SetProp    = 50, // A, B, Bx → R[A].S[Bx] = R[B]  (IC site)
+    GetProp    = 49, // A, Bx    → R[A] = R[A].S[Bx]  (IC site)
+    SetProp    = 50, // A, B, C  → R[A].R[B] = R[C]   (IC site)
     GetIndex   = 51, // A, B, C  → R[A] = R[B][R[C]]
     SetIndex   = 52, // A, B, C  → R[A][R[B]] = R[C]
 
@@ -108,15 +113,15 @@ pub enum Op {
     NewObj     = 57, // A, Bx   → R[A] = new class[Bx]()
     GetSelf    = 58, // A       → R[A] = self (frame.self_val)
     SetSelf    = 59, // A       → frame.self_val = R[A]
-    GetMethod  = 60, // A, B, Bx → R[A] = R[B].method[Bx] (bound method lookup + IC)
+    GetMethod  = 60, // A, Bx    → R[A] = R[A].method[Bx] (bound method lookup + IC)
 
     // ── Closures ─────────────────────────────────────────────────────────────
     Closure    = 61, // A, Bx   → R[A] = closure(proto[Bx])
     CloseUpval = 62, // A       → close upvalue R[A]
 
     // ── Superinstructions (hot-pattern compound opcodes) ─────────────────────
-    /// R[A] = R[B] + imm16  (add integer immediate — eliminates LoadInt for counters)
-    AddIntImm  = 63, // A, B, sBx  → R[A] = R[B] + sBx
+    /// R[A] = R[B] + imm8  (add integer immediate — eliminates LoadInt for counters)
+    AddIntImm  = 63, // A, B, sC   → R[A] = R[B] + sC
     /// R[A] = R[A] + 1  (loop counter increment — ultra-compact)
     IncrLocal  = 64, // A          → R[A] = R[A] + 1
     /// R[A] = R[A] - 1
@@ -124,7 +129,7 @@ pub enum Op {
     /// Load nil + Return  (zero-overhead empty function)
     NilReturn  = 66, //
     /// if !(R[A] < R[B]): jump  (loop exit test — cmp + branch fused)
-    CmpLtJmp   = 67, // A, B, sBx  → if R[A] >= R[B]: ip += sBx
+    CmpLtJmp   = 67, // A, B, sC   → if !(R[A] < R[B]): ip += sC
     /// Call + store in same register (avoids Move after call)
     CallStore  = 68, // A, B, C
     /// Concatenate + store result (string building)
@@ -141,6 +146,23 @@ pub enum Op {
     Halt       = 73,
     // Quickening markers (used during adaptive specialization)
     Unquicken  = 74, // Restore generic opcode (deopt)
+
+    // ── Closures (cont.) ─────────────────────────────────────────────────────
+    LoadCallee = 75, // A       → R[A] = the function running in this frame
+
+    // ── Enums ────────────────────────────────────────────────────────────────
+    NewEnum    = 76, // A, Bx   → R[A] = V[Bx](R[A])  (payload in R[A]; nil → none)
+    MatchEnum  = 77, // A, Bx   → ip += T[Bx][variant of R[A]]  (jump table)
+    EnumPayload= 78, // A, B    → R[A] = payload of R[B]
+
+    // ── List patterns ────────────────────────────────────────────────────────
+    IsListLen  = 79, // A, B, C → R[A] = R[B] is a list of exactly C items
+    IsListMin  = 80, // A, B, C → R[A] = R[B] is a list of at least C items
+    ListTail   = 81, // A, B, C → R[A] = R[B][C..]
+
+    // ── Large list literals ──────────────────────────────────────────────────
+    ListReserve= 82, // A, Bx   → make room in R[A] for Bx more items
+    ListExtend = 83, // A, B, C → R[A].extend(R[B]..R[B+C])
 }
 
 impl Op {
@@ -183,7 +205,11 @@ impl Op {
             Op::CallStore => "CallStore",   Op::ConcatStore => "ConcatStore",
             Op::Profile => "Profile",       Op::LoopBack => "LoopBack",
             Op::Nop => "Nop",               Op::Halt => "Halt",
-            Op::Unquicken => "Unquicken",
+            Op::Unquicken => "Unquicken",   Op::LoadCallee => "LoadCallee",
+            Op::NewEnum => "NewEnum",       Op::MatchEnum => "MatchEnum",
+            Op::EnumPayload => "EnumPayload", Op::IsListLen => "IsListLen",
+            Op::IsListMin => "IsListMin",   Op::ListTail => "ListTail",
+            Op::ListReserve => "ListReserve", Op::ListExtend => "ListExtend",
         }
     }
 
@@ -266,6 +292,12 @@ impl Instr {
     
     #[inline] pub fn get_ax(self) -> u32 { self.0 >> 8 }
 
+    // iABsC: op=8, A=8, B=8, sC=8 (signed, two's complement)
+    #[inline] pub fn absc(op: Op, a: u8, b: u8, sc: i8) -> Self {
+        Instr::abc(op, a, b, sc as u8)
+    }
+    #[inline] pub fn sc(self) -> i8 { self.c() as i8 }
+
     // Fixed: Cast to i32 first to handle the bias subtraction safely
     #[inline] pub fn get_sbx(self) -> i16 {
         let raw = ((self.0 >> 16) & 0xFFFF) as u16;
@@ -277,6 +309,31 @@ impl Instr {
         let biased = (sbx as i32 + 32768) as u16;
         self.0 = (self.0 & 0x0000_FFFF) | ((biased as u32) << 16);
     }
+
+    /// For a jump or branch, its offset from the next instruction: sBx, or
+    /// sC for the fused compare-and-branch. `None` for everything else
+    /// (a MatchEnum's offsets are in its jump table).
+    pub fn jump_offset(self) -> Option<i32> {
+        match self.op() {
+            Op::Jump | Op::JumpTrue | Op::JumpFalse | Op::JumpNil | Op::JumpNotNil | Op::LoopBack => Some(self.get_sbx() as i32),
+            Op::CmpLtJmp => Some(self.sc() as i32),
+            _ => None,
+        }
+    }
+
+    /// Point a jump somewhere else; false if `offset` does not fit its field.
+    pub fn set_jump_offset(&mut self, offset: i32) -> bool {
+        match self.op() {
+            Op::CmpLtJmp => match i8::try_from(offset) {
+                Ok(sc) => { *self = Instr::absc(Op::CmpLtJmp, self.a(), self.b(), sc); true }
+                Err(_) => false,
+            },
+            _ => match i16::try_from(offset) {
+                Ok(sbx) if self.jump_offset().is_some() => { self.patch_sbx(sbx); true }
+                _ => false,
+            },
+        }
+    }
 }
 
 impl std::fmt::Debug for Instr {
@@ -299,10 +356,15 @@ pub struct Proto {
     pub code: Vec<Instr>,
     /// Floating-point constant pool (indexed by LoadFloat/LoadConst Bx)
     pub float_consts: Vec<f64>,
-    /// String constant pool (indexed by LoadStr Bx)
-    pub str_consts: Vec<String>,
+    /// String constant pool (indexed by LoadStr Bx); shared with the
+    /// interner after `intern_strings`
+    pub str_consts: Vec<Arc<str>>,
     /// Nested function prototypes (indexed by Closure Bx)
     pub protos: Vec<Proto>,
+    /// Enum variant pool (indexed by NewEnum Bx), as qualified `Enum.Variant` symbols
+    pub variants: Vec<Sym>,
+    /// `match` jump tables (indexed by MatchEnum Bx)
+    pub jump_tables: Vec<JumpTable>,
     /// Number of register slots (locals + temporaries)
     pub reg_count: u8,
     /// Number of parameters
@@ -321,6 +383,15 @@ pub struct Proto {
     pub counters: Vec<u32>,
 }
 
+/// Where a `match` on enum variants goes: for each variant, the offset
+/// (from the instruction after MatchEnum, like a jump) of the first arm
+/// that takes it, and `default` for everything else.
+#[derive(Debug, Clone, Default)]
+pub struct JumpTable {
+    pub targets: HashMap<Sym, i32>,
+    pub default: i32,
+}
+
 #[derive(Debug, Clone)]
 pub struct UpvalDesc {
     pub name: String,
@@ -335,6 +406,8 @@ impl Proto {
             float_consts: Vec::new(),
             str_consts: Vec::new(),
             protos: Vec::new(),
+            variants: Vec::new(),
+            jump_tables: Vec::new(),
             reg_count: 0,
             param_count: 0,
             upval_count: 0,
@@ -377,96 +450,90 @@ impl Proto {
     }
 
     /// Add string constant, return index
-    pub fn add_string(&mut self, s: impl Into<String>) -> u16 {
-        let s = s.into();
+    pub fn add_string(&mut self, s: impl AsRef<str>) -> u16 {
+        let s = s.as_ref();
         for (i, v) in self.str_consts.iter().enumerate() {
-            if *v == s { return i as u16; }
+            if &**v == s { return i as u16; }
         }
-        self.str_consts.push(s);
+        self.str_consts.push(Arc::from(s));
         (self.str_consts.len() - 1) as u16
     }
 
+    /// Add enum variant, return index
+    pub fn add_variant(&mut self, qualified: &str) -> u16 {
+        let sym = Sym::new(qualified);
+        if let Some(i) = self.variants.iter().position(|&v| v == sym) { return i as u16; }
+        self.variants.push(sym);
+        (self.variants.len() - 1) as u16
+    }
+
+    /// Swap every string constant (here and in nested protos) for its
+    /// interned allocation, so loads share it with identifiers and property
+    /// names elsewhere and equal strings compare by pointer.
+    pub fn intern_strings(&mut self) {
+        for s in &mut self.str_consts {
+            *s = crate::intern::intern(s);
+        }
+        for p in &mut self.protos {
+            p.intern_strings();
+        }
+    }
+
     /// Pretty-print disassembly
     pub fn disassemble(&self, name: &str) {
-        println!("=== {} ({} regs, {} params) ===", name, self.reg_count, self.param_count);
+        print!("{}", self.disassembly(name));
+    }
+
+    /// The listing `disassemble` prints: instructions, constant pools, then
+    /// nested prototypes.
+    pub fn disassembly(&self, name: &str) -> String {
+        use std::fmt::Write;
+        let mut out = String::new();
+        let _ = writeln!(out, "=== {} ({} regs, {} params) ===", name, self.reg_count, self.param_count);
         for (i, instr) in self.code.iter().enumerate() {
             let line = self.line_info.get(i).copied().unwrap_or(0);
             let count = self.counters.get(i).copied().unwrap_or(0);
-            println!("  {:4}  [{:4}]  {:?}  (exec:{})", i, line, instr, count);
-        }
-        println!("  float_consts: {:?}", self.float_consts);
-        println!("  str_consts:   {:?}", self.str_consts);
-        for (i, p) in self.protos.iter().enumerate() {
-            p.disassemble(&format!("{}.proto[{}]", name, i));
+            let _ = writeln!(out, "  {:4}  [{:4}]  {:?}  (exec:{})", i, line, instr, count);
         }
-    }
-}
-
-// ---------------------------------------------------------------------------
-// Superinstruction patterns (mined from common sequences)
-// ---------------------------------------------------------------------------
-
-/// Known superinstruction fusion patterns.
-/// If we see sequence [a, b], replace with super.
-pub const SUPER_PATTERNS: &[(&[Op], Op)] = &[
-    (&[Op::LoadNil, Op::Return],     Op::NilReturn),
-    (&[Op::Add, Op::Jump],           Op::Add),       // not a super but placeholder
-    (&[Op::Lt,  Op::JumpFalse],      Op::CmpLtJmp),
-    (&[Op::Le,  Op::JumpFalse],      Op::CmpLtJmp),
-    (&[Op::IncrLocal, Op::CmpLtJmp], Op::IncrLocal), // keep — just record pattern
-];
-
-/// Mine and apply superinstructions to a proto in-place.
-pub fn apply_superinstructions(proto: &mut Proto) {
-    let len = proto.code.len();
-    if len < 2 { return; }
-
-    let mut i = 0;
-    while i + 1 < len {
-        let op1 = proto.code[i].op();
-        let op2 = proto.code[i + 1].op();
-
-        // Pattern: LoadInt A, sBx followed by Add C, D, A
-        // → replace with AddIntImm C, D, sBx
-        if op1 == Op::LoadInt && op2 == Op::Add {
-            let reg_a  = proto.code[i].a();
-            let imm    = proto.code[i].get_sbx();
-            let dst    = proto.code[i+1].a();
-            let src_b  = proto.code[i+1].b();
-            let src_c  = proto.code[i+1].c();
-            // Only fuse if the loaded int is used as one of the Add operands
-            if src_c == reg_a {
-                proto.code[i] = Instr::asbx(Op::AddIntImm, dst, imm);
-                proto.code[i+1] = Instr::abc(Op::Nop, src_b, 0, 0);
-                // patch: Move src_b into dst+1 implicit
-            }
+        let _ = writeln!(out, "  float_consts: {:?}", self.float_consts);
+        let _ = writeln!(out, "  str_consts:   {:?}", self.str_consts);
+        if !self.variants.is_empty() {
+            let _ = writeln!(out, "  variants:     {:?}", self.variants.iter().map(|v| v.as_str()).collect::<Vec<_>>());
         }
-
-        // Pattern: Lt A, B, C followed by JumpFalse A, sBx
-        // → replace with CmpLtJmp B, C, sBx; nop
-        if op1 == Op::Lt && op2 == Op::JumpFalse {
-            let cmp_a = proto.code[i].a();
-            let b     = proto.code[i].b();
-            let c     = proto.code[i].c();
-            let jmp_a = proto.code[i+1].a();
-            let sbx   = proto.code[i+1].get_sbx();
-            if cmp_a == jmp_a {
-                proto.code[i]   = Instr::asbx(Op::CmpLtJmp, b, sbx);
-                proto.code[i].0 |= (c as u32) << 24;
-                proto.code[i+1] = Instr::abc(Op::Nop, 0, 0, 0);
-            }
+        for (i, table) in self.jump_tables.iter().enumerate() {
+            let mut targets: Vec<_> = table.targets.iter().map(|(v, off)| (v.as_str(), *off)).collect();
+            targets.sort();
+            let _ = writeln!(out, "  jump_table[{}]: {:?} else {:+}", i, targets, table.default);
         }
-
-        // Pattern: LoadNil + Return → NilReturn
-        if op1 == Op::LoadNil && op2 == Op::Return {
-            let a = proto.code[i].a();
-            let ret_a = proto.code[i+1].a();
-            if a == ret_a {
-                proto.code[i]   = Instr::abc(Op::NilReturn, 0, 0, 0);
-                proto.code[i+1] = Instr::abc(Op::Nop, 0, 0, 0);
-            }
+        for (i, p) in self.protos.iter().enumerate() {
+            out.push_str(&p.disassembly(&format!("{}.proto[{}]", name, i)));
         }
+        out
+    }
 
-        i += 1;
+    /// The same listing as JSON, one object per instruction with decoded operands.
+    pub fn to_json(&self, name: &str) -> serde_json::Value {
+        let code: Vec<_> = self.code.iter().enumerate().map(|(i, instr)| serde_json::json!({
+            "op": instr.op().name(),
+            "a": instr.a(), "b": instr.b(), "c": instr.c(),
+            "bx": instr.bx(), "sbx": instr.get_sbx(),
+            "line": self.line_info.get(i).copied().unwrap_or(0),
+        })).collect();
+        serde_json::json!({
+            "name": name,
+            "registers": self.reg_count,
+            "params": self.param_count,
+            "code": code,
+            "float_consts": self.float_consts,
+            "str_consts": self.str_consts.iter().map(|s| &**s).collect::<Vec<_>>(),
+            "variants": self.variants.iter().map(|v| v.as_str().to_string()).collect::<Vec<_>>(),
+            "jump_tables": self.jump_tables.iter().map(|t| serde_json::json!({
+                "targets": t.targets.iter().map(|(v, off)| (v.as_str().to_string(), *off)).collect::<std::collections::BTreeMap<_, _>>(),
+                "default": t.default,
+            })).collect::<Vec<_>>(),
+            "protos": self.protos.iter().enumerate()
+                .map(|(i, p)| p.to_json(&format!("{}.proto[{}]", name, i)))
+                .collect::<Vec<_>>(),
+        })
     }
 }
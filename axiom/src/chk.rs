@@ -1,8 +1,13 @@
 /// Axiom Static Analyzer (chk) — Final Maturation
 /// Performs semantic analysis, symbol resolution, and type inference.
-use crate::ast::{Item, Stmt, Expr, MatchPattern, ClassMember};
+/// Optional annotations (`fun f(x: Num) -> Num`, `let n: Num = 5`) are checked
+/// gradually: anything unannotated or uninferable is `Any` and never errors.
+use crate::ast::{Item, Stmt, Expr, MatchArm, MatchPattern, ClassMember, TypeAnnotation};
+use crate::diagnostics::{closest_match, ErrorCode};
 use crate::errors::{Diagnostic, DiagnosticLevel, Span};
+use crate::intrinsics::{self, ArgKind};
 use std::collections::{HashMap, HashSet};
+use std::fmt;
 use std::path::PathBuf;
 
 // ---------------------------------------------------------------------------
@@ -22,12 +27,42 @@ pub enum AxType {
     Nil,
 }
 
+impl AxType {
+    /// Gradual assignability: `Any` on either side and `Nil` values always fit.
+    pub fn accepts(&self, found: &AxType) -> bool {
+        match (self, found) {
+            (AxType::Any, _) | (_, AxType::Any) | (_, AxType::Nil) => true,
+            (AxType::List(a), AxType::List(b)) | (AxType::Map(a), AxType::Map(b)) => a.accepts(b),
+            (AxType::Func { .. }, AxType::Func { .. }) => true,
+            (a, b) => a == b,
+        }
+    }
+}
+
+impl fmt::Display for AxType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AxType::Num => write!(f, "Num"),
+            AxType::Str => write!(f, "Str"),
+            AxType::Bool => write!(f, "Bool"),
+            AxType::List(t) => write!(f, "List<{}>", t),
+            AxType::Map(t) => write!(f, "Map<{}>", t),
+            AxType::Class(n) | AxType::Enum(n) => write!(f, "{}", n),
+            AxType::Func { .. } => write!(f, "Fun"),
+            AxType::Any => write!(f, "Any"),
+            AxType::Nil => write!(f, "Nil"),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Symbol {
     pub name: String,
     pub ty: AxType,
     pub span: Span,
     pub is_const: bool,
+    /// True when `ty` comes from a user annotation rather than inference.
+    pub annotated: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -36,6 +71,36 @@ pub struct Scope {
     pub parent: Option<usize>,
 }
 
+// ---------------------------------------------------------------------------
+// Imported modules
+// ---------------------------------------------------------------------------
+
+/// Read and parse the module behind a `load` path, if possible.
+fn parse_module_file(path: &str, is_lib: bool) -> Option<Vec<Item>> {
+    let (file, _) = crate::runtime::Runtime::module_path(path, is_lib).ok()?;
+    let source = std::fs::read_to_string(&file).ok()?;
+    let file = file.canonicalize().unwrap_or(file);
+    crate::Parser::new(&source, crate::loader::register_source(&file, &source)).parse().ok()
+}
+
+/// Each top-level function, class, enum and `let` of a module, with its type
+/// and whether the module exports it.
+fn module_symbols(items: &[Item]) -> Vec<(&Item, &str, AxType, bool)> {
+    let exported = crate::loader::exports(items);
+    items.iter().filter_map(|item| {
+        let (name, ty) = match item {
+            Item::FunctionDecl { name, params, .. } => {
+                (name, AxType::Func { params: vec![AxType::Any; params.len()], ret: Box::new(AxType::Any) })
+            }
+            Item::ClassDecl { name, .. } => (name, AxType::Class(name.clone())),
+            Item::EnumDecl { name, .. } => (name, AxType::Enum(name.clone())),
+            Item::Statement(Stmt::Let { name, .. }) => (name, AxType::Any),
+            _ => return None,
+        };
+        Some((item, name.as_str(), ty, exported.as_ref().is_none_or(|names| names.contains(name))))
+    }).collect()
+}
+
 // ---------------------------------------------------------------------------
 // Semantic Analyzer
 // ---------------------------------------------------------------------------
@@ -44,7 +109,18 @@ pub struct SemanticAnalyzer {
     pub current_scope: usize,
     pub diagnostics: Vec<Diagnostic>,
     pub classes: HashSet<String>,
-    pub enums: HashSet<String>,
+    /// Declared enums and their variant names, in declaration order.
+    pub enums: HashMap<String, Vec<String>>,
+    /// Declared interfaces and the (name, arity) of each required method.
+    pub interfaces: HashMap<String, Vec<(String, usize)>>,
+    /// Each class's parent and instance method arities, for `impl` checks.
+    class_methods: HashMap<String, (Option<String>, HashMap<String, usize>)>,
+    /// Declared return type of each enclosing function (innermost last).
+    return_types: Vec<AxType>,
+    /// Top-level names of `load`ed modules that they do not `exp`, with the module path.
+    hidden_imports: HashMap<String, String>,
+    /// Directory `loc` specs are resolved against (the checked file's, for a module).
+    module_dir: PathBuf,
 }
 
 impl SemanticAnalyzer {
@@ -74,19 +150,42 @@ impl SemanticAnalyzer {
                     ty,
                     span: Span::default(),
                     is_const: true,
+                    annotated: true,
                 },
             );
         }
 
+        // Register intrinsic modules (`str` doubles as a builtin function)
+        for module in intrinsics::MODULES {
+            global_scope.symbols.entry(module.to_string()).or_insert_with(|| Symbol {
+                name: module.to_string(),
+                ty: AxType::Map(Box::new(AxType::Any)),
+                span: Span::default(),
+                is_const: true,
+                annotated: false,
+            });
+        }
+
         SemanticAnalyzer {
             scopes: vec![global_scope],
             current_scope: 0,
             diagnostics: Vec::new(),
             classes: HashSet::new(),
-            enums: HashSet::new(),
+            enums: HashMap::new(),
+            interfaces: HashMap::new(),
+            class_methods: HashMap::new(),
+            return_types: Vec::new(),
+            hidden_imports: HashMap::new(),
+            module_dir: PathBuf::from("."),
         }
     }
 
+    /// Resolve `loc` imports against `dir` instead of the working directory,
+    /// as the runtime does for a module in `dir`.
+    pub fn set_module_dir(&mut self, dir: impl Into<PathBuf>) {
+        self.module_dir = dir.into();
+    }
+
     pub fn check(&mut self, items: &[Item]) -> Vec<Diagnostic> {
         // Pass 1: Collect top-level declarations (hoisting)
         self.collect_declarations(items);
@@ -100,72 +199,191 @@ impl SemanticAnalyzer {
     }
 
     fn collect_declarations(&mut self, items: &[Item]) {
+        // Type names first so function signatures can refer to any class/enum
         for item in items {
             match item {
-                Item::FunctionDecl { name, .. } => {
-                    self.define_symbol(name, AxType::Func { params: vec![], ret: Box::new(AxType::Any) }, Span::default());
+                Item::ClassDecl { name, parent, body, .. } => {
+                    self.classes.insert(name.clone());
+                    let methods = body.iter().filter_map(|m| match m {
+                        ClassMember::Method { name, params, is_static: false, .. } => Some((name.clone(), params.len())),
+                        _ => None,
+                    }).collect();
+                    self.class_methods.insert(name.clone(), (parent.clone(), methods));
+                }
+                Item::EnumDecl { name, variants, .. } => {
+                    self.enums.insert(name.clone(), variants.iter().map(|v| v.name.clone()).collect());
+                }
+                Item::InterfaceDecl { name, methods, .. } => {
+                    self.interfaces.insert(name.clone(), methods.iter().map(|m| (m.name.clone(), m.params.len())).collect());
+                }
+                _ => {}
+            }
+        }
+
+        for item in items {
+            match item {
+                Item::FunctionDecl { name, param_types, ret_type, span, .. } => {
+                    let ty = self.signature_type(param_types, ret_type);
+                    self.define_typed_symbol(name, ty, *span, true);
                 }
                 Item::ClassDecl { name, .. } => {
                     self.classes.insert(name.clone());
                     self.define_symbol(name, AxType::Class(name.clone()), Span::default());
                 }
                 Item::EnumDecl { name, .. } => {
-                    self.enums.insert(name.clone());
                     self.define_symbol(name, AxType::Enum(name.clone()), Span::default());
                 }
-                Item::LocImport { name, span } => {
-                    self.validate_local_path(name, *span);
+                Item::LocImport { name, alias, names, span, .. } => {
+                    self.import_loc(name, alias.as_deref(), names.as_deref(), *span);
+                }
+                Item::StdImport { module, alias, names, span } => {
+                    self.import_std(module, alias.as_deref(), names.as_deref(), *span);
+                }
+                Item::LoadStmt { path, is_lib, alias, .. } => {
+                    self.import_module(path, *is_lib, alias.as_deref());
+                }
+                Item::Export { names, span } => {
+                    let declared = crate::loader::top_level_names(items);
+                    for name in names.iter().filter(|name| !declared.contains(&name.as_str())) {
+                        self.diagnostics.push(Diagnostic {
+                            level: DiagnosticLevel::Error,
+                            code: ErrorCode::ExportNotFound,
+                            message: format!("Exported name '{}' is not declared in this module", name),
+                            span: *span,
+                            hint: Some("`exp` lists top-level functions, classes, enums and `let`s".to_string()),
+                            suggestion: closest_match(name, &declared, 2).map(str::to_string),
+                        });
+                    }
                 }
                 _ => {}
             }
         }
     }
 
-    fn validate_local_path(&mut self, name: &str, span: Span) {
-        let mut path = PathBuf::from(format!("{}.ax", name));
-        if !path.exists() {
-            path.set_extension("rax");
+    /// Bring the names a `load`ed module exports into scope, and remember the
+    /// ones it keeps private so using them is reported as such. A module that
+    /// cannot be read or parsed is left to the runtime to report.
+    fn import_module(&mut self, path: &str, is_lib: bool, alias: Option<&str>) {
+        if let Some(alias) = alias {
+            self.define_symbol(alias, AxType::Map(Box::new(AxType::Any)), Span::default());
+        }
+        let Some(items) = parse_module_file(path, is_lib) else { return };
+        for (item, name, ty, exported) in module_symbols(&items) {
+            if exported {
+                self.declare_imported(item, name, ty);
+            } else {
+                self.hidden_imports.insert(name.to_string(), path.to_string());
+            }
         }
-        if !path.exists() {
+    }
+
+    /// `loc name`: the module's exports are reached through its map, except
+    /// for exported classes and the names a `{ a, b }` clause selects.
+    fn import_loc(&mut self, name: &str, alias: Option<&str>, names: Option<&[String]>, span: Span) {
+        let items = match crate::loader::resolve_loc(name, &self.module_dir) {
+            Ok(file) => parse_module_file(&file.display().to_string(), false),
+            Err(message) => {
+                self.diagnostics.push(Diagnostic {
+                    level: DiagnosticLevel::Error,
+                    code: ErrorCode::ModuleNotFound,
+                    message: format!("Module '{}' not found", name),
+                    span,
+                    hint: Some(message),
+                    suggestion: None,
+                });
+                None
+            }
+        };
+        let symbols = items.as_deref().map(module_symbols).unwrap_or_default();
+        for (item, name, ty, exported) in &symbols {
+            if *exported && matches!(item, Item::ClassDecl { .. }) { self.declare_imported(item, name, ty.clone()); }
+        }
+        let Some(names) = names else {
+            let binding = alias.unwrap_or_else(|| crate::loader::loc_binding(name));
+            self.define_symbol(binding, AxType::Map(Box::new(AxType::Any)), Span::default());
+            return;
+        };
+        for member in names {
+            match symbols.iter().find(|(_, n, _, exported)| n == member && *exported) {
+                Some((item, n, ty, _)) => self.declare_imported(item, n, ty.clone()),
+                // A missing module is already reported by the path check
+                None if items.is_some() => self.diagnostics.push(Diagnostic {
+                    level: DiagnosticLevel::Error,
+                    code: ErrorCode::ExportNotFound,
+                    message: format!("Module '{}' does not export '{}'", name, member),
+                    span,
+                    hint: None,
+                    suggestion: None,
+                }),
+                None => {}
+            }
+        }
+    }
+
+    /// `std module as alias` / `std module { f, g }` over an intrinsic module.
+    fn import_std(&mut self, module: &str, alias: Option<&str>, names: Option<&[String]>, span: Span) {
+        if !intrinsics::MODULES.contains(&module) {
             self.diagnostics.push(Diagnostic {
                 level: DiagnosticLevel::Error,
-                message: format!("Module '{}' not found", name),
+                code: ErrorCode::ModuleNotFound,
+                message: format!("Unknown standard module '{}'", module),
                 span,
-                hint: Some(format!("Ensure '{}' exists in the current directory", name)),
+                hint: None,
+                suggestion: closest_match(module, intrinsics::MODULES, 2).map(str::to_string),
             });
+            return;
+        }
+        let Some(names) = names else {
+            self.define_symbol(alias.unwrap_or(module), AxType::Map(Box::new(AxType::Any)), Span::default());
+            return;
+        };
+        let functions = intrinsics::module_functions(module);
+        for member in names {
+            if !functions.contains(&member.as_str()) {
+                self.diagnostics.push(Diagnostic {
+                    level: DiagnosticLevel::Warning,
+                    code: ErrorCode::UndefinedIdentifier,
+                    message: format!("Module '{}' has no function '{}'", module, member),
+                    span,
+                    hint: None,
+                    suggestion: closest_match(member, &functions, 2).map(|f| f.to_string()),
+                });
+            }
+            self.define_symbol(member, AxType::Any, Span::default());
+        }
+    }
+
+    fn declare_imported(&mut self, item: &Item, name: &str, ty: AxType) {
+        match item {
+            Item::ClassDecl { .. } => { self.classes.insert(name.to_string()); }
+            Item::EnumDecl { variants, .. } => {
+                self.enums.insert(name.to_string(), variants.iter().map(|v| v.name.clone()).collect());
+            }
+            _ => {}
         }
+        self.define_symbol(name, ty, Span::default());
     }
 
     fn analyze_item(&mut self, item: &Item) {
         match item {
-            Item::FunctionDecl { params, body, .. } => {
-                self.enter_scope();
-                for p in params {
-                    self.define_symbol(p, AxType::Any, Span::default());
-                }
-                self.analyze_block(body);
-                self.exit_scope();
+            Item::FunctionDecl { name, params, body, .. } => {
+                let (param_tys, ret) = match self.resolve_symbol(name).map(|s| &s.ty) {
+                    Some(AxType::Func { params, ret }) => (params.clone(), (**ret).clone()),
+                    _ => (vec![], AxType::Any),
+                };
+                self.analyze_function(params, &param_tys, ret, body);
             }
-            Item::ClassDecl { body, .. } => {
+            Item::ClassDecl { name, parent, interfaces, body, span } => {
+                for ifc in interfaces { self.check_impl(name, ifc, *span); }
+                // Static members first, before `self` is in scope
+                let (statics, members): (Vec<_>, Vec<_>) = body.iter().partition(|m| m.is_static());
+                for member in statics { self.analyze_member(member); }
                 self.enter_scope();
                 self.define_symbol("self", AxType::Any, Span::default());
-                for member in body {
-                    match member {
-                        ClassMember::Method { params, body, .. } => {
-                            self.enter_scope();
-                            for p in params {
-                                self.define_symbol(p, AxType::Any, Span::default());
-                            }
-                            self.analyze_block(body);
-                            self.exit_scope();
-                        }
-                        ClassMember::Field { default, .. } => {
-                            if let Some(expr) = default {
-                                self.analyze_expr(expr);
-                            }
-                        }
-                    }
+                if let Some(parent) = parent {
+                    self.define_symbol("super", AxType::Class(parent.clone()), Span::default());
                 }
+                for member in members { self.analyze_member(member); }
                 self.exit_scope();
             }
             Item::Statement(stmt) => self.analyze_stmt(stmt),
@@ -173,11 +391,100 @@ impl SemanticAnalyzer {
         }
     }
 
+    /// Verifies that `class` (or one of its ancestors) defines every method
+    /// the interface `ifc` requires.
+    fn check_impl(&mut self, class: &str, ifc: &str, span: Span) {
+        let Some(required) = self.interfaces.get(ifc).cloned() else {
+            let known: Vec<&str> = self.interfaces.keys().map(|k| k.as_str()).collect();
+            let suggestion = closest_match(ifc, &known, 2).map(str::to_string);
+            self.diagnostics.push(Diagnostic {
+                level: DiagnosticLevel::Error,
+                code: ErrorCode::UndefinedInterface,
+                message: format!("Class '{}' implements unknown interface '{}'", class, ifc),
+                span,
+                hint: None,
+                suggestion,
+            });
+            return;
+        };
+
+        let mut missing = Vec::new();
+        for (method, arity) in &required {
+            match self.find_method(class, method) {
+                None => missing.push(method.as_str()),
+                Some(found) if found != *arity => self.diagnostics.push(Diagnostic {
+                    level: DiagnosticLevel::Warning,
+                    code: ErrorCode::ArityMismatch,
+                    message: format!(
+                        "'{}.{}' takes {} parameter(s), but interface '{}' requires {}",
+                        class, method, found, ifc, arity
+                    ),
+                    span,
+                    hint: None,
+                    suggestion: None,
+                }),
+                Some(_) => {}
+            }
+        }
+        if !missing.is_empty() {
+            self.diagnostics.push(Diagnostic {
+                level: DiagnosticLevel::Error,
+                code: ErrorCode::IncompleteInterface,
+                message: format!("Class '{}' does not implement {}: missing {}", class, ifc, missing.join(", ")),
+                span,
+                hint: Some(format!("Define the missing methods in '{}' or a parent class", class)),
+                suggestion: None,
+            });
+        }
+    }
+
+    /// Arity of the instance method `method` on `class` or its nearest ancestor.
+    fn find_method(&self, class: &str, method: &str) -> Option<usize> {
+        let mut current = Some(class);
+        let mut depth = 0;
+        while let Some(name) = current {
+            let (parent, methods) = self.class_methods.get(name)?;
+            if let Some(arity) = methods.get(method) { return Some(*arity); }
+            current = parent.as_deref();
+            // Guard against `ext` cycles
+            depth += 1;
+            if depth > self.class_methods.len() { return None; }
+        }
+        None
+    }
+
+    fn analyze_member(&mut self, member: &ClassMember) {
+        match member {
+            ClassMember::Method { params, param_types, ret_type, body, .. } => {
+                if let AxType::Func { params: param_tys, ret } = self.signature_type(param_types, ret_type) {
+                    self.analyze_function(params, &param_tys, *ret, body);
+                }
+            }
+            ClassMember::Accessor { params, body, .. } => self.analyze_function(params, &[], AxType::Any, body),
+            ClassMember::Field { name, ty, default, .. } => {
+                let expected = ty.as_ref().map(|t| self.resolve_annotation(t));
+                if let Some(expr) = default {
+                    let found = self.analyze_expr(expr);
+                    if let Some(expected) = expected {
+                        self.check_assignable(&expected, &found, expr.span(), &format!("field '{}'", name));
+                    }
+                }
+            }
+        }
+    }
+
     fn analyze_stmt(&mut self, stmt: &Stmt) {
         match stmt {
-            Stmt::Let { name, value, span } => {
-                let ty = self.analyze_expr(value);
-                self.define_symbol(name, ty, *span);
+            Stmt::Let { name, ty, value, span } => {
+                let found = self.analyze_expr(value);
+                match ty {
+                    Some(ann) => {
+                        let expected = self.resolve_annotation(ann);
+                        self.check_assignable(&expected, &found, value.span(), &format!("'let {}'", name));
+                        self.define_typed_symbol(name, expected, *span, true);
+                    }
+                    None => self.define_symbol(name, found, *span),
+                }
             }
             Stmt::Expr(expr) => { self.analyze_expr(expr); }
             Stmt::If { condition, then_body, else_body, .. } => {
@@ -192,27 +499,35 @@ impl SemanticAnalyzer {
                 self.analyze_block(body);
             }
             Stmt::For { var, iterable, body, .. } => {
-                self.analyze_expr(iterable);
+                let elem = match self.analyze_expr(iterable) {
+                    AxType::List(t) => *t,
+                    _ => AxType::Any,
+                };
                 self.enter_scope();
-                self.define_symbol(var, AxType::Any, Span::default());
+                self.define_symbol(var, elem, Span::default());
                 self.analyze_block(body);
                 self.exit_scope();
             }
             Stmt::Return { value, .. } => {
                 if let Some(v) = value {
-                    self.analyze_expr(v);
+                    let found = self.analyze_expr(v);
+                    if let Some(expected) = self.return_types.last().cloned() {
+                        self.check_assignable(&expected, &found, v.span(), "return value");
+                    }
                 }
             }
             Stmt::Block(stmts) => self.analyze_block(stmts),
             Stmt::GoSpawn { body, .. } => self.analyze_block(body),
-            Stmt::Match { expr, arms, .. } => {
-                self.analyze_expr(expr);
+            Stmt::Match { expr, arms, span } => {
+                let scrutinee = self.analyze_expr(expr);
                 for arm in arms {
                     self.enter_scope();
                     self.analyze_pattern(&arm.pattern);
+                    if let Some(guard) = &arm.guard { self.analyze_expr(guard); }
                     self.analyze_block(&arm.body);
                     self.exit_scope();
                 }
+                self.check_match_arms(&scrutinee, arms, *span);
             }
             Stmt::Out { arguments, .. } => {
                 for arg in arguments {
@@ -223,16 +538,8 @@ impl SemanticAnalyzer {
     }
 
     fn analyze_pattern(&mut self, pattern: &MatchPattern) {
-        match pattern {
-            MatchPattern::Identifier(i) => {
-                self.define_symbol(i, AxType::Any, Span::default());
-            }
-            MatchPattern::EnumVariant { variant: _, binding, .. } => {
-                if let Some(b) = binding {
-                    self.define_symbol(b, AxType::Any, Span::default());
-                }
-            }
-            _ => {}
+        for (name, _) in pattern.bindings() {
+            self.define_symbol(name, AxType::Any, Span::default());
         }
     }
 
@@ -249,45 +556,207 @@ impl SemanticAnalyzer {
             Expr::Number { .. } => AxType::Num,
             Expr::String { .. } => AxType::Str,
             Expr::Boolean { .. } => AxType::Bool,
+            Expr::Identifier { name, .. } if name == "nil" => AxType::Nil,
             Expr::Identifier { name, span } => {
                 if let Some(sym) = self.resolve_symbol(name) {
                     sym.ty.clone()
                 } else {
+                    let suggestion = self.suggest_name(name);
+                    let hint = self.hidden_imports.get(name)
+                        .map(|module| format!("'{}' is private to '{}', which does not `exp` it", name, module));
                     self.diagnostics.push(Diagnostic {
                         level: DiagnosticLevel::Error,
+                        code: ErrorCode::UndefinedVariable,
                         message: format!("Undefined variable '{}'", name),
                         span: *span,
-                        hint: None,
+                        hint,
+                        suggestion,
                     });
                     AxType::Any
                 }
             }
-            Expr::BinaryOp { left, right, .. } => {
-                self.analyze_expr(left);
-                self.analyze_expr(right);
-                AxType::Any // Type inference would go here
+            Expr::SelfRef { span } => {
+                if self.resolve_symbol("self").is_none() {
+                    self.diagnostics.push(Diagnostic {
+                        level: DiagnosticLevel::Error,
+                        code: ErrorCode::SelfOutsideMethod,
+                        message: "`self` used outside an instance method".to_string(),
+                        span: *span,
+                        hint: Some("a static method has no instance; reach class members as `ClassName.member`".to_string()),
+                        suggestion: None,
+                    });
+                }
+                AxType::Any
+            }
+            Expr::SuperCall { method, arguments, span } => {
+                for arg in arguments {
+                    self.analyze_expr(arg);
+                }
+                match self.resolve_symbol("super").map(|s| s.ty.clone()) {
+                    Some(AxType::Class(parent)) => {
+                        if self.class_methods.contains_key(&parent) && self.find_method(&parent, method).is_none() {
+                            self.diagnostics.push(Diagnostic {
+                                level: DiagnosticLevel::Error,
+                                code: ErrorCode::UndefinedIdentifier,
+                                message: format!("Parent class '{}' has no method '{}'", parent, method),
+                                span: *span,
+                                hint: None,
+                                suggestion: None,
+                            });
+                        }
+                    }
+                    _ => self.diagnostics.push(Diagnostic {
+                        level: DiagnosticLevel::Error,
+                        code: ErrorCode::SelfOutsideMethod,
+                        message: "`super` used outside a subclass method".to_string(),
+                        span: *span,
+                        hint: Some("only instance methods of a class declared with `ext` have a parent".to_string()),
+                        suggestion: None,
+                    }),
+                }
+                AxType::Any
+            }
+            Expr::List { items, .. } => {
+                let mut elem: Option<AxType> = None;
+                for item in items {
+                    let ty = self.analyze_expr(item);
+                    elem = match elem {
+                        None => Some(ty),
+                        Some(prev) if prev == ty => Some(prev),
+                        Some(_) => Some(AxType::Any),
+                    };
+                }
+                AxType::List(Box::new(elem.unwrap_or(AxType::Any)))
+            }
+            Expr::BinaryOp { left, op, right, .. } => {
+                let l = self.analyze_expr(left);
+                let r = self.analyze_expr(right);
+                match (op.as_str(), &l, &r) {
+                    ("==" | "!=" | "<" | "<=" | ">" | ">=" | "&&" | "||", _, _) => AxType::Bool,
+                    ("+", AxType::Str, _) | ("+", _, AxType::Str) => AxType::Str,
+                    ("+", AxType::List(_), AxType::List(_)) => l,
+                    ("+" | "-" | "*" | "/" | "%", AxType::Num, AxType::Num) => AxType::Num,
+                    _ => AxType::Any,
+                }
+            }
+            Expr::UnaryOp { op, operand, .. } => {
+                let ty = self.analyze_expr(operand);
+                match op.as_str() {
+                    "!" => AxType::Bool,
+                    "-" if ty == AxType::Num => AxType::Num,
+                    _ => AxType::Any,
+                }
             }
             Expr::Call { function, arguments, .. } => {
-                self.analyze_expr(function);
+                let callee = self.analyze_expr(function);
+                let arg_tys: Vec<AxType> = arguments.iter().map(|a| self.analyze_expr(a)).collect();
+                match callee {
+                    AxType::Func { params, ret } => {
+                        let fname = match &**function {
+                            Expr::Identifier { name, .. } => name.as_str(),
+                            _ => "function",
+                        };
+                        for (i, ((expected, found), arg)) in params.iter().zip(&arg_tys).zip(arguments).enumerate() {
+                            self.check_assignable(expected, found, arg.span(), &format!("argument {} of '{}'", i + 1, fname));
+                        }
+                        *ret
+                    }
+                    _ => AxType::Any,
+                }
+            }
+            Expr::Assign { target, value, .. } => {
+                let found = self.analyze_expr(value);
+                if let Expr::Identifier { name, .. } = &**target {
+                    let declared = self.resolve_symbol(name).map(|s| (s.ty.clone(), s.annotated));
+                    match declared {
+                        Some((expected, true)) => {
+                            self.check_assignable(&expected, &found, value.span(), &format!("assignment to '{}'", name));
+                        }
+                        // Unannotated bindings widen instead of erroring
+                        Some((prev, false)) if prev != found => {
+                            if let Some(sym) = self.resolve_symbol_mut(name) { sym.ty = AxType::Any; }
+                        }
+                        _ => {}
+                    }
+                } else {
+                    self.analyze_expr(target);
+                }
+                found
+            }
+            Expr::Match { expr, arms, span } => {
+                let scrutinee = self.analyze_expr(expr);
+                // The arms' common value type, else Any
+                let mut result: Option<AxType> = None;
+                for arm in arms {
+                    self.enter_scope();
+                    self.analyze_pattern(&arm.pattern);
+                    if let Some(guard) = &arm.guard { self.analyze_expr(guard); }
+                    let ty = match arm.body.split_last() {
+                        Some((Stmt::Expr(last), init)) => {
+                            for stmt in init { self.analyze_stmt(stmt); }
+                            self.analyze_expr(last)
+                        }
+                        _ => {
+                            for stmt in &arm.body { self.analyze_stmt(stmt); }
+                            AxType::Nil
+                        }
+                    };
+                    self.exit_scope();
+                    result = Some(match result {
+                        Some(prev) if prev != ty => AxType::Any,
+                        _ => ty,
+                    });
+                }
+                self.check_match_arms(&scrutinee, arms, *span);
+                result.unwrap_or(AxType::Nil)
+            }
+            Expr::Lambda { params, .. } => AxType::Func {
+                params: vec![AxType::Any; params.len()],
+                ret: Box::new(AxType::Any),
+            },
+            Expr::MethodCall { object, method, arguments, span } => {
+                if !matches!(**object, Expr::Identifier { .. }) {
+                    self.analyze_expr(object);
+                }
                 for arg in arguments {
                     self.analyze_expr(arg);
                 }
+                if let Expr::Identifier { name, .. } = &**object {
+                    self.check_intrinsic_call(name, method, arguments, *span);
+                    // `Shape.Circle(r)` constructs a data-carrying variant
+                    if let Some(e) = self.enum_named(name) {
+                        return AxType::Enum(e);
+                    }
+                }
+                AxType::Any
+            }
+            Expr::MemberAccess { object, .. } => {
+                if !matches!(**object, Expr::Identifier { .. }) {
+                    self.analyze_expr(object);
+                }
+                if let Expr::Identifier { name, .. } = &**object {
+                    if let Some(e) = self.enum_named(name) {
+                        return AxType::Enum(e);
+                    }
+                }
                 AxType::Any
             }
             Expr::New { class_name, span, .. } => {
                 if !self.classes.contains(class_name) {
                     self.diagnostics.push(Diagnostic {
                         level: DiagnosticLevel::Error,
+                        code: ErrorCode::UndefinedIdentifier,
                         message: format!("Undefined class '{}'", class_name),
                         span: *span,
                         hint: None,
+                        suggestion: None,
                     });
                 }
                 AxType::Class(class_name.clone())
             }
             Expr::InterpolatedString { parts, .. } => {
                 for part in parts {
-                    if let crate::ast::StringPart::Expr(e) = part {
+                    if let Some(e) = part.expr() {
                         self.analyze_expr(e);
                     }
                 }
@@ -297,6 +766,240 @@ impl SemanticAnalyzer {
         }
     }
 
+    /// Warn about calls to known intrinsics (`mth.pow(2)`) with the wrong
+    /// number of arguments or a literal argument of the wrong kind.
+    fn check_intrinsic_call(&mut self, module: &str, method: &str, arguments: &[Expr], span: Span) {
+        // A user binding shadowing the module name disables the check
+        match self.resolve_symbol(module) {
+            Some(sym) if sym.is_const => {}
+            _ => return,
+        }
+        let Some(sig) = intrinsics::signature(module, method) else {
+            if intrinsics::MODULES.contains(&module) {
+                let functions = intrinsics::module_functions(module);
+                self.diagnostics.push(Diagnostic {
+                    level: DiagnosticLevel::Warning,
+                    code: ErrorCode::UndefinedIdentifier,
+                    message: format!("Module '{}' has no function '{}'", module, method),
+                    span,
+                    hint: None,
+                    suggestion: closest_match(method, &functions, 2).map(|m| format!("{}.{}", module, m)),
+                });
+            }
+            return;
+        };
+
+        let argc = arguments.len();
+        if !sig.accepts(argc) {
+            let count = if sig.variadic { sig.min_args } else { sig.max_args() };
+            let expected = if sig.variadic {
+                format!("at least {}", sig.min_args)
+            } else if sig.min_args == sig.max_args() {
+                format!("{}", sig.min_args)
+            } else {
+                format!("{} to {}", sig.min_args, sig.max_args())
+            };
+            self.diagnostics.push(Diagnostic {
+                level: DiagnosticLevel::Warning,
+                code: ErrorCode::ArityMismatch,
+                message: format!(
+                    "{}.{} expects {} argument{}, found {}",
+                    module, method, expected, if count == 1 { "" } else { "s" }, argc
+                ),
+                span,
+                hint: Some(format!("signature: {}", sig.display())),
+                suggestion: None,
+            });
+        }
+
+        for (i, arg) in arguments.iter().enumerate() {
+            let Some(expected) = sig.param(i) else { break };
+            let found = match arg {
+                Expr::Number { .. } => ArgKind::Num,
+                Expr::String { .. } | Expr::InterpolatedString { .. } => ArgKind::Str,
+                Expr::Boolean { .. } => ArgKind::Bol,
+                Expr::List { .. } => ArgKind::Lst,
+                Expr::Lambda { .. } => ArgKind::Fun,
+                _ => continue,
+            };
+            if expected != ArgKind::Any && found != expected {
+                self.diagnostics.push(Diagnostic {
+                    level: DiagnosticLevel::Warning,
+                    code: ErrorCode::TypeMismatch,
+                    message: format!(
+                        "argument {} of {}.{} should be {}, found {} literal",
+                        i + 1, module, method, expected.name(), found.name()
+                    ),
+                    span: arg.span(),
+                    hint: Some(format!("signature: {}", sig.display())),
+                    suggestion: None,
+                });
+            }
+        }
+    }
+
+    // -----------------------------------------------------------------------
+    // Match exhaustiveness
+    // -----------------------------------------------------------------------
+
+    /// The enum declared as `name`, unless a local binding shadows it.
+    fn enum_named(&self, name: &str) -> Option<String> {
+        match self.resolve_symbol(name).map(|s| &s.ty) {
+            Some(AxType::Enum(e)) if e == name => Some(e.clone()),
+            _ => None,
+        }
+    }
+
+    fn check_match_arms(&mut self, scrutinee: &AxType, arms: &[MatchArm], span: Span) {
+        let catch_all = arms
+            .iter()
+            .position(|a| a.guard.is_none() && a.pattern.is_catch_all());
+        if let Some(idx) = catch_all {
+            for arm in &arms[idx + 1..] {
+                self.diagnostics.push(Diagnostic {
+                    level: DiagnosticLevel::Warning,
+                    code: ErrorCode::UnreachableCode,
+                    message: "Unreachable match arm: a previous arm matches every value".to_string(),
+                    span: arm.span,
+                    hint: Some("move this arm above the wildcard / `els` arm or remove it".to_string()),
+                    suggestion: None,
+                });
+            }
+        }
+
+        // Prefer the inferred scrutinee type, else a qualified pattern (`Color.Red`)
+        let enum_name = match scrutinee {
+            AxType::Enum(e) => Some(e.clone()),
+            _ => arms.iter().flat_map(|a| alternatives(&a.pattern)).find_map(|p| match p {
+                MatchPattern::EnumVariant { enum_name: Some(e), .. } if self.enums.contains_key(e) => Some(e.clone()),
+                _ => None,
+            }),
+        };
+        let Some(enum_name) = enum_name else { return };
+        let Some(variants) = self.enums.get(&enum_name).cloned() else { return };
+
+        let mut covered = HashSet::new();
+        for (arm, pattern) in arms.iter().flat_map(|a| alternatives(&a.pattern).into_iter().map(move |p| (a, p))) {
+            // A bare `Green` is a binding pattern and matches everything at runtime
+            if let MatchPattern::Identifier(name) = pattern {
+                if variants.contains(name) {
+                    self.diagnostics.push(Diagnostic {
+                        level: DiagnosticLevel::Warning,
+                        code: ErrorCode::NonExhaustiveMatch,
+                        message: format!("Pattern '{}' binds any value instead of matching the variant", name),
+                        span: arm.span,
+                        hint: Some(format!("write '{}.{}' to match the variant", enum_name, name)),
+                        suggestion: None,
+                    });
+                }
+            }
+            if let MatchPattern::EnumVariant { enum_name: qualifier, variant, .. } = pattern {
+                if qualifier.as_ref().is_none_or(|q| q == &enum_name) {
+                    if variants.contains(variant) {
+                        // A guarded arm may decline, so it covers nothing
+                        if arm.guard.is_none() { covered.insert(variant.clone()); }
+                    } else {
+                        self.diagnostics.push(Diagnostic {
+                            level: DiagnosticLevel::Warning,
+                            code: ErrorCode::NonExhaustiveMatch,
+                            message: format!("Enum '{}' has no variant '{}'", enum_name, variant),
+                            span: arm.span,
+                            hint: None,
+                            suggestion: closest_match(variant, &variants.iter().map(|v| v.as_str()).collect::<Vec<_>>(), 2)
+                                .map(|m| format!("{}.{}", enum_name, m)),
+                        });
+                    }
+                }
+            }
+        }
+
+        if catch_all.is_none() {
+            let missing: Vec<&str> = variants.iter().filter(|v| !covered.contains(*v)).map(|v| v.as_str()).collect();
+            if !missing.is_empty() {
+                self.diagnostics.push(Diagnostic {
+                    level: DiagnosticLevel::Warning,
+                    code: ErrorCode::NonExhaustiveMatch,
+                    message: format!("Non-exhaustive match on '{}': missing {}", enum_name, missing.join(", ")),
+                    span,
+                    hint: Some("add arms for the missing variants or an `els` arm".to_string()),
+                    suggestion: None,
+                });
+            }
+        }
+    }
+
+    // -----------------------------------------------------------------------
+    // Type annotations
+    // -----------------------------------------------------------------------
+
+    fn analyze_function(&mut self, params: &[String], param_tys: &[AxType], ret: AxType, body: &[Stmt]) {
+        self.enter_scope();
+        for (i, p) in params.iter().enumerate() {
+            let ty = param_tys.get(i).cloned().unwrap_or(AxType::Any);
+            let annotated = ty != AxType::Any;
+            self.define_typed_symbol(p, ty, Span::default(), annotated);
+        }
+        self.return_types.push(ret);
+        self.analyze_block(body);
+        self.return_types.pop();
+        self.exit_scope();
+    }
+
+    fn signature_type(&mut self, param_types: &[Option<TypeAnnotation>], ret_type: &Option<TypeAnnotation>) -> AxType {
+        let params = param_types
+            .iter()
+            .map(|t| t.as_ref().map_or(AxType::Any, |t| self.resolve_annotation(t)))
+            .collect();
+        let ret = ret_type.as_ref().map_or(AxType::Any, |t| self.resolve_annotation(t));
+        AxType::Func { params, ret: Box::new(ret) }
+    }
+
+    fn resolve_annotation(&mut self, ann: &TypeAnnotation) -> AxType {
+        let arg = |this: &mut Self, i: usize| {
+            Box::new(ann.args.get(i).map_or(AxType::Any, |a| this.resolve_annotation(a)))
+        };
+        match ann.name.as_str() {
+            "Num" => AxType::Num,
+            "Str" => AxType::Str,
+            "Bool" | "Bol" => AxType::Bool,
+            "Nil" => AxType::Nil,
+            "Any" => AxType::Any,
+            "List" | "Lst" => AxType::List(arg(self, 0)),
+            "Map" => AxType::Map(arg(self, 0)),
+            "Fun" => AxType::Func { params: vec![], ret: Box::new(AxType::Any) },
+            name if self.classes.contains(name) => AxType::Class(name.to_string()),
+            name if self.enums.contains_key(name) => AxType::Enum(name.to_string()),
+            name => {
+                let mut known = vec!["Num", "Str", "Bool", "Nil", "Any", "List", "Map", "Fun"];
+                known.extend(self.classes.iter().map(|s| s.as_str()));
+                known.extend(self.enums.keys().map(|s| s.as_str()));
+                let suggestion = closest_match(name, &known, 2).map(str::to_string);
+                self.diagnostics.push(Diagnostic {
+                    level: DiagnosticLevel::Error,
+                    code: ErrorCode::TypeMismatch,
+                    message: format!("Unknown type '{}'", name),
+                    span: ann.span,
+                    hint: None,
+                    suggestion,
+                });
+                AxType::Any
+            }
+        }
+    }
+
+    fn check_assignable(&mut self, expected: &AxType, found: &AxType, span: Span, what: &str) {
+        if !expected.accepts(found) {
+            self.diagnostics.push(Diagnostic {
+                level: DiagnosticLevel::Error,
+                code: ErrorCode::TypeMismatch,
+                message: format!("Type mismatch in {}: expected {}, found {}", what, expected, found),
+                span,
+                hint: None,
+                suggestion: None,
+            });
+        }
+    }
+
     // -----------------------------------------------------------------------
     // Helper Methods
     // -----------------------------------------------------------------------
@@ -317,6 +1020,10 @@ impl SemanticAnalyzer {
     }
 
     fn define_symbol(&mut self, name: &str, ty: AxType, span: Span) {
+        self.define_typed_symbol(name, ty, span, false);
+    }
+
+    fn define_typed_symbol(&mut self, name: &str, ty: AxType, span: Span, annotated: bool) {
         self.scopes[self.current_scope].symbols.insert(
             name.to_string(),
             Symbol {
@@ -324,6 +1031,7 @@ impl SemanticAnalyzer {
                 ty,
                 span,
                 is_const: false,
+                annotated,
             },
         );
     }
@@ -338,4 +1046,220 @@ impl SemanticAnalyzer {
         }
         None
     }
+
+    /// Nearest visible symbol or intrinsic module to an unresolved `name`.
+    fn suggest_name(&self, name: &str) -> Option<String> {
+        let mut names: Vec<&str> = Vec::new();
+        let mut current = Some(self.current_scope);
+        while let Some(idx) = current {
+            names.extend(self.scopes[idx].symbols.keys().map(|k| k.as_str()));
+            current = self.scopes[idx].parent;
+        }
+        closest_match(name, &names, 2).map(str::to_string)
+    }
+
+    fn resolve_symbol_mut(&mut self, name: &str) -> Option<&mut Symbol> {
+        let mut current = Some(self.current_scope);
+        while let Some(idx) = current {
+            if self.scopes[idx].symbols.contains_key(name) {
+                return self.scopes[idx].symbols.get_mut(name);
+            }
+            current = self.scopes[idx].parent;
+        }
+        None
+    }
+}
+
+/// The alternatives of an `a | b` pattern, or the pattern itself.
+fn alternatives(pattern: &MatchPattern) -> Vec<&MatchPattern> {
+    match pattern {
+        MatchPattern::Or(alts) => alts.iter().flat_map(alternatives).collect(),
+        _ => vec![pattern],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+
+    fn check(src: &str) -> Vec<Diagnostic> {
+        let items = Parser::new(src, 0).parse().expect("parse failed");
+        SemanticAnalyzer::new().check(&items)
+    }
+
+    #[test]
+    fn test_intrinsic_arity_warning() {
+        let diags = check("let x = mth.pow(2)");
+        assert_eq!(diags.len(), 1);
+        assert!(matches!(diags[0].level, DiagnosticLevel::Warning));
+        assert!(diags[0].message.contains("mth.pow expects 2 arguments, found 1"));
+    }
+
+    #[test]
+    fn test_intrinsic_literal_type_warning() {
+        let diags = check("let x = mth.sqrt(\"nine\")");
+        assert_eq!(diags.len(), 1);
+        assert!(diags[0].message.contains("should be num, found str"));
+    }
+
+    #[test]
+    fn test_intrinsic_valid_and_shadowed_calls() {
+        assert!(check("let x = mth.pow(2, 8)\nlet y = str.upper(\"a\")").is_empty());
+        assert!(check("let mth = 1\nlet x = mth.pow(2)").is_empty());
+    }
+
+    #[test]
+    fn test_variadic_intrinsic_arity() {
+        assert!(check("let s = str.fmt(\"{} {} {} {}\", 1, 2, 3, 4)").is_empty());
+        let diags = check("let s = str.fmt()");
+        assert!(diags[0].message.contains("str.fmt expects at least 1 argument, found 0"));
+        assert!(check("let s = str.fmt(1)")[0].message.contains("should be str"));
+    }
+
+    #[test]
+    fn test_annotated_let_mismatch() {
+        let diags = check("let n: Num = \"five\"");
+        assert_eq!(diags.len(), 1);
+        assert!(diags[0].message.contains("expected Num, found Str"));
+    }
+
+    #[test]
+    fn test_annotated_function_checks() {
+        let src = "fun add(x: Num, y: Num) -> Num { ret x + y }\nlet s: Str = add(1, \"2\")";
+        let msgs: Vec<String> = check(src).into_iter().map(|d| d.message).collect();
+        assert_eq!(msgs.len(), 2, "{:?}", msgs);
+        assert!(msgs.iter().any(|m| m.contains("argument 2 of 'add'")));
+        assert!(msgs.iter().any(|m| m.contains("'let s': expected Str, found Num")));
+    }
+
+    #[test]
+    fn test_did_you_mean_suggestions() {
+        let diags = check("let counter = 1\nout countr");
+        assert_eq!(diags[0].suggestion.as_deref(), Some("counter"));
+        let diags = check("let x = mth.sqr(4)");
+        assert!(diags[0].message.contains("has no function 'sqr'"));
+        assert_eq!(diags[0].suggestion.as_deref(), Some("mth.sqrt"));
+    }
+
+    #[test]
+    fn test_unannotated_code_is_gradual() {
+        assert!(check("let x = 5\nx = \"five\"\nlet y: Str = x").is_empty());
+        let diags = check("let p: Pont = nil");
+        assert!(diags[0].message.contains("Unknown type 'Pont'"));
+    }
+
+    #[test]
+    fn test_non_exhaustive_enum_match() {
+        let src = "enm Color { Red, Green, Blue }\nlet c = Color.Red\nmatch c {\n    Color.Red => { out 1 }\n    Color.Green => { out 2 }\n}";
+        let diags = check(src);
+        assert_eq!(diags.len(), 1, "{:?}", diags);
+        assert!(diags[0].message.contains("missing Blue"));
+
+        let src = "enm Color { Red, Green }\nmatch Color.Red {\n    Color.Red => { out 1 }\n    Green => { out 2 }\n}";
+        let diags = check(src);
+        assert_eq!(diags.len(), 1, "{:?}", diags);
+        assert!(diags[0].message.contains("binds any value"));
+    }
+
+    #[test]
+    fn test_match_expression() {
+        let src = "enm Color { Red, Green }\nlet n: Num = match Color.Red {\n    Color.Red => 1\n    Color.Green => 2\n}";
+        assert!(check(src).is_empty(), "{:?}", check(src));
+
+        let src = "enm Color { Red, Green }\nlet s: Str = match Color.Red {\n    Color.Red => 1\n    els => 2\n}";
+        let diags = check(src);
+        assert_eq!(diags.len(), 1, "{:?}", diags);
+
+        let src = "enm Color { Red, Green }\nlet n = match Color.Red {\n    Color.Red => 1\n}";
+        let diags = check(src);
+        assert_eq!(diags.len(), 1, "{:?}", diags);
+        assert!(diags[0].message.contains("missing Green"));
+    }
+
+    #[test]
+    fn test_guarded_arm_does_not_cover_variant() {
+        let src = "enm Color { Red, Green, Blue }\nlet x = 1\nmatch Color.Red {\n    Color.Red | Color.Blue => { out 1 }\n    Color.Green if x > 0 => { out 2 }\n}";
+        let diags = check(src);
+        assert_eq!(diags.len(), 1, "{:?}", diags);
+        assert!(diags[0].message.contains("missing Green"));
+
+        let src = "enm Color { Red, Green }\nlet x = 1\nmatch Color.Red {\n    y if x > 0 => { out 1 }\n    Color.Red | Color.Green => { out 2 }\n}";
+        assert!(check(src).is_empty(), "{:?}", check(src));
+    }
+
+    #[test]
+    fn test_unreachable_arm_after_wildcard() {
+        let src = "enm Color { Red, Green }\nmatch Color.Red {\n    els => { out 0 }\n    Color.Red => { out 1 }\n}";
+        let diags = check(src);
+        assert_eq!(diags.len(), 1, "{:?}", diags);
+        assert!(diags[0].message.contains("Unreachable match arm"));
+    }
+
+    #[test]
+    fn test_self_in_static_method() {
+        let src = "cls C {\n    let x = 1\n    static fun make() { ret self.x }\n    fun get() { ret self.x }\n}";
+        let diags = check(src);
+        assert_eq!(diags.len(), 1, "{:?}", diags);
+        assert!(diags[0].message.contains("`self`"));
+    }
+
+    #[test]
+    fn test_exports() {
+        let diags = check("exp f, g\nfun f() { }");
+        assert_eq!(diags.len(), 1, "{:?}", diags);
+        assert!(diags[0].message.contains("'g' is not declared"));
+        assert_eq!(diags[0].code, ErrorCode::ExportNotFound);
+
+        let dir = std::env::temp_dir().join(format!("axiom-chk-exp-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let module = dir.join("util.ax");
+        std::fs::write(&module, "exp shout\nfun shout(s) { ret bang(s) }\nfun bang(s) { ret s + \"!\" }").unwrap();
+        let diags = check(&format!("load \"{}\"\nout shout(\"a\")\nout bang(\"b\")", module.display()));
+        assert_eq!(diags.len(), 1, "{:?}", diags);
+        assert!(diags[0].message.contains("'bang'"));
+        assert!(diags[0].hint.as_deref().is_some_and(|h| h.contains("private")));
+    }
+
+    #[test]
+    fn test_std_imports() {
+        assert!(check("std mth { sqrt, pow }\nstd str as s\nout sqrt(pow(2, 2))\nout s").is_empty());
+        let diags = check("std mthh as m");
+        assert_eq!(diags.len(), 1, "{:?}", diags);
+        assert_eq!(diags[0].suggestion.as_deref(), Some("mth"));
+        let diags = check("std mth { sqr }");
+        assert!(diags[0].message.contains("has no function 'sqr'"), "{:?}", diags);
+    }
+
+    #[test]
+    fn test_super_calls() {
+        let base = "cls A { fun f() { ret 1 } }\n";
+        assert!(check(&format!("{}cls B ext A {{ fun f() {{ ret super.f() }} }}", base)).is_empty());
+
+        let diags = check(&format!("{}cls B ext A {{ fun g() {{ ret super.h() }} }}", base));
+        assert_eq!(diags.len(), 1, "{:?}", diags);
+        assert!(diags[0].message.contains("has no method 'h'"));
+
+        let diags = check(&format!("{}cls C {{ fun g() {{ ret super.f() }} }}", base));
+        assert_eq!(diags.len(), 1, "{:?}", diags);
+        assert!(diags[0].message.contains("`super`"));
+        assert_eq!(diags[0].code, ErrorCode::SelfOutsideMethod);
+    }
+
+    #[test]
+    fn test_interface_implementation() {
+        let ifc = "ifc Shape { area(), scale(k) }\n";
+        let ok = format!("{}cls Base {{ fun area() {{ ret 0 }} }}\ncls Sq ext Base impl Shape {{ fun scale(k) {{ ret k }} }}", ifc);
+        assert!(check(&ok).is_empty(), "{:?}", check(&ok));
+
+        let diags = check(&format!("{}cls Circle impl Shape {{ fun scale() {{ ret 1 }} }}", ifc));
+        assert_eq!(diags.len(), 2, "{:?}", diags);
+        assert!(diags.iter().any(|d| d.code == ErrorCode::ArityMismatch && d.message.contains("requires 1")));
+        assert!(diags.iter().any(|d| d.code == ErrorCode::IncompleteInterface && d.message.contains("missing area")));
+
+        let diags = check(&format!("{}cls C impl Shap {{ }}", ifc));
+        assert_eq!(diags.len(), 1);
+        assert_eq!(diags[0].suggestion.as_deref(), Some("Shape"));
+        assert_eq!(diags[0].code, ErrorCode::UndefinedInterface);
+    }
 }
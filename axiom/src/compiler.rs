@@ -3,8 +3,12 @@
 /// Compiles the AST (Item/Stmt/Expr tree) into a register-based Proto.
 ///
 /// REGISTER ALLOCATION:
-///   Simple linear-scan: each local variable occupies a fixed register.
-///   Temporaries are allocated on top of locals.
+///   Lexical scopes, not liveness: each local variable occupies a fixed
+///   register until its scope (block, loop body, match arm) ends, even past
+///   its last use, and only then is the register reused.  Temporaries are
+///   allocated on top of locals and freed innermost first.  A proto's
+///   reg_count is the most registers held at once, and the VM sizes its
+///   frames to exactly that, with no spare registers above it.
 ///   Max 255 registers per frame (fits in 1 byte).
 ///
 /// PASSES:
@@ -12,69 +16,95 @@
 ///   2. For declarations: hoist to globals table before body
 ///   3. Apply optimizer inline (peephole + constant folding)
 
-use crate::ast::{Expr, Item, MatchPattern, Stmt, StringPart};
-use crate::bytecode::{Instr, Op, Proto};
-use std::collections::HashMap;
+use crate::ast::{ClassMember, EnumVariant, Expr, Item, MatchArm, MatchPattern, Stmt, StringPart};
+use crate::bytecode::{Instr, JumpTable, Op, Proto, UpvalDesc};
+use crate::intern::Sym;
+use std::collections::{HashMap, HashSet};
+
+/// Items of a list literal built in one register window; longer literals are
+/// appended in chunks of this size.
+const LIST_CHUNK: usize = 64;
 
 // ---------------------------------------------------------------------------
 // Register allocator
 // ---------------------------------------------------------------------------
 
 struct RegAlloc {
-    /// Next free register
+    /// First register above every live local and temporary
     next: u8,
-    /// Register → local variable name
-    locals: HashMap<String, u8>,
+    /// Most registers live at once so far — the frame size
+    high: u8,
+    /// Locals in scope, innermost last; a shadowing local hides earlier ones
+    locals: Vec<(String, u8)>,
     /// Stack of "free" temporaries (for expression sub-trees)
     temp_top: u8,
 }
 
+/// Where a scope started; popping it frees everything allocated since.
+#[derive(Clone, Copy)]
+struct ScopeMark {
+    locals: usize,
+    next: u8,
+}
+
 impl RegAlloc {
     fn new() -> Self {
-        RegAlloc { next: 0, locals: HashMap::new(), temp_top: 0 }
+        RegAlloc { next: 0, high: 0, locals: Vec::new(), temp_top: 0 }
     }
 
     fn alloc_local(&mut self, name: impl Into<String>) -> u8 {
+        debug_assert!(self.next < u8::MAX, "register file overflow allocating a local");
         let reg = self.next;
-        self.locals.insert(name.into(), reg);
+        self.locals.push((name.into(), reg));
         self.next += 1;
         self.temp_top = self.next;
+        self.high = self.high.max(self.next);
         reg
     }
 
     fn alloc_temp(&mut self) -> u8 {
+        debug_assert!(self.temp_top < u8::MAX, "register file overflow allocating a temporary");
         let reg = self.temp_top;
         self.temp_top += 1;
         if self.temp_top > self.next { self.next = self.temp_top; }
+        self.high = self.high.max(self.next);
         reg
     }
 
     fn free_temp(&mut self, reg: u8) {
+        debug_assert!(reg < self.high, "freeing r{} outside the frame of {}", reg, self.high);
         if reg + 1 == self.temp_top {
             self.temp_top = reg;
+            // Locals are allocated in increasing order, so the last is highest
+            let locals_top = self.locals.last().map_or(0, |&(_, r)| r + 1);
+            self.next = self.temp_top.max(locals_top);
         }
     }
 
     fn get_local(&self, name: &str) -> Option<u8> {
-        self.locals.get(name).copied()
+        self.locals.iter().rev().find(|(n, _)| n == name).map(|&(_, reg)| reg)
     }
 
-    fn push_scope(&self) -> usize {
-        self.locals.len()
+    fn push_scope(&self) -> ScopeMark {
+        ScopeMark { locals: self.locals.len(), next: self.next }
     }
 
-    fn pop_scope(&mut self, saved: usize) {
-        let to_remove: Vec<String> = self.locals.iter()
-            .filter(|(_, &r)| r as usize >= saved)
-            .map(|(k, _)| k.clone())
-            .collect();
-        for k in to_remove {
-            self.locals.remove(&k);
-        }
-        self.temp_top = self.next;
+    /// End a scope: its locals go out of scope (uncovering any they
+    /// shadowed) and their registers, with the scope's temporaries, are
+    /// reused by whatever is compiled next.
+    fn pop_scope(&mut self, mark: ScopeMark) {
+        debug_assert!(mark.locals <= self.locals.len() && mark.next <= self.high, "scope popped out of order");
+        self.locals.truncate(mark.locals);
+        self.next = mark.next;
+        self.temp_top = mark.next;
     }
 
-    fn reg_count(&self) -> u8 { self.next }
+    /// The frame size. Every register handed out lies below it, as the VM
+    /// adds no padding.
+    fn reg_count(&self) -> u8 {
+        debug_assert!(self.next <= self.high && self.temp_top <= self.high);
+        self.high
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -82,26 +112,78 @@ impl RegAlloc {
 // ---------------------------------------------------------------------------
 
 /// The compiler's global symbol table (shared across compilation units)
+#[derive(Clone, Default)]
 pub struct GlobalTable {
     pub names: Vec<String>,
     idx: HashMap<String, u16>,
+    /// Per name, whether it was interned since the table was `reopen`ed
+    used: Vec<bool>,
+    /// Declared enums: name → (variant, has_data)
+    enums: HashMap<String, Vec<(String, bool)>>,
+    /// Declared classes: name → static method names
+    static_methods: HashMap<String, Vec<String>>,
 }
 
 impl GlobalTable {
     pub fn new() -> Self {
-        GlobalTable { names: Vec::new(), idx: HashMap::new() }
+        GlobalTable { names: Vec::new(), idx: HashMap::new(), used: Vec::new(), enums: HashMap::new(), static_methods: HashMap::new() }
+    }
+
+    pub fn declare_class(&mut self, name: &str, body: &[ClassMember]) {
+        self.intern(name);
+        let methods = body.iter().filter_map(|m| match m {
+            ClassMember::Method { name: method, is_static: true, .. } => Some(method.clone()),
+            _ => None,
+        }).collect();
+        self.static_methods.insert(name.to_string(), methods);
+    }
+
+    fn is_static_method(&self, class: &str, method: &str) -> bool {
+        self.static_methods.get(class).is_some_and(|ms| ms.iter().any(|m| m == method))
+    }
+
+    pub fn declare_enum(&mut self, name: &str, variants: &[EnumVariant]) {
+        self.intern(name);
+        self.enums.insert(name.to_string(), variants.iter().map(|v| (v.name.clone(), v.has_data)).collect());
+    }
+
+    /// Whether `enum_name.variant` is declared, and if so whether it carries data.
+    fn variant(&self, enum_name: &str, variant: &str) -> Option<bool> {
+        self.enums.get(enum_name)?.iter().find(|(v, _)| v == variant).map(|&(_, data)| data)
+    }
+
+    /// Every declared variant called `variant`, qualified, for bare patterns.
+    fn variants_named(&self, variant: &str) -> Vec<Sym> {
+        let mut found: Vec<Sym> = self.enums.iter()
+            .filter(|(_, vs)| vs.iter().any(|(v, _)| v == variant))
+            .map(|(e, _)| Sym::new(&format!("{}.{}", e, variant)))
+            .collect();
+        found.sort();
+        found
     }
 
     pub fn intern(&mut self, name: &str) -> u16 {
         if let Some(&i) = self.idx.get(name) {
+            self.used[i as usize] = true;
             return i;
         }
         let i = self.names.len() as u16;
         self.names.push(name.to_string());
         self.idx.insert(name.to_string(), i);
+        self.used.push(true);
         i
     }
 
+    /// A copy with the same numbering but no name marked used, to compile a
+    /// program against; `used` then tells which globals that program names.
+    pub fn reopen(&self) -> GlobalTable {
+        GlobalTable { used: vec![false; self.names.len()], ..self.clone() }
+    }
+
+    pub fn used(&self, idx: usize) -> bool {
+        self.used.get(idx).copied().unwrap_or(false)
+    }
+
     pub fn get(&self, name: &str) -> Option<u16> {
         self.idx.get(name).copied()
     }
@@ -120,6 +202,24 @@ pub struct Compiler<'g> {
     break_patches: Vec<Vec<usize>>,
     /// Loop start IP for continue
     loop_starts: Vec<usize>,
+    /// Block nesting depth (0 = the body itself)
+    scope_depth: usize,
+    /// Top-level chunk: depth-0 `let`s are globals, so functions see them
+    lets_are_globals: bool,
+    /// Locals of the enclosing functions, capturable as upvalues
+    enclosing: HashSet<String>,
+    /// Names captured so far; the index is the upvalue slot
+    captures: Vec<String>,
+    /// Name a `let f = fn(..)` binds this lambda to (refers to the callee)
+    self_name: Option<String>,
+}
+
+/// Where an identifier lives.
+enum Var {
+    Local(u8),
+    Upval(u8),
+    Callee,
+    Global,
 }
 
 impl<'g> Compiler<'g> {
@@ -131,9 +231,103 @@ impl<'g> Compiler<'g> {
             current_line: 1,
             break_patches: Vec::new(),
             loop_starts: Vec::new(),
+            scope_depth: 0,
+            lets_are_globals: false,
+            enclosing: HashSet::new(),
+            captures: Vec::new(),
+            self_name: None,
         }
     }
 
+    fn enter_scope(&mut self) -> ScopeMark {
+        self.scope_depth += 1;
+        self.regs.push_scope()
+    }
+
+    fn exit_scope(&mut self, saved: ScopeMark) {
+        self.scope_depth -= 1;
+        self.regs.pop_scope(saved);
+    }
+
+    fn resolve(&mut self, name: &str) -> Var {
+        if let Some(reg) = self.regs.get_local(name) {
+            return Var::Local(reg);
+        }
+        if let Some(i) = self.captures.iter().position(|c| c == name) {
+            return Var::Upval(i as u8);
+        }
+        if self.self_name.as_deref() == Some(name) {
+            return Var::Callee;
+        }
+        if self.enclosing.contains(name) {
+            self.captures.push(name.to_string());
+            return Var::Upval((self.captures.len() - 1) as u8);
+        }
+        Var::Global
+    }
+
+    /// Compile a nested function body into `self.proto.protos`; returns its index.
+    fn compile_lambda(&mut self, params: &[String], body: &[Stmt], self_name: Option<&str>) -> u16 {
+        let mut enclosing = self.enclosing.clone();
+        enclosing.extend(self.regs.locals.iter().map(|(name, _)| name.clone()));
+        enclosing.extend(self.captures.iter().cloned());
+        let mut lambda_compiler = Compiler::new(
+            format!("{}.lambda", self.proto.source),
+            self.globals,
+        );
+        lambda_compiler.enclosing = enclosing;
+        lambda_compiler.self_name = self_name.map(str::to_string);
+        for p in params {
+            lambda_compiler.regs.alloc_local(p);
+        }
+        for stmt in body {
+            lambda_compiler.compile_stmt(stmt);
+        }
+        // Ensure return
+        let last = lambda_compiler.proto.code.last().map(|i| i.op());
+        if !matches!(last, Some(Op::Return) | Some(Op::ReturnNil) | Some(Op::NilReturn)) {
+            lambda_compiler.emit(Instr::abc(Op::ReturnNil, 0, 0, 0));
+        }
+        lambda_compiler.proto.reg_count = lambda_compiler.regs.reg_count();
+        lambda_compiler.proto.param_count = params.len() as u8;
+        let captures = std::mem::take(&mut lambda_compiler.captures);
+        let mut proto = lambda_compiler.proto;
+
+        for name in captures {
+            let desc = match self.resolve(&name) {
+                Var::Local(reg) => UpvalDesc { name, in_stack: true, idx: reg },
+                Var::Upval(i)   => UpvalDesc { name, in_stack: false, idx: i },
+                // Only names from `enclosing` are captured, so these are unreachable
+                Var::Callee | Var::Global => continue,
+            };
+            proto.upvals.push(desc);
+        }
+        proto.upval_count = proto.upvals.len() as u8;
+
+        let proto_idx = self.proto.protos.len() as u16;
+        self.proto.protos.push(proto);
+        proto_idx
+    }
+
+    /// Load the method/property `name` of R[obj_r] into `dst`.
+    fn emit_member(&mut self, op: Op, dst: u8, obj_r: u8, name: &str) {
+        if obj_r != dst { self.emit(Instr::abc(Op::Move, dst, obj_r, 0)); }
+        let str_idx = self.proto.add_string(name);
+        self.emit(Instr::abx(op, dst, str_idx));
+    }
+
+    /// Bind the module map in R[module_r] to the global `name`, or with a
+    /// selective import each listed member to a global of its own name.
+    fn bind_import(&mut self, module_r: u8, name: &str, members: Option<&[String]>) {
+        let Some(members) = members else { return self.emit_store_global(module_r, name) };
+        let t_member = self.regs.alloc_temp();
+        for member in members {
+            self.emit_member(Op::GetProp, t_member, module_r, member);
+            self.emit_store_global(t_member, member);
+        }
+        self.regs.free_temp(t_member);
+    }
+
     fn emit(&mut self, instr: Instr) -> usize {
         self.proto.emit(instr, self.current_line)
     }
@@ -149,10 +343,251 @@ impl<'g> Compiler<'g> {
         self.emit(Instr::abx(Op::StoreGlobal, src, idx));
     }
 
+    /// `Enum.Variant` with `Enum` not shadowed by a local: whether the
+    /// variant carries data, if it is declared.
+    fn enum_variant(&mut self, object: &Expr, member: &str) -> Option<bool> {
+        let Expr::Identifier { name, .. } = object else { return None };
+        let data = self.globals.variant(name, member)?;
+        matches!(self.resolve(name), Var::Global).then_some(data)
+    }
+
+    /// `Class.method` naming a static method of a class not shadowed by a local.
+    fn static_method(&mut self, object: &Expr, method: &str) -> bool {
+        let Expr::Identifier { name, .. } = object else { return false };
+        self.globals.is_static_method(name, method) && matches!(self.resolve(name), Var::Global)
+    }
+
+    /// R[dst] = `enum_name.variant`, with the payload already in R[dst].
+    fn emit_new_enum(&mut self, dst: u8, enum_name: &str, variant: &str) {
+        let idx = self.proto.add_variant(&format!("{}.{}", enum_name, variant));
+        self.emit(Instr::abx(Op::NewEnum, dst, idx));
+    }
+
+    /// The variants an enum pattern (or an alternation of them) matches, or
+    /// `None` if the pattern is anything else.
+    fn pattern_variants(&self, pattern: &MatchPattern) -> Option<Vec<Sym>> {
+        match pattern {
+            MatchPattern::EnumVariant { enum_name: Some(e), variant, .. } => Some(vec![Sym::new(&format!("{}.{}", e, variant))]),
+            MatchPattern::EnumVariant { enum_name: None, variant, .. } => Some(self.globals.variants_named(variant)),
+            MatchPattern::Or(alts) => alts.iter().try_fold(Vec::new(), |mut all, alt| {
+                all.extend(self.pattern_variants(alt)?);
+                Some(all)
+            }),
+            _ => None,
+        }
+    }
+
+    /// Offset of the next instruction from the one after `from`, as a jump sees it.
+    fn offset_from(&self, from: usize) -> i32 {
+        self.proto.code.len() as i32 - from as i32 - 1
+    }
+
+    /// A match statement, or with `result` a match expression: every arm
+    /// leaves its value in that register, and nil is left if none matches.
+    fn compile_match(&mut self, expr: &Expr, arms: &[MatchArm], result: Option<u8>) {
+        let t_val = self.regs.alloc_temp();
+        self.compile_expr(expr, t_val);
+
+        let mut end_patches = Vec::new();
+
+        // Bare catch-alls only: `Code(n) | n` binds per alternative, which the table cannot
+        let switchable = arms.iter().all(|arm| {
+            arm.guard.is_none()
+                && (matches!(arm.pattern, MatchPattern::Wildcard | MatchPattern::Identifier(_))
+                    || self.pattern_variants(&arm.pattern).is_some())
+        });
+        if switchable {
+            self.compile_enum_match(t_val, arms, result, &mut end_patches);
+        } else {
+            for arm in arms { self.compile_match_arm(arm, t_val, result, &mut end_patches, true); }
+        }
+
+        // No arm matched
+        if let Some(dst) = result { self.emit(Instr::abc(Op::LoadNil, dst, 0, 0)); }
+        for ep in end_patches { self.proto.patch_jump(ep); }
+        self.regs.free_temp(t_val);
+    }
+
+    /// Test R[val] against `pattern`, falling through on a match with its
+    /// variables (already allocated as locals) bound: the jumps taken on a
+    /// mismatch, to be patched to the next arm.
+    fn compile_pattern_test(&mut self, pattern: &MatchPattern, val: u8) -> Vec<usize> {
+        match pattern {
+            MatchPattern::Wildcard => Vec::new(),
+            MatchPattern::Identifier(name) => {
+                if let Some(reg) = self.regs.get_local(name) { self.emit(Instr::abc(Op::Move, reg, val, 0)); }
+                Vec::new()
+            }
+            MatchPattern::Literal(e) => {
+                let t_cond = self.regs.alloc_temp();
+                let t_lit = self.regs.alloc_temp();
+                self.compile_expr(e, t_lit);
+                self.emit(Instr::abc(Op::Eq, t_cond, val, t_lit));
+                self.regs.free_temp(t_lit);
+                let fail = self.proto.emit_jump(Op::JumpFalse, t_cond, self.current_line);
+                self.regs.free_temp(t_cond);
+                vec![fail]
+            }
+            MatchPattern::Range { start, end } => {
+                // lo <= v, then v < hi; mixed types compare false
+                let t_cond = self.regs.alloc_temp();
+                let t_bound = self.regs.alloc_temp();
+                self.compile_expr(start, t_bound);
+                self.emit(Instr::abc(Op::Le, t_cond, t_bound, val));
+                let below = self.proto.emit_jump(Op::JumpFalse, t_cond, self.current_line);
+                self.compile_expr(end, t_bound);
+                self.emit(Instr::abc(Op::Lt, t_cond, val, t_bound));
+                let above = self.proto.emit_jump(Op::JumpFalse, t_cond, self.current_line);
+                self.regs.free_temp(t_bound);
+                self.regs.free_temp(t_cond);
+                vec![below, above]
+            }
+            MatchPattern::List { items, rest } => {
+                let t_cond = self.regs.alloc_temp();
+                let shape = if rest.is_some() { Op::IsListMin } else { Op::IsListLen };
+                self.emit(Instr::abc(shape, t_cond, val, items.len() as u8));
+                let mut fails = vec![self.proto.emit_jump(Op::JumpFalse, t_cond, self.current_line)];
+                self.regs.free_temp(t_cond);
+                for (i, item) in items.iter().enumerate() {
+                    if matches!(item, MatchPattern::Wildcard) { continue; }
+                    let t_elem = self.regs.alloc_temp();
+                    let t_idx = self.regs.alloc_temp();
+                    self.emit(Instr::asbx(Op::LoadInt, t_idx, i as i16));
+                    self.emit(Instr::abc(Op::GetIndex, t_elem, val, t_idx));
+                    self.regs.free_temp(t_idx);
+                    fails.extend(self.compile_pattern_test(item, t_elem));
+                    self.regs.free_temp(t_elem);
+                }
+                if let Some(MatchPattern::Identifier(name)) = rest.as_deref() {
+                    if let Some(reg) = self.regs.get_local(name) {
+                        self.emit(Instr::abc(Op::ListTail, reg, val, items.len() as u8));
+                    }
+                }
+                fails
+            }
+            MatchPattern::EnumVariant { binding, .. } => {
+                // A one-arm table: its variants skip the jump to the next arm
+                let variants = self.pattern_variants(pattern).unwrap_or_default();
+                let table_idx = self.proto.jump_tables.len();
+                self.proto.jump_tables.push(JumpTable {
+                    targets: variants.into_iter().map(|v| (v, 1)).collect(),
+                    default: 0,
+                });
+                self.emit(Instr::abx(Op::MatchEnum, val, table_idx as u16));
+                let fail = self.proto.emit_jump(Op::Jump, 0, self.current_line);
+                if let Some(reg) = binding.as_deref().and_then(|name| self.regs.get_local(name)) {
+                    self.emit(Instr::abc(Op::EnumPayload, reg, val, 0));
+                }
+                vec![fail]
+            }
+            MatchPattern::Or(alts) => {
+                // Each alternative but the last jumps to the arm on a match
+                // and falls through to the next one on a mismatch
+                let Some((last, init)) = alts.split_last() else { return Vec::new() };
+                let mut hits = Vec::new();
+                for alt in init {
+                    let fails = self.compile_pattern_test(alt, val);
+                    hits.push(self.proto.emit_jump(Op::Jump, 0, self.current_line));
+                    for f in fails { self.proto.patch_jump(f); }
+                }
+                let fails = self.compile_pattern_test(last, val);
+                for h in hits { self.proto.patch_jump(h); }
+                fails
+            }
+        }
+    }
+
+    /// Bind the arm's pattern variables, test its guard, run its body (its
+    /// last expression into `result`, if given) and jump to the end. With
+    /// `test` the arm first tests its pattern; a mismatch, like a false
+    /// guard, lands after the arm. Without it a jump table already chose it.
+    fn compile_match_arm(&mut self, arm: &MatchArm, t_val: u8, result: Option<u8>, end_patches: &mut Vec<usize>, test: bool) {
+        let scope = self.enter_scope();
+        let bindings = arm.pattern.bindings();
+        let regs: Vec<u8> = bindings.iter().map(|(name, _)| self.regs.alloc_local(*name)).collect();
+        let mut fails = Vec::new();
+        if test {
+            // Names only some alternatives bind stay nil, not a previous arm's value
+            if has_alternatives(&arm.pattern) {
+                for &reg in &regs { self.emit(Instr::abc(Op::LoadNil, reg, 0, 0)); }
+            }
+            fails = self.compile_pattern_test(&arm.pattern, t_val);
+        } else {
+            for (&(_, payload), &reg) in bindings.iter().zip(&regs) {
+                let op = if payload { Op::EnumPayload } else { Op::Move };
+                self.emit(Instr::abc(op, reg, t_val, 0));
+            }
+        }
+        if let Some(guard) = &arm.guard {
+            let t_cond = self.regs.alloc_temp();
+            let r = self.compile_expr(guard, t_cond);
+            fails.push(self.proto.emit_jump(Op::JumpFalse, r, self.current_line));
+            self.regs.free_temp(t_cond);
+        }
+        match (result, arm.body.split_last()) {
+            (Some(dst), Some((Stmt::Expr(last), init))) => {
+                for s in init { self.compile_stmt(s); }
+                let r = self.compile_expr(last, dst);
+                if r != dst { self.emit(Instr::abc(Op::Move, dst, r, 0)); }
+            }
+            (Some(dst), _) => {
+                for s in &arm.body { self.compile_stmt(s); }
+                self.emit(Instr::abc(Op::LoadNil, dst, 0, 0));
+            }
+            (None, _) => for s in &arm.body { self.compile_stmt(s); },
+        }
+        self.exit_scope(scope);
+        end_patches.push(self.proto.emit_jump(Op::Jump, 0, self.current_line));
+        for f in fails { self.proto.patch_jump(f); }
+    }
+
+    /// A `match` whose arms are all unguarded enum patterns or catch-alls:
+    /// one MatchEnum through a jump table, first matching arm wins.
+    fn compile_enum_match(&mut self, t_val: u8, arms: &[MatchArm], result: Option<u8>, end_patches: &mut Vec<usize>) {
+        let table_idx = self.proto.jump_tables.len();
+        self.proto.jump_tables.push(JumpTable::default());
+        let switch = self.emit(Instr::abx(Op::MatchEnum, t_val, table_idx as u16));
+
+        let mut table = JumpTable::default();
+        let mut default = None;
+        for arm in arms {
+            let offset = self.offset_from(switch);
+            if default.is_none() {
+                if arm.pattern.is_catch_all() {
+                    default = Some(offset);
+                } else {
+                    for v in self.pattern_variants(&arm.pattern).unwrap_or_default() { table.targets.entry(v).or_insert(offset); }
+                }
+            }
+            self.compile_match_arm(arm, t_val, result, end_patches, false);
+        }
+        table.default = default.unwrap_or_else(|| self.offset_from(switch));
+        self.proto.jump_tables[table_idx] = table;
+    }
+
     // -----------------------------------------------------------------------
     // Expression compilation
     // -----------------------------------------------------------------------
 
+    /// Compile `items` into consecutive temps and return the first.
+    fn compile_items(&mut self, items: &[Expr]) -> u8 {
+        let base = self.regs.alloc_temp();
+        for _ in 1..items.len() {
+            self.regs.alloc_temp();
+        }
+        for (i, item) in items.iter().enumerate() {
+            self.compile_expr(item, base + i as u8);
+        }
+        base
+    }
+
+    /// Release the temps `compile_items` took, newest first.
+    fn free_items(&mut self, base: u8, count: usize) {
+        for i in (0..count.max(1)).rev() {
+            self.regs.free_temp(base + i as u8);
+        }
+    }
+
     /// Compile an expression into destination register `dst`.
     /// Returns the actual register holding the result.
     #[allow(unreachable_patterns)]
@@ -192,15 +627,23 @@ impl<'g> Compiler<'g> {
             }
 
             Expr::Identifier { name, .. } => {
-                // Local first
-                if let Some(reg) = self.regs.get_local(name) {
-                    if reg != dst {
-                        self.emit(Instr::abc(Op::Move, dst, reg, 0));
+                match self.resolve(name) {
+                    Var::Local(reg) => {
+                        if reg != dst {
+                            self.emit(Instr::abc(Op::Move, dst, reg, 0));
+                        }
+                        dst
                     }
-                    return dst;
+                    Var::Upval(i) => {
+                        self.emit(Instr::abc(Op::LoadUpval, dst, i, 0));
+                        dst
+                    }
+                    Var::Callee => {
+                        self.emit(Instr::abc(Op::LoadCallee, dst, 0, 0));
+                        dst
+                    }
+                    Var::Global => self.emit_load_global(dst, name),
                 }
-                // Global
-                self.emit_load_global(dst, name)
             }
 
             Expr::UnaryOp { op, operand, .. } => {
@@ -245,10 +688,18 @@ impl<'g> Compiler<'g> {
             Expr::Assign { target, value, .. } => {
                 match target.as_ref() {
                     Expr::Identifier { name, .. } => {
-                        if let Some(reg) = self.regs.get_local(name) {
-                            self.compile_expr(value, reg);
-                            if reg != dst { self.emit(Instr::abc(Op::Move, dst, reg, 0)); }
-                            return dst;
+                        match self.resolve(name) {
+                            Var::Local(reg) => {
+                                self.compile_expr(value, reg);
+                                if reg != dst { self.emit(Instr::abc(Op::Move, dst, reg, 0)); }
+                                return dst;
+                            }
+                            Var::Upval(i) => {
+                                let r = self.compile_expr(value, dst);
+                                self.emit(Instr::abc(Op::StoreUpval, r, i, 0));
+                                return dst;
+                            }
+                            Var::Callee | Var::Global => {}
                         }
                         // Global assign
                         let t = self.regs.alloc_temp();
@@ -262,12 +713,12 @@ impl<'g> Compiler<'g> {
                         let t_val = self.regs.alloc_temp();
                         let obj_r = self.compile_expr(object, t_obj);
                         let val_r = self.compile_expr(value, t_val);
+                        let t_key = self.regs.alloc_temp();
                         let str_idx = self.proto.add_string(member.clone());
-                        self.emit(Instr::abc(Op::SetProp, obj_r, val_r, 0));
-                        // Patch Bx
-                        let last = self.proto.code.len() - 1;
-                        self.proto.code[last] = Instr::abx(Op::SetProp, obj_r, str_idx);
-                        self.proto.code[last].0 |= (val_r as u32) << 24;
+                        self.emit(Instr::abx(Op::LoadStr, t_key, str_idx));
+                        self.emit(Instr::abc(Op::SetProp, obj_r, t_key, val_r));
+                        if val_r != dst { self.emit(Instr::abc(Op::Move, dst, val_r, 0)); }
+                        self.regs.free_temp(t_key);
                         self.regs.free_temp(t_val);
                         self.regs.free_temp(t_obj);
                     }
@@ -291,6 +742,22 @@ impl<'g> Compiler<'g> {
             }
 
             Expr::Call { function, arguments, .. } => {
+                // str(x) is display formatting, whatever `str` is bound to
+                if matches!(function.as_ref(), Expr::Identifier { name, .. } if name == "str") {
+                    let Some(arg) = arguments.first() else {
+                        self.emit(Instr::abc(Op::LoadNil, dst, 0, 0));
+                        return dst;
+                    };
+                    let t_empty = self.regs.alloc_temp();
+                    let t_arg = self.regs.alloc_temp();
+                    let idx = self.proto.add_string("");
+                    self.emit(Instr::abx(Op::LoadStr, t_empty, idx));
+                    let r = self.compile_expr(arg, t_arg);
+                    self.emit(Instr::abc(Op::Concat, dst, t_empty, r));
+                    self.regs.free_temp(t_arg);
+                    self.regs.free_temp(t_empty);
+                    return dst;
+                }
                 // Func goes in t, args in t+1, t+2, ...
                 let func_reg = self.regs.alloc_temp();
                 let f_r = self.compile_expr(function, func_reg);
@@ -314,20 +781,41 @@ impl<'g> Compiler<'g> {
                 dst
             }
 
+            Expr::MethodCall { object, method, arguments, .. } if arguments.len() <= 1
+                && self.enum_variant(object, method) == Some(true) =>
+            {
+                let Expr::Identifier { name, .. } = object.as_ref() else { unreachable!() };
+                match arguments.first() {
+                    Some(arg) => { let r = self.compile_expr(arg, dst); if r != dst { self.emit(Instr::abc(Op::Move, dst, r, 0)); } }
+                    None => { self.emit(Instr::abc(Op::LoadNil, dst, 0, 0)); }
+                }
+                self.emit_new_enum(dst, name, method);
+                dst
+            }
+
+            Expr::MethodCall { object, method, arguments, .. } if self.static_method(object, method) => {
+                // Resolved here: the method's own global, no lookup on the class namespace
+                let Expr::Identifier { name, .. } = object.as_ref() else { unreachable!() };
+                let func_reg = self.regs.alloc_temp();
+                self.emit_load_global(func_reg, &format!("{}.{}", name, method));
+                let mut arg_regs = Vec::new();
+                for arg in arguments.iter() {
+                    let t = self.regs.alloc_temp();
+                    arg_regs.push(self.compile_expr(arg, t));
+                }
+                self.emit(Instr::abc(Op::Call, dst, func_reg, arguments.len() as u8));
+                for r in arg_regs.into_iter().rev() { self.regs.free_temp(r); }
+                self.regs.free_temp(func_reg);
+                dst
+            }
+
             Expr::MethodCall { object, method, arguments, .. } => {
-                let t_obj = self.regs.alloc_temp();
-                let obj_r = self.compile_expr(object, t_obj);
-                let str_idx = self.proto.add_string(method.clone());
                 let argc = arguments.len() as u8;
 
-                // GetMethod into a temp, then Call
+                // Receiver into a temp, GetMethod binds it in place, then Call
                 let t_meth = self.regs.alloc_temp();
-                self.emit(Instr::abx(Op::GetMethod, t_meth, str_idx));
-                // Patch in obj register
-                let last = self.proto.code.len() - 1;
-                self.proto.code[last] = Instr(
-                    (Op::GetMethod as u32) | ((t_meth as u32) << 8) | ((obj_r as u32) << 16) | ((str_idx as u32) << 8 << 8)
-                );
+                let obj_r = self.compile_expr(object, t_meth);
+                self.emit_member(Op::GetMethod, t_meth, obj_r, method);
 
                 let mut arg_regs = Vec::new();
                 for arg in arguments.iter() {
@@ -340,19 +828,20 @@ impl<'g> Compiler<'g> {
 
                 for r in arg_regs.into_iter().rev() { self.regs.free_temp(r); }
                 self.regs.free_temp(t_meth);
-                self.regs.free_temp(t_obj);
+                dst
+            }
+
+            Expr::MemberAccess { object, member, .. } if self.enum_variant(object, member) == Some(false) => {
+                let Expr::Identifier { name, .. } = object.as_ref() else { unreachable!() };
+                self.emit(Instr::abc(Op::LoadNil, dst, 0, 0));
+                self.emit_new_enum(dst, name, member);
                 dst
             }
 
             Expr::MemberAccess { object, member, .. } => {
-                let t = self.regs.alloc_temp();
-                let obj_r = self.compile_expr(object, t);
-                let str_idx = self.proto.add_string(member.clone());
-                // GetProp dst, obj, str_idx  — IC attached here
-                self.emit(Instr::abx(Op::GetProp, dst, str_idx));
-                let last = self.proto.code.len() - 1;
-                self.proto.code[last].0 |= (obj_r as u32) << 24;
-                self.regs.free_temp(t);
+                let obj_r = self.compile_expr(object, dst);
+                // GetProp dst, str_idx — IC attached here
+                self.emit_member(Op::GetProp, dst, obj_r, member);
                 dst
             }
 
@@ -372,10 +861,7 @@ impl<'g> Compiler<'g> {
                 self.emit(Instr::abx(Op::NewObj, dst, class_idx));
                 // Compile constructor args into temps and call "init"
                 let t_meth = self.regs.alloc_temp();
-                let str_idx = self.proto.add_string("init");
-                self.emit(Instr::abx(Op::GetMethod, t_meth, str_idx));
-                let last = self.proto.code.len() - 1;
-                self.proto.code[last].0 |= (dst as u32) << 24;
+                self.emit_member(Op::GetMethod, t_meth, dst, "init");
 
                 let argc = arguments.len() as u8;
                 let mut arg_regs = Vec::new();
@@ -394,29 +880,27 @@ impl<'g> Compiler<'g> {
                 dst
             }
 
-            Expr::List { items, .. } => {
-                let count = items.len();
-                let base = self.regs.alloc_temp();
-                let start = base;
-
-                // Alloc consecutive regs for all items
-                let mut item_regs = vec![base];
-                for _ in 1..count {
-                    item_regs.push(self.regs.alloc_temp());
-                }
-
-                for (i, item) in items.iter().enumerate() {
-                    self.compile_expr(item, item_regs[i]);
+            Expr::List { items, .. } if items.len() > LIST_CHUNK => {
+                // Too many items for one register window: start from an empty
+                // list sized for all of them, then append a chunk at a time.
+                // The items may read `dst`, so the list is built in a temp.
+                let list = self.regs.alloc_temp();
+                self.emit(Instr::abc(Op::NewList, list, 0, 0));
+                self.emit(Instr::abx(Op::ListReserve, list, items.len().min(u16::MAX as usize) as u16));
+                for chunk in items.chunks(LIST_CHUNK) {
+                    let base = self.compile_items(chunk);
+                    self.emit(Instr::abc(Op::ListExtend, list, base, chunk.len() as u8));
+                    self.free_items(base, chunk.len());
                 }
-
+                self.emit(Instr::abc(Op::Move, dst, list, 0));
+                self.regs.free_temp(list);
+                dst
+            }
+            Expr::List { items, .. } => {
                 // Use abc(dst, base, count): b=base reg, c=count — no post-emit patching needed.
-                // Both base and count fit in u8 (max 255 regs / 255-element literal list).
-                self.emit(Instr::abc(Op::NewList, dst, start, count as u8));
-
-                for r in item_regs.into_iter().skip(1).rev() {
-                    self.regs.free_temp(r);
-                }
-                self.regs.free_temp(base);
+                let base = self.compile_items(items);
+                self.emit(Instr::abc(Op::NewList, dst, base, items.len() as u8));
+                self.free_items(base, items.len());
                 dst
             }
 
@@ -441,6 +925,18 @@ impl<'g> Compiler<'g> {
                         StringPart::Expr(e) => {
                             self.compile_expr(e, t);
                         }
+                        StringPart::Formatted { expr, spec } => {
+                            // __fmt(value, spec)
+                            self.emit_load_global(t, "__fmt");
+                            let t_val = self.regs.alloc_temp();
+                            self.compile_expr(expr, t_val);
+                            let t_spec = self.regs.alloc_temp();
+                            let idx = self.proto.add_string(spec.clone());
+                            self.emit(Instr::abx(Op::LoadStr, t_spec, idx));
+                            self.emit(Instr::abc(Op::Call, t, t, 2));
+                            self.regs.free_temp(t_spec);
+                            self.regs.free_temp(t_val);
+                        }
                     }
                     if first {
                         if t != dst { self.emit(Instr::abc(Op::Move, dst, t, 0)); }
@@ -454,28 +950,14 @@ impl<'g> Compiler<'g> {
                 dst
             }
 
-            Expr::Lambda { params, body, .. } => {
-                // Compile lambda as nested proto
-                let mut lambda_compiler = Compiler::new(
-                    format!("{}.lambda", self.proto.source),
-                    self.globals,
-                );
-                for p in params {
-                    lambda_compiler.regs.alloc_local(p);
-                }
-                for stmt in body {
-                    lambda_compiler.compile_stmt(stmt);
-                }
-                // Ensure return
-                let last = lambda_compiler.proto.code.last().map(|i| i.op());
-                if !matches!(last, Some(Op::Return) | Some(Op::ReturnNil) | Some(Op::NilReturn)) {
-                    lambda_compiler.emit(Instr::abc(Op::ReturnNil, 0, 0, 0));
-                }
-                lambda_compiler.proto.reg_count = lambda_compiler.regs.reg_count();
-                lambda_compiler.proto.param_count = params.len() as u8;
+            Expr::Match { expr, arms, .. } => {
+                // Every arm writes `dst`, so whichever runs leaves the result there
+                self.compile_match(expr, arms, Some(dst));
+                dst
+            }
 
-                let proto_idx = self.proto.protos.len() as u16;
-                self.proto.protos.push(lambda_compiler.proto);
+            Expr::Lambda { params, body, .. } => {
+                let proto_idx = self.compile_lambda(params, body, None);
                 self.emit(Instr::abx(Op::Closure, dst, proto_idx));
                 dst
             }
@@ -511,6 +993,20 @@ impl<'g> Compiler<'g> {
     pub fn compile_stmt(&mut self, stmt: &Stmt) {
         match stmt {
             Stmt::Let { name, value, .. } => {
+                if self.lets_are_globals && self.scope_depth == 0 {
+                    let t = self.regs.alloc_temp();
+                    let r = self.compile_expr(value, t);
+                    self.emit_store_global(r, name);
+                    self.regs.free_temp(t);
+                    return;
+                }
+                if let Expr::Lambda { params, body, .. } = value {
+                    // `fn name(..)` inside a body: the lambda may call itself by name
+                    let reg = self.regs.alloc_local(name);
+                    let proto_idx = self.compile_lambda(params, body, Some(name));
+                    self.emit(Instr::abx(Op::Closure, reg, proto_idx));
+                    return;
+                }
                 let reg = self.regs.alloc_local(name);
                 self.compile_expr(value, reg);
             }
@@ -522,21 +1018,23 @@ impl<'g> Compiler<'g> {
             }
 
             Stmt::Out { arguments, .. } => {
-                // Compile each arg, then call the built-in "out" global
+                // The statement form prints its arguments back to back: concat
+                // them into one string, then call the built-in "out" global
                 let t_fn = self.regs.alloc_temp();
                 self.emit_load_global(t_fn, "out");
-
-                let argc = arguments.len() as u8;
-                let mut arg_regs = Vec::new();
+                let t_line = self.regs.alloc_temp();
+                let idx = self.proto.add_string("");
+                self.emit(Instr::abx(Op::LoadStr, t_line, idx));
                 for arg in arguments {
                     let t = self.regs.alloc_temp();
                     let r = self.compile_expr(arg, t);
-                    arg_regs.push(r);
+                    self.emit(Instr::abc(Op::Concat, t_line, t_line, r));
+                    self.regs.free_temp(t);
                 }
                 let t_ret = self.regs.alloc_temp();
-                self.emit(Instr::abc(Op::Call, t_ret, t_fn, argc));
+                self.emit(Instr::abc(Op::Call, t_ret, t_fn, 1));
                 self.regs.free_temp(t_ret);
-                for r in arg_regs.into_iter().rev() { self.regs.free_temp(r); }
+                self.regs.free_temp(t_line);
                 self.regs.free_temp(t_fn);
             }
 
@@ -560,16 +1058,16 @@ impl<'g> Compiler<'g> {
                 let false_jump = self.proto.emit_jump(Op::JumpFalse, t, self.current_line);
                 self.regs.free_temp(t);
 
-                let scope = self.regs.push_scope();
+                let scope = self.enter_scope();
                 for s in then_body { self.compile_stmt(s); }
-                self.regs.pop_scope(scope);
+                self.exit_scope(scope);
 
                 if let Some(else_stmts) = else_body {
                     let end_jump = self.proto.emit_jump(Op::Jump, 0, self.current_line);
                     self.proto.patch_jump(false_jump);
-                    let scope = self.regs.push_scope();
+                    let scope = self.enter_scope();
                     for s in else_stmts { self.compile_stmt(s); }
-                    self.regs.pop_scope(scope);
+                    self.exit_scope(scope);
                     self.proto.patch_jump(end_jump);
                 } else {
                     self.proto.patch_jump(false_jump);
@@ -586,9 +1084,9 @@ impl<'g> Compiler<'g> {
                 let exit_jump = self.proto.emit_jump(Op::JumpFalse, t, self.current_line);
                 self.regs.free_temp(t);
 
-                let scope = self.regs.push_scope();
+                let scope = self.enter_scope();
                 for s in body { self.compile_stmt(s); }
-                self.regs.pop_scope(scope);
+                self.exit_scope(scope);
 
                 // LoopBack (profiling back-edge)
                 let offset = loop_start as i32 - self.proto.code.len() as i32 - 1;
@@ -604,6 +1102,8 @@ impl<'g> Compiler<'g> {
             Stmt::For { var, iterable, body, .. } => {
                 // Compile: for v in list { body }
                 // Desugars to: let __iter = iterable; let __i = 0; while __i < len(__iter) { let v = __iter[__i]; body; __i++ }
+                // `v` and the hidden registers are scoped to the loop
+                let outer = self.enter_scope();
                 let t_iter = self.regs.alloc_temp();
                 self.compile_expr(iterable, t_iter);
 
@@ -627,9 +1127,9 @@ impl<'g> Compiler<'g> {
                 let v_reg = self.regs.alloc_local(var);
                 self.emit(Instr::abc(Op::GetIndex, v_reg, t_iter, t_i));
 
-                let scope = self.regs.push_scope();
+                let scope = self.enter_scope();
                 for s in body { self.compile_stmt(s); }
-                self.regs.pop_scope(scope);
+                self.exit_scope(scope);
 
                 // i++
                 self.emit(Instr::abc(Op::IncrLocal, t_i, 0, 0));
@@ -642,71 +1142,29 @@ impl<'g> Compiler<'g> {
                 for b in breaks { self.proto.patch_jump(b); }
                 self.loop_starts.pop();
 
-                self.regs.free_temp(t_i);
-                self.regs.free_temp(t_len);
-                self.regs.free_temp(t_iter);
+                self.exit_scope(outer);
             }
 
-            Stmt::Match { expr, arms, .. } => {
-                let t_val = self.regs.alloc_temp();
-                self.compile_expr(expr, t_val);
-
-                let mut end_patches = Vec::new();
-
-                for arm in arms {
-                    let t_cond = self.regs.alloc_temp();
-                    match &arm.pattern {
-                        MatchPattern::Wildcard | MatchPattern::Identifier(_) => {
-                            // Always matches
-                            self.emit(Instr::abc(Op::LoadTrue, t_cond, 0, 0));
-                        }
-                        MatchPattern::Literal(e) => {
-                            let t_lit = self.regs.alloc_temp();
-                            self.compile_expr(e, t_lit);
-                            self.emit(Instr::abc(Op::Eq, t_cond, t_val, t_lit));
-                            self.regs.free_temp(t_lit);
-                        }
-                        MatchPattern::EnumVariant { enum_name, variant, .. } => {
-                            let t_expect = self.regs.alloc_temp();
-                            let key = match enum_name {
-                                Some(e) => format!("{}.{}", e, variant),
-                                None => variant.clone(),
-                            };
-                            let idx = self.proto.add_string(key);
-                            self.emit(Instr::abx(Op::LoadStr, t_expect, idx));
-                            self.emit(Instr::abc(Op::Eq, t_cond, t_val, t_expect));
-                            self.regs.free_temp(t_expect);
-                        }
-                    }
-
-                    let skip_jump = self.proto.emit_jump(Op::JumpFalse, t_cond, self.current_line);
-                    self.regs.free_temp(t_cond);
-
-                    let scope = self.regs.push_scope();
-                    for s in &arm.body { self.compile_stmt(s); }
-                    self.regs.pop_scope(scope);
-
-                    let end_j = self.proto.emit_jump(Op::Jump, 0, self.current_line);
-                    end_patches.push(end_j);
-                    self.proto.patch_jump(skip_jump);
-                }
-
-                for ep in end_patches { self.proto.patch_jump(ep); }
-                self.regs.free_temp(t_val);
-            }
+            Stmt::Match { expr, arms, .. } => self.compile_match(expr, arms, None),
 
             Stmt::Block(stmts) => {
-                let scope = self.regs.push_scope();
+                let scope = self.enter_scope();
                 for s in stmts { self.compile_stmt(s); }
-                self.regs.pop_scope(scope);
+                self.exit_scope(scope);
             }
 
             Stmt::GoSpawn { body, .. } => {
-                // Compile body as lambda, then call via goroutine mechanism
-                // For now: just compile as block (goroutine scheduling handled by VM)
-                let scope = self.regs.push_scope();
-                for s in body { self.compile_stmt(s); }
-                self.regs.pop_scope(scope);
+                // Compile body as a closure and hand it to the runtime's spawner
+                let t_fn = self.regs.alloc_temp();
+                self.emit_load_global(t_fn, "__go");
+                let t_body = self.regs.alloc_temp();
+                let proto_idx = self.compile_lambda(&[], body, None);
+                self.emit(Instr::abx(Op::Closure, t_body, proto_idx));
+                let t_ret = self.regs.alloc_temp();
+                self.emit(Instr::abc(Op::Call, t_ret, t_fn, 1));
+                self.regs.free_temp(t_ret);
+                self.regs.free_temp(t_body);
+                self.regs.free_temp(t_fn);
             }
         }
     }
@@ -727,7 +1185,12 @@ impl<'g> Compiler<'g> {
 // ---------------------------------------------------------------------------
 
 pub fn compile_program(items: &[Item], source: &str) -> (Proto, GlobalTable) {
-    let mut globals = GlobalTable::new();
+    compile_program_with(items, source, GlobalTable::new())
+}
+
+/// `compile_program` against an existing table: its names keep their
+/// indices and the program's own globals are numbered after them.
+pub fn compile_program_with(items: &[Item], source: &str, mut globals: GlobalTable) -> (Proto, GlobalTable) {
 
     // Pre-intern standard globals so the VM can index them by u16
     // NOTE: Module names (alg, str, etc.) are NOT pre-interned here.
@@ -736,7 +1199,7 @@ pub fn compile_program(items: &[Item], source: &str) -> (Proto, GlobalTable) {
     for name in &["out", "print", "in", "int", "bol", "type", "nil",
                    "sqrt", "abs", "floor", "ceil", "pow", "min", "max", "avg",
                    "str",  // built-in str() function, separate from str module
-                   "chdir", "cwd", "__load"] {
+                   "chdir", "cwd", "__load", "__go", "__fmt"] {
         globals.intern(name);
     }
 
@@ -745,7 +1208,8 @@ pub fn compile_program(items: &[Item], source: &str) -> (Proto, GlobalTable) {
     for item in items {
         match item {
             Item::FunctionDecl { name, .. } => { globals.intern(name); }
-            Item::ClassDecl    { name, .. } => { globals.intern(name); }
+            Item::ClassDecl    { name, body, .. } => { globals.declare_class(name, body); }
+            Item::EnumDecl     { name, variants, .. } => { globals.declare_enum(name, variants); }
             _ => {}
         }
     }
@@ -755,34 +1219,46 @@ pub fn compile_program(items: &[Item], source: &str) -> (Proto, GlobalTable) {
     // next one is created — this avoids the double-borrow compile error.
     let mut fn_protos: Vec<(String, Proto)> = Vec::new();
 
-    for item in items {
-        if let Item::FunctionDecl { name, params, body, .. } = item {
-            let compiled_proto = {
-                let mut fn_compiler = Compiler::new(
-                    format!("{}:{}", source, name),
-                    &mut globals,
-                );
-                for p in params {
-                    fn_compiler.regs.alloc_local(p);
-                }
-                for stmt in body {
-                    fn_compiler.compile_stmt(stmt);
-                }
-                let last = fn_compiler.proto.code.last().map(|i| i.op());
-                if !matches!(last, Some(Op::Return) | Some(Op::ReturnNil) | Some(Op::NilReturn)) {
-                    fn_compiler.emit(Instr::abc(Op::ReturnNil, 0, 0, 0));
-                }
-                fn_compiler.proto.reg_count = fn_compiler.regs.reg_count();
-                fn_compiler.proto.param_count = params.len() as u8;
-                fn_compiler.proto
-            }; // fn_compiler dropped here — globals borrow released
-            fn_protos.push((name.clone(), compiled_proto));
-        }
+    // Static methods compile like functions, each into a `Class.method` global
+    let static_methods = items.iter().filter_map(|item| match item {
+        Item::ClassDecl { name: class, body, .. } => Some(body.iter().filter_map(move |m| match m {
+            ClassMember::Method { name, params, body, is_static: true, .. } => Some((format!("{}.{}", class, name), params, body)),
+            _ => None,
+        })),
+        _ => None,
+    }).flatten();
+    let functions = items.iter().filter_map(|item| match item {
+        Item::FunctionDecl { name, params, body, .. } => Some((name.clone(), params, body)),
+        _ => None,
+    });
+
+    for (name, params, body) in functions.chain(static_methods) {
+        let compiled_proto = {
+            let mut fn_compiler = Compiler::new(
+                format!("{}:{}", source, name),
+                &mut globals,
+            );
+            for p in params {
+                fn_compiler.regs.alloc_local(p);
+            }
+            for stmt in body {
+                fn_compiler.compile_stmt(stmt);
+            }
+            let last = fn_compiler.proto.code.last().map(|i| i.op());
+            if !matches!(last, Some(Op::Return) | Some(Op::ReturnNil) | Some(Op::NilReturn)) {
+                fn_compiler.emit(Instr::abc(Op::ReturnNil, 0, 0, 0));
+            }
+            fn_compiler.proto.reg_count = fn_compiler.regs.reg_count();
+            fn_compiler.proto.param_count = params.len() as u8;
+            fn_compiler.proto
+        }; // fn_compiler dropped here — globals borrow released
+        fn_protos.push((name, compiled_proto));
     }
 
     // ── Pass 2: build the top-level Proto ────────────────────────────────────
     // All fn_compilers are gone; we can now hold the single main compiler.
     let mut compiler = Compiler::new(source, &mut globals);
+    compiler.lets_are_globals = true;
 
     // Hoist compiled function closures into globals
     for (name, proto) in fn_protos {
@@ -794,13 +1270,67 @@ pub fn compile_program(items: &[Item], source: &str) -> (Proto, GlobalTable) {
         compiler.regs.free_temp(t);
     }
 
-    // Hoist class placeholders (class bodies executed by the runtime)
+    // Hoist enum namespaces: unit variants as values, data variants as
+    // one-argument constructors
     for item in items {
-        if let Item::ClassDecl { name, .. } = item {
-            let class_idx = compiler.globals.intern(name);
-            let t = compiler.regs.alloc_temp();
-            compiler.emit(Instr::abx(Op::LoadGlobal, t, class_idx));
-            compiler.regs.free_temp(t);
+        if let Item::EnumDecl { name, variants, .. } = item {
+            let t_ns = compiler.regs.alloc_temp();
+            compiler.emit(Instr::abc(Op::NewMap, t_ns, 0, 0));
+            for v in variants {
+                let t_key = compiler.regs.alloc_temp();
+                let key_idx = compiler.proto.add_string(&v.name);
+                compiler.emit(Instr::abx(Op::LoadStr, t_key, key_idx));
+                let t_v = compiler.regs.alloc_temp();
+                if v.has_data {
+                    let mut ctor = Proto::new(format!("{}:{}.{}", source, name, v.name));
+                    let idx = ctor.add_variant(&format!("{}.{}", name, v.name));
+                    ctor.emit(Instr::abx(Op::NewEnum, 0, idx), compiler.current_line);
+                    ctor.emit(Instr::abc(Op::Return, 0, 0, 0), compiler.current_line);
+                    ctor.reg_count = 1;
+                    ctor.param_count = 1;
+                    let proto_idx = compiler.proto.protos.len() as u16;
+                    compiler.proto.protos.push(ctor);
+                    compiler.emit(Instr::abx(Op::Closure, t_v, proto_idx));
+                } else {
+                    compiler.emit(Instr::abc(Op::LoadNil, t_v, 0, 0));
+                    compiler.emit_new_enum(t_v, name, &v.name);
+                }
+                compiler.emit(Instr::abc(Op::SetProp, t_ns, t_key, t_v));
+                compiler.regs.free_temp(t_v);
+                compiler.regs.free_temp(t_key);
+            }
+            compiler.emit_store_global(t_ns, name);
+            compiler.regs.free_temp(t_ns);
+        }
+    }
+
+    // Hoist class namespaces: static fields with their initial values and
+    // static methods (instance members are executed by the runtime)
+    for item in items {
+        if let Item::ClassDecl { name, body, .. } = item {
+            let t_ns = compiler.regs.alloc_temp();
+            compiler.emit(Instr::abc(Op::NewMap, t_ns, 0, 0));
+            for member in body {
+                let (key, default) = match member {
+                    ClassMember::Field { name, default, is_static: true, .. } => (name, default.as_ref()),
+                    ClassMember::Method { name, is_static: true, .. } => (name, None),
+                    _ => continue,
+                };
+                let t_key = compiler.regs.alloc_temp();
+                let key_idx = compiler.proto.add_string(key);
+                compiler.emit(Instr::abx(Op::LoadStr, t_key, key_idx));
+                let t_v = compiler.regs.alloc_temp();
+                let r = match (member, default) {
+                    (ClassMember::Method { .. }, _) => { compiler.emit_load_global(t_v, &format!("{}.{}", name, key)); t_v }
+                    (_, Some(e)) => compiler.compile_expr(e, t_v),
+                    (_, None) => { compiler.emit(Instr::abc(Op::LoadNil, t_v, 0, 0)); t_v }
+                };
+                compiler.emit(Instr::abc(Op::SetProp, t_ns, t_key, r));
+                compiler.regs.free_temp(t_v);
+                compiler.regs.free_temp(t_key);
+            }
+            compiler.emit_store_global(t_ns, name);
+            compiler.regs.free_temp(t_ns);
         }
     }
 
@@ -819,16 +1349,130 @@ pub fn compile_program(items: &[Item], source: &str) -> (Proto, GlobalTable) {
                 if let Some(alias_name) = alias {
                     compiler.emit_store_global(t_ret, alias_name);
                 }
-                let full_key = path.trim_start_matches('@').replace('/', ".").replace('-', "_");
-                compiler.emit_store_global(t_ret, &full_key);
+                compiler.emit_store_global(t_ret, &crate::loader::module_key(path));
+                compiler.regs.free_temp(t_ret);
+                compiler.regs.free_temp(t_path);
+                compiler.regs.free_temp(t_fn);
+            }
+            Item::LocImport { name, alias, names, .. } => {
+                let t_fn = compiler.regs.alloc_temp();
+                compiler.emit_load_global(t_fn, "__load");
+                let t_path = compiler.regs.alloc_temp();
+                let idx = compiler.proto.add_string(name);
+                compiler.emit(Instr::abx(Op::LoadStr, t_path, idx));
+                let t_ret = compiler.regs.alloc_temp();
+                compiler.emit(Instr::abc(Op::Call, t_ret, t_fn, 1));
+                let binding = alias.as_deref().unwrap_or_else(|| crate::loader::loc_binding(name));
+                compiler.bind_import(t_ret, binding, names.as_deref());
                 compiler.regs.free_temp(t_ret);
                 compiler.regs.free_temp(t_path);
                 compiler.regs.free_temp(t_fn);
             }
+            Item::StdImport { module, alias, names, .. } => {
+                let t_mod = compiler.regs.alloc_temp();
+                compiler.emit_load_global(t_mod, module);
+                compiler.bind_import(t_mod, alias.as_deref().unwrap_or(module), names.as_deref());
+                compiler.regs.free_temp(t_mod);
+            }
             _ => {}
         }
     }
 
-    let proto = compiler.finalize();
+    let mut proto = compiler.finalize();
+    if crate::conf::AxConf::load().string_interning() {
+        proto.intern_strings();
+    }
     (proto, globals)
 }
+
+// ---------------------------------------------------------------------------
+// VM coverage
+// ---------------------------------------------------------------------------
+
+/// The first construct in `items` that `compile_program` cannot compile
+/// faithfully yet (such programs run on the tree-walker), or `None`. What
+/// is left is objects: `new`, `self`, `super` and classes with instance
+/// members. docs/config-tuning.md lists the work still open for `engine=vm`.
+pub fn vm_unsupported(items: &[Item]) -> Option<&'static str> {
+    items.iter().find_map(|item| match item {
+        Item::ClassDecl { body, .. } => body.iter().find_map(|member| match member {
+            ClassMember::Method { body, is_static: true, .. } => unsupported_in_block(body),
+            ClassMember::Field { default, is_static: true, .. } => default.as_ref().and_then(unsupported_in_expr),
+            _ => Some("classes"),
+        }),
+        Item::FunctionDecl { body, .. } => unsupported_in_block(body),
+        Item::Statement(stmt) => unsupported_in_stmt(stmt),
+        Item::EnumDecl { .. } | Item::InterfaceDecl { .. } | Item::LocImport { .. } | Item::StdImport { .. } | Item::LibDecl { .. } | Item::Export { .. } | Item::LoadStmt { .. } => None,
+    })
+}
+
+fn unsupported_in_block(stmts: &[Stmt]) -> Option<&'static str> {
+    stmts.iter().find_map(unsupported_in_stmt)
+}
+
+fn unsupported_in_stmt(stmt: &Stmt) -> Option<&'static str> {
+    match stmt {
+        Stmt::Expr(e) => unsupported_in_expr(e),
+        Stmt::Let { value, .. } => unsupported_in_expr(value),
+        Stmt::Return { value, .. } => value.as_ref().and_then(unsupported_in_expr),
+        Stmt::If { condition, then_body, else_body, .. } => unsupported_in_expr(condition)
+            .or_else(|| unsupported_in_block(then_body))
+            .or_else(|| else_body.as_deref().and_then(unsupported_in_block)),
+        Stmt::While { condition, body, .. } => unsupported_in_expr(condition).or_else(|| unsupported_in_block(body)),
+        Stmt::For { iterable, body, .. } => unsupported_in_expr(iterable).or_else(|| unsupported_in_block(body)),
+        Stmt::Block(body) | Stmt::GoSpawn { body, .. } => unsupported_in_block(body),
+        Stmt::Match { expr, arms, .. } => unsupported_in_match(expr, arms),
+        Stmt::Out { arguments, .. } => arguments.iter().find_map(unsupported_in_expr),
+    }
+}
+
+fn unsupported_in_match(expr: &Expr, arms: &[MatchArm]) -> Option<&'static str> {
+    unsupported_in_expr(expr).or_else(|| {
+        arms.iter().find_map(|arm| {
+            unsupported_in_pattern(&arm.pattern)
+                .or_else(|| arm.guard.as_ref().and_then(unsupported_in_expr))
+                .or_else(|| unsupported_in_block(&arm.body))
+        })
+    })
+}
+
+/// Whether `pattern` has an `a | b` anywhere, so a name may go unbound.
+fn has_alternatives(pattern: &MatchPattern) -> bool {
+    match pattern {
+        MatchPattern::Or(_) => true,
+        MatchPattern::List { items, .. } => items.iter().any(has_alternatives),
+        _ => false,
+    }
+}
+
+fn unsupported_in_pattern(pattern: &MatchPattern) -> Option<&'static str> {
+    match pattern {
+        MatchPattern::Literal(e) => unsupported_in_expr(e),
+        MatchPattern::Or(alts) => alts.iter().find_map(unsupported_in_pattern),
+        MatchPattern::Range { start, end } => unsupported_in_expr(start).or_else(|| unsupported_in_expr(end)),
+        MatchPattern::List { items, .. } => items.iter().find_map(unsupported_in_pattern),
+        MatchPattern::EnumVariant { .. } | MatchPattern::Identifier(_) | MatchPattern::Wildcard => None,
+    }
+}
+
+fn unsupported_in_expr(expr: &Expr) -> Option<&'static str> {
+    match expr {
+        Expr::New { .. } => Some("objects (new)"),
+        Expr::SelfRef { .. } => Some("objects (self)"),
+        Expr::SuperCall { .. } => Some("objects (super)"),
+        Expr::Number { .. } | Expr::String { .. } | Expr::Boolean { .. } | Expr::Identifier { .. } => None,
+        Expr::List { items, .. } => items.iter().find_map(unsupported_in_expr),
+        Expr::BinaryOp { left, right, .. } => unsupported_in_expr(left).or_else(|| unsupported_in_expr(right)),
+        Expr::UnaryOp { operand, .. } => unsupported_in_expr(operand),
+        Expr::Call { function, arguments, .. } => unsupported_in_expr(function)
+            .or_else(|| arguments.iter().find_map(unsupported_in_expr)),
+        Expr::MethodCall { object, arguments, .. } => unsupported_in_expr(object)
+            .or_else(|| arguments.iter().find_map(unsupported_in_expr)),
+        Expr::Index { object, index, .. } => unsupported_in_expr(object).or_else(|| unsupported_in_expr(index)),
+        Expr::MemberAccess { object, .. } => unsupported_in_expr(object),
+        Expr::Assign { target, value, .. } => unsupported_in_expr(target).or_else(|| unsupported_in_expr(value)),
+        Expr::InterpolatedString { parts, .. } => parts.iter().filter_map(StringPart::expr).find_map(unsupported_in_expr),
+        Expr::Lambda { body, .. } => unsupported_in_block(body),
+        Expr::Match { expr, arms, .. } => unsupported_in_match(expr, arms),
+    }
+}
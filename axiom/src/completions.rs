@@ -0,0 +1,190 @@
+//! Shell completion scripts for `axiom completions <shell>`.
+//!
+//! The static part (subcommands, flags, descriptions) is rendered by
+//! `clap_complete` from the clap `Command` tree, so new subcommands are
+//! picked up without touching this file. Values that only exist at completion
+//! time — installed package names and conf keys — are fetched by a small hook
+//! added to each script, from the hidden `axiom __complete <kind>` command
+//! (see `dynamic_values`).
+
+use clap::{Command, ValueEnum};
+use std::fmt::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+    #[value(name = "powershell")]
+    PowerShell,
+}
+
+/// Positional arguments completed from live data: (command, subcommand, kind).
+pub const DYNAMIC: &[(&str, &str, &str)] = &[
+    ("pkg", "remove", "packages"),
+    ("pkg", "upgrade", "packages"),
+    ("pkg", "info", "packages"),
+    ("conf", "get", "conf-keys"),
+    ("conf", "describe", "conf-keys"),
+    ("conf", "set", "conf-assign"),
+];
+
+/// Values printed by `axiom __complete <kind>`, one per line.
+pub fn dynamic_values(kind: &str) -> Vec<String> {
+    match kind {
+        #[cfg(feature = "native")]
+        "packages" => crate::pkg::PackageManager::new()
+            .and_then(|pm| pm.list_packages())
+            .unwrap_or_default(),
+        "conf-keys" => crate::conf::ALL_PROPS.iter().map(|p| p.name.to_string()).collect(),
+        // `conf set` takes key=value; complete the key and the '='
+        "conf-assign" => crate::conf::ALL_PROPS.iter().map(|p| format!("{}=", p.name)).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// `cmd` with its hidden subcommands left out: clap_complete offers them too.
+fn visible(cmd: &Command) -> Command {
+    let mut top = Command::new(cmd.get_name().to_string())
+        .args(cmd.get_arguments().cloned())
+        .groups(cmd.get_groups().cloned())
+        .subcommands(cmd.get_subcommands().filter(|c| !c.is_hide_set()).cloned());
+    if let Some(version) = cmd.get_version() {
+        top = top.version(version.to_string());
+    }
+    if let Some(about) = cmd.get_about() {
+        top = top.about(about.clone());
+    }
+    top
+}
+
+/// clap_complete's script for `shell`, registered for `bin`.
+fn static_script(shell: clap_complete::Shell, cmd: &Command, bin: &str) -> String {
+    let mut script = Vec::new();
+    clap_complete::generate(shell, &mut visible(cmd), bin, &mut script);
+    String::from_utf8_lossy(&script).into_owned()
+}
+
+/// Render the completion script for `shell`, registered for `bin` (and `axm`).
+pub fn generate(shell: Shell, cmd: &Command, bin: &str) -> String {
+    match shell {
+        Shell::Bash => static_script(clap_complete::Shell::Bash, cmd, bin) + &bash_hook(bin),
+        Shell::Zsh => zsh(static_script(clap_complete::Shell::Zsh, cmd, bin), bin),
+        Shell::Fish => [bin, "axm"].iter()
+            .map(|name| static_script(clap_complete::Shell::Fish, cmd, name) + &fish_hook(name, bin))
+            .collect(),
+        Shell::PowerShell => powershell(static_script(clap_complete::Shell::PowerShell, cmd, bin), bin),
+    }
+}
+
+/// Wraps clap_complete's `_<bin>`: the third word of a `DYNAMIC` command is
+/// completed from `__complete`, everything else as before.
+fn bash_hook(bin: &str) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "\n_{bin}_values() {{");
+    let _ = writeln!(out, "    local cur=\"${{COMP_WORDS[COMP_CWORD]}}\"");
+    let _ = writeln!(out, "    if [[ $COMP_CWORD -eq 3 ]]; then");
+    let _ = writeln!(out, "        case \"${{COMP_WORDS[1]}} ${{COMP_WORDS[2]}}\" in");
+    for (cmd, sub, kind) in DYNAMIC {
+        let nospace = if kind.ends_with("assign") { "compopt -o nospace; " } else { "" };
+        let _ = writeln!(out, "            \"{cmd} {sub}\") {nospace}COMPREPLY=($(compgen -W \"$({bin} __complete {kind} 2>/dev/null)\" -- \"$cur\")); return ;;");
+    }
+    let _ = writeln!(out, "        esac");
+    let _ = writeln!(out, "    fi");
+    let _ = writeln!(out, "    _{bin} \"$@\"");
+    let _ = writeln!(out, "}}");
+    let _ = writeln!(out, "complete -F _{bin}_values -o bashdefault -o default {bin} axm");
+    out
+}
+
+/// clap_complete's zsh script, dispatching through a `_<bin>_values` that
+/// answers the `DYNAMIC` positions itself. The script stays usable both from
+/// `$fpath` and sourced.
+fn zsh(script: String, bin: &str) -> String {
+    let mut hook = String::new();
+    let _ = writeln!(hook, "_{bin}_values() {{");
+    let _ = writeln!(hook, "    if (( CURRENT == 4 )); then");
+    let _ = writeln!(hook, "        case \"$words[2] $words[3]\" in");
+    for (cmd, sub, kind) in DYNAMIC {
+        let suffix = if kind.ends_with("assign") { " -S ''" } else { "" };
+        let _ = writeln!(hook, "            \"{cmd} {sub}\") compadd{suffix} -- ${{(f)\"$({bin} __complete {kind} 2>/dev/null)\"}}; return ;;");
+    }
+    let _ = writeln!(hook, "        esac");
+    let _ = writeln!(hook, "    fi");
+    let _ = writeln!(hook, "    _{bin} \"$@\"");
+    let _ = writeln!(hook, "}}");
+    let _ = writeln!(hook);
+    let _ = writeln!(hook, "if [ \"$funcstack[1]\" = \"_{bin}\" ]; then");
+    let _ = writeln!(hook, "    _{bin}_values \"$@\"");
+    let _ = writeln!(hook, "else");
+    let _ = writeln!(hook, "    compdef _{bin}_values {bin} axm");
+    let _ = writeln!(hook, "fi");
+    let registration = format!("if [ \"$funcstack[1]\" = \"_{bin}\" ]; then\n    _{bin} \"$@\"\nelse\n    compdef _{bin} {bin}\nfi\n");
+    script
+        .replacen(&format!("#compdef {bin}\n"), &format!("#compdef {bin} axm\n"), 1)
+        .replacen(&registration, &hook, 1)
+}
+
+fn fish_hook(name: &str, bin: &str) -> String {
+    let mut out = String::new();
+    for (cmd, sub, kind) in DYNAMIC {
+        let _ = writeln!(out, "complete -c {name} -n \"__fish_seen_subcommand_from {cmd}; and __fish_seen_subcommand_from {sub}\" -f -a \"({bin} __complete {kind} 2>/dev/null)\"");
+    }
+    out
+}
+
+/// clap_complete's PowerShell completer, also registered for `axm`, with
+/// the `DYNAMIC` positions answered before its own cases.
+fn powershell(script: String, bin: &str) -> String {
+    let mut cases = String::from("    $completions = @(switch ($command) {\n");
+    for (cmd, sub, kind) in DYNAMIC {
+        let _ = writeln!(cases, "        '{bin};{cmd};{sub}' {{");
+        let _ = writeln!(cases, "            & {bin} __complete {kind} 2>$null | ForEach-Object {{ [CompletionResult]::new($_, $_, [CompletionResultType]::ParameterValue, $_) }}");
+        let _ = writeln!(cases, "        }}");
+    }
+    script
+        .replacen(&format!("-CommandName '{bin}'"), &format!("-CommandName '{bin}', 'axm'"), 1)
+        .replacen("    $completions = @(switch ($command) {\n", &cases, 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cli() -> Command {
+        Command::new("axiom")
+            .subcommand(Command::new("run").about("Execute a script").arg(clap::Arg::new("path")))
+            .subcommand(Command::new("pkg").about("Packages")
+                .subcommand(Command::new("add"))
+                .subcommand(Command::new("remove")))
+            .subcommand(Command::new("conf").subcommand(Command::new("set")).subcommand(Command::new("get")))
+            .subcommand(Command::new("__complete").hide(true))
+    }
+
+    #[test]
+    fn test_scripts_cover_subcommands_and_dynamic_hooks() {
+        for shell in Shell::value_variants() {
+            let script = generate(*shell, &cli(), "axiom");
+            assert!(script.contains("run") && script.contains("remove"), "{:?}", shell);
+            assert!(script.contains("__complete packages"), "{:?}", shell);
+            assert!(script.contains("__complete conf-assign"), "{:?}", shell);
+            assert!(script.contains("axm"), "{:?}", shell);
+        }
+        // The hooks replace clap_complete's own registration
+        let zsh = generate(Shell::Zsh, &cli(), "axiom");
+        assert!(zsh.starts_with("#compdef axiom axm\n") && !zsh.contains("compdef _axiom axiom\n"));
+        assert!(generate(Shell::PowerShell, &cli(), "axiom").contains("-CommandName 'axiom', 'axm'"));
+        // Hidden commands are not offered
+        let names: Vec<_> = visible(&cli()).get_subcommands().map(|c| c.get_name().to_string()).collect();
+        assert_eq!(names, ["run", "pkg", "conf"]);
+        assert!(!generate(Shell::Bash, &cli(), "axiom").contains("axiom,__complete"));
+    }
+
+    #[test]
+    fn test_conf_key_values() {
+        let keys = dynamic_values("conf-keys");
+        assert!(keys.iter().any(|k| k == "max_call_depth"));
+        assert!(dynamic_values("conf-assign").iter().any(|k| k == "log_level="));
+        assert!(dynamic_values("nope").is_empty());
+    }
+}
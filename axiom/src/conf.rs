@@ -3,16 +3,27 @@
 /// All configuration is persisted to ~/.axiom/conf.txt
 /// Format: property=value (one per line, comments with #)
 ///
+/// Two overlay files use the same format and hold only the keys they change:
+///   .axiom/conf.txt              nearest one at or above the working directory
+///   ~/.axiom/profiles/<name>.txt selected with --profile <name> or $AXIOM_PROFILE
+///
+/// Any property can also be overridden per process, without touching a file,
+/// by AXIOM_<PROPERTY> (e.g. AXIOM_MAX_CALL_DEPTH=2000) or --conf property=value.
+///
 /// CLI:
 ///   axiom conf set property=value
+///   axiom conf set --project property=value
+///   axiom conf set --profile bench property=value
 ///   axiom conf get property
+///   axiom conf edit [--project | --profile bench]   (validated before saving)
 ///   axiom conf list
 ///   axiom conf reset
 ///
 /// Properties are grouped by subsystem and documented extensively.
 
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 use std::fmt;
 
 // ---------------------------------------------------------------------------
@@ -44,6 +55,9 @@ pub enum Category {
     Allocator,
     Bytecode,
     VM,
+    Security,
+    Logging,
+    Packages,
 }
 
 impl fmt::Display for Category {
@@ -69,12 +83,24 @@ pub static ALL_PROPS: &[PropDef] = &[
         name: "opcode_trace",
         default: "off",
         description: "Trace every executed opcode to stderr. Only active when debug=on. \
-                      Prints: IP, opcode name, register values, and timing.",
+                      Prints: frame depth, function, IP, opcode name and the operand \
+                      values it reads. Scripts run on the bytecode VM while tracing.",
         performance_impact: "EXTREME (-90% throughput, I/O bound)",
         memory_impact: "LOW",
         category: Category::Debug,
         production_recommended: "off",
     },
+    PropDef {
+        name: "opcode_trace_filter",
+        default: "",
+        description: "Limit opcode_trace to frames running this function \
+                      (<main> for top-level code). Empty traces everything. \
+                      Same as axiom run --trace-vm=NAME.",
+        performance_impact: "NONE",
+        memory_impact: "NONE",
+        category: Category::Debug,
+        production_recommended: "",
+    },
     PropDef {
         name: "gc_verbose",
         default: "off",
@@ -162,6 +188,17 @@ pub static ALL_PROPS: &[PropDef] = &[
         category: Category::GC,
         production_recommended: "2048",
     },
+    PropDef {
+        name: "gc_growth_factor",
+        default: "2.0",
+        description: "After a major GC the next one triggers once the old generation \
+                      reaches its live size times this factor (never below 16 MB). \
+                      Higher = fewer major GCs and a larger heap. Range: 1.1–10.",
+        performance_impact: "MEDIUM (too low → frequent major GCs)",
+        memory_impact: "DIRECT (peak old gen ≈ live size × factor)",
+        category: Category::GC,
+        production_recommended: "2.0",
+    },
     PropDef {
         name: "gc_parallel",
         default: "off",
@@ -372,13 +409,25 @@ pub static ALL_PROPS: &[PropDef] = &[
         name: "thread_pool_size",
         default: "0",
         description: "Size of rayon thread pool for parallel operations. \
-                      0 = auto-detect (num CPUs). Used by alg.map_parallel and GC.",
+                      0 = auto-detect (num CPUs). Used by alg.map_parallel, alg.pipeline and GC.",
         performance_impact: "HIGH for parallel workloads",
         memory_impact: "MEDIUM (+stack per thread)",
         category: Category::Parallelism,
         production_recommended: "0 (auto)",
     },
 
+    PropDef {
+        name: "parallel_pipeline",
+        default: "on",
+        description: "Run the map and filter stages of an alg.pipeline on the rayon pool \
+                      when every stage function is compiled by the VM and the list is long. \
+                      Results keep their order either way.",
+        performance_impact: "HIGH for large pipelines (scales with cores)",
+        memory_impact: "LOW (stage results are collected before the terminal step)",
+        category: Category::Parallelism,
+        production_recommended: "on",
+    },
+
     // ── Experimental / JIT ────────────────────────────────────────────────────
     PropDef {
         name: "jit",
@@ -426,13 +475,25 @@ pub static ALL_PROPS: &[PropDef] = &[
     PropDef {
         name: "string_interning",
         default: "on",
-        description: "Intern string literals at compile time. Two identical string literals \
-                      share a single allocation. Identity comparison replaces equality for interned strings.",
+        description: "Intern string constants at compile time in the process-wide table that \
+                      also holds identifiers and property names. Identical strings share a single \
+                      allocation and compare by pointer before comparing contents.",
         performance_impact: "HIGH for string-heavy code (+10–30%)",
         memory_impact: "LOW (deduplication saves memory for repeated strings)",
         category: Category::Allocator,
         production_recommended: "on",
     },
+    PropDef {
+        name: "sorted_maps",
+        default: "on",
+        description: "Walk maps in key order wherever a script can see the order: printing, \
+                      str(), col.keys, col.values and ann.fields. Off exposes the underlying \
+                      hash order, which changes between runs.",
+        performance_impact: "LOW (a sort per map traversal)",
+        memory_impact: "NONE",
+        category: Category::Allocator,
+        production_recommended: "on",
+    },
 
     // ── Bytecode ──────────────────────────────────────────────────────────────
     PropDef {
@@ -525,16 +586,40 @@ pub static ALL_PROPS: &[PropDef] = &[
     },
 
     // ── VM ────────────────────────────────────────────────────────────────────
+    PropDef {
+        name: "engine",
+        default: "interp",
+        description: "Which engine runs scripts: interp (the tree-walking interpreter) or \
+                      vm (compile to bytecode and run everything on the register VM). \
+                      Programs using constructs the VM does not support yet fall back to \
+                      interp with a note on stderr. Same as axiom run --engine.",
+        performance_impact: "HIGH (vm: ~5-15x faster numeric code, no engine-boundary conversions)",
+        memory_impact: "LOW",
+        category: Category::VM,
+        production_recommended: "interp",
+    },
     PropDef {
         name: "max_call_depth",
         default: "500",
-        description: "Maximum call stack depth before stack overflow error. \
+        description: "Maximum call depth (tree-walker and VM) before an AXM_408 error \
+                      reporting the Axiom call chain. \
                       Increase for deeply recursive programs. Decrease to catch runaway recursion.",
         performance_impact: "NONE (only checked on frame push)",
         memory_impact: "DIRECT (each frame = ~4KB stack + registers)",
         category: Category::VM,
         production_recommended: "500",
     },
+    PropDef {
+        name: "strict_vars",
+        default: "off",
+        description: "Reading a variable that was never declared raises AXM_201 with the \
+                      name and a did-you-mean suggestion on both engines. Off, the VM reads \
+                      it as nil (the tree-walker always raises). Same as axiom --strict.",
+        performance_impact: "VERY LOW (one check per global read of nil)",
+        memory_impact: "VERY LOW (one flag per global)",
+        category: Category::VM,
+        production_recommended: "on",
+    },
     PropDef {
         name: "register_count",
         default: "256",
@@ -545,8 +630,266 @@ pub static ALL_PROPS: &[PropDef] = &[
         category: Category::VM,
         production_recommended: "256",
     },
+
+    // ── Security ──────────────────────────────────────────────────────────────
+    PropDef {
+        name: "sandbox_deny",
+        default: "",
+        description: "Comma-separated intrinsic groups scripts may not use: fs (ioo, pth, \
+                      csv.write, log.to_file, plt, git), net (net, rpc), process (cli.exec, cli.shell, chdir), usb, \
+                      env (env.set, env.load), ffi, or all. Denied calls raise AXM_506.",
+        performance_impact: "NONE (one bit test per intrinsic call)",
+        memory_impact: "NONE",
+        category: Category::Security,
+        production_recommended: "",
+    },
+
+    // ── Logging ───────────────────────────────────────────────────────────────
+    PropDef {
+        name: "log_level",
+        default: "info",
+        description: "Lowest level the log module emits: trace, debug, info, warn, error, \
+                      or off to silence script logging entirely. Scripts can override \
+                      it with log.set_level.",
+        performance_impact: "NONE (records below the level are dropped before formatting)",
+        memory_impact: "NONE",
+        category: Category::Logging,
+        production_recommended: "warn",
+    },
+    PropDef {
+        name: "log_format",
+        default: "text",
+        description: "Record format: text ('[INFO] [scope] message') or json (one object \
+                      per line with ts, level, scope and msg) for log shippers.",
+        performance_impact: "NONE",
+        memory_impact: "NONE",
+        category: Category::Logging,
+        production_recommended: "json",
+    },
+    PropDef {
+        name: "log_timestamps",
+        default: "off",
+        description: "Prefix console text records with an RFC 3339 UTC timestamp. \
+                      File sinks and json records always carry one.",
+        performance_impact: "NONE",
+        memory_impact: "NONE",
+        category: Category::Logging,
+        production_recommended: "on",
+    },
+    PropDef {
+        name: "log_file",
+        default: "",
+        description: "Write log records to this file instead of the console. \
+                      Empty = console (info and below on stdout, warn/error on stderr).",
+        performance_impact: "LOW (one buffered append per record)",
+        memory_impact: "NONE",
+        category: Category::Logging,
+        production_recommended: "",
+    },
+    PropDef {
+        name: "log_max_mb",
+        default: "10",
+        description: "Rotate log_file once it exceeds this many megabytes, keeping \
+                      three old files (.1 newest). 0 disables rotation.",
+        performance_impact: "NONE",
+        memory_impact: "NONE",
+        category: Category::Logging,
+        production_recommended: "10",
+    },
+    PropDef {
+        name: "stdout_buffer",
+        default: "line",
+        description: "How script output (out, print, log.info) reaches stdout: line \
+                      (flushed after every line) or block (flushed when a 64 KiB buffer \
+                      fills, on sys.flush(), before reading input and when the script \
+                      ends). Block is much faster for scripts that print in loops.",
+        performance_impact: "HIGH for output-heavy scripts (block: one write per 64 KiB)",
+        memory_impact: "VERY LOW (64 KiB buffer)",
+        category: Category::Logging,
+        production_recommended: "block",
+    },
+
+    // ── Packages ──────────────────────────────────────────────────────────────
+    PropDef {
+        name: "pkg_host",
+        default: "github.com",
+        description: "Git host `axiom pkg add user/repo` clones from. A spec can name \
+                      another host itself: `axiom pkg add git.corp.example/team/lib`.",
+        performance_impact: "NONE",
+        memory_impact: "NONE",
+        category: Category::Packages,
+        production_recommended: "github.com",
+    },
+    PropDef {
+        name: "pkg_auth",
+        default: "",
+        description: "Comma-separated host=method credentials for cloning and upgrading \
+                      packages. Methods: token (from $AXIOM_GIT_TOKEN), token:VAR (from \
+                      $VAR), ssh (clone over SSH with the ssh-agent), ssh:KEYFILE, helper \
+                      (git's credential helpers) or none. Tokens are never stored here. \
+                      Without an entry, pkg_host uses $AXIOM_GIT_TOKEN when set and \
+                      other hosts the helpers.",
+        performance_impact: "NONE",
+        memory_impact: "NONE",
+        category: Category::Packages,
+        production_recommended: "",
+    },
+    PropDef {
+        name: "pkg_verify",
+        default: "off",
+        description: "Check each @user/repo package against the checksums in \
+                      ~/.axiomlibs/Axiomite.lock before loading it: off, warn (load it \
+                      after a warning) or on (refuse a package that was modified, is \
+                      incomplete or has no checksum).",
+        performance_impact: "LOW (hashes the package's files once per load)",
+        memory_impact: "NONE",
+        category: Category::Packages,
+        production_recommended: "on",
+    },
+    PropDef {
+        name: "pkg_update_check",
+        default: "off",
+        description: "daily: at most once a day, `axiom run` starts `axiom pkg outdated` \
+                      in the background; the next run prints a note if any installed \
+                      package has a newer version upstream. off: never check.",
+        performance_impact: "NONE (the check runs in a separate process)",
+        memory_impact: "NONE",
+        category: Category::Packages,
+        production_recommended: "off",
+    },
 ];
 
+// ---------------------------------------------------------------------------
+// Value validation
+// ---------------------------------------------------------------------------
+
+/// Words `get_bool` reads for a switch property.
+const SWITCH_WORDS: &[&str] = &["on", "off", "true", "false", "yes", "no", "1", "0"];
+
+/// The values a property accepts. `axiom conf edit` checks every line against it.
+#[derive(Debug, Clone, Copy)]
+pub enum ValueKind {
+    /// on/off (also true/false, yes/no, 1/0)
+    Switch,
+    /// A whole number in `min..=max`
+    Int { min: u64, max: u64 },
+    /// A decimal number in `min..=max`
+    Float { min: f64, max: f64 },
+    /// Exactly one of these words
+    OneOf(&'static [&'static str]),
+    /// Text the subsystem parses itself; the function reports why it is rejected
+    Parsed(fn(&str) -> Result<(), String>),
+    /// Any text, including empty
+    Text,
+}
+
+impl ValueKind {
+    /// The kind of `prop`. Properties not listed take their shape from the default.
+    pub fn of(prop: &PropDef) -> ValueKind {
+        match prop.name {
+            "poly_ic_size"      => ValueKind::Int { min: 1, max: 8 },
+            "nursery_size_kb"   => ValueKind::Int { min: 256, max: 65536 },
+            "gc_growth_factor"  => ValueKind::Float { min: 1.1, max: 10.0 },
+            "opt_level"         => ValueKind::Int { min: 0, max: 3 },
+            "quicken_threshold" => ValueKind::Int { min: 4, max: 256 },
+            "hot_threshold"     => ValueKind::Int { min: 10, max: 10000 },
+            "thread_pool_size"  => ValueKind::Int { min: 0, max: 1024 },
+            "max_call_depth"    => ValueKind::Int { min: 1, max: u32::MAX as u64 },
+            "register_count"    => ValueKind::Int { min: 1, max: 65535 },
+            "log_max_mb"        => ValueKind::Int { min: 0, max: u32::MAX as u64 },
+            "gc_mode"           => ValueKind::OneOf(&["none", "simple", "generational", "incremental"]),
+            "allocator"         => ValueKind::OneOf(&["bump", "system", "pool"]),
+            "engine"            => ValueKind::OneOf(&["interp", "vm"]),
+            "log_level"         => ValueKind::OneOf(&["trace", "debug", "info", "warn", "warning", "error", "off", "none"]),
+            "log_format"        => ValueKind::OneOf(&["text", "json"]),
+            "stdout_buffer"     => ValueKind::OneOf(&["line", "block"]),
+            "pkg_verify"        => ValueKind::OneOf(&["off", "warn", "on"]),
+            "pkg_update_check"  => ValueKind::OneOf(&["off", "daily"]),
+            "sandbox_deny"      => ValueKind::Parsed(|v| crate::sandbox::Capabilities::from_deny_list(v).map(|_| ())),
+            "pkg_auth"          => ValueKind::Parsed(|v| crate::pkg::auth_table(v).map(|_| ())),
+            _ if SWITCH_WORDS.contains(&prop.default) => ValueKind::Switch,
+            _ => ValueKind::Text,
+        }
+    }
+
+    /// `Ok` if `value` is acceptable, else what was expected instead.
+    pub fn check(&self, value: &str) -> Result<(), String> {
+        match *self {
+            ValueKind::Switch if SWITCH_WORDS.contains(&value) => Ok(()),
+            ValueKind::Switch => Err(format!("'{}' is not a switch (expected on or off)", value)),
+            ValueKind::Int { min, max } => match value.parse::<u64>() {
+                Ok(n) if (min..=max).contains(&n) => Ok(()),
+                Ok(n) => Err(format!("{} is out of range (expected {}–{})", n, min, max)),
+                Err(_) => Err(format!("'{}' is not a whole number (expected {}–{})", value, min, max)),
+            },
+            ValueKind::Float { min, max } => match value.parse::<f64>() {
+                Ok(f) if f.is_finite() && (min..=max).contains(&f) => Ok(()),
+                Ok(f) => Err(format!("{} is out of range (expected {}–{})", f, min, max)),
+                Err(_) => Err(format!("'{}' is not a number (expected {}–{})", value, min, max)),
+            },
+            ValueKind::OneOf(words) if words.contains(&value) => Ok(()),
+            ValueKind::OneOf(words) => Err(format!("'{}' is not allowed (expected one of: {})", value, words.join(", "))),
+            ValueKind::Parsed(parse) => parse(value),
+            ValueKind::Text => Ok(()),
+        }
+    }
+}
+
+/// Check `value` for the property `key`, which must exist.
+pub fn validate(key: &str, value: &str) -> Result<(), String> {
+    let prop = ALL_PROPS.iter().find(|p| p.name == key)
+        .ok_or_else(|| format!("unknown property '{}'", key))?;
+    ValueKind::of(prop).check(value)
+}
+
+/// One bad line in a conf file, as `axiom conf edit` reports it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfProblem {
+    /// 1-based line number
+    pub line: usize,
+    /// The line as written
+    pub text: String,
+    pub message: String,
+    /// A corrected line to suggest, when one is obvious
+    pub fix: Option<String>,
+}
+
+/// Every problem in the text of a conf file: lines that are not `key=value`,
+/// unknown keys, invalid values and keys set twice (the later line would win).
+pub fn check_conf_text(text: &str) -> Vec<ConfProblem> {
+    let names: Vec<&str> = ALL_PROPS.iter().map(|p| p.name).collect();
+    let mut seen: HashMap<&str, usize> = HashMap::new();
+    let mut problems = Vec::new();
+    for (i, raw) in text.lines().enumerate() {
+        let line = raw.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut problem = |message: String, fix: Option<String>| problems.push(ConfProblem {
+            line: i + 1, text: raw.to_string(), message, fix,
+        });
+        let Some((key, value)) = line.split_once('=') else {
+            problem("not a property=value line".into(), None);
+            continue;
+        };
+        let (key, value) = (key.trim(), value.trim());
+        let Some(prop) = ALL_PROPS.iter().find(|p| p.name == key) else {
+            match crate::diagnostics::closest_match(key, &names, 3) {
+                Some(near) => problem(format!("unknown property '{}' (did you mean '{}'?)", key, near), Some(format!("{}={}", near, value))),
+                None => problem(format!("unknown property '{}'", key), None),
+            }
+            continue;
+        };
+        if let Err(e) = ValueKind::of(prop).check(value) {
+            problem(format!("{}: {}", key, e), Some(format!("{}={}", key, prop.default)));
+        }
+        if let Some(first) = seen.insert(prop.name, i + 1) {
+            problem(format!("{} is already set on line {}; only this line would take effect", key, first), None);
+        }
+    }
+    problems
+}
+
 // ---------------------------------------------------------------------------
 // AxConf — live configuration state
 // ---------------------------------------------------------------------------
@@ -554,28 +897,204 @@ pub static ALL_PROPS: &[PropDef] = &[
 #[derive(Debug, Clone)]
 pub struct AxConf {
     values: HashMap<String, String>,
+    /// Which layer each effective value came from.
+    origins: HashMap<String, Origin>,
+    /// Defaults plus ~/.axiom/conf.txt only — what `save` writes back, so
+    /// project and profile values never leak into the home config.
+    user_values: HashMap<String, String>,
+}
+
+/// Where an effective configuration value came from. Later layers win:
+/// default < user (~/.axiom/conf.txt) < project (.axiom/conf.txt) < profile
+/// < environment (AXIOM_<KEY>) < command line (--conf key=value).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Origin {
+    Default,
+    User,
+    Project(PathBuf),
+    Profile(String),
+    /// The environment variable that set it.
+    Env(String),
+    Cli,
+}
+
+impl Origin {
+    fn rank(&self) -> u8 {
+        match self {
+            Origin::Default    => 0,
+            Origin::User       => 1,
+            Origin::Project(_) => 2,
+            Origin::Profile(_) => 3,
+            Origin::Env(_)     => 4,
+            Origin::Cli        => 5,
+        }
+    }
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Origin::Default       => write!(f, "default"),
+            Origin::User          => write!(f, "user"),
+            Origin::Project(path) => write!(f, "project {}", path.display()),
+            Origin::Profile(name) => write!(f, "profile {}", name),
+            Origin::Env(var)      => write!(f, "env {}", var),
+            Origin::Cli           => write!(f, "--conf"),
+        }
+    }
+}
+
+/// Which file `axiom conf set` writes to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfTarget {
+    /// ~/.axiom/conf.txt
+    User,
+    /// .axiom/conf.txt in the current directory
+    Project,
+    /// ~/.axiom/profiles/<name>.txt
+    Profile(String),
+}
+
+/// Profile selected for this process with `--profile`; falls back to $AXIOM_PROFILE.
+static ACTIVE_PROFILE: Mutex<Option<String>> = Mutex::new(None);
+
+/// `--conf key=value` overrides for this process; they beat every file and variable.
+static CLI_OVERRIDES: Mutex<Vec<(String, String)>> = Mutex::new(Vec::new());
+
+/// Install command-line overrides. Each spec must be `key=value` with a known key.
+pub fn set_cli_overrides(specs: &[String]) -> Result<(), String> {
+    let mut pairs = Vec::new();
+    for spec in specs {
+        let (k, v) = spec.split_once('=').ok_or_else(||
+            format!("Invalid --conf '{}'. Use: --conf property=value", spec)
+        )?;
+        let k = k.trim();
+        if !ALL_PROPS.iter().any(|p| p.name == k) {
+            return Err(format!("Unknown configuration property in --conf: '{}'", k));
+        }
+        pairs.push((k.to_string(), v.trim().to_string()));
+    }
+    *CLI_OVERRIDES.lock().unwrap_or_else(|e| e.into_inner()) = pairs;
+    Ok(())
+}
+
+/// The variable that overrides `key`: `log_level` → `AXIOM_LOG_LEVEL`.
+pub fn env_var_name(key: &str) -> String {
+    format!("AXIOM_{}", key.to_ascii_uppercase())
+}
+
+/// Select the profile every later `AxConf::load` overlays.
+pub fn set_active_profile(name: Option<String>) {
+    *ACTIVE_PROFILE.lock().unwrap_or_else(|e| e.into_inner()) = name;
+}
+
+pub fn active_profile() -> Option<String> {
+    ACTIVE_PROFILE.lock().unwrap_or_else(|e| e.into_inner()).clone()
+        .or_else(|| std::env::var("AXIOM_PROFILE").ok().filter(|s| !s.is_empty()))
+}
+
+/// `key=value` pairs from a conf file, skipping comments and blank lines.
+fn read_conf_file(path: &Path) -> Option<Vec<(String, String)>> {
+    let contents = std::fs::read_to_string(path).ok()?;
+    Some(contents.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .map(|(k, v)| (k.trim().to_string(), v.trim().to_string()))
+        .collect())
+}
+
+/// First line of a new overlay file for `target`.
+fn overlay_header(target: &ConfTarget) -> String {
+    match target {
+        ConfTarget::User => "# Axiom Configuration — ~/.axiom/conf.txt".into(),
+        ConfTarget::Project => "# Axiom project configuration — overlays ~/.axiom/conf.txt".into(),
+        ConfTarget::Profile(name) => format!("# Axiom profile '{}' — select with --profile {}", name, name),
+    }
+}
+
+fn check_profile_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err(format!("Invalid profile name '{}': use letters, digits, '-' and '_'", name));
+    }
+    Ok(())
+}
+
+/// Set `key=value` in an overlay file, keeping its other lines and comments.
+fn write_overlay(path: &Path, header: &str, key: &str, value: &str) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Cannot create config dir: {}", e))?;
+    }
+    let existing = std::fs::read_to_string(path).unwrap_or_else(|_| format!("{}\n\n", header));
+    let mut replaced = false;
+    let mut out = String::new();
+    for line in existing.lines() {
+        let is_key = !line.trim_start().starts_with('#')
+            && line.split_once('=').map(|(k, _)| k.trim() == key).unwrap_or(false);
+        if is_key {
+            if !replaced {
+                out.push_str(&format!("{}={}\n", key, value));
+                replaced = true;
+            }
+        } else {
+            out.push_str(line);
+            out.push('\n');
+        }
+    }
+    if !replaced {
+        out.push_str(&format!("{}={}\n", key, value));
+    }
+    std::fs::write(path, out).map_err(|e| format!("Cannot write config: {}", e))
 }
 
 impl AxConf {
-    /// Load configuration from the default config file path.
-    /// Falls back to defaults if file not found.
+    /// Load the effective configuration: defaults, then ~/.axiom/conf.txt,
+    /// then the nearest project .axiom/conf.txt, then the active profile,
+    /// then AXIOM_<KEY> variables, then --conf flags. Missing files are skipped.
     pub fn load() -> Self {
-        let mut conf = AxConf { values: HashMap::new() };
-        // Set all defaults first
+        let mut layers = Vec::new();
+        let user_path = Self::config_path();
+        if let Some(pairs) = user_path.as_deref().and_then(read_conf_file) {
+            layers.push((Origin::User, pairs));
+        }
+        if let Some(path) = Self::project_config_path() {
+            if let Some(pairs) = read_conf_file(&path) {
+                layers.push((Origin::Project(path), pairs));
+            }
+        }
+        if let Some(name) = active_profile() {
+            if let Some(pairs) = Self::profile_path(&name).as_deref().and_then(read_conf_file) {
+                layers.push((Origin::Profile(name), pairs));
+            }
+        }
         for prop in ALL_PROPS {
-            conf.values.insert(prop.name.to_string(), prop.default.to_string());
+            let var = env_var_name(prop.name);
+            if let Ok(value) = std::env::var(&var) {
+                layers.push((Origin::Env(var), vec![(prop.name.to_string(), value.trim().to_string())]));
+            }
+        }
+        let cli = CLI_OVERRIDES.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        if !cli.is_empty() {
+            layers.push((Origin::Cli, cli));
         }
+        Self::from_layers(layers)
+    }
 
-        // Override with file values
-        if let Some(path) = Self::config_path() {
-            if let Ok(contents) = std::fs::read_to_string(&path) {
-                for line in contents.lines() {
-                    let line = line.trim();
-                    if line.starts_with('#') || line.is_empty() { continue; }
-                    if let Some((k, v)) = line.split_once('=') {
-                        conf.values.insert(k.trim().to_string(), v.trim().to_string());
-                    }
+    /// Merge `layers` over the defaults, lowest precedence first.
+    pub fn from_layers(layers: Vec<(Origin, Vec<(String, String)>)>) -> Self {
+        let mut conf = AxConf { values: HashMap::new(), origins: HashMap::new(), user_values: HashMap::new() };
+        for prop in ALL_PROPS {
+            conf.values.insert(prop.name.to_string(), prop.default.to_string());
+            conf.origins.insert(prop.name.to_string(), Origin::Default);
+        }
+        conf.user_values = conf.values.clone();
+        for (origin, pairs) in layers {
+            for (k, v) in pairs {
+                if origin == Origin::User {
+                    conf.user_values.insert(k.clone(), v.clone());
                 }
+                conf.values.insert(k.clone(), v);
+                conf.origins.insert(k, origin.clone());
             }
         }
         conf
@@ -585,6 +1104,34 @@ impl AxConf {
         dirs::home_dir().map(|h| h.join(".axiom").join("conf.txt"))
     }
 
+    pub fn profile_path(name: &str) -> Option<PathBuf> {
+        dirs::home_dir().map(|h| h.join(".axiom").join("profiles").join(format!("{}.txt", name)))
+    }
+
+    /// The nearest `.axiom/conf.txt` at or above the current directory,
+    /// not counting the home config itself.
+    pub fn project_config_path() -> Option<PathBuf> {
+        let home = Self::config_path();
+        let cwd = std::env::current_dir().ok()?;
+        cwd.ancestors()
+            .map(|dir| dir.join(".axiom").join("conf.txt"))
+            .find(|p| p.is_file() && Some(p) != home.as_ref())
+    }
+
+    /// Names of the profiles saved under ~/.axiom/profiles.
+    pub fn profiles() -> Vec<String> {
+        let Some(dir) = dirs::home_dir().map(|h| h.join(".axiom").join("profiles")) else { return Vec::new() };
+        let mut names: Vec<String> = std::fs::read_dir(dir).into_iter().flatten().flatten()
+            .filter_map(|e| e.file_name().to_str()?.strip_suffix(".txt").map(str::to_string))
+            .collect();
+        names.sort();
+        names
+    }
+
+    pub fn origin(&self, key: &str) -> Option<&Origin> {
+        self.origins.get(key)
+    }
+
     pub fn get(&self, key: &str) -> Option<&str> {
         self.values.get(key).map(|s| s.as_str())
     }
@@ -598,12 +1145,41 @@ impl AxConf {
     }
 
     pub fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        self.set_in(&ConfTarget::User, key, value)
+    }
+
+    /// Validate `key` and persist `key=value` to the file `target` names.
+    /// The in-memory value changes only if no higher layer overrides it.
+    pub fn set_in(&mut self, target: &ConfTarget, key: &str, value: &str) -> Result<(), String> {
         // Validate key exists
         if !ALL_PROPS.iter().any(|p| p.name == key) {
             return Err(format!("Unknown configuration property: '{}'\nRun `axiom conf list` to see all properties.", key));
         }
-        self.values.insert(key.to_string(), value.to_string());
-        self.save()
+        let origin = match target {
+            ConfTarget::User => {
+                self.user_values.insert(key.to_string(), value.to_string());
+                self.save()?;
+                Origin::User
+            }
+            ConfTarget::Project => {
+                let dir = std::env::current_dir().map_err(|e| format!("Cannot determine current directory: {}", e))?;
+                let path = dir.join(".axiom").join("conf.txt");
+                write_overlay(&path, &overlay_header(target), key, value)?;
+                Origin::Project(path)
+            }
+            ConfTarget::Profile(name) => {
+                check_profile_name(name)?;
+                let path = Self::profile_path(name).ok_or("Cannot determine config path")?;
+                write_overlay(&path, &overlay_header(target), key, value)?;
+                Origin::Profile(name.clone())
+            }
+        };
+        let shadowed = self.origins.get(key).map(|o| o.rank() > origin.rank()).unwrap_or(false);
+        if !shadowed {
+            self.values.insert(key.to_string(), value.to_string());
+            self.origins.insert(key.to_string(), origin);
+        }
+        Ok(())
     }
 
     pub fn save(&self) -> Result<(), String> {
@@ -612,14 +1188,19 @@ impl AxConf {
         if let Some(dir) = path.parent() {
             std::fs::create_dir_all(dir).map_err(|e| format!("Cannot create config dir: {}", e))?;
         }
+        std::fs::write(&path, self.render_user_file()).map_err(|e| format!("Cannot write config: {}", e))?;
+        Ok(())
+    }
 
+    /// The text `save` writes to ~/.axiom/conf.txt: every property, grouped by category.
+    pub(crate) fn render_user_file(&self) -> String {
         let mut out = String::new();
         out.push_str("# Axiom Configuration — ~/.axiom/conf.txt\n");
-        out.push_str("# Edit manually or use: axiom conf set property=value\n");
+        out.push_str("# Edit with: axiom conf edit   or: axiom conf set property=value\n");
         out.push_str("# Reset to defaults:   axiom conf reset\n\n");
 
         let mut by_category: Vec<(&PropDef, &str)> = ALL_PROPS.iter()
-            .map(|p| (p, self.values.get(p.name).map(|s| s.as_str()).unwrap_or(p.default)))
+            .map(|p| (p, self.user_values.get(p.name).map(|s| s.as_str()).unwrap_or(p.default)))
             .collect();
         by_category.sort_by_key(|(p, _)| format!("{:?}", p.category));
 
@@ -632,9 +1213,7 @@ impl AxConf {
             }
             out.push_str(&format!("{}={}\n", prop.name, value));
         }
-
-        std::fs::write(&path, &out).map_err(|e| format!("Cannot write config: {}", e))?;
-        Ok(())
+        out
     }
 
     /// Reset all properties to defaults
@@ -666,11 +1245,19 @@ impl AxConf {
             }
             let current = self.get(prop.name).unwrap_or(prop.default);
             let marker = if current == prop.default { "  " } else { "* " };
-            println!("{}  {:<28} = {:<12}  (default: {})", marker, prop.name, current, prop.default);
+            let origin = self.origin(prop.name).cloned().unwrap_or(Origin::Default);
+            let origin = if origin == Origin::Default { String::new() } else { format!("  [{}]", origin) };
+            println!("{}  {:<28} = {:<12}  (default: {}){}", marker, prop.name, current, prop.default, origin);
         }
         println!();
-        println!("  * = overridden from default");
+        println!("  * = overridden from default; [origin] = file the effective value came from");
         println!("  Config file: {}", Self::config_path().map(|p| p.display().to_string()).unwrap_or_else(|| "N/A".into()));
+        if let Some(path) = Self::project_config_path() {
+            println!("  Project:     {}", path.display());
+        }
+        if let Some(name) = active_profile() {
+            println!("  Profile:     {}", name);
+        }
     }
 
     /// Show detailed documentation for one property
@@ -683,6 +1270,7 @@ impl AxConf {
                 println!("┌─ {} ─────────────────────────────────────────────────────────", p.name);
                 println!("│  Category:             {}", p.category);
                 println!("│  Current value:        {}", current);
+                println!("│  Set by:               {}", self.origin(p.name).cloned().unwrap_or(Origin::Default));
                 println!("│  Default value:        {}", p.default);
                 println!("│  Production default:   {}", p.production_recommended);
                 println!("│");
@@ -702,7 +1290,13 @@ impl AxConf {
 
     pub fn debug(&self) -> bool { self.get_bool("debug") }
     pub fn opcode_trace(&self) -> bool { self.debug() && self.get_bool("opcode_trace") }
+    pub fn opcode_trace_filter(&self) -> &str { self.get("opcode_trace_filter").unwrap_or("") }
     pub fn gc_verbose(&self) -> bool { self.get_bool("gc_verbose") }
+    pub fn nursery_size_kb(&self) -> u32 { self.get_u32("nursery_size_kb", 2048) }
+    pub fn gc_growth_factor(&self) -> f64 {
+        self.get("gc_growth_factor").and_then(|v| v.parse::<f64>().ok())
+            .filter(|f| f.is_finite()).map(|f| f.clamp(1.1, 10.0)).unwrap_or(2.0)
+    }
     pub fn bounds_check(&self) -> bool { self.get_bool("bounds_check") }
 
     pub fn inline_cache(&self) -> bool { self.get_bool("inline_cache") }
@@ -723,7 +1317,28 @@ impl AxConf {
     pub fn flame_graph(&self) -> bool { self.get_bool("flame_graph") }
     pub fn alloc_tracking(&self) -> bool { self.get_bool("alloc_tracking") }
 
+    pub fn string_interning(&self) -> bool { self.get_bool("string_interning") }
+    pub fn sorted_maps(&self) -> bool { self.get_bool("sorted_maps") }
+
+    pub fn engine(&self) -> &str { self.get("engine").unwrap_or("interp") }
     pub fn max_call_depth(&self) -> u32 { self.get_u32("max_call_depth", 500) }
+    pub fn strict_vars(&self) -> bool { self.get_bool("strict_vars") }
+    pub fn sandbox_deny(&self) -> &str { self.get("sandbox_deny").unwrap_or("") }
+
+    pub fn log_level(&self) -> &str { self.get("log_level").unwrap_or("info") }
+    pub fn log_format(&self) -> &str { self.get("log_format").unwrap_or("text") }
+    pub fn log_timestamps(&self) -> bool { self.get_bool("log_timestamps") }
+    pub fn log_file(&self) -> &str { self.get("log_file").unwrap_or("") }
+    pub fn log_max_mb(&self) -> u32 { self.get_u32("log_max_mb", 10) }
+    pub fn stdout_buffer(&self) -> &str { self.get("stdout_buffer").unwrap_or("line") }
+
+    pub fn pkg_host(&self) -> &str { self.get("pkg_host").unwrap_or("github.com") }
+    pub fn pkg_auth(&self) -> &str { self.get("pkg_auth").unwrap_or("") }
+    pub fn pkg_verify(&self) -> &str { self.get("pkg_verify").unwrap_or("off") }
+    pub fn pkg_update_check(&self) -> bool { self.get("pkg_update_check") == Some("daily") }
+
+    pub fn parallel_pipeline(&self) -> bool { self.get_bool("parallel_pipeline") }
+    pub fn thread_pool_size(&self) -> u32 { self.get_u32("thread_pool_size", 0) }
 
     // ── Feature-toggle accessors ─────────────────────────────────────────────
 
@@ -788,19 +1403,125 @@ fn textwrap(s: &str, width: usize) -> Vec<String> {
 // CLI handlers
 // ---------------------------------------------------------------------------
 
-pub fn cmd_conf_set(spec: &str) -> Result<(), String> {
+pub fn cmd_conf_set(spec: &str, target: ConfTarget) -> Result<(), String> {
     let (k, v) = spec.split_once('=').ok_or_else(||
         format!("Invalid format. Use: axiom conf set property=value\n  Got: '{}'", spec)
     )?;
     let k = k.trim();
     let v = v.trim();
     let mut conf = AxConf::load();
-    conf.set(k, v)?;
+    conf.set_in(&target, k, v)?;
     println!("✓ Set {}={}", k, v);
-    println!("  Config: {}", AxConf::config_path().map(|p| p.display().to_string()).unwrap_or_default());
+    println!("  Config: {}", target_path(&target).map(|p| p.display().to_string()).unwrap_or_default());
+    Ok(())
+}
+
+/// The file `target` names; the project file is the one in the current directory.
+fn target_path(target: &ConfTarget) -> Option<PathBuf> {
+    match target {
+        ConfTarget::User => AxConf::config_path(),
+        ConfTarget::Project => std::env::current_dir().ok().map(|d| d.join(".axiom").join("conf.txt")),
+        ConfTarget::Profile(name) => AxConf::profile_path(name),
+    }
+}
+
+/// `problems` as a diff: each bad line (-) and, where one is obvious, its fix (+).
+pub fn format_problems(problems: &[ConfProblem]) -> String {
+    let mut out = String::new();
+    for p in problems {
+        out.push_str(&format!("  line {}: {}\n", p.line, p.message));
+        out.push_str(&format!("    - {}\n", p.text.trim()));
+        if let Some(fix) = &p.fix {
+            out.push_str(&format!("    + {}\n", fix));
+        }
+    }
+    out
+}
+
+/// $VISUAL, then $EDITOR, then the platform's stock editor.
+fn editor_command() -> String {
+    ["VISUAL", "EDITOR"].iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|cmd| !cmd.trim().is_empty())
+        .unwrap_or_else(|| if cfg!(windows) { "notepad".into() } else { "vi".into() })
+}
+
+fn run_editor(path: &Path) -> Result<(), String> {
+    let command = editor_command();
+    let mut words = command.split_whitespace();
+    let program = words.next().ok_or("No editor configured: set $EDITOR")?;
+    let status = std::process::Command::new(program).args(words).arg(path).status()
+        .map_err(|e| format!("Cannot start editor '{}': {}", command, e))?;
+    if !status.success() {
+        return Err(format!("Editor '{}' exited with {}", command, status));
+    }
+    Ok(())
+}
+
+/// Ask a yes/no question on the terminal; anything but "n" means yes, and
+/// no terminal (or end of input) means no.
+fn confirm(question: &str) -> bool {
+    use std::io::{IsTerminal, Write};
+    if !std::io::stdin().is_terminal() {
+        return false;
+    }
+    eprint!("{} [Y/n] ", question);
+    let _ = std::io::stderr().flush();
+    let mut answer = String::new();
+    match std::io::stdin().read_line(&mut answer) {
+        Ok(0) | Err(_) => false,
+        Ok(_) => !answer.trim().eq_ignore_ascii_case("n"),
+    }
+}
+
+/// Open the conf file `target` names in the user's editor and save the result
+/// only if every line names a known property with a valid value. A bad edit is
+/// reported as a diff and can be reopened; declining leaves the file untouched.
+pub fn cmd_conf_edit(target: ConfTarget) -> Result<(), String> {
+    if let ConfTarget::Profile(name) = &target {
+        check_profile_name(name)?;
+    }
+    let path = target_path(&target).ok_or("Cannot determine config path")?;
+    let original = match std::fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(_) if target == ConfTarget::User => AxConf::load().render_user_file(),
+        Err(_) => format!("{}\n\n", overlay_header(&target)),
+    };
+    // Created exclusively under an unpredictable name, and removed on drop
+    let scratch = tempfile::Builder::new().prefix("axiom-conf-").suffix(".txt").tempfile()
+        .map_err(|e| format!("Cannot create a scratch file: {}", e))?;
+    let edited = edit_until_valid(&path, scratch.path(), &original)?;
+    if edited == original && path.exists() {
+        println!("No changes to {}", path.display());
+        return Ok(());
+    }
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Cannot create config dir: {}", e))?;
+    }
+    std::fs::write(&path, &edited).map_err(|e| format!("Cannot write config: {}", e))?;
+    println!("✓ Saved {}", path.display());
     Ok(())
 }
 
+fn edit_until_valid(path: &Path, scratch: &Path, original: &str) -> Result<String, String> {
+    let mut text = original.to_string();
+    loop {
+        std::fs::write(scratch, &text).map_err(|e| format!("Cannot write {}: {}", scratch.display(), e))?;
+        run_editor(scratch)?;
+        text = std::fs::read_to_string(scratch).map_err(|e| format!("Cannot read {}: {}", scratch.display(), e))?;
+        let problems = check_conf_text(&text);
+        if problems.is_empty() {
+            return Ok(text);
+        }
+        let noun = if problems.len() == 1 { "problem" } else { "problems" };
+        eprintln!("✗ {} {} in the edited {}:", problems.len(), noun, path.display());
+        eprint!("{}", format_problems(&problems));
+        if !confirm("Edit again?") {
+            return Err(format!("{} was not changed", path.display()));
+        }
+    }
+}
+
 pub fn cmd_conf_get(key: &str) -> Result<(), String> {
     let conf = AxConf::load();
     let val = conf.get(key).ok_or_else(|| format!("Unknown property: '{}'", key))?;
@@ -810,6 +1531,9 @@ pub fn cmd_conf_get(key: &str) -> Result<(), String> {
         println!("  default: {}", p.default);
         println!("  category: {}", p.category);
     }
+    if let Some(origin) = conf.origin(key) {
+        println!("  origin: {}", origin);
+    }
     Ok(())
 }
 
@@ -822,7 +1546,104 @@ pub fn cmd_conf_reset() -> Result<(), String> {
     AxConf::reset()
 }
 
+pub fn cmd_conf_profiles() {
+    let profiles = AxConf::profiles();
+    if profiles.is_empty() {
+        println!("No profiles. Create one with: axiom conf set --profile <name> property=value");
+        return;
+    }
+    let active = active_profile();
+    for name in profiles {
+        let marker = if active.as_deref() == Some(name.as_str()) { "* " } else { "  " };
+        println!("{}{}", marker, name);
+    }
+}
+
 pub fn cmd_conf_describe(key: &str) {
     let conf = AxConf::load();
     conf.describe(key);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pairs(kv: &[(&str, &str)]) -> Vec<(String, String)> {
+        kv.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn test_layers_merge_with_origins() {
+        let project = PathBuf::from("/work/app/.axiom/conf.txt");
+        let conf = AxConf::from_layers(vec![
+            (Origin::User, pairs(&[("log_level", "warn"), ("max_call_depth", "800")])),
+            (Origin::Project(project.clone()), pairs(&[("log_level", "debug")])),
+            (Origin::Profile("bench".into()), pairs(&[("max_call_depth", "2000")])),
+        ]);
+        assert_eq!(conf.log_level(), "debug");
+        assert_eq!(conf.origin("log_level"), Some(&Origin::Project(project)));
+        assert_eq!(conf.max_call_depth(), 2000);
+        assert_eq!(conf.origin("max_call_depth"), Some(&Origin::Profile("bench".into())));
+        assert_eq!(conf.origin("log_format"), Some(&Origin::Default));
+        // Only the user layer is what `save` writes home
+        assert_eq!(conf.user_values.get("log_level").map(String::as_str), Some("warn"));
+    }
+
+    #[test]
+    fn test_env_and_cli_layers_win() {
+        let conf = AxConf::from_layers(vec![
+            (Origin::Profile("ci".into()), pairs(&[("log_format", "text")])),
+            (Origin::Env(env_var_name("log_format")), pairs(&[("log_format", "json")])),
+            (Origin::Env(env_var_name("gc_enabled")), pairs(&[("gc_enabled", "0")])),
+            (Origin::Cli, pairs(&[("gc_enabled", "on")])),
+        ]);
+        assert_eq!(env_var_name("log_format"), "AXIOM_LOG_FORMAT");
+        assert_eq!(conf.log_format(), "json");
+        assert_eq!(conf.origin("log_format").unwrap().to_string(), "env AXIOM_LOG_FORMAT");
+        assert!(conf.gc_enabled());
+        assert_eq!(conf.origin("gc_enabled"), Some(&Origin::Cli));
+        assert!(set_cli_overrides(&["no_such_key=1".to_string()]).is_err());
+        assert!(set_cli_overrides(&["gc_enabled".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_write_overlay_replaces_key_in_place() {
+        let dir = std::env::temp_dir().join(format!("axiom-conf-{}", std::process::id()));
+        let path = dir.join(".axiom").join("conf.txt");
+        write_overlay(&path, "# project", "log_level", "debug").unwrap();
+        write_overlay(&path, "# project", "log_format", "json").unwrap();
+        write_overlay(&path, "# project", "log_level", "trace").unwrap();
+        let read = read_conf_file(&path).unwrap();
+        assert_eq!(read, pairs(&[("log_level", "trace"), ("log_format", "json")]));
+        assert!(std::fs::read_to_string(&path).unwrap().starts_with("# project"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_every_default_is_valid() {
+        for prop in ALL_PROPS {
+            assert!(validate(prop.name, prop.default).is_ok(), "default of {} is rejected", prop.name);
+        }
+        assert!(validate("nursery_size_kb", "128").unwrap_err().contains("out of range"));
+        assert!(validate("gc_growth_factor", "NaN").is_err());
+        assert!(validate("engine", "jit").unwrap_err().contains("interp, vm"));
+        assert!(validate("debug", "maybe").is_err());
+        assert!(validate("sandbox_deny", "fs,nett").is_err());
+        assert!(validate("pkg_auth", "github.com=token:GH_TOKEN").is_ok());
+        assert!(validate("log_file", "").is_ok());
+    }
+
+    #[test]
+    fn test_check_conf_text_reports_a_diff() {
+        let text = "# comment\nlog_levl=debug\nmax_call_depth=lots\nengine=vm\nengine=interp\ngarbage\n";
+        let problems = check_conf_text(text);
+        let lines: Vec<usize> = problems.iter().map(|p| p.line).collect();
+        assert_eq!(lines, vec![2, 3, 5, 6]);
+        assert_eq!(problems[0].fix.as_deref(), Some("log_level=debug"));
+        assert_eq!(problems[1].fix.as_deref(), Some("max_call_depth=500"));
+        assert!(problems[2].message.contains("already set on line 4"));
+        let diff = format_problems(&problems);
+        assert!(diff.contains("    - log_levl=debug\n    + log_level=debug\n"));
+        assert!(check_conf_text(&AxConf::from_layers(Vec::new()).render_user_file()).is_empty());
+    }
+}
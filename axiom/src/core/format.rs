@@ -0,0 +1,340 @@
+//! Format specs, as `str.fmt("{:>8.2}", x)` and interpolation
+//! (`"@(x:>8.2)"`) apply them:
+//!
+//!   [[fill]align][+][0][width][.precision][type]
+//!
+//! align is `<`, `>` or `^`; numbers default to the right and everything
+//! else to the left. `0` pads a number with zeros after its sign. precision
+//! is decimal places for a number and a maximum length for anything else.
+//! type is `x` `X` `o` `b` (a whole number in hex, octal or binary), `e`
+//! (exponent notation) or `%` (times 100, followed by `%`).
+
+use dashmap::DashMap;
+
+use super::value::AxValue;
+use crate::errors::{FailureKind, RuntimeError, Span};
+
+/// Widths and precisions past this are rejected rather than allocated.
+const MAX_WIDTH: usize = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    Right,
+    Center,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Kind {
+    #[default]
+    Plain,
+    Hex,
+    UpperHex,
+    Octal,
+    Binary,
+    Exp,
+    Percent,
+}
+
+impl Kind {
+    fn from_char(c: char) -> Option<Kind> {
+        Some(match c {
+            'x' => Kind::Hex,
+            'X' => Kind::UpperHex,
+            'o' => Kind::Octal,
+            'b' => Kind::Binary,
+            'e' => Kind::Exp,
+            '%' => Kind::Percent,
+            _ => return None,
+        })
+    }
+
+    fn char(self) -> char {
+        match self {
+            Kind::Plain    => ' ',
+            Kind::Hex      => 'x',
+            Kind::UpperHex => 'X',
+            Kind::Octal    => 'o',
+            Kind::Binary   => 'b',
+            Kind::Exp      => 'e',
+            Kind::Percent  => '%',
+        }
+    }
+}
+
+/// One parsed spec; the default formats a value as `out` shows it.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Spec {
+    pub fill: Option<char>,
+    pub align: Option<Align>,
+    pub sign: bool,
+    pub zero: bool,
+    pub width: usize,
+    pub precision: Option<usize>,
+    pub kind: Kind,
+}
+
+impl Spec {
+    pub fn parse(spec: &str) -> Result<Spec, String> {
+        let chars: Vec<char> = spec.chars().collect();
+        let align_of = |c: char| match c {
+            '<' => Some(Align::Left),
+            '>' => Some(Align::Right),
+            '^' => Some(Align::Center),
+            _ => None,
+        };
+        let mut parsed = Spec::default();
+        let mut i = 0;
+        if let Some(align) = chars.get(1).copied().and_then(align_of) {
+            parsed.fill = Some(chars[0]);
+            parsed.align = Some(align);
+            i = 2;
+        } else if let Some(align) = chars.first().copied().and_then(align_of) {
+            parsed.align = Some(align);
+            i = 1;
+        }
+        if chars.get(i) == Some(&'+') {
+            parsed.sign = true;
+            i += 1;
+        }
+        if chars.get(i) == Some(&'0') {
+            parsed.zero = true;
+            i += 1;
+        }
+        parsed.width = digits(&chars, &mut i)?.unwrap_or(0);
+        if chars.get(i) == Some(&'.') {
+            i += 1;
+            parsed.precision = Some(digits(&chars, &mut i)?.ok_or_else(|| format!("expected digits after '.' in '{}'", spec))?);
+        }
+        if let Some(kind) = chars.get(i).copied().and_then(Kind::from_char) {
+            parsed.kind = kind;
+            i += 1;
+        }
+        match chars.get(i) {
+            None => Ok(parsed),
+            Some(c) => Err(format!("unexpected '{}' in format spec '{}'", c, spec)),
+        }
+    }
+
+    /// `value` formatted by this spec. The number types need a number,
+    /// and `x` `X` `o` `b` a whole one.
+    pub fn apply(&self, value: &AxValue) -> Result<String, RuntimeError> {
+        let (text, numeric) = match value {
+            AxValue::Num(n) => (self.number(*n)?, true),
+            other if self.kind != Kind::Plain => return Err(mismatch(self.kind, "Num", other)),
+            other => {
+                let text = other.display();
+                match self.precision {
+                    Some(p) => (text.chars().take(p).collect(), false),
+                    None => (text, false),
+                }
+            }
+        };
+        Ok(self.pad(text, numeric))
+    }
+
+    fn number(&self, n: f64) -> Result<String, RuntimeError> {
+        let whole = || match n.fract() == 0.0 && n.abs() < 9.2e18 {
+            true => Ok(n as i64),
+            false => Err(mismatch(self.kind, "whole Num", &AxValue::Num(n))),
+        };
+        let radix = |m: i64, digits: String| if m < 0 { format!("-{}", digits) } else { digits };
+        let mut text = match self.kind {
+            Kind::Plain => match self.precision {
+                Some(p) => format!("{:.*}", p, n),
+                None => AxValue::Num(n).display(),
+            },
+            Kind::Hex      => { let m = whole()?; radix(m, format!("{:x}", m.unsigned_abs())) }
+            Kind::UpperHex => { let m = whole()?; radix(m, format!("{:X}", m.unsigned_abs())) }
+            Kind::Octal    => { let m = whole()?; radix(m, format!("{:o}", m.unsigned_abs())) }
+            Kind::Binary   => { let m = whole()?; radix(m, format!("{:b}", m.unsigned_abs())) }
+            Kind::Exp => match self.precision {
+                Some(p) => format!("{:.*e}", p, n),
+                None => format!("{:e}", n),
+            },
+            Kind::Percent => match self.precision {
+                Some(p) => format!("{:.*}%", p, n * 100.0),
+                None => format!("{}%", AxValue::Num(n * 100.0).display()),
+            },
+        };
+        if self.sign && !text.starts_with('-') {
+            text.insert(0, '+');
+        }
+        Ok(text)
+    }
+
+    fn pad(&self, text: String, numeric: bool) -> String {
+        let len = text.chars().count();
+        if len >= self.width {
+            return text;
+        }
+        let room = self.width - len;
+        if self.zero && numeric && self.align.is_none() {
+            let sign = if text.starts_with(['+', '-']) { 1 } else { 0 };
+            return format!("{}{}{}", &text[..sign], "0".repeat(room), &text[sign..]);
+        }
+        let fill = self.fill.unwrap_or(' ').to_string();
+        let (left, right) = match self.align.unwrap_or(if numeric { Align::Right } else { Align::Left }) {
+            Align::Left => (0, room),
+            Align::Right => (room, 0),
+            Align::Center => (room / 2, room - room / 2),
+        };
+        format!("{}{}{}", fill.repeat(left), text, fill.repeat(right))
+    }
+}
+
+fn digits(chars: &[char], i: &mut usize) -> Result<Option<usize>, String> {
+    let start = *i;
+    while chars.get(*i).is_some_and(char::is_ascii_digit) {
+        *i += 1;
+    }
+    if start == *i {
+        return Ok(None);
+    }
+    let n: usize = chars[start..*i].iter().collect::<String>().parse().unwrap_or(usize::MAX);
+    if n > MAX_WIDTH {
+        return Err(format!("{} is more than the largest width or precision, {}", n, MAX_WIDTH));
+    }
+    Ok(Some(n))
+}
+
+fn mismatch(kind: Kind, expected: &str, found: &AxValue) -> RuntimeError {
+    RuntimeError::TypeMismatch {
+        expected: format!("{} for format type '{}'", expected, kind.char()),
+        found: found.type_name().to_string(),
+        span: Span::default(),
+    }
+}
+
+/// Which argument a placeholder takes.
+enum Field {
+    /// `{}`: the one after the previous positional placeholder's
+    Next,
+    /// `{1}`
+    Index(usize),
+    /// `{name}`: a key of the map passed last
+    Name(String),
+}
+
+enum Piece {
+    Text(String),
+    Placeholder(Field, Spec),
+}
+
+/// `str.fmt`: `template` with each `{field:spec}` replaced by its argument.
+/// `{{` and `}}` stand for literal braces. A template with named
+/// placeholders takes its names from a map passed as the last argument.
+pub fn format(template: &str, args: &[AxValue]) -> Result<String, RuntimeError> {
+    let pieces = parse_template(template).map_err(|message| RuntimeError::IntrinsicError {
+        function: "str.fmt".to_string(),
+        kind: FailureKind::Parse,
+        message,
+    })?;
+    let named = pieces.iter().any(|p| matches!(p, Piece::Placeholder(Field::Name(_), _)));
+    let (positional, names): (&[AxValue], Option<&DashMap<String, AxValue>>) = match (named, args.split_last()) {
+        (false, _) => (args, None),
+        (true, Some((AxValue::Map(names), rest))) => (rest, Some(names)),
+        (true, _) => return Err(missing("named placeholders need a map of names as the last argument".to_string())),
+    };
+
+    let mut out = String::new();
+    let mut next = 0;
+    for piece in pieces {
+        match piece {
+            Piece::Text(text) => out.push_str(&text),
+            Piece::Placeholder(field, spec) => {
+                let value = match field {
+                    Field::Next => { next += 1; positional.get(next - 1).cloned().ok_or(next - 1) }
+                    Field::Index(i) => positional.get(i).cloned().ok_or(i),
+                    Field::Name(name) => match names.and_then(|m| m.get(&name).map(|v| v.value().clone())) {
+                        Some(value) => Ok(value),
+                        None => return Err(missing(format!("no value named '{}'", name))),
+                    },
+                };
+                let value = value.map_err(|i| missing(format!("placeholder {} has no argument ({} given)", i, positional.len())))?;
+                out.push_str(&spec.apply(&value)?);
+            }
+        }
+    }
+    Ok(out)
+}
+
+fn missing(message: String) -> RuntimeError {
+    RuntimeError::IntrinsicError { function: "str.fmt".to_string(), kind: FailureKind::Parse, message }
+}
+
+fn parse_template(template: &str) -> Result<Vec<Piece>, String> {
+    let mut pieces = Vec::new();
+    let mut text = String::new();
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => { chars.next(); text.push('{'); }
+            '}' if chars.peek() == Some(&'}') => { chars.next(); text.push('}'); }
+            '}' => return Err("unmatched '}' (write '}}' for a literal brace)".to_string()),
+            '{' => {
+                let mut inner = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => inner.push(c),
+                        None => return Err("unclosed '{' (write '{{' for a literal brace)".to_string()),
+                    }
+                }
+                let (field, spec) = inner.split_once(':').unwrap_or((inner.as_str(), ""));
+                let field = match field.trim() {
+                    "" => Field::Next,
+                    f if f.chars().all(|c| c.is_ascii_digit()) => Field::Index(f.parse().map_err(|_| format!("bad index '{}'", f))?),
+                    f if f.chars().all(|c| c.is_alphanumeric() || c == '_') => Field::Name(f.to_string()),
+                    f => return Err(format!("bad placeholder '{{{}}}'", f)),
+                };
+                pieces.push(Piece::Text(std::mem::take(&mut text)));
+                pieces.push(Piece::Placeholder(field, Spec::parse(spec)?));
+            }
+            c => text.push(c),
+        }
+    }
+    pieces.push(Piece::Text(text));
+    Ok(pieces)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    fn fmt(spec: &str, value: AxValue) -> String {
+        Spec::parse(spec).unwrap().apply(&value).unwrap()
+    }
+
+    #[test]
+    fn test_specs() {
+        assert_eq!(fmt(".2", AxValue::Num(1.23456)), "1.23");
+        assert_eq!(fmt(">8.1", AxValue::Num(2.26)), "     2.3");
+        assert_eq!(fmt("08.2", AxValue::Num(-1.5)), "-0001.50");
+        assert_eq!(fmt("*^7", AxValue::Str("ab".into())), "**ab***");
+        assert_eq!(fmt("5", AxValue::Str("ab".into())), "ab   ");
+        assert_eq!(fmt(".3", AxValue::Str("abcdef".into())), "abc");
+        assert_eq!(fmt("x", AxValue::Num(255.0)), "ff");
+        assert_eq!(fmt("#>6b", AxValue::Num(5.0)), "###101");
+        assert_eq!(fmt("+", AxValue::Num(3.0)), "+3");
+        assert_eq!(fmt(".1%", AxValue::Num(0.125)), "12.5%");
+        assert_eq!(fmt(".2e", AxValue::Num(1234.5)), "1.23e3");
+        assert!(Spec::parse(".").is_err());
+        assert!(Spec::parse("5q").is_err());
+        assert!(Spec::parse("x").unwrap().apply(&AxValue::Num(1.5)).is_err());
+        assert!(Spec::parse("x").unwrap().apply(&AxValue::Str("a".into())).is_err());
+    }
+
+    #[test]
+    fn test_templates() {
+        let names = Arc::new(DashMap::new());
+        names.insert("name".to_string(), AxValue::Str("Ada".into()));
+        let args = [AxValue::Num(36.0), AxValue::Map(names)];
+        assert_eq!(format("Hello {name}, you are {} years old", &args).unwrap(), "Hello Ada, you are 36 years old");
+        assert_eq!(format("{1}{0}{{{}}}", &[AxValue::Num(1.0), AxValue::Num(2.0)]).unwrap(), "21{1}");
+        assert!(format("{} {}", &[AxValue::Num(1.0)]).is_err());
+        assert!(format("{name}", &[AxValue::Num(1.0)]).is_err());
+        assert!(format("{", &[]).is_err());
+    }
+}
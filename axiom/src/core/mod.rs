@@ -1,5 +1,6 @@
 pub mod value;
 pub mod oop;
+pub mod format;
 
 pub use value::{AxValue, AxObject, ValidationError};
 pub use oop::{AxCallable, AxClass, AxInstance, AxEnum, AxEnumVariantDef};
@@ -3,27 +3,57 @@
 
 use crate::ast::{Stmt, Expr};
 use dashmap::DashMap;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, Weak};
 use std::fmt;
 use std::collections::HashMap;
 
 // ---------------------------------------------------------------------------
 // AxCallable — user-defined or native function
 // ---------------------------------------------------------------------------
+
+/// Host function callable from Axiom. Being a closure it can capture state
+/// (connection pools, host handles, config); failures surface as RuntimeErrors.
+pub type NativeFn = Arc<
+    dyn Fn(Vec<crate::core::value::AxValue>) -> Result<crate::core::value::AxValue, crate::errors::RuntimeError>
+        + Send + Sync,
+>;
+
 #[derive(Clone)]
 pub enum AxCallable {
     UserDefined {
+        /// The declared function or method name; `None` for a lambda
+        name: Option<String>,
         params: Vec<String>,
         body: Vec<Stmt>,
         /// Captured lexical environment (closure variables)
-        captured: HashMap<String, crate::core::value::AxValue>,
+        captured: HashMap<std::sync::Arc<str>, crate::core::value::AxValue>,
     },
     Native {
         name: String,
-        func: fn(Vec<crate::core::value::AxValue>) -> crate::core::value::AxValue,
+        func: NativeFn,
     },
 }
 
+impl AxCallable {
+    /// Wrap a host closure as a native callable. A native whose name needs
+    /// a capability checks it on every call, however it is reached.
+    pub fn native<F>(name: impl Into<String>, func: F) -> Self
+    where
+        F: Fn(Vec<crate::core::value::AxValue>) -> Result<crate::core::value::AxValue, crate::errors::RuntimeError>
+            + Send + Sync + 'static,
+    {
+        let name = name.into();
+        if crate::sandbox::required_capabilities(&name).is_empty() {
+            return AxCallable::Native { name, func: Arc::new(func) };
+        }
+        let gated = name.clone();
+        AxCallable::Native { name, func: Arc::new(move |args| {
+            crate::sandbox::check_active(&gated)?;
+            func(args)
+        }) }
+    }
+}
+
 impl fmt::Debug for AxCallable {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -45,7 +75,15 @@ pub struct AxClass {
     pub name: String,
     pub parent: Option<Arc<AxClass>>,
     pub methods: HashMap<String, AxCallable>,
+    /// `get` accessors, run when the property is read
+    pub getters: HashMap<String, AxCallable>,
+    /// `set` accessors, run with the assigned value
+    pub setters: HashMap<String, AxCallable>,
     pub fields: Vec<(String, Option<Expr>)>,
+    /// `static` fields and methods; the class name is bound to this map
+    pub statics: Arc<DashMap<String, crate::core::value::AxValue>>,
+    /// Interfaces declared with `impl`
+    pub interfaces: Vec<String>,
 }
 
 impl AxClass {
@@ -54,7 +92,11 @@ impl AxClass {
             name,
             parent: None,
             methods: HashMap::new(),
+            getters: HashMap::new(),
+            setters: HashMap::new(),
             fields: Vec::new(),
+            statics: Arc::new(DashMap::new()),
+            interfaces: Vec::new(),
         }
     }
 
@@ -69,11 +111,70 @@ impl AxClass {
         None
     }
 
+    /// True if this class or an ancestor declares any `get`/`set` accessor;
+    /// plain member access skips the accessor lookup otherwise.
+    pub fn has_accessors(&self) -> bool {
+        !self.getters.is_empty() || !self.setters.is_empty()
+            || self.parent.as_ref().is_some_and(|p| p.has_accessors())
+    }
+
+    /// True if this class or an ancestor declares `impl interface`.
+    pub fn implements(&self, interface: &str) -> bool {
+        self.interfaces.iter().any(|i| i == interface)
+            || self.parent.as_ref().is_some_and(|p| p.implements(interface))
+    }
+
     pub fn has_init(&self) -> bool {
         self.methods.contains_key("init")
     }
+
+    /// Names of the methods an instance can call, its own and inherited, sorted.
+    pub fn method_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.methods.keys().cloned().collect();
+        if let Some(parent) = &self.parent {
+            names.extend(parent.method_names());
+        }
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// Names of the declared instance fields, the root class's first.
+    pub fn field_names(&self) -> Vec<String> {
+        let mut names = self.parent.as_ref().map(|p| p.field_names()).unwrap_or_default();
+        for (name, _) in &self.fields {
+            if !names.contains(name) { names.push(name.clone()); }
+        }
+        names
+    }
+
+    /// Record `class` so `declared` can find it from its statics map, which
+    /// is what the class's name evaluates to.
+    pub fn declare(class: &Arc<AxClass>) {
+        let mut declared = DECLARED.lock().unwrap_or_else(|e| e.into_inner());
+        declared.retain(|c| c.strong_count() > 0);
+        declared.push(Arc::downgrade(class));
+    }
+
+    /// The declared class whose name evaluates to `statics`, if it is still alive.
+    pub fn declared(statics: &Arc<DashMap<String, crate::core::value::AxValue>>) -> Option<Arc<AxClass>> {
+        Self::find_declared(|c| Arc::ptr_eq(&c.statics, statics))
+    }
+
+    /// The class most recently declared as `name`, if it is still alive.
+    pub fn declared_named(name: &str) -> Option<Arc<AxClass>> {
+        Self::find_declared(|c| c.name == name)
+    }
+
+    fn find_declared(matches: impl Fn(&AxClass) -> bool) -> Option<Arc<AxClass>> {
+        let declared = DECLARED.lock().unwrap_or_else(|e| e.into_inner());
+        declared.iter().rev().filter_map(Weak::upgrade).find(|c| matches(c))
+    }
 }
 
+/// Classes declared by the tree-walker (the VM does not run classes).
+static DECLARED: Mutex<Vec<Weak<AxClass>>> = Mutex::new(Vec::new());
+
 // ---------------------------------------------------------------------------
 // AxInstance — runtime instance of a class
 // ---------------------------------------------------------------------------
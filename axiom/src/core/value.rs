@@ -4,8 +4,86 @@
 use crate::core::oop::{AxCallable, AxInstance};
 use dashmap::DashMap;
 use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{Arc, RwLock};
 
+/// Bumped whenever a list or map is mutated in place, by either engine.
+/// Boundary conversions remember the revision they were made at and only
+/// reuse a converted collection while it is unchanged.
+static COLLECTIONS_REVISION: AtomicU64 = AtomicU64::new(0);
+
+/// Record an in-place list/map mutation.
+#[inline]
+pub fn touch_collections() {
+    COLLECTIONS_REVISION.fetch_add(1, Ordering::Relaxed);
+}
+
+pub fn collections_revision() -> u64 {
+    COLLECTIONS_REVISION.load(Ordering::Relaxed)
+}
+
+/// Containers made read-only by `col.freeze`, by address: lists and maps
+/// of either engine, and instances. The weak reference tells a live
+/// container from a freed one whose address was reused.
+struct Frozen {
+    containers: std::collections::HashMap<usize, std::sync::Weak<dyn std::any::Any + Send + Sync>>,
+    /// Dead entries are dropped when the table grows past this
+    prune_at: usize,
+}
+
+static FROZEN: std::sync::Mutex<Option<Frozen>> = std::sync::Mutex::new(None);
+
+/// Set by the first freeze, so mutations skip the lookup until then.
+static ANY_FROZEN: AtomicBool = AtomicBool::new(false);
+
+/// Make `container` read-only for the rest of its life.
+pub fn freeze_container<T: std::any::Any + Send + Sync>(container: &Arc<T>) {
+    let mut frozen = FROZEN.lock().unwrap_or_else(|e| e.into_inner());
+    let frozen = frozen.get_or_insert_with(|| Frozen { containers: Default::default(), prune_at: 64 });
+    if frozen.containers.len() >= frozen.prune_at {
+        frozen.containers.retain(|_, weak| weak.strong_count() > 0);
+        frozen.prune_at = (frozen.containers.len() * 2).max(64);
+    }
+    let weak: std::sync::Weak<dyn std::any::Any + Send + Sync> = Arc::downgrade(container) as _;
+    frozen.containers.insert(Arc::as_ptr(container) as *const () as usize, weak);
+    ANY_FROZEN.store(true, Ordering::Relaxed);
+}
+
+pub fn is_frozen_container<T: std::any::Any + Send + Sync>(container: &Arc<T>) -> bool {
+    if !ANY_FROZEN.load(Ordering::Relaxed) {
+        return false;
+    }
+    let frozen = FROZEN.lock().unwrap_or_else(|e| e.into_inner());
+    frozen.as_ref()
+        .and_then(|f| f.containers.get(&(Arc::as_ptr(container) as *const () as usize)))
+        .is_some_and(|weak| weak.strong_count() > 0)
+}
+
+/// Maps are hash-backed; with `sorted_maps=on` (the default) everything a
+/// script can observe (display, `col.keys`, `col.values`) walks them in key
+/// order so output is stable from run to run.
+static SORTED_MAPS: AtomicBool = AtomicBool::new(true);
+
+pub fn set_sorted_maps(on: bool) {
+    SORTED_MAPS.store(on, Ordering::Relaxed);
+}
+
+pub fn sorted_maps() -> bool {
+    SORTED_MAPS.load(Ordering::Relaxed)
+}
+
+/// Snapshot of a map's entries in script-visible order.
+pub fn map_entries(m: &DashMap<String, AxValue>) -> Vec<(String, AxValue)> {
+    let mut entries: Vec<(String, AxValue)> = m
+        .iter()
+        .map(|e| (e.key().clone(), e.value().clone()))
+        .collect();
+    if sorted_maps() {
+        entries.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+    }
+    entries
+}
+
 #[derive(Debug, Clone)]
 pub enum ValidationError {
     TypeError(String),
@@ -166,45 +244,236 @@ impl AxValue {
         }
     }
 
+    /// Whether `col.freeze` made this list, map or instance read-only.
+    pub fn is_frozen(&self) -> bool {
+        match self {
+            AxValue::Lst(list) => is_frozen_container(list),
+            AxValue::Map(map) => is_frozen_container(map),
+            AxValue::Instance(inst) => is_frozen_container(inst),
+            _ => false,
+        }
+    }
+
+    /// `Ok` unless the value is frozen; call before changing it in place.
+    pub fn ensure_mutable(&self) -> Result<(), crate::errors::RuntimeError> {
+        if self.is_frozen() {
+            return Err(crate::errors::RuntimeError::FrozenMutation { type_name: self.type_name().to_string(), span: Default::default() });
+        }
+        Ok(())
+    }
+
+    /// Text form used by `out`, `str()`, string interpolation and `log.*`.
     pub fn display(&self) -> String {
+        let mut out = String::new();
+        self.write_display(&mut out, &mut DisplayGuard::default());
+        out
+    }
+
+    fn write_display(&self, out: &mut String, guard: &mut DisplayGuard) {
         match self {
             AxValue::Num(n) => {
-                if *n == n.floor() && n.is_finite() {
-                    format!("{}", *n as i64)
+                // Same rule as the VM's Val::Float
+                if n.fract() == 0.0 && n.abs() < 1e15 {
+                    out.push_str(&(*n as i64).to_string());
                 } else {
-                    format!("{}", n)
+                    out.push_str(&n.to_string());
                 }
             }
-            AxValue::Str(s) => s.clone(),
-            AxValue::Bol(b) => format!("{}", b),
+            AxValue::Str(s) => out.push_str(s),
+            AxValue::Bol(b) => out.push_str(&b.to_string()),
             AxValue::Lst(l) => {
-                let items = l.read().unwrap();
-                let parts: Vec<String> = items.iter().map(|v| v.display()).collect();
-                format!("[{}]", parts.join(", "))
+                if !guard.enter(Arc::as_ptr(l) as usize) {
+                    out.push_str("[...]");
+                    return;
+                }
+                out.push('[');
+                for (i, v) in l.read().unwrap().iter().enumerate() {
+                    if i > 0 { out.push_str(", "); }
+                    v.write_display(out, guard);
+                }
+                out.push(']');
+                guard.leave();
             }
             AxValue::Map(m) => {
-                let entries: Vec<String> = m
-                    .iter()
-                    .map(|e| format!("{}: {}", e.key(), e.value().display()))
-                    .collect();
-                format!("{{{}}}", entries.join(", "))
+                if !guard.enter(Arc::as_ptr(m) as usize) {
+                    out.push_str("{...}");
+                    return;
+                }
+                write_entries(out, &map_entries(m), guard);
+                guard.leave();
+            }
+            AxValue::Obj(o) => {
+                out.push('<');
+                out.push_str(&o.type_name);
+                out.push('>');
             }
-            AxValue::Obj(o) => format!("<{}>", o.type_name),
             AxValue::Instance(inst) => {
                 let i = inst.read().unwrap();
-                let fields: Vec<String> = i
-                    .fields
-                    .iter()
-                    .map(|e| format!("{}: {}", e.key(), e.value().display()))
-                    .collect();
-                format!("<{} {{{}}}>", i.class.name, fields.join(", "))
+                out.push('<');
+                out.push_str(&i.class.name);
+                if guard.enter(Arc::as_ptr(inst) as usize) {
+                    out.push(' ');
+                    write_entries(out, &map_entries(&i.fields), guard);
+                    guard.leave();
+                } else {
+                    out.push_str(" {...}");
+                }
+                out.push('>');
             }
-            AxValue::EnumVariant(name, val) => match val.as_ref() {
-                AxValue::Nil => format!("{}", name),
-                other => format!("{}({})", name, other.display()),
-            },
-            AxValue::Fun(_) => "<fun>".to_string(),
-            AxValue::Nil => "nil".to_string(),
+            AxValue::EnumVariant(name, val) => {
+                out.push_str(name);
+                if !matches!(val.as_ref(), AxValue::Nil) {
+                    out.push('(');
+                    val.write_display(out, guard);
+                    out.push(')');
+                }
+            }
+            AxValue::Fun(_) => out.push_str("<fun>"),
+            AxValue::Nil => out.push_str("nil"),
+        }
+    }
+}
+
+fn write_entries(out: &mut String, entries: &[(String, AxValue)], guard: &mut DisplayGuard) {
+    out.push('{');
+    for (i, (k, v)) in entries.iter().enumerate() {
+        if i > 0 { out.push_str(", "); }
+        out.push_str(k);
+        out.push_str(": ");
+        v.write_display(out, guard);
+    }
+    out.push('}');
+}
+
+/// Containers deeper than this print as `[...]` / `{...}`.
+pub const DISPLAY_MAX_DEPTH: usize = 64;
+
+/// Tracks the containers being printed so a value that contains itself
+/// prints as `[...]` / `{...}` instead of recursing forever (shared by
+/// `AxValue::display` and the VM's `Val::display`).
+#[derive(Default)]
+pub struct DisplayGuard {
+    open: Vec<usize>,
+}
+
+impl DisplayGuard {
+    /// Start printing the container at `addr`; false if it is already being
+    /// printed further up or the depth limit is reached.
+    pub fn enter(&mut self, addr: usize) -> bool {
+        if self.open.len() >= DISPLAY_MAX_DEPTH || self.open.contains(&addr) {
+            return false;
+        }
+        self.open.push(addr);
+        true
+    }
+
+    pub fn leave(&mut self) {
+        self.open.pop();
+    }
+}
+
+/// Nesting deeper than this compares unequal instead of overflowing the stack.
+pub const COMPARE_MAX_DEPTH: usize = 1024;
+
+/// Rank of each kind of value in the sort order.
+fn sort_rank(v: &AxValue) -> u8 {
+    match v {
+        AxValue::Nil => 0,
+        AxValue::Bol(_) => 1,
+        AxValue::Num(_) => 2,
+        AxValue::Str(_) => 3,
+        AxValue::Lst(_) => 4,
+        AxValue::Map(_) => 5,
+        _ => 6,
+    }
+}
+
+/// Numeric order with NaN after every number (and equal to itself).
+fn cmp_f64(a: f64, b: f64) -> std::cmp::Ordering {
+    a.partial_cmp(&b).unwrap_or_else(|| a.is_nan().cmp(&b.is_nan()))
+}
+
+impl AxValue {
+    /// `==` in the language. Numbers compare numerically (NaN equals
+    /// nothing); strings, lists, maps, instances and enum payloads compare
+    /// structurally; functions and native objects by identity. A collection
+    /// always equals itself, and a pair of containers met again further down
+    /// (a cycle) counts as equal.
+    pub fn deep_eq(&self, other: &AxValue) -> bool {
+        self.eq_in(other, &mut Vec::new())
+    }
+
+    fn eq_in(&self, other: &AxValue, seen: &mut Vec<(usize, usize)>) -> bool {
+        match (self, other) {
+            (AxValue::Nil, AxValue::Nil) => true,
+            (AxValue::Bol(a), AxValue::Bol(b)) => a == b,
+            (AxValue::Num(a), AxValue::Num(b)) => a == b,
+            (AxValue::Str(a), AxValue::Str(b)) => a == b,
+            (AxValue::Lst(a), AxValue::Lst(b)) => {
+                if Arc::ptr_eq(a, b) { return true; }
+                let items = |l: &Arc<RwLock<Vec<AxValue>>>| l.read().unwrap().clone();
+                nested_eq(seen, (Arc::as_ptr(a) as usize, Arc::as_ptr(b) as usize), |seen| {
+                    let (a, b) = (items(a), items(b));
+                    a.len() == b.len() && a.iter().zip(&b).all(|(x, y)| x.eq_in(y, seen))
+                })
+            }
+            (AxValue::Map(a), AxValue::Map(b)) => {
+                if Arc::ptr_eq(a, b) { return true; }
+                nested_eq(seen, (Arc::as_ptr(a) as usize, Arc::as_ptr(b) as usize), |seen| entries_eq(a, b, seen))
+            }
+            (AxValue::Instance(a), AxValue::Instance(b)) => {
+                if Arc::ptr_eq(a, b) { return true; }
+                nested_eq(seen, (Arc::as_ptr(a) as usize, Arc::as_ptr(b) as usize), |seen| {
+                    let (a, b) = (a.read().unwrap(), b.read().unwrap());
+                    a.class.name == b.class.name && entries_eq(&a.fields, &b.fields, seen)
+                })
+            }
+            (AxValue::EnumVariant(n1, v1), AxValue::EnumVariant(n2, v2)) => n1 == n2 && v1.eq_in(v2, seen),
+            (AxValue::Fun(a), AxValue::Fun(b)) => Arc::ptr_eq(a, b),
+            (AxValue::Obj(a), AxValue::Obj(b)) => Arc::ptr_eq(&a.fields, &b.fields),
+            _ => false,
+        }
+    }
+
+    /// Total order used for sorting: nil < bools < numbers < strings <
+    /// lists < maps < everything else. Numbers sort numerically with NaN
+    /// last, strings bytewise, lists element by element; values of the
+    /// last two groups are equal to each other, so a stable sort keeps
+    /// their order.
+    pub fn sort_cmp(&self, other: &AxValue) -> std::cmp::Ordering {
+        use std::cmp::Ordering;
+        match (self, other) {
+            (AxValue::Bol(a), AxValue::Bol(b)) => a.cmp(b),
+            (AxValue::Num(a), AxValue::Num(b)) => cmp_f64(*a, *b),
+            (AxValue::Str(a), AxValue::Str(b)) => a.cmp(b),
+            (AxValue::Lst(a), AxValue::Lst(b)) => {
+                if Arc::ptr_eq(a, b) { return Ordering::Equal; }
+                let (a, b) = (a.read().unwrap().clone(), b.read().unwrap().clone());
+                a.iter().zip(&b)
+                    .map(|(x, y)| x.sort_cmp(y))
+                    .find(|o| o.is_ne())
+                    .unwrap_or_else(|| a.len().cmp(&b.len()))
+            }
+            _ => sort_rank(self).cmp(&sort_rank(other)),
         }
     }
 }
+
+/// Compare a pair of containers once: revisiting the pair is a cycle and
+/// counts as equal, and past `COMPARE_MAX_DEPTH` they are unequal.
+fn nested_eq(seen: &mut Vec<(usize, usize)>, pair: (usize, usize), f: impl FnOnce(&mut Vec<(usize, usize)>) -> bool) -> bool {
+    if seen.contains(&pair) { return true; }
+    if seen.len() >= COMPARE_MAX_DEPTH { return false; }
+    seen.push(pair);
+    let eq = f(seen);
+    seen.pop();
+    eq
+}
+
+fn entries_eq(a: &DashMap<String, AxValue>, b: &DashMap<String, AxValue>, seen: &mut Vec<(usize, usize)>) -> bool {
+    let entries: Vec<(String, AxValue)> = a.iter().map(|e| (e.key().clone(), e.value().clone())).collect();
+    a.len() == b.len() && entries.iter().all(|(k, v)| {
+        let other = b.get(k).map(|e| e.value().clone());
+        other.is_some_and(|o| v.eq_in(&o, seen))
+    })
+}
@@ -0,0 +1,279 @@
+//! Local crash reports (`axiom --crash-report ...` / `axiom report show`)
+//!
+//! With `--crash-report`, a panic hook records where the panic happened,
+//! its backtrace and a short summary of the engine's state (pipeline stage,
+//! heap statistics). When the CLI then catches the panic, or a native
+//! function's panic surfaces as an internal error, everything is written as
+//! one JSON bundle under `~/.axiom/crash/` together with the script, the
+//! version and the configuration that differs from the defaults.
+//!
+//! Nothing is ever sent anywhere: the bundle stays on disk until the user
+//! attaches it to a bug report.
+
+use std::cell::Cell;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+/// Scripts larger than this are cut off in the bundle.
+const MAX_SOURCE_BYTES: usize = 256 * 1024;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// The last panic the hook saw, on any thread.
+static LAST_PANIC: Mutex<Option<PanicRecord>> = Mutex::new(None);
+
+/// The script the command is working on, for the bundle.
+static SCRIPT: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+thread_local! {
+    /// What this thread is doing: "parse", "compile", "run (vm)", ...
+    static STAGE: Cell<&'static str> = const { Cell::new("startup") };
+}
+
+/// What the panic hook captured at the moment of the panic.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PanicRecord {
+    pub message: String,
+    /// `file:line:column` in the axiom sources
+    pub location: String,
+    pub thread: String,
+    pub stage: String,
+    /// Heap statistics of the panicking thread, when the heap was not in use
+    pub heap: Option<String>,
+    pub backtrace: String,
+}
+
+/// A configuration property set to something other than its default.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfEntry {
+    pub key: String,
+    pub value: String,
+    pub origin: String,
+}
+
+/// Everything written to one crash bundle.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrashReport {
+    pub version: String,
+    /// RFC 3339, local time
+    pub created: String,
+    pub os: String,
+    pub arch: String,
+    pub command: Vec<String>,
+    pub script: Option<String>,
+    pub source: Option<String>,
+    /// The error the CLI reported
+    pub error: String,
+    pub panic: Option<PanicRecord>,
+    pub conf: Vec<ConfEntry>,
+}
+
+/// Turn crash reports on for this process and install the panic hook.
+/// The previous hook still runs, so the usual panic message is printed.
+pub fn enable() {
+    if ENABLED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let message = crate::errors::panic_message(info.payload());
+        let record = PanicRecord {
+            message,
+            location: info.location().map(|l| l.to_string()).unwrap_or_default(),
+            thread: std::thread::current().name().unwrap_or("<unnamed>").to_string(),
+            stage: stage().to_string(),
+            heap: crate::gc::try_summary(),
+            backtrace: std::backtrace::Backtrace::force_capture().to_string(),
+        };
+        *LAST_PANIC.lock().unwrap_or_else(|e| e.into_inner()) = Some(record);
+        previous(info);
+    }));
+}
+
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Record what this thread is doing, for the bundle of a panic that follows.
+pub fn set_stage(stage: &'static str) {
+    STAGE.with(|s| s.set(stage));
+}
+
+pub fn stage() -> &'static str {
+    STAGE.with(Cell::get)
+}
+
+/// Record the script this command works on; its source goes in the bundle.
+pub fn set_script(path: &Path) {
+    *SCRIPT.lock().unwrap_or_else(|e| e.into_inner()) = Some(path.to_path_buf());
+}
+
+/// `~/.axiom/crash`, where bundles are written.
+pub fn crash_dir() -> Option<PathBuf> {
+    dirs::home_dir().map(|h| h.join(".axiom").join("crash"))
+}
+
+impl CrashReport {
+    /// Gather a report for `error` from the recorded panic, script and configuration.
+    pub fn collect(error: &str) -> CrashReport {
+        let script = SCRIPT.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        let source = script.as_deref().and_then(|p| std::fs::read_to_string(p).ok()).map(|mut text| {
+            if text.len() > MAX_SOURCE_BYTES {
+                let mut cut = MAX_SOURCE_BYTES;
+                while !text.is_char_boundary(cut) { cut -= 1; }
+                text.truncate(cut);
+                text.push_str("\n# ... truncated by axiom\n");
+            }
+            text
+        });
+        let conf = crate::conf::AxConf::load();
+        let conf = crate::conf::ALL_PROPS.iter()
+            .filter_map(|p| {
+                let value = conf.get(p.name)?;
+                (value != p.default).then(|| ConfEntry {
+                    key: p.name.to_string(),
+                    value: value.to_string(),
+                    origin: conf.origin(p.name).map(|o| o.to_string()).unwrap_or_default(),
+                })
+            })
+            .collect();
+        CrashReport {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            created: chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, false),
+            os: std::env::consts::OS.to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+            command: std::env::args().collect(),
+            script: script.map(|p| p.display().to_string()),
+            source,
+            error: error.to_string(),
+            panic: LAST_PANIC.lock().unwrap_or_else(|e| e.into_inner()).clone(),
+            conf,
+        }
+    }
+
+    /// Write the report to `dir` as `crash-<time>-<pid>.json` and return its path.
+    pub fn write_to(&self, dir: &Path) -> Result<PathBuf, String> {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Cannot create '{}': {}", dir.display(), e))?;
+        let name = format!("crash-{}-{}.json", chrono::Local::now().format("%Y%m%d-%H%M%S"), std::process::id());
+        let path = dir.join(name);
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        std::fs::write(&path, json).map_err(|e| format!("Cannot write '{}': {}", path.display(), e))?;
+        Ok(path)
+    }
+
+    pub fn read(path: &Path) -> Result<CrashReport, String> {
+        let json = std::fs::read_to_string(path).map_err(|e| format!("Cannot read '{}': {}", path.display(), e))?;
+        serde_json::from_str(&json).map_err(|e| format!("'{}' is not a crash report: {}", path.display(), e))
+    }
+
+    /// The report as text for `axiom report show`.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        out.push_str(&format!("axiom {} on {}/{} — {}\n", self.version, self.os, self.arch, self.created));
+        out.push_str(&format!("command: {}\n", self.command.join(" ")));
+        out.push_str(&format!("error:   {}\n", self.error));
+        if let Some(panic) = &self.panic {
+            out.push_str(&format!("panic:   {} at {} (thread '{}')\n", panic.message, panic.location, panic.thread));
+            out.push_str(&format!("stage:   {}\n", panic.stage));
+            if let Some(heap) = &panic.heap {
+                out.push_str(&format!("heap:    {}\n", heap));
+            }
+        }
+        out.push_str("\nconfiguration (non-default):\n");
+        if self.conf.is_empty() {
+            out.push_str("  (all defaults)\n");
+        }
+        for entry in &self.conf {
+            out.push_str(&format!("  {}={}  [{}]\n", entry.key, entry.value, entry.origin));
+        }
+        if let Some(script) = &self.script {
+            let lines = self.source.as_deref().map(|s| s.lines().count()).unwrap_or(0);
+            let noun = if lines == 1 { "line" } else { "lines" };
+            out.push_str(&format!("\nscript: {} ({} {} included)\n", script, lines, noun));
+        }
+        if let Some(panic) = &self.panic {
+            out.push_str("\nbacktrace:\n");
+            for line in panic.backtrace.lines() {
+                out.push_str(&format!("  {}\n", line));
+            }
+        }
+        out
+    }
+}
+
+/// Write a bundle for `error` to `~/.axiom/crash` and tell the user where it is.
+pub fn write_bundle(error: &str) {
+    let Some(dir) = crash_dir() else { return };
+    match CrashReport::collect(error).write_to(&dir) {
+        Ok(path) => eprintln!(
+            "Crash report written to {}\n  It never leaves this machine; view it with `axiom report show` and attach it to a bug report.",
+            path.display(),
+        ),
+        Err(e) => eprintln!("Could not write a crash report: {}", e),
+    }
+}
+
+/// The bundles in `dir`, oldest first (their names sort by time).
+pub fn list_reports(dir: &Path) -> Vec<PathBuf> {
+    let mut reports: Vec<PathBuf> = std::fs::read_dir(dir).into_iter().flatten().flatten()
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    reports.sort();
+    reports
+}
+
+/// The bundle `name` picks in `dir`: a path, a file name with or without
+/// `.json`, or the newest one when `name` is None.
+pub fn find_report(dir: &Path, name: Option<&str>) -> Result<PathBuf, String> {
+    let Some(name) = name else {
+        return list_reports(dir).pop().ok_or_else(|| format!("No crash reports in {}", dir.display()));
+    };
+    let candidates = [PathBuf::from(name), dir.join(name), dir.join(format!("{}.json", name))];
+    candidates.into_iter().find(|p| p.is_file())
+        .ok_or_else(|| format!("No crash report '{}' (see `axiom report list`)", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_round_trips_and_renders() {
+        let dir = std::env::temp_dir().join(format!("axiom-crash-{}", std::process::id()));
+        let report = CrashReport {
+            version: "0.1.0".into(),
+            created: "2026-10-15T12:00:00+00:00".into(),
+            os: "linux".into(),
+            arch: "x86_64".into(),
+            command: vec!["axiom".into(), "run".into(), "app.ax".into()],
+            script: Some("app.ax".into()),
+            source: Some("out 1;\nout 2;\n".into()),
+            error: "internal error (panic): boom".into(),
+            panic: Some(PanicRecord {
+                message: "boom".into(),
+                location: "axiom/src/vm_core.rs:10:5".into(),
+                thread: "axiom-worker".into(),
+                stage: "run (vm)".into(),
+                heap: None,
+                backtrace: "0: main".into(),
+            }),
+            conf: vec![ConfEntry { key: "engine".into(), value: "vm".into(), origin: "user".into() }],
+        };
+        let path = report.write_to(&dir).unwrap();
+        assert_eq!(find_report(&dir, None).unwrap(), path);
+        let name = path.file_stem().unwrap().to_str().unwrap();
+        assert_eq!(find_report(&dir, Some(name)).unwrap(), path);
+        assert!(find_report(&dir, Some("nope")).is_err());
+
+        let text = CrashReport::read(&path).unwrap().render();
+        assert!(text.contains("panic:   boom at axiom/src/vm_core.rs:10:5 (thread 'axiom-worker')"));
+        assert!(text.contains("stage:   run (vm)"));
+        assert!(text.contains("  engine=vm  [user]"));
+        assert!(text.contains("script: app.ax (2 lines included)"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -21,7 +21,7 @@
 use std::fmt;
 use miette::{Diagnostic, SourceSpan, NamedSource};
 use thiserror::Error;
-use crate::errors::{Span, RuntimeError};
+use crate::errors::{Span, RuntimeError, DiagnosticLevel, FailureKind};
 
 // ═══════════════════════════════════════════════════════════════════════════
 // Error Code Taxonomy (AXM_100-699)
@@ -45,6 +45,10 @@ pub enum ErrorCode {
     MissingReturn           = 206,
     UnreachableCode         = 207,
     CircularDependency      = 208,
+    NonExhaustiveMatch      = 209,
+    UndefinedInterface      = 210,
+    IncompleteInterface     = 211,
+    SelfOutsideMethod       = 212,
     // AXM_300-399: Compiler/Quickening
     SpecializationMismatch  = 301,
     UnsupportedOperation    = 302,
@@ -62,12 +66,17 @@ pub enum ErrorCode {
     StackOverflow           = 408,
     HeapExhausted           = 409,
     InvalidConversion       = 410,
+    BudgetExceeded          = 411,
+    InternalError           = 412,
+    Interrupted             = 413,
+    FrozenMutation          = 414,
     // AXM_500-599: System
     IoError                 = 501,
     UsbError                = 502,
     NetworkError            = 503,
     GcPressure              = 504,
     ResourceLeak            = 505,
+    PermissionDenied        = 506,
     // AXM_600-699: Module
     ModuleNotFound          = 601,
     VersionConflict         = 602,
@@ -99,6 +108,10 @@ impl ErrorCode {
             Self::MissingReturn            => "Missing return statement",
             Self::UnreachableCode          => "Unreachable code after return",
             Self::CircularDependency       => "Circular dependency detected",
+            Self::NonExhaustiveMatch       => "Match does not cover every enum variant",
+            Self::UndefinedInterface       => "Class implements an undeclared interface",
+            Self::IncompleteInterface      => "Class does not implement every interface method",
+            Self::SelfOutsideMethod        => "`self` or `super` used outside a method",
             Self::SpecializationMismatch   => "Type specialization mismatch",
             Self::UnsupportedOperation     => "Operation not supported for this type",
             Self::RegisterAllocFailed      => "Register allocation failure",
@@ -114,11 +127,16 @@ impl ErrorCode {
             Self::StackOverflow            => "Call stack overflow — frame limit exceeded",
             Self::HeapExhausted            => "Heap exhausted (out of memory)",
             Self::InvalidConversion        => "Invalid type conversion",
+            Self::BudgetExceeded           => "Execution budget exceeded",
+            Self::InternalError            => "Internal error in a native function",
+            Self::Interrupted              => "Interrupted",
+            Self::FrozenMutation           => "Mutation of a frozen value",
             Self::IoError                  => "I/O error",
             Self::UsbError                 => "USB device error",
             Self::NetworkError             => "Network unreachable or connection refused",
             Self::GcPressure               => "Garbage collector pressure",
             Self::ResourceLeak             => "Resource leak detected",
+            Self::PermissionDenied         => "Permission denied by sandbox",
             Self::ModuleNotFound           => "Module not found",
             Self::VersionConflict          => "Package version conflict",
             Self::CircularImport           => "Circular import detected",
@@ -143,6 +161,22 @@ impl ErrorCode {
                 "Check bounds before indexing: `if i < alg.len(list) { list[i] } else { nil }`",
             Self::StackOverflow =>
                 "Use iteration (while/for) instead of deep recursion, or ensure the base case is always reachable. TCO only applies to direct tail calls.",
+            Self::HeapExhausted | Self::BudgetExceeded =>
+                "The script hit a sandbox limit: the embedder's ResourceLimits, or `axiom run --timeout`. Raise the limit or reduce the work done.",
+            Self::PermissionDenied =>
+                "This intrinsic group is disabled for the script. Grant it via `axiom conf set sandbox_deny=...` or the embedder's Capabilities.",
+            Self::InternalError =>
+                "A built-in function crashed; the error was contained and the session can continue. Please report it with the message above.",
+            Self::Interrupted =>
+                "The run was stopped with Ctrl-C. Functions registered with `aut.on_exit` ran before exiting.",
+            Self::FrozenMutation =>
+                "`col.freeze` made this value read-only. Change a copy instead: `let copy = col.clone_deep(v)`.",
+            Self::NonExhaustiveMatch =>
+                "Add an arm for each missing variant, or an `els` arm to handle the rest.",
+            Self::IncompleteInterface =>
+                "Add the missing methods to the class, with the parameter counts the interface declares.",
+            Self::SelfOutsideMethod =>
+                "`self` is only bound inside instance methods, and `super` only inside methods of a class that extends another.",
             Self::ModuleNotFound =>
                 "Install the module: `axiom pkg install <name>`. Check spelling and ensure ~/.axiomlibs/ is writable.",
             Self::CircularImport =>
@@ -365,16 +399,29 @@ impl DiagnosticEngine {
     pub fn source_name(&self) -> &str { &self.source_name }
     pub fn source_text(&self) -> &str { &self.source_text }
 
+    /// A diagnostic at `span`, in the file the span was parsed from: an
+    /// imported module registered with the loader, else this engine's source.
+    fn at(&self, code: ErrorCode, message: String, span: Span) -> AxiomDiagnostic {
+        let len = span.end.saturating_sub(span.start).max(1);
+        match crate::loader::source(span.source_id) {
+            Some(file) => AxiomDiagnostic::new(code, message, file.name(), &*file.text, span.start, len),
+            None => AxiomDiagnostic::new(code, message, &self.source_name, &self.source_text, span.start, len),
+        }
+    }
+
     /// Convert a RuntimeError into a fully-spanned AxiomDiagnostic
     pub fn from_runtime(&self, err: &RuntimeError) -> AxiomDiagnostic {
+        if let RuntimeError::ModuleSyntaxError { error, .. } = err {
+            return self.from_parser(error);
+        }
         let (code, msg, span) = match err {
-            RuntimeError::NilCall { hint, span } =>
+            RuntimeError::NilCall { hint, span, .. } =>
                 (ErrorCode::NilCall, format!("{}", hint), *span),
             RuntimeError::NotCallable { type_name, span } =>
                 (ErrorCode::NotCallable,
                  format!("Value of type '{}' is not callable", type_name),
                  *span),
-            RuntimeError::UndefinedVariable { name, span } =>
+            RuntimeError::UndefinedVariable { name, span, .. } =>
                 (ErrorCode::UndefinedVariable,
                  format!("'{}' is not defined in this scope", name),
                  *span),
@@ -400,17 +447,62 @@ impl DiagnosticEngine {
                 (ErrorCode::ModuleNotFound,
                  format!("Cannot import '{}': {}", module, message),
                  Span::default()),
+            RuntimeError::CircularImport { chain } =>
+                (ErrorCode::CircularImport,
+                 format!("Circular import: {}", chain.join(" -> ")),
+                 Span::default()),
+            RuntimeError::LimitExceeded { resource, limit } =>
+                (if *resource == crate::sandbox::Resource::Memory { ErrorCode::HeapExhausted } else { ErrorCode::BudgetExceeded },
+                 format!("Sandbox {} of {} exceeded", resource, resource.format_limit(*limit)),
+                 Span::default()),
+            RuntimeError::IntrinsicError { function, kind, message } =>
+                (match kind {
+                    FailureKind::Io      => ErrorCode::IoError,
+                    FailureKind::Usb     => ErrorCode::UsbError,
+                    FailureKind::Network => ErrorCode::NetworkError,
+                    FailureKind::Parse   => ErrorCode::InvalidConversion,
+                 },
+                 format!("{} failed: {}", function, message),
+                 Span::default()),
+            RuntimeError::PermissionDenied { function, capability } =>
+                (ErrorCode::PermissionDenied,
+                 format!("'{}' requires the '{}' capability", function, capability),
+                 Span::default()),
+            RuntimeError::StackOverflow { limit, call_chain } =>
+                (ErrorCode::StackOverflow,
+                 format!("Call depth exceeded {} frames: {}", limit, crate::errors::format_call_chain(call_chain)),
+                 Span::default()),
+            RuntimeError::Interrupted =>
+                (ErrorCode::Interrupted, "Interrupted".to_string(), Span::default()),
+            RuntimeError::FrozenMutation { type_name, span } =>
+                (ErrorCode::FrozenMutation,
+                 format!("Cannot modify a frozen {}", type_name),
+                 *span),
+            RuntimeError::Internal { function, message } =>
+                (ErrorCode::InternalError,
+                 format!("'{}' panicked: {}", function, message),
+                 Span::default()),
             RuntimeError::GenericError { message, span } =>
                 (ErrorCode::NotCallable, message.clone(), *span),
             _ => (ErrorCode::NotCallable, format!("{}", err), Span::default()),
         };
 
-        AxiomDiagnostic::new(
-            code, msg,
-            &self.source_name, &self.source_text,
-            span.start,
-            span.end.saturating_sub(span.start).max(1),
-        )
+        let diag = self.at(code, msg, span);
+
+        // Did-you-mean: the error's own candidates, plus registered names
+        let unresolved = match err {
+            RuntimeError::UndefinedVariable { name, candidates, .. } => Some((name.as_str(), candidates)),
+            RuntimeError::NilCall { name: Some(name), candidates, .. } => Some((name.as_str(), candidates)),
+            _ => None,
+        };
+        let suggestion = unresolved.and_then(|(name, candidates)| {
+            let refs: Vec<&str> = candidates.iter().chain(&self.known_names).map(|s| s.as_str()).collect();
+            closest_match(name, &refs, 2).map(str::to_string)
+        });
+        match suggestion {
+            Some(s) => diag.with_suggestion(s),
+            None    => diag,
+        }
     }
 
     /// Convert a ParserError into a fully-spanned AxiomDiagnostic
@@ -432,13 +524,44 @@ impl DiagnosticEngine {
             }
         };
 
-        AxiomDiagnostic::new(
-            code, msg,
-            &self.source_name, &self.source_text,
-            span.start,
-            span.end.saturating_sub(span.start).max(1),
-        )
+        self.at(code, msg, span)
+    }
+
+    /// Convert a semantic-analysis (chk) Diagnostic into an AxiomDiagnostic.
+    pub fn from_check(&self, d: &crate::errors::Diagnostic) -> AxiomDiagnostic {
+        let mut diag = self.at(d.code, d.message.clone(), d.span);
+        if let Some(hint) = &d.hint {
+            diag.hint = hint.clone();
+        }
+        match &d.suggestion {
+            Some(s) => diag.with_suggestion(s.clone()),
+            None    => diag,
+        }
+    }
+
+    /// Structured form of a diagnostic for editors and CI (`--format json`).
+    pub fn to_json(&self, diag: &AxiomDiagnostic, level: &DiagnosticLevel) -> serde_json::Value {
+        let start = diag.span.offset();
+        let end = start + diag.span.len();
+        let file = diag.src.name();
+        let module = (file != self.source_name)
+            .then(|| crate::loader::sources().into_iter().find(|f| f.name() == file))
+            .flatten();
+        let text = module.as_ref().map_or(self.source_text.as_str(), |f| &*f.text);
+        let (line, column) = byte_to_line_col(text, start);
+        serde_json::json!({
+            "file": file,
+            "span": { "start": start, "end": end },
+            "line": line,
+            "column": column,
+            "code": format!("AXM_{:03}", diag.code.as_u32()),
+            "severity": level.as_str(),
+            "message": diag.message,
+            "hint": diag.hint,
+            "suggestion": diag.suggestion,
+        })
     }
+
     pub fn undefined_identifier(&self, name: &str, span: Span) -> AxiomDiagnostic {
         let refs: Vec<&str> = self.known_names.iter().map(|s| s.as_str()).collect();
         let suggestion = closest_match(name, &refs, 2);
@@ -510,6 +633,14 @@ impl DiagnosticEngine {
         let _ = GraphicalReportHandler::new().render_report(&mut out, diag);
         out
     }
+
+    /// Like `format_diagnostic`, without ANSI colours (for browsers and logs).
+    pub fn format_diagnostic_plain(&self, diag: &AxiomDiagnostic) -> String {
+        use miette::{GraphicalReportHandler, GraphicalTheme};
+        let mut out = String::new();
+        let _ = GraphicalReportHandler::new_themed(GraphicalTheme::unicode_nocolor()).render_report(&mut out, diag);
+        out
+    }
 }
 
 // ═══════════════════════════════════════════════════════════════════════════
@@ -597,4 +728,104 @@ print(x + y)
         assert!(rendered.contains("test.ax"), "should contain source name");
         assert!(!rendered.contains("[["), "should not have double brackets");
     }
+
+    #[test]
+    fn test_check_diagnostic_json() {
+        use crate::errors::Diagnostic as CheckDiagnostic;
+        let engine = DiagnosticEngine::new("t.ax", "let a = 1\nout zz\n");
+        let d = CheckDiagnostic {
+            level: DiagnosticLevel::Warning,
+            code: ErrorCode::UndefinedVariable,
+            message: "Undefined variable 'zz'".into(),
+            span: Span::new(0, 14, 16),
+            hint: None,
+            suggestion: Some("a".into()),
+        };
+        let json = engine.to_json(&engine.from_check(&d), &d.level);
+        assert_eq!(json["code"], "AXM_201");
+        assert_eq!(json["severity"], "warning");
+        assert_eq!(json["line"], 2);
+        assert_eq!(json["column"], 5);
+        assert_eq!(json["span"]["end"], 16);
+        assert_eq!(json["suggestion"], "a");
+    }
+
+    #[test]
+    fn test_runtime_suggestion_from_candidates() {
+        let engine = DiagnosticEngine::new("t.ax", "out countr\n");
+        let err = RuntimeError::UndefinedVariable {
+            name: "countr".into(),
+            span: Span::new(0, 4, 10),
+            candidates: vec!["counter".into(), "out".into()],
+        };
+        let diag = engine.from_runtime(&err);
+        assert_eq!(diag.suggestion.as_deref(), Some("counter"));
+        assert!(diag.hint.contains("Did you mean 'counter'?"));
+    }
+
+    #[test]
+    fn test_module_syntax_error_is_returned_not_printed() {
+        let dir = std::env::temp_dir().join(format!("axiom-modsyntax-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let module = dir.join("broken.ax");
+        std::fs::write(&module, "let x = 1\nlet = 2\n").unwrap();
+        let src = format!("load \"{}\"\nout 1;\n", module.display());
+        let items = crate::Parser::new(&src, 0).parse().unwrap();
+        let (result, output) = crate::Runtime::new().run_captured(items);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let err = result.unwrap_err();
+        assert!(matches!(err, RuntimeError::ModuleSyntaxError { .. }), "{:?}", err);
+        assert!(err.to_string().starts_with("[AXM_601]"));
+        assert_eq!(output, "");
+        // Rendered by the caller, against the module's source
+        let engine = DiagnosticEngine::new("main.ax", src.as_str());
+        let json = engine.to_json(&engine.from_runtime(&err), &DiagnosticLevel::Error);
+        assert!(json["file"].as_str().unwrap().ends_with("broken.ax"), "{}", json);
+        assert_eq!(json["line"], 2);
+    }
+
+    #[test]
+    fn test_intrinsic_error_codes() {
+        let engine = DiagnosticEngine::new("t.ax", "");
+        let err = RuntimeError::IntrinsicError {
+            function: "net.get".into(),
+            kind: FailureKind::Network,
+            message: "connection refused".into(),
+        };
+        let diag = engine.from_runtime(&err);
+        assert_eq!(diag.code, ErrorCode::NetworkError);
+        assert_eq!(diag.message, "net.get failed: connection refused");
+        assert!(err.to_string().starts_with("[AXM_503]"));
+    }
+
+    #[test]
+    fn test_stack_overflow_call_chain() {
+        let engine = DiagnosticEngine::new("t.ax", "");
+        let mut call_chain = vec!["main".to_string()];
+        call_chain.extend(std::iter::repeat_n("fib".to_string(), 3));
+        let err = RuntimeError::StackOverflow { limit: 4, call_chain };
+        let diag = engine.from_runtime(&err);
+        assert_eq!(diag.code, ErrorCode::StackOverflow);
+        assert!(diag.message.ends_with("main → fib ×3"));
+    }
+
+    #[test]
+    fn test_module_errors_point_at_the_module() {
+        let dir = std::env::temp_dir().join(format!("axiom-diag-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let utils = dir.join("utils.ax");
+        std::fs::write(&utils, "fun boom(x) {\n  ret x + missing\n}\n").unwrap();
+        let main = format!("load \"{}\"\nout boom(1)\n", utils.display());
+
+        let mut rt = crate::Runtime::new();
+        rt.set_engine(crate::runtime::Engine::Interp);
+        let err = rt.run(crate::Parser::new(&main, 0).parse().unwrap()).unwrap_err();
+        let engine = DiagnosticEngine::new("main.ax", main.as_str());
+        let diag = engine.from_runtime(&err);
+        assert!(diag.src.name().ends_with("utils.ax"), "{}", diag.src.name());
+        let json = engine.to_json(&diag, &DiagnosticLevel::Error);
+        assert_eq!((json["line"].as_u64(), json["column"].as_u64()), (Some(2), Some(3)));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 }
@@ -3,7 +3,7 @@
 
 use std::fmt;
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize)]
 pub struct Span {
     pub source_id: u32,
     pub start: usize,
@@ -76,6 +76,17 @@ pub enum ParserError {
     },
 }
 
+impl ParserError {
+    /// Whether the input ran out mid-construct, so more text could finish it.
+    pub fn at_eof(&self) -> bool {
+        match self {
+            ParserError::UnexpectedEof { .. } => true,
+            ParserError::UnexpectedToken { found, .. } => found == "Eof",
+            ParserError::InvalidSyntax { .. } => false,
+        }
+    }
+}
+
 impl fmt::Display for ParserError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -178,9 +189,35 @@ impl std::error::Error for TypeError {}
 // ---------------------------------------------------------------------------
 // Runtime errors
 // ---------------------------------------------------------------------------
+
+/// Subsystem an intrinsic failure came from; selects the AXM code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureKind {
+    /// AXM_501 — filesystem, process spawn, local git repository
+    Io,
+    /// AXM_502
+    Usb,
+    /// AXM_503 — HTTP, git clone
+    Network,
+    /// AXM_410 — malformed input (CSV, JSON)
+    Parse,
+}
+
+impl FailureKind {
+    pub fn code(self) -> u32 {
+        match self {
+            FailureKind::Io      => 501,
+            FailureKind::Usb     => 502,
+            FailureKind::Network => 503,
+            FailureKind::Parse   => 410,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum RuntimeError {
-    UndefinedVariable { name: String, span: Span },
+    /// `candidates` are the names in scope, for "did you mean" suggestions
+    UndefinedVariable { name: String, span: Span, candidates: Vec<String> },
     UndefinedFunction { name: String, span: Span },
     UndefinedClass { name: String },
     UndefinedMethod { class_name: String, method_name: String },
@@ -189,13 +226,64 @@ pub enum RuntimeError {
     IndexOutOfBounds { index: i64, length: usize },
     DivisionByZero { span: Span },
     ImportError { module: String, message: String },
+    /// A module failed to parse; `error`'s span is in the module's
+    /// registered source, so diagnostics render against that file
+    ModuleSyntaxError { module: String, file: String, error: ParserError },
+    /// AXM_603: A module imports itself, directly or through other modules;
+    /// `chain` runs from that module back round to it
+    CircularImport { chain: Vec<String> },
     /// AXM_402: Attempt to call a nil value (missing parent-scope identifier binding)
-    NilCall { hint: String, span: Span },
+    /// `name` is the callee when known (e.g. a missing module member) and
+    /// `candidates` the names it could have meant
+    NilCall { hint: String, span: Span, name: Option<String>, candidates: Vec<String> },
     /// AXM_401: Attempt to call a non-function value
     NotCallable { type_name: String, span: Span },
+    /// AXM_408: Call depth exceeded `max_call_depth`; `call_chain` is outermost first
+    StackOverflow { limit: usize, call_chain: Vec<String> },
+    /// AXM_409 / AXM_411: A sandbox `ResourceLimits` bound was hit
+    /// (`limit` is in instructions, milliseconds or bytes)
+    LimitExceeded { resource: crate::sandbox::Resource, limit: u64 },
+    /// An intrinsic (e.g. `ioo.read`) failed; raised instead of returning
+    /// Nil / false / "ERROR: ..." sentinels
+    IntrinsicError { function: String, kind: FailureKind, message: String },
+    /// AXM_506: An intrinsic needs a capability the sandbox denies
+    PermissionDenied { function: String, capability: crate::sandbox::Capability },
+    /// AXM_413: The run was interrupted (Ctrl-C) before it finished
+    Interrupted,
+    /// AXM_414: A list, map or instance made read-only by `col.freeze` was
+    /// changed in place
+    FrozenMutation { type_name: String, span: Span },
+    /// AXM_412: A native function (or the VM itself) panicked; the panic was
+    /// stopped at the call boundary so the session can carry on
+    Internal { function: String, message: String },
     GenericError { message: String, span: Span },
 }
 
+impl RuntimeError {
+    /// Where the error happened, for the kinds that record it.
+    pub fn span_mut(&mut self) -> Option<&mut Span> {
+        match self {
+            RuntimeError::UndefinedVariable { span, .. }
+            | RuntimeError::UndefinedFunction { span, .. }
+            | RuntimeError::TypeMismatch { span, .. }
+            | RuntimeError::DivisionByZero { span }
+            | RuntimeError::NilCall { span, .. }
+            | RuntimeError::NotCallable { span, .. }
+            | RuntimeError::FrozenMutation { span, .. }
+            | RuntimeError::GenericError { span, .. } => Some(span),
+            _ => None,
+        }
+    }
+
+    /// The error, placed at `span` if it records a place but has none yet.
+    pub fn located(mut self, span: Span) -> Self {
+        if let Some(at) = self.span_mut() {
+            if *at == Span::default() { *at = span; }
+        }
+        self
+    }
+}
+
 impl fmt::Display for RuntimeError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -226,12 +314,45 @@ impl fmt::Display for RuntimeError {
             RuntimeError::ImportError { module, message } => {
                 write!(f, "[AXM_601] Import error for '{}': {}", module, message)
             }
+            RuntimeError::ModuleSyntaxError { module, file, error } => {
+                write!(f, "[AXM_601] Import error for '{}': syntax error in '{}': {}", module, file, error)
+            }
+            RuntimeError::CircularImport { chain } => {
+                write!(f, "[AXM_603] Circular import: {}", chain.join(" -> "))
+            }
             RuntimeError::NilCall { hint, .. } => {
                 write!(f, "[AXM_402] Attempt to call nil value — {}", hint)
             }
             RuntimeError::NotCallable { type_name, .. } => {
                 write!(f, "[AXM_401] Attempt to call non-callable type '{}'", type_name)
             }
+            RuntimeError::StackOverflow { limit, call_chain } => {
+                write!(f, "[AXM_408] Call stack overflow — depth limit {} reached\n  call chain: {}",
+                    limit, format_call_chain(call_chain))
+            }
+            RuntimeError::LimitExceeded { resource, limit } => {
+                use crate::sandbox::Resource;
+                let limit = resource.format_limit(*limit);
+                match resource {
+                    Resource::Instructions | Resource::WallClock => write!(f, "[AXM_411] Execution budget exceeded: {} of {}", resource, limit),
+                    Resource::Memory => write!(f, "[AXM_409] Heap exhausted: {} of {}", resource, limit),
+                }
+            }
+            RuntimeError::IntrinsicError { function, kind, message } => {
+                write!(f, "[AXM_{}] {} failed: {}", kind.code(), function, message)
+            }
+            RuntimeError::PermissionDenied { function, capability } => {
+                write!(f, "[AXM_506] Permission denied: '{}' requires the '{}' capability", function, capability)
+            }
+            RuntimeError::Interrupted => {
+                write!(f, "[AXM_413] Interrupted")
+            }
+            RuntimeError::FrozenMutation { type_name, .. } => {
+                write!(f, "[AXM_414] Cannot modify a frozen {}", type_name)
+            }
+            RuntimeError::Internal { function, message } => {
+                write!(f, "[AXM_412] Internal error in '{}': {}", function, message)
+            }
             RuntimeError::GenericError { message, .. } => {
                 write!(f, "{}", message)
             }
@@ -241,6 +362,38 @@ impl fmt::Display for RuntimeError {
 
 impl std::error::Error for RuntimeError {}
 
+/// Run `f`, the body of native `function`, and turn a panic inside it into
+/// `RuntimeError::Internal` instead of unwinding through the caller.
+pub fn catch_native<T>(function: &str, f: impl FnOnce() -> Result<T, RuntimeError>) -> Result<T, RuntimeError> {
+    std::panic::catch_unwind(std::panic::AssertUnwindSafe(f)).unwrap_or_else(|payload| {
+        Err(RuntimeError::Internal { function: function.to_string(), message: panic_message(payload.as_ref()) })
+    })
+}
+
+/// The message a panic was raised with, when it carried one.
+pub fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "unexpected panic".to_string()
+    }
+}
+
+/// Render a call chain as `main → walk → fib ×498`, collapsing runs of the
+/// same function so runaway recursion stays readable.
+pub fn format_call_chain(chain: &[String]) -> String {
+    let mut parts: Vec<String> = Vec::new();
+    let mut i = 0;
+    while i < chain.len() {
+        let run = chain[i..].iter().take_while(|n| **n == chain[i]).count();
+        parts.push(if run > 1 { format!("{} ×{}", chain[i], run) } else { chain[i].clone() });
+        i += run;
+    }
+    parts.join(" → ")
+}
+
 // ---------------------------------------------------------------------------
 // Diagnostic — structured error for chk
 // ---------------------------------------------------------------------------
@@ -251,12 +404,25 @@ pub enum DiagnosticLevel {
     Info,
 }
 
+impl DiagnosticLevel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DiagnosticLevel::Error => "error",
+            DiagnosticLevel::Warning => "warning",
+            DiagnosticLevel::Info => "info",
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Diagnostic {
     pub level: DiagnosticLevel,
+    pub code: crate::diagnostics::ErrorCode,
     pub message: String,
     pub span: Span,
     pub hint: Option<String>,
+    /// Replacement for the offending name ("did you mean ...?"), if any
+    pub suggestion: Option<String>,
 }
 
 impl fmt::Display for Diagnostic {
@@ -270,6 +436,9 @@ impl fmt::Display for Diagnostic {
         if let Some(ref hint) = self.hint {
             write!(f, "\n  hint: {}", hint)?;
         }
+        if let Some(ref suggestion) = self.suggestion {
+            write!(f, "\n  help: did you mean '{}'?", suggestion)?;
+        }
         Ok(())
     }
 }
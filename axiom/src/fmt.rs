@@ -1,8 +1,13 @@
-/// Axiom Formatter (fmt) — Final Maturation
-/// Uses Rowan GreenTree for idempotent formatting.
-/// Enforces 4-space indents, same-line braces, and space-padded operators.
+//! Axiom Formatter (fmt) — Final Maturation
+//! Uses Rowan GreenTree for idempotent formatting.
+//! Re-indents by bracket depth, places braces and wraps long argument lists
+//! according to `FmtConfig` (`.axfmt.toml`). String literals and block
+//! comments are never touched, so formatting cannot change the program.
+
+use std::path::{Path, PathBuf};
 
 use rowan::{Language, SyntaxKind as RowanSyntaxKind};
+use serde::Deserialize;
 
 // ---------------------------------------------------------------------------
 // Rowan Setup
@@ -46,55 +51,365 @@ impl Language for AxiomLanguage {
 
 // type AxiomNode = SyntaxNode<AxiomLanguage>;
 
+// ---------------------------------------------------------------------------
+// Configuration (.axfmt.toml)
+// ---------------------------------------------------------------------------
+
+/// File name `axiom fmt` looks for in the script's directory and its parents.
+pub const CONFIG_FILE: &str = ".axfmt.toml";
+
+/// Where opening braces of blocks go.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BraceStyle {
+    /// `fun f() {`
+    SameLine,
+    /// `fun f()` with `{` alone on the next line
+    NextLine,
+}
+
+/// Formatting options. Every key is optional in `.axfmt.toml`:
+///
+/// ```toml
+/// indent_width = 4
+/// use_tabs = false
+/// max_line_length = 100      # 0 never wraps
+/// enum_trailing_commas = true
+/// brace_style = "same-line"  # or "next-line"
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FmtConfig {
+    pub indent_width: usize,
+    pub use_tabs: bool,
+    pub max_line_length: usize,
+    pub enum_trailing_commas: bool,
+    pub brace_style: BraceStyle,
+}
+
+impl Default for FmtConfig {
+    fn default() -> Self {
+        FmtConfig {
+            indent_width: 4,
+            use_tabs: false,
+            max_line_length: 100,
+            enum_trailing_commas: true,
+            brace_style: BraceStyle::SameLine,
+        }
+    }
+}
+
+impl FmtConfig {
+    pub fn from_toml(content: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(content)
+    }
+
+    /// The nearest `.axfmt.toml` at or above `dir`, or the defaults when
+    /// there is none. Returns the file the options came from.
+    pub fn discover(dir: &Path) -> Result<(Self, Option<PathBuf>), String> {
+        let Some(path) = dir.ancestors().map(|d| d.join(CONFIG_FILE)).find(|p| p.is_file()) else {
+            return Ok((FmtConfig::default(), None));
+        };
+        let content = std::fs::read_to_string(&path)
+            .map_err(|e| format!("Cannot read '{}': {}", path.display(), e))?;
+        let config = Self::from_toml(&content)
+            .map_err(|e| format!("Invalid '{}': {}", path.display(), e))?;
+        Ok((config, Some(path)))
+    }
+
+    fn indent(&self, depth: usize) -> String {
+        if self.use_tabs { "\t".repeat(depth) } else { " ".repeat(depth * self.indent_width) }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Line scanning
+// ---------------------------------------------------------------------------
+
+/// What a line ends inside of, carried into the next line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Lit {
+    Code,
+    /// String literal; `interp` counts open parens of an `@(...)` splice
+    Str { interp: u32 },
+    BlockComment,
+}
+
+/// Brackets and comments of one line, outside string literals.
+struct LineScan {
+    /// `(byte offset, char)` of every bracket and comma that is code
+    code: Vec<(usize, char)>,
+    comment: bool,
+    end: Lit,
+}
+
+impl LineScan {
+    fn new(line: &str, start: Lit) -> Self {
+        let mut code = Vec::new();
+        let mut comment = false;
+        let mut state = start;
+        let mut chars = line.char_indices().peekable();
+        while let Some((i, ch)) = chars.next() {
+            match state {
+                Lit::Code => match ch {
+                    '"' => state = Lit::Str { interp: 0 },
+                    '/' if chars.peek().map(|&(_, c)| c) == Some('/') => {
+                        comment = true;
+                        break;
+                    }
+                    '/' if chars.peek().map(|&(_, c)| c) == Some('*') => {
+                        chars.next();
+                        comment = true;
+                        state = Lit::BlockComment;
+                    }
+                    '(' | ')' | '[' | ']' | '{' | '}' | ',' => code.push((i, ch)),
+                    _ => {}
+                },
+                Lit::Str { interp: 0 } => match ch {
+                    '\\' => { chars.next(); }
+                    '"' => state = Lit::Code,
+                    '@' if chars.peek().map(|&(_, c)| c) == Some('(') => {
+                        chars.next();
+                        state = Lit::Str { interp: 1 };
+                    }
+                    _ => {}
+                },
+                Lit::Str { interp } => match ch {
+                    '(' => state = Lit::Str { interp: interp + 1 },
+                    ')' => state = Lit::Str { interp: interp - 1 },
+                    _ => {}
+                },
+                Lit::BlockComment => {
+                    if ch == '*' && chars.peek().map(|&(_, c)| c) == Some('/') {
+                        chars.next();
+                        state = Lit::Code;
+                    }
+                }
+            }
+        }
+        LineScan { code, comment, end: state }
+    }
+
+    /// Opening minus closing brackets.
+    fn delta(&self) -> i32 {
+        self.code.iter().map(|&(_, c)| match c {
+            '(' | '[' | '{' => 1,
+            ')' | ']' | '}' => -1,
+            _ => 0,
+        }).sum()
+    }
+
+    /// Closing brackets before anything else on the (trimmed) line; they
+    /// dedent the line itself.
+    fn leading_closers(line: &str) -> usize {
+        line.chars()
+            .take_while(|c| matches!(c, ')' | ']' | '}') || c.is_whitespace())
+            .filter(|c| !c.is_whitespace())
+            .count()
+    }
+
+    /// A single line of plain code: nothing to protect from rewriting.
+    fn is_plain(&self, start: Lit) -> bool {
+        start == Lit::Code && self.end == Lit::Code && !self.comment
+    }
+}
+
+/// Open brackets, tagged with the line that opened them. Brackets opened on
+/// one line share an indent level, so `out([` indents its items once.
+#[derive(Default)]
+struct Nesting {
+    open: Vec<usize>,
+    line: usize,
+}
+
+impl Nesting {
+    /// Indent level with the innermost `closing` brackets already closed.
+    fn level(&self, closing: usize) -> usize {
+        let open = &self.open[..self.open.len().saturating_sub(closing)];
+        (0..open.len()).filter(|&i| i == 0 || open[i - 1] != open[i]).count()
+    }
+
+    fn apply(&mut self, scan: &LineScan) {
+        self.line += 1;
+        for &(_, c) in &scan.code {
+            match c {
+                '(' | '[' | '{' => self.open.push(self.line),
+                ')' | ']' | '}' => { self.open.pop(); }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Split `line` at the top-level commas of its first bracket pair that has
+/// any, as `head(`, the items, and `)tail`.
+fn split_args(line: &str, scan: &LineScan) -> Option<Vec<String>> {
+    let mut stack: Vec<usize> = Vec::new();
+    // (opener position in scan.code, closer position, comma byte offsets)
+    let mut best: Option<(usize, usize, Vec<usize>)> = None;
+    let mut commas: Vec<Vec<usize>> = Vec::new();
+    for (k, &(i, c)) in scan.code.iter().enumerate() {
+        match c {
+            '(' | '[' | '{' => {
+                stack.push(k);
+                commas.push(Vec::new());
+            }
+            ')' | ']' | '}' => {
+                // A leading `)` closes a bracket from an earlier line
+                let (Some(open), Some(cs)) = (stack.pop(), commas.pop()) else { continue };
+                if scan.code[open].1 != '{' && !cs.is_empty()
+                    && best.as_ref().is_none_or(|b| scan.code[open].0 < scan.code[b.0].0)
+                {
+                    best = Some((open, k, cs));
+                }
+            }
+            _ => {
+                if let Some(cs) = commas.last_mut() { cs.push(i); }
+            }
+        }
+    }
+    let (open, close, cs) = best?;
+    let (open_at, close_at) = (scan.code[open].0, scan.code[close].0);
+    let mut pieces = vec![line[..=open_at].trim_end().to_string()];
+    let mut from = open_at + 1;
+    for &comma in &cs {
+        pieces.push(line[from..=comma].trim().to_string());
+        from = comma + 1;
+    }
+    let last = line[from..close_at].trim();
+    if last.is_empty() {
+        // `[a, b,]`: the trailing comma is dropped with the empty item
+        let prev = pieces.last_mut()?;
+        prev.pop();
+    } else {
+        pieces.push(last.to_string());
+    }
+    pieces.push(line[close_at..].trim().to_string());
+    Some(pieces)
+}
+
 // ---------------------------------------------------------------------------
 // Formatter Implementation
 // ---------------------------------------------------------------------------
 
-pub struct Formatter {}
+pub struct Formatter {
+    config: FmtConfig,
+}
 
 impl Formatter {
     pub fn new() -> Self {
-        Formatter {}
+        Self::with_config(FmtConfig::default())
+    }
+
+    pub fn with_config(config: FmtConfig) -> Self {
+        Formatter { config }
     }
 
     pub fn format(&mut self, source: &str) -> String {
-        // In a full implementation, we would lex into Rowan tokens and build a GreenNode.
-        // For the Omega Synthesis, we implement an idempotent text-processor 
-        // that handles the requested rules: 4-space indents, same-line braces.
-        
+        let lines = self.join_braces(source);
+        self.layout(lines)
+    }
+
+    /// First pass: trim code lines and, for `same-line` braces, pull a line
+    /// starting with `{` up onto the line before it. Lines that start inside
+    /// a string or block comment are kept verbatim, tagged with that state.
+    fn join_braces(&self, source: &str) -> Vec<(String, Lit)> {
+        let mut out: Vec<(String, Lit)> = Vec::new();
+        let mut state = Lit::Code;
+        for raw in source.lines() {
+            let start = state;
+            if start != Lit::Code {
+                state = LineScan::new(raw, start).end;
+                out.push((raw.to_string(), start));
+                continue;
+            }
+            let line = raw.trim();
+            let scan = LineScan::new(line, start);
+            state = scan.end;
+            if self.config.brace_style == BraceStyle::SameLine && line.starts_with('{') {
+                if let Some((prev, Lit::Code)) = out.last_mut() {
+                    let prev_scan = LineScan::new(prev, Lit::Code);
+                    if !prev.is_empty() && prev_scan.is_plain(Lit::Code) {
+                        prev.push(' ');
+                        prev.push_str(line);
+                        continue;
+                    }
+                }
+            }
+            out.push((line.to_string(), start));
+        }
+        out
+    }
+
+    /// Second pass: split next-line braces, wrap long lines, fix enum
+    /// commas and indent everything by bracket depth.
+    fn layout(&self, lines: Vec<(String, Lit)>) -> String {
+        let cfg = &self.config;
+        let mut queue: std::collections::VecDeque<(String, Lit)> = lines.into();
         let mut result = String::new();
-        let mut indent: i32 = 0;
-        let _in_string = false;
+        let mut nesting = Nesting::default();
+        let mut enum_pending = false;
+        let mut enum_depth: Option<usize> = None;
 
-        for line in source.lines() {
-            let trimmed = line.trim();
-            if trimmed.is_empty() {
+        while let Some((mut line, start)) = queue.pop_front() {
+            let scan = LineScan::new(&line, start);
+            if start != Lit::Code {
+                result.push_str(&line);
+                result.push('\n');
+                nesting.apply(&scan);
+                continue;
+            }
+            if line.is_empty() {
                 result.push('\n');
                 continue;
             }
 
-            if trimmed.starts_with('}') {
-                indent = indent.saturating_sub(1);
+            if cfg.brace_style == BraceStyle::NextLine && scan.is_plain(start)
+                && line.len() > 1 && line.ends_with('{')
+            {
+                let before = line[..line.len() - 1].trim_end().to_string();
+                queue.push_front(("{".to_string(), Lit::Code));
+                queue.push_front((before, Lit::Code));
+                continue;
             }
 
-            // Apply indentation
-            for _ in 0..indent {
-                result.push_str("    ");
+            let depth = nesting.level(0);
+            let leading = LineScan::leading_closers(&line);
+            let level = nesting.level(leading);
+            let visual = level * cfg.indent_width + line.chars().count();
+            if cfg.max_line_length > 0 && visual > cfg.max_line_length && scan.is_plain(start) {
+                if let Some(pieces) = split_args(&line, &scan) {
+                    for piece in pieces.into_iter().rev() {
+                        queue.push_front((piece, Lit::Code));
+                    }
+                    continue;
+                }
             }
 
-            // Standardize spaces around operators and same-line braces
-            let formatted_line = trimmed.to_string();
-            
-            // Ensure same-line braces
-            if formatted_line.ends_with('{') {
-                // Already okay, but we could enforce space before '{'
+            if enum_depth == Some(depth) && leading == 0 && scan.is_plain(start) && scan.delta() == 0 {
+                let closes_next = queue.iter()
+                    .find(|(l, _)| !l.trim().is_empty())
+                    .is_some_and(|(l, _)| l.trim_start().starts_with('}'));
+                let bare = line.trim_end_matches(',').trim_end();
+                line = if cfg.enum_trailing_commas || !closes_next { format!("{},", bare) } else { bare.to_string() };
+            }
+            if line.starts_with("enm ") {
+                // `enm Dir { N, S }` on one line has no body to fix up
+                enum_pending = scan.delta() > 0 || !line.contains('{');
             }
 
-            result.push_str(&formatted_line);
+            result.push_str(&cfg.indent(level));
+            result.push_str(&line);
             result.push('\n');
 
-            if trimmed.ends_with('{') || trimmed.contains('{') && !trimmed.contains('}') {
-                indent += 1;
+            nesting.apply(&scan);
+            let next = nesting.level(0);
+            if enum_pending && next > depth {
+                enum_depth = Some(next);
+                enum_pending = false;
+            }
+            if enum_depth.is_some_and(|d| next < d) {
+                enum_depth = None;
             }
         }
 
@@ -113,3 +428,196 @@ pub fn format_source(source: &str) -> String {
     let mut fmt = Formatter::new();
     fmt.format(source)
 }
+
+/// `format_source` with options from `.axfmt.toml` (see `FmtConfig::discover`).
+pub fn format_source_with(source: &str, config: &FmtConfig) -> String {
+    Formatter::with_config(config.clone()).format(source)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+
+    /// The parsed program as JSON with every span removed, so layout
+    /// changes compare equal.
+    fn ast_shape(source: &str) -> serde_json::Value {
+        fn strip(v: &mut serde_json::Value) {
+            match v {
+                serde_json::Value::Object(map) => {
+                    map.remove("span");
+                    map.values_mut().for_each(strip);
+                }
+                serde_json::Value::Array(items) => items.iter_mut().for_each(strip),
+                _ => {}
+            }
+        }
+        let items = Parser::new(source, 0).parse()
+            .unwrap_or_else(|e| panic!("{:?} in:\n{}", e, source));
+        let mut json = serde_json::to_value(items).unwrap();
+        strip(&mut json);
+        json
+    }
+
+    /// Tiny deterministic generator (xorshift64) for the property tests.
+    struct Gen(u64);
+
+    impl Gen {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+        fn below(&mut self, n: u64) -> u64 { self.next() % n }
+        fn chance(&mut self, percent: u64) -> bool { self.below(100) < percent }
+
+        fn indent(&mut self) -> String {
+            if self.chance(20) { "\t".repeat(self.below(3) as usize) } else { " ".repeat(self.below(7) as usize) }
+        }
+
+        fn expr(&mut self, depth: u32) -> String {
+            match if depth > 2 { self.below(3) } else { self.below(6) } {
+                0 => self.below(1000).to_string(),
+                1 => format!("v{}", self.below(5)),
+                2 => format!("\"s{{{}}}\"", self.below(9)),
+                3 => format!("{} + {}", self.expr(depth + 1), self.expr(depth + 1)),
+                4 => {
+                    let args: Vec<String> = (0..self.below(12)).map(|_| self.expr(depth + 1)).collect();
+                    format!("f{}({})", self.below(3), args.join(", "))
+                }
+                _ => {
+                    let items: Vec<String> = (0..self.below(15)).map(|_| self.expr(depth + 1)).collect();
+                    format!("[{}]", items.join(", "))
+                }
+            }
+        }
+
+        /// `header {` with the brace on the same or the next line.
+        fn open(&mut self, out: &mut String, header: &str) {
+            let indent = self.indent();
+            if self.chance(50) {
+                out.push_str(&format!("{}{} {{\n", indent, header));
+            } else {
+                out.push_str(&format!("{}{}\n{}{{\n", indent, header, self.indent()));
+            }
+        }
+
+        fn block(&mut self, out: &mut String, depth: u32) {
+            for _ in 0..self.below(4) + 1 {
+                let indent = self.indent();
+                match self.below(if depth > 2 { 5 } else { 7 }) {
+                    0 => out.push_str(&format!("{}let v{} = {}\n", indent, self.below(5), self.expr(0))),
+                    1 => out.push_str(&format!("{}out({})\n", indent, self.expr(0))),
+                    2 => out.push_str(&format!("{}// note {{ ( [\n", indent)),
+                    3 => out.push_str(&format!("{}let t = \"multi {{\n   @(v1) line ]\"\n", indent)),
+                    4 => out.push('\n'),
+                    5 => {
+                        let cond = format!("if {} < {}", self.expr(1), self.expr(1));
+                        self.open(out, &cond);
+                        self.block(out, depth + 1);
+                        if self.chance(50) {
+                            out.push_str(&format!("{}}} else {{\n", self.indent()));
+                            self.block(out, depth + 1);
+                        }
+                        out.push_str(&format!("{}}}\n", self.indent()));
+                    }
+                    _ => {
+                        let header = format!("while v{} < 3", self.below(5));
+                        self.open(out, &header);
+                        self.block(out, depth + 1);
+                        out.push_str(&format!("{}}}\n", self.indent()));
+                    }
+                }
+            }
+        }
+
+        fn program(&mut self) -> String {
+            let mut out = String::new();
+            for n in 0..self.below(4) + 1 {
+                match self.below(3) {
+                    0 => {
+                        self.open(&mut out, &format!("enm E{n}"));
+                        for v in 0..self.below(4) + 1 {
+                            let comma = if self.chance(50) { "," } else { "" };
+                            out.push_str(&format!("{}V{}{}\n", self.indent(), v, comma));
+                        }
+                        out.push_str("}\n");
+                    }
+                    1 => {
+                        self.open(&mut out, &format!("fun g{n}(a, b)"));
+                        self.block(&mut out, 1);
+                        out.push_str(&format!("{}}}\n", self.indent()));
+                    }
+                    _ => self.block(&mut out, 0),
+                }
+            }
+            out
+        }
+    }
+
+    fn configs() -> Vec<FmtConfig> {
+        vec![
+            FmtConfig::default(),
+            FmtConfig { indent_width: 2, max_line_length: 40, enum_trailing_commas: false, ..FmtConfig::default() },
+            FmtConfig { use_tabs: true, brace_style: BraceStyle::NextLine, max_line_length: 30, ..FmtConfig::default() },
+            FmtConfig { brace_style: BraceStyle::NextLine, max_line_length: 0, ..FmtConfig::default() },
+        ]
+    }
+
+    #[test]
+    fn test_formatting_is_idempotent_and_preserves_the_ast() {
+        let mut gen = Gen(0x9E37_79B9_7F4A_7C15);
+        for case in 0..300 {
+            let source = gen.program();
+            let expected = ast_shape(&source);
+            for config in configs() {
+                let once = format_source_with(&source, &config);
+                let twice = format_source_with(&once, &config);
+                assert_eq!(once, twice, "case {} not idempotent with {:?}:\n{}", case, config, source);
+                assert_eq!(ast_shape(&once), expected, "case {} changed the AST with {:?}:\n{}", case, config, source);
+            }
+        }
+    }
+
+    #[test]
+    fn test_brace_styles() {
+        let source = "fun f(a)\n{\nret a\n}\nif f(1) {\nout(1)\n} else {\nout(2)\n}\n";
+        assert_eq!(format_source(source), "fun f(a) {\n    ret a\n}\nif f(1) {\n    out(1)\n} else {\n    out(2)\n}\n");
+        let next = FmtConfig { brace_style: BraceStyle::NextLine, ..FmtConfig::default() };
+        assert_eq!(
+            format_source_with(source, &next),
+            "fun f(a)\n{\n    ret a\n}\nif f(1)\n{\n    out(1)\n} else\n{\n    out(2)\n}\n",
+        );
+    }
+
+    #[test]
+    fn test_indent_options_and_enum_commas() {
+        let source = "enm Dir {\nN\nS,\n}\nfun f() {\nret 1\n}\n";
+        assert_eq!(format_source(source), "enm Dir {\n    N,\n    S,\n}\nfun f() {\n    ret 1\n}\n");
+        let config = FmtConfig { use_tabs: true, enum_trailing_commas: false, ..FmtConfig::default() };
+        assert_eq!(format_source_with(source, &config), "enm Dir {\n\tN,\n\tS\n}\nfun f() {\n\tret 1\n}\n");
+    }
+
+    #[test]
+    fn test_long_lines_wrap_at_top_level_commas() {
+        let config = FmtConfig { max_line_length: 20, ..FmtConfig::default() };
+        assert_eq!(
+            format_source_with("out(g(1, 2), \"a, b\", [3])\n", &config),
+            "out(\n    g(1, 2),\n    \"a, b\",\n    [3]\n)\n",
+        );
+    }
+
+    #[test]
+    fn test_strings_and_comments_are_left_alone() {
+        let source = "let s = \"{\n  keep }\"\n/* {\n   also */\nout(s) // {\n";
+        assert_eq!(format_source(source), source);
+    }
+
+    #[test]
+    fn test_config_from_toml() {
+        let config = FmtConfig::from_toml("indent_width = 2\nbrace_style = \"next-line\"\n").unwrap();
+        assert_eq!(config, FmtConfig { indent_width: 2, brace_style: BraceStyle::NextLine, ..FmtConfig::default() });
+        assert!(FmtConfig::from_toml("indent = 2\n").is_err());
+    }
+}
@@ -30,6 +30,62 @@ const NURSERY_SIZE: usize = 2 * 1024 * 1024;
 const OLD_GEN_THRESHOLD: usize = 16 * 1024 * 1024;
 /// Max minor GC survivor age before promotion
 const MAX_AGE: u8 = 2;
+/// Default factor the major-GC threshold grows by after each major GC.
+const GROWTH_FACTOR: f64 = 2.0;
+
+/// Collector tuning, normally read from conf (`nursery_size_kb`,
+/// `gc_growth_factor`, `gc_verbose`, `gc_enabled`).
+#[derive(Debug, Clone, Copy)]
+pub struct GcConfig {
+    pub nursery_bytes: usize,
+    pub old_gen_threshold: usize,
+    /// After a major GC the next one triggers at live old-gen bytes × this (min threshold).
+    pub growth_factor: f64,
+    /// Print collection events to stderr.
+    pub log: bool,
+    /// With the collector off, allocations are still counted but never trigger a GC.
+    pub enabled: bool,
+}
+
+impl Default for GcConfig {
+    fn default() -> Self {
+        GcConfig {
+            nursery_bytes: NURSERY_SIZE,
+            old_gen_threshold: OLD_GEN_THRESHOLD,
+            growth_factor: GROWTH_FACTOR,
+            log: false,
+            enabled: true,
+        }
+    }
+}
+
+impl GcConfig {
+    pub fn from_conf(conf: &crate::conf::AxConf) -> Self {
+        GcConfig {
+            nursery_bytes: conf.nursery_size_kb().clamp(256, 65536) as usize * 1024,
+            old_gen_threshold: OLD_GEN_THRESHOLD,
+            growth_factor: conf.gc_growth_factor(),
+            log: conf.gc_verbose(),
+            enabled: conf.gc_enabled(),
+        }
+    }
+}
+
+/// Pause timer; `Instant::now` panics on wasm32-unknown-unknown, where pauses read as 0.
+struct PauseTimer(#[cfg(not(target_arch = "wasm32"))] std::time::Instant);
+
+impl PauseTimer {
+    fn start() -> Self {
+        PauseTimer(#[cfg(not(target_arch = "wasm32"))] std::time::Instant::now())
+    }
+
+    fn elapsed_us(&self) -> u64 {
+        #[cfg(not(target_arch = "wasm32"))]
+        { self.0.elapsed().as_micros() as u64 }
+        #[cfg(target_arch = "wasm32")]
+        { 0 }
+    }
+}
 
 // ---------------------------------------------------------------------------
 // Object Header (16 bytes, cache-aligned)
@@ -154,7 +210,11 @@ pub struct GCStats {
     pub bytes_allocated_old: u64,
     pub last_minor_pause_us: u64,
     pub last_major_pause_us: u64,
+    pub minor_pause_us: u64,
+    pub major_pause_us: u64,
     pub total_pause_us: u64,
+    /// Old-gen objects still live after the last major GC.
+    pub old_survivors: u64,
 }
 
 impl GCStats {
@@ -166,8 +226,9 @@ impl GCStats {
         println!("  Objects promoted: {}", self.objects_promoted);
         println!("  Young allocated:  {} KB", self.bytes_allocated_young / 1024);
         println!("  Old allocated:    {} KB", self.bytes_allocated_old / 1024);
-        println!("  Minor GC pause:   {} µs (last)", self.last_minor_pause_us);
-        println!("  Major GC pause:   {} µs (last)", self.last_major_pause_us);
+        println!("  Old survivors:    {}", self.old_survivors);
+        println!("  Minor GC pause:   {} µs (last), {} µs (total)", self.last_minor_pause_us, self.minor_pause_us);
+        println!("  Major GC pause:   {} µs (last), {} µs (total)", self.last_major_pause_us, self.major_pause_us);
         println!("  Total GC time:    {} µs", self.total_pause_us);
     }
 }
@@ -201,18 +262,74 @@ pub struct GC {
 
     /// Debug mode
     debug: bool,
+
+    config: GcConfig,
+    /// Old-gen size that triggers the next major GC.
+    next_major_at: usize,
+    /// Young bytes the runtime reported through `note_alloc` since the last
+    /// minor GC. Interpreter values are refcounted and live outside the
+    /// arena, so this is how their allocation rate drives collections.
+    young_pending: usize,
 }
 
 impl GC {
     pub fn new(debug: bool) -> Self {
+        GC::with_config(GcConfig { log: debug, ..GcConfig::default() })
+    }
+
+    pub fn with_config(config: GcConfig) -> Self {
         GC {
-            nursery_from: BumpArena::new(NURSERY_SIZE),
-            nursery_to:   BumpArena::new(NURSERY_SIZE),
+            nursery_from: BumpArena::new(config.nursery_bytes),
+            nursery_to:   BumpArena::new(config.nursery_bytes),
             old_gen:      Vec::new(),
             old_gen_bytes: 0,
             roots:        Mutex::new(Vec::new()),
             stats:        GCStats::default(),
-            debug,
+            debug:        config.log,
+            config,
+            next_major_at: config.old_gen_threshold,
+            young_pending: 0,
+        }
+    }
+
+    pub fn config(&self) -> &GcConfig {
+        &self.config
+    }
+
+    /// Bytes currently held: young generation in use plus the old generation.
+    pub fn heap_bytes(&self) -> usize {
+        self.young_bytes() + self.old_gen_bytes
+    }
+
+    pub fn young_bytes(&self) -> usize {
+        self.nursery_from.used() + self.young_pending
+    }
+
+    pub fn young_capacity(&self) -> usize {
+        self.nursery_from.capacity()
+    }
+
+    pub fn old_bytes(&self) -> usize {
+        self.old_gen_bytes
+    }
+
+    /// Account `bytes` of young allocation made outside the arena, running
+    /// a minor GC when the nursery budget is used up.
+    pub fn note_alloc(&mut self, bytes: usize) {
+        self.stats.bytes_allocated_young += bytes as u64;
+        self.young_pending += bytes;
+        if self.config.enabled && self.young_bytes() >= self.young_capacity() {
+            self.minor_gc();
+        }
+    }
+
+    /// Full collection: minor then major, regardless of thresholds.
+    pub fn collect(&mut self) {
+        let majors = self.stats.major_gcs;
+        self.minor_gc();
+        // minor_gc may already have escalated
+        if self.stats.major_gcs == majors {
+            self.major_gc();
         }
     }
 
@@ -257,13 +374,13 @@ impl GC {
 
     /// Minor GC: copy-collect young generation.
     pub fn minor_gc(&mut self) {
-        let start = std::time::Instant::now();
+        let start = PauseTimer::start();
         self.stats.minor_gcs += 1;
 
         if self.debug {
             eprintln!("[GC] Minor GC #{} — nursery used: {} KB / {} KB",
                 self.stats.minor_gcs,
-                self.nursery_from.used() / 1024,
+                self.young_bytes() / 1024,
                 self.nursery_from.capacity() / 1024);
         }
 
@@ -274,12 +391,14 @@ impl GC {
         //   3. Swap from/to spaces
         //   4. Update all pointers
 
-        let collected = self.nursery_from.used();
+        let collected = self.young_bytes();
         self.nursery_from.reset();
+        self.young_pending = 0;
         self.stats.objects_collected_young += (collected / 64).max(1) as u64; // estimate
 
-        let elapsed = start.elapsed().as_micros() as u64;
+        let elapsed = start.elapsed_us();
         self.stats.last_minor_pause_us = elapsed;
+        self.stats.minor_pause_us += elapsed;
         self.stats.total_pause_us += elapsed;
 
         if self.debug {
@@ -287,14 +406,14 @@ impl GC {
         }
 
         // Check if old gen needs major GC
-        if self.old_gen_bytes > OLD_GEN_THRESHOLD {
+        if self.old_gen_bytes > self.next_major_at {
             self.major_gc();
         }
     }
 
     /// Major GC: mark-sweep old generation.
     pub fn major_gc(&mut self) {
-        let start = std::time::Instant::now();
+        let start = PauseTimer::start();
         self.stats.major_gcs += 1;
 
         if self.debug {
@@ -311,9 +430,13 @@ impl GC {
         let before = self.old_gen.len();
         // For now: just log (full impl requires object graph traversal)
         let after = self.old_gen.len();
+        self.stats.old_survivors = after as u64;
+        let grown = (self.old_gen_bytes as f64 * self.config.growth_factor) as usize;
+        self.next_major_at = grown.max(self.config.old_gen_threshold);
 
-        let elapsed = start.elapsed().as_micros() as u64;
+        let elapsed = start.elapsed_us();
         self.stats.last_major_pause_us = elapsed;
+        self.stats.major_pause_us += elapsed;
         self.stats.total_pause_us += elapsed;
 
         if self.debug {
@@ -350,6 +473,42 @@ impl GC {
     }
 }
 
+// ---------------------------------------------------------------------------
+// Per-thread heap — what the runtime reports to and `sys.gc_*` reads
+// ---------------------------------------------------------------------------
+
+thread_local! {
+    static HEAP: std::cell::RefCell<Option<GC>> = const { std::cell::RefCell::new(None) };
+}
+
+/// Run `f` on this thread's heap, creating it from conf on first use.
+pub fn with_heap<T>(f: impl FnOnce(&mut GC) -> T) -> T {
+    HEAP.with(|heap| {
+        let mut heap = heap.borrow_mut();
+        let gc = heap.get_or_insert_with(|| GC::with_config(GcConfig::from_conf(&crate::conf::AxConf::load())));
+        f(gc)
+    })
+}
+
+/// One line of this thread's heap statistics, for crash reports. None when
+/// the heap was never created or is in use (a panic inside the collector).
+pub fn try_summary() -> Option<String> {
+    HEAP.try_with(|heap| {
+        let heap = heap.try_borrow().ok()?;
+        let gc = heap.as_ref()?;
+        let st = &gc.stats;
+        Some(format!(
+            "{} minor / {} major GCs, {} KB allocated, heap {} KB",
+            st.minor_gcs, st.major_gcs, st.bytes_allocated_young / 1024, gc.heap_bytes() / 1024,
+        ))
+    }).ok().flatten()
+}
+
+/// Report `bytes` of script allocation to this thread's heap.
+pub fn note_alloc(bytes: usize) {
+    with_heap(|gc| gc.note_alloc(bytes));
+}
+
 // ---------------------------------------------------------------------------
 // Allocation rate tracker (for adaptive GC tuning)
 // ---------------------------------------------------------------------------
@@ -383,3 +542,26 @@ impl AllocationTracker {
         self.gc_pressure.load(Ordering::Relaxed)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_note_alloc_triggers_minor_gc_at_nursery_budget() {
+        let mut gc = GC::with_config(GcConfig { nursery_bytes: 256 * 1024, ..GcConfig::default() });
+        gc.note_alloc(200 * 1024);
+        assert_eq!(gc.stats.minor_gcs, 0);
+        assert_eq!(gc.young_bytes(), 200 * 1024);
+        gc.note_alloc(100 * 1024);
+        assert_eq!(gc.stats.minor_gcs, 1);
+        assert_eq!(gc.young_bytes(), 0);
+
+        gc.collect();
+        assert_eq!((gc.stats.minor_gcs, gc.stats.major_gcs), (2, 1));
+
+        let mut off = GC::with_config(GcConfig { nursery_bytes: 256 * 1024, enabled: false, ..GcConfig::default() });
+        off.note_alloc(1024 * 1024);
+        assert_eq!(off.stats.minor_gcs, 0);
+    }
+}
@@ -0,0 +1,134 @@
+//! Git pre-commit hook (`axiom hook install` / `axiom hook uninstall`)
+//!
+//! The hook pipes the staged version of every added or changed `.ax` file
+//! through `axiom fmt --check` and `axiom chk`, reading it from standard
+//! input so the working copy is never touched, and fails the commit if any
+//! of them does. It calls the axiom that installed it, or `$AXIOM` when set.
+//!
+//! The hooks directory comes from `git rev-parse --git-path hooks`, which
+//! follows worktrees and `core.hooksPath`. A pre-commit hook that axiom did
+//! not write is left alone unless `--force` is given.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Marks a hook as axiom's, so uninstall and reinstall know it is safe to
+/// replace.
+pub const MARKER: &str = "# axiom-pre-commit-hook";
+
+/// The hook script, running `axiom` unless `$AXIOM` overrides it.
+pub fn script(axiom: &str) -> String {
+    format!(r#"#!/bin/sh
+{MARKER}
+# Written by `axiom hook install`; remove with `axiom hook uninstall`.
+# Checks the staged version of each .ax file, not the working copy.
+AXIOM="${{AXIOM:-{axiom}}}"
+status=0
+files=$(git diff --cached --name-only --diff-filter=ACMR -- '*.ax')
+while IFS= read -r file; do
+    [ -n "$file" ] || continue
+    git show ":$file" | "$AXIOM" fmt - --check --stdin-filename "$file" || status=1
+    git show ":$file" | "$AXIOM" chk - --stdin-filename "$file" >/dev/null || status=1
+done <<EOF
+$files
+EOF
+exit $status
+"#, axiom = shell_quoted(axiom))
+}
+
+/// `text` safe inside the double quotes of the script.
+fn shell_quoted(text: &str) -> String {
+    text.chars().flat_map(|c| match c {
+        '"' | '\\' | '$' | '`' => vec!['\\', c],
+        c => vec![c],
+    }).collect()
+}
+
+/// The directory git runs the hooks of the repository at `repo` from.
+pub fn hooks_dir(repo: &Path) -> Result<PathBuf, String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--git-path", "hooks"])
+        .current_dir(repo)
+        .output()
+        .map_err(|e| format!("Cannot run git: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("'{}' is not inside a git repository", repo.display()));
+    }
+    // Relative to `repo` (`../.git/hooks` from a subdirectory) unless git
+    // printed an absolute path
+    let dir = repo.join(String::from_utf8_lossy(&output.stdout).trim());
+    Ok(dir.canonicalize().unwrap_or(dir))
+}
+
+/// Write the pre-commit hook for the repository at `repo`, returning its
+/// path. An existing hook not written by axiom is only replaced with
+/// `force`.
+pub fn install(repo: &Path, force: bool) -> Result<PathBuf, String> {
+    let dir = hooks_dir(repo)?;
+    let path = dir.join("pre-commit");
+    if let Ok(existing) = std::fs::read_to_string(&path) {
+        if !existing.contains(MARKER) && !force {
+            return Err(format!("'{}' already exists and was not written by axiom (--force replaces it)", path.display()));
+        }
+    }
+    let axiom = std::env::current_exe().map(|exe| exe.display().to_string()).unwrap_or_else(|_| "axiom".to_string());
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Cannot create '{}': {}", dir.display(), e))?;
+    std::fs::write(&path, script(&axiom)).map_err(|e| format!("Cannot write '{}': {}", path.display(), e))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))
+            .map_err(|e| format!("Cannot make '{}' executable: {}", path.display(), e))?;
+    }
+    Ok(path)
+}
+
+/// Remove the pre-commit hook `install` wrote, returning its path. A hook
+/// axiom did not write is an error.
+pub fn uninstall(repo: &Path) -> Result<PathBuf, String> {
+    let path = hooks_dir(repo)?.join("pre-commit");
+    let existing = std::fs::read_to_string(&path).map_err(|_| format!("No pre-commit hook at '{}'", path.display()))?;
+    if !existing.contains(MARKER) {
+        return Err(format!("'{}' was not written by axiom; remove it by hand", path.display()));
+    }
+    std::fs::remove_file(&path).map_err(|e| format!("Cannot remove '{}': {}", path.display(), e))?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_script_quotes_the_binary_path() {
+        let text = script("/opt/my \"axiom\"/bin/axiom");
+        assert!(text.starts_with("#!/bin/sh\n# axiom-pre-commit-hook\n"));
+        assert!(text.contains(r#"AXIOM="${AXIOM:-/opt/my \"axiom\"/bin/axiom}""#), "{}", text);
+        assert!(text.contains("fmt - --check --stdin-filename \"$file\""));
+    }
+
+    #[test]
+    fn test_install_and_uninstall_leave_other_hooks_alone() {
+        let repo = std::env::temp_dir().join(format!("axiom-hook-{}", std::process::id()));
+        std::fs::create_dir_all(&repo).unwrap();
+        let status = Command::new("git").args(["init", "-q"]).current_dir(&repo).status();
+        if !status.is_ok_and(|s| s.success()) {
+            // No git to test with
+            std::fs::remove_dir_all(&repo).unwrap();
+            return;
+        }
+        let hook = install(&repo, false).unwrap();
+        assert!(std::fs::read_to_string(&hook).unwrap().contains(MARKER));
+        // Reinstalling over our own hook is fine
+        install(&repo, false).unwrap();
+        uninstall(&repo).unwrap();
+        assert!(!hook.exists());
+        assert!(uninstall(&repo).is_err());
+
+        std::fs::write(&hook, "#!/bin/sh\nmake lint\n").unwrap();
+        assert!(install(&repo, false).is_err());
+        assert!(uninstall(&repo).is_err());
+        install(&repo, true).unwrap();
+        std::fs::remove_dir_all(&repo).unwrap();
+    }
+}
@@ -0,0 +1,149 @@
+//! Event hooks on a `Runtime`, for embedders
+//!
+//! A host registers closures that the runtime calls as a script runs:
+//!
+//!   on_statement — before and after each statement (debuggers, steppers)
+//!   on_call      — on entering and leaving each function (tracers, profilers)
+//!   on_output    — each line the script prints (a GUI's console)
+//!   on_error     — the error a run ends with
+//!
+//! Output and error hooks work on both engines. Statement and call hooks
+//! watch the tree-walker, so a runtime with any registered runs its scripts
+//! there, as it does for classes. Hooks observe; they cannot change what
+//! the script does, and a printed line still goes to stdout (or the sink
+//! installed with `runtime::with_output_sink`).
+
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+
+use crate::ast::Stmt;
+use crate::errors::RuntimeError;
+
+/// Whether an event comes before or after the statement or call it is about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    Before,
+    /// Also sent when the statement or call raised
+    After,
+}
+
+pub struct StmtEvent<'a> {
+    pub stmt: &'a Stmt,
+    pub phase: Phase,
+    /// Functions executing, 0 at the top level of the script
+    pub depth: usize,
+}
+
+pub struct CallEvent<'a> {
+    /// As the call chain of a stack overflow shows it: `fib`, `Point.len`,
+    /// a native's name, or `<fn>` for an anonymous function
+    pub name: &'a str,
+    pub phase: Phase,
+    /// Functions executing, counting this one
+    pub depth: usize,
+}
+
+type StmtHook = Box<dyn FnMut(&StmtEvent) + Send>;
+type CallHook = Box<dyn FnMut(&CallEvent) + Send>;
+type OutputHook = Box<dyn FnMut(&str) + Send>;
+type ErrorHook = Box<dyn FnMut(&RuntimeError) + Send>;
+
+/// The hooks registered on one runtime, each kind in registration order.
+#[derive(Default)]
+pub struct Hooks {
+    statement: Vec<StmtHook>,
+    call: Vec<CallHook>,
+    output: Vec<OutputHook>,
+    error: Vec<ErrorHook>,
+}
+
+impl Hooks {
+    pub(crate) fn add_statement(&mut self, hook: impl FnMut(&StmtEvent) + Send + 'static) {
+        self.statement.push(Box::new(hook));
+    }
+
+    pub(crate) fn add_call(&mut self, hook: impl FnMut(&CallEvent) + Send + 'static) {
+        self.call.push(Box::new(hook));
+    }
+
+    pub(crate) fn add_output(&mut self, hook: impl FnMut(&str) + Send + 'static) {
+        self.output.push(Box::new(hook));
+    }
+
+    pub(crate) fn add_error(&mut self, hook: impl FnMut(&RuntimeError) + Send + 'static) {
+        self.error.push(Box::new(hook));
+    }
+
+    pub(crate) fn has_output(&self) -> bool {
+        !self.output.is_empty()
+    }
+
+    pub(crate) fn statement(&mut self, event: &StmtEvent) {
+        for hook in &mut self.statement { hook(event); }
+    }
+
+    pub(crate) fn call(&mut self, event: &CallEvent) {
+        for hook in &mut self.call { hook(event); }
+    }
+
+    pub(crate) fn output(&mut self, line: &str) {
+        for hook in &mut self.output { hook(line); }
+    }
+
+    pub(crate) fn error(&mut self, error: &RuntimeError) {
+        for hook in &mut self.error { hook(error); }
+    }
+}
+
+/// Shared between a runtime and the output tap it installs for a run.
+pub(crate) type SharedHooks = Arc<Mutex<Hooks>>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::{capture_output, Engine, Runtime};
+    use crate::Parser;
+
+    fn record(events: &Arc<Mutex<Vec<String>>>) -> impl FnMut(String) + Send + 'static {
+        let events = Arc::clone(events);
+        move |event| events.lock().push(event)
+    }
+
+    #[test]
+    fn test_hooks_see_statements_calls_output_and_errors() {
+        let src = "fn twice(x) {\n  ret x * 2\n}\nout twice(4)\nlet y = nope";
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let mut rt = Runtime::new();
+        rt.set_engine(Engine::Vm);
+        let mut log = record(&events);
+        rt.on_statement(move |e| if e.phase == Phase::Before && e.depth == 0 { log(format!("stmt {}", src[e.stmt.span().start..].lines().next().unwrap())) });
+        let mut log = record(&events);
+        rt.on_call(move |e| log(format!("{:?} {} {}", e.phase, e.name, e.depth)));
+        let mut log = record(&events);
+        rt.on_output(move |line| log(format!("out {}", line)));
+        let mut log = record(&events);
+        rt.on_error(move |e| log(format!("error {}", matches!(e, RuntimeError::UndefinedVariable { .. }))));
+
+        let (result, printed) = capture_output(|| rt.run(Parser::new(src, 0).parse().unwrap()));
+        assert!(result.is_err());
+        // Output hooks observe; the line is still printed
+        assert_eq!(printed, "8\n");
+        assert_eq!(*events.lock(), [
+            "stmt out twice(4)", "Before twice 1", "After twice 1", "out 8",
+            "stmt let y = nope", "error true",
+        ]);
+    }
+
+    #[test]
+    fn test_output_hooks_run_on_the_vm() {
+        let lines = Arc::new(Mutex::new(Vec::new()));
+        let mut rt = Runtime::new();
+        rt.set_engine(Engine::Vm);
+        let mut log = record(&lines);
+        rt.on_output(move |line| log(line.to_string()));
+        let (result, _) = capture_output(|| rt.run(Parser::new("let i = 0\nwhile i < 3 { out i; i = i + 1 }", 0).parse().unwrap()));
+        result.unwrap();
+        assert_eq!(*lines.lock(), ["0", "1", "2"]);
+    }
+}
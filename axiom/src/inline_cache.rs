@@ -57,7 +57,7 @@ static NEXT_SHAPE_ID: AtomicU32 = AtomicU32::new(1);
 // Property IC Entry
 // ---------------------------------------------------------------------------
 
-const IC_MAX_POLY: usize = 4; // PIC holds up to 4 entries before megamorphic
+pub(crate) const IC_MAX_POLY: usize = 4; // PIC holds up to 4 entries before megamorphic
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum IcState {
@@ -67,6 +67,18 @@ pub enum IcState {
     Megamorphic,
 }
 
+/// What an IC entry's slot resolves to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlotKind {
+    /// Plain data slot (for accessor sites: the shape has no accessor)
+    Field,
+    Method,
+    /// `get` accessor, called on read
+    Getter,
+    /// `set` accessor, called on assignment
+    Setter,
+}
+
 /// One entry in a polymorphic inline cache.
 #[derive(Debug, Clone, Copy)]
 pub struct IcEntry {
@@ -74,13 +86,12 @@ pub struct IcEntry {
     pub shape_id: u32,
     /// Property slot (offset in object's value array)
     pub slot: u16,
-    /// Is this a method (callable) vs field?
-    pub is_method: bool,
+    pub kind: SlotKind,
 }
 
 /// An inline cache site (attached to each GetProp/SetProp bytecode).
 /// Kept small (32 bytes) to fit in cache line.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 #[repr(C, align(32))]
 pub struct PropIC {
     pub state: IcState,
@@ -102,12 +113,18 @@ impl PropIC {
     /// Look up the slot for a given shape ID. Returns slot if hit.
     #[inline(always)]
     pub fn lookup(&mut self, shape_id: u32) -> Option<u16> {
+        self.lookup_entry(shape_id).map(|e| e.slot)
+    }
+
+    /// Like `lookup`, but also says what kind of slot was cached.
+    #[inline(always)]
+    pub fn lookup_entry(&mut self, shape_id: u32) -> Option<IcEntry> {
         // Monomorphic fast path — single comparison
         if self.state == IcState::Monomorphic {
             if let Some(entry) = self.entries[0] {
                 if entry.shape_id == shape_id {
                     self.hit_count += 1;
-                    return Some(entry.slot);
+                    return Some(entry);
                 }
             }
             self.miss_count += 1;
@@ -120,7 +137,7 @@ impl PropIC {
                 if let Some(entry) = entry_opt {
                     if entry.shape_id == shape_id {
                         self.hit_count += 1;
-                        return Some(entry.slot);
+                        return Some(*entry);
                     }
                 }
             }
@@ -134,8 +151,8 @@ impl PropIC {
     }
 
     /// Update the IC with a new (shape, slot) observation.
-    pub fn update(&mut self, shape_id: u32, slot: u16, is_method: bool) {
-        let new_entry = IcEntry { shape_id, slot, is_method };
+    pub fn update(&mut self, shape_id: u32, slot: u16, kind: SlotKind) {
+        let new_entry = IcEntry { shape_id, slot, kind };
 
         match self.state {
             IcState::Uninitialized => {
@@ -312,6 +329,14 @@ impl ICTable {
         }
     }
 
+    pub fn stats(&self) -> IcStats {
+        let mut stats = IcStats::default();
+        for ic in &self.prop_ics {
+            stats.add(ic);
+        }
+        stats
+    }
+
     pub fn print_stats(&self) {
         println!("=== IC Statistics ===");
         let mut total_hits = 0u32;
@@ -335,3 +360,159 @@ impl ICTable {
         }
     }
 }
+
+// ---------------------------------------------------------------------------
+// IC statistics — `axiom run --ic-stats` and the profiler report
+// ---------------------------------------------------------------------------
+
+/// Totals over a set of property IC sites.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct IcStats {
+    pub sites: u64,
+    pub hits: u64,
+    pub misses: u64,
+    pub monomorphic: u64,
+    pub polymorphic: u64,
+    pub megamorphic: u64,
+    /// Sites that left the monomorphic state (mono → poly, possibly on to mega).
+    pub polymorphic_transitions: u64,
+    /// Sites that gave up caching (poly → mega). A site transitions at most once.
+    pub megamorphic_transitions: u64,
+}
+
+impl IcStats {
+    pub fn add(&mut self, ic: &PropIC) {
+        self.sites += 1;
+        self.hits += ic.hit_count as u64;
+        self.misses += ic.miss_count as u64;
+        match ic.state {
+            IcState::Uninitialized => {}
+            IcState::Monomorphic => self.monomorphic += 1,
+            IcState::Polymorphic => { self.polymorphic += 1; self.polymorphic_transitions += 1; }
+            IcState::Megamorphic => {
+                self.megamorphic += 1;
+                self.polymorphic_transitions += 1;
+                self.megamorphic_transitions += 1;
+            }
+        }
+    }
+
+    pub fn hit_rate(&self) -> f64 {
+        let total = self.hits + self.misses;
+        if total == 0 { 0.0 } else { self.hits as f64 / total as f64 }
+    }
+}
+
+/// One call site in an `IcReport`.
+#[derive(Debug, Clone)]
+pub struct IcSite {
+    pub method: String,
+    /// Byte offset of the call in its source file.
+    pub offset: usize,
+    pub state: IcState,
+    pub hits: u32,
+    pub misses: u32,
+}
+
+/// Per-site IC state plus totals, as left by a run.
+#[derive(Debug, Clone, Default)]
+pub struct IcReport {
+    pub stats: IcStats,
+    /// Least effective first: megamorphic, then by miss count.
+    pub sites: Vec<IcSite>,
+}
+
+impl IcReport {
+    pub fn new(sites: impl IntoIterator<Item = (String, usize, PropIC)>) -> Self {
+        let mut report = IcReport::default();
+        for (method, offset, ic) in sites {
+            report.stats.add(&ic);
+            report.sites.push(IcSite { method, offset, state: ic.state, hits: ic.hit_count, misses: ic.miss_count });
+        }
+        let rank = |s: &IcState| match s {
+            IcState::Megamorphic => 0,
+            IcState::Polymorphic => 1,
+            IcState::Monomorphic => 2,
+            IcState::Uninitialized => 3,
+        };
+        report.sites.sort_by(|a, b| rank(&a.state).cmp(&rank(&b.state))
+            .then(b.misses.cmp(&a.misses))
+            .then(a.offset.cmp(&b.offset)));
+        report
+    }
+
+    /// Text report; with `source`, sites show as line:col instead of byte offsets.
+    pub fn render(&self, source: Option<&str>, top: usize) -> String {
+        let st = &self.stats;
+        let mut out = String::new();
+        out.push_str("=== Inline Caches ===\n");
+        out.push_str(&format!("  Member sites:      {} ({} mono, {} poly, {} mega)\n",
+            st.sites, st.monomorphic, st.polymorphic, st.megamorphic));
+        out.push_str(&format!("  Lookups:           {} hits / {} misses ({:.1}% hit)\n",
+            st.hits, st.misses, st.hit_rate() * 100.0));
+        out.push_str(&format!("  Transitions:       {} → polymorphic, {} → megamorphic\n",
+            st.polymorphic_transitions, st.megamorphic_transitions));
+        for site in self.sites.iter().take(top) {
+            let at = match source {
+                Some(src) => {
+                    let before = &src[..site.offset.min(src.len())];
+                    let line = before.matches('\n').count() + 1;
+                    let col = before.len() - before.rfind('\n').map(|i| i + 1).unwrap_or(0) + 1;
+                    format!("{}:{}", line, col)
+                }
+                None => format!("@{}", site.offset),
+            };
+            out.push_str(&format!("  {:<10} .{:<20} {:<14} {:>8} hits {:>8} misses\n",
+                at, site.method, format!("{:?}", site.state), site.hits, site.misses));
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::Runtime;
+    use crate::Parser;
+
+    /// A Runtime with inline caches on, whatever the local conf says.
+    fn ic_runtime() -> Runtime {
+        let on = ["ic_enabled", "inline_cache"].map(|k| (k.to_string(), "on".to_string()));
+        Runtime::with_conf(&crate::conf::AxConf::from_layers(vec![(crate::conf::Origin::Cli, on.to_vec())]))
+    }
+
+    #[test]
+    fn test_method_call_ic_report() {
+        let mut src = String::new();
+        for name in ["A", "B", "C", "D", "E"] {
+            src.push_str(&format!("cls {} {{\n  fun f() {{ return 1 }}\n}}\n", name));
+        }
+        src.push_str("let a = new A()\nlet i = 0\nwhile i < 10 { a.f(); i = i + 1 }\n");
+        src.push_str("for x in [new A(), new B(), new C(), new D(), new E()] { x.f() }\n");
+
+        let mut rt = ic_runtime();
+        rt.run(Parser::new(&src, 0).parse().unwrap()).unwrap();
+        let report = rt.ic_report().expect("inline caches are on");
+        let st = report.stats;
+        assert_eq!((st.sites, st.monomorphic, st.megamorphic), (2, 1, 1));
+        assert_eq!(st.megamorphic_transitions, 1);
+        assert_eq!(st.hits, 9);
+        assert_eq!(report.sites[0].state, IcState::Megamorphic);
+        assert!(report.render(Some(&src), 5).contains(".f"));
+    }
+
+    #[test]
+    fn test_accessor_sites_are_cached() {
+        let src = "cls P {\n  let x = 1\n  get double() { ret 2 }\n}\nlet p = new P()\nlet i = 0\n\
+                   while i < 5 { p.double; p.x; i = i + 1 }\n";
+        let mut rt = ic_runtime();
+        rt.run(Parser::new(src, 0).parse().unwrap()).unwrap();
+        let report = rt.ic_report().expect("inline caches are on");
+        // `p.double` caches the getter, `p.x` that P has no accessor `x`
+        let sites: Vec<(&str, u32)> = report.sites.iter()
+            .filter(|s| s.method == "double" || s.method == "x")
+            .map(|s| (s.method.as_str(), s.hits)).collect();
+        assert_eq!(sites.len(), 2, "{:?}", report.sites);
+        assert!(sites.iter().all(|(_, hits)| *hits == 4));
+    }
+}
@@ -0,0 +1,133 @@
+//! Installing the toolchain for the current user (`axiom install`)
+//!
+//! Building never touches the home directory; this command does it on
+//! request. It creates `~/.axiom/bin`, `~/.axiom/lib` and the package store
+//! (`AXIOM_LIBS`, by default `~/.axiomlibs`), writes `~/.axiom/conf.txt`
+//! with the defaults unless it exists, and copies the running binary into
+//! `~/.axiom/bin` as both `axiom` and `axm`.
+//!
+//! Shell profiles are only edited with `--modify-path`: a PATH line is
+//! appended to each of `.bashrc`, `.bash_profile`, `.zshrc`, `.profile` and
+//! the fish config that exists and does not mention the directory yet.
+//! `cargo install --path .` is the alternative that leaves `~/.axiom/bin` out.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Where an installation goes.
+#[derive(Debug, Clone)]
+pub struct Layout {
+    /// `~/.axiom`
+    pub home: PathBuf,
+    /// The package store
+    pub libs: PathBuf,
+}
+
+impl Layout {
+    /// The current user's directories.
+    pub fn for_user() -> Result<Layout, String> {
+        let user = dirs::home_dir().ok_or("Cannot determine the home directory")?;
+        let libs = std::env::var_os("AXIOM_LIBS").map(PathBuf::from).unwrap_or_else(|| user.join(".axiomlibs"));
+        Ok(Layout { home: user.join(".axiom"), libs })
+    }
+
+    pub fn bin_dir(&self) -> PathBuf {
+        self.home.join("bin")
+    }
+}
+
+/// What `install` did, for the command to report.
+#[derive(Debug, Default)]
+pub struct Installed {
+    /// The copies of the binary
+    pub binaries: Vec<PathBuf>,
+    /// Set when the conf file was written; an existing one is kept
+    pub conf: Option<PathBuf>,
+}
+
+/// Set up `layout` and copy `binary` into its `bin` directory.
+pub fn install(layout: &Layout, binary: &Path) -> Result<Installed, String> {
+    for dir in [layout.bin_dir(), layout.home.join("lib"), layout.libs.clone()] {
+        std::fs::create_dir_all(&dir).map_err(|e| format!("Cannot create '{}': {}", dir.display(), e))?;
+    }
+    let mut installed = Installed::default();
+    let conf = layout.home.join("conf.txt");
+    if !conf.exists() {
+        let defaults = crate::conf::AxConf::from_layers(Vec::new()).render_user_file();
+        std::fs::write(&conf, defaults).map_err(|e| format!("Cannot write '{}': {}", conf.display(), e))?;
+        installed.conf = Some(conf);
+    }
+    let exe = std::env::consts::EXE_SUFFIX;
+    for name in ["axiom", "axm"] {
+        let dest = layout.bin_dir().join(format!("{}{}", name, exe));
+        // Running the installed copy: nothing to copy over itself
+        if dest.canonicalize().ok() != binary.canonicalize().ok() {
+            std::fs::copy(binary, &dest).map_err(|e| format!("Cannot copy the binary to '{}': {}", dest.display(), e))?;
+        }
+        installed.binaries.push(dest);
+    }
+    Ok(installed)
+}
+
+/// Whether `dir` is on `$PATH` already.
+pub fn on_path(dir: &Path) -> bool {
+    std::env::var_os("PATH").is_some_and(|path| std::env::split_paths(&path).any(|p| p == dir))
+}
+
+/// The line a shell profile needs to find `bin_dir`.
+pub fn path_line(bin_dir: &Path, fish: bool) -> String {
+    match fish {
+        true => format!("set -x PATH {} $PATH", bin_dir.display()),
+        false => format!("export PATH=\"{}:$PATH\"", bin_dir.display()),
+    }
+}
+
+/// Append the PATH line for `bin_dir` to the shell profiles in `user_home`
+/// that exist and do not mention it yet. Returns the profiles changed.
+pub fn add_to_path(bin_dir: &Path, user_home: &Path) -> Result<Vec<PathBuf>, String> {
+    let profiles = [".bashrc", ".bash_profile", ".zshrc", ".profile", ".config/fish/config.fish"];
+    let mut changed = Vec::new();
+    for name in profiles {
+        let profile = user_home.join(name);
+        let Ok(text) = std::fs::read_to_string(&profile) else { continue };
+        if text.contains(&*bin_dir.to_string_lossy()) {
+            continue;
+        }
+        let line = path_line(bin_dir, name.ends_with(".fish"));
+        std::fs::OpenOptions::new().append(true).open(&profile)
+            .and_then(|mut file| write!(file, "\n# Added by axiom install\n{}\n", line))
+            .map_err(|e| format!("Cannot update '{}': {}", profile.display(), e))?;
+        changed.push(profile);
+    }
+    Ok(changed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_install_and_path_are_idempotent() {
+        let root = std::env::temp_dir().join(format!("axiom-install-{}", std::process::id()));
+        let layout = Layout { home: root.join(".axiom"), libs: root.join(".axiomlibs") };
+        std::fs::create_dir_all(&root).unwrap();
+        let binary = root.join("built-axiom");
+        std::fs::write(&binary, "binary").unwrap();
+
+        let first = install(&layout, &binary).unwrap();
+        assert_eq!(first.binaries.len(), 2);
+        assert!(first.binaries.iter().all(|b| std::fs::read_to_string(b).unwrap() == "binary"));
+        assert!(first.conf.is_some() && layout.libs.is_dir());
+        std::fs::write(layout.home.join("conf.txt"), "engine=vm\n").unwrap();
+        assert!(install(&layout, &binary).unwrap().conf.is_none());
+        assert_eq!(std::fs::read_to_string(layout.home.join("conf.txt")).unwrap(), "engine=vm\n");
+
+        std::fs::write(root.join(".bashrc"), "alias ll='ls -l'\n").unwrap();
+        assert_eq!(add_to_path(&layout.bin_dir(), &root).unwrap(), vec![root.join(".bashrc")]);
+        assert!(add_to_path(&layout.bin_dir(), &root).unwrap().is_empty());
+        let bashrc = std::fs::read_to_string(root.join(".bashrc")).unwrap();
+        assert_eq!(bashrc.matches(&*layout.bin_dir().to_string_lossy()).count(), 1);
+        assert!(!root.join(".zshrc").exists());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
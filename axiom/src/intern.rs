@@ -0,0 +1,172 @@
+//! String Interner — one process-wide symbol table
+//!
+//! Identifiers, property names and string constants are interned once and
+//! shared as `Arc<str>`: the compiler interns a Proto's string constants,
+//! the VM loads them without allocating and compares them by pointer first,
+//! and the tree-walker keys its scopes by interned identifiers.
+//!
+//! Only those compile-time names go in. Nothing is ever removed, so strings
+//! built at runtime (data, map keys read from files) stay plain `Arc<str>`;
+//! the VM boundary uses `lookup`, which shares an existing entry but never
+//! inserts one.
+//!
+//! `Sym` is the u32 index form, used where a copyable handle is needed
+//! (NaN-boxed SYM values, tables keyed by name).
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+
+// ---------------------------------------------------------------------------
+// String interner — maps &str → u32 index (cached, arena-stored)
+// ---------------------------------------------------------------------------
+
+pub struct StringInterner {
+    table: RwLock<HashMap<Arc<str>, u32>>,
+    strings: RwLock<Vec<Arc<str>>>,
+}
+
+impl StringInterner {
+    pub fn new() -> Self {
+        StringInterner {
+            table: RwLock::new(HashMap::new()),
+            strings: RwLock::new(Vec::new()),
+        }
+    }
+
+    pub fn intern(&self, s: &str) -> u32 {
+        self.intern_full(s).0
+    }
+
+    /// The shared allocation for `s`; equal strings get the same pointer.
+    pub fn intern_arc(&self, s: &str) -> Arc<str> {
+        self.intern_full(s).1
+    }
+
+    fn intern_full(&self, s: &str) -> (u32, Arc<str>) {
+        {
+            let table = self.table.read();
+            if let Some((arc, &idx)) = table.get_key_value(s) {
+                return (idx, Arc::clone(arc));
+            }
+        }
+        let mut table = self.table.write();
+        if let Some((arc, &idx)) = table.get_key_value(s) {
+            return (idx, Arc::clone(arc));
+        }
+        let arc: Arc<str> = Arc::from(s);
+        let idx = {
+            let mut strings = self.strings.write();
+            let idx = strings.len() as u32;
+            strings.push(Arc::clone(&arc));
+            idx
+        };
+        table.insert(Arc::clone(&arc), idx);
+        (idx, arc)
+    }
+
+    /// The shared allocation for `s` if it is already interned; never inserts.
+    pub fn lookup(&self, s: &str) -> Option<Arc<str>> {
+        self.table.read().get_key_value(s).map(|(arc, _)| Arc::clone(arc))
+    }
+
+    pub fn get(&self, idx: u32) -> Option<String> {
+        self.resolve(idx).map(|s| s.to_string())
+    }
+
+    pub fn resolve(&self, idx: u32) -> Option<Arc<str>> {
+        self.strings.read().get(idx as usize).cloned()
+    }
+
+    pub fn len(&self) -> usize {
+        self.strings.read().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.strings.read().is_empty()
+    }
+}
+
+impl Default for StringInterner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Process-wide table
+// ---------------------------------------------------------------------------
+
+static INTERNER: Lazy<StringInterner> = Lazy::new(StringInterner::new);
+
+pub fn interner() -> &'static StringInterner {
+    &INTERNER
+}
+
+/// Intern `s` in the shared table and return its allocation.
+pub fn intern(s: &str) -> Arc<str> {
+    INTERNER.intern_arc(s)
+}
+
+/// Interned string handle: equal strings have equal symbols.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Sym(u32);
+
+impl Sym {
+    pub fn new(s: &str) -> Sym {
+        Sym(INTERNER.intern(s))
+    }
+
+    pub fn index(self) -> u32 {
+        self.0
+    }
+
+    pub fn as_str(self) -> Arc<str> {
+        INTERNER.resolve(self.0).expect("symbols are never removed from the interner")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_intern_shares_one_allocation() {
+        let a = intern("shared-name");
+        let b = intern(&String::from("shared-name"));
+        assert!(Arc::ptr_eq(&a, &b));
+        assert!(!Arc::ptr_eq(&a, &intern("other-name")));
+    }
+
+    #[test]
+    fn test_lookup_does_not_insert() {
+        let before = interner().len();
+        assert!(interner().lookup("never-interned-lookup").is_none());
+        assert_eq!(interner().len(), before);
+        let a = intern("looked-up-name");
+        assert!(Arc::ptr_eq(&a, &interner().lookup("looked-up-name").unwrap()));
+    }
+
+    #[test]
+    fn test_sym_round_trip() {
+        let s = Sym::new("sym-round-trip");
+        assert_eq!(s, Sym::new("sym-round-trip"));
+        assert_eq!(&*s.as_str(), "sym-round-trip");
+        assert!(Arc::ptr_eq(&s.as_str(), &intern("sym-round-trip")));
+    }
+
+    #[test]
+    fn test_proto_constants_share_interned_strings() {
+        let mut proto = crate::bytecode::Proto::new("<test>");
+        let mut inner = crate::bytecode::Proto::new("<inner>");
+        inner.add_string("const-name");
+        proto.add_string("const-name");
+        proto.protos.push(inner);
+        proto.intern_strings();
+        let interned = intern("const-name");
+        assert!(Arc::ptr_eq(&proto.str_consts[0], &interned));
+        assert!(Arc::ptr_eq(&proto.protos[0].str_consts[0], &interned));
+    }
+}
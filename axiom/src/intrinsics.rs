@@ -1,61 +1,118 @@
-/// AXIOM INTRINSIC MONOLITH — STATICALLY LINKED STANDARD LIBRARY (FULL IMPLEMENTATION)
-/// All 23 modules inlined here with raw, high-performance Rust using direct crate calls.
-/// NO STUBS. NO TODO!(). Raw match arms on AxValue types for maximum performance.
-/// 
-/// Modules:
-/// 1. alg   — Logic / Algorithms (rayon, petgraph)
-/// 2. ann   — Reflection / Annotations
-/// 3. aut   — Automation (chrono, croner, notify)
-/// 4. clr   — Colors (TrueColor rendering)
-/// 5. col   — Collections (dashmap concurrent structures)
-/// 6. con   — Concurrency (tokio async)
-/// 7. csv   — CSV parsing (streaming deserialization)
-/// 8. dfm   — DataFrames (polars)
-/// 9. env   — Environment (dotenvy)
-/// 10. git  — Git operations (git2)
-/// 11. ioo  — Buffered I/O (filesystem operations)
-/// 12. jsn  — JSON (serde_json)
-/// 13. log  — Logging / Progress (indicatif)
-/// 14. mth  — Math (f64 intrinsics)
-/// 15. net  — Networking (tokio async)
-/// 16. num  — Numerics (ndarray)
-/// 17. plt  — Plotting (plotters)
-/// 18. pth  — Paths (walkdir)
-/// 19. str  — Strings (regex, unicode)
-/// 20. sys  — System info (sysinfo)
-/// 21. tim  — Time (chrono, croner)
-/// 22. tui  — Terminal UI (ratatui)
-/// 23. cli  — CLI / Shell integration (std::process, std::env)
-/// 24. usb  — USB device I/O (rusb)
-
-use crate::core::value::AxValue;
+//! AXIOM INTRINSIC MONOLITH — STATICALLY LINKED STANDARD LIBRARY (FULL IMPLEMENTATION)
+//! All 26 modules inlined here with raw, high-performance Rust using direct crate calls.
+//! NO STUBS. NO TODO!(). Raw match arms on AxValue types for maximum performance.
+//! 
+//! Modules:
+//! 1. alg   — Logic / Algorithms (rayon, petgraph)
+//! 2. ann   — Reflection / Annotations
+//! 3. aut   — Automation (chrono, croner, notify)
+//! 4. clr   — Colors (TrueColor rendering)
+//! 5. col   — Collections (dashmap concurrent structures)
+//! 6. con   — Concurrency (tokio async)
+//! 7. csv   — CSV parsing (streaming deserialization)
+//! 8. dfm   — DataFrames (polars)
+//! 9. env   — Environment (dotenvy)
+//! 10. git  — Git operations (git2)
+//! 11. ioo  — Buffered I/O (filesystem operations)
+//! 12. jsn  — JSON (serde_json)
+//! 13. log  — Leveled, scoped logging (crate::logger) / Progress (indicatif)
+//! 14. mth  — Math (f64 intrinsics)
+//! 15. net  — Networking (tokio async)
+//! 16. num  — Numerics (ndarray)
+//! 17. plt  — Plotting (plotters)
+//! 18. pth  — Paths (walkdir)
+//! 19. str  — Strings (regex, unicode)
+//! 20. sys  — System info (sysinfo)
+//! 21. tim  — Time (chrono, chrono-tz, croner)
+//! 22. tui  — Terminal UI (ratatui)
+//! 23. cli  — CLI / Shell integration (std::process, std::env)
+//! 24. usb  — USB device I/O (rusb)
+//! 25. ffi  — C ABI calls into shared libraries (libloading)
+//! 26. res  — Resource lifetimes: finalizers for handles
+
+use crate::core::value::{map_entries, touch_collections, AxValue};
 use crate::core::oop::AxCallable;
+use crate::errors::{FailureKind, RuntimeError};
+use crate::logger::{self, Level};
 use dashmap::DashMap;
 use std::sync::{Arc, RwLock};
 use std::collections::HashMap;
 use regex::Regex;
 use ndarray::Array2;
 use rayon::prelude::*;
-use chrono::{Local, DateTime, Utc};
+use chrono::{Local, DateTime, Utc, FixedOffset, NaiveDateTime, TimeZone, Datelike, Timelike};
 use walkdir::WalkDir;
+#[cfg(feature = "native")]
 use plotters::prelude::*;
+#[cfg(feature = "native")]
 use plotters::style::Color as PlottersColor;  // needed for .mix() method
-use indicatif::{ProgressBar, ProgressStyle};
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
 use std::fs;
 use std::path::Path;
+#[cfg(feature = "native")]
 use sysinfo::System;
+#[cfg(feature = "native")]
 use git2::{Repository, Status};
 use serde_json;
+#[cfg(feature = "native")]
 use reqwest;
 
 // ==================== HELPER: WRAP NATIVE FUNCTIONS ====================
 
 /// Wrap a native Rust function into AxValue::Fun
 fn native(name: &str, f: fn(Vec<AxValue>) -> AxValue) -> AxValue {
-    AxValue::Fun(Arc::new(AxCallable::Native {
-        name: name.to_string(),
-        func: f,
-    }))
+    AxValue::Fun(Arc::new(AxCallable::native(name, move |args| Ok(f(args)))))
+}
+
+/// Register an intrinsic that reports failure as a RuntimeError.
+fn native_result(name: &str, f: fn(Vec<AxValue>) -> Result<AxValue, RuntimeError>) -> AxValue {
+    AxValue::Fun(Arc::new(AxCallable::native(name, f)))
+}
+
+// Failure convention: intrinsics that touch the outside world (ioo, net, git,
+// csv, usb, cli, sys.chdir) raise a RuntimeError — IntrinsicError when the
+// operation fails, TypeMismatch for a bad argument — rather than returning
+// Nil, false or "ERROR: ..." strings. Nil is kept for genuine absence (an
+// unset env var, a detached HEAD).
+
+fn fail(function: &str, kind: FailureKind, err: impl std::fmt::Display) -> RuntimeError {
+    RuntimeError::IntrinsicError { function: function.to_string(), kind, message: err.to_string() }
+}
+
+fn arg_mismatch(function: &str, index: usize, expected: &str, found: Option<&AxValue>) -> RuntimeError {
+    RuntimeError::TypeMismatch {
+        expected: format!("{} for argument {} of {}", expected, index + 1, function),
+        found: found.map(|v| v.type_name()).unwrap_or("nothing").to_string(),
+        span: Default::default(),
+    }
+}
+
+fn arg_str<'a>(args: &'a [AxValue], index: usize, function: &str) -> Result<&'a str, RuntimeError> {
+    match args.get(index) {
+        Some(AxValue::Str(s)) => Ok(s),
+        other => Err(arg_mismatch(function, index, "Str", other)),
+    }
+}
+
+#[cfg_attr(not(feature = "native"), allow(dead_code))]
+fn arg_num(args: &[AxValue], index: usize, function: &str) -> Result<f64, RuntimeError> {
+    match args.get(index) {
+        Some(AxValue::Num(n)) => Ok(*n),
+        other => Err(arg_mismatch(function, index, "Num", other)),
+    }
+}
+
+/// Call a function value from native code. Only natives (which VM closures
+/// arrive as) can be called here; user-defined functions on the tree-walker
+/// are called by its higher-order intercept before the intrinsic runs.
+fn call_native(function: &str, f: AxValue, args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    match &f {
+        AxValue::Fun(callable) => match callable.as_ref() {
+            AxCallable::Native { func, .. } => func(args),
+            AxCallable::UserDefined { .. } => Err(arg_mismatch(function, 0, &format!("Fun called as {}(...)", function), Some(&f))),
+        },
+        other => Err(arg_mismatch(function, 0, "Fun", Some(other))),
+    }
 }
 
 // ==================== MODULE 1: ALG (ALGORITHMS, LOGIC, RAYON, PETGRAPH) ====================
@@ -112,15 +169,28 @@ fn alg_sum(args: Vec<AxValue>) -> AxValue {
     }
 }
 
-fn alg_filter(args: Vec<AxValue>) -> AxValue {
-    // Filter list by predicate (simplified)
-    match args.get(0) {
-        Some(AxValue::Lst(lst)) => {
-            let list = lst.read().unwrap().clone();
-            AxValue::Lst(Arc::new(RwLock::new(list)))
+fn alg_filter(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    // alg.filter(list, native_fn) — user-defined predicates are handled by the
+    // tree-walker's higher-order intercept; VM closures arrive here as natives.
+    use crate::core::oop::AxCallable;
+    Ok(match (args.first(), args.get(1)) {
+        (Some(AxValue::Lst(lst)), Some(AxValue::Fun(callable))) => {
+            let items: Vec<AxValue> = lst.read().unwrap().clone();
+            let kept = match callable.as_ref() {
+                AxCallable::Native { func, .. } => {
+                    let mut kept = Vec::new();
+                    for item in items {
+                        if func(vec![item.clone()])?.is_truthy() { kept.push(item); }
+                    }
+                    kept
+                }
+                AxCallable::UserDefined { .. } => items,
+            };
+            AxValue::Lst(Arc::new(RwLock::new(kept)))
         }
+        (Some(AxValue::Lst(lst)), _) => AxValue::Lst(Arc::new(RwLock::new(lst.read().unwrap().clone()))),
         _ => AxValue::Nil,
-    }
+    })
 }
 
 fn alg_fold(args: Vec<AxValue>) -> AxValue {
@@ -135,27 +205,153 @@ fn alg_fold(args: Vec<AxValue>) -> AxValue {
 }
 
 fn alg_sort(args: Vec<AxValue>) -> AxValue {
-    // Sort a list of numbers
-    match args.get(0) {
+    // Stable sort in AxValue::sort_cmp order
+    match args.first() {
         Some(AxValue::Lst(lst)) => {
             let mut list = lst.read().unwrap().clone();
-            list.sort_by(|a, b| {
-                let a_num = match a {
-                    AxValue::Num(n) => *n,
-                    _ => f64::NEG_INFINITY,
-                };
-                let b_num = match b {
-                    AxValue::Num(n) => *n,
-                    _ => f64::NEG_INFINITY,
-                };
-                a_num.partial_cmp(&b_num).unwrap_or(std::cmp::Ordering::Equal)
-            });
+            list.sort_by(|a, b| a.sort_cmp(b));
             AxValue::Lst(Arc::new(RwLock::new(list)))
         }
         _ => AxValue::Nil,
     }
 }
 
+// alg.sort_by(list, key)    — stable sort on key(item), ascending
+// alg.sort_desc(list, key?) — stable sort, descending; equal items keep their order
+// alg.sort_with(list, cmp)  — stable sort by cmp(a, b): negative, 0 or positive
+//
+// A key is a function of the item, a Str naming a map or instance field, or
+// a Lst of keys compared in turn (`["dept", "name"]`). Keys are computed
+// once per item and compared in sort_cmp order, so strings sort
+// lexicographically and a missing field (nil) sorts first.
+
+/// The keys for `item` under `key`, appended to `out`.
+fn sort_keys(function: &str, key: &AxValue, item: &AxValue, out: &mut Vec<AxValue>, call: &mut impl FnMut(AxValue, Vec<AxValue>) -> Result<AxValue, RuntimeError>) -> Result<(), RuntimeError> {
+    match key {
+        AxValue::Fun(_) => out.push(call(key.clone(), vec![item.clone()])?),
+        AxValue::Str(field) => {
+            let value = match item {
+                AxValue::Map(m) => m.get(field).map(|v| v.value().clone()),
+                AxValue::Instance(inst) => inst.read().unwrap().fields.get(field).map(|v| v.value().clone()),
+                _ => None,
+            };
+            out.push(value.unwrap_or(AxValue::Nil));
+        }
+        AxValue::Lst(keys) => {
+            for key in keys.read().unwrap().iter() {
+                if matches!(key, AxValue::Lst(_)) {
+                    return Err(arg_mismatch(function, 1, "Fun, Str or Lst of keys", Some(key)));
+                }
+                sort_keys(function, key, item, out, call)?;
+            }
+        }
+        other => return Err(arg_mismatch(function, 1, "Fun, Str or Lst of keys", Some(other))),
+    }
+    Ok(())
+}
+
+/// Stable merge sort with a comparator that can fail. `slice::sort_by`
+/// may panic on a comparator that is not a total order, which a script's
+/// can easily be.
+fn merge_sort(mut items: Vec<AxValue>, cmp: &mut impl FnMut(&AxValue, &AxValue) -> Result<std::cmp::Ordering, RuntimeError>) -> Result<Vec<AxValue>, RuntimeError> {
+    if items.len() < 2 {
+        return Ok(items);
+    }
+    let right = merge_sort(items.split_off(items.len() / 2), cmp)?;
+    let left = merge_sort(items, cmp)?;
+    let mut merged = Vec::with_capacity(left.len() + right.len());
+    let (mut left, mut right) = (left.into_iter().peekable(), right.into_iter().peekable());
+    while let (Some(a), Some(b)) = (left.peek(), right.peek()) {
+        // Ties take from the left, which keeps the sort stable
+        let next = if cmp(a, b)?.is_gt() { right.next() } else { left.next() };
+        merged.extend(next);
+    }
+    merged.extend(left);
+    merged.extend(right);
+    Ok(merged)
+}
+
+/// `alg.sort_by`, `alg.sort_desc` and `alg.sort_with`, named by `function`,
+/// calling keys and comparators through `call`.
+pub(crate) fn alg_sort_keyed(function: &str, args: &[AxValue], mut call: impl FnMut(AxValue, Vec<AxValue>) -> Result<AxValue, RuntimeError>) -> Result<AxValue, RuntimeError> {
+    let items = match args.first() {
+        Some(AxValue::Lst(list)) => list.read().unwrap().clone(),
+        other => return Err(arg_mismatch(function, 0, "Lst", other)),
+    };
+    if function == "alg.sort_with" {
+        let cmp = arg_fun(args, 1, function)?;
+        let sorted = merge_sort(items, &mut |a, b| match call(cmp.clone(), vec![a.clone(), b.clone()])? {
+            AxValue::Num(n) => Ok(n.partial_cmp(&0.0).unwrap_or(std::cmp::Ordering::Equal)),
+            other => Err(arg_mismatch(function, 1, "Fun returning Num", Some(&other))),
+        })?;
+        return Ok(AxValue::Lst(Arc::new(RwLock::new(sorted))));
+    }
+    let descending = function == "alg.sort_desc";
+    let mut keyed = Vec::with_capacity(items.len());
+    for item in items {
+        let mut keys = Vec::new();
+        match args.get(1) {
+            None | Some(AxValue::Nil) if descending => keys.push(item.clone()),
+            key => sort_keys(function, key.unwrap_or(&AxValue::Nil), &item, &mut keys, &mut call)?,
+        }
+        keyed.push((keys, item));
+    }
+    keyed.sort_by(|(a, _), (b, _)| {
+        let order = a.iter().zip(b).map(|(x, y)| x.sort_cmp(y)).find(|o| o.is_ne()).unwrap_or(std::cmp::Ordering::Equal);
+        if descending { order.reverse() } else { order }
+    });
+    Ok(AxValue::Lst(Arc::new(RwLock::new(keyed.into_iter().map(|(_, item)| item).collect()))))
+}
+
+fn alg_sort_by(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    alg_sort_keyed("alg.sort_by", &args, |f, a| call_native("alg.sort_by", f, a))
+}
+
+fn alg_sort_desc(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    alg_sort_keyed("alg.sort_desc", &args, |f, a| call_native("alg.sort_desc", f, a))
+}
+
+fn alg_sort_with(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    alg_sort_keyed("alg.sort_with", &args, |f, a| call_native("alg.sort_with", f, a))
+}
+
+/// `alg.unique(list)`: the list without repeats, first occurrences kept,
+/// compared by value as set members are.
+fn alg_unique(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    match args.first() {
+        Some(AxValue::Lst(list)) => Ok(AxValue::Lst(Arc::new(RwLock::new(Set::from_items(list.read().unwrap().clone()).items)))),
+        other => Err(arg_mismatch("alg.unique", 0, "Lst", other)),
+    }
+}
+
+/// `alg.group_by(list, key)`: a map from each key (its display form) to
+/// the items with that key, in list order. The key is a function or a
+/// field name, as for `alg.sort_by`.
+pub(crate) fn alg_group_by_with(args: &[AxValue], mut call: impl FnMut(AxValue, Vec<AxValue>) -> Result<AxValue, RuntimeError>) -> Result<AxValue, RuntimeError> {
+    let items = match args.first() {
+        Some(AxValue::Lst(list)) => list.read().unwrap().clone(),
+        other => return Err(arg_mismatch("alg.group_by", 0, "Lst", other)),
+    };
+    let key = match args.get(1) {
+        Some(key @ (AxValue::Fun(_) | AxValue::Str(_))) => key,
+        other => return Err(arg_mismatch("alg.group_by", 1, "Fun or Str", other)),
+    };
+    let mut groups: HashMap<String, Vec<AxValue>> = HashMap::new();
+    for item in items {
+        let mut keys = Vec::with_capacity(1);
+        sort_keys("alg.group_by", key, &item, &mut keys, &mut call)?;
+        groups.entry(keys[0].display()).or_default().push(item);
+    }
+    let map = DashMap::new();
+    for (label, group) in groups {
+        map.insert(label, AxValue::Lst(Arc::new(RwLock::new(group))));
+    }
+    Ok(AxValue::Map(Arc::new(map)))
+}
+
+fn alg_group_by(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    alg_group_by_with(&args, |f, a| call_native("alg.group_by", f, a))
+}
 
 fn alg_len(args: Vec<AxValue>) -> AxValue {
     match args.first() {
@@ -166,16 +362,16 @@ fn alg_len(args: Vec<AxValue>) -> AxValue {
     }
 }
 
-fn alg_map_fn(args: Vec<AxValue>) -> AxValue {
+fn alg_map_fn(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
     // alg.map(list, native_fn) — only works with native callable (user-defined
     // functions require runtime context; use for-loop for those in scripts)
     use crate::core::oop::AxCallable;
-    match (args.first(), args.get(1)) {
+    Ok(match (args.first(), args.get(1)) {
         (Some(AxValue::Lst(lst)), Some(AxValue::Fun(callable))) => {
             let items: Vec<AxValue> = lst.read().unwrap().clone();
             let results: Vec<AxValue> = match callable.as_ref() {
                 AxCallable::Native { func, .. } => {
-                    items.into_iter().map(|item| func(vec![item])).collect()
+                    items.into_iter().map(|item| func(vec![item])).collect::<Result<_, _>>()?
                 }
                 AxCallable::UserDefined { .. } => {
                     // Cannot call user-defined fns from native context.
@@ -187,7 +383,7 @@ fn alg_map_fn(args: Vec<AxValue>) -> AxValue {
         }
         (Some(AxValue::Lst(lst)), _) => AxValue::Lst(lst.clone()),
         _ => AxValue::Nil,
-    }
+    })
 }
 
 fn alg_min(args: Vec<AxValue>) -> AxValue {
@@ -216,6 +412,391 @@ fn alg_max(args: Vec<AxValue>) -> AxValue {
     }
 }
 
+// alg.pipeline(list) — chainable, lazily evaluated stages over a list:
+//
+//   alg.pipeline(xs).map(f).filter(g).skip(10).take(100).collect()
+//
+// Stage methods (map, filter, take, skip) return a new pipeline and run
+// nothing. Terminals (collect, count, reduce, each, first) stream each item
+// through every stage in turn, so no intermediate list is built, and stop
+// reading the list once a `take` is satisfied. When every stage function is
+// a VM closure, there is no take or skip and the list is long, map and
+// filter run on the rayon pool instead (conf `parallel_pipeline`); the
+// terminal still sees items in list order.
+
+/// Lists shorter than this are not worth splitting across threads.
+const PARALLEL_PIPELINE_MIN: usize = 1024;
+
+#[derive(Clone)]
+enum Stage {
+    Map(AxValue),
+    Filter(AxValue),
+    Take(usize),
+    Skip(usize),
+}
+
+pub(crate) struct Pipeline {
+    source: Arc<RwLock<Vec<AxValue>>>,
+    stages: Vec<Stage>,
+}
+
+type Call<'a> = dyn FnMut(AxValue, Vec<AxValue>) -> Result<AxValue, RuntimeError> + 'a;
+
+impl Pipeline {
+    fn with(&self, stage: Stage) -> AxValue {
+        let mut stages = self.stages.clone();
+        stages.push(stage);
+        pipeline_value(Pipeline { source: Arc::clone(&self.source), stages })
+    }
+
+    /// Pass one item through the stages: `None` if a filter, skip or spent
+    /// take dropped it. `counts` holds one slot per stage (only takes and
+    /// skips use theirs); `exhausted` is set once a take has let through
+    /// its last item.
+    fn apply(&self, mut item: AxValue, counts: &mut [usize], exhausted: &mut bool, call: &mut Call) -> Result<Option<AxValue>, RuntimeError> {
+        for (i, stage) in self.stages.iter().enumerate() {
+            match stage {
+                Stage::Map(f) => item = call(f.clone(), vec![item])?,
+                Stage::Filter(f) => if !call(f.clone(), vec![item.clone()])?.is_truthy() { return Ok(None) },
+                Stage::Skip(n) => {
+                    counts[i] += 1;
+                    if counts[i] <= *n { return Ok(None) }
+                }
+                Stage::Take(n) => {
+                    counts[i] += 1;
+                    if counts[i] >= *n { *exhausted = true }
+                    if counts[i] > *n { return Ok(None) }
+                }
+            }
+        }
+        Ok(Some(item))
+    }
+
+    fn parallel(&self, len: usize) -> bool {
+        len >= PARALLEL_PIPELINE_MIN
+            && self.stages.iter().all(|stage| match stage {
+                Stage::Map(AxValue::Fun(f)) | Stage::Filter(AxValue::Fun(f)) => matches!(f.as_ref(), AxCallable::Native { .. }),
+                _ => false,
+            })
+            && parallel_pipeline_enabled()
+    }
+
+    /// Feed every item that comes out of the last stage to `sink`, which
+    /// returns false to stop early.
+    fn drive(&self, call: &mut Call, mut sink: impl FnMut(AxValue, &mut Call) -> Result<bool, RuntimeError>) -> Result<(), RuntimeError> {
+        if self.stages.iter().any(|stage| matches!(stage, Stage::Take(0))) {
+            return Ok(());
+        }
+        let len = self.source.read().unwrap().len();
+        if self.parallel(len) {
+            let items = self.source.read().unwrap().clone();
+            // Worker threads check gated stages against this run's capabilities
+            let caps = crate::sandbox::active_capabilities();
+            let run = || items.into_par_iter()
+                .map(|item| crate::sandbox::with_capabilities(caps, || {
+                    self.apply(item, &mut [], &mut false, &mut |f, a| call_native("alg.pipeline", f, a))
+                }))
+                .collect::<Result<Vec<_>, _>>();
+            let results = match pipeline_pool() {
+                Some(pool) => pool.install(run),
+                None => run(),
+            }?;
+            for item in results.into_iter().flatten() {
+                if !sink(item, call)? { break }
+            }
+            return Ok(());
+        }
+        let mut counts = vec![0; self.stages.len()];
+        let mut exhausted = false;
+        let mut index = 0;
+        // The list is read an item at a time, so it is never copied
+        while !exhausted {
+            let Some(item) = self.source.read().unwrap().get(index).cloned() else { break };
+            index += 1;
+            if let Some(item) = self.apply(item, &mut counts, &mut exhausted, call)? {
+                if !sink(item, call)? { break }
+            }
+        }
+        Ok(())
+    }
+
+    /// Run a terminal method, calling stage and terminal functions through
+    /// `call`.
+    pub(crate) fn finish(&self, method: &str, args: &[AxValue], call: &mut Call) -> Result<AxValue, RuntimeError> {
+        let function = format!("alg.pipeline.{}", method);
+        match method {
+            "collect" => {
+                let mut items = Vec::new();
+                self.drive(call, |item, _| { items.push(item); Ok(true) })?;
+                Ok(AxValue::Lst(Arc::new(RwLock::new(items))))
+            }
+            "count" => {
+                let mut count = 0;
+                self.drive(call, |_, _| { count += 1; Ok(true) })?;
+                Ok(AxValue::Num(count as f64))
+            }
+            "first" => {
+                let mut first = AxValue::Nil;
+                self.drive(call, |item, _| { first = item; Ok(false) })?;
+                Ok(first)
+            }
+            "each" => {
+                let f = arg_fun(args, 0, &function)?;
+                self.drive(call, |item, call| { call(f.clone(), vec![item])?; Ok(true) })?;
+                Ok(AxValue::Nil)
+            }
+            "reduce" => {
+                let f = arg_fun(args, 0, &function)?;
+                // Without a start value the first item is the accumulator
+                let mut acc = args.get(1).cloned();
+                self.drive(call, |item, call| {
+                    acc = Some(match acc.take() {
+                        Some(acc) => call(f.clone(), vec![acc, item])?,
+                        None => item,
+                    });
+                    Ok(true)
+                })?;
+                Ok(acc.unwrap_or(AxValue::Nil))
+            }
+            _ => unreachable!("{} is not a pipeline terminal", function),
+        }
+    }
+}
+
+fn arg_fun(args: &[AxValue], index: usize, function: &str) -> Result<AxValue, RuntimeError> {
+    match args.get(index) {
+        Some(f @ AxValue::Fun(_)) => Ok(f.clone()),
+        other => Err(arg_mismatch(function, index, "Fun", other)),
+    }
+}
+
+fn arg_count(args: &[AxValue], index: usize, function: &str) -> Result<usize, RuntimeError> {
+    arg_num(args, index, function).map(|n| n.max(0.0) as usize)
+}
+
+/// The pool sized by conf `thread_pool_size`, or `None` for rayon's
+/// global pool (size 0, or a pool that could not be built).
+fn pipeline_pool() -> Option<&'static rayon::ThreadPool> {
+    static POOL: std::sync::OnceLock<Option<rayon::ThreadPool>> = std::sync::OnceLock::new();
+    POOL.get_or_init(|| match crate::conf::AxConf::load().thread_pool_size() {
+        0 => None,
+        n => rayon::ThreadPoolBuilder::new().num_threads(n as usize).build().ok(),
+    }).as_ref()
+}
+
+/// Conf `parallel_pipeline`, read once like the pool size.
+fn parallel_pipeline_enabled() -> bool {
+    static ENABLED: std::sync::OnceLock<bool> = std::sync::OnceLock::new();
+    *ENABLED.get_or_init(|| crate::conf::AxConf::load().parallel_pipeline())
+}
+
+type PipelineEntry = (std::sync::Weak<DashMap<String, AxValue>>, Arc<Pipeline>);
+
+/// Pipeline maps by address, so the tree-walker can run a terminal with
+/// its own calls; entries go when their map is dropped.
+static PIPELINES: std::sync::OnceLock<std::sync::Mutex<HashMap<usize, PipelineEntry>>> = std::sync::OnceLock::new();
+
+fn pipelines() -> std::sync::MutexGuard<'static, HashMap<usize, PipelineEntry>> {
+    PIPELINES.get_or_init(Default::default).lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// The pipeline behind `value`, if it is a map made by `alg.pipeline`.
+pub(crate) fn pipeline_of(value: &AxValue) -> Option<Arc<Pipeline>> {
+    let AxValue::Map(map) = value else { return None };
+    let pipelines = pipelines();
+    let (weak, pipeline) = pipelines.get(&(Arc::as_ptr(map) as usize))?;
+    weak.upgrade().filter(|m| Arc::ptr_eq(m, map)).map(|_| Arc::clone(pipeline))
+}
+
+fn pipeline_value(pipeline: Pipeline) -> AxValue {
+    type Method = fn(&Pipeline, &str, Vec<AxValue>) -> Result<AxValue, RuntimeError>;
+    let pipeline = Arc::new(pipeline);
+    let map = Arc::new(DashMap::new());
+    let methods: [(&str, Method); 9] = [
+        ("map", |p, name, args| Ok(p.with(Stage::Map(arg_fun(&args, 0, name)?)))),
+        ("filter", |p, name, args| Ok(p.with(Stage::Filter(arg_fun(&args, 0, name)?)))),
+        ("take", |p, name, args| Ok(p.with(Stage::Take(arg_count(&args, 0, name)?)))),
+        ("skip", |p, name, args| Ok(p.with(Stage::Skip(arg_count(&args, 0, name)?)))),
+        ("collect", |p, name, args| p.finish("collect", &args, &mut |f, a| call_native(name, f, a))),
+        ("count", |p, name, args| p.finish("count", &args, &mut |f, a| call_native(name, f, a))),
+        ("first", |p, name, args| p.finish("first", &args, &mut |f, a| call_native(name, f, a))),
+        ("each", |p, name, args| p.finish("each", &args, &mut |f, a| call_native(name, f, a))),
+        ("reduce", |p, name, args| p.finish("reduce", &args, &mut |f, a| call_native(name, f, a))),
+    ];
+    for (name, method) in methods {
+        let pipeline = Arc::clone(&pipeline);
+        let function = format!("alg.pipeline.{}", name);
+        map.insert(name.to_string(), AxValue::Fun(Arc::new(AxCallable::native(function.clone(), move |args| method(&pipeline, &function, args)))));
+    }
+    let mut pipelines = pipelines();
+    pipelines.retain(|_, (weak, _)| weak.strong_count() > 0);
+    pipelines.insert(Arc::as_ptr(&map) as usize, (Arc::downgrade(&map), pipeline));
+    AxValue::Map(map)
+}
+
+fn alg_pipeline(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    match args.first() {
+        Some(AxValue::Lst(list)) => Ok(pipeline_value(Pipeline { source: Arc::clone(list), stages: Vec::new() })),
+        other => Err(arg_mismatch("alg.pipeline", 0, "Lst", other)),
+    }
+}
+
+// alg.graph(adjacency?) — a directed graph (petgraph) as a map of methods:
+//
+//   g.add_node(label)             — add a node if new; returns the label
+//   g.add_edge(from, to, w?)      — edge of weight w ≥ 0 (default 1); adds missing nodes
+//   g.nodes()                     — labels in the order they were added
+//   g.shortest_path(from, to)     — {path, cost} by Dijkstra, nil if `to` is unreachable
+//   g.topo_sort()                 — labels with every edge pointing forward, nil on a cycle
+//   g.connected_components()      — lists of labels joined by edges either way
+//   g.to_map()                    — {label: [labels it has edges to]}
+//
+// Labels are Str or Num; a node is known by its display form, so 1 and "1"
+// name the same node. `alg.graph(m)` builds from a map shaped like `to_map`.
+
+struct Graph {
+    graph: petgraph::graph::DiGraph<AxValue, f64>,
+    index: HashMap<String, petgraph::graph::NodeIndex>,
+}
+
+impl Graph {
+    /// The node for `label`, added if new.
+    fn node(&mut self, label: &AxValue) -> petgraph::graph::NodeIndex {
+        let key = label.display();
+        if let Some(&node) = self.index.get(&key) {
+            return node;
+        }
+        let node = self.graph.add_node(label.clone());
+        self.index.insert(key, node);
+        node
+    }
+
+    /// The node named by argument `index`, which must already exist.
+    fn find(&self, args: &[AxValue], index: usize, function: &str) -> Result<petgraph::graph::NodeIndex, RuntimeError> {
+        let label = arg_label(args, index, function)?;
+        self.index.get(&label.display()).copied()
+            .ok_or_else(|| arg_mismatch(function, index, "node of the graph", Some(label)))
+    }
+
+    fn labels(&self, nodes: impl IntoIterator<Item = petgraph::graph::NodeIndex>) -> AxValue {
+        AxValue::Lst(Arc::new(RwLock::new(nodes.into_iter().map(|n| self.graph[n].clone()).collect())))
+    }
+
+    /// Successors in the order their edges were added (petgraph walks
+    /// them newest first).
+    fn successors(&self, node: petgraph::graph::NodeIndex) -> Vec<petgraph::graph::NodeIndex> {
+        let mut successors: Vec<_> = self.graph.neighbors(node).collect();
+        successors.reverse();
+        successors
+    }
+}
+
+fn arg_label<'a>(args: &'a [AxValue], index: usize, function: &str) -> Result<&'a AxValue, RuntimeError> {
+    match args.get(index) {
+        Some(label @ (AxValue::Str(_) | AxValue::Num(_))) => Ok(label),
+        other => Err(arg_mismatch(function, index, "Str or Num", other)),
+    }
+}
+
+fn graph_add_edge(g: &mut Graph, function: &str, args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    let weight = match args.get(2) {
+        None | Some(AxValue::Nil) => 1.0,
+        Some(AxValue::Num(w)) if *w >= 0.0 && w.is_finite() => *w,
+        other => return Err(arg_mismatch(function, 2, "non-negative Num", other)),
+    };
+    let from = g.node(arg_label(&args, 0, function)?);
+    let to = g.node(arg_label(&args, 1, function)?);
+    g.graph.add_edge(from, to, weight);
+    Ok(AxValue::Nil)
+}
+
+fn graph_shortest_path(g: &mut Graph, function: &str, args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    let from = g.find(&args, 0, function)?;
+    let to = g.find(&args, 1, function)?;
+    // A* with a zero heuristic is Dijkstra that also returns the path
+    let found = petgraph::algo::astar(&g.graph, from, |n| n == to, |e| *e.weight(), |_| 0.0);
+    Ok(match found {
+        Some((cost, path)) => {
+            let map = Arc::new(DashMap::new());
+            map.insert("path".to_string(), g.labels(path));
+            map.insert("cost".to_string(), AxValue::Num(cost));
+            AxValue::Map(map)
+        }
+        None => AxValue::Nil,
+    })
+}
+
+fn graph_components(g: &mut Graph, _function: &str, _args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    let mut sets = petgraph::unionfind::UnionFind::new(g.graph.node_count());
+    for edge in g.graph.raw_edges() {
+        sets.union(edge.source().index(), edge.target().index());
+    }
+    // Components in order of their first node, each in node order
+    let mut slots: HashMap<usize, usize> = HashMap::new();
+    let mut components: Vec<Vec<petgraph::graph::NodeIndex>> = Vec::new();
+    for node in g.graph.node_indices() {
+        let slot = *slots.entry(sets.find(node.index())).or_insert_with(|| {
+            components.push(Vec::new());
+            components.len() - 1
+        });
+        components[slot].push(node);
+    }
+    let lists = components.into_iter().map(|nodes| g.labels(nodes)).collect();
+    Ok(AxValue::Lst(Arc::new(RwLock::new(lists))))
+}
+
+fn graph_to_map(g: &mut Graph, _function: &str, _args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    let map = Arc::new(DashMap::new());
+    for node in g.graph.node_indices() {
+        map.insert(g.graph[node].display(), g.labels(g.successors(node)));
+    }
+    Ok(AxValue::Map(map))
+}
+
+fn alg_graph(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    type Method = fn(&mut Graph, &str, Vec<AxValue>) -> Result<AxValue, RuntimeError>;
+    let mut graph = Graph { graph: petgraph::graph::DiGraph::new(), index: HashMap::new() };
+    match args.first() {
+        None | Some(AxValue::Nil) => {}
+        Some(AxValue::Map(adjacency)) => {
+            for (label, targets) in map_entries(adjacency) {
+                let from = graph.node(&AxValue::Str(label));
+                let AxValue::Lst(targets) = targets else {
+                    return Err(arg_mismatch("alg.graph", 0, "Map of Lst", Some(&targets)));
+                };
+                for target in targets.read().unwrap().iter() {
+                    let to = graph.node(arg_label(std::slice::from_ref(target), 0, "alg.graph")?);
+                    graph.graph.add_edge(from, to, 1.0);
+                }
+            }
+        }
+        other => return Err(arg_mismatch("alg.graph", 0, "Map", other)),
+    }
+    let graph = Arc::new(std::sync::Mutex::new(graph));
+    let map = Arc::new(DashMap::new());
+    let methods: [(&str, Method); 7] = [
+        ("add_node", |g, name, args| {
+            let label = arg_label(&args, 0, name)?;
+            g.node(label);
+            Ok(label.clone())
+        }),
+        ("add_edge", graph_add_edge),
+        ("nodes", |g, _, _| Ok(g.labels(g.graph.node_indices()))),
+        ("shortest_path", graph_shortest_path),
+        ("topo_sort", |g, _, _| Ok(petgraph::algo::toposort(&g.graph, None).map_or(AxValue::Nil, |order| g.labels(order)))),
+        ("connected_components", graph_components),
+        ("to_map", graph_to_map),
+    ];
+    for (name, method) in methods {
+        let graph = Arc::clone(&graph);
+        let function = format!("alg.graph.{}", name);
+        map.insert(name.to_string(), AxValue::Fun(Arc::new(AxCallable::native(function.clone(), move |args| {
+            method(&mut graph.lock().unwrap_or_else(|e| e.into_inner()), &function, args)
+        }))));
+    }
+    Ok(AxValue::Map(map))
+}
+
 // ==================== MODULE 2: ANN (REFLECTION, ANNOTATIONS) ====================
 
 fn ann_type_of(args: Vec<AxValue>) -> AxValue {
@@ -257,27 +838,208 @@ fn ann_fields(args: Vec<AxValue>) -> AxValue {
     // Return fields of an object or keys of a map
     match args.get(0) {
         Some(AxValue::Map(map)) => {
-            let keys: Vec<AxValue> = map
-                .iter()
-                .map(|entry| AxValue::Str(entry.key().clone()))
+            let keys: Vec<AxValue> = map_entries(map)
+                .into_iter()
+                .map(|(k, _)| AxValue::Str(k))
                 .collect();
             AxValue::Lst(Arc::new(RwLock::new(keys)))
         }
+        Some(AxValue::Instance(inst)) => {
+            let names = instance_fields(&inst.read().unwrap()).into_iter().map(|(k, _)| AxValue::Str(k)).collect();
+            AxValue::Lst(Arc::new(RwLock::new(names)))
+        }
+        _ => AxValue::Nil,
+    }
+}
+
+fn str_list(names: impl IntoIterator<Item = String>) -> AxValue {
+    AxValue::Lst(Arc::new(RwLock::new(names.into_iter().map(AxValue::Str).collect())))
+}
+
+/// An instance's fields and values: the declared ones in declaration order
+/// (the root class's first), then any added later, sorted.
+fn instance_fields(inst: &crate::core::oop::AxInstance) -> Vec<(String, AxValue)> {
+    let declared = inst.class.field_names();
+    let mut extra: Vec<String> = inst.fields.iter().map(|e| e.key().clone()).filter(|k| !declared.contains(k)).collect();
+    extra.sort();
+    declared.into_iter().chain(extra)
+        .filter_map(|k| inst.fields.get(&k).map(|v| (k.clone(), v.clone())))
+        .collect()
+}
+
+/// A function's declared name (`None` for a lambda) and parameter count,
+/// as far as they are known: natives other than intrinsics, and variadic
+/// ones like `str.fmt`, have no arity.
+fn fun_signature(f: &Arc<AxCallable>) -> (Option<String>, Option<usize>) {
+    match f.as_ref() {
+        AxCallable::UserDefined { name, params, .. } => (name.clone(), Some(params.len())),
+        AxCallable::Native { name, .. } => match crate::vm_core::compiled_signature(f) {
+            Some((name, params)) => (name, Some(params)),
+            None => {
+                let arity = name.split_once('.').and_then(|(m, n)| signature(m, n)).filter(|s| !s.variadic).map(IntrinsicSig::max_args);
+                (Some(name.clone()), arity)
+            }
+        },
+    }
+}
+
+/// The enum whose name evaluates to `map`, and its variants (sorted, with
+/// whether each carries data): every entry must be a variant of one enum,
+/// a unit variant's value or a data variant's constructor.
+fn enum_namespace(map: &DashMap<String, AxValue>) -> Option<(String, Vec<(String, bool)>)> {
+    let mut enum_name: Option<String> = None;
+    let mut variants = Vec::new();
+    for (key, value) in map_entries(map) {
+        let (qualified, data) = match &value {
+            AxValue::EnumVariant(qualified, _) => (qualified.to_string(), false),
+            AxValue::Fun(f) => (fun_signature(f).0?, true),
+            _ => return None,
+        };
+        let (owner, variant) = qualified.rsplit_once('.')?;
+        if variant != key || MODULES.contains(&owner) || enum_name.get_or_insert_with(|| owner.to_string()) != owner {
+            return None;
+        }
+        variants.push((key, data));
+    }
+    variants.sort();
+    Some((enum_name?, variants))
+}
+
+fn ann_describe(args: Vec<AxValue>) -> AxValue {
+    // One map describing any value: its kind, then what reflection knows of it
+    let desc = DashMap::new();
+    let put = |key: &str, value: AxValue| { desc.insert(key.to_string(), value); };
+    let value = args.into_iter().next().unwrap_or(AxValue::Nil);
+    match &value {
+        AxValue::Instance(inst) => {
+            let inst = inst.read().unwrap();
+            put("kind", AxValue::Str("instance".into()));
+            put("class", AxValue::Str(inst.class.name.clone()));
+            let fields = DashMap::new();
+            for (k, v) in instance_fields(&inst) { fields.insert(k, v); }
+            put("fields", AxValue::Map(Arc::new(fields)));
+        }
+        AxValue::Map(map) => {
+            if let Some(class) = crate::core::oop::AxClass::declared(map) {
+                put("kind", AxValue::Str("class".into()));
+                put("name", AxValue::Str(class.name.clone()));
+                put("parent", class.parent.as_ref().map_or(AxValue::Nil, |p| AxValue::Str(p.name.clone())));
+                put("fields", str_list(class.field_names()));
+                put("methods", str_list(class.method_names()));
+                let mut statics: Vec<String> = class.statics.iter().map(|e| e.key().clone()).collect();
+                statics.sort();
+                put("statics", str_list(statics));
+                put("interfaces", str_list(class.interfaces.clone()));
+            } else if let Some((name, variants)) = enum_namespace(map) {
+                put("kind", AxValue::Str("enum".into()));
+                put("name", AxValue::Str(name));
+                let variants = variants.into_iter().map(|(name, data)| {
+                    let variant = DashMap::new();
+                    variant.insert("name".to_string(), AxValue::Str(name));
+                    variant.insert("data".to_string(), AxValue::Bol(data));
+                    AxValue::Map(Arc::new(variant))
+                }).collect();
+                put("variants", AxValue::Lst(Arc::new(RwLock::new(variants))));
+            } else {
+                put("kind", AxValue::Str("map".into()));
+                put("keys", str_list(map_entries(map).into_iter().map(|(k, _)| k)));
+            }
+        }
+        AxValue::EnumVariant(qualified, payload) => {
+            let (owner, variant) = qualified.rsplit_once('.').unwrap_or(("", qualified));
+            put("kind", AxValue::Str("variant".into()));
+            put("enum", AxValue::Str(owner.to_string()));
+            put("variant", AxValue::Str(variant.to_string()));
+            put("value", (**payload).clone());
+        }
+        AxValue::Fun(f) => {
+            let (name, arity) = fun_signature(f);
+            put("kind", AxValue::Str("function".into()));
+            put("name", name.map_or(AxValue::Nil, AxValue::Str));
+            put("arity", arity.map_or(AxValue::Nil, |n| AxValue::Num(n as f64)));
+        }
+        other => {
+            put("kind", AxValue::Str("value".into()));
+            put("type", AxValue::Str(other.type_name().to_string()));
+        }
+    }
+    AxValue::Map(Arc::new(desc))
+}
+
+fn ann_methods(args: Vec<AxValue>) -> AxValue {
+    // Methods an instance (or instances of a class) can call, inherited included
+    let class = match args.first() {
+        Some(AxValue::Instance(inst)) => Some(Arc::clone(&inst.read().unwrap().class)),
+        Some(AxValue::Map(map)) => crate::core::oop::AxClass::declared(map),
+        _ => None,
+    };
+    class.map_or(AxValue::Nil, |c| str_list(c.method_names()))
+}
+
+fn ann_variants(args: Vec<AxValue>) -> AxValue {
+    // Variant names of an enum, sorted
+    match args.first() {
+        Some(AxValue::Map(map)) => enum_namespace(map).map_or(AxValue::Nil, |(_, vs)| str_list(vs.into_iter().map(|(v, _)| v))),
+        _ => AxValue::Nil,
+    }
+}
+
+fn ann_arity(args: Vec<AxValue>) -> AxValue {
+    match args.first() {
+        Some(AxValue::Fun(f)) => fun_signature(f).1.map_or(AxValue::Nil, |n| AxValue::Num(n as f64)),
         _ => AxValue::Nil,
     }
 }
 
+fn ann_implements(args: Vec<AxValue>) -> AxValue {
+    // True if the instance's class (or an ancestor) declares `impl name`
+    match (args.first(), args.get(1)) {
+        (Some(AxValue::Instance(inst)), Some(AxValue::Str(name))) => {
+            AxValue::Bol(inst.read().unwrap().class.implements(name))
+        }
+        _ => AxValue::Bol(false),
+    }
+}
+
 // ==================== MODULE 3: AUT (AUTOMATION, CHRONO, CRONER, NOTIFY) ====================
 
+/// The wall clock, or the fake one under `--frozen-time`.
+fn utc_now() -> DateTime<Utc> {
+    crate::repro::frozen_now_ms()
+        .and_then(|ms| Utc.timestamp_millis_opt(ms as i64).single())
+        .unwrap_or_else(Utc::now)
+}
+
+/// Local time, except that frozen time is UTC so runs agree across machines.
+fn local_now() -> DateTime<FixedOffset> {
+    if crate::repro::time_frozen() {
+        utc_now().fixed_offset()
+    } else {
+        Local::now().fixed_offset()
+    }
+}
+
 fn aut_now(_args: Vec<AxValue>) -> AxValue {
-    let now = Utc::now().timestamp_millis() as f64;
+    let now = utc_now().timestamp_millis() as f64;
     AxValue::Num(now)
 }
 
+/// Sleep for `ms` milliseconds, waking early if the run is interrupted.
+/// Frozen time moves on by the same amount.
+fn sleep_ms(ms: f64) {
+    crate::repro::advance(ms.max(0.0) as u64);
+    let deadline = std::time::Instant::now() + std::time::Duration::from_millis(ms as u64);
+    while !crate::sandbox::interrupted() {
+        let left = deadline.saturating_duration_since(std::time::Instant::now());
+        if left.is_zero() { break; }
+        std::thread::sleep(left.min(std::time::Duration::from_millis(50)));
+    }
+}
+
 fn aut_sleep(args: Vec<AxValue>) -> AxValue {
     match args.get(0) {
         Some(AxValue::Num(ms)) => {
-            std::thread::sleep(std::time::Duration::from_millis(*ms as u64));
+            sleep_ms(*ms);
             AxValue::Nil
         }
         _ => AxValue::Nil,
@@ -285,7 +1047,7 @@ fn aut_sleep(args: Vec<AxValue>) -> AxValue {
 }
 
 fn aut_timestamp(_args: Vec<AxValue>) -> AxValue {
-    let now = Local::now();
+    let now = local_now();
     AxValue::Str(now.to_rfc3339())
 }
 
@@ -306,13 +1068,23 @@ fn aut_delay(args: Vec<AxValue>) -> AxValue {
     // Delayed execution (simplified)
     match args.get(0) {
         Some(AxValue::Num(delay_ms)) => {
-            std::thread::sleep(std::time::Duration::from_millis(*delay_ms as u64));
+            sleep_ms(*delay_ms);
             AxValue::Nil
         }
         _ => AxValue::Nil,
     }
 }
 
+fn aut_on_exit(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    match args.first() {
+        Some(hook @ AxValue::Fun(_)) => {
+            crate::runtime::on_exit(hook.clone());
+            Ok(AxValue::Nil)
+        }
+        other => Err(arg_mismatch("aut.on_exit", 0, "Fun", other)),
+    }
+}
+
 // ==================== MODULE 4: CLR (COLORS, TRUECOLOR) ====================
 
 fn clr_rgb(args: Vec<AxValue>) -> AxValue {
@@ -410,6 +1182,17 @@ fn col_new(_args: Vec<AxValue>) -> AxValue {
     AxValue::Map(Arc::new(DashMap::new()))
 }
 
+/// Largest capacity `col.with_capacity` allocates up front; it is only a hint.
+const MAX_MAP_CAPACITY: usize = 1 << 20;
+
+fn col_with_capacity(args: Vec<AxValue>) -> AxValue {
+    let n = match args.first() {
+        Some(AxValue::Num(n)) if *n > 0.0 => (*n as usize).min(MAX_MAP_CAPACITY),
+        _ => 0,
+    };
+    AxValue::Map(Arc::new(DashMap::with_capacity(n)))
+}
+
 fn col_get(args: Vec<AxValue>) -> AxValue {
     match (&args.get(0), &args.get(1)) {
         (Some(AxValue::Map(map)), Some(AxValue::Str(key))) => {
@@ -419,23 +1202,27 @@ fn col_get(args: Vec<AxValue>) -> AxValue {
     }
 }
 
-fn col_set(args: Vec<AxValue>) -> AxValue {
+fn col_set(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
     match (args.get(0), args.get(1), args.get(2)) {
-        (Some(AxValue::Map(map)), Some(AxValue::Str(key)), Some(val)) => {
+        (Some(target @ AxValue::Map(map)), Some(AxValue::Str(key)), Some(val)) => {
+            target.ensure_mutable()?;
             map.insert(key.clone(), val.clone());
-            AxValue::Nil
+            touch_collections();
+            Ok(AxValue::Nil)
         }
-        _ => AxValue::Nil,
+        _ => Ok(AxValue::Nil),
     }
 }
 
-fn col_remove(args: Vec<AxValue>) -> AxValue {
+fn col_remove(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
     match (&args.get(0), &args.get(1)) {
-        (Some(AxValue::Map(map)), Some(AxValue::Str(key))) => {
+        (Some(target @ AxValue::Map(map)), Some(AxValue::Str(key))) => {
+            target.ensure_mutable()?;
             map.remove(key);
-            AxValue::Nil
+            touch_collections();
+            Ok(AxValue::Nil)
         }
-        _ => AxValue::Nil,
+        _ => Ok(AxValue::Nil),
     }
 }
 
@@ -450,9 +1237,9 @@ fn col_len(args: Vec<AxValue>) -> AxValue {
 fn col_keys(args: Vec<AxValue>) -> AxValue {
     match args.get(0) {
         Some(AxValue::Map(map)) => {
-            let keys: Vec<AxValue> = map
-                .iter()
-                .map(|entry| AxValue::Str(entry.key().clone()))
+            let keys: Vec<AxValue> = map_entries(map)
+                .into_iter()
+                .map(|(k, _)| AxValue::Str(k))
                 .collect();
             AxValue::Lst(Arc::new(RwLock::new(keys)))
         }
@@ -463,9 +1250,9 @@ fn col_keys(args: Vec<AxValue>) -> AxValue {
 fn col_values(args: Vec<AxValue>) -> AxValue {
     match args.get(0) {
         Some(AxValue::Map(map)) => {
-            let vals: Vec<AxValue> = map
-                .iter()
-                .map(|entry| entry.value().clone())
+            let vals: Vec<AxValue> = map_entries(map)
+                .into_iter()
+                .map(|(_, v)| v)
                 .collect();
             AxValue::Lst(Arc::new(RwLock::new(vals)))
         }
@@ -473,13 +1260,247 @@ fn col_values(args: Vec<AxValue>) -> AxValue {
     }
 }
 
-// ==================== MODULE 6: CON (CONCURRENCY, TOKIO ASYNC) ====================
-
-fn con_now(_args: Vec<AxValue>) -> AxValue {
-    let now = Utc::now().timestamp_millis() as f64;
-    AxValue::Num(now)
-}
-
+// col.clone_deep(v) — a copy of v with nothing shared: lists, maps and
+//                     instances are copied all the way down
+// col.merge(a, b)   — a new map with b's entries over a copy of a's; where
+//                     both hold a map under one key, those are merged too
+// col.freeze(v)     — a read-only deep copy of v: changing any list, map or
+//                     instance in it raises AXM_414
+// col.is_frozen(v)  — whether v itself is read-only
+//
+// Copies keep the shape of what they copy: a container reached twice is
+// copied once, so shared parts stay shared and a cycle stays a cycle.
+// Functions are not copied, so methods of a frozen `str.builder` or set
+// still work.
+
+fn deep_copy(value: &AxValue, copies: &mut HashMap<usize, AxValue>, freeze: bool) -> AxValue {
+    match value {
+        AxValue::Lst(list) => {
+            let key = Arc::as_ptr(list) as usize;
+            if let Some(copy) = copies.get(&key) {
+                return copy.clone();
+            }
+            let copy = Arc::new(RwLock::new(Vec::new()));
+            copies.insert(key, AxValue::Lst(Arc::clone(&copy)));
+            let items = list.read().unwrap().clone();
+            *copy.write().unwrap() = items.iter().map(|item| deep_copy(item, copies, freeze)).collect();
+            if freeze {
+                crate::core::value::freeze_container(&copy);
+            }
+            AxValue::Lst(copy)
+        }
+        AxValue::Map(map) => {
+            let key = Arc::as_ptr(map) as usize;
+            if let Some(copy) = copies.get(&key) {
+                return copy.clone();
+            }
+            let copy = Arc::new(DashMap::new());
+            copies.insert(key, AxValue::Map(Arc::clone(&copy)));
+            for (k, v) in map_entries(map) {
+                copy.insert(k, deep_copy(&v, copies, freeze));
+            }
+            if freeze {
+                crate::core::value::freeze_container(&copy);
+            }
+            AxValue::Map(copy)
+        }
+        AxValue::Instance(inst) => {
+            let key = Arc::as_ptr(inst) as usize;
+            if let Some(copy) = copies.get(&key) {
+                return copy.clone();
+            }
+            let class = Arc::clone(&inst.read().unwrap().class);
+            let copy = Arc::new(RwLock::new(crate::core::oop::AxInstance { class, fields: DashMap::new() }));
+            copies.insert(key, AxValue::Instance(Arc::clone(&copy)));
+            let fields: Vec<(String, AxValue)> = inst.read().unwrap().fields.iter().map(|e| (e.key().clone(), e.value().clone())).collect();
+            for (k, v) in fields {
+                let v = deep_copy(&v, copies, freeze);
+                copy.read().unwrap().fields.insert(k, v);
+            }
+            if freeze {
+                crate::core::value::freeze_container(&copy);
+            }
+            AxValue::Instance(copy)
+        }
+        other => other.clone(),
+    }
+}
+
+fn col_clone_deep(args: Vec<AxValue>) -> AxValue {
+    deep_copy(args.first().unwrap_or(&AxValue::Nil), &mut HashMap::new(), false)
+}
+
+fn col_freeze(args: Vec<AxValue>) -> AxValue {
+    deep_copy(args.first().unwrap_or(&AxValue::Nil), &mut HashMap::new(), true)
+}
+
+fn col_is_frozen(args: Vec<AxValue>) -> AxValue {
+    AxValue::Bol(args.first().is_some_and(AxValue::is_frozen))
+}
+
+/// Deeper than this, `col.merge` takes the maps for ones that contain
+/// themselves.
+const MERGE_MAX_DEPTH: usize = 256;
+
+fn merge_maps(a: &DashMap<String, AxValue>, b: &DashMap<String, AxValue>, depth: usize) -> Result<AxValue, RuntimeError> {
+    if depth > MERGE_MAX_DEPTH {
+        return Err(fail("col.merge", FailureKind::Parse, format!("nested deeper than {} levels (does a map contain itself?)", MERGE_MAX_DEPTH)));
+    }
+    let merged = Arc::new(DashMap::new());
+    for (k, v) in map_entries(a) {
+        merged.insert(k, deep_copy(&v, &mut HashMap::new(), false));
+    }
+    for (k, v) in map_entries(b) {
+        let value = match (a.get(&k).map(|e| e.value().clone()), &v) {
+            (Some(AxValue::Map(left)), AxValue::Map(right)) => merge_maps(&left, right, depth + 1)?,
+            _ => deep_copy(&v, &mut HashMap::new(), false),
+        };
+        merged.insert(k, value);
+    }
+    Ok(AxValue::Map(merged))
+}
+
+fn col_merge(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    match (args.first(), args.get(1)) {
+        (Some(AxValue::Map(a)), Some(AxValue::Map(b))) => merge_maps(a, b, 0),
+        (Some(AxValue::Map(_)), other) => Err(arg_mismatch("col.merge", 1, "Map", other)),
+        (other, _) => Err(arg_mismatch("col.merge", 0, "Map", other)),
+    }
+}
+
+// Sets: col.new_set() and col.set_from(list) return a map of methods over
+// one shared set, like str.builder:
+//
+//   s.add(x)        — true if x was not there yet
+//   s.has(x)        — membership
+//   s.remove(x)     — true if x was there
+//   s.len()         — number of members
+//   s.to_list()     — members in the order they were added
+//   s.union(t), s.intersect(t), s.difference(t) — new sets; t is a set or a list
+//
+// Members are compared by value: 1 and "1" differ, [1, 2] equals [1, 2].
+
+#[derive(Default)]
+struct Set {
+    items: Vec<AxValue>,
+    index: HashMap<String, usize>,
+}
+
+/// The identity of a value as a set member: its JSON text, which tells
+/// types apart and lists map keys in order. Values with no JSON form
+/// (functions, NaN) fall back to their display.
+fn member_key(value: &AxValue) -> String {
+    value_to_json(value, 0).map(|json| json.to_string()).unwrap_or_else(|_| format!("\u{0}{}", value.display()))
+}
+
+impl Set {
+    fn from_items(items: impl IntoIterator<Item = AxValue>) -> Set {
+        let mut set = Set::default();
+        for item in items {
+            set.add(item);
+        }
+        set
+    }
+
+    fn add(&mut self, item: AxValue) -> bool {
+        let key = member_key(&item);
+        if self.index.contains_key(&key) {
+            return false;
+        }
+        self.index.insert(key, self.items.len());
+        self.items.push(item);
+        true
+    }
+
+    fn has(&self, item: &AxValue) -> bool {
+        self.index.contains_key(&member_key(item))
+    }
+
+    fn remove(&mut self, item: &AxValue) -> bool {
+        let Some(at) = self.index.remove(&member_key(item)) else { return false };
+        self.items.remove(at);
+        for slot in self.index.values_mut() {
+            if *slot > at { *slot -= 1 }
+        }
+        true
+    }
+}
+
+/// The members of a set, or the items of a list, passed as argument 0.
+fn set_operand(function: &str, args: &[AxValue]) -> Result<Vec<AxValue>, RuntimeError> {
+    match args.first() {
+        Some(AxValue::Lst(list)) => Ok(list.read().unwrap().clone()),
+        Some(AxValue::Map(map)) => match map.get("to_list").map(|f| f.value().clone()) {
+            Some(to_list @ AxValue::Fun(_)) => match call_native(function, to_list, vec![])? {
+                AxValue::Lst(list) => Ok(list.read().unwrap().clone()),
+                other => Err(arg_mismatch(function, 0, "set or Lst", Some(&other))),
+            },
+            _ => Err(arg_mismatch(function, 0, "set or Lst", args.first())),
+        },
+        other => Err(arg_mismatch(function, 0, "set or Lst", other)),
+    }
+}
+
+fn set_member(args: &[AxValue]) -> AxValue {
+    args.first().cloned().unwrap_or(AxValue::Nil)
+}
+
+fn set_value(set: Set) -> AxValue {
+    type Method = fn(&std::sync::Mutex<Set>, &str, Vec<AxValue>) -> Result<AxValue, RuntimeError>;
+    let set = Arc::new(std::sync::Mutex::new(set));
+    let map = Arc::new(DashMap::new());
+    let methods: [(&str, Method); 8] = [
+        ("add", |s, _, args| Ok(AxValue::Bol(s.lock().unwrap().add(set_member(&args))))),
+        ("has", |s, _, args| Ok(AxValue::Bol(s.lock().unwrap().has(&set_member(&args))))),
+        ("remove", |s, _, args| Ok(AxValue::Bol(s.lock().unwrap().remove(&set_member(&args))))),
+        ("len", |s, _, _| Ok(AxValue::Num(s.lock().unwrap().items.len() as f64))),
+        ("to_list", |s, _, _| Ok(AxValue::Lst(Arc::new(RwLock::new(s.lock().unwrap().items.clone()))))),
+        // The operand is read before this set is locked, so `s.union(s)` works
+        ("union", |s, name, args| {
+            let other = set_operand(name, &args)?;
+            let mut union = Set::from_items(s.lock().unwrap().items.clone());
+            for item in other {
+                union.add(item);
+            }
+            Ok(set_value(union))
+        }),
+        ("intersect", |s, name, args| {
+            let other = Set::from_items(set_operand(name, &args)?);
+            let items = s.lock().unwrap().items.iter().filter(|item| other.has(item)).cloned().collect::<Vec<_>>();
+            Ok(set_value(Set::from_items(items)))
+        }),
+        ("difference", |s, name, args| {
+            let other = Set::from_items(set_operand(name, &args)?);
+            let items = s.lock().unwrap().items.iter().filter(|item| !other.has(item)).cloned().collect::<Vec<_>>();
+            Ok(set_value(Set::from_items(items)))
+        }),
+    ];
+    for (name, method) in methods {
+        let set = Arc::clone(&set);
+        let function = format!("col.set.{}", name);
+        map.insert(name.to_string(), AxValue::Fun(Arc::new(AxCallable::native(function.clone(), move |args| method(&set, &function, args)))));
+    }
+    AxValue::Map(map)
+}
+
+fn col_new_set(_args: Vec<AxValue>) -> AxValue {
+    set_value(Set::default())
+}
+
+fn col_set_from(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    match args.first() {
+        Some(AxValue::Lst(list)) => Ok(set_value(Set::from_items(list.read().unwrap().clone()))),
+        other => Err(arg_mismatch("col.set_from", 0, "Lst", other)),
+    }
+}
+
+// ==================== MODULE 6: CON (CONCURRENCY, TOKIO ASYNC) ====================
+
+fn con_now(_args: Vec<AxValue>) -> AxValue {
+    let now = utc_now().timestamp_millis() as f64;
+    AxValue::Num(now)
+}
+
 fn con_spawn(args: Vec<AxValue>) -> AxValue {
     // Placeholder: spawn async task
     match args.get(0) {
@@ -505,76 +1526,64 @@ fn con_mutex_new(_args: Vec<AxValue>) -> AxValue {
 
 // ==================== MODULE 7: CSV (CSV PARSING, STREAMING) ====================
 
-fn csv_parse(args: Vec<AxValue>) -> AxValue {
-    match args.get(0) {
-        Some(AxValue::Str(s)) => {
-            let mut reader = csv::Reader::from_reader(s.as_bytes());
-            let mut rows = Vec::new();
-
-            for result in reader.deserialize::<HashMap<String, String>>() {
-                match result {
-                    Ok(record) => {
-                        let map = Arc::new(DashMap::new());
-                        for (k, v) in record {
-                            map.insert(k, AxValue::Str(v));
-                        }
-                        rows.push(AxValue::Map(map));
-                    }
-                    Err(_) => continue,
-                }
-            }
+fn csv_parse(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    let s = arg_str(&args, 0, "csv.parse")?;
+    let mut reader = csv::Reader::from_reader(s.as_bytes());
+    let mut rows = Vec::new();
 
-            AxValue::Lst(Arc::new(RwLock::new(rows)))
+    for result in reader.deserialize::<HashMap<String, String>>() {
+        let record = result.map_err(|e| fail("csv.parse", FailureKind::Parse, e))?;
+        let map = Arc::new(DashMap::new());
+        for (k, v) in record {
+            map.insert(k, AxValue::Str(v));
         }
-        _ => AxValue::Nil,
+        rows.push(AxValue::Map(map));
     }
+
+    Ok(AxValue::Lst(Arc::new(RwLock::new(rows))))
 }
 
-fn csv_write(args: Vec<AxValue>) -> AxValue {
+fn csv_write(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
     // Write list of maps as CSV
-    match (&args.get(0), &args.get(1)) {
-        (Some(AxValue::Lst(lst)), Some(AxValue::Str(path))) => {
-            let list = lst.read().unwrap();
-            if list.is_empty() {
-                return AxValue::Nil;
-            }
+    let path = arg_str(&args, 1, "csv.write")?;
+    let lst = match args.first() {
+        Some(AxValue::Lst(lst)) => lst,
+        other => return Err(arg_mismatch("csv.write", 0, "Lst", other)),
+    };
+    let list = lst.read().unwrap();
+    if list.is_empty() {
+        return Ok(AxValue::Nil);
+    }
 
-            if let Ok(mut writer) = csv::Writer::from_path(path) {
-                for row in list.iter() {
-                    if let AxValue::Map(map) = row {
-                        let record: HashMap<String, String> = map
-                            .iter()
-                            .map(|entry| {
-                                (
-                                    entry.key().clone(),
-                                    format!("{}", entry.value().display()),
-                                )
-                            })
-                            .collect();
-                        let _ = writer.serialize(record);
-                    }
-                }
-                let _ = writer.flush();
-            }
-            AxValue::Nil
+    let mut writer = csv::Writer::from_path(path).map_err(|e| fail("csv.write", FailureKind::Io, e))?;
+    for row in list.iter() {
+        if let AxValue::Map(map) = row {
+            let record: HashMap<String, String> = map
+                .iter()
+                .map(|entry| {
+                    (
+                        entry.key().clone(),
+                        entry.value().display().to_string(),
+                    )
+                })
+                .collect();
+            writer.serialize(record).map_err(|e| fail("csv.write", FailureKind::Io, e))?;
         }
-        _ => AxValue::Nil,
     }
+    writer.flush().map_err(|e| fail("csv.write", FailureKind::Io, e))?;
+    Ok(AxValue::Nil)
 }
 
-fn csv_headers(args: Vec<AxValue>) -> AxValue {
-    match args.get(0) {
-        Some(AxValue::Str(s)) => {
-            let mut reader = csv::Reader::from_reader(s.as_bytes());
-            let headers: Vec<AxValue> = reader
-                .headers()
-                .ok()
-                .map(|h| h.iter().map(|s| AxValue::Str(s.to_string())).collect())
-                .unwrap_or_default();
-            AxValue::Lst(Arc::new(RwLock::new(headers)))
-        }
-        _ => AxValue::Nil,
-    }
+fn csv_headers(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    let s = arg_str(&args, 0, "csv.headers")?;
+    let mut reader = csv::Reader::from_reader(s.as_bytes());
+    let headers: Vec<AxValue> = reader
+        .headers()
+        .map_err(|e| fail("csv.headers", FailureKind::Parse, e))?
+        .iter()
+        .map(|s| AxValue::Str(s.to_string()))
+        .collect();
+    Ok(AxValue::Lst(Arc::new(RwLock::new(headers))))
 }
 
 // ==================== MODULE 8: DFM (DATAFRAMES, POLARS) ====================
@@ -714,194 +1723,499 @@ fn env_all(_args: Vec<AxValue>) -> AxValue {
     AxValue::Map(map)
 }
 
-// ==================== MODULE 10: GIT (GIT OPERATIONS, GIT2) ====================
+/// A variable name `std::env::set_var` accepts: non-empty, no `=` or NUL.
+fn env_key<'a>(function: &str, index: usize, key: &'a AxValue) -> Result<&'a str, RuntimeError> {
+    match key {
+        AxValue::Str(k) if !k.is_empty() && !k.contains(['=', '\0']) => Ok(k),
+        AxValue::Str(k) => Err(fail(function, FailureKind::Parse, format!("'{}' is not a valid variable name", k))),
+        other => Err(arg_mismatch(function, index, "Str", Some(other))),
+    }
+}
 
-fn git_branch(args: Vec<AxValue>) -> AxValue {
-    match args.get(0) {
-        Some(AxValue::Str(path)) => {
-            match Repository::open(path) {
-                Ok(repo) => {
-                    if let Ok(head) = repo.head() {
-                        if let Some(name) = head.shorthand() {
-                            return AxValue::Str(name.to_string());
-                        }
-                    }
-                }
-                Err(_) => {}
-            }
-            AxValue::Nil
+fn env_unset(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    let key = env_key("env.unset", 0, args.first().unwrap_or(&AxValue::Nil))?;
+    std::env::remove_var(key);
+    Ok(AxValue::Nil)
+}
+
+/// One `.env` line. Values other than plain words are double-quoted, with
+/// the escapes dotenvy reads back.
+fn dotenv_line(key: &str, value: &str) -> String {
+    let plain = value.chars().all(|c| c.is_ascii_alphanumeric() || "_-./:@,+".contains(c));
+    if plain {
+        return format!("{}={}", key, value);
+    }
+    let mut quoted = String::with_capacity(value.len() + 2);
+    for c in value.chars() {
+        match c {
+            '\\' => quoted.push_str("\\\\"),
+            '"' => quoted.push_str("\\\""),
+            '$' => quoted.push_str("\\$"),
+            '\n' => quoted.push_str("\\n"),
+            c => quoted.push(c),
         }
-        _ => AxValue::Nil,
     }
+    format!("{}=\"{}\"", key, quoted)
 }
 
-fn git_log(args: Vec<AxValue>) -> AxValue {
-    match args.get(0) {
-        Some(AxValue::Str(path)) => {
-            match Repository::open(path) {
-                Ok(repo) => {
-                    if let Ok(revwalk) = repo.revwalk() {
-                        let mut commits = Vec::new();
-                        for oid in revwalk.take(10) {
-                            if let Ok(id) = oid {
-                                if let Ok(commit) = repo.find_commit(id) {
-                                    let map = Arc::new(DashMap::new());
-                                    map.insert("id".to_string(), AxValue::Str(id.to_string()));
-                                    map.insert("message".to_string(), AxValue::Str(commit.message().unwrap_or("").to_string()));
-                                    commits.push(AxValue::Map(map));
-                                }
-                            }
-                        }
-                        return AxValue::Lst(Arc::new(RwLock::new(commits)));
-                    }
-                }
-                Err(_) => {}
-            }
-            AxValue::Nil
-        }
-        _ => AxValue::Nil,
+fn env_save(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    let path = arg_str(&args, 0, "env.save")?;
+    let Some(AxValue::Map(vars)) = args.get(1) else {
+        return Err(arg_mismatch("env.save", 1, "Map", args.get(1)));
+    };
+    let mut entries: Vec<(String, AxValue)> = vars.iter().map(|e| (e.key().clone(), e.value().clone())).collect();
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+    let mut text = String::new();
+    for (key, value) in &entries {
+        let key = env_key("env.save", 1, &AxValue::Str(key.clone()))?.to_string();
+        let value = if matches!(value, AxValue::Nil) { String::new() } else { value.display() };
+        text.push_str(&dotenv_line(&key, &value));
+        text.push('\n');
     }
+    fs::write(path, text)
+        .map(|_| AxValue::Nil)
+        .map_err(|e| fail("env.save", FailureKind::Io, format!("{}: {}", path, e)))
 }
 
-fn git_status(args: Vec<AxValue>) -> AxValue {
-    match args.get(0) {
-        Some(AxValue::Str(path)) => {
-            match Repository::open(path) {
-                Ok(repo) => {
-                    match repo.statuses(None) {
-                        Ok(statuses) => {
-                            let mut status_list = Vec::new();
-                            for entry in statuses.iter() {
-                                let path = entry.path().unwrap_or("unknown");
-                                let status = if entry.status().contains(Status::WT_MODIFIED) {
-                                    "modified"
-                                } else if entry.status().contains(Status::INDEX_NEW) {
-                                    "new"
-                                } else {
-                                    "unknown"
-                                };
-                                let map = Arc::new(DashMap::new());
-                                map.insert("path".to_string(), AxValue::Str(path.to_string()));
-                                map.insert("status".to_string(), AxValue::Str(status.to_string()));
-                                status_list.push(AxValue::Map(map));
-                            }
-                            return AxValue::Lst(Arc::new(RwLock::new(status_list)));
-                        }
-                        Err(_) => {}
-                    }
-                }
-                Err(_) => {}
-            }
-            AxValue::Nil
+/// `env.with(vars, fn)`: set `vars` (nil unsets one), call `fn`, then put
+/// every variable back as it was, whether or not `fn` raised.
+pub(crate) fn env_with_with(args: &[AxValue], mut call: impl FnMut(AxValue, Vec<AxValue>) -> Result<AxValue, RuntimeError>) -> Result<AxValue, RuntimeError> {
+    let Some(AxValue::Map(vars)) = args.first() else {
+        return Err(arg_mismatch("env.with", 0, "Map", args.first()));
+    };
+    let Some(body @ AxValue::Fun(_)) = args.get(1) else {
+        return Err(arg_mismatch("env.with", 1, "Fun", args.get(1)));
+    };
+    let mut overrides = Vec::with_capacity(vars.len());
+    for entry in vars.iter() {
+        let key = env_key("env.with", 0, &AxValue::Str(entry.key().clone()))?.to_string();
+        overrides.push((key, entry.value().clone()));
+    }
+    let saved: Vec<(String, Option<std::ffi::OsString>)> = overrides.iter()
+        .map(|(key, _)| (key.clone(), std::env::var_os(key)))
+        .collect();
+    for (key, value) in &overrides {
+        match value {
+            AxValue::Nil => std::env::remove_var(key),
+            value => std::env::set_var(key, value.display()),
         }
-        _ => AxValue::Nil,
     }
+    let result = call(body.clone(), vec![]);
+    for (key, previous) in saved {
+        match previous {
+            Some(value) => std::env::set_var(&key, value),
+            None => std::env::remove_var(&key),
+        }
+    }
+    result
 }
 
-fn git_clone(args: Vec<AxValue>) -> AxValue {
-    match (&args.get(0), &args.get(1)) {
-        (Some(AxValue::Str(url)), Some(AxValue::Str(path))) => {
-            match Repository::clone(url, Path::new(path)) {
-                Ok(_) => AxValue::Bol(true),
-                Err(_) => AxValue::Bol(false),
-            }
-        }
-        _ => AxValue::Nil,
+fn env_with(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    env_with_with(&args, |f, a| call_native("env.with", f, a))
+}
+
+// ==================== MODULE 10: GIT (GIT OPERATIONS, GIT2) ====================
+
+#[cfg(feature = "native")]
+fn git_branch(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    let path = arg_str(&args, 0, "git.branch")?;
+    let repo = Repository::open(path).map_err(|e| fail("git.branch", FailureKind::Io, e))?;
+    // An unborn or detached HEAD has no branch name — that is Nil, not a failure
+    Ok(repo.head().ok()
+        .and_then(|head| head.shorthand().map(|name| AxValue::Str(name.to_string())))
+        .unwrap_or(AxValue::Nil))
+}
+
+#[cfg(feature = "native")]
+fn git_log(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    let path = arg_str(&args, 0, "git.log")?;
+    let repo = Repository::open(path).map_err(|e| fail("git.log", FailureKind::Io, e))?;
+    let mut revwalk = repo.revwalk().map_err(|e| fail("git.log", FailureKind::Io, e))?;
+    revwalk.push_head().map_err(|e| fail("git.log", FailureKind::Io, e))?;
+    let mut commits = Vec::new();
+    for oid in revwalk.take(10) {
+        let id = oid.map_err(|e| fail("git.log", FailureKind::Io, e))?;
+        let commit = repo.find_commit(id).map_err(|e| fail("git.log", FailureKind::Io, e))?;
+        let map = Arc::new(DashMap::new());
+        map.insert("id".to_string(), AxValue::Str(id.to_string()));
+        map.insert("message".to_string(), AxValue::Str(commit.message().unwrap_or("").to_string()));
+        commits.push(AxValue::Map(map));
+    }
+    Ok(AxValue::Lst(Arc::new(RwLock::new(commits))))
+}
+
+#[cfg(feature = "native")]
+fn git_status(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    let path = arg_str(&args, 0, "git.status")?;
+    let repo = Repository::open(path).map_err(|e| fail("git.status", FailureKind::Io, e))?;
+    let statuses = repo.statuses(None).map_err(|e| fail("git.status", FailureKind::Io, e))?;
+    let mut status_list = Vec::new();
+    for entry in statuses.iter() {
+        let path = entry.path().unwrap_or("unknown");
+        let status = if entry.status().contains(Status::WT_MODIFIED) {
+            "modified"
+        } else if entry.status().contains(Status::INDEX_NEW) {
+            "new"
+        } else {
+            "unknown"
+        };
+        let map = Arc::new(DashMap::new());
+        map.insert("path".to_string(), AxValue::Str(path.to_string()));
+        map.insert("status".to_string(), AxValue::Str(status.to_string()));
+        status_list.push(AxValue::Map(map));
     }
+    Ok(AxValue::Lst(Arc::new(RwLock::new(status_list))))
+}
+
+#[cfg(feature = "native")]
+fn git_clone(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    let url = arg_str(&args, 0, "git.clone")?;
+    let path = arg_str(&args, 1, "git.clone")?;
+    Repository::clone(url, Path::new(path))
+        .map(|_| AxValue::Bol(true))
+        .map_err(|e| fail("git.clone", FailureKind::Network, e))
 }
 
 // ==================== MODULE 11: IOO (BUFFERED I/O, FILESYSTEM) ====================
 
-fn ioo_read(args: Vec<AxValue>) -> AxValue {
-    match args.get(0) {
-        Some(AxValue::Str(path)) => {
-            match fs::read_to_string(path) {
-                Ok(content) => AxValue::Str(content),
-                Err(_) => AxValue::Nil,
-            }
-        }
-        _ => AxValue::Nil,
-    }
+fn ioo_read(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    let path = arg_str(&args, 0, "ioo.read")?;
+    fs::read_to_string(path)
+        .map(AxValue::Str)
+        .map_err(|e| fail("ioo.read", FailureKind::Io, format!("{}: {}", path, e)))
 }
 
-fn ioo_write(args: Vec<AxValue>) -> AxValue {
-    match (&args.get(0), &args.get(1)) {
-        (Some(AxValue::Str(path)), Some(val)) => {
-            let content = val.display().to_string();
-            match fs::write(path, content) {
-                Ok(_) => AxValue::Nil,
-                Err(_) => AxValue::Nil,
-            }
-        }
-        _ => AxValue::Nil,
+fn ioo_write(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    let path = arg_str(&args, 0, "ioo.write")?;
+    let content = args.get(1).map(|v| v.display()).unwrap_or_default();
+    fs::write(path, content)
+        .map(|_| AxValue::Nil)
+        .map_err(|e| fail("ioo.write", FailureKind::Io, format!("{}: {}", path, e)))
+}
+
+fn ioo_append(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    use std::io::Write;
+    let path = arg_str(&args, 0, "ioo.append")?;
+    let content = args.get(1).map(|v| v.display()).unwrap_or_default();
+    std::fs::OpenOptions::new()
+        .append(true)
+        .open(path)
+        .and_then(|mut file| writeln!(file, "{}", content))
+        .map(|_| AxValue::Nil)
+        .map_err(|e| fail("ioo.append", FailureKind::Io, format!("{}: {}", path, e)))
+}
+
+fn ioo_exists(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    let path = arg_str(&args, 0, "ioo.exists")?;
+    Ok(AxValue::Bol(Path::new(path).exists()))
+}
+
+fn ioo_delete(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    // true = removed, false = nothing there; a failed removal raises
+    let path = arg_str(&args, 0, "ioo.delete")?;
+    let result = if Path::new(path).is_file() {
+        fs::remove_file(path)
+    } else if Path::new(path).is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        return Ok(AxValue::Bol(false));
+    };
+    result
+        .map(|_| AxValue::Bol(true))
+        .map_err(|e| fail("ioo.delete", FailureKind::Io, format!("{}: {}", path, e)))
+}
+
+fn ioo_list(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    let path = arg_str(&args, 0, "ioo.list")?;
+    let entries = fs::read_dir(path)
+        .map_err(|e| fail("ioo.list", FailureKind::Io, format!("{}: {}", path, e)))?;
+    let files: Vec<AxValue> = entries
+        .filter_map(|entry| {
+            entry
+                .ok()
+                .map(|e| AxValue::Str(e.file_name().to_string_lossy().to_string()))
+        })
+        .collect();
+    Ok(AxValue::Lst(Arc::new(RwLock::new(files))))
+}
+
+/// Permission bits as octal text, e.g. `644`. Windows only knows the
+/// read-only flag, reported as `444` or `666`.
+fn mode_text(permissions: &fs::Permissions) -> String {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        format!("{:o}", permissions.mode() & 0o7777)
+    }
+    #[cfg(not(unix))]
+    {
+        if permissions.readonly() { "444".to_string() } else { "666".to_string() }
     }
 }
 
-fn ioo_append(args: Vec<AxValue>) -> AxValue {
-    match (&args.get(0), &args.get(1)) {
-        (Some(AxValue::Str(path)), Some(val)) => {
-            use std::io::Write;
-            let content = val.display().to_string();
-            if let Ok(mut file) = std::fs::OpenOptions::new()
-                .write(true)
-                .append(true)
-                .open(path)
-            {
-                let _ = writeln!(file, "{}", content);
-            }
-            AxValue::Nil
-        }
-        _ => AxValue::Nil,
+fn ioo_stat(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    let path = arg_str(&args, 0, "ioo.stat")?;
+    let io_fail = |e: std::io::Error| fail("ioo.stat", FailureKind::Io, format!("{}: {}", path, e));
+    // A symlink reports what it points to; is_symlink says it is one
+    let meta = fs::metadata(path).map_err(io_fail)?;
+    let is_symlink = fs::symlink_metadata(path).map_err(io_fail)?.file_type().is_symlink();
+    let map = Arc::new(DashMap::new());
+    map.insert("size".to_string(), AxValue::Num(meta.len() as f64));
+    map.insert("is_dir".to_string(), AxValue::Bol(meta.is_dir()));
+    map.insert("is_file".to_string(), AxValue::Bol(meta.is_file()));
+    map.insert("is_symlink".to_string(), AxValue::Bol(is_symlink));
+    map.insert("permissions".to_string(), AxValue::Str(mode_text(&meta.permissions())));
+    map.insert("readonly".to_string(), AxValue::Bol(meta.permissions().readonly()));
+    let modified = meta.modified().map_err(io_fail)?;
+    map.insert("modified".to_string(), AxValue::Str(DateTime::<Local>::from(modified).to_rfc3339()));
+    let unix = match modified.duration_since(std::time::UNIX_EPOCH) {
+        Ok(after) => after.as_secs_f64(),
+        Err(before) => -before.duration().as_secs_f64(),
+    };
+    map.insert("modified_unix".to_string(), AxValue::Num(unix));
+    Ok(AxValue::Map(map))
+}
+
+fn ioo_chmod(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    let path = arg_str(&args, 0, "ioo.chmod")?;
+    let mode = match args.get(1) {
+        Some(AxValue::Str(text)) => u32::from_str_radix(text, 8)
+            .map_err(|_| fail("ioo.chmod", FailureKind::Parse, format!("'{}' is not an octal mode like \"644\"", text)))?,
+        Some(AxValue::Num(n)) if n.fract() == 0.0 && *n >= 0.0 => *n as u32,
+        other => return Err(arg_mismatch("ioo.chmod", 1, "octal Str or Num", other)),
+    };
+    if mode > 0o7777 {
+        return Err(fail("ioo.chmod", FailureKind::Parse, format!("{:o} is not a file mode", mode)));
     }
+    let io_fail = |e: std::io::Error| fail("ioo.chmod", FailureKind::Io, format!("{}: {}", path, e));
+    let mut permissions = fs::metadata(path).map_err(io_fail)?.permissions();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        permissions.set_mode(mode);
+    }
+    #[cfg(not(unix))]
+    permissions.set_readonly(mode & 0o200 == 0);
+    fs::set_permissions(path, permissions).map(|_| AxValue::Nil).map_err(io_fail)
 }
 
-fn ioo_exists(args: Vec<AxValue>) -> AxValue {
-    match args.get(0) {
-        Some(AxValue::Str(path)) => AxValue::Bol(Path::new(path).exists()),
-        _ => AxValue::Nil,
+fn ioo_touch(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    let path = arg_str(&args, 0, "ioo.touch")?;
+    // Creates a missing file; never truncates an existing one
+    fs::OpenOptions::new().create(true).append(true).open(path)
+        .and_then(|file| file.set_modified(std::time::SystemTime::now()))
+        .map(|_| AxValue::Nil)
+        .map_err(|e| fail("ioo.touch", FailureKind::Io, format!("{}: {}", path, e)))
+}
+
+fn ioo_symlink(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    let target = arg_str(&args, 0, "ioo.symlink")?;
+    let link = arg_str(&args, 1, "ioo.symlink")?;
+    #[cfg(unix)]
+    let made = std::os::unix::fs::symlink(target, link);
+    #[cfg(windows)]
+    let made = if Path::new(link).parent().map_or(Path::new(target).to_path_buf(), |dir| dir.join(target)).is_dir() {
+        std::os::windows::fs::symlink_dir(target, link)
+    } else {
+        std::os::windows::fs::symlink_file(target, link)
+    };
+    #[cfg(not(any(unix, windows)))]
+    let made: std::io::Result<()> = Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "symlinks are not supported on this platform"));
+    made.map(|_| AxValue::Nil)
+        .map_err(|e| fail("ioo.symlink", FailureKind::Io, format!("{} -> {}: {}", link, target, e)))
+}
+
+/// Finalizer for `ioo.temp_file` and `ioo.temp_dir`: remove what is left.
+fn ioo_remove_temp(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    ioo_delete(args).map(|_| AxValue::Nil)
+}
+
+/// Register `path` to be removed when the enclosing `res.scope`, or the
+/// run, ends.
+fn defer_removal(path: std::path::PathBuf) -> AxValue {
+    let handle = AxValue::Str(path.display().to_string());
+    let finalizer = AxValue::Fun(Arc::new(AxCallable::native("ioo.remove_temp", ioo_remove_temp)));
+    crate::runtime::defer(handle.clone(), finalizer);
+    handle
+}
+
+fn ioo_temp_file(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    let suffix = match args.first() {
+        None | Some(AxValue::Nil) => "",
+        Some(_) => arg_str(&args, 0, "ioo.temp_file")?,
+    };
+    let (_, path) = tempfile::Builder::new().prefix("axiom-").suffix(suffix).tempfile()
+        .and_then(|file| file.keep().map_err(|e| e.error))
+        .map_err(|e| fail("ioo.temp_file", FailureKind::Io, e))?;
+    Ok(defer_removal(path))
+}
+
+fn ioo_temp_dir(_args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    let dir = tempfile::Builder::new().prefix("axiom-").tempdir()
+        .map_err(|e| fail("ioo.temp_dir", FailureKind::Io, e))?;
+    Ok(defer_removal(dir.keep()))
+}
+
+/// Fill a temp file next to `path` with `fill`, then rename it over `path`,
+/// so readers see either the old contents or all of the new ones.
+fn replace_atomically(path: &Path, permissions: Option<fs::Permissions>, fill: impl FnOnce(&mut fs::File) -> std::io::Result<()>) -> std::io::Result<()> {
+    // The temp file must be on the same filesystem for the rename to be atomic
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let mut temp = tempfile::Builder::new().prefix(".axiom-").tempfile_in(dir)?;
+    fill(temp.as_file_mut())?;
+    temp.as_file().sync_all()?;
+    if let Some(permissions) = permissions {
+        temp.as_file().set_permissions(permissions)?;
     }
+    temp.persist(path).map(|_| ()).map_err(|e| e.error)
 }
 
-fn ioo_delete(args: Vec<AxValue>) -> AxValue {
-    match args.get(0) {
-        Some(AxValue::Str(path)) => {
-            if Path::new(path).is_file() {
-                match fs::remove_file(path) {
-                    Ok(_) => AxValue::Bol(true),
-                    Err(_) => AxValue::Bol(false),
-                }
-            } else if Path::new(path).is_dir() {
-                match fs::remove_dir_all(path) {
-                    Ok(_) => AxValue::Bol(true),
-                    Err(_) => AxValue::Bol(false),
-                }
-            } else {
-                AxValue::Bol(false)
-            }
+fn ioo_write_atomic(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    use std::io::Write;
+    let path = arg_str(&args, 0, "ioo.write_atomic")?;
+    let content = args.get(1).map(|v| v.display()).unwrap_or_default();
+    // Replacing a file keeps its permissions
+    let permissions = fs::metadata(path).ok().map(|m| m.permissions());
+    replace_atomically(Path::new(path), permissions, |file| file.write_all(content.as_bytes()))
+        .map(|_| AxValue::Nil)
+        .map_err(|e| fail("ioo.write_atomic", FailureKind::Io, format!("{}: {}", path, e)))
+}
+
+fn ioo_copy(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    let src = arg_str(&args, 0, "ioo.copy")?;
+    let dst = arg_str(&args, 1, "ioo.copy")?;
+    fs::File::open(src)
+        .and_then(|mut from| {
+            let permissions = from.metadata()?.permissions();
+            replace_atomically(Path::new(dst), Some(permissions), |to| std::io::copy(&mut from, to).map(|_| ()))
+        })
+        .map(|_| AxValue::Nil)
+        .map_err(|e| fail("ioo.copy", FailureKind::Io, format!("{} -> {}: {}", src, dst, e)))
+}
+
+// ioo.mmap(path) maps a file into memory and returns a view of its bytes: a
+// map of methods over one shared mapping, so a multi-GB file is searched and
+// sliced without being read into a string. Offsets count bytes from the
+// start of the view; a negative offset counts back from its end.
+//
+//   v.len()                 — length in bytes
+//   v.at(i)                 — the byte at i (0-255), nil outside the view
+//   v.slice(start, [end])   — a view of bytes start..end; nothing is copied
+//   v.find(needle, [from])  — offset of the first needle at or after from, or nil
+//   v.rfind(needle)         — offset of the last needle, or nil
+//   v.count(needle)         — how many times needle occurs, not overlapping
+//   v.text()                — the bytes as a Str (invalid UTF-8 becomes U+FFFD)
+//   v.bytes()               — the bytes as a list of numbers
+//
+// A needle is a Str or a list of byte values. The file must not shrink
+// while a view of it is alive: touching the lost pages kills the process.
+
+#[cfg(feature = "native")]
+struct MmapView {
+    map: Arc<memmap2::Mmap>,
+    start: usize,
+    end: usize,
+}
+
+#[cfg(feature = "native")]
+impl MmapView {
+    fn bytes(&self) -> &[u8] {
+        &self.map[self.start..self.end]
+    }
+
+    /// Argument `index` as a position in 0..=len, `default` when absent.
+    fn offset(&self, args: &[AxValue], index: usize, function: &str, default: usize) -> Result<usize, RuntimeError> {
+        let len = self.end - self.start;
+        match args.get(index) {
+            None | Some(AxValue::Nil) => Ok(default),
+            Some(AxValue::Num(n)) if n.fract() == 0.0 && *n < 0.0 => Ok(len.saturating_sub(n.abs() as usize)),
+            Some(AxValue::Num(n)) if n.fract() == 0.0 => Ok((*n as usize).min(len)),
+            other => Err(arg_mismatch(function, index, "whole Num", other)),
         }
-        _ => AxValue::Nil,
     }
 }
 
-fn ioo_list(args: Vec<AxValue>) -> AxValue {
-    match args.get(0) {
-        Some(AxValue::Str(path)) => {
-            match fs::read_dir(path) {
-                Ok(entries) => {
-                    let files: Vec<AxValue> = entries
-                        .filter_map(|entry| {
-                            entry
-                                .ok()
-                                .map(|e| AxValue::Str(e.file_name().to_string_lossy().to_string()))
-                        })
-                        .collect();
-                    AxValue::Lst(Arc::new(RwLock::new(files)))
-                }
-                Err(_) => AxValue::Nil,
-            }
-        }
-        _ => AxValue::Nil,
+#[cfg(feature = "native")]
+fn mmap_needle(args: &[AxValue], function: &str) -> Result<Vec<u8>, RuntimeError> {
+    let needle = match args.first() {
+        Some(AxValue::Str(s)) => s.as_bytes().to_vec(),
+        Some(AxValue::Lst(items)) => items.read().unwrap().iter()
+            .map(|v| match v {
+                AxValue::Num(n) if n.fract() == 0.0 && (0.0..=255.0).contains(n) => Ok(*n as u8),
+                _ => Err(arg_mismatch(function, 0, "list of byte values", args.first())),
+            })
+            .collect::<Result<_, _>>()?,
+        other => return Err(arg_mismatch(function, 0, "Str or list of bytes", other)),
+    };
+    if needle.is_empty() {
+        return Err(arg_mismatch(function, 0, "non-empty needle", args.first()));
     }
+    Ok(needle)
+}
+
+/// A search result: the offset, or nil when the needle was not found.
+#[cfg(feature = "native")]
+fn mmap_found(offset: Option<usize>) -> AxValue {
+    offset.map_or(AxValue::Nil, |i| AxValue::Num(i as f64))
+}
+
+#[cfg(feature = "native")]
+fn mmap_view(view: MmapView) -> AxValue {
+    type Method = fn(&MmapView, &str, Vec<AxValue>) -> Result<AxValue, RuntimeError>;
+    let view = Arc::new(view);
+    let methods: [(&str, Method); 8] = [
+        ("len", |v, _, _| Ok(AxValue::Num(v.bytes().len() as f64))),
+        ("at", |v, name, args| {
+            let len = v.bytes().len() as f64;
+            let i = match args.first() {
+                Some(AxValue::Num(n)) if n.fract() == 0.0 => if *n < 0.0 { len + n } else { *n },
+                other => return Err(arg_mismatch(name, 0, "whole Num", other)),
+            };
+            Ok(if (0.0..len).contains(&i) { AxValue::Num(v.bytes()[i as usize] as f64) } else { AxValue::Nil })
+        }),
+        ("slice", |v, name, args| {
+            let start = v.offset(&args, 0, name, 0)?;
+            let end = v.offset(&args, 1, name, v.bytes().len())?.max(start);
+            Ok(mmap_view(MmapView { map: Arc::clone(&v.map), start: v.start + start, end: v.start + end }))
+        }),
+        ("find", |v, name, args| {
+            let needle = mmap_needle(&args, name)?;
+            let from = v.offset(&args, 1, name, 0)?;
+            Ok(mmap_found(memchr::memmem::find(&v.bytes()[from..], &needle).map(|i| from + i)))
+        }),
+        ("rfind", |v, name, args| Ok(mmap_found(memchr::memmem::rfind(v.bytes(), &mmap_needle(&args, name)?)))),
+        ("count", |v, name, args| {
+            let needle = mmap_needle(&args, name)?;
+            Ok(AxValue::Num(memchr::memmem::find_iter(v.bytes(), &needle).count() as f64))
+        }),
+        ("text", |v, _, _| Ok(AxValue::Str(String::from_utf8_lossy(v.bytes()).into_owned()))),
+        ("bytes", |v, _, _| {
+            let bytes = v.bytes().iter().map(|b| AxValue::Num(*b as f64)).collect();
+            Ok(AxValue::Lst(Arc::new(RwLock::new(bytes))))
+        }),
+    ];
+    let map = Arc::new(DashMap::new());
+    for (name, method) in methods {
+        let view = Arc::clone(&view);
+        let function = format!("ioo.mmap.{}", name);
+        map.insert(name.to_string(), AxValue::Fun(Arc::new(AxCallable::native(function.clone(), move |args| method(&view, &function, args)))));
+    }
+    AxValue::Map(map)
+}
+
+#[cfg(feature = "native")]
+fn ioo_mmap(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    let path = arg_str(&args, 0, "ioo.mmap")?;
+    let io_fail = |e: std::io::Error| fail("ioo.mmap", FailureKind::Io, format!("{}: {}", path, e));
+    let file = fs::File::open(path).map_err(io_fail)?;
+    // SAFETY: the mapping is read-only and outlives every slice taken from
+    // it; the file shrinking underneath is the documented hazard above.
+    let map = unsafe { memmap2::Mmap::map(&file) }.map_err(io_fail)?;
+    let end = map.len();
+    Ok(mmap_view(MmapView { map: Arc::new(map), start: 0, end }))
 }
 
 // ==================== MODULE 12: JSN (JSON OPERATIONS) ====================
@@ -952,55 +2266,256 @@ fn jsn_get(args: Vec<AxValue>) -> AxValue {
     }
 }
 
+// Script values as JSON. Lists, maps, numbers, strings, booleans and nil
+// map onto JSON directly; an instance is an object of its fields tagged
+// with "$class", and an enum variant {"$enum": "Shape.Circle", "value": 2}
+// (no "value" for a unit variant). Reading one back needs its class
+// declared; enum variants need nothing.
+
+/// Deeper than this is taken for a list or map that contains itself.
+const JSN_MAX_DEPTH: usize = 256;
+
+fn value_to_json(value: &AxValue, depth: usize) -> Result<serde_json::Value, RuntimeError> {
+    use serde_json::Value as J;
+    if depth > JSN_MAX_DEPTH {
+        return Err(fail("jsn.from_value", FailureKind::Parse, format!("nested deeper than {} levels (does a list or map contain itself?)", JSN_MAX_DEPTH)));
+    }
+    Ok(match value {
+        AxValue::Nil => J::Null,
+        AxValue::Bol(b) => J::Bool(*b),
+        AxValue::Num(n) => {
+            let number = if n.fract() == 0.0 && n.abs() < 9007199254740992.0 {
+                serde_json::Number::from(*n as i64)
+            } else {
+                serde_json::Number::from_f64(*n).ok_or_else(|| fail("jsn.from_value", FailureKind::Parse, format!("{} has no JSON form", n)))?
+            };
+            J::Number(number)
+        }
+        AxValue::Str(s) => J::String(s.clone()),
+        AxValue::Lst(items) => J::Array(items.read().unwrap().iter().map(|v| value_to_json(v, depth + 1)).collect::<Result<_, _>>()?),
+        AxValue::Map(map) => {
+            let mut object = serde_json::Map::new();
+            for (k, v) in map_entries(map) {
+                object.insert(k, value_to_json(&v, depth + 1)?);
+            }
+            J::Object(object)
+        }
+        AxValue::Instance(inst) => {
+            let inst = inst.read().unwrap();
+            let mut object = serde_json::Map::new();
+            object.insert("$class".to_string(), J::String(inst.class.name.clone()));
+            for (k, v) in instance_fields(&inst) {
+                object.insert(k, value_to_json(&v, depth + 1)?);
+            }
+            J::Object(object)
+        }
+        AxValue::EnumVariant(qualified, payload) => {
+            let mut object = serde_json::Map::new();
+            object.insert("$enum".to_string(), J::String(qualified.to_string()));
+            if !matches!(**payload, AxValue::Nil) {
+                object.insert("value".to_string(), value_to_json(payload, depth + 1)?);
+            }
+            J::Object(object)
+        }
+        AxValue::Fun(_) | AxValue::Obj(_) => {
+            return Err(fail("jsn.from_value", FailureKind::Parse, format!("a {} has no JSON form", value.type_name())));
+        }
+    })
+}
+
+fn json_to_value(json: serde_json::Value) -> Result<AxValue, RuntimeError> {
+    use serde_json::Value as J;
+    Ok(match json {
+        J::Null => AxValue::Nil,
+        J::Bool(b) => AxValue::Bol(b),
+        J::Number(n) => AxValue::Num(n.as_f64().unwrap_or(f64::NAN)),
+        J::String(s) => AxValue::Str(s),
+        J::Array(items) => AxValue::Lst(Arc::new(RwLock::new(items.into_iter().map(json_to_value).collect::<Result<_, _>>()?))),
+        J::Object(mut object) => {
+            if let Some(J::String(class_name)) = object.remove("$class") {
+                let class = crate::core::oop::AxClass::declared_named(&class_name)
+                    .ok_or_else(|| fail("jsn.to_value", FailureKind::Parse, format!("no class '{}' is declared", class_name)))?;
+                let inst = crate::core::oop::AxInstance::new(class);
+                for (k, v) in object {
+                    inst.fields.insert(k, json_to_value(v)?);
+                }
+                return Ok(AxValue::Instance(Arc::new(RwLock::new(inst))));
+            }
+            if let Some(J::String(qualified)) = object.remove("$enum") {
+                if !qualified.contains('.') {
+                    return Err(fail("jsn.to_value", FailureKind::Parse, format!("'{}' is not an Enum.Variant name", qualified)));
+                }
+                let payload = object.remove("value").map(json_to_value).transpose()?.unwrap_or(AxValue::Nil);
+                return Ok(AxValue::EnumVariant(Arc::from(qualified), Box::new(payload)));
+            }
+            let map = DashMap::new();
+            for (k, v) in object {
+                map.insert(k, json_to_value(v)?);
+            }
+            AxValue::Map(Arc::new(map))
+        }
+    })
+}
+
+fn jsn_from_value(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    let json = value_to_json(args.first().unwrap_or(&AxValue::Nil), 0)?;
+    let pretty = matches!(args.get(1), Some(AxValue::Bol(true)));
+    let text = if pretty { serde_json::to_string_pretty(&json) } else { serde_json::to_string(&json) };
+    text.map(AxValue::Str).map_err(|e| fail("jsn.from_value", FailureKind::Parse, e))
+}
+
+fn jsn_to_value(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    let text = arg_str(&args, 0, "jsn.to_value")?;
+    let json = serde_json::from_str(text).map_err(|e| fail("jsn.to_value", FailureKind::Parse, e))?;
+    json_to_value(json)
+}
+
 // ==================== MODULE 13: LOG (LOGGING & PROGRESS) ====================
 
-fn log_progress(args: Vec<AxValue>) -> AxValue {
-    match args.get(0) {
-        Some(AxValue::Num(total)) => {
-            let total_u64 = *total as u64;
-            let pb = ProgressBar::new(total_u64);
-            pb.set_style(ProgressStyle::default_bar()
-                .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} {msg}")
-                .unwrap());
-            for _ in 0..total_u64 {
-                pb.inc(1);
-                std::thread::sleep(std::time::Duration::from_millis(5));
-            }
-            pb.finish_with_message("✓ complete");
-            AxValue::Nil
-        }
-        _ => AxValue::Nil,
-    }
+// Progress bars are handles: log.progress_new(total) → {progress_id, total}.
+// Every bar joins one MultiProgress so concurrent bars redraw together
+// instead of fighting over the cursor; finished bars leave the table.
+
+static PROGRESS: std::sync::Mutex<Vec<Option<ProgressBar>>> = std::sync::Mutex::new(Vec::new());
+static MULTI_PROGRESS: std::sync::OnceLock<MultiProgress> = std::sync::OnceLock::new();
+
+fn progress_bar(args: &[AxValue], function: &str) -> Result<ProgressBar, RuntimeError> {
+    let id = match args.first() {
+        Some(AxValue::Map(m)) => m.get("progress_id").and_then(|v| match v.value() {
+            AxValue::Num(n) => Some(*n as usize),
+            _ => None,
+        }),
+        _ => None,
+    };
+    id.and_then(|id| PROGRESS.lock().unwrap().get(id).cloned().flatten())
+        .ok_or_else(|| arg_mismatch(function, 0, "open handle from log.progress_new", args.first()))
+}
+
+fn log_progress_new(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    let total = arg_num(&args, 0, "log.progress_new")?.max(0.0) as u64;
+    let pb = MULTI_PROGRESS.get_or_init(MultiProgress::new).add(ProgressBar::new(total));
+    pb.set_style(ProgressStyle::default_bar()
+        .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} {msg}")
+        .unwrap()
+        .progress_chars("=> "));
+    if let Some(AxValue::Str(msg)) = args.get(1) {
+        pb.set_message(msg.clone());
+    }
+    let mut bars = PROGRESS.lock().unwrap();
+    bars.push(Some(pb));
+    let map = Arc::new(DashMap::new());
+    map.insert("progress_id".to_string(), AxValue::Num((bars.len() - 1) as f64));
+    map.insert("total".to_string(), AxValue::Num(total as f64));
+    Ok(AxValue::Map(map))
+}
+
+fn log_progress_inc(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    let pb = progress_bar(&args, "log.progress_inc")?;
+    let n = match args.get(1) {
+        None => 1.0,
+        Some(_) => arg_num(&args, 1, "log.progress_inc")?,
+    };
+    pb.inc(n.max(0.0) as u64);
+    Ok(AxValue::Num(pb.position() as f64))
+}
+
+fn log_progress_msg(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    let pb = progress_bar(&args, "log.progress_msg")?;
+    pb.set_message(args.get(1).map(|v| v.display()).unwrap_or_default());
+    Ok(AxValue::Nil)
+}
+
+fn log_progress_done(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    let pb = progress_bar(&args, "log.progress_done")?;
+    match args.get(1) {
+        Some(msg) => pb.finish_with_message(msg.display()),
+        None => pb.finish(),
+    }
+    if let Some(AxValue::Map(m)) = args.first() {
+        if let Some(AxValue::Num(id)) = m.get("progress_id").map(|v| v.value().clone()) {
+            if let Some(slot) = PROGRESS.lock().unwrap().get_mut(id as usize) {
+                *slot = None;
+            }
+        }
+    }
+    Ok(AxValue::Nil)
+}
+
+// Records are built from all arguments joined with spaces; routing, levels,
+// formats and scopes live in crate::logger.
+
+fn log_message(args: &[AxValue]) -> String {
+    args.iter().map(|a| a.display()).collect::<Vec<_>>().join(" ")
+}
+
+fn log_trace(args: Vec<AxValue>) -> AxValue {
+    logger::log(Level::Trace, &log_message(&args));
+    AxValue::Nil
+}
+
+fn log_debug(args: Vec<AxValue>) -> AxValue {
+    logger::log(Level::Debug, &log_message(&args));
+    AxValue::Nil
+}
+
+fn log_info(args: Vec<AxValue>) -> AxValue {
+    logger::log(Level::Info, &log_message(&args));
+    AxValue::Nil
+}
+
+fn log_warn(args: Vec<AxValue>) -> AxValue {
+    logger::log(Level::Warn, &log_message(&args));
+    AxValue::Nil
+}
+
+fn log_error(args: Vec<AxValue>) -> AxValue {
+    logger::log(Level::Error, &log_message(&args));
+    AxValue::Nil
+}
+
+fn log_set_level(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    let level = Level::from_name(arg_str(&args, 0, "log.set_level")?)
+        .ok_or_else(|| arg_mismatch("log.set_level", 0, Level::NAMES, args.first()))?;
+    logger::set_level(level);
+    Ok(AxValue::Nil)
+}
+
+fn log_level(_args: Vec<AxValue>) -> AxValue {
+    AxValue::Str(logger::level().name().to_string())
+}
+
+fn log_set_format(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    let format = logger::Format::from_name(arg_str(&args, 0, "log.set_format")?)
+        .ok_or_else(|| arg_mismatch("log.set_format", 0, "\"text\" or \"json\"", args.first()))?;
+    logger::set_format(format);
+    Ok(AxValue::Nil)
 }
 
-fn log_info(args: Vec<AxValue>) -> AxValue {
-    match args.get(0) {
-        Some(val) => {
-            println!("[INFO] {}", val.display());
-            AxValue::Nil
-        }
-        None => AxValue::Nil,
+/// log.to_file(path, max_mb = 10) — log.to_file(nil) returns to the console.
+fn log_to_file(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    if matches!(args.first(), Some(AxValue::Nil)) {
+        logger::to_console();
+        return Ok(AxValue::Nil);
     }
+    let path = arg_str(&args, 0, "log.to_file")?;
+    let max_mb = match args.get(1) {
+        None => 10.0,
+        Some(_) => arg_num(&args, 1, "log.to_file")?,
+    };
+    logger::to_file(Path::new(path), (max_mb.max(0.0) * 1024.0 * 1024.0) as u64)
+        .map_err(|e| fail("log.to_file", FailureKind::Io, format!("{}: {}", path, e)))?;
+    Ok(AxValue::Nil)
 }
 
-fn log_warn(args: Vec<AxValue>) -> AxValue {
-    match args.get(0) {
-        Some(val) => {
-            eprintln!("[WARN] {}", val.display());
-            AxValue::Nil
-        }
-        None => AxValue::Nil,
-    }
+/// log.scope(name) opens a nested scope until log.end(); the runtime also
+/// accepts log.scope(name, fn) and closes the scope when fn returns.
+fn log_scope(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    logger::push_scope(arg_str(&args, 0, "log.scope")?);
+    Ok(AxValue::Str(logger::current_scope()))
 }
 
-fn log_error(args: Vec<AxValue>) -> AxValue {
-    match args.get(0) {
-        Some(val) => {
-            eprintln!("[ERROR] {}", val.display());
-            AxValue::Nil
-        }
-        None => AxValue::Nil,
-    }
+fn log_end(_args: Vec<AxValue>) -> AxValue {
+    logger::pop_scope().map(AxValue::Str).unwrap_or(AxValue::Nil)
 }
 
 // ==================== MODULE 14: MTH (MATHEMATICS) ====================
@@ -1085,44 +2600,29 @@ fn mth_log10(args: Vec<AxValue>) -> AxValue {
 
 // ==================== MODULE 15: NET (NETWORKING) ====================
 
-fn net_get(args: Vec<AxValue>) -> AxValue {
-    match args.get(0) {
-        Some(AxValue::Str(url)) => {
-            let rt = tokio::runtime::Runtime::new().unwrap();
-            let res = rt.block_on(async move {
-                match reqwest::get(url).await {
-                    Ok(resp) => resp.text().await.unwrap_or_default(),
-                    Err(_) => String::new(),
-                }
-            });
-            if res.is_empty() {
-                AxValue::Nil
-            } else {
-                AxValue::Str(res)
-            }
-        }
-        _ => AxValue::Nil,
-    }
+#[cfg(feature = "native")]
+fn net_get(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    let url = arg_str(&args, 0, "net.get")?;
+    let rt = tokio::runtime::Runtime::new().map_err(|e| fail("net.get", FailureKind::Io, e))?;
+    rt.block_on(async move {
+        let resp = reqwest::get(url).await?;
+        resp.text().await
+    })
+    .map(AxValue::Str)
+    .map_err(|e| fail("net.get", FailureKind::Network, e))
 }
 
-fn net_post(args: Vec<AxValue>) -> AxValue {
-    match (&args.get(0), &args.get(1)) {
-        (Some(AxValue::Str(url)), Some(AxValue::Str(body))) => {
-            let rt = tokio::runtime::Runtime::new().unwrap();
-            let res = rt.block_on(async move {
-                match reqwest::Client::new().post(url).body(body.clone()).send().await {
-                    Ok(resp) => resp.text().await.unwrap_or_default(),
-                    Err(_) => String::new(),
-                }
-            });
-            if res.is_empty() {
-                AxValue::Nil
-            } else {
-                AxValue::Str(res)
-            }
-        }
-        _ => AxValue::Nil,
-    }
+#[cfg(feature = "native")]
+fn net_post(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    let url = arg_str(&args, 0, "net.post")?;
+    let body = arg_str(&args, 1, "net.post")?.to_string();
+    let rt = tokio::runtime::Runtime::new().map_err(|e| fail("net.post", FailureKind::Io, e))?;
+    rt.block_on(async move {
+        let resp = reqwest::Client::new().post(url).body(body).send().await?;
+        resp.text().await
+    })
+    .map(AxValue::Str)
+    .map_err(|e| fail("net.post", FailureKind::Network, e))
 }
 
 // ==================== MODULE 16: NUM (NUMERICS) ====================
@@ -1163,8 +2663,114 @@ fn num_range_array(args: Vec<AxValue>) -> AxValue {
     }
 }
 
+/// `num.parse(text)`: a decimal number (`-1.5`, `2e3`), or a whole one
+/// written `0x1f`, `0o17` or `0b101`, with surrounding whitespace allowed.
+/// Nil for anything else, including `inf` and `nan`.
+fn num_parse(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    let text = arg_str(&args, 0, "num.parse")?.trim();
+    let (sign, digits) = match text.strip_prefix('-') {
+        Some(rest) => (-1.0, rest),
+        None => (1.0, text.strip_prefix('+').unwrap_or(text)),
+    };
+    let prefixed = [("0x", 16), ("0X", 16), ("0o", 8), ("0O", 8), ("0b", 2), ("0B", 2)].iter()
+        .find_map(|(prefix, base)| digits.strip_prefix(prefix).map(|d| (d, *base)));
+    let value = match prefixed {
+        Some((d, base)) => parse_radix(d, base).map(|n| sign * n),
+        None if digits.starts_with(|c: char| c.is_ascii_digit() || c == '.') => text.parse::<f64>().ok().filter(|n| n.is_finite()),
+        None => None,
+    };
+    Ok(value.map_or(AxValue::Nil, AxValue::Num))
+}
+
+/// Digits (no sign or prefix) in `base`, as long as they fit an i64.
+fn parse_radix(digits: &str, base: u32) -> Option<f64> {
+    if digits.is_empty() || digits.starts_with(['+', '-']) {
+        return None;
+    }
+    i64::from_str_radix(digits, base).ok().map(|n| n as f64)
+}
+
+/// `num.parse_int(text, base?)`: a whole number in `base` (2 to 36,
+/// default 10), optionally signed and, for 16, 8 and 2, prefixed with
+/// `0x`, `0o` or `0b`. Nil if the text is not one.
+fn num_parse_int(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    let text = arg_str(&args, 0, "num.parse_int")?.trim();
+    let base = match args.get(1) {
+        None | Some(AxValue::Nil) => 10,
+        Some(AxValue::Num(b)) if (2.0..=36.0).contains(b) && b.fract() == 0.0 => *b as u32,
+        other => return Err(arg_mismatch("num.parse_int", 1, "whole Num base from 2 to 36", other)),
+    };
+    let (sign, digits) = match text.strip_prefix('-') {
+        Some(rest) => (-1.0, rest),
+        None => (1.0, text.strip_prefix('+').unwrap_or(text)),
+    };
+    let prefix = match base { 16 => "0x", 8 => "0o", 2 => "0b", _ => "" };
+    let digits = match digits.get(..2) {
+        Some(p) if !prefix.is_empty() && p.eq_ignore_ascii_case(prefix) => &digits[2..],
+        _ => digits,
+    };
+    Ok(parse_radix(digits, base).map_or(AxValue::Nil, |n| AxValue::Num(sign * n)))
+}
+
+/// `num.to_hex` / `to_bin` / `to_oct`: a whole number's digits in that
+/// base, with a `-` for a negative one and no prefix.
+fn num_to_base(args: &[AxValue], function: &str, kind: crate::core::format::Kind) -> Result<AxValue, RuntimeError> {
+    let n = arg_num(args, 0, function)?;
+    let spec = crate::core::format::Spec { kind, ..Default::default() };
+    match spec.apply(&AxValue::Num(n)) {
+        Ok(digits) => Ok(AxValue::Str(digits)),
+        Err(_) => Err(arg_mismatch(function, 0, "whole Num", args.first())),
+    }
+}
+
+fn num_to_hex(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    num_to_base(&args, "num.to_hex", crate::core::format::Kind::Hex)
+}
+
+fn num_to_bin(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    num_to_base(&args, "num.to_bin", crate::core::format::Kind::Binary)
+}
+
+fn num_to_oct(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    num_to_base(&args, "num.to_oct", crate::core::format::Kind::Octal)
+}
+
+/// `num.format(n, decimals?, separator?)`: `n` rounded to `decimals`
+/// places (as `out` shows it when nil) with its whole part grouped in
+/// thousands by `separator` (default ","): `1,234,567.89`.
+fn num_format(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    let n = arg_num(&args, 0, "num.format")?;
+    let text = match args.get(1) {
+        None | Some(AxValue::Nil) => AxValue::Num(n).display(),
+        Some(AxValue::Num(d)) if (0.0..=100.0).contains(d) && d.fract() == 0.0 => format!("{:.*}", *d as usize, n),
+        other => return Err(arg_mismatch("num.format", 1, "whole Num of decimals from 0 to 100", other)),
+    };
+    let separator = match args.get(2) {
+        None | Some(AxValue::Nil) => ",",
+        Some(AxValue::Str(s)) => s.as_str(),
+        other => return Err(arg_mismatch("num.format", 2, "Str", other)),
+    };
+    if !n.is_finite() {
+        return Ok(AxValue::Str(text));
+    }
+    let (sign, unsigned) = text.strip_prefix('-').map_or(("", text.as_str()), |rest| ("-", rest));
+    let (whole, fraction) = unsigned.split_once('.').map_or((unsigned, None), |(w, f)| (w, Some(f)));
+    let mut grouped = String::with_capacity(text.len() + whole.len() / 3 * separator.len());
+    for (i, digit) in whole.chars().enumerate() {
+        if i > 0 && (whole.len() - i) % 3 == 0 {
+            grouped.push_str(separator);
+        }
+        grouped.push(digit);
+    }
+    Ok(AxValue::Str(match fraction {
+        Some(f) => format!("{}{}.{}", sign, grouped, f),
+        None => format!("{}{}", sign, grouped),
+    }))
+}
+
 // ==================== MODULE 17: PLT (PLOTTING) ====================
 
+#[cfg(feature = "native")]
 fn plt_scatter(args: Vec<AxValue>) -> AxValue {
     match args.get(0) {
         Some(AxValue::Str(path)) => {
@@ -1181,6 +2787,7 @@ fn plt_scatter(args: Vec<AxValue>) -> AxValue {
     }
 }
 
+#[cfg(feature = "native")]
 fn plt_line(args: Vec<AxValue>) -> AxValue {
     match args.get(0) {
         Some(AxValue::Str(path)) => {
@@ -1221,17 +2828,146 @@ fn pth_walk(args: Vec<AxValue>) -> AxValue {
 }
 
 fn pth_join(args: Vec<AxValue>) -> AxValue {
-    match (&args.get(0), &args.get(1)) {
-        (Some(AxValue::Str(a)), Some(AxValue::Str(b))) => {
-            let path = if a.ends_with('/') || a.ends_with('\\') {
-                format!("{}{}", a, b)
-            } else {
-                format!("{}/{}", a, b)
-            };
-            AxValue::Str(path)
+    // Path::push uses the platform separator and keeps a trailing one from
+    // doubling; a later absolute part replaces what came before it
+    let mut path = std::path::PathBuf::new();
+    for part in &args {
+        match part {
+            AxValue::Str(part) => path.push(part),
+            _ => return AxValue::Nil,
+        }
+    }
+    if args.len() < 2 {
+        return AxValue::Nil;
+    }
+    AxValue::Str(path.display().to_string())
+}
+
+fn path_value(path: &Path) -> AxValue {
+    AxValue::Str(path.display().to_string())
+}
+
+fn pth_base(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    let path = arg_str(&args, 0, "pth.base")?;
+    Ok(Path::new(path).file_name().map_or(AxValue::Nil, |name| AxValue::Str(name.to_string_lossy().into_owned())))
+}
+
+fn pth_dir(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    let path = arg_str(&args, 0, "pth.dir")?;
+    Ok(Path::new(path).parent().map_or(AxValue::Nil, path_value))
+}
+
+fn pth_ext(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    let path = arg_str(&args, 0, "pth.ext")?;
+    Ok(Path::new(path).extension().map_or(AxValue::Nil, |ext| AxValue::Str(ext.to_string_lossy().into_owned())))
+}
+
+/// `path` made absolute against the working directory, with `.` and `..`
+/// resolved by name. Symlinks are left alone.
+fn absolute_path(function: &str, path: &str) -> Result<std::path::PathBuf, RuntimeError> {
+    use std::path::Component;
+    let absolute = std::path::absolute(path).map_err(|e| fail(function, FailureKind::Io, format!("{}: {}", path, e)))?;
+    let mut clean = std::path::PathBuf::new();
+    for component in absolute.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => { clean.pop(); }
+            other => clean.push(other),
+        }
+    }
+    Ok(clean)
+}
+
+fn pth_abs(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    let path = arg_str(&args, 0, "pth.abs")?;
+    Ok(path_value(&absolute_path("pth.abs", path)?))
+}
+
+fn pth_rel(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    use std::path::Component;
+    let from = absolute_path("pth.rel", arg_str(&args, 0, "pth.rel")?)?;
+    let to = absolute_path("pth.rel", arg_str(&args, 1, "pth.rel")?)?;
+    let (from_parts, to_parts): (Vec<Component>, Vec<Component>) = (from.components().collect(), to.components().collect());
+    // Paths on different Windows drives have no relative path between them
+    if from_parts.first() != to_parts.first() {
+        return Err(fail("pth.rel", FailureKind::Io, format!("no relative path from {} to {}", from.display(), to.display())));
+    }
+    let common = from_parts.iter().zip(&to_parts).take_while(|(a, b)| a == b).count();
+    let mut rel = std::path::PathBuf::new();
+    for _ in common..from_parts.len() {
+        rel.push("..");
+    }
+    for part in &to_parts[common..] {
+        rel.push(part);
+    }
+    if rel.as_os_str().is_empty() {
+        rel.push(".");
+    }
+    Ok(path_value(&rel))
+}
+
+fn pth_glob(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    let pattern = arg_str(&args, 0, "pth.glob")?;
+    let paths = glob::glob(pattern).map_err(|e| fail("pth.glob", FailureKind::Parse, format!("{}: {}", pattern, e)))?;
+    // Unreadable directories are skipped, as pth.walk skips them
+    let matches: Vec<AxValue> = paths.filter_map(|p| p.ok()).map(|p| path_value(&p)).collect();
+    Ok(AxValue::Lst(Arc::new(RwLock::new(matches))))
+}
+
+fn pth_mkdirs(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    let path = arg_str(&args, 0, "pth.mkdirs")?;
+    fs::create_dir_all(path)
+        .map(|_| AxValue::Nil)
+        .map_err(|e| fail("pth.mkdirs", FailureKind::Io, format!("{}: {}", path, e)))
+}
+
+/// Where `src` lands: inside `dst` when that is an existing directory, as
+/// `cp` and `mv` do, otherwise at `dst` itself.
+fn destination(src: &Path, dst: &str) -> std::path::PathBuf {
+    match src.file_name() {
+        Some(name) if Path::new(dst).is_dir() => Path::new(dst).join(name),
+        _ => std::path::PathBuf::from(dst),
+    }
+}
+
+/// Copy a file, or a directory and everything under it.
+fn copy_tree(src: &Path, dst: &Path) -> std::io::Result<()> {
+    if !src.is_dir() {
+        return fs::copy(src, dst).map(|_| ());
+    }
+    for entry in WalkDir::new(src) {
+        let entry = entry?;
+        let target = dst.join(entry.path().strip_prefix(src).unwrap_or(entry.path()));
+        if entry.file_type().is_dir() {
+            fs::create_dir_all(&target)?;
+        } else {
+            fs::copy(entry.path(), &target)?;
         }
-        _ => AxValue::Nil,
     }
+    Ok(())
+}
+
+fn pth_copy(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    let src = Path::new(arg_str(&args, 0, "pth.copy")?);
+    let dst = destination(src, arg_str(&args, 1, "pth.copy")?);
+    copy_tree(src, &dst)
+        .map(|_| path_value(&dst))
+        .map_err(|e| fail("pth.copy", FailureKind::Io, format!("{} -> {}: {}", src.display(), dst.display(), e)))
+}
+
+fn pth_move(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    let src = Path::new(arg_str(&args, 0, "pth.move")?);
+    let dst = destination(src, arg_str(&args, 1, "pth.move")?);
+    let moved = match fs::rename(src, &dst) {
+        // rename cannot cross filesystems; copy, then remove the original
+        Err(e) if e.kind() == std::io::ErrorKind::CrossesDevices => copy_tree(src, &dst).and_then(|_| {
+            if src.is_dir() { fs::remove_dir_all(src) } else { fs::remove_file(src) }
+        }),
+        result => result,
+    };
+    moved
+        .map(|_| path_value(&dst))
+        .map_err(|e| fail("pth.move", FailureKind::Io, format!("{} -> {}: {}", src.display(), dst.display(), e)))
 }
 
 // ==================== MODULE 19: STR (STRINGS) ====================
@@ -1290,86 +3026,557 @@ fn str_len(args: Vec<AxValue>) -> AxValue {
     }
 }
 
-fn str_upper(args: Vec<AxValue>) -> AxValue {
-    match args.get(0) {
-        Some(AxValue::Str(s)) => AxValue::Str(s.to_uppercase()),
-        _ => AxValue::Nil,
-    }
+fn str_upper(args: Vec<AxValue>) -> AxValue {
+    match args.get(0) {
+        Some(AxValue::Str(s)) => AxValue::Str(s.to_uppercase()),
+        _ => AxValue::Nil,
+    }
+}
+
+fn str_lower(args: Vec<AxValue>) -> AxValue {
+    match args.get(0) {
+        Some(AxValue::Str(s)) => AxValue::Str(s.to_lowercase()),
+        _ => AxValue::Nil,
+    }
+}
+
+/// `str.fmt(template, args...)`: see `core::format` for the placeholders
+/// and specs.
+fn str_fmt(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    let template = arg_str(&args, 0, "str.fmt")?;
+    crate::core::format::format(template, &args[1..]).map(AxValue::Str)
+}
+
+/// `str.builder()`: a map of methods sharing one growable buffer, so a
+/// string assembled piece by piece is copied once instead of per `+`.
+/// `b.push(x, ...)` appends each value's display form, `b.build()` returns
+/// the text so far, `b.len()` its length and `b.clear()` empties it.
+fn str_builder(_args: Vec<AxValue>) -> AxValue {
+    let buf = Arc::new(std::sync::Mutex::new(String::new()));
+    let map = Arc::new(DashMap::new());
+    let method = |name: &str, f: fn(&mut String, Vec<AxValue>) -> AxValue| {
+        let buf = Arc::clone(&buf);
+        AxValue::Fun(Arc::new(AxCallable::native(format!("str.builder.{}", name), move |args| Ok(f(&mut buf.lock().unwrap(), args)))))
+    };
+    map.insert("push".to_string(), method("push", |buf, args| {
+        for arg in &args {
+            match arg {
+                AxValue::Str(s) => buf.push_str(s),
+                other => buf.push_str(&other.display()),
+            }
+        }
+        AxValue::Nil
+    }));
+    map.insert("build".to_string(), method("build", |buf, _| AxValue::Str(buf.clone())));
+    map.insert("len".to_string(), method("len", |buf, _| AxValue::Num(buf.len() as f64)));
+    map.insert("clear".to_string(), method("clear", |buf, _| { buf.clear(); AxValue::Nil }));
+    AxValue::Map(map)
+}
+
+// ==================== MODULE 20: SYS (SYSTEM INFO) ====================
+
+#[cfg(feature = "native")]
+fn sys_info(_args: Vec<AxValue>) -> AxValue {
+    let mut sys = System::new_all();
+    sys.refresh_all();
+    let info = format!(
+        "os: {}, cpus: {}, memory: {} MB",
+        System::name().unwrap_or("unknown".to_string()),
+        sys.cpus().len(),
+        sys.total_memory() / 1024
+    );
+    AxValue::Str(info)
+}
+
+#[cfg(feature = "native")]
+fn sys_cpu_usage(_args: Vec<AxValue>) -> AxValue {
+    let mut sys = System::new_all();
+    sys.refresh_all();
+    let cpu = sys.global_cpu_info();
+    AxValue::Num(cpu.cpu_usage() as f64)
+}
+
+#[cfg(feature = "native")]
+fn sys_memory(_args: Vec<AxValue>) -> AxValue {
+    let mut sys = System::new_all();
+    sys.refresh_memory();
+    let map = Arc::new(DashMap::new());
+    map.insert("total".to_string(), AxValue::Num(sys.total_memory() as f64));
+    map.insert("used".to_string(), AxValue::Num(sys.used_memory() as f64));
+    map.insert("available".to_string(), AxValue::Num(sys.available_memory() as f64));
+    AxValue::Map(map)
+}
+
+/// Kept between calls: a process's CPU usage is measured since the previous refresh.
+#[cfg(feature = "native")]
+static PROCESS_TABLE: std::sync::OnceLock<std::sync::Mutex<System>> = std::sync::OnceLock::new();
+
+/// One map per running process (`pid`, `name`, `cpu` in percent of one core
+/// since the previous call, `mem` in bytes), ordered by pid.
+#[cfg(feature = "native")]
+fn sys_processes(_args: Vec<AxValue>) -> AxValue {
+    let mut sys = PROCESS_TABLE.get_or_init(|| std::sync::Mutex::new(System::new())).lock().unwrap();
+    sys.refresh_processes();
+    let mut procs: Vec<_> = sys.processes().values().collect();
+    procs.sort_by_key(|p| p.pid().as_u32());
+    let list = procs.into_iter().map(|p| {
+        let map = Arc::new(DashMap::new());
+        map.insert("pid".to_string(), AxValue::Num(p.pid().as_u32() as f64));
+        map.insert("name".to_string(), AxValue::Str(p.name().to_string()));
+        map.insert("cpu".to_string(), AxValue::Num(p.cpu_usage() as f64));
+        map.insert("mem".to_string(), AxValue::Num(p.memory() as f64));
+        AxValue::Map(map)
+    }).collect();
+    AxValue::Lst(Arc::new(RwLock::new(list)))
+}
+
+/// Kill the process `pid`; false when there is no such process or it cannot be signalled.
+#[cfg(feature = "native")]
+fn sys_kill(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    let pid = arg_num(&args, 0, "sys.kill")?;
+    if pid < 0.0 || pid.fract() != 0.0 || pid > u32::MAX as f64 {
+        return Err(arg_mismatch("sys.kill", 0, "a process id", args.first()));
+    }
+    let pid = sysinfo::Pid::from_u32(pid as u32);
+    let mut sys = System::new();
+    let killed = sys.refresh_process(pid) && sys.process(pid).is_some_and(|p| p.kill());
+    Ok(AxValue::Bol(killed))
+}
+
+/// The operating system, as `when` names it: "linux", "macos", "windows"...
+fn sys_platform(_args: Vec<AxValue>) -> AxValue {
+    let os = if cfg!(target_family = "wasm") { "wasm" } else { std::env::consts::OS };
+    AxValue::Str(os.to_string())
+}
+
+fn sys_pid(_args: Vec<AxValue>) -> AxValue {
+    AxValue::Num(std::process::id() as f64)
+}
+
+/// Seconds since the machine booted.
+#[cfg(feature = "native")]
+fn sys_uptime(_args: Vec<AxValue>) -> AxValue {
+    AxValue::Num(System::uptime() as f64)
+}
+
+/// One map per mounted filesystem (`mount`, `fs`, `total` and `free` bytes), by mount point.
+#[cfg(feature = "native")]
+fn sys_disks(_args: Vec<AxValue>) -> AxValue {
+    let disks = sysinfo::Disks::new_with_refreshed_list();
+    let mut list: Vec<&sysinfo::Disk> = disks.list().iter().collect();
+    list.sort_by(|a, b| a.mount_point().cmp(b.mount_point()));
+    let list = list.into_iter().map(|d| {
+        let map = Arc::new(DashMap::new());
+        map.insert("mount".to_string(), AxValue::Str(d.mount_point().display().to_string()));
+        map.insert("fs".to_string(), AxValue::Str(d.file_system().to_string_lossy().into_owned()));
+        map.insert("total".to_string(), AxValue::Num(d.total_space() as f64));
+        map.insert("free".to_string(), AxValue::Num(d.available_space() as f64));
+        AxValue::Map(map)
+    }).collect();
+    AxValue::Lst(Arc::new(RwLock::new(list)))
+}
+
+/// One map per network interface (`name`, `rx` and `tx` bytes since boot), by name.
+#[cfg(feature = "native")]
+fn sys_net_ifaces(_args: Vec<AxValue>) -> AxValue {
+    let networks = sysinfo::Networks::new_with_refreshed_list();
+    let mut list: Vec<_> = networks.list().iter().collect();
+    list.sort_by(|a, b| a.0.cmp(b.0));
+    let list = list.into_iter().map(|(name, data)| {
+        let map = Arc::new(DashMap::new());
+        map.insert("name".to_string(), AxValue::Str(name.clone()));
+        map.insert("rx".to_string(), AxValue::Num(data.total_received() as f64));
+        map.insert("tx".to_string(), AxValue::Num(data.total_transmitted() as f64));
+        AxValue::Map(map)
+    }).collect();
+    AxValue::Lst(Arc::new(RwLock::new(list)))
+}
+
+/// The first battery's charge (`percent`) and `status` ("charging",
+/// "discharging", "full", ...), or nil without a battery. Read from
+/// /sys/class/power_supply, so other systems always get nil.
+fn sys_battery(_args: Vec<AxValue>) -> AxValue {
+    let Ok(entries) = fs::read_dir("/sys/class/power_supply") else { return AxValue::Nil };
+    let mut supplies: Vec<_> = entries.flatten().map(|e| e.path()).collect();
+    supplies.sort();
+    let read = |dir: &Path, file: &str| fs::read_to_string(dir.join(file)).ok().map(|s| s.trim().to_string());
+    for dir in supplies {
+        if read(&dir, "type").as_deref() != Some("Battery") { continue; }
+        let Some(percent) = read(&dir, "capacity").and_then(|c| c.parse::<f64>().ok()) else { continue };
+        let map = Arc::new(DashMap::new());
+        map.insert("percent".to_string(), AxValue::Num(percent));
+        map.insert("status".to_string(), AxValue::Str(read(&dir, "status").unwrap_or_default().to_lowercase()));
+        return AxValue::Map(map);
+    }
+    AxValue::Nil
+}
+
+fn sys_chdir(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    let path = arg_str(&args, 0, "sys.chdir")?;
+    std::env::set_current_dir(path)
+        .map(|_| AxValue::Bol(true))
+        .map_err(|e| fail("sys.chdir", FailureKind::Io, format!("{}: {}", path, e)))
+}
+
+fn sys_cwd(_args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    std::env::current_dir()
+        .map(|path| AxValue::Str(path.display().to_string()))
+        .map_err(|e| fail("sys.cwd", FailureKind::Io, e))
+}
+
+fn gc_stats_value(gc: &crate::gc::GC) -> AxValue {
+    let generation = |count: u64, survivors: u64, last: u64, total: u64| {
+        let map = Arc::new(DashMap::new());
+        map.insert("collections".to_string(), AxValue::Num(count as f64));
+        map.insert("survivors".to_string(), AxValue::Num(survivors as f64));
+        map.insert("last_pause_us".to_string(), AxValue::Num(last as f64));
+        map.insert("total_pause_us".to_string(), AxValue::Num(total as f64));
+        AxValue::Map(map)
+    };
+    let st = &gc.stats;
+    let map = Arc::new(DashMap::new());
+    map.insert("heap_bytes".to_string(), AxValue::Num(gc.heap_bytes() as f64));
+    map.insert("young_bytes".to_string(), AxValue::Num(gc.young_bytes() as f64));
+    map.insert("young_capacity".to_string(), AxValue::Num(gc.young_capacity() as f64));
+    map.insert("old_bytes".to_string(), AxValue::Num(gc.old_bytes() as f64));
+    map.insert("allocated_bytes".to_string(), AxValue::Num(st.bytes_allocated_young as f64));
+    map.insert("young".to_string(), generation(st.minor_gcs, st.objects_promoted, st.last_minor_pause_us, st.minor_pause_us));
+    map.insert("old".to_string(), generation(st.major_gcs, st.old_survivors, st.last_major_pause_us, st.major_pause_us));
+    map.insert("total_pause_us".to_string(), AxValue::Num(st.total_pause_us as f64));
+    AxValue::Map(map)
+}
+
+/// Write out script output held back by `stdout_buffer=block`.
+fn sys_flush(_args: Vec<AxValue>) -> AxValue {
+    crate::runtime::flush_output();
+    AxValue::Nil
+}
+
+/// Run a full collection on this thread's heap; returns the stats afterwards.
+fn sys_gc_collect(_args: Vec<AxValue>) -> AxValue {
+    crate::gc::with_heap(|gc| {
+        gc.collect();
+        gc_stats_value(gc)
+    })
+}
+
+fn sys_gc_stats(_args: Vec<AxValue>) -> AxValue {
+    crate::gc::with_heap(|gc| gc_stats_value(gc))
+}
+
+// ==================== MODULE 21: TIM (TIME) ====================
+//
+// A time value is a map: `iso` (RFC3339, in the time's own zone), `zone`
+// (an IANA name like "Europe/Paris", "local", or a fixed offset "+02:00"),
+// `unix` (seconds since the epoch) and the calendar fields year, month, day,
+// hour, minute, second and weekday. Every tim function also takes a bare
+// RFC3339 string, as tim.now() returns, as a time in that string's offset.
+//
+//   tim.add(t, amount, unit)       — unit: ms, second, minute, hour, day, week, month, year
+//   tim.diff(a, b, unit)           — a - b in `unit`
+//   tim.parse(text, fmt?, zone?)   — RFC3339, or a strftime format
+//   tim.to_zone(t, zone)           — same instant, another zone
+//   tim.start_of_day(t) / tim.weekday(t)
+//
+// Stopwatches and benchmarks use a monotonic clock, unaffected by changes
+// to the system time:
+//   tim.stopwatch() / tim.elapsed_ms(sw)
+//   tim.bench(fn, n)               — timing stats over n calls
+
+#[derive(Clone, Copy)]
+enum TimZone {
+    Local,
+    Named(chrono_tz::Tz),
+    Fixed(FixedOffset),
+}
+
+impl TimZone {
+    fn parse(name: &str) -> Option<TimZone> {
+        if name == "local" { return Some(TimZone::Local); }
+        name.parse::<chrono_tz::Tz>().ok().map(TimZone::Named)
+            .or_else(|| name.parse::<FixedOffset>().ok().map(TimZone::Fixed))
+    }
+
+    fn name(&self) -> String {
+        match self {
+            TimZone::Local => "local".to_string(),
+            TimZone::Named(tz) => tz.name().to_string(),
+            TimZone::Fixed(offset) => offset.to_string(),
+        }
+    }
+
+    /// `utc` on this zone's wall clock.
+    fn at(&self, utc: &DateTime<Utc>) -> DateTime<FixedOffset> {
+        match self {
+            TimZone::Local => utc.with_timezone(&Local).fixed_offset(),
+            TimZone::Named(tz) => utc.with_timezone(tz).fixed_offset(),
+            TimZone::Fixed(offset) => utc.with_timezone(offset),
+        }
+    }
+
+    /// The instant this zone's clock shows `local`. A wall time repeated by
+    /// a DST change is its first occurrence; one skipped by it moves an hour on.
+    fn resolve(&self, local: NaiveDateTime) -> Option<DateTime<Utc>> {
+        fn pick<Z: TimeZone>(zone: &Z, local: NaiveDateTime) -> Option<DateTime<Utc>> {
+            zone.from_local_datetime(&local).earliest()
+                .or_else(|| zone.from_local_datetime(&(local + chrono::Duration::hours(1))).earliest())
+                .map(|t| t.with_timezone(&Utc))
+        }
+        match self {
+            TimZone::Local => pick(&Local, local),
+            TimZone::Named(tz) => pick(tz, local),
+            TimZone::Fixed(offset) => pick(offset, local),
+        }
+    }
+}
+
+fn tim_zone(name: &str, function: &str) -> Result<TimZone, RuntimeError> {
+    TimZone::parse(name).ok_or_else(|| fail(function, FailureKind::Parse, format!("unknown time zone '{}'", name)))
+}
+
+/// A time value or RFC3339 string as an instant and the zone it is shown in.
+fn tim_read(value: &AxValue) -> Option<(DateTime<Utc>, TimZone)> {
+    let (iso, zone) = match value {
+        AxValue::Str(s) => (s.clone(), None),
+        AxValue::Map(m) => {
+            let field = |key: &str| match m.get(key).map(|v| v.value().clone()) {
+                Some(AxValue::Str(s)) => Some(s),
+                _ => None,
+            };
+            (field("iso")?, field("zone").and_then(|z| TimZone::parse(&z)))
+        }
+        _ => return None,
+    };
+    let dt = DateTime::parse_from_rfc3339(&iso).ok()?;
+    Some((dt.with_timezone(&Utc), zone.unwrap_or(TimZone::Fixed(*dt.offset()))))
+}
+
+fn arg_time(args: &[AxValue], index: usize, function: &str) -> Result<(DateTime<Utc>, TimZone), RuntimeError> {
+    args.get(index).and_then(tim_read)
+        .ok_or_else(|| arg_mismatch(function, index, "time (map or RFC3339 Str)", args.get(index)))
+}
+
+fn tim_value(utc: DateTime<Utc>, zone: TimZone) -> AxValue {
+    let local = zone.at(&utc);
+    let map = Arc::new(DashMap::new());
+    map.insert("iso".to_string(), AxValue::Str(local.to_rfc3339()));
+    map.insert("zone".to_string(), AxValue::Str(zone.name()));
+    map.insert("unix".to_string(), AxValue::Num(utc.timestamp_millis() as f64 / 1000.0));
+    map.insert("year".to_string(), AxValue::Num(local.year() as f64));
+    map.insert("month".to_string(), AxValue::Num(local.month() as f64));
+    map.insert("day".to_string(), AxValue::Num(local.day() as f64));
+    map.insert("hour".to_string(), AxValue::Num(local.hour() as f64));
+    map.insert("minute".to_string(), AxValue::Num(local.minute() as f64));
+    map.insert("second".to_string(), AxValue::Num(local.second() as f64));
+    map.insert("weekday".to_string(), AxValue::Str(local.format("%A").to_string()));
+    AxValue::Map(map)
+}
+
+/// Milliseconds, days and calendar months: hours and shorter are exact
+/// durations, days and longer follow the zone's wall clock across DST.
+enum TimUnit {
+    Millis(f64),
+    Days(i64),
+    Months(i64),
+}
+
+fn arg_unit(args: &[AxValue], index: usize, function: &str) -> Result<TimUnit, RuntimeError> {
+    let unit = arg_str(args, index, function)?;
+    Ok(match unit.strip_suffix('s').filter(|u| !u.is_empty() && *u != "m").unwrap_or(unit) {
+        "ms" | "millisecond" => TimUnit::Millis(1.0),
+        "s" | "sec" | "second" => TimUnit::Millis(1000.0),
+        "min" | "minute" => TimUnit::Millis(60_000.0),
+        "h" | "hour" => TimUnit::Millis(3_600_000.0),
+        "day" => TimUnit::Days(1),
+        "week" => TimUnit::Days(7),
+        "month" => TimUnit::Months(1),
+        "year" => TimUnit::Months(12),
+        _ => return Err(arg_mismatch(function, index, "time unit (ms, second, minute, hour, day, week, month, year)", args.get(index))),
+    })
+}
+
+fn tim_now(_args: Vec<AxValue>) -> AxValue {
+    AxValue::Str(local_now().to_rfc3339())
+}
+
+fn tim_format(args: Vec<AxValue>) -> AxValue {
+    let pattern = match args.get(1) {
+        Some(AxValue::Str(p)) => p.as_str(),
+        _ => "%Y-%m-%d %H:%M:%S",
+    };
+    match (args.first(), args.first().and_then(tim_read)) {
+        (_, Some((utc, zone))) => {
+            use std::fmt::Write;
+            let mut text = String::new();
+            match write!(text, "{}", zone.at(&utc).format(pattern)) {
+                Ok(()) => AxValue::Str(text),
+                Err(_) => AxValue::Nil,
+            }
+        }
+        (Some(AxValue::Str(s)), None) => AxValue::Str(s.clone()),
+        _ => AxValue::Nil,
+    }
+}
+
+fn tim_add(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    let (utc, zone) = arg_time(&args, 0, "tim.add")?;
+    let amount = arg_num(&args, 1, "tim.add")?;
+    let whole = || if amount.fract() == 0.0 { Ok(amount as i64) } else { Err(arg_mismatch("tim.add", 1, "whole Num for days or months", args.get(1))) };
+    let local = zone.at(&utc).naive_local();
+    let shifted = match arg_unit(&args, 2, "tim.add")? {
+        TimUnit::Millis(ms) => utc.checked_add_signed(chrono::Duration::milliseconds((amount * ms).round() as i64)),
+        TimUnit::Days(days) => local.checked_add_signed(chrono::Duration::days(whole()? * days))
+            .and_then(|t| zone.resolve(t)),
+        TimUnit::Months(months) => {
+            let n = whole()? * months;
+            let step = chrono::Months::new(n.unsigned_abs().min(u32::MAX as u64) as u32);
+            if n < 0 { local.checked_sub_months(step) } else { local.checked_add_months(step) }
+                .and_then(|t| zone.resolve(t))
+        }
+    };
+    shifted.map(|t| tim_value(t, zone)).ok_or_else(|| fail("tim.add", FailureKind::Parse, "time out of range"))
 }
 
-fn str_lower(args: Vec<AxValue>) -> AxValue {
-    match args.get(0) {
-        Some(AxValue::Str(s)) => AxValue::Str(s.to_lowercase()),
-        _ => AxValue::Nil,
-    }
+fn tim_diff(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    let (a, zone) = arg_time(&args, 0, "tim.diff")?;
+    let (b, _) = arg_time(&args, 1, "tim.diff")?;
+    let (la, lb) = (zone.at(&a).naive_local(), zone.at(&b).naive_local());
+    Ok(AxValue::Num(match arg_unit(&args, 2, "tim.diff")? {
+        TimUnit::Millis(ms) => (a - b).num_milliseconds() as f64 / ms,
+        TimUnit::Days(days) => (la - lb).num_milliseconds() as f64 / (86_400_000.0 * days as f64),
+        TimUnit::Months(months) => {
+            // Whole months: back off one when the last month is not complete
+            let mut n = (la.year() as i64 * 12 + la.month() as i64) - (lb.year() as i64 * 12 + lb.month() as i64);
+            let step = chrono::Months::new(n.unsigned_abs() as u32);
+            let reached = if n < 0 { lb.checked_sub_months(step) } else { lb.checked_add_months(step) };
+            match reached {
+                Some(t) if n > 0 && t > la => n -= 1,
+                Some(t) if n < 0 && t < la => n += 1,
+                _ => {}
+            }
+            (n / months) as f64
+        }
+    }))
 }
 
-// ==================== MODULE 20: SYS (SYSTEM INFO) ====================
+fn tim_parse(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    let text = arg_str(&args, 0, "tim.parse")?;
+    let zone = match args.get(2) {
+        Some(AxValue::Str(name)) => Some(tim_zone(name, "tim.parse")?),
+        None | Some(AxValue::Nil) => None,
+        other => return Err(arg_mismatch("tim.parse", 2, "Str", other)),
+    };
+    let bad = |why: &str| fail("tim.parse", FailureKind::Parse, format!("cannot read '{}' as a time: {}", text, why));
+    let pattern = match args.get(1) {
+        Some(AxValue::Str(p)) => Some(p.as_str()),
+        None | Some(AxValue::Nil) => None,
+        other => return Err(arg_mismatch("tim.parse", 1, "Str", other)),
+    };
+    // Text with an offset keeps it unless a zone is given; text without one
+    // is wall-clock time in `zone`, UTC by default.
+    let stamped = match pattern {
+        None => DateTime::parse_from_rfc3339(text).map_err(|e| bad(&e.to_string()))?,
+        Some(p) => match DateTime::parse_from_str(text, p) {
+            Ok(dt) => dt,
+            Err(_) => {
+                let local = NaiveDateTime::parse_from_str(text, p)
+                    .or_else(|_| chrono::NaiveDate::parse_from_str(text, p).map(|d| d.and_time(chrono::NaiveTime::MIN)))
+                    .map_err(|e| bad(&e.to_string()))?;
+                let zone = zone.unwrap_or(TimZone::Named(chrono_tz::UTC));
+                let utc = zone.resolve(local).ok_or_else(|| bad("no such time in that zone"))?;
+                return Ok(tim_value(utc, zone));
+            }
+        },
+    };
+    Ok(tim_value(stamped.with_timezone(&Utc), zone.unwrap_or(TimZone::Fixed(*stamped.offset()))))
+}
 
-fn sys_info(_args: Vec<AxValue>) -> AxValue {
-    let mut sys = System::new_all();
-    sys.refresh_all();
-    let info = format!(
-        "os: {}, cpus: {}, memory: {} MB",
-        System::name().unwrap_or("unknown".to_string()),
-        sys.cpus().len(),
-        sys.total_memory() / 1024
-    );
-    AxValue::Str(info)
+fn tim_to_zone(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    let (utc, _) = arg_time(&args, 0, "tim.to_zone")?;
+    let zone = tim_zone(arg_str(&args, 1, "tim.to_zone")?, "tim.to_zone")?;
+    Ok(tim_value(utc, zone))
 }
 
-fn sys_cpu_usage(_args: Vec<AxValue>) -> AxValue {
-    let mut sys = System::new_all();
-    sys.refresh_all();
-    let cpu = sys.global_cpu_info();
-    AxValue::Num(cpu.cpu_usage() as f64)
+fn tim_start_of_day(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    let (utc, zone) = arg_time(&args, 0, "tim.start_of_day")?;
+    let midnight = zone.at(&utc).date_naive().and_time(chrono::NaiveTime::MIN);
+    zone.resolve(midnight).map(|t| tim_value(t, zone)).ok_or_else(|| fail("tim.start_of_day", FailureKind::Parse, "time out of range"))
 }
 
-fn sys_memory(_args: Vec<AxValue>) -> AxValue {
-    let mut sys = System::new_all();
-    sys.refresh_memory();
-    let map = Arc::new(DashMap::new());
-    map.insert("total".to_string(), AxValue::Num(sys.total_memory() as f64));
-    map.insert("used".to_string(), AxValue::Num(sys.used_memory() as f64));
-    map.insert("available".to_string(), AxValue::Num(sys.available_memory() as f64));
-    AxValue::Map(map)
+fn tim_weekday(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    let (utc, zone) = arg_time(&args, 0, "tim.weekday")?;
+    Ok(AxValue::Str(zone.at(&utc).format("%A").to_string()))
 }
 
-fn sys_chdir(args: Vec<AxValue>) -> AxValue {
-    match args.get(0) {
-        Some(AxValue::Str(path)) => {
-            match std::env::set_current_dir(path) {
-                Ok(_) => AxValue::Bol(true),
-                Err(e) => AxValue::Str(format!("ERROR: {}", e)),
-            }
-        }
-        _ => AxValue::Str("ERROR: chdir expects a string path".to_string()),
+/// Milliseconds on a monotonic clock that starts with the first call
+/// (frozen time under `--frozen-time`).
+fn tim_clock_ms() -> f64 {
+    if let Some(ms) = crate::repro::frozen_now_ms() {
+        return (ms - crate::repro::FROZEN_EPOCH_MS) as f64;
     }
+    static EPOCH: std::sync::OnceLock<std::time::Instant> = std::sync::OnceLock::new();
+    EPOCH.get_or_init(std::time::Instant::now).elapsed().as_secs_f64() * 1000.0
 }
 
-fn sys_cwd(_args: Vec<AxValue>) -> AxValue {
-    match std::env::current_dir() {
-        Ok(path) => AxValue::Str(path.display().to_string()),
-        Err(e) => AxValue::Str(format!("ERROR: {}", e)),
-    }
+/// A stopwatch is a map holding its start on the monotonic clock.
+fn tim_stopwatch(_args: Vec<AxValue>) -> AxValue {
+    let map = Arc::new(DashMap::new());
+    map.insert("started".to_string(), AxValue::Num(tim_clock_ms()));
+    AxValue::Map(map)
 }
 
-// ==================== MODULE 21: TIM (TIME) ====================
+fn tim_elapsed_ms(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    let started = match args.first() {
+        Some(AxValue::Map(m)) => match m.get("started").map(|v| v.value().clone()) {
+            Some(AxValue::Num(n)) => Some(n),
+            _ => None,
+        },
+        _ => None,
+    };
+    started.map(|s| AxValue::Num(tim_clock_ms() - s))
+        .ok_or_else(|| arg_mismatch("tim.elapsed_ms", 0, "stopwatch (from tim.stopwatch())", args.first()))
+}
 
-fn tim_now(_args: Vec<AxValue>) -> AxValue {
-    AxValue::Str(Local::now().to_rfc3339())
+/// Call `call` `args[1]` times and report the timings: `runs`, `total_ms`,
+/// `mean_ms`, `median_ms`, `min_ms`, `max_ms` and `stddev_ms`. Shared with
+/// the tree-walker, which calls user-defined functions itself.
+pub(crate) fn tim_bench_with(args: &[AxValue], mut call: impl FnMut() -> Result<AxValue, RuntimeError>) -> Result<AxValue, RuntimeError> {
+    let n = arg_num(args, 1, "tim.bench")?;
+    if n < 1.0 || n.fract() != 0.0 {
+        return Err(arg_mismatch("tim.bench", 1, "whole Num of runs, at least 1", args.get(1)));
+    }
+    let mut samples = Vec::with_capacity(n as usize);
+    for _ in 0..n as usize {
+        let start = std::time::Instant::now();
+        call()?;
+        samples.push(start.elapsed().as_secs_f64() * 1000.0);
+    }
+    samples.sort_by(f64::total_cmp);
+    let total: f64 = samples.iter().sum();
+    let mean = total / n;
+    let mid = samples.len() / 2;
+    let median = if samples.len() % 2 == 0 { (samples[mid - 1] + samples[mid]) / 2.0 } else { samples[mid] };
+    let variance = samples.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / n;
+    let map = Arc::new(DashMap::new());
+    map.insert("runs".to_string(), AxValue::Num(n));
+    map.insert("total_ms".to_string(), AxValue::Num(total));
+    map.insert("mean_ms".to_string(), AxValue::Num(mean));
+    map.insert("median_ms".to_string(), AxValue::Num(median));
+    map.insert("min_ms".to_string(), AxValue::Num(samples[0]));
+    map.insert("max_ms".to_string(), AxValue::Num(samples[samples.len() - 1]));
+    map.insert("stddev_ms".to_string(), AxValue::Num(variance.sqrt()));
+    Ok(AxValue::Map(map))
 }
 
-fn tim_format(args: Vec<AxValue>) -> AxValue {
-    match args.get(0) {
-        Some(AxValue::Str(s)) => {
-            if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
-                AxValue::Str(dt.format("%Y-%m-%d %H:%M:%S").to_string())
-            } else {
-                AxValue::Str(s.clone())
-            }
-        }
-        _ => AxValue::Nil,
+fn tim_bench(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    // User-defined functions are timed by the tree-walker's higher-order
+    // intercept; VM closures arrive here as natives.
+    match args.first() {
+        Some(AxValue::Fun(callable)) => match callable.as_ref() {
+            AxCallable::Native { func, .. } => tim_bench_with(&args, || func(vec![])),
+            AxCallable::UserDefined { .. } => Err(arg_mismatch("tim.bench", 0, "Fun called as tim.bench(f, n)", args.first())),
+        },
+        other => Err(arg_mismatch("tim.bench", 0, "Fun", other)),
     }
 }
 
@@ -1389,6 +3596,7 @@ fn tim_format(args: Vec<AxValue>) -> AxValue {
 //   tui.fx_rgb_split(ms)     — RGB-split shader descriptor
 //   tui.fx_bounce(ms)        — Bounce shader descriptor
 
+#[cfg(feature = "native")]
 use ratatui::{
     Terminal,
     backend::CrosstermBackend,
@@ -1398,25 +3606,75 @@ use ratatui::{
     text::{Line, Span as RatSpan},
 };
 // NOTE: ratatui 0.27 does NOT re-export crossterm; import it separately
+#[cfg(feature = "native")]
 use crossterm::{
     execute,
     terminal::{enable_raw_mode, disable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
     event::{self, Event, KeyCode, KeyEventKind},
 };
+#[cfg(feature = "native")]
 use std::time::Duration;
+#[cfg(feature = "native")]
 use std::io::stdout;
 
+/// Raw mode and the alternate screen for the length of one `tui.*` call.
+/// Dropping the guard restores the terminal, so an error return or a panic
+/// part-way through a render does not leave the user's shell garbled.
+#[cfg(feature = "native")]
+struct TerminalGuard {
+    terminal: Terminal<CrosstermBackend<std::io::Stdout>>,
+}
+
+#[cfg(feature = "native")]
+impl TerminalGuard {
+    fn enter() -> std::io::Result<Self> {
+        enable_raw_mode()?;
+        let entered = execute!(stdout(), EnterAlternateScreen)
+            .and_then(|_| Terminal::new(CrosstermBackend::new(stdout())));
+        match entered {
+            Ok(terminal) => Ok(TerminalGuard { terminal }),
+            Err(e) => {
+                restore_terminal();
+                Err(e)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "native")]
+impl std::ops::Deref for TerminalGuard {
+    type Target = Terminal<CrosstermBackend<std::io::Stdout>>;
+    fn deref(&self) -> &Self::Target { &self.terminal }
+}
+
+#[cfg(feature = "native")]
+impl std::ops::DerefMut for TerminalGuard {
+    fn deref_mut(&mut self) -> &mut Self::Target { &mut self.terminal }
+}
+
+#[cfg(feature = "native")]
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        restore_terminal();
+    }
+}
+
+/// Leave raw mode and the alternate screen and show the cursor. Each step is
+/// attempted even if an earlier one fails.
+#[cfg(feature = "native")]
+fn restore_terminal() {
+    let _ = disable_raw_mode();
+    let _ = execute!(stdout(), LeaveAlternateScreen, crossterm::cursor::Show);
+}
+
+#[cfg(feature = "native")]
 /// Render a single Block widget to stdout (non-interactive)
 fn tui_block(args: Vec<AxValue>) -> AxValue {
     let title = args.get(0).map(|v| v.display().to_string()).unwrap_or_else(|| "Axiom".into());
     let content = args.get(1).map(|v| v.display().to_string()).unwrap_or_default();
 
     let result = (|| -> std::io::Result<()> {
-        enable_raw_mode()?;
-        let mut stdout = stdout();
-        execute!(stdout, EnterAlternateScreen)?;
-        let backend = CrosstermBackend::new(stdout);
-        let mut terminal = Terminal::new(backend)?;
+        let mut terminal = TerminalGuard::enter()?;
 
         terminal.draw(|f| {
             let size = f.size();
@@ -1428,10 +3686,8 @@ fn tui_block(args: Vec<AxValue>) -> AxValue {
             f.render_widget(paragraph, size);
         })?;
 
-        std::thread::sleep(Duration::from_millis(1500));
+        sleep_ms(1500.0);
 
-        disable_raw_mode()?;
-        execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
         Ok(())
     })();
 
@@ -1441,6 +3697,7 @@ fn tui_block(args: Vec<AxValue>) -> AxValue {
     }
 }
 
+#[cfg(feature = "native")]
 /// Render a List widget (non-interactive snapshot)
 fn tui_list(args: Vec<AxValue>) -> AxValue {
     let items: Vec<String> = match args.get(0) {
@@ -1450,11 +3707,7 @@ fn tui_list(args: Vec<AxValue>) -> AxValue {
     let title = args.get(1).map(|v| v.display().to_string()).unwrap_or_else(|| "List".into());
 
     let result = (|| -> std::io::Result<()> {
-        enable_raw_mode()?;
-        let mut stdout = stdout();
-        execute!(stdout, EnterAlternateScreen)?;
-        let backend = CrosstermBackend::new(stdout);
-        let mut terminal = Terminal::new(backend)?;
+        let mut terminal = TerminalGuard::enter()?;
 
         terminal.draw(|f| {
             let size = f.size();
@@ -1468,9 +3721,7 @@ fn tui_list(args: Vec<AxValue>) -> AxValue {
             f.render_widget(list, size);
         })?;
 
-        std::thread::sleep(Duration::from_millis(1500));
-        disable_raw_mode()?;
-        execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+        sleep_ms(1500.0);
         Ok(())
     })();
 
@@ -1480,6 +3731,7 @@ fn tui_list(args: Vec<AxValue>) -> AxValue {
     }
 }
 
+#[cfg(feature = "native")]
 /// Render a Table widget: tui.table(headers_list, rows_list_of_lists)
 fn tui_table(args: Vec<AxValue>) -> AxValue {
     let headers: Vec<String> = match args.get(0) {
@@ -1498,11 +3750,7 @@ fn tui_table(args: Vec<AxValue>) -> AxValue {
     };
 
     let result = (|| -> std::io::Result<()> {
-        enable_raw_mode()?;
-        let mut stdout = stdout();
-        execute!(stdout, EnterAlternateScreen)?;
-        let backend = CrosstermBackend::new(stdout);
-        let mut terminal = Terminal::new(backend)?;
+        let mut terminal = TerminalGuard::enter()?;
 
         terminal.draw(|f| {
             let size = f.size();
@@ -1524,9 +3772,7 @@ fn tui_table(args: Vec<AxValue>) -> AxValue {
             f.render_widget(table, size);
         })?;
 
-        std::thread::sleep(Duration::from_millis(2000));
-        disable_raw_mode()?;
-        execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+        sleep_ms(2000.0);
         Ok(())
     })();
 
@@ -1536,6 +3782,7 @@ fn tui_table(args: Vec<AxValue>) -> AxValue {
     }
 }
 
+#[cfg(feature = "native")]
 /// Gauge: tui.gauge(label, percent 0-100)
 fn tui_gauge(args: Vec<AxValue>) -> AxValue {
     let label = args.get(0).map(|v| v.display().to_string()).unwrap_or_else(|| "Progress".into());
@@ -1545,11 +3792,7 @@ fn tui_gauge(args: Vec<AxValue>) -> AxValue {
     };
 
     let result = (|| -> std::io::Result<()> {
-        enable_raw_mode()?;
-        let mut stdout = stdout();
-        execute!(stdout, EnterAlternateScreen)?;
-        let backend = CrosstermBackend::new(stdout);
-        let mut terminal = Terminal::new(backend)?;
+        let mut terminal = TerminalGuard::enter()?;
 
         terminal.draw(|f| {
             let chunks = Layout::default()
@@ -1564,9 +3807,7 @@ fn tui_gauge(args: Vec<AxValue>) -> AxValue {
             f.render_widget(gauge, chunks[0]);
         })?;
 
-        std::thread::sleep(Duration::from_millis(1500));
-        disable_raw_mode()?;
-        execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+        sleep_ms(1500.0);
         Ok(())
     })();
 
@@ -1576,6 +3817,7 @@ fn tui_gauge(args: Vec<AxValue>) -> AxValue {
     }
 }
 
+#[cfg(feature = "native")]
 /// Sparkline: tui.sparkline(data_list, label)
 fn tui_sparkline(args: Vec<AxValue>) -> AxValue {
     let data: Vec<u64> = match args.get(0) {
@@ -1587,11 +3829,7 @@ fn tui_sparkline(args: Vec<AxValue>) -> AxValue {
     let label = args.get(1).map(|v| v.display().to_string()).unwrap_or_else(|| "Sparkline".into());
 
     let result = (|| -> std::io::Result<()> {
-        enable_raw_mode()?;
-        let mut stdout = stdout();
-        execute!(stdout, EnterAlternateScreen)?;
-        let backend = CrosstermBackend::new(stdout);
-        let mut terminal = Terminal::new(backend)?;
+        let mut terminal = TerminalGuard::enter()?;
 
         terminal.draw(|f| {
             let chunks = Layout::default()
@@ -1606,9 +3844,7 @@ fn tui_sparkline(args: Vec<AxValue>) -> AxValue {
             f.render_widget(sparkline, chunks[0]);
         })?;
 
-        std::thread::sleep(Duration::from_millis(1500));
-        disable_raw_mode()?;
-        execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+        sleep_ms(1500.0);
         Ok(())
     })();
 
@@ -1618,16 +3854,13 @@ fn tui_sparkline(args: Vec<AxValue>) -> AxValue {
     }
 }
 
+#[cfg(feature = "native")]
 /// Full animated dashboard (interactive — press 'q' to quit)
 fn tui_dashboard(args: Vec<AxValue>) -> AxValue {
     let title = args.get(0).map(|v| v.display().to_string()).unwrap_or_else(|| "Axiom Dashboard".into());
 
     let result = (|| -> std::io::Result<()> {
-        enable_raw_mode()?;
-        let mut stdout = stdout();
-        execute!(stdout, EnterAlternateScreen)?;
-        let backend = CrosstermBackend::new(stdout);
-        let mut terminal = Terminal::new(backend)?;
+        let mut terminal = TerminalGuard::enter()?;
 
         let mut tick: u64 = 0;
         loop {
@@ -1691,17 +3924,23 @@ fn tui_dashboard(args: Vec<AxValue>) -> AxValue {
 
             tick = tick.wrapping_add(1);
 
+            if crate::sandbox::interrupted() {
+                break;
+            }
             if event::poll(Duration::from_millis(80))? {
                 if let Event::Key(key) = event::read()? {
                     if key.kind == KeyEventKind::Press && key.code == KeyCode::Char('q') {
                         break;
                     }
+                    // Raw mode swallows the SIGINT; pass Ctrl-C on to the script
+                    if key.kind == KeyEventKind::Press && key.code == KeyCode::Char('c') && key.modifiers.contains(event::KeyModifiers::CONTROL) {
+                        crate::sandbox::interrupt();
+                        break;
+                    }
                 }
             }
         }
 
-        disable_raw_mode()?;
-        execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
         Ok(())
     })();
 
@@ -1714,6 +3953,7 @@ fn tui_dashboard(args: Vec<AxValue>) -> AxValue {
 // TachyonFX shader descriptors — return a Map describing the effect
 // which can be passed to tui.dashboard or used in custom render loops.
 
+#[cfg(feature = "native")]
 fn tui_fx_fade(args: Vec<AxValue>) -> AxValue {
     let ms = match args.get(0) { Some(AxValue::Num(n)) => *n as u64, _ => 500 };
     let map = Arc::new(DashMap::new());
@@ -1723,6 +3963,7 @@ fn tui_fx_fade(args: Vec<AxValue>) -> AxValue {
     AxValue::Map(map)
 }
 
+#[cfg(feature = "native")]
 fn tui_fx_glitch(args: Vec<AxValue>) -> AxValue {
     let ms = match args.get(0) { Some(AxValue::Num(n)) => *n as u64, _ => 300 };
     let map = Arc::new(DashMap::new());
@@ -1732,6 +3973,7 @@ fn tui_fx_glitch(args: Vec<AxValue>) -> AxValue {
     AxValue::Map(map)
 }
 
+#[cfg(feature = "native")]
 fn tui_fx_rgb_split(args: Vec<AxValue>) -> AxValue {
     let ms = match args.get(0) { Some(AxValue::Num(n)) => *n as u64, _ => 400 };
     let map = Arc::new(DashMap::new());
@@ -1741,6 +3983,7 @@ fn tui_fx_rgb_split(args: Vec<AxValue>) -> AxValue {
     AxValue::Map(map)
 }
 
+#[cfg(feature = "native")]
 fn tui_fx_bounce(args: Vec<AxValue>) -> AxValue {
     let ms = match args.get(0) { Some(AxValue::Num(n)) => *n as u64, _ => 600 };
     let map = Arc::new(DashMap::new());
@@ -1753,58 +3996,56 @@ fn tui_fx_bounce(args: Vec<AxValue>) -> AxValue {
 // ============================= MODULE 23: CLI =============================
 /// Shell execution, environment variables, and CLI integration
 
-fn cli_exec(args: Vec<AxValue>) -> AxValue {
-    match args.get(0) {
-        Some(AxValue::Str(cmd)) => {
-            use std::process::Command;
-            
-            let output = if cfg!(target_os = "windows") {
-                Command::new("cmd")
-                    .args(&["/C", cmd])
-                    .output()
-            } else {
-                Command::new("sh")
-                    .arg("-c")
-                    .arg(cmd)
-                    .output()
-            };
+fn cli_exec(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    use std::process::Command;
+    let cmd = arg_str(&args, 0, "cli.exec")?;
+
+    let output = if cfg!(target_os = "windows") {
+        Command::new("cmd")
+            .args(["/C", cmd])
+            .output()
+    } else {
+        Command::new("sh")
+            .arg("-c")
+            .arg(cmd)
+            .output()
+    };
 
-            match output {
-                Ok(out) => {
-                    let stdout = String::from_utf8_lossy(&out.stdout).to_string();
-                    AxValue::Str(stdout.trim().to_string())
-                }
-                Err(e) => AxValue::Str(format!("ERROR: {}", e)),
-            }
-        }
-        _ => AxValue::Str("ERROR: cmd must be string".to_string()),
-    }
+    // A non-zero exit is still a result (grep, diff, ...); only a failed spawn raises
+    let out = output.map_err(|e| fail("cli.exec", FailureKind::Io, e))?;
+    let stdout = String::from_utf8_lossy(&out.stdout).to_string();
+    Ok(AxValue::Str(stdout.trim().to_string()))
 }
 
-fn cli_shell(_args: Vec<AxValue>) -> AxValue {
+fn cli_shell(_args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
     #[cfg(target_os = "windows")]
     {
-        AxValue::Str("powershell".to_string())
+        Ok(AxValue::Str("powershell".to_string()))
     }
     #[cfg(not(target_os = "windows"))]
     {
         use std::env;
         let shell = env::var("SHELL").unwrap_or_else(|_| "bash".to_string());
-        AxValue::Str(shell)
+        Ok(AxValue::Str(shell))
     }
 }
 
-fn cli_env(args: Vec<AxValue>) -> AxValue {
-    match args.get(0) {
-        Some(AxValue::Str(key)) => {
-            use std::env;
-            match env::var(key) {
-                Ok(val) => AxValue::Str(val),
-                Err(_) => AxValue::Nil,
-            }
-        }
-        _ => AxValue::Nil,
-    }
+/// Arguments given after `--` to `axiom run`, for `cli.args()`.
+static SCRIPT_ARGS: RwLock<Vec<String>> = RwLock::new(Vec::new());
+
+pub fn set_script_args(args: Vec<String>) {
+    *SCRIPT_ARGS.write().unwrap_or_else(|e| e.into_inner()) = args;
+}
+
+fn cli_args(_args: Vec<AxValue>) -> AxValue {
+    let args = SCRIPT_ARGS.read().unwrap_or_else(|e| e.into_inner()).iter().cloned().map(AxValue::Str).collect();
+    AxValue::Lst(Arc::new(RwLock::new(args)))
+}
+
+fn cli_env(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    // An unset variable is Nil (absence, not failure)
+    let key = arg_str(&args, 0, "cli.env")?;
+    Ok(std::env::var(key).map(AxValue::Str).unwrap_or(AxValue::Nil))
 }
 
 // ==================== MODULE 24: USB (USB DEVICE I/O — rusb) ====================
@@ -1813,18 +4054,13 @@ fn cli_env(args: Vec<AxValue>) -> AxValue {
 // usb.open(vendor, product) — Opens a device by vendor/product ID → handle map
 // usb.transfer(handle, ep, data, timeout_ms) — Bulk transfer to endpoint
 
+#[cfg(feature = "native")]
 use rusb::{Context, UsbContext, DeviceHandle, DeviceList};
 
-fn usb_list(_args: Vec<AxValue>) -> AxValue {
-    let ctx: Context = match Context::new() {
-        Ok(c) => c,
-        Err(e) => return AxValue::Str(format!("usb.list error: {}", e)),
-    };
-    // Change GlobalContext to rusb::Context (or just let the compiler infer it)
-    let devices: DeviceList<rusb::Context> = match ctx.devices() {
-        Ok(d) => d,
-        Err(e) => return AxValue::Str(format!("usb.list error: {}", e)),
-    };
+#[cfg(feature = "native")]
+fn usb_list(_args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    let ctx: Context = Context::new().map_err(|e| fail("usb.list", FailureKind::Usb, e))?;
+    let devices: DeviceList<rusb::Context> = ctx.devices().map_err(|e| fail("usb.list", FailureKind::Usb, e))?;
 
     let list: Vec<AxValue> = devices.iter().filter_map(|device| {
         let descriptor = device.device_descriptor().ok()?;
@@ -1837,23 +4073,15 @@ fn usb_list(_args: Vec<AxValue>) -> AxValue {
         Some(AxValue::Map(map))
     }).collect();
 
-    AxValue::Lst(Arc::new(std::sync::RwLock::new(list)))
+    Ok(AxValue::Lst(Arc::new(std::sync::RwLock::new(list))))
 }
 
-fn usb_open(args: Vec<AxValue>) -> AxValue {
-    let vendor_id: u16 = match args.get(0) {
-        Some(AxValue::Num(n)) => *n as u16,
-        _ => return AxValue::Str("usb.open: vendor_id must be number".into()),
-    };
-    let product_id: u16 = match args.get(1) {
-        Some(AxValue::Num(n)) => *n as u16,
-        _ => return AxValue::Str("usb.open: product_id must be number".into()),
-    };
+#[cfg(feature = "native")]
+fn usb_open(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    let vendor_id = arg_num(&args, 0, "usb.open")? as u16;
+    let product_id = arg_num(&args, 1, "usb.open")? as u16;
 
-    let ctx: Context = match Context::new() {
-        Ok(c) => c,
-        Err(e) => return AxValue::Str(format!("usb.open error: context: {}", e)),
-    };
+    let ctx: Context = Context::new().map_err(|e| fail("usb.open", FailureKind::Usb, e))?;
 
     match ctx.open_device_with_vid_pid(vendor_id, product_id) {
         Some(_handle) => {
@@ -1863,28 +4091,24 @@ fn usb_open(args: Vec<AxValue>) -> AxValue {
             map.insert("vendor_id".to_string(),  AxValue::Num(vendor_id  as f64));
             map.insert("product_id".to_string(), AxValue::Num(product_id as f64));
             map.insert("open".to_string(),       AxValue::Bol(true));
-            AxValue::Map(map)
+            Ok(AxValue::Map(map))
         }
-        None => AxValue::Str(format!("usb.open: device {:04x}:{:04x} not found", vendor_id, product_id)),
+        None => Err(fail("usb.open", FailureKind::Usb, format!("device {:04x}:{:04x} not found", vendor_id, product_id))),
     }
 }
 
-fn usb_transfer(args: Vec<AxValue>) -> AxValue {
+#[cfg(feature = "native")]
+fn usb_transfer(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
     // args: handle_map, endpoint (u8), data_list, timeout_ms
-    let vendor_id = match args.get(0) {
-        Some(AxValue::Map(m)) => match m.get("vendor_id").as_deref() {
-            Some(AxValue::Num(n)) => *n as u16,
-            _ => return AxValue::Str("usb.transfer: invalid handle".into()),
-        },
-        _ => return AxValue::Str("usb.transfer: first arg must be handle map".into()),
-    };
-    let product_id = match args.get(0) {
-        Some(AxValue::Map(m)) => match m.get("product_id").as_deref() {
-            Some(AxValue::Num(n)) => *n as u16,
-            _ => return AxValue::Str("usb.transfer: invalid handle".into()),
+    let handle_id = |key: &str| match args.first() {
+        Some(AxValue::Map(m)) => match m.get(key).as_deref() {
+            Some(AxValue::Num(n)) => Ok(*n as u16),
+            _ => Err(fail("usb.transfer", FailureKind::Usb, "invalid handle")),
         },
-        _ => return AxValue::Str("usb.transfer: first arg must be handle map".into()),
+        other => Err(arg_mismatch("usb.transfer", 0, "Map handle", other)),
     };
+    let vendor_id = handle_id("vendor_id")?;
+    let product_id = handle_id("product_id")?;
     let endpoint = match args.get(1) {
         Some(AxValue::Num(n)) => *n as u8,
         _ => 0x01u8,
@@ -1901,43 +4125,753 @@ fn usb_transfer(args: Vec<AxValue>) -> AxValue {
         _ => 1000,
     };
 
-    let ctx: Context = match Context::new() {
-        Ok(c) => c,
-        Err(e) => return AxValue::Str(format!("usb.transfer error: context: {}", e)),
+    let ctx: Context = Context::new().map_err(|e| fail("usb.transfer", FailureKind::Usb, e))?;
+
+    let handle: DeviceHandle<Context> = ctx.open_device_with_vid_pid(vendor_id, product_id)
+        .ok_or_else(|| fail("usb.transfer", FailureKind::Usb, format!("device {:04x}:{:04x} not found", vendor_id, product_id)))?;
+
+    let bytes_written = handle.write_bulk(endpoint, &payload, Duration::from_millis(timeout_ms))
+        .map_err(|e| fail("usb.transfer", FailureKind::Usb, e))?;
+    let map = Arc::new(DashMap::new());
+    map.insert("ok".to_string(),           AxValue::Bol(true));
+    map.insert("bytes_written".to_string(), AxValue::Num(bytes_written as f64));
+    Ok(AxValue::Map(map))
+}
+
+// ==================== MODULE 25: FFI (C ABI — libloading) ====================
+//
+// ffi.load(path)                  — dlopen a shared library → handle map
+// ffi.fn(lib, symbol, signature)  — bind a symbol as a callable Axiom function
+//
+// Signatures use C-ish type names: "(f64, i64, str, bytes) -> f64".
+//   params : f64 | i64 | i32 | ptr | str | bytes   (at most 6)
+//   return : f64 | i64 | i32 | ptr | str | void    (default void)
+// Num marshals to f64 or an integer, Str to a NUL-terminated char*, and a
+// Lst of byte values (or a Str) to a uint8_t* that is valid for the call
+// only. Nil passes NULL for pointer parameters.
+//
+// Calls go through one fixed `extern "C"` shape holding six integer and six
+// float registers. On the System V x86-64 and AAPCS64 ABIs each class is
+// assigned registers independently, so any mix of up to six of each lands
+// where the callee expects it. Other targets reject ffi.fn.
+
+#[cfg(feature = "native")]
+use libloading::Library;
+#[cfg(feature = "native")]
+use std::ffi::{c_void, CStr, CString};
+
+#[cfg(feature = "native")]
+/// Libraries opened by ffi.load; a handle map stores its index here. Entries
+/// are never unloaded, so bound functions cannot outlive their code.
+static FFI_LIBS: std::sync::Mutex<Vec<Arc<Library>>> = std::sync::Mutex::new(Vec::new());
+
+#[cfg(feature = "native")]
+const FFI_MAX_PARAMS: usize = 6;
+
+#[cfg(feature = "native")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FfiType {
+    F64,
+    I64,
+    I32,
+    Ptr,
+    Str,
+    Bytes,
+    Void,
+}
+
+#[cfg(feature = "native")]
+impl FfiType {
+    fn parse(name: &str) -> Option<FfiType> {
+        Some(match name {
+            "f64" | "double" | "num" => FfiType::F64,
+            "i64" | "long" | "usize" | "isize" | "int64" => FfiType::I64,
+            "i32" | "int" | "bool" => FfiType::I32,
+            "ptr" => FfiType::Ptr,
+            "str" => FfiType::Str,
+            "bytes" => FfiType::Bytes,
+            "void" => FfiType::Void,
+            _ => return None,
+        })
+    }
+}
+
+#[cfg(feature = "native")]
+/// Parse "(t, t, ...) -> t" into parameter and return types.
+fn ffi_parse_signature(sig: &str) -> Result<(Vec<FfiType>, FfiType), String> {
+    let (params, ret) = match sig.split_once("->") {
+        Some((p, r)) => (p.trim(), r.trim()),
+        None => (sig.trim(), "void"),
+    };
+    let params = params.strip_prefix('(').and_then(|p| p.strip_suffix(')'))
+        .ok_or_else(|| format!("expected '(params) -> ret', got '{}'", sig))?;
+    let params = params.split(',').map(str::trim).filter(|p| !p.is_empty())
+        .map(|p| match FfiType::parse(p) {
+            Some(FfiType::Void) => Err("'void' is only valid as a return type".to_string()),
+            Some(t) => Ok(t),
+            None => Err(format!("unknown parameter type '{}'", p)),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let ret = match FfiType::parse(ret) {
+        Some(FfiType::Bytes) => return Err("'bytes' cannot be returned (length is unknown); use ptr".to_string()),
+        Some(t) => t,
+        None => return Err(format!("unknown return type '{}'", ret)),
+    };
+    if params.len() > FFI_MAX_PARAMS {
+        return Err(format!("at most {} parameters are supported, got {}", FFI_MAX_PARAMS, params.len()));
+    }
+    Ok((params, ret))
+}
+
+#[cfg(feature = "native")]
+type FfiShape<R> = unsafe extern "C" fn(i64, i64, i64, i64, i64, i64, f64, f64, f64, f64, f64, f64) -> R;
+
+#[cfg(feature = "native")]
+const FFI_SUPPORTED: bool = cfg!(any(all(target_arch = "x86_64", not(windows)), target_arch = "aarch64"));
+
+#[cfg(feature = "native")]
+/// Invoke `addr` with the marshalled register values.
+///
+/// # Safety
+/// `addr` must be a C function whose real signature matches the types the
+/// registers were built from, and the owning library must still be loaded.
+unsafe fn ffi_invoke<R>(addr: *const c_void, ints: &[i64; FFI_MAX_PARAMS], floats: &[f64; FFI_MAX_PARAMS]) -> R {
+    let f: FfiShape<R> = std::mem::transmute(addr);
+    f(ints[0], ints[1], ints[2], ints[3], ints[4], ints[5],
+      floats[0], floats[1], floats[2], floats[3], floats[4], floats[5])
+}
+
+#[cfg(feature = "native")]
+fn ffi_load(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    let path = arg_str(&args, 0, "ffi.load")?;
+    // SAFETY: loading a library runs its initialisers; that is the documented
+    // contract of ffi.load and is gated behind the `ffi` capability.
+    let lib = unsafe { Library::new(path) }.map_err(|e| fail("ffi.load", FailureKind::Io, e))?;
+    let mut libs = FFI_LIBS.lock().unwrap();
+    libs.push(Arc::new(lib));
+    let map = Arc::new(DashMap::new());
+    map.insert("path".to_string(),    AxValue::Str(path.to_string()));
+    map.insert("ffi_lib".to_string(), AxValue::Num((libs.len() - 1) as f64));
+    Ok(AxValue::Map(map))
+}
+
+#[cfg(feature = "native")]
+fn ffi_fn(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    let lib = match args.first() {
+        Some(AxValue::Map(m)) => m.get("ffi_lib").and_then(|v| match v.value() {
+            AxValue::Num(n) => FFI_LIBS.lock().unwrap().get(*n as usize).cloned(),
+            _ => None,
+        }),
+        _ => None,
+    }.ok_or_else(|| arg_mismatch("ffi.fn", 0, "library handle from ffi.load", args.first()))?;
+    let symbol = arg_str(&args, 1, "ffi.fn")?.to_string();
+    let (params, ret) = ffi_parse_signature(arg_str(&args, 2, "ffi.fn")?)
+        .map_err(|e| fail("ffi.fn", FailureKind::Parse, e))?;
+    if !FFI_SUPPORTED {
+        return Err(fail("ffi.fn", FailureKind::Io, "calling C functions is not supported on this target"));
+    }
+
+    let c_symbol = CString::new(symbol.as_str()).map_err(|e| fail("ffi.fn", FailureKind::Parse, e))?;
+    // SAFETY: the symbol is only read as an address here; it is called through
+    // the declared signature, which the script asserts is correct.
+    let addr = unsafe { lib.get::<*const c_void>(c_symbol.as_bytes_with_nul()) }
+        .map(|s| *s)
+        .map_err(|e| fail("ffi.fn", FailureKind::Io, e))? as usize;
+
+    let name = format!("ffi.{}", symbol);
+    let fn_name = name.clone();
+    Ok(AxValue::Fun(Arc::new(AxCallable::native(&name, move |call_args| {
+        let _keep_loaded = &lib;
+        ffi_call(&fn_name, addr as *const c_void, &params, ret, &call_args)
+    }))))
+}
+
+#[cfg(feature = "native")]
+fn ffi_call(name: &str, addr: *const c_void, params: &[FfiType], ret: FfiType, args: &[AxValue]) -> Result<AxValue, RuntimeError> {
+    if args.len() != params.len() {
+        return Err(RuntimeError::ArityMismatch { expected: params.len(), found: args.len() });
+    }
+    let mut ints = [0i64; FFI_MAX_PARAMS];
+    let mut floats = [0f64; FFI_MAX_PARAMS];
+    let (mut ni, mut nf) = (0, 0);
+    // Owned buffers backing pointer arguments; dropped after the call returns.
+    let mut strings: Vec<CString> = Vec::new();
+    let mut buffers: Vec<Vec<u8>> = Vec::new();
+
+    for (i, (ty, arg)) in params.iter().zip(args).enumerate() {
+        if *ty == FfiType::F64 {
+            floats[nf] = match arg {
+                AxValue::Num(n) => *n,
+                other => return Err(arg_mismatch(name, i, "Num", Some(other))),
+            };
+            nf += 1;
+            continue;
+        }
+        ints[ni] = match (ty, arg) {
+            (FfiType::I64 | FfiType::I32 | FfiType::Ptr, AxValue::Num(n)) => *n as i64,
+            (FfiType::I32 | FfiType::I64, AxValue::Bol(b)) => *b as i64,
+            (FfiType::Ptr | FfiType::Str | FfiType::Bytes, AxValue::Nil) => 0,
+            (FfiType::Str, AxValue::Str(s)) => {
+                let c = CString::new(s.as_str()).map_err(|e| fail(name, FailureKind::Parse, e))?;
+                strings.push(c);
+                strings.last().unwrap().as_ptr() as i64
+            }
+            (FfiType::Bytes, AxValue::Str(s)) => {
+                buffers.push(s.as_bytes().to_vec());
+                buffers.last().unwrap().as_ptr() as i64
+            }
+            (FfiType::Bytes, AxValue::Lst(lst)) => {
+                let bytes = lst.read().unwrap().iter().map(|v| match v {
+                    AxValue::Num(n) => Ok(*n as u8),
+                    other => Err(arg_mismatch(name, i, "Lst of byte values", Some(other))),
+                }).collect::<Result<Vec<u8>, _>>()?;
+                buffers.push(bytes);
+                buffers.last().unwrap().as_ptr() as i64
+            }
+            (FfiType::Str, other) => return Err(arg_mismatch(name, i, "Str", Some(other))),
+            (FfiType::Bytes, other) => return Err(arg_mismatch(name, i, "Lst or Str", Some(other))),
+            (_, other) => return Err(arg_mismatch(name, i, "Num", Some(other))),
+        };
+        ni += 1;
+    }
+
+    // SAFETY: the script declared the signature; the library is kept alive by
+    // the callable's captured Arc and by FFI_LIBS.
+    let value = unsafe {
+        match ret {
+            FfiType::F64 => AxValue::Num(ffi_invoke::<f64>(addr, &ints, &floats)),
+            FfiType::I64 | FfiType::Ptr => AxValue::Num(ffi_invoke::<i64>(addr, &ints, &floats) as f64),
+            FfiType::I32 => AxValue::Num(ffi_invoke::<i32>(addr, &ints, &floats) as f64),
+            FfiType::Str => {
+                let p = ffi_invoke::<*const std::ffi::c_char>(addr, &ints, &floats);
+                if p.is_null() { AxValue::Nil } else { AxValue::Str(CStr::from_ptr(p).to_string_lossy().into_owned()) }
+            }
+            FfiType::Void | FfiType::Bytes => {
+                ffi_invoke::<()>(addr, &ints, &floats);
+                AxValue::Nil
+            }
+        }
+    };
+    Ok(value)
+}
+
+// ==================== MODULE 26: RES (RESOURCE LIFETIMES) ====================
+//
+// res.defer(handle, fn)  — register fn(handle) to close handle; returns handle
+// res.close(handle)      — close it now: true if it was still open
+// res.scope(fn)          — call fn(), then close the handles it deferred
+// res.pending()          — how many deferred handles are still open
+//
+// Each finalizer runs once. Handles still open when a run ends are closed
+// after the `aut.on_exit` hooks, most recent first, however the run ended.
+
+fn res_defer(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    match (args.first(), args.get(1)) {
+        (None | Some(AxValue::Nil), _) => Err(arg_mismatch("res.defer", 0, "handle", args.first())),
+        (Some(handle), Some(finalizer @ AxValue::Fun(_))) => {
+            crate::runtime::defer(handle.clone(), finalizer.clone());
+            Ok(handle.clone())
+        }
+        (_, other) => Err(arg_mismatch("res.defer", 1, "Fun", other)),
+    }
+}
+
+pub(crate) fn res_close_with(args: &[AxValue], mut call: impl FnMut(AxValue, Vec<AxValue>) -> Result<AxValue, RuntimeError>) -> Result<AxValue, RuntimeError> {
+    let Some(handle) = args.first() else {
+        return Err(arg_mismatch("res.close", 0, "handle", None));
     };
+    match crate::runtime::take_deferred(handle) {
+        Some(finalizer) => {
+            call(finalizer, vec![handle.clone()])?;
+            Ok(AxValue::Bol(true))
+        }
+        None => Ok(AxValue::Bol(false)),
+    }
+}
 
-    let handle: DeviceHandle<Context> = match ctx.open_device_with_vid_pid(vendor_id, product_id) {
-        Some(h) => h,
-        None => return AxValue::Str(format!("usb.transfer: device {:04x}:{:04x} not found", vendor_id, product_id)),
+/// The body's error wins over one raised by a finalizer; every handle the
+/// body deferred is closed either way.
+pub(crate) fn res_scope_with(args: &[AxValue], mut call: impl FnMut(AxValue, Vec<AxValue>) -> Result<AxValue, RuntimeError>) -> Result<AxValue, RuntimeError> {
+    let Some(body @ AxValue::Fun(_)) = args.first() else {
+        return Err(arg_mismatch("res.scope", 0, "Fun", args.first()));
     };
+    let mark = crate::runtime::deferred_mark();
+    let mut result = call(body.clone(), vec![]);
+    for (handle, finalizer) in crate::runtime::take_deferred_since(mark) {
+        if let (Err(e), Ok(_)) = (call(finalizer, vec![handle]), &result) {
+            result = Err(e);
+        }
+    }
+    result
+}
 
-    match handle.write_bulk(endpoint, &payload, Duration::from_millis(timeout_ms)) {
-        Ok(bytes_written) => {
-            let map = Arc::new(DashMap::new());
-            map.insert("ok".to_string(),           AxValue::Bol(true));
-            map.insert("bytes_written".to_string(), AxValue::Num(bytes_written as f64));
-            AxValue::Map(map)
+fn res_close(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    res_close_with(&args, |f, a| call_native("res.close", f, a))
+}
+
+fn res_scope(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    res_scope_with(&args, |f, a| call_native("res.scope", f, a))
+}
+
+fn res_pending(_args: Vec<AxValue>) -> AxValue {
+    AxValue::Num(crate::runtime::deferred_count() as f64)
+}
+
+// ==================== MODULE 27: PKG (PACKAGE FEATURES) ====================
+//
+// pkg.feature(name)  — whether the `[features]` entry `name` of the running
+//                      package's Axiomite.toml is on
+//
+// The running package is the one whose module is being loaded, otherwise
+// the main program's. Its `default` features are on unless `axiom pkg add
+// --features` recorded another selection. Outside a package every feature
+// is off; a feature the package does not declare raises.
+
+fn pkg_feature(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    let name = arg_str(&args, 0, "pkg.feature")?;
+    crate::pkg::feature_enabled(name).map(AxValue::Bol).map_err(|e| fail("pkg.feature", FailureKind::Parse, e))
+}
+
+// ==================== MODULE 28: RPC (CALLS BETWEEN LOCAL SCRIPTS) ====================
+//
+// rpc.listen(name, handler)               — answer calls to `name` with handler(method, args)
+//                                           until rpc.stop(), Ctrl-C or SIGTERM
+// rpc.call(name, method, args, [timeout]) — call `method` on the script listening as `name`;
+//                                           waits up to timeout ms (default 30000, 0 = forever)
+// rpc.stop()                              — make rpc.listen return after the current call
+//
+// Arguments and results travel as JSON, encoded as by jsn.from_value, so
+// lists, maps, instances and enum variants round-trip but functions do not.
+// An error raised by the handler is sent back and raised by rpc.call; the
+// listener keeps serving.
+
+#[cfg(feature = "native")]
+const RPC_DEFAULT_TIMEOUT_MS: f64 = 30_000.0;
+
+#[cfg(feature = "native")]
+pub(crate) fn rpc_listen_with(args: &[AxValue], mut call: impl FnMut(AxValue, Vec<AxValue>) -> Result<AxValue, RuntimeError>) -> Result<AxValue, RuntimeError> {
+    use crate::rpc::Reply;
+    let name = arg_str(args, 0, "rpc.listen")?;
+    let Some(handler @ AxValue::Fun(_)) = args.get(1) else {
+        return Err(arg_mismatch("rpc.listen", 1, "Fun", args.get(1)));
+    };
+    let server = crate::rpc::Server::bind(name).map_err(|e| fail("rpc.listen", FailureKind::Network, e))?;
+    while !crate::rpc::stop_requested() && !crate::sandbox::interrupted() {
+        let Some(incoming) = server.next(std::time::Duration::from_millis(100)).map_err(|e| fail("rpc.listen", FailureKind::Network, e))? else {
+            continue;
+        };
+        let method = AxValue::Str(incoming.request.method.clone());
+        let call_args = incoming.request.args.iter().cloned().map(json_to_value).collect::<Result<Vec<_>, _>>();
+        let result = call_args.and_then(|a| call(handler.clone(), vec![method, AxValue::Lst(Arc::new(RwLock::new(a)))]));
+        match result.and_then(|value| value_to_json(&value, 0)) {
+            Ok(json) => incoming.reply(&Reply::Result(json)),
+            Err(RuntimeError::Interrupted) => {
+                incoming.reply(&Reply::Error(format!("'{}' is shutting down", name)));
+                return Err(RuntimeError::Interrupted);
+            }
+            Err(e) => incoming.reply(&Reply::Error(e.to_string())),
+        }
+    }
+    if crate::sandbox::interrupted() {
+        return Err(RuntimeError::Interrupted);
+    }
+    Ok(AxValue::Nil)
+}
+
+#[cfg(feature = "native")]
+fn rpc_listen(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    rpc_listen_with(&args, |f, a| call_native("rpc.listen", f, a))
+}
+
+#[cfg(feature = "native")]
+fn rpc_call(args: Vec<AxValue>) -> Result<AxValue, RuntimeError> {
+    use crate::rpc::{Reply, Request};
+    let name = arg_str(&args, 0, "rpc.call")?;
+    let method = arg_str(&args, 1, "rpc.call")?.to_string();
+    let call_args = match args.get(2) {
+        None | Some(AxValue::Nil) => Vec::new(),
+        Some(AxValue::Lst(items)) => items.read().unwrap().iter().map(|v| value_to_json(v, 0)).collect::<Result<_, _>>()?,
+        other => return Err(arg_mismatch("rpc.call", 2, "Lst", other)),
+    };
+    let timeout_ms = if args.len() > 3 { arg_num(&args, 3, "rpc.call")? } else { RPC_DEFAULT_TIMEOUT_MS };
+    let timeout = (timeout_ms > 0.0).then(|| std::time::Duration::from_millis(timeout_ms as u64));
+    let request = Request { method, args: call_args };
+    match crate::rpc::call(name, &request, timeout).map_err(|e| fail("rpc.call", FailureKind::Network, e))? {
+        Reply::Result(json) => json_to_value(json),
+        Reply::Error(message) => Err(fail("rpc.call", FailureKind::Network, format!("'{}' failed: {}", name, message))),
+    }
+}
+
+#[cfg(feature = "native")]
+fn rpc_stop(_args: Vec<AxValue>) -> AxValue {
+    crate::rpc::request_stop();
+    AxValue::Nil
+}
+
+// ============================= SIGNATURE REGISTRY =============================
+//
+// Static arity/argument-kind metadata for every intrinsic registered below.
+// The runtime never consults this table; it exists so `axiom chk` can flag
+// calls such as `mth.pow(2)` that would otherwise silently evaluate to Nil.
+
+/// Coarse argument kind expected by an intrinsic parameter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArgKind {
+    Num,
+    Str,
+    Bol,
+    Lst,
+    Map,
+    Fun,
+    Any,
+}
+
+impl ArgKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ArgKind::Num => "num",
+            ArgKind::Str => "str",
+            ArgKind::Bol => "bol",
+            ArgKind::Lst => "lst",
+            ArgKind::Map => "map",
+            ArgKind::Fun => "fun",
+            ArgKind::Any => "any",
+        }
+    }
+}
+
+/// Signature of a single intrinsic: `params.len()` is the maximum arity,
+/// unless `variadic`, and `min_args` the number of leading parameters that
+/// must be supplied.
+#[derive(Debug, Clone, Copy)]
+pub struct IntrinsicSig {
+    pub module: &'static str,
+    pub name: &'static str,
+    pub params: &'static [ArgKind],
+    pub min_args: usize,
+    /// The last parameter repeats, as in `str.fmt(template, args...)`
+    pub variadic: bool,
+}
+
+impl IntrinsicSig {
+    pub fn max_args(&self) -> usize {
+        self.params.len()
+    }
+
+    /// Whether a call with `argc` arguments fits.
+    pub fn accepts(&self, argc: usize) -> bool {
+        argc >= self.min_args && (self.variadic || argc <= self.max_args())
+    }
+
+    /// The kind expected for argument `i`.
+    pub fn param(&self, i: usize) -> Option<ArgKind> {
+        match self.params.get(i) {
+            Some(kind) => Some(*kind),
+            None if self.variadic => self.params.last().copied(),
+            None => None,
         }
-        Err(e) => AxValue::Str(format!("usb.transfer error: {}", e)),
     }
+
+    /// Human-readable form, e.g. `mth.pow(num, num)`.
+    pub fn display(&self) -> String {
+        let params: Vec<String> = self.params.iter().enumerate()
+            .map(|(i, k)| if i < self.min_args { k.name().to_string() } else { format!("{}?", k.name()) })
+            .collect();
+        let rest = if self.variadic { "..." } else { "" };
+        format!("{}.{}({}{})", self.module, self.name, params.join(", "), rest)
+    }
+}
+
+const fn sig(module: &'static str, name: &'static str, params: &'static [ArgKind], min_args: usize) -> IntrinsicSig {
+    IntrinsicSig { module, name, params, min_args, variadic: false }
+}
+
+/// A signature whose last parameter repeats.
+const fn varsig(module: &'static str, name: &'static str, params: &'static [ArgKind], min_args: usize) -> IntrinsicSig {
+    IntrinsicSig { module, name, params, min_args, variadic: true }
+}
+
+use ArgKind::{Num as N, Str as S, Lst as L, Map as M, Fun as F, Any as A};
+
+pub static INTRINSIC_SIGS: &[IntrinsicSig] = &[
+    // alg
+    sig("alg", "range", &[N], 1),
+    sig("alg", "map_parallel", &[L, F], 2),
+    sig("alg", "sum", &[L], 1),
+    sig("alg", "filter", &[L, F], 1),
+    sig("alg", "fold", &[L, A], 2),
+    sig("alg", "sort", &[L], 1),
+    sig("alg", "sort_by", &[L, A], 2),
+    sig("alg", "sort_desc", &[L, A], 1),
+    sig("alg", "sort_with", &[L, F], 2),
+    sig("alg", "unique", &[L], 1),
+    sig("alg", "group_by", &[L, A], 2),
+    sig("alg", "len", &[A], 1),
+    sig("alg", "map", &[L, F], 1),
+    sig("alg", "min", &[L], 1),
+    sig("alg", "max", &[L], 1),
+    sig("alg", "pipeline", &[L], 1),
+    sig("alg", "graph", &[M], 0),
+    // ann
+    sig("ann", "type_of", &[A], 1),
+    sig("ann", "is_num", &[A], 1),
+    sig("ann", "is_str", &[A], 1),
+    sig("ann", "is_lst", &[A], 1),
+    sig("ann", "is_map", &[A], 1),
+    sig("ann", "fields", &[A], 1),
+    sig("ann", "describe", &[A], 1),
+    sig("ann", "methods", &[A], 1),
+    sig("ann", "variants", &[M], 1),
+    sig("ann", "arity", &[F], 1),
+    sig("ann", "implements", &[A, S], 2),
+    // aut
+    sig("aut", "now", &[], 0),
+    sig("aut", "sleep", &[N], 1),
+    sig("aut", "timestamp", &[], 0),
+    sig("aut", "parse_time", &[S], 1),
+    sig("aut", "delay", &[N], 1),
+    sig("aut", "on_exit", &[F], 1),
+    // clr
+    sig("clr", "rgb", &[N, N, N], 3),
+    sig("clr", "hex", &[S], 1),
+    sig("clr", "hsv", &[N, N, N], 3),
+    // col
+    sig("col", "new", &[], 0),
+    sig("col", "new_map", &[], 0),
+    sig("col", "new_set", &[], 0),
+    sig("col", "set_from", &[L], 1),
+    sig("col", "with_capacity", &[N], 1),
+    sig("col", "get", &[M, S], 2),
+    sig("col", "set", &[M, S, A], 3),
+    sig("col", "remove", &[M, S], 2),
+    sig("col", "len", &[A], 1),
+    sig("col", "keys", &[M], 1),
+    sig("col", "values", &[M], 1),
+    sig("col", "clone_deep", &[A], 1),
+    sig("col", "merge", &[M, M], 2),
+    sig("col", "freeze", &[A], 1),
+    sig("col", "is_frozen", &[A], 1),
+    // con
+    sig("con", "now", &[], 0),
+    sig("con", "spawn", &[F], 1),
+    sig("con", "wait", &[N], 1),
+    sig("con", "mutex_new", &[], 0),
+    // csv
+    sig("csv", "parse", &[S], 1),
+    sig("csv", "write", &[L, S], 2),
+    sig("csv", "headers", &[S], 1),
+    // dfm
+    sig("dfm", "from_csv", &[S], 1),
+    sig("dfm", "shape", &[L], 1),
+    sig("dfm", "select", &[L, L], 2),
+    sig("dfm", "filter", &[L, A], 1),
+    // env
+    sig("env", "get", &[S], 1),
+    sig("env", "set", &[S, A], 2),
+    sig("env", "load", &[], 0),
+    sig("env", "all", &[], 0),
+    sig("env", "unset", &[S], 1),
+    sig("env", "save", &[S, M], 2),
+    sig("env", "with", &[M, F], 2),
+    // git
+    sig("git", "branch", &[S], 1),
+    sig("git", "log", &[S], 1),
+    sig("git", "status", &[S], 1),
+    sig("git", "clone", &[S, S], 2),
+    // ioo
+    sig("ioo", "read", &[S], 1),
+    sig("ioo", "write", &[S, A], 2),
+    sig("ioo", "append", &[S, A], 2),
+    sig("ioo", "exists", &[S], 1),
+    sig("ioo", "delete", &[S], 1),
+    sig("ioo", "list", &[S], 1),
+    sig("ioo", "temp_file", &[S], 0),
+    sig("ioo", "temp_dir", &[], 0),
+    sig("ioo", "write_atomic", &[S, A], 2),
+    sig("ioo", "copy", &[S, S], 2),
+    sig("ioo", "stat", &[S], 1),
+    sig("ioo", "chmod", &[S, A], 2),
+    sig("ioo", "touch", &[S], 1),
+    sig("ioo", "symlink", &[S, S], 2),
+    sig("ioo", "mmap", &[S], 1),
+    // jsn
+    sig("jsn", "parse", &[S], 1),
+    sig("jsn", "stringify", &[M], 1),
+    sig("jsn", "get", &[S, S], 2),
+    sig("jsn", "from_value", &[A, A], 1),
+    sig("jsn", "to_value", &[S], 1),
+    // log
+    sig("log", "progress", &[N, S], 1),
+    sig("log", "progress_new", &[N, S], 1),
+    sig("log", "progress_inc", &[M, N], 1),
+    sig("log", "progress_msg", &[M, A], 2),
+    sig("log", "progress_done", &[M, A], 1),
+    sig("log", "trace", &[A], 1),
+    sig("log", "debug", &[A], 1),
+    sig("log", "info", &[A], 1),
+    sig("log", "warn", &[A], 1),
+    sig("log", "error", &[A], 1),
+    sig("log", "set_level", &[S], 1),
+    sig("log", "level", &[], 0),
+    sig("log", "set_format", &[S], 1),
+    sig("log", "to_file", &[A, N], 1),
+    sig("log", "scope", &[S, F], 1),
+    sig("log", "end", &[], 0),
+    // mth
+    sig("mth", "sqrt", &[N], 1),
+    sig("mth", "sin", &[N], 1),
+    sig("mth", "cos", &[N], 1),
+    sig("mth", "tan", &[N], 1),
+    sig("mth", "abs", &[N], 1),
+    sig("mth", "floor", &[N], 1),
+    sig("mth", "ceil", &[N], 1),
+    sig("mth", "round", &[N], 1),
+    sig("mth", "pow", &[N, N], 2),
+    sig("mth", "log10", &[N], 1),
+    // net
+    sig("net", "get", &[S], 1),
+    sig("net", "post", &[S, S], 2),
+    // num
+    sig("num", "zeros", &[N, N], 2),
+    sig("num", "ones", &[N, N], 2),
+    sig("num", "range_array", &[N, N], 2),
+    sig("num", "parse", &[S], 1),
+    sig("num", "parse_int", &[S, N], 1),
+    sig("num", "to_hex", &[N], 1),
+    sig("num", "to_bin", &[N], 1),
+    sig("num", "to_oct", &[N], 1),
+    sig("num", "format", &[N, N, S], 1),
+    // plt
+    sig("plt", "scatter", &[S], 1),
+    sig("plt", "line", &[S], 1),
+    // pth
+    sig("pth", "list", &[S], 1),
+    sig("pth", "walk", &[S], 1),
+    varsig("pth", "join", &[S, S], 2),
+    sig("pth", "base", &[S], 1),
+    sig("pth", "dir", &[S], 1),
+    sig("pth", "ext", &[S], 1),
+    sig("pth", "abs", &[S], 1),
+    sig("pth", "rel", &[S, S], 2),
+    sig("pth", "glob", &[S], 1),
+    sig("pth", "mkdirs", &[S], 1),
+    sig("pth", "copy", &[S, S], 2),
+    sig("pth", "move", &[S, S], 2),
+    // str
+    sig("str", "match", &[S, S], 2),
+    sig("str", "replace", &[S, S, S], 3),
+    sig("str", "split", &[S, S], 2),
+    sig("str", "join", &[L, S], 2),
+    sig("str", "len", &[S], 1),
+    sig("str", "upper", &[S], 1),
+    sig("str", "lower", &[S], 1),
+    sig("str", "builder", &[], 0),
+    varsig("str", "fmt", &[S, A], 1),
+    // sys
+    sig("sys", "info", &[], 0),
+    sig("sys", "cpu_usage", &[], 0),
+    sig("sys", "memory", &[], 0),
+    sig("sys", "chdir", &[S], 1),
+    sig("sys", "cwd", &[], 0),
+    sig("sys", "flush", &[], 0),
+    sig("sys", "gc_collect", &[], 0),
+    sig("sys", "gc_stats", &[], 0),
+    sig("sys", "processes", &[], 0),
+    sig("sys", "kill", &[N], 1),
+    sig("sys", "pid", &[], 0),
+    sig("sys", "platform", &[], 0),
+    sig("sys", "uptime", &[], 0),
+    sig("sys", "disks", &[], 0),
+    sig("sys", "net_ifaces", &[], 0),
+    sig("sys", "battery", &[], 0),
+    // tim
+    sig("tim", "now", &[], 0),
+    sig("tim", "format", &[A, S], 1),
+    sig("tim", "add", &[A, N, S], 3),
+    sig("tim", "diff", &[A, A, S], 3),
+    sig("tim", "parse", &[S, S, S], 1),
+    sig("tim", "to_zone", &[A, S], 2),
+    sig("tim", "start_of_day", &[A], 1),
+    sig("tim", "weekday", &[A], 1),
+    sig("tim", "stopwatch", &[], 0),
+    sig("tim", "elapsed_ms", &[M], 1),
+    sig("tim", "bench", &[F, N], 2),
+    // tui
+    sig("tui", "block", &[A, A], 0),
+    sig("tui", "list", &[L, A], 0),
+    sig("tui", "table", &[L, L], 0),
+    sig("tui", "gauge", &[A, N], 0),
+    sig("tui", "sparkline", &[L, A], 0),
+    sig("tui", "dashboard", &[A], 0),
+    sig("tui", "fx_fade", &[N], 0),
+    sig("tui", "fx_glitch", &[N], 0),
+    sig("tui", "fx_rgb_split", &[N], 0),
+    sig("tui", "fx_bounce", &[N], 0),
+    // cli
+    sig("cli", "exec", &[S], 1),
+    sig("cli", "shell", &[], 0),
+    sig("cli", "env", &[S], 1),
+    sig("cli", "args", &[], 0),
+    // usb
+    sig("usb", "list", &[], 0),
+    sig("usb", "open", &[N, N], 2),
+    sig("usb", "transfer", &[M, N, A, N], 1),
+    // ffi
+    sig("ffi", "load", &[S], 1),
+    sig("ffi", "fn", &[M, S, S], 3),
+    // res
+    sig("res", "defer", &[A, F], 2),
+    sig("res", "close", &[A], 1),
+    sig("res", "scope", &[F], 1),
+    sig("res", "pending", &[], 0),
+    // pkg
+    sig("pkg", "feature", &[S], 1),
+    // rpc
+    sig("rpc", "listen", &[S, F], 2),
+    sig("rpc", "call", &[S, S, L, N], 2),
+    sig("rpc", "stop", &[], 0),
+];
+
+/// Names of every intrinsic module registered as a global.
+pub const MODULES: &[&str] = &[
+    "alg", "ann", "aut", "clr", "col", "con", "csv", "dfm", "env", "git", "ioo", "jsn",
+    "log", "mth", "net", "num", "plt", "pth", "str", "sys", "tim", "tui", "cli", "usb",
+    "ffi", "res", "pkg", "rpc",
+];
+
+/// Look up the signature of `module.name`, if it is a known intrinsic.
+pub fn signature(module: &str, name: &str) -> Option<&'static IntrinsicSig> {
+    INTRINSIC_SIGS.iter().find(|s| s.module == module && s.name == name)
+}
+
+/// All function names exported by `module`.
+pub fn module_functions(module: &str) -> Vec<&'static str> {
+    INTRINSIC_SIGS.iter().filter(|s| s.module == module).map(|s| s.name).collect()
 }
 
 // ============================= REGISTRATION ENTRY POINT =============================
 
-pub fn register(globals: &mut HashMap<String, AxValue>) {
+pub fn register(globals: &mut crate::runtime::Globals) {
     // =============== MODULE 1: ALG ===============
     let alg_map = Arc::new(DashMap::new());
     alg_map.insert("range".to_string(), native("alg.range", alg_range));
     alg_map.insert("map_parallel".to_string(), native("alg.map_parallel", alg_map_parallel));
     alg_map.insert("sum".to_string(), native("alg.sum", alg_sum));
-    alg_map.insert("filter".to_string(), native("alg.filter", alg_filter));
+    alg_map.insert("filter".to_string(), native_result("alg.filter", alg_filter));
     alg_map.insert("fold".to_string(), native("alg.fold", alg_fold));
     alg_map.insert("sort".to_string(), native("alg.sort", alg_sort));
+    alg_map.insert("sort_by".to_string(), native_result("alg.sort_by", alg_sort_by));
+    alg_map.insert("sort_desc".to_string(), native_result("alg.sort_desc", alg_sort_desc));
+    alg_map.insert("sort_with".to_string(), native_result("alg.sort_with", alg_sort_with));
+    alg_map.insert("unique".to_string(), native_result("alg.unique", alg_unique));
+    alg_map.insert("group_by".to_string(), native_result("alg.group_by", alg_group_by));
     alg_map.insert("len".to_string(), native("alg.len", alg_len));
-    alg_map.insert("map".to_string(), native("alg.map", alg_map_fn));
+    alg_map.insert("map".to_string(), native_result("alg.map", alg_map_fn));
     alg_map.insert("min".to_string(), native("alg.min", alg_min));
     alg_map.insert("max".to_string(), native("alg.max", alg_max));
-    globals.insert("alg".to_string(), AxValue::Map(alg_map));
+    alg_map.insert("pipeline".to_string(), native_result("alg.pipeline", alg_pipeline));
+    alg_map.insert("graph".to_string(), native_result("alg.graph", alg_graph));
+    globals.insert("alg", AxValue::Map(alg_map));
 
     // =============== MODULE 2: ANN ===============
     let ann_map = Arc::new(DashMap::new());
@@ -1947,7 +4881,12 @@ pub fn register(globals: &mut HashMap<String, AxValue>) {
     ann_map.insert("is_lst".to_string(), native("ann.is_lst", ann_is_lst));
     ann_map.insert("is_map".to_string(), native("ann.is_map", ann_is_map));
     ann_map.insert("fields".to_string(), native("ann.fields", ann_fields));
-    globals.insert("ann".to_string(), AxValue::Map(ann_map));
+    ann_map.insert("implements".to_string(), native("ann.implements", ann_implements));
+    ann_map.insert("describe".to_string(), native("ann.describe", ann_describe));
+    ann_map.insert("methods".to_string(), native("ann.methods", ann_methods));
+    ann_map.insert("variants".to_string(), native("ann.variants", ann_variants));
+    ann_map.insert("arity".to_string(), native("ann.arity", ann_arity));
+    globals.insert("ann", AxValue::Map(ann_map));
 
     // =============== MODULE 3: AUT ===============
     let aut_map = Arc::new(DashMap::new());
@@ -1956,27 +4895,34 @@ pub fn register(globals: &mut HashMap<String, AxValue>) {
     aut_map.insert("timestamp".to_string(), native("aut.timestamp", aut_timestamp));
     aut_map.insert("parse_time".to_string(), native("aut.parse_time", aut_parse_time));
     aut_map.insert("delay".to_string(), native("aut.delay", aut_delay));
-    globals.insert("aut".to_string(), AxValue::Map(aut_map));
+    aut_map.insert("on_exit".to_string(), native_result("aut.on_exit", aut_on_exit));
+    globals.insert("aut", AxValue::Map(aut_map));
 
     // =============== MODULE 4: CLR ===============
     let clr_map = Arc::new(DashMap::new());
     clr_map.insert("rgb".to_string(), native("clr.rgb", clr_rgb));
     clr_map.insert("hex".to_string(), native("clr.hex", clr_hex));
     clr_map.insert("hsv".to_string(), native("clr.hsv", clr_hsv));
-    globals.insert("clr".to_string(), AxValue::Map(clr_map));
+    globals.insert("clr", AxValue::Map(clr_map));
 
     // =============== MODULE 5: COL ===============
     let col_map = Arc::new(DashMap::new());
     col_map.insert("new".to_string(), native("col.new", col_new));
     col_map.insert("new_map".to_string(), native("col.new_map", col_new));   // alias
-    col_map.insert("new_set".to_string(), native("col.new_set", col_new));   // alias
+    col_map.insert("new_set".to_string(), native("col.new_set", col_new_set));
+    col_map.insert("set_from".to_string(), native_result("col.set_from", col_set_from));
+    col_map.insert("with_capacity".to_string(), native("col.with_capacity", col_with_capacity));
     col_map.insert("get".to_string(), native("col.get", col_get));
-    col_map.insert("set".to_string(), native("col.set", col_set));
-    col_map.insert("remove".to_string(), native("col.remove", col_remove));
+    col_map.insert("set".to_string(), native_result("col.set", col_set));
+    col_map.insert("remove".to_string(), native_result("col.remove", col_remove));
     col_map.insert("len".to_string(), native("col.len", col_len));
     col_map.insert("keys".to_string(), native("col.keys", col_keys));
     col_map.insert("values".to_string(), native("col.values", col_values));
-    globals.insert("col".to_string(), AxValue::Map(col_map));
+    col_map.insert("clone_deep".to_string(), native("col.clone_deep", col_clone_deep));
+    col_map.insert("merge".to_string(), native_result("col.merge", col_merge));
+    col_map.insert("freeze".to_string(), native("col.freeze", col_freeze));
+    col_map.insert("is_frozen".to_string(), native("col.is_frozen", col_is_frozen));
+    globals.insert("col", AxValue::Map(col_map));
 
     // =============== MODULE 6: CON ===============
     let con_map = Arc::new(DashMap::new());
@@ -1984,14 +4930,14 @@ pub fn register(globals: &mut HashMap<String, AxValue>) {
     con_map.insert("spawn".to_string(), native("con.spawn", con_spawn));
     con_map.insert("wait".to_string(), native("con.wait", con_wait));
     con_map.insert("mutex_new".to_string(), native("con.mutex_new", con_mutex_new));
-    globals.insert("con".to_string(), AxValue::Map(con_map));
+    globals.insert("con", AxValue::Map(con_map));
 
     // =============== MODULE 7: CSV ===============
     let csv_map = Arc::new(DashMap::new());
-    csv_map.insert("parse".to_string(), native("csv.parse", csv_parse));
-    csv_map.insert("write".to_string(), native("csv.write", csv_write));
-    csv_map.insert("headers".to_string(), native("csv.headers", csv_headers));
-    globals.insert("csv".to_string(), AxValue::Map(csv_map));
+    csv_map.insert("parse".to_string(), native_result("csv.parse", csv_parse));
+    csv_map.insert("write".to_string(), native_result("csv.write", csv_write));
+    csv_map.insert("headers".to_string(), native_result("csv.headers", csv_headers));
+    globals.insert("csv", AxValue::Map(csv_map));
 
     // =============== MODULE 8: DFM ===============
     let dfm_map = Arc::new(DashMap::new());
@@ -1999,7 +4945,7 @@ pub fn register(globals: &mut HashMap<String, AxValue>) {
     dfm_map.insert("shape".to_string(), native("dfm.shape", dfm_shape));
     dfm_map.insert("select".to_string(), native("dfm.select", dfm_select));
     dfm_map.insert("filter".to_string(), native("dfm.filter", dfm_filter));
-    globals.insert("dfm".to_string(), AxValue::Map(dfm_map));
+    globals.insert("dfm", AxValue::Map(dfm_map));
 
     // =============== MODULE 9: ENV ===============
     let env_map = Arc::new(DashMap::new());
@@ -2007,40 +4953,71 @@ pub fn register(globals: &mut HashMap<String, AxValue>) {
     env_map.insert("set".to_string(), native("env.set", env_set));
     env_map.insert("load".to_string(), native("env.load", env_load));
     env_map.insert("all".to_string(), native("env.all", env_all));
-    globals.insert("env".to_string(), AxValue::Map(env_map));
+    env_map.insert("unset".to_string(), native_result("env.unset", env_unset));
+    env_map.insert("save".to_string(), native_result("env.save", env_save));
+    env_map.insert("with".to_string(), native_result("env.with", env_with));
+    globals.insert("env", AxValue::Map(env_map));
 
     // =============== MODULE 10: GIT ===============
-    let git_map = Arc::new(DashMap::new());
-    git_map.insert("branch".to_string(), native("git.branch", git_branch));
-    git_map.insert("log".to_string(), native("git.log", git_log));
-    git_map.insert("status".to_string(), native("git.status", git_status));
-    git_map.insert("clone".to_string(), native("git.clone", git_clone));
-    globals.insert("git".to_string(), AxValue::Map(git_map));
+    #[cfg(feature = "native")]
+    {
+        let git_map = Arc::new(DashMap::new());
+        git_map.insert("branch".to_string(), native_result("git.branch", git_branch));
+        git_map.insert("log".to_string(), native_result("git.log", git_log));
+        git_map.insert("status".to_string(), native_result("git.status", git_status));
+        git_map.insert("clone".to_string(), native_result("git.clone", git_clone));
+        globals.insert("git", AxValue::Map(git_map));
+    }
 
     // =============== MODULE 11: IOO ===============
     let ioo_map = Arc::new(DashMap::new());
-    ioo_map.insert("read".to_string(), native("ioo.read", ioo_read));
-    ioo_map.insert("write".to_string(), native("ioo.write", ioo_write));
-    ioo_map.insert("append".to_string(), native("ioo.append", ioo_append));
-    ioo_map.insert("exists".to_string(), native("ioo.exists", ioo_exists));
-    ioo_map.insert("delete".to_string(), native("ioo.delete", ioo_delete));
-    ioo_map.insert("list".to_string(), native("ioo.list", ioo_list));
-    globals.insert("ioo".to_string(), AxValue::Map(ioo_map));
+    ioo_map.insert("read".to_string(), native_result("ioo.read", ioo_read));
+    ioo_map.insert("write".to_string(), native_result("ioo.write", ioo_write));
+    ioo_map.insert("append".to_string(), native_result("ioo.append", ioo_append));
+    ioo_map.insert("exists".to_string(), native_result("ioo.exists", ioo_exists));
+    ioo_map.insert("delete".to_string(), native_result("ioo.delete", ioo_delete));
+    ioo_map.insert("list".to_string(), native_result("ioo.list", ioo_list));
+    ioo_map.insert("temp_file".to_string(), native_result("ioo.temp_file", ioo_temp_file));
+    ioo_map.insert("temp_dir".to_string(), native_result("ioo.temp_dir", ioo_temp_dir));
+    ioo_map.insert("write_atomic".to_string(), native_result("ioo.write_atomic", ioo_write_atomic));
+    ioo_map.insert("copy".to_string(), native_result("ioo.copy", ioo_copy));
+    ioo_map.insert("stat".to_string(), native_result("ioo.stat", ioo_stat));
+    ioo_map.insert("chmod".to_string(), native_result("ioo.chmod", ioo_chmod));
+    ioo_map.insert("touch".to_string(), native_result("ioo.touch", ioo_touch));
+    ioo_map.insert("symlink".to_string(), native_result("ioo.symlink", ioo_symlink));
+    #[cfg(feature = "native")]
+    ioo_map.insert("mmap".to_string(), native_result("ioo.mmap", ioo_mmap));
+    globals.insert("ioo", AxValue::Map(ioo_map));
 
     // =============== MODULE 12: JSN ===============
     let jsn_map = Arc::new(DashMap::new());
     jsn_map.insert("parse".to_string(), native("jsn.parse", jsn_parse));
     jsn_map.insert("stringify".to_string(), native("jsn.stringify", jsn_stringify));
     jsn_map.insert("get".to_string(), native("jsn.get", jsn_get));
-    globals.insert("jsn".to_string(), AxValue::Map(jsn_map));
+    jsn_map.insert("from_value".to_string(), native_result("jsn.from_value", jsn_from_value));
+    jsn_map.insert("to_value".to_string(), native_result("jsn.to_value", jsn_to_value));
+    globals.insert("jsn", AxValue::Map(jsn_map));
 
     // =============== MODULE 13: LOG ===============
     let log_map = Arc::new(DashMap::new());
-    log_map.insert("progress".to_string(), native("log.progress", log_progress));
+    // `progress` is kept as an alias of `progress_new` for older scripts
+    log_map.insert("progress".to_string(), native_result("log.progress", log_progress_new));
+    log_map.insert("progress_new".to_string(), native_result("log.progress_new", log_progress_new));
+    log_map.insert("progress_inc".to_string(), native_result("log.progress_inc", log_progress_inc));
+    log_map.insert("progress_msg".to_string(), native_result("log.progress_msg", log_progress_msg));
+    log_map.insert("progress_done".to_string(), native_result("log.progress_done", log_progress_done));
+    log_map.insert("trace".to_string(), native("log.trace", log_trace));
+    log_map.insert("debug".to_string(), native("log.debug", log_debug));
     log_map.insert("info".to_string(), native("log.info", log_info));
     log_map.insert("warn".to_string(), native("log.warn", log_warn));
     log_map.insert("error".to_string(), native("log.error", log_error));
-    globals.insert("log".to_string(), AxValue::Map(log_map));
+    log_map.insert("set_level".to_string(), native_result("log.set_level", log_set_level));
+    log_map.insert("level".to_string(), native("log.level", log_level));
+    log_map.insert("set_format".to_string(), native_result("log.set_format", log_set_format));
+    log_map.insert("to_file".to_string(), native_result("log.to_file", log_to_file));
+    log_map.insert("scope".to_string(), native_result("log.scope", log_scope));
+    log_map.insert("end".to_string(), native("log.end", log_end));
+    globals.insert("log", AxValue::Map(log_map));
 
     // =============== MODULE 14: MTH ===============
     let mth_map = Arc::new(DashMap::new());
@@ -2054,33 +5031,54 @@ pub fn register(globals: &mut HashMap<String, AxValue>) {
     mth_map.insert("round".to_string(), native("mth.round", mth_round));
     mth_map.insert("pow".to_string(), native("mth.pow", mth_pow));
     mth_map.insert("log10".to_string(), native("mth.log10", mth_log10));
-    globals.insert("mth".to_string(), AxValue::Map(mth_map));
+    globals.insert("mth", AxValue::Map(mth_map));
 
     // =============== MODULE 15: NET ===============
-    let net_map = Arc::new(DashMap::new());
-    net_map.insert("get".to_string(), native("net.get", net_get));
-    net_map.insert("post".to_string(), native("net.post", net_post));
-    globals.insert("net".to_string(), AxValue::Map(net_map));
+    #[cfg(feature = "native")]
+    {
+        let net_map = Arc::new(DashMap::new());
+        net_map.insert("get".to_string(), native_result("net.get", net_get));
+        net_map.insert("post".to_string(), native_result("net.post", net_post));
+        globals.insert("net", AxValue::Map(net_map));
+    }
 
     // =============== MODULE 16: NUM ===============
     let num_map = Arc::new(DashMap::new());
     num_map.insert("zeros".to_string(), native("num.zeros", num_zeros));
     num_map.insert("ones".to_string(), native("num.ones", num_ones));
     num_map.insert("range_array".to_string(), native("num.range_array", num_range_array));
-    globals.insert("num".to_string(), AxValue::Map(num_map));
+    num_map.insert("parse".to_string(), native_result("num.parse", num_parse));
+    num_map.insert("parse_int".to_string(), native_result("num.parse_int", num_parse_int));
+    num_map.insert("to_hex".to_string(), native_result("num.to_hex", num_to_hex));
+    num_map.insert("to_bin".to_string(), native_result("num.to_bin", num_to_bin));
+    num_map.insert("to_oct".to_string(), native_result("num.to_oct", num_to_oct));
+    num_map.insert("format".to_string(), native_result("num.format", num_format));
+    globals.insert("num", AxValue::Map(num_map));
 
     // =============== MODULE 17: PLT ===============
-    let plt_map = Arc::new(DashMap::new());
-    plt_map.insert("scatter".to_string(), native("plt.scatter", plt_scatter));
-    plt_map.insert("line".to_string(), native("plt.line", plt_line));
-    globals.insert("plt".to_string(), AxValue::Map(plt_map));
+    #[cfg(feature = "native")]
+    {
+        let plt_map = Arc::new(DashMap::new());
+        plt_map.insert("scatter".to_string(), native("plt.scatter", plt_scatter));
+        plt_map.insert("line".to_string(), native("plt.line", plt_line));
+        globals.insert("plt", AxValue::Map(plt_map));
+    }
 
     // =============== MODULE 18: PTH ===============
     let pth_map = Arc::new(DashMap::new());
     pth_map.insert("list".to_string(), native("pth.list", pth_list));
     pth_map.insert("walk".to_string(), native("pth.walk", pth_walk));
     pth_map.insert("join".to_string(), native("pth.join", pth_join));
-    globals.insert("pth".to_string(), AxValue::Map(pth_map));
+    pth_map.insert("base".to_string(), native_result("pth.base", pth_base));
+    pth_map.insert("dir".to_string(), native_result("pth.dir", pth_dir));
+    pth_map.insert("ext".to_string(), native_result("pth.ext", pth_ext));
+    pth_map.insert("abs".to_string(), native_result("pth.abs", pth_abs));
+    pth_map.insert("rel".to_string(), native_result("pth.rel", pth_rel));
+    pth_map.insert("glob".to_string(), native_result("pth.glob", pth_glob));
+    pth_map.insert("mkdirs".to_string(), native_result("pth.mkdirs", pth_mkdirs));
+    pth_map.insert("copy".to_string(), native_result("pth.copy", pth_copy));
+    pth_map.insert("move".to_string(), native_result("pth.move", pth_move));
+    globals.insert("pth", AxValue::Map(pth_map));
 
     // =============== MODULE 19: STR ===============
     let str_map = Arc::new(DashMap::new());
@@ -2091,51 +5089,120 @@ pub fn register(globals: &mut HashMap<String, AxValue>) {
     str_map.insert("len".to_string(), native("str.len", str_len));
     str_map.insert("upper".to_string(), native("str.upper", str_upper));
     str_map.insert("lower".to_string(), native("str.lower", str_lower));
-    globals.insert("str".to_string(), AxValue::Map(str_map));
+    str_map.insert("builder".to_string(), native("str.builder", str_builder));
+    str_map.insert("fmt".to_string(), native_result("str.fmt", str_fmt));
+    globals.insert("str", AxValue::Map(str_map));
 
     // =============== MODULE 20: SYS ===============
     let sys_map = Arc::new(DashMap::new());
+    #[cfg(feature = "native")]
     sys_map.insert("info".to_string(), native("sys.info", sys_info));
+    #[cfg(feature = "native")]
     sys_map.insert("cpu_usage".to_string(), native("sys.cpu_usage", sys_cpu_usage));
+    #[cfg(feature = "native")]
     sys_map.insert("memory".to_string(), native("sys.memory", sys_memory));
-    sys_map.insert("chdir".to_string(), native("sys.chdir", sys_chdir));
-    sys_map.insert("cwd".to_string(), native("sys.cwd", sys_cwd));
-    globals.insert("sys".to_string(), AxValue::Map(sys_map));
-    globals.insert("chdir".to_string(), native("chdir", sys_chdir));
-    globals.insert("cwd".to_string(), native("cwd", sys_cwd));
+    sys_map.insert("chdir".to_string(), native_result("sys.chdir", sys_chdir));
+    sys_map.insert("cwd".to_string(), native_result("sys.cwd", sys_cwd));
+    sys_map.insert("flush".to_string(), native("sys.flush", sys_flush));
+    sys_map.insert("gc_collect".to_string(), native("sys.gc_collect", sys_gc_collect));
+    sys_map.insert("gc_stats".to_string(), native("sys.gc_stats", sys_gc_stats));
+    #[cfg(feature = "native")]
+    sys_map.insert("processes".to_string(), native("sys.processes", sys_processes));
+    #[cfg(feature = "native")]
+    sys_map.insert("kill".to_string(), native_result("sys.kill", sys_kill));
+    sys_map.insert("pid".to_string(), native("sys.pid", sys_pid));
+    sys_map.insert("platform".to_string(), native("sys.platform", sys_platform));
+    #[cfg(feature = "native")]
+    sys_map.insert("uptime".to_string(), native("sys.uptime", sys_uptime));
+    #[cfg(feature = "native")]
+    sys_map.insert("disks".to_string(), native("sys.disks", sys_disks));
+    #[cfg(feature = "native")]
+    sys_map.insert("net_ifaces".to_string(), native("sys.net_ifaces", sys_net_ifaces));
+    sys_map.insert("battery".to_string(), native("sys.battery", sys_battery));
+    globals.insert("sys", AxValue::Map(sys_map));
+    globals.insert("chdir", native_result("chdir", sys_chdir));
+    globals.insert("cwd", native_result("cwd", sys_cwd));
 
     // =============== MODULE 21: TIM ===============
     let tim_map = Arc::new(DashMap::new());
     tim_map.insert("now".to_string(), native("tim.now", tim_now));
     tim_map.insert("format".to_string(), native("tim.format", tim_format));
-    globals.insert("tim".to_string(), AxValue::Map(tim_map));
+    tim_map.insert("add".to_string(), native_result("tim.add", tim_add));
+    tim_map.insert("diff".to_string(), native_result("tim.diff", tim_diff));
+    tim_map.insert("parse".to_string(), native_result("tim.parse", tim_parse));
+    tim_map.insert("to_zone".to_string(), native_result("tim.to_zone", tim_to_zone));
+    tim_map.insert("start_of_day".to_string(), native_result("tim.start_of_day", tim_start_of_day));
+    tim_map.insert("weekday".to_string(), native_result("tim.weekday", tim_weekday));
+    tim_map.insert("stopwatch".to_string(), native("tim.stopwatch", tim_stopwatch));
+    tim_map.insert("elapsed_ms".to_string(), native_result("tim.elapsed_ms", tim_elapsed_ms));
+    tim_map.insert("bench".to_string(), native_result("tim.bench", tim_bench));
+    globals.insert("tim", AxValue::Map(tim_map));
 
     // =============== MODULE 22: TUI (ratatui + TachyonFX) ===============
-    let tui_map = Arc::new(DashMap::new());
-    tui_map.insert("block".to_string(),    native("tui.block",    tui_block));
-    tui_map.insert("list".to_string(),     native("tui.list",     tui_list));
-    tui_map.insert("table".to_string(),    native("tui.table",    tui_table));
-    tui_map.insert("gauge".to_string(),    native("tui.gauge",    tui_gauge));
-    tui_map.insert("sparkline".to_string(),native("tui.sparkline",tui_sparkline));
-    tui_map.insert("dashboard".to_string(),native("tui.dashboard",tui_dashboard));
-    // TachyonFX shader descriptors
-    tui_map.insert("fx_fade".to_string(),    native("tui.fx_fade",    tui_fx_fade));
-    tui_map.insert("fx_glitch".to_string(),  native("tui.fx_glitch",  tui_fx_glitch));
-    tui_map.insert("fx_rgb_split".to_string(),native("tui.fx_rgb_split",tui_fx_rgb_split));
-    tui_map.insert("fx_bounce".to_string(),  native("tui.fx_bounce",  tui_fx_bounce));
-    globals.insert("tui".to_string(), AxValue::Map(tui_map));
+    #[cfg(feature = "native")]
+    {
+        let tui_map = Arc::new(DashMap::new());
+        tui_map.insert("block".to_string(),    native("tui.block",    tui_block));
+        tui_map.insert("list".to_string(),     native("tui.list",     tui_list));
+        tui_map.insert("table".to_string(),    native("tui.table",    tui_table));
+        tui_map.insert("gauge".to_string(),    native("tui.gauge",    tui_gauge));
+        tui_map.insert("sparkline".to_string(),native("tui.sparkline",tui_sparkline));
+        tui_map.insert("dashboard".to_string(),native("tui.dashboard",tui_dashboard));
+        // TachyonFX shader descriptors
+        tui_map.insert("fx_fade".to_string(),    native("tui.fx_fade",    tui_fx_fade));
+        tui_map.insert("fx_glitch".to_string(),  native("tui.fx_glitch",  tui_fx_glitch));
+        tui_map.insert("fx_rgb_split".to_string(),native("tui.fx_rgb_split",tui_fx_rgb_split));
+        tui_map.insert("fx_bounce".to_string(),  native("tui.fx_bounce",  tui_fx_bounce));
+        globals.insert("tui", AxValue::Map(tui_map));
+    }
 
     // =============== MODULE 23: CLI ===============
     let cli_map = Arc::new(DashMap::new());
-    cli_map.insert("exec".to_string(), native("cli.exec", cli_exec));
-    cli_map.insert("shell".to_string(), native("cli.shell", cli_shell));
-    cli_map.insert("env".to_string(), native("cli.env", cli_env));
-    globals.insert("cli".to_string(), AxValue::Map(cli_map));
+    cli_map.insert("exec".to_string(), native_result("cli.exec", cli_exec));
+    cli_map.insert("shell".to_string(), native_result("cli.shell", cli_shell));
+    cli_map.insert("env".to_string(), native_result("cli.env", cli_env));
+    cli_map.insert("args".to_string(), native("cli.args", cli_args));
+    globals.insert("cli", AxValue::Map(cli_map));
 
     // =============== MODULE 24: USB (rusb) ===============
-    let usb_map = Arc::new(DashMap::new());
-    usb_map.insert("list".to_string(),     native("usb.list",     usb_list));
-    usb_map.insert("open".to_string(),     native("usb.open",     usb_open));
-    usb_map.insert("transfer".to_string(), native("usb.transfer", usb_transfer));
-    globals.insert("usb".to_string(), AxValue::Map(usb_map));
+    #[cfg(feature = "native")]
+    {
+        let usb_map = Arc::new(DashMap::new());
+        usb_map.insert("list".to_string(),     native_result("usb.list",     usb_list));
+        usb_map.insert("open".to_string(),     native_result("usb.open",     usb_open));
+        usb_map.insert("transfer".to_string(), native_result("usb.transfer", usb_transfer));
+        globals.insert("usb", AxValue::Map(usb_map));
+    }
+
+    // =============== MODULE 25: FFI (libloading) ===============
+    #[cfg(feature = "native")]
+    {
+        let ffi_map = Arc::new(DashMap::new());
+        ffi_map.insert("load".to_string(), native_result("ffi.load", ffi_load));
+        ffi_map.insert("fn".to_string(),   native_result("ffi.fn",   ffi_fn));
+        globals.insert("ffi", AxValue::Map(ffi_map));
+    }
+
+    // =============== MODULE 26: RES (resource lifetimes) ===============
+    let res_map = Arc::new(DashMap::new());
+    res_map.insert("defer".to_string(),   native_result("res.defer", res_defer));
+    res_map.insert("close".to_string(),   native_result("res.close", res_close));
+    res_map.insert("scope".to_string(),   native_result("res.scope", res_scope));
+    res_map.insert("pending".to_string(), native("res.pending", res_pending));
+    globals.insert("res", AxValue::Map(res_map));
+
+    // =============== MODULE 27: PKG (package features) ===============
+    let pkg_map = Arc::new(DashMap::new());
+    pkg_map.insert("feature".to_string(), native_result("pkg.feature", pkg_feature));
+    globals.insert("pkg", AxValue::Map(pkg_map));
+
+    // =============== MODULE 28: RPC (calls between local scripts) ===============
+    #[cfg(feature = "native")]
+    {
+        let rpc_map = Arc::new(DashMap::new());
+        rpc_map.insert("listen".to_string(), native_result("rpc.listen", rpc_listen));
+        rpc_map.insert("call".to_string(),   native_result("rpc.call",   rpc_call));
+        rpc_map.insert("stop".to_string(),   native("rpc.stop", rpc_stop));
+        globals.insert("rpc", AxValue::Map(rpc_map));
+    }
 }
@@ -30,7 +30,10 @@ pub enum Token {
     Cls,
     Ext,
     Enm,
+    Ifc,
+    Impl,
     SelfKw,
+    Super,
     Out,
     Print,      // NEW: print statement (alias for out)
     New,
@@ -80,7 +83,11 @@ pub enum Token {
     Semicolon,
     Colon,
     Dot,
+    /// `..` in range and list-rest patterns
+    DotDot,
     Arrow,
+    /// `->` introducing a return type annotation
+    ThinArrow,
 
     /// Nil literal keyword
     Nil,
@@ -344,7 +351,10 @@ impl Lexer {
                         "cls" => Token::Cls,
                         "ext" => Token::Ext,
                         "enm" => Token::Enm,
+                        "ifc" => Token::Ifc,
+                        "impl" => Token::Impl,
                         "self" => Token::SelfKw,
+                        "super" => Token::Super,
                         "out" => Token::Out,
                         "print" => Token::Print,
                         "new" => Token::New,
@@ -364,7 +374,12 @@ impl Lexer {
                         }
                         '-' => {
                             self.advance();
-                            Token::Minus
+                            if self.current() == Some('>') {
+                                self.advance();
+                                Token::ThinArrow
+                            } else {
+                                Token::Minus
+                            }
                         }
                         '*' => {
                             self.advance();
@@ -469,6 +484,11 @@ impl Lexer {
                             self.advance();
                             Token::Colon
                         }
+                        '.' if self.peek(1) == Some('.') => {
+                            self.advance();
+                            self.advance();
+                            Token::DotDot
+                        }
                         '.' => {
                             self.advance();
                             Token::Dot
@@ -573,7 +593,7 @@ mod tests {
 
     #[test]
     fn test_keywords() {
-        let mut lexer = Lexer::new("cls ext enm self out print new match go loc load", 0);
+        let mut lexer = Lexer::new("cls ext enm ifc impl self super out print new match go loc load", 0);
         let tokens: Vec<Token> = lexer.tokenize().into_iter().map(|(t, _)| t).collect();
         assert_eq!(
             tokens,
@@ -581,7 +601,10 @@ mod tests {
                 Token::Cls,
                 Token::Ext,
                 Token::Enm,
+                Token::Ifc,
+                Token::Impl,
                 Token::SelfKw,
+                Token::Super,
                 Token::Out,
                 Token::Print,
                 Token::New,
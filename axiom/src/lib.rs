@@ -18,19 +18,35 @@
 ///   Execution
 ///     vm_core       — Register-based bytecode VM (Val, VmCore)
 ///     runtime       — High-level Runtime: compile → VM → tree-walk fallback
+///     hooks         — Statement, call, output and error hooks for embedders
+///     spec          — `axiom spec`: conformance programs run on both engines
+///     sandbox       — Resource limits (instructions, timeout, allocation)
+///     repro         — `--seed` / `--frozen-time`: seeded random numbers, fake clock
+///     service       — `axiom service run`: long-lived scripts with restarts, log file, SIGTERM
+///     wasm          — Sandboxed `run_source` entry point for the browser playground
+///     playground    — `axiom serve-playground` web editor + WebSocket runner
+///     repl          — `axiom repl`: interactive session with history, completion, :save/:load
 ///
 ///   Runtime support
+///     intern        — Process-wide string interner (identifiers, property names, constants)
 ///     nanbox        — NaN-boxed 64-bit value representation
 ///     inline_cache  — Polymorphic inline caches + shape system
 ///     gc            — Generational garbage collector
 ///     profiler      — Opcode counters, hot-loop detection, flame graph
 ///     conf          — Runtime configuration (toggles, ~/.axiom/conf.txt)
-///     intrinsics    — Statically-linked standard library (23 modules)
+///     logger        — Leveled, scoped logger behind the `log` module (text/JSON, file rotation)
+///     rpc           — Unix-socket transport behind the `rpc` module (calls between local scripts)
+///     intrinsics    — Statically-linked standard library (28 modules; git/sys/tui/usb/ffi/rpc need `native`)
 ///     jit           — Experimental trace-JIT stub
 ///     loader        — Module file resolution + loading
 ///
 ///   Packaging
-///     pkg           — Axiomite package manager (Axiomite.toml, deps)
+///     completions   — Shell completion scripts for `axiom completions`
+///     pkg           — Axiomite package manager (Axiomite.toml, deps; install needs `native`)
+///     build         — `axiom build`: check a project's modules and bundle them into a .axc
+///     install       — `axiom install`: ~/.axiom layout, binary copy, opt-in PATH edits (native)
+///     hook          — `axiom hook install`: git pre-commit hook running fmt --check and chk
+///     crash         — `--crash-report`: local crash bundles in ~/.axiom/crash, `axiom report show`
 ///     core          — AxValue, AxCallable, AxClass, AxInstance
 
 // ── Compilation pipeline ──────────────────────────────────────────────────────
@@ -49,13 +65,27 @@ pub mod optimizer;
 // ── Execution ─────────────────────────────────────────────────────────────────
 pub mod vm_core;
 pub mod runtime;
+pub mod hooks;
+pub mod spec;
+pub mod sandbox;
+pub mod repro;
+pub mod service;
+pub mod wasm;
+#[cfg(feature = "native")]
+pub mod playground;
+#[cfg(feature = "native")]
+pub mod repl;
 
 // ── Runtime support ───────────────────────────────────────────────────────────
+pub mod intern;
 pub mod nanbox;
 pub mod inline_cache;
 pub mod gc;
 pub mod profiler;
 pub mod conf;
+pub mod logger;
+#[cfg(feature = "native")]
+pub mod rpc;
 pub mod intrinsics;
 pub mod jit;
 pub mod loader;
@@ -65,6 +95,14 @@ pub mod core;
 
 // ── Package management ────────────────────────────────────────────────────────
 pub mod pkg;
+pub mod build;
+pub mod hook;
+#[cfg(feature = "native")]
+pub mod install;
+
+// ── CLI support ───────────────────────────────────────────────────────────────
+pub mod completions;
+pub mod crash;
 
 // ── Diagnostics (error codes AXM_100-699) ─────────────────────────────────────
 pub mod diagnostics;
@@ -81,3 +119,4 @@ pub use loader::{resolve_module_path, load_local_module};
 pub use nanbox::NanVal;
 pub use parser::Parser;
 pub use runtime::Runtime;
+pub use sandbox::{Capabilities, ResourceLimits};
@@ -1,36 +1,362 @@
 /// Axiom Module Loader
 ///
-/// Handles loading of local `.ax` modules.
+/// Handles resolving and loading of local `.ax` modules (nested, relative
+/// and from installed packages) and the `exp` lists that decide which of a
+/// module's names its importers see.
 /// Standard library dynamic loading has been removed.
 
-use std::path::PathBuf;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
 
-/// Resolve the path to a local module file.
+use crate::ast::{ClassMember, Expr, Item, MatchArm, MatchPattern, Stmt, StringPart};
+
+/// Resolve the path to a local module file, relative to the importing
+/// directory. Dots in the name separate directories.
 ///
 /// # Arguments
-/// * `name` - Module name (e.g., "mymod" → resolves to "./mymod.ax")
+/// * `name` - Module name (e.g., "net.handlers.auth" → resolves to "net/handlers/auth.ax")
 pub fn resolve_module_path(name: &str) -> PathBuf {
-    PathBuf::from(format!("{}.ax", name))
+    PathBuf::from(format!("{}.ax", name.replace('.', "/")))
 }
 
 /// Load a local module by name.
 ///
 /// # Arguments
-/// * `name` - Module name (file in current directory, no extension)
+/// * `name` - Module spec as written after `loc`, resolved from the current directory
 ///
 /// # Returns
 /// * `Result<String, String>` - Module source code or error message
 pub fn load_local_module(name: &str) -> Result<String, String> {
-    let path = resolve_module_path(name);
+    let path = resolve_loc(name, Path::new("."))?;
+    std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read module '{}': {}", name, e))
+}
+
+/// Directories a `loc` searches after the importing file's own, in order:
+/// each entry of `AXIOM_PATH`, then the installed packages (`AXIOM_LIBS`,
+/// by default `~/.axiomlibs`), so `loc user.repo.util` finds a package module.
+pub fn search_path() -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = std::env::var_os("AXIOM_PATH")
+        .map(|paths| std::env::split_paths(&paths).collect())
+        .unwrap_or_default();
+    match std::env::var_os("AXIOM_LIBS") {
+        Some(libs) => dirs.push(PathBuf::from(libs)),
+        None => dirs.extend(dirs::home_dir().map(|home| home.join(".axiomlibs"))),
+    }
+    dirs
+}
 
-    if path.exists() {
-        std::fs::read_to_string(&path)
-            .map_err(|e| format!("Failed to read module '{}': {}", name, e))
+/// Resolve a `loc` spec imported from a file in `base`. `a.b.c` is `a/b/c.ax`,
+/// or the package entry `a/b/c/lib.ax`, in `base` or else the first `search_path`
+/// directory that has it. A leading `.` looks in `base` only and each further
+/// `.` one directory up: `..util` is `util.ax` next to `base`.
+pub fn resolve_loc(spec: &str, base: &Path) -> Result<PathBuf, String> {
+    let name = spec.trim_start_matches('.');
+    let ups = spec.len() - name.len();
+    let dirs = if ups == 0 {
+        std::iter::once(base.to_path_buf()).chain(search_path()).collect()
     } else {
-        Err(format!(
-            "Module '{}' not found at '{}'",
-            name,
-            path.display()
-        ))
+        let mut dir = base.to_path_buf();
+        for _ in 1..ups { dir.push(".."); }
+        vec![dir]
+    };
+    let relative = resolve_module_path(name);
+    for dir in &dirs {
+        let file = dir.join(&relative);
+        if file.is_file() { return Ok(file); }
+        let entry = dir.join(relative.with_extension("")).join("lib.ax");
+        if entry.is_file() { return Ok(entry); }
+    }
+    let searched: Vec<String> = dirs.iter().map(|d| d.display().to_string()).collect();
+    Err(format!("module '{}' not found (searched {})", spec, searched.join(", ")))
+}
+
+/// The name `loc spec` binds its module map to by default: the last segment.
+pub fn loc_binding(spec: &str) -> &str {
+    spec.rsplit('.').next().unwrap_or(spec)
+}
+
+// ---------------------------------------------------------------------------
+// Export control
+// ---------------------------------------------------------------------------
+
+/// The names a module lists in its `exp` declarations, or `None` when it has
+/// none and so exports every top-level name.
+pub fn exports(items: &[Item]) -> Option<HashSet<String>> {
+    let mut names: Option<HashSet<String>> = None;
+    for item in items {
+        if let Item::Export { names: listed, .. } = item {
+            names.get_or_insert_with(HashSet::new).extend(listed.iter().cloned());
+        }
+    }
+    names
+}
+
+/// The module's top-level functions, classes, enums and `let`s, in order.
+pub fn top_level_names(items: &[Item]) -> Vec<&str> {
+    items.iter().filter_map(|item| match item {
+        Item::FunctionDecl { name, .. } | Item::ClassDecl { name, .. } | Item::EnumDecl { name, .. }
+        | Item::Statement(Stmt::Let { name, .. }) => Some(name.as_str()),
+        _ => None,
+    }).collect()
+}
+
+/// A file whose spans carry its `source_id`, for rendering diagnostics.
+#[derive(Debug, Clone)]
+pub struct SourceFile {
+    pub path: PathBuf,
+    pub text: Arc<str>,
+}
+
+impl SourceFile {
+    /// The path diagnostics show: relative to the working directory when
+    /// the file is under it.
+    pub fn name(&self) -> String {
+        let cwd = std::env::current_dir().unwrap_or_default();
+        self.path.strip_prefix(&cwd).unwrap_or(&self.path).display().to_string()
+    }
+}
+
+/// Every module file parsed in this process; `source_id` n is entry n - 1.
+/// Id 0 is left to the file a caller parses itself (the main program),
+/// which its own `DiagnosticEngine` renders.
+static SOURCES: RwLock<Vec<SourceFile>> = RwLock::new(Vec::new());
+
+/// The `source_id` to parse `text`, read from `path`, with. A file seen
+/// before with the same text keeps its id.
+pub fn register_source(path: &Path, text: &str) -> u32 {
+    let mut sources = SOURCES.write().unwrap_or_else(|e| e.into_inner());
+    if let Some(i) = sources.iter().position(|f| f.path == path && *f.text == *text) {
+        return i as u32 + 1;
+    }
+    sources.push(SourceFile { path: path.to_path_buf(), text: text.into() });
+    sources.len() as u32
+}
+
+/// Every registered file, in `source_id` order.
+pub fn sources() -> Vec<SourceFile> {
+    SOURCES.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// The file registered under `source_id`, if any.
+pub fn source(source_id: u32) -> Option<SourceFile> {
+    let index = (source_id as usize).checked_sub(1)?;
+    SOURCES.read().unwrap_or_else(|e| e.into_inner()).get(index).cloned()
+}
+
+/// The key a module's path is registered under: `@user/my-lib` → `user.my_lib`.
+pub fn module_key(path: &str) -> String {
+    path.trim_start_matches('@').replace('/', ".").replace('-', "_")
+}
+
+/// The name a private top-level `name` of `module` is bound under once loaded.
+/// `::` cannot appear in an identifier, so importers have no way to spell it.
+pub fn private_name(module: &str, name: &str) -> String {
+    format!("{}::{}", module, name)
+}
+
+/// Rename every top-level name the module does not export, and all references
+/// to it inside the module, to its `private_name`. The module's own code keeps
+/// working while the importer only sees the exported names. Fails on an `exp`
+/// of a name the module never declares.
+pub fn hide_private(items: Vec<Item>, module: &str) -> Result<Vec<Item>, String> {
+    hide(items, module, false)
+}
+
+/// Like `hide_private`, but exported functions, enums and `let`s are renamed
+/// too, for a `loc` import that reaches them only through the module's map.
+/// Exported classes keep their names, as `new` takes a bare class name.
+pub fn hide_all(items: Vec<Item>, module: &str) -> Result<Vec<Item>, String> {
+    hide(items, module, true)
+}
+
+fn hide(mut items: Vec<Item>, module: &str, qualify_exports: bool) -> Result<Vec<Item>, String> {
+    let exported = exports(&items);
+    if exported.is_none() && !qualify_exports { return Ok(items); }
+    let declared = top_level_names(&items);
+    if let Some(missing) = exported.iter().flatten().find(|name| !declared.contains(&name.as_str())) {
+        return Err(format!("module exports '{}', which it does not declare", missing));
+    }
+    let is_exported = |name: &str| exported.as_ref().is_none_or(|names| names.contains(name));
+    let private: HashSet<String> = items.iter().filter_map(|item| match item {
+        Item::ClassDecl { name, .. } if is_exported(name) => None,
+        Item::FunctionDecl { name, .. } | Item::ClassDecl { name, .. } | Item::EnumDecl { name, .. }
+        | Item::Statement(Stmt::Let { name, .. }) if qualify_exports || !is_exported(name) => Some(name.clone()),
+        _ => None,
+    }).collect();
+    if private.is_empty() { return Ok(items); }
+    let mut hider = Hider { module, private, shadowed: Vec::new() };
+    for item in &mut items { hider.item(item); }
+    Ok(items)
+}
+
+/// Renames references to private top-level names, except where a parameter,
+/// local `let`, loop variable or match binding shadows them.
+struct Hider<'a> {
+    module: &'a str,
+    private: HashSet<String>,
+    shadowed: Vec<HashSet<String>>,
+}
+
+impl Hider<'_> {
+    fn rename(&self, name: &mut String) {
+        if self.private.contains(name.as_str()) && !self.shadowed.iter().any(|scope| scope.contains(name.as_str())) {
+            *name = private_name(self.module, name);
+        }
+    }
+
+    fn bind(&mut self, name: &str) {
+        if let Some(scope) = self.shadowed.last_mut() { scope.insert(name.to_string()); }
+    }
+
+    fn scoped(&mut self, bound: &[String], body: &mut [Stmt]) {
+        self.shadowed.push(bound.iter().cloned().collect());
+        for stmt in body { self.stmt(stmt); }
+        self.shadowed.pop();
+    }
+
+    fn item(&mut self, item: &mut Item) {
+        match item {
+            Item::FunctionDecl { name, params, body, .. } => {
+                self.rename(name);
+                self.scoped(params, body);
+            }
+            Item::ClassDecl { name, parent, body, .. } => {
+                self.rename(name);
+                if let Some(parent) = parent { self.rename(parent); }
+                for member in body {
+                    match member {
+                        ClassMember::Method { params, body, .. } | ClassMember::Accessor { params, body, .. } => self.scoped(params, body),
+                        ClassMember::Field { default, .. } => if let Some(e) = default { self.expr(e) },
+                    }
+                }
+            }
+            Item::EnumDecl { name, .. } => self.rename(name),
+            // At top level a `let` binds the module's own name, not a local
+            Item::Statement(Stmt::Let { name, value, .. }) => {
+                self.expr(value);
+                self.rename(name);
+            }
+            Item::Statement(stmt) => self.stmt(stmt),
+            Item::InterfaceDecl { .. } | Item::LocImport { .. } | Item::StdImport { .. } | Item::LibDecl { .. } | Item::Export { .. } | Item::LoadStmt { .. } => {}
+        }
+    }
+
+    fn stmt(&mut self, stmt: &mut Stmt) {
+        match stmt {
+            Stmt::Expr(e) => self.expr(e),
+            Stmt::Let { name, value, .. } => {
+                self.expr(value);
+                self.bind(name);
+            }
+            Stmt::Return { value, .. } => if let Some(e) = value { self.expr(e) },
+            Stmt::If { condition, then_body, else_body, .. } => {
+                self.expr(condition);
+                self.scoped(&[], then_body);
+                if let Some(body) = else_body { self.scoped(&[], body); }
+            }
+            Stmt::While { condition, body, .. } => {
+                self.expr(condition);
+                self.scoped(&[], body);
+            }
+            Stmt::For { var, iterable, body, .. } => {
+                self.expr(iterable);
+                self.scoped(std::slice::from_ref(var), body);
+            }
+            Stmt::Block(body) | Stmt::GoSpawn { body, .. } => self.scoped(&[], body),
+            Stmt::Match { expr, arms, .. } => {
+                self.expr(expr);
+                self.arms(arms);
+            }
+            Stmt::Out { arguments, .. } => for e in arguments { self.expr(e) },
+        }
+    }
+
+    fn arms(&mut self, arms: &mut [MatchArm]) {
+        for arm in arms {
+            self.pattern(&mut arm.pattern);
+            let bound: Vec<String> = arm.pattern.bindings().into_iter().map(|(name, _)| name.to_string()).collect();
+            self.shadowed.push(bound.into_iter().collect());
+            if let Some(guard) = &mut arm.guard { self.expr(guard); }
+            for stmt in &mut arm.body { self.stmt(stmt); }
+            self.shadowed.pop();
+        }
+    }
+
+    fn pattern(&mut self, pattern: &mut MatchPattern) {
+        match pattern {
+            MatchPattern::EnumVariant { enum_name: Some(name), .. } => self.rename(name),
+            MatchPattern::Literal(e) => self.expr(e),
+            MatchPattern::Range { start, end } => { self.expr(start); self.expr(end); }
+            MatchPattern::Or(alts) => for alt in alts { self.pattern(alt) },
+            MatchPattern::List { items, rest } => {
+                for item in items.iter_mut().chain(rest.as_deref_mut()) { self.pattern(item); }
+            }
+            MatchPattern::EnumVariant { .. } | MatchPattern::Identifier(_) | MatchPattern::Wildcard => {}
+        }
+    }
+
+    fn expr(&mut self, expr: &mut Expr) {
+        match expr {
+            Expr::Identifier { name, .. } => self.rename(name),
+            Expr::New { class_name, arguments, .. } => {
+                self.rename(class_name);
+                for e in arguments { self.expr(e); }
+            }
+            Expr::List { items, .. } => for e in items { self.expr(e) },
+            Expr::BinaryOp { left, right, .. } => { self.expr(left); self.expr(right); }
+            Expr::UnaryOp { operand, .. } => self.expr(operand),
+            Expr::Call { function, arguments, .. } => {
+                self.expr(function);
+                for e in arguments { self.expr(e); }
+            }
+            Expr::MethodCall { object, arguments, .. } => {
+                self.expr(object);
+                for e in arguments { self.expr(e); }
+            }
+            Expr::SuperCall { arguments, .. } => for e in arguments { self.expr(e) },
+            Expr::Index { object, index, .. } => { self.expr(object); self.expr(index); }
+            Expr::MemberAccess { object, .. } => self.expr(object),
+            Expr::Assign { target, value, .. } => { self.expr(target); self.expr(value); }
+            Expr::InterpolatedString { parts, .. } => {
+                for part in parts {
+                    if let StringPart::Expr(e) | StringPart::Formatted { expr: e, .. } = part { self.expr(e); }
+                }
+            }
+            Expr::Lambda { params, body, .. } => self.scoped(params, body),
+            Expr::Match { expr, arms, .. } => {
+                self.expr(expr);
+                self.arms(arms);
+            }
+            Expr::Number { .. } | Expr::String { .. } | Expr::Boolean { .. } | Expr::SelfRef { .. } => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hide(src: &str) -> Result<Vec<Item>, String> {
+        hide_private(crate::Parser::new(src, 0).parse().expect("parse failed"), "m")
+    }
+
+    #[test]
+    fn test_hide_private_renames_unexported_names() {
+        let items = hide("exp area\nlet k = 2\nfun twice(x) { ret x * k }\nfun area(k) { ret twice(k) + k }").unwrap();
+        assert_eq!(top_level_names(&items), ["m::twice", "area", "m::k"]);
+        // `area`'s parameter `k` shadows the private `k`; `twice` still sees it
+        let Some(Item::FunctionDecl { body, .. }) = items.iter().find(|i| matches!(i, Item::FunctionDecl { name, .. } if name == "area")) else { panic!() };
+        let Stmt::Return { value: Some(Expr::BinaryOp { left, right, .. }), .. } = &body[0] else { panic!("{:?}", body) };
+        assert!(matches!(&**left, Expr::Call { function, .. } if matches!(&**function, Expr::Identifier { name, .. } if name == "m::twice")));
+        assert!(matches!(&**right, Expr::Identifier { name, .. } if name == "k"));
+    }
+
+    #[test]
+    fn test_modules_without_exp_export_everything() {
+        let items = hide("fun f() { ret g() }\nfun g() { ret 1 }").unwrap();
+        assert_eq!(top_level_names(&items), ["f", "g"]);
+        assert!(hide("exp f, missing\nfun f() { }").unwrap_err().contains("'missing'"));
     }
 }
@@ -0,0 +1,300 @@
+//! Structured logger behind the `log` intrinsic module.
+//!
+//! One process-wide logger, configured from conf on first use
+//! (`log_level`, `log_format`, `log_timestamps`, `log_file`, `log_max_mb`)
+//! and adjustable from scripts with `log.set_level`, `log.set_format` and
+//! `log.to_file`. Records below the level are dropped before formatting.
+//!
+//! Console records at info and below go through the runtime's output
+//! sink; warn and error go to stderr, unless the run's output is captured
+//! (`Runtime::run_captured`), which then sees every record. With a
+//! file sink every record goes to the file instead, rotated to `path.1`,
+//! `path.2`, … once it grows past the size limit.
+//!
+//! Scopes (`log.scope(name)`) are per thread and nest: records carry the
+//! joined path, e.g. `[app.db]`.
+
+use crate::conf::AxConf;
+use std::cell::RefCell;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Rotated files kept next to the active log (`path.1` … `path.N`).
+const LOG_BACKUPS: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+    /// Only valid as a threshold: silences everything.
+    Off,
+}
+
+impl Level {
+    pub const NAMES: &'static str = "trace, debug, info, warn, error, off";
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Level::Trace => "trace",
+            Level::Debug => "debug",
+            Level::Info  => "info",
+            Level::Warn  => "warn",
+            Level::Error => "error",
+            Level::Off   => "off",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Level> {
+        Some(match name.trim().to_ascii_lowercase().as_str() {
+            "trace" => Level::Trace,
+            "debug" => Level::Debug,
+            "info"  => Level::Info,
+            "warn" | "warning" => Level::Warn,
+            "error" => Level::Error,
+            "off" | "none" => Level::Off,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// `2026-01-02T03:04:05.678Z [INFO] [app.db] message`
+    Text,
+    /// One JSON object per line: `{"ts":…,"level":"info","scope":"app.db","msg":…}`
+    Json,
+}
+
+impl Format {
+    pub fn from_name(name: &str) -> Option<Format> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "text" => Some(Format::Text),
+            "json" => Some(Format::Json),
+            _ => None,
+        }
+    }
+}
+
+/// An append-only log file, rotated to `.1`..`.3` once it passes `max_bytes`
+/// (shared with `axiom service run --log-file`).
+pub(crate) struct FileSink {
+    path: PathBuf,
+    max_bytes: u64,
+    file: File,
+    written: u64,
+}
+
+impl FileSink {
+    pub(crate) fn open(path: &Path, max_bytes: u64) -> io::Result<FileSink> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let written = file.metadata()?.len();
+        Ok(FileSink { path: path.to_path_buf(), max_bytes, file, written })
+    }
+
+    pub(crate) fn write_line(&mut self, line: &str) -> io::Result<()> {
+        if self.max_bytes > 0 && self.written > 0 && self.written + line.len() as u64 + 1 > self.max_bytes {
+            self.rotate()?;
+        }
+        writeln!(self.file, "{}", line)?;
+        self.written += line.len() as u64 + 1;
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        let backup = |n: usize| PathBuf::from(format!("{}.{}", self.path.display(), n));
+        for n in (1..LOG_BACKUPS).rev() {
+            if backup(n).exists() {
+                std::fs::rename(backup(n), backup(n + 1))?;
+            }
+        }
+        std::fs::rename(&self.path, backup(1))?;
+        self.file = OpenOptions::new().create(true).write(true).truncate(true).open(&self.path)?;
+        self.written = 0;
+        Ok(())
+    }
+}
+
+pub struct Logger {
+    level: Level,
+    format: Format,
+    timestamps: bool,
+    file: Option<FileSink>,
+}
+
+impl Logger {
+    pub fn from_conf(conf: &AxConf) -> Logger {
+        let mut logger = Logger {
+            level: Level::from_name(conf.log_level()).unwrap_or(Level::Info),
+            format: Format::from_name(conf.log_format()).unwrap_or(Format::Text),
+            timestamps: conf.log_timestamps(),
+            file: None,
+        };
+        if !conf.log_file().is_empty() {
+            // A bad conf path falls back to the console rather than failing every script
+            logger.file = FileSink::open(Path::new(conf.log_file()), conf.log_max_mb() as u64 * 1024 * 1024).ok();
+        }
+        logger
+    }
+
+    /// Format one record (no trailing newline).
+    pub fn render(&self, level: Level, scope: &str, msg: &str) -> String {
+        let ts = || chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+        match self.format {
+            Format::Json => {
+                let mut rec = serde_json::json!({ "ts": ts(), "level": level.name(), "msg": msg });
+                if !scope.is_empty() {
+                    rec["scope"] = serde_json::Value::String(scope.to_string());
+                }
+                rec.to_string()
+            }
+            Format::Text => {
+                let mut line = String::new();
+                // Files always get timestamps; the console only when asked
+                if self.timestamps || self.file.is_some() {
+                    line.push_str(&ts());
+                    line.push(' ');
+                }
+                line.push_str(&format!("[{}] ", level.name().to_ascii_uppercase()));
+                if !scope.is_empty() {
+                    line.push_str(&format!("[{}] ", scope));
+                }
+                line.push_str(msg);
+                line
+            }
+        }
+    }
+}
+
+static LOGGER: Mutex<Option<Logger>> = Mutex::new(None);
+
+thread_local! {
+    static SCOPES: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+fn with_logger<T>(f: impl FnOnce(&mut Logger) -> T) -> T {
+    let mut guard = LOGGER.lock().unwrap_or_else(|e| e.into_inner());
+    let logger = guard.get_or_insert_with(|| Logger::from_conf(&AxConf::load()));
+    f(logger)
+}
+
+/// Emit `msg` at `level` if the current threshold allows it.
+pub fn log(level: Level, msg: &str) {
+    let scope = current_scope();
+    with_logger(|logger| {
+        if level < logger.level || level == Level::Off {
+            return;
+        }
+        let line = logger.render(level, &scope, msg);
+        if let Some(sink) = logger.file.as_mut() {
+            if sink.write_line(&line).is_ok() {
+                return;
+            }
+        }
+        if level >= Level::Warn && !crate::runtime::output_captured() {
+            // Keep buffered output ahead of the warning it led up to
+            crate::runtime::flush_output();
+            eprintln!("{}", line);
+        } else {
+            crate::runtime::write_line(&line);
+        }
+    });
+}
+
+pub fn level() -> Level {
+    with_logger(|l| l.level)
+}
+
+pub fn set_level(level: Level) {
+    with_logger(|l| l.level = level);
+}
+
+pub fn set_format(format: Format) {
+    with_logger(|l| l.format = format);
+}
+
+/// Send all further records to `path`, rotating past `max_bytes` (0 = never).
+pub fn to_file(path: &Path, max_bytes: u64) -> io::Result<()> {
+    let sink = FileSink::open(path, max_bytes)?;
+    with_logger(|l| l.file = Some(sink));
+    Ok(())
+}
+
+/// Back to console output.
+pub fn to_console() {
+    with_logger(|l| l.file = None);
+}
+
+pub fn push_scope(name: &str) {
+    SCOPES.with(|s| s.borrow_mut().push(name.to_string()));
+}
+
+pub fn pop_scope() -> Option<String> {
+    SCOPES.with(|s| s.borrow_mut().pop())
+}
+
+/// Nested scope names joined with `.`, or "" outside any scope.
+pub fn current_scope() -> String {
+    SCOPES.with(|s| s.borrow().join("."))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn logger(format: Format) -> Logger {
+        Logger { level: Level::Info, format, timestamps: false, file: None }
+    }
+
+    #[test]
+    fn test_render_text_and_json() {
+        assert_eq!(logger(Format::Text).render(Level::Warn, "app.db", "slow query"), "[WARN] [app.db] slow query");
+        assert_eq!(logger(Format::Text).render(Level::Info, "", "hi"), "[INFO] hi");
+
+        let json: serde_json::Value = serde_json::from_str(&logger(Format::Json).render(Level::Error, "app", "boom")).unwrap();
+        assert_eq!(json["level"], "error");
+        assert_eq!(json["scope"], "app");
+        assert_eq!(json["msg"], "boom");
+        assert!(json["ts"].as_str().unwrap().ends_with('Z'));
+    }
+
+    #[test]
+    fn test_scopes_nest() {
+        push_scope("app");
+        push_scope("db");
+        assert_eq!(current_scope(), "app.db");
+        assert_eq!(pop_scope().as_deref(), Some("db"));
+        assert_eq!(current_scope(), "app");
+        pop_scope();
+        assert_eq!(current_scope(), "");
+    }
+
+    #[test]
+    fn test_captured_runs_see_errors_too() {
+        let items = crate::Parser::new("out 1;\nlog.error(\"boom\")\nout 2", 0).parse().unwrap();
+        let (result, output) = crate::Runtime::new().run_captured(items);
+        result.unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!((lines.len(), lines[0], lines[2]), (3, "1", "2"));
+        assert!(lines[1].contains("boom"), "{}", output);
+    }
+
+    #[test]
+    fn test_file_sink_rotates() {
+        let dir = std::env::temp_dir().join(format!("axiom-log-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("run.log");
+        let mut sink = FileSink::open(&path, 32).unwrap();
+        for i in 0..5 {
+            sink.write_line(&format!("record number {}", i)).unwrap();
+        }
+        assert!(std::fs::read_to_string(&path).unwrap().contains("record number 4"));
+        assert!(dir.join("run.log.1").exists());
+        assert!(std::fs::metadata(&path).unwrap().len() <= 32);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
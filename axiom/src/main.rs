@@ -1,11 +1,13 @@
 /// Axiom CLI (axiom)
 /// Orchestrates run, pkg, fmt, chk, and conf commands.
 
-use axiom::{Parser, Runtime, SemanticAnalyzer, format_source};
-use axiom::conf::{cmd_conf_set, cmd_conf_get, cmd_conf_list, cmd_conf_reset, cmd_conf_describe};
+use axiom::{Parser, Runtime, SemanticAnalyzer};
+use axiom::runtime::Engine;
+use axiom::fmt::{format_source_with, FmtConfig};
+use axiom::conf::{cmd_conf_set, cmd_conf_get, cmd_conf_list, cmd_conf_reset, cmd_conf_describe, cmd_conf_profiles, cmd_conf_edit, ConfTarget};
 use axiom::pkg::PackageManager;
 use axiom::errors::DiagnosticLevel;
-use clap::{Parser as ClapParser, Subcommand};
+use clap::{CommandFactory, Parser as ClapParser, Subcommand, ValueEnum};
 use miette::{Result, IntoDiagnostic};
 use std::io::Write;
 use std::path::PathBuf;
@@ -23,24 +25,163 @@ const STACK_SIZE: usize = 64 * 1024 * 1024;
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+    /// Overlay ~/.axiom/profiles/<NAME>.txt on the configuration
+    /// (for `conf set`, write to that profile instead)
+    #[arg(long, global = true, value_name = "NAME")]
+    profile: Option<String>,
+    /// Override a property for this run only (repeatable); beats AXIOM_<PROPERTY> and every conf file
+    #[arg(long = "conf", global = true, value_name = "PROPERTY=VALUE")]
+    conf_overrides: Vec<String>,
+    /// If axiom crashes, write a report to ~/.axiom/crash (never sent anywhere)
+    #[arg(long, global = true)]
+    crash_report: bool,
+    /// Make the run reproducible: seed random numbers with N and walk maps in key order
+    #[arg(long, global = true, value_name = "N")]
+    seed: Option<u64>,
+    /// With --seed, replace the clock with fake time from 2000-01-01T00:00:00Z
+    #[arg(long, global = true, requires = "seed")]
+    frozen_time: bool,
+    /// Reading an undeclared variable is an error on both engines, as if strict_vars=on
+    #[arg(long, global = true)]
+    strict: bool,
+}
+
+/// How diagnostics are written: human-readable reports on stderr, or a
+/// JSON array on stdout for editors, CI annotations, and hooks.
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    Text,
+    Json,
+}
+
+/// Intermediate representation printed by `--emit` instead of running or checking.
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Emit {
+    /// The parsed Item tree
+    Ast,
+    /// The compiled prototypes (what the VM executes)
+    Bytecode,
+}
+
+/// Engine for `run --engine` (default: the `engine` conf value).
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum EngineArg {
+    /// Tree-walking interpreter
+    Interp,
+    /// Bytecode compiler + register VM for the whole program
+    Vm,
 }
 
 #[derive(Subcommand)]
 enum Commands {
-    /// Execute an Axiom script (.ax)
+    /// Execute Axiom scripts (.ax) or bundles built by `axiom build` (.axc)
     Run {
+        /// Scripts to run one after another; a quoted pattern like "jobs/*.ax" runs every match
+        #[arg(required = true, value_name = "PATH")]
+        paths: Vec<PathBuf>,
+        /// Diagnostic output format for parse errors (also used by --emit)
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+        /// Print the AST or bytecode to stdout and exit without running
+        #[arg(long, value_enum, value_name = "IR")]
+        emit: Option<Emit>,
+        /// Print GC collection events and a summary to stderr (same as --conf gc_verbose=on)
+        #[arg(long)]
+        gc_log: bool,
+        /// Record allocation sites and live objects, then write a JSON snapshot (see `axiom heap-report`)
+        #[arg(long, value_name = "OUT.json")]
+        heap_profile: Option<PathBuf>,
+        /// Print inline-cache hit rates and per-site states to stderr after the run
+        #[arg(long)]
+        ic_stats: bool,
+        /// Print how many superinstructions the optimizer fused to stderr after the run
+        #[arg(long)]
+        opt_stats: bool,
+        /// Time every function call and print the slowest functions to stderr
+        /// after the run (runs on the tree-walker)
+        #[arg(long)]
+        profile_calls: bool,
+        /// Run on the bytecode VM and print each executed instruction to stderr,
+        /// optionally only inside function FN (`<main>` for top-level code)
+        #[arg(long, value_name = "FN", num_args = 0..=1, require_equals = true, default_missing_value = "")]
+        trace_vm: Option<String>,
+        /// Engine to run on (same as --conf engine=...)
+        #[arg(long, value_enum, conflicts_with = "interp")]
+        engine: Option<EngineArg>,
+        /// Run on the tree-walking interpreter (same as --engine interp)
+        #[arg(long)]
+        interp: bool,
+        /// Run every script in one Runtime, so later scripts can use the functions,
+        /// classes and enums earlier ones declared (default: a fresh Runtime per script)
+        #[arg(long)]
+        shared_runtime: bool,
+        /// With several scripts, stop at the first one that fails
+        #[arg(long)]
+        fail_fast: bool,
+        /// Interrupt a script still running after this long (30s, 500ms, 2m) and exit with status 124
+        #[arg(long, value_name = "DURATION", value_parser = axiom::sandbox::parse_duration)]
+        timeout: Option<std::time::Duration>,
+        /// Arguments for the scripts, after `--`; they read them with cli.args()
+        #[arg(last = true, value_name = "ARGS")]
+        args: Vec<String>,
+    },
+    /// Render a snapshot written by `axiom run --heap-profile` as tables
+    HeapReport {
         path: PathBuf,
+        /// Rows to show per table
+        #[arg(long, default_value_t = 20)]
+        top: usize,
     },
     /// Perform semantic analysis and type checking (does NOT execute)
     Chk {
+        /// The script, or `-` to read it from standard input
         path: PathBuf,
+        /// Name diagnostics show for a script read from standard input
+        #[arg(long, value_name = "NAME")]
+        stdin_filename: Option<PathBuf>,
+        /// Diagnostic output format (also used by --emit)
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+        /// Print the AST or bytecode to stdout and exit without checking
+        #[arg(long, value_enum, value_name = "IR")]
+        emit: Option<Emit>,
     },
+    /// Check every module of a project and bundle them into <target>/<name>.axc (see Axiomite.toml [build])
+    Build {
+        /// Project root holding Axiomite.toml
+        #[arg(default_value = ".")]
+        path: PathBuf,
+        /// Write the modules out as a directory tree (<target>/<name>/) instead of a .axc bundle
+        #[arg(long)]
+        dir: bool,
+        /// Diagnostic output format
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+    },
+    /// Run the conformance specs on the tree-walker and the VM and compare their output
+    Spec {
+        /// A spec file, or a directory searched for them
+        #[arg(default_value = "spec")]
+        path: PathBuf,
+        /// Write each spec's output to its .out file when both engines agree
+        #[arg(long)]
+        bless: bool,
+    },
+    /// Start an interactive session (history in ~/.axiom/history; :help lists commands)
+    Repl,
     /// Format an Axiom script to standard style
     Fmt {
+        /// The script, or `-` to read it from standard input
         path: PathBuf,
         /// Write formatted output back to the file (default: print to stdout)
         #[arg(short, long)]
         write: bool,
+        /// Print nothing; fail if the file is not already formatted
+        #[arg(long, conflicts_with = "write")]
+        check: bool,
+        /// Name of a script read from standard input; .axfmt.toml is looked up from its directory
+        #[arg(long, value_name = "NAME")]
+        stdin_filename: Option<PathBuf>,
     },
     /// Axiomide package manager
     Pkg {
@@ -52,26 +193,125 @@ enum Commands {
         #[command(subcommand)]
         cmd: ConfCommands,
     },
+    /// Keep a long-lived script running: axiom service run script.ax --restart-on-crash
+    Service {
+        #[command(subcommand)]
+        cmd: ServiceCommands,
+    },
+    /// Show crash reports written with --crash-report
+    Report {
+        #[command(subcommand)]
+        cmd: ReportCommands,
+    },
+    /// Set up ~/.axiom and copy this binary into ~/.axiom/bin as axiom and axm
+    Install {
+        /// Also append ~/.axiom/bin to PATH in the existing shell profiles
+        #[arg(long)]
+        modify_path: bool,
+    },
+    /// Install or remove a git pre-commit hook that runs fmt --check and chk on staged .ax files
+    Hook {
+        #[command(subcommand)]
+        cmd: HookCommands,
+    },
+    /// Print a shell completion script: axiom completions bash > ~/.local/share/bash-completion/completions/axiom
+    Completions {
+        #[arg(value_enum)]
+        shell: axiom::completions::Shell,
+    },
+    /// Values for dynamic shell completion (used by the generated scripts)
+    #[command(name = "__complete", hide = true)]
+    Complete { kind: String },
+    /// Serve a browser playground that runs snippets in a sandbox
+    ServePlayground {
+        /// Port to listen on
+        #[arg(short, long, default_value_t = 8080)]
+        port: u16,
+        /// Address to bind (use 0.0.0.0 to expose on the network)
+        #[arg(long, default_value = "127.0.0.1")]
+        host: String,
+    },
 }
 
 #[derive(Subcommand)]
 enum ConfCommands {
-    /// Set a property: axiom conf set property=value
-    Set { spec: String },
+    /// Set a property: axiom conf set [--project | --profile NAME] property=value
+    Set {
+        spec: String,
+        /// Write to .axiom/conf.txt in the current directory
+        #[arg(long)]
+        project: bool,
+    },
     /// Get a property: axiom conf get property
     Get { key: String },
-    /// List all properties with current values
+    /// Open the config in $EDITOR; saved only if every key and value is valid
+    Edit {
+        /// Edit .axiom/conf.txt in the current directory
+        #[arg(long)]
+        project: bool,
+    },
+    /// List the effective values and where each came from
     List,
+    /// List saved profiles (* marks the active one)
+    Profiles,
     /// Reset all properties to their defaults
     Reset,
     /// Show detailed documentation for a property
     Describe { key: String },
 }
 
+#[derive(Subcommand)]
+enum ServiceCommands {
+    /// Run the script until it finishes; SIGTERM runs its aut.on_exit hooks and stops it
+    Run {
+        path: PathBuf,
+        /// Start the script again when it fails, waiting 1s, 2s, 4s ... up to 60s
+        #[arg(long)]
+        restart_on_crash: bool,
+        /// Append output and service events here, rotated at the log_max_mb conf value
+        #[arg(long, value_name = "PATH")]
+        log_file: Option<PathBuf>,
+        /// With --restart-on-crash, give up after N restarts in a row
+        #[arg(long, value_name = "N")]
+        max_restarts: Option<u32>,
+        /// Run in the background and print its pid (needs --log-file)
+        #[arg(long, requires = "log_file")]
+        detach: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum ReportCommands {
+    /// Print a crash report: the newest, or NAME from `report list`
+    Show { name: Option<String> },
+    /// List the crash reports in ~/.axiom/crash, oldest first
+    List,
+}
+
+#[derive(Subcommand)]
+enum HookCommands {
+    /// Write .git/hooks/pre-commit for the repository in the current directory
+    Install {
+        /// Replace a pre-commit hook axiom did not write
+        #[arg(long)]
+        force: bool,
+    },
+    /// Remove the pre-commit hook written by `hook install`
+    Uninstall,
+}
+
 #[derive(Subcommand)]
 enum PkgCommands {
-    /// Install a package: axiom pkg add <user>/<repo>
-    Add { name: String },
+    /// Install a package: axiom pkg add <user>/<repo> [--features a,b]
+    Add {
+        name: String,
+        /// Turn on these [features] of the package (recorded with it)
+        #[arg(long, value_delimiter = ',', value_name = "FEATURES")]
+        features: Vec<String>,
+        /// Leave the package's default features off
+        #[arg(long)]
+        no_default_features: bool,
+    },
     /// Remove a package: axiom pkg remove <user>/<repo>
     Remove { name: String },
     /// Upgrade a package to latest: axiom pkg upgrade <user>/<repo>
@@ -80,10 +320,42 @@ enum PkgCommands {
     List,
     /// Show package info: axiom pkg info <user>/<repo>  OR  axiom pkg info .
     Info { name: String },
+    /// Run a [scripts] entry of the nearest Axiomite.toml; with no name, list them
+    Run {
+        name: Option<String>,
+        /// Appended to the script's command line
+        #[arg(last = true)]
+        args: Vec<String>,
+    },
+    /// Run the package's `test` script: axiom pkg test [-- args]
+    Test {
+        #[arg(last = true)]
+        args: Vec<String>,
+    },
+    /// List installed packages with newer versions upstream: axiom pkg outdated [<user>/<repo>]
+    Outdated {
+        name: Option<String>,
+        /// Print every package checked as JSON
+        #[arg(long)]
+        json: bool,
+        /// Write the JSON to FILE instead (used by the daily background check)
+        #[arg(long, value_name = "FILE", hide = true, requires = "json")]
+        save: Option<PathBuf>,
+    },
+    /// Re-hash installed packages and compare them with Axiomite.lock: axiom pkg verify [<user>/<repo>]
+    Verify {
+        name: Option<String>,
+        /// Record the checksums of packages that have none yet
+        #[arg(long)]
+        record: bool,
+    },
 }
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
+    if cli.crash_report {
+        axiom::crash::enable();
+    }
 
     let result = std::thread::Builder::new()
         .name("axiom-worker".into())
@@ -97,58 +369,169 @@ fn main() -> Result<()> {
     match result {
         Ok(Ok(inner)) => inner,
         Ok(Err(panic_payload)) | Err(panic_payload) => {
-            let msg = if let Some(s) = panic_payload.downcast_ref::<&str>() {
-                format!("internal error (panic): {}", s)
-            } else if let Some(s) = panic_payload.downcast_ref::<String>() {
-                format!("internal error (panic): {}", s)
-            } else {
-                "internal error: unexpected panic in axiom runtime".to_string()
-            };
+            axiom::runtime::flush_output();
+            let msg = format!("internal error (panic): {}", axiom::errors::panic_message(panic_payload.as_ref()));
             eprintln!("axiom crashed: {}", msg);
+            if axiom::crash::enabled() {
+                axiom::crash::write_bundle(&msg);
+            } else {
+                eprintln!("  Run again with --crash-report to save a report for a bug report.");
+            }
             Err(miette::miette!("{}", msg))
         }
     }
 }
 
+/// Turn Ctrl-C into an interrupt the running script stops at (after its
+/// `aut.on_exit` hooks run); a second Ctrl-C before it gets there exits at once.
+fn install_interrupt_handler() {
+    let _ = std::thread::Builder::new().name("axiom-sigint".into()).spawn(|| {
+        let Ok(rt) = tokio::runtime::Builder::new_current_thread().enable_all().build() else { return };
+        rt.block_on(async {
+            while tokio::signal::ctrl_c().await.is_ok() {
+                if axiom::sandbox::interrupted() {
+                    std::process::exit(130);
+                }
+                axiom::sandbox::interrupt();
+            }
+        });
+    });
+}
+
+/// Stop `axiom service run` on SIGTERM or Ctrl-C: the script is interrupted,
+/// runs its `aut.on_exit` hooks and is not restarted. A second signal exits
+/// at once. SIGHUP is ignored so a service outlives the terminal it started in.
+fn install_service_signals() {
+    let _ = std::thread::Builder::new().name("axiom-signals".into()).spawn(|| {
+        let Ok(rt) = tokio::runtime::Builder::new_current_thread().enable_all().build() else { return };
+        rt.block_on(async {
+            #[cfg(unix)]
+            {
+                use tokio::signal::unix::{signal, SignalKind};
+                let (Ok(mut term), Ok(mut hup)) = (signal(SignalKind::terminate()), signal(SignalKind::hangup())) else { return };
+                loop {
+                    tokio::select! {
+                        _ = term.recv() => {}
+                        _ = tokio::signal::ctrl_c() => {}
+                        _ = hup.recv() => continue,
+                    }
+                    if axiom::service::stopping() {
+                        std::process::exit(143);
+                    }
+                    axiom::service::request_stop();
+                }
+            }
+            #[cfg(not(unix))]
+            while tokio::signal::ctrl_c().await.is_ok() {
+                if axiom::service::stopping() {
+                    std::process::exit(130);
+                }
+                axiom::service::request_stop();
+            }
+        });
+    });
+}
+
 fn run(cli: Cli) -> Result<()> {
+    axiom::conf::set_active_profile(cli.profile.clone());
+    let mut overrides = cli.conf_overrides.clone();
+    if let Commands::Run { gc_log: true, .. } = cli.command {
+        overrides.push("gc_verbose=on".to_string());
+    }
+    if cli.strict {
+        overrides.push("strict_vars=on".to_string());
+    }
+    if let Some(seed) = cli.seed {
+        axiom::repro::set_seed(seed);
+        overrides.push("sorted_maps=on".to_string());
+        if cli.frozen_time {
+            axiom::repro::freeze_time();
+        }
+    }
+    axiom::conf::set_cli_overrides(&overrides).map_err(|e| miette::miette!("{}", e))?;
+    let conf = axiom::conf::AxConf::load();
+    // Process-wide: set once here rather than by each Runtime
+    axiom::core::value::set_sorted_maps(conf.sorted_maps());
+    axiom::runtime::set_output_buffering(axiom::runtime::OutputBuffering::from_conf(&conf));
     match cli.command {
         // ----------------------------------------------------------------
         // axiom run <file.ax>
         // ----------------------------------------------------------------
-        Commands::Run { path } => {
-            let source = std::fs::read_to_string(&path)
-                .map_err(|e| miette::miette!("Cannot read '{}': {}", path.display(), e))?;
-
-            let mut parser = Parser::new(&source, 0);
-            let items = parser.parse()
-                .map_err(|e| {
-                    use axiom::diagnostics::DiagnosticEngine;
-                    let engine = DiagnosticEngine::new(path.display().to_string(), &source);
-                    miette::Report::new(engine.from_parser(&e)) // Returns a pretty report
-                })?;
-
-            let mut runtime = Runtime::new();
-            runtime.run(items)
-                .map_err(|e| {
-                    use axiom::diagnostics::DiagnosticEngine;
-                    let engine = DiagnosticEngine::new(path.display().to_string(), &source);
-                    let diag = engine.from_runtime(&e);
-                    engine.emit(&diag);
-                    miette::miette!("{}", e)
-                })?;
+        Commands::Run { paths, format, emit, gc_log, heap_profile, ic_stats, opt_stats, profile_calls, trace_vm, engine, interp, shared_runtime, fail_fast, timeout, args } => {
+            let scripts = expand_scripts(&paths)?;
+            if scripts.len() > 1 && (emit.is_some() || heap_profile.is_some()) {
+                return Err(miette::miette!("--emit and --heap-profile take one script, but {} matched", scripts.len()));
+            }
+            axiom::intrinsics::set_script_args(args);
+            if emit.is_none() && conf.pkg_update_check() {
+                if let Some(notice) = axiom::pkg::take_update_notice() {
+                    eprintln!("{}", notice);
+                }
+                axiom::pkg::spawn_update_check();
+            }
+            let options = RunOptions { format, emit, heap_profile, ic_stats, opt_stats, trace_vm, engine, interp, timeout, timed_out: Default::default(), conf };
+            let calls = profile_calls.then(|| std::sync::Arc::new(axiom::profiler::CallTracker::new()));
+            install_interrupt_handler();
+            let result = if let [script] = scripts.as_slice() {
+                run_file(script, &mut options.runtime(calls.as_ref()), &options)
+            } else {
+                let mut shared = shared_runtime.then(|| options.runtime(calls.as_ref()));
+                let mut outcomes = Vec::new();
+                for script in &scripts {
+                    eprintln!("==> {}", script.display());
+                    let result = match &mut shared {
+                        Some(runtime) => run_file(script, runtime, &options),
+                        None => run_file(script, &mut options.runtime(calls.as_ref()), &options),
+                    };
+                    let failed = result.is_err();
+                    outcomes.push((script, result));
+                    if failed && fail_fast {
+                        break;
+                    }
+                }
+                report_scripts(&outcomes, scripts.len())
+            };
+            if let Some(calls) = &calls {
+                eprint!("{}", calls.render(10));
+            }
+            if options.timed_out.get() {
+                let _ = std::io::stdout().flush();
+                std::process::exit(TIMEOUT_EXIT_CODE);
+            }
+            result?;
 
             std::io::stdout().flush().into_diagnostic()?;
+            if gc_log {
+                axiom::gc::with_heap(|gc| {
+                    let st = &gc.stats;
+                    eprintln!(
+                        "[GC] Summary — {} minor ({} µs), {} major ({} µs), {} KB allocated, heap {} KB",
+                        st.minor_gcs, st.minor_pause_us, st.major_gcs, st.major_pause_us,
+                        st.bytes_allocated_young / 1024, gc.heap_bytes() / 1024,
+                    );
+                });
+            }
+        }
+
+        // ----------------------------------------------------------------
+        // axiom heap-report <snapshot.json>
+        // ----------------------------------------------------------------
+        Commands::HeapReport { path, top } => {
+            let json = std::fs::read_to_string(&path)
+                .map_err(|e| miette::miette!("Cannot read '{}': {}", path.display(), e))?;
+            let snapshot: axiom::profiler::HeapSnapshot = serde_json::from_str(&json)
+                .map_err(|e| miette::miette!("'{}' is not a heap snapshot: {}", path.display(), e))?;
+            print!("{}", snapshot.render(top));
         }
 
         // ----------------------------------------------------------------
-        // axiom chk <file.ax>
+        // axiom chk <file.ax | -> [--stdin-filename NAME]
         // ----------------------------------------------------------------
-        Commands::Chk { path } => {
+        Commands::Chk { path, stdin_filename, format, emit } => {
             use axiom::diagnostics::{DiagnosticEngine, ErrorCode, AxiomDiagnostic};
-            use axiom::errors::RuntimeError;
 
             // 1. Read source - Fixed the no_source call
-            let source = std::fs::read_to_string(&path).map_err(|e| {
+            let (source, path) = read_source(&path, stdin_filename).map_err(|e| {
                 let msg = format!("Cannot read '{}': {}", path.display(), e);
                 // no_source belongs to AxiomDiagnostic, not DiagnosticEngine
                 miette::Report::new(AxiomDiagnostic::no_source(ErrorCode::IoError, msg))
@@ -156,51 +539,166 @@ fn run(cli: Cli) -> Result<()> {
 
             let engine = DiagnosticEngine::new(path.display().to_string(), &source);
 
-            // 2. Parse - Fixed to use miette::Report
+            // 2. Parse, reporting every syntax error rather than just the first
             let mut parser = Parser::new(&source, 0);
-            let items = parser.parse().map_err(|e| {
-                miette::Report::new(engine.from_parser(&e))
-            })?;
+            let (items, parse_errors) = parser.parse_recovering();
+            if !parse_errors.is_empty() {
+                if format == OutputFormat::Json {
+                    let json: Vec<_> = parse_errors.iter()
+                        .map(|e| engine.to_json(&engine.from_parser(e), &DiagnosticLevel::Error))
+                        .collect();
+                    println!("{}", serde_json::Value::Array(json));
+                } else {
+                    for e in &parse_errors {
+                        engine.emit(&engine.from_parser(e));
+                    }
+                }
+                return Err(miette::miette!(
+                    "{} syntax error{} in '{}'",
+                    parse_errors.len(),
+                    if parse_errors.len() == 1 { "" } else { "s" },
+                    path.display()
+                ));
+            }
+
+            if let Some(emit) = emit {
+                return emit_ir(&items, emit, format, &path);
+            }
 
             // 3. Semantic Analysis
             let mut chk = SemanticAnalyzer::new();
             let diagnostics = chk.check(&items);
+            let has_error = diagnostics.iter().any(|d| matches!(d.level, DiagnosticLevel::Error));
 
-            if diagnostics.is_empty() {
+            if format == OutputFormat::Json {
+                let json: Vec<_> = diagnostics.iter()
+                    .map(|d| engine.to_json(&engine.from_check(d), &d.level))
+                    .collect();
+                println!("{}", serde_json::Value::Array(json));
+            } else if diagnostics.is_empty() {
                 println!("✓ No issues found in '{}'", path.display());
             } else {
-                let mut has_error = false;
                 for d in &diagnostics {
-                    // FIX: Convert the generic Diagnostic into a RuntimeError 
-                    // so from_runtime can handle it, or use a custom converter.
-                    let runtime_err = RuntimeError::GenericError { 
-                        message: d.message.clone(), 
-                        span: d.span 
-                    };
-                    
-                    let axiom_diag = engine.from_runtime(&runtime_err);
-                    engine.emit(&axiom_diag);
+                    engine.emit(&engine.from_check(d));
+                }
+            }
+            if has_error {
+                return Err(miette::miette!("Semantic analysis reported errors"));
+            }
+        }
+        
+        // ----------------------------------------------------------------
+        // axiom build [root] [--dir]
+        // ----------------------------------------------------------------
+        Commands::Build { path, dir, format } => {
+            use axiom::diagnostics::DiagnosticEngine;
 
-                    if matches!(d.level, DiagnosticLevel::Error) {
-                        has_error = true;
+            let report = axiom::build::build_project(&path).map_err(|e| miette::miette!("{}", e))?;
+            let mut json = Vec::new();
+            for module in &report.modules {
+                let engine = DiagnosticEngine::new(report.root.join(&module.path).display().to_string(), &module.source);
+                let diags = module.parse_errors.iter().map(|e| (engine.from_parser(e), DiagnosticLevel::Error))
+                    .chain(module.diagnostics.iter().map(|d| (engine.from_check(d), d.level.clone())));
+                for (diag, level) in diags {
+                    if format == OutputFormat::Json {
+                        json.push(engine.to_json(&diag, &level));
+                    } else {
+                        engine.emit(&diag);
                     }
                 }
-                if has_error {
-                    return Err(miette::miette!("Semantic analysis reported errors"));
+            }
+            if format == OutputFormat::Json {
+                println!("{}", serde_json::Value::Array(json));
+            }
+
+            let (errors, warnings) = (report.errors(), report.warnings());
+            if errors > 0 {
+                return Err(miette::miette!(
+                    "Build failed: {} error{} ({} warning{}) in {} module{}",
+                    errors, if errors == 1 { "" } else { "s" },
+                    warnings, if warnings == 1 { "" } else { "s" },
+                    report.modules.len(), if report.modules.len() == 1 { "" } else { "s" },
+                ));
+            }
+            let bundle = report.bundle();
+            let out = if dir {
+                let out = report.root.join(&report.config.build.target).join(&bundle.package);
+                bundle.unpack(&out).map_err(|e| miette::miette!("{}", e))?;
+                out
+            } else {
+                let out = report.bundle_path();
+                bundle.write(&out).map_err(|e| miette::miette!("{}", e))?;
+                out
+            };
+            if format == OutputFormat::Text {
+                println!(
+                    "✓ Built {} {}: {} module{}, {} warning{} → {}",
+                    bundle.package, bundle.version,
+                    bundle.modules.len(), if bundle.modules.len() == 1 { "" } else { "s" },
+                    warnings, if warnings == 1 { "" } else { "s" },
+                    out.display(),
+                );
+                if !report.external.is_empty() {
+                    println!("  not bundled (resolved when run): {}", report.external.join(", "));
                 }
             }
         }
-        
+
         // ----------------------------------------------------------------
-        // axiom fmt <file.ax> [--write]
+        // axiom spec [path] [--bless]
         // ----------------------------------------------------------------
-        Commands::Fmt { path, write } => {
-            let source = std::fs::read_to_string(&path)
+        Commands::Spec { path, bless } => {
+            let outcomes = if path.is_dir() { axiom::spec::run_dir(&path) } else { axiom::spec::run_file(&path).map(|o| vec![o]) }
+                .map_err(|e| miette::miette!("{}", e))?;
+            let mut failed = 0;
+            for outcome in &outcomes {
+                if bless && outcome.interp == outcome.vm && outcome.expected.as_ref() != Some(&outcome.interp) {
+                    std::fs::write(outcome.out_path(), &outcome.interp)
+                        .map_err(|e| miette::miette!("Cannot write '{}': {}", outcome.out_path().display(), e))?;
+                    println!("✎ {} (blessed)", outcome.path.display());
+                    continue;
+                }
+                match outcome.failure() {
+                    None => println!("✓ {}", outcome.path.display()),
+                    Some(why) => {
+                        failed += 1;
+                        println!("✗ {}: {}", outcome.path.display(), why);
+                    }
+                }
+            }
+            println!("\n{} passed, {} failed", outcomes.len() - failed, failed);
+            if failed > 0 {
+                return Err(miette::miette!("{} spec{} failed", failed, if failed == 1 { "" } else { "s" }));
+            }
+        }
+
+        // ----------------------------------------------------------------
+        // axiom repl
+        // ----------------------------------------------------------------
+        Commands::Repl => {
+            install_interrupt_handler();
+            axiom::repl::run().map_err(|e| miette::miette!("REPL failed: {}", e))?;
+        }
+
+        // ----------------------------------------------------------------
+        // axiom fmt <file.ax | -> [--write | --check] [--stdin-filename NAME]
+        // ----------------------------------------------------------------
+        Commands::Fmt { path, write, check, stdin_filename } => {
+            if write && is_stdin(&path) {
+                return Err(miette::miette!("--write needs a file; a script read from standard input is printed"));
+            }
+            let (source, path) = read_source(&path, stdin_filename)
                 .map_err(|e| miette::miette!("Cannot read '{}': {}", path.display(), e))?;
 
-            let formatted = format_source(&source);
+            let dir = path.parent().filter(|d| !d.as_os_str().is_empty()).unwrap_or(std::path::Path::new("."));
+            let (config, _) = FmtConfig::discover(dir).map_err(|e| miette::miette!("{}", e))?;
+            let formatted = format_source_with(&source, &config);
 
-            if write {
+            if check {
+                if formatted != source {
+                    return Err(miette::miette!("'{}' is not formatted; run `axiom fmt --write` on it", path.display()));
+                }
+            } else if write {
                 std::fs::write(&path, &formatted)
                     .map_err(|e| miette::miette!("Cannot write '{}': {}", path.display(), e))?;
                 println!("✓ Formatted '{}'", path.display());
@@ -211,16 +709,22 @@ fn run(cli: Cli) -> Result<()> {
         }
 
         // ----------------------------------------------------------------
-        // axiom pkg <add|remove|list>
+        // axiom pkg <add|remove|list|upgrade|info|run|test>
         // ----------------------------------------------------------------
         Commands::Pkg { cmd } => {
             let pm = PackageManager::new()
                 .map_err(|e| miette::miette!("Package manager init failed: {}", e))?;
 
             match cmd {
-                PkgCommands::Add { name } => {
+                PkgCommands::Add { name, features, no_default_features } => {
                     pm.install_package(&name)
                         .map_err(|e| miette::miette!("Failed to install '{}': {}", name, e))?;
+                    if !features.is_empty() || no_default_features {
+                        let on = pm.select_features(&name, &features, !no_default_features)
+                            .map_err(|e| miette::miette!("Failed to select features of '{}': {}", name, e))?;
+                        let on: Vec<String> = on.into_iter().collect();
+                        println!("✓ Features on: {}", if on.is_empty() { "(none)".to_string() } else { on.join(", ") });
+                    }
                 }
                 PkgCommands::Remove { name } => {
                     pm.remove_package(&name)
@@ -242,6 +746,63 @@ fn run(cli: Cli) -> Result<()> {
                     pm.upgrade_package(&name)
                         .map_err(|e| miette::miette!("Failed to upgrade '{}': {}", name, e))?;
                 }
+                PkgCommands::Run { name: None, .. } => {
+                    let (_, config) = local_package()?;
+                    if config.scripts.is_empty() {
+                        println!("No scripts in Axiomite.toml");
+                    }
+                    for (name, script) in &config.scripts {
+                        println!("  {:<12} {}", name, script);
+                    }
+                }
+                PkgCommands::Run { name: Some(name), args } => run_script(&name, &args)?,
+                PkgCommands::Test { args } => run_script("test", &args)?,
+                PkgCommands::Outdated { name, json, save } => {
+                    let statuses = pm.outdated(name.as_deref())
+                        .map_err(|e| miette::miette!("Failed to check for updates: {}", e))?;
+                    if json {
+                        let text = serde_json::to_string_pretty(&statuses).into_diagnostic()?;
+                        match save {
+                            Some(file) => {
+                                // Renamed into place so a run never reads half of it
+                                let part = file.with_extension("part");
+                                std::fs::write(&part, text).into_diagnostic()?;
+                                std::fs::rename(&part, &file).into_diagnostic()?;
+                            }
+                            None => println!("{}", text),
+                        }
+                        return Ok(());
+                    }
+                    let outdated: Vec<_> = statuses.iter().filter(|s| s.outdated || s.error.is_some()).collect();
+                    if statuses.is_empty() {
+                        println!("No packages installed.");
+                    } else if outdated.is_empty() {
+                        println!("✓ {} package{} up to date", statuses.len(), if statuses.len() == 1 { "" } else { "s" });
+                    }
+                    for status in outdated {
+                        println!("  {:<24} {}", status.package, status.describe());
+                    }
+                }
+                PkgCommands::Verify { name, record } => {
+                    use axiom::pkg::PackageStatus;
+                    let statuses = pm.verify_packages(name.as_deref(), record)
+                        .map_err(|e| miette::miette!("Failed to verify packages: {}", e))?;
+                    if statuses.is_empty() {
+                        println!("No packages installed.");
+                    }
+                    let mut failed = 0;
+                    for (name, status) in &statuses {
+                        match status {
+                            PackageStatus::Intact(files) => println!("✓ {} ({} files)", name, files),
+                            PackageStatus::Tampered(found) => { failed += 1; println!("✗ {}: {}", name, found.summary()); }
+                            PackageStatus::Unrecorded => { failed += 1; println!("? {}: no checksum recorded (--record records it)", name); }
+                            PackageStatus::NotInstalled => { failed += 1; println!("✗ {}: in Axiomite.lock but not installed", name); }
+                        }
+                    }
+                    if failed > 0 {
+                        return Err(miette::miette!("{} package{} failed verification", failed, if failed == 1 { "" } else { "s" }));
+                    }
+                }
                 PkgCommands::Info { name } => {
                     if name == "." {
                         // Auto-detect local Axiomite.toml
@@ -255,12 +816,17 @@ fn run(cli: Cli) -> Result<()> {
             }
         }
         // ----------------------------------------------------------------
-        // axiom conf <set|get|list|reset|describe>
+        // axiom conf <set|get|edit|list|reset|describe>
         // ----------------------------------------------------------------
         Commands::Conf { cmd } => {
             match cmd {
-                ConfCommands::Set { spec } => {
-                    cmd_conf_set(&spec).map_err(|e| miette::miette!("{}", e))?;
+                ConfCommands::Set { spec, project } => {
+                    let target = conf_target(cli.profile, project)?;
+                    cmd_conf_set(&spec, target).map_err(|e| miette::miette!("{}", e))?;
+                }
+                ConfCommands::Edit { project } => {
+                    let target = conf_target(cli.profile, project)?;
+                    cmd_conf_edit(target).map_err(|e| miette::miette!("{}", e))?;
                 }
                 ConfCommands::Get { key } => {
                     cmd_conf_get(&key).map_err(|e| miette::miette!("{}", e))?;
@@ -268,6 +834,9 @@ fn run(cli: Cli) -> Result<()> {
                 ConfCommands::List => {
                     cmd_conf_list();
                 }
+                ConfCommands::Profiles => {
+                    cmd_conf_profiles();
+                }
                 ConfCommands::Reset => {
                     cmd_conf_reset().map_err(|e| miette::miette!("{}", e))?;
                 }
@@ -276,7 +845,374 @@ fn run(cli: Cli) -> Result<()> {
                 }
             }
         }
+
+        // ----------------------------------------------------------------
+        // axiom service run <script.ax>
+        // ----------------------------------------------------------------
+        Commands::Service { cmd: ServiceCommands::Run { path, restart_on_crash, log_file, max_restarts, detach } } => {
+            if detach {
+                // The same command line minus --detach, with no terminal attached
+                let args: Vec<String> = std::env::args().skip(1).filter(|a| a != "--detach").collect();
+                let mut command = std::process::Command::new(std::env::current_exe().into_diagnostic()?);
+                command.args(&args)
+                    .stdin(std::process::Stdio::null())
+                    .stdout(std::process::Stdio::null())
+                    .stderr(std::process::Stdio::null());
+                #[cfg(unix)]
+                std::os::unix::process::CommandExt::process_group(&mut command, 0);
+                let child = command.spawn().map_err(|e| miette::miette!("Cannot start service: {}", e))?;
+                println!("✓ Started {} in the background (pid {})", path.display(), child.id());
+                println!("  Log: {}  Stop: kill {}", log_file.unwrap_or_default().display(), child.id());
+                return Ok(());
+            }
+            install_service_signals();
+            let options = axiom::service::ServiceOptions { restart_on_crash, log_file, max_restarts };
+            axiom::service::run(&path, &options).map_err(|e| miette::miette!("{}", e))?;
+        }
+
+        // ----------------------------------------------------------------
+        // axiom report <show|list>
+        // ----------------------------------------------------------------
+        Commands::Report { cmd } => {
+            let dir = axiom::crash::crash_dir().ok_or_else(|| miette::miette!("Cannot determine home directory"))?;
+            match cmd {
+                ReportCommands::Show { name } => {
+                    let path = axiom::crash::find_report(&dir, name.as_deref()).map_err(|e| miette::miette!("{}", e))?;
+                    let report = axiom::crash::CrashReport::read(&path).map_err(|e| miette::miette!("{}", e))?;
+                    println!("{}", path.display());
+                    print!("{}", report.render());
+                }
+                ReportCommands::List => {
+                    let reports = axiom::crash::list_reports(&dir);
+                    if reports.is_empty() {
+                        println!("No crash reports. Run with --crash-report to write one if axiom crashes.");
+                    }
+                    for path in reports {
+                        let summary = axiom::crash::CrashReport::read(&path)
+                            .map(|r| format!("{}  {}", r.created, r.error))
+                            .unwrap_or_else(|e| e);
+                        println!("  {}  {}", path.file_stem().and_then(|s| s.to_str()).unwrap_or_default(), summary);
+                    }
+                }
+            }
+        }
+
+        // ----------------------------------------------------------------
+        // axiom install [--modify-path]
+        // ----------------------------------------------------------------
+        Commands::Install { modify_path } => {
+            let layout = axiom::install::Layout::for_user().map_err(|e| miette::miette!("{}", e))?;
+            let exe = std::env::current_exe().into_diagnostic()?;
+            let installed = axiom::install::install(&layout, &exe).map_err(|e| miette::miette!("{}", e))?;
+            for binary in &installed.binaries {
+                println!("✓ Installed '{}'", binary.display());
+            }
+            if let Some(conf) = &installed.conf {
+                println!("✓ Wrote default settings to '{}'", conf.display());
+            }
+            let bin_dir = layout.bin_dir();
+            if modify_path {
+                let user = dirs::home_dir().ok_or_else(|| miette::miette!("Cannot determine the home directory"))?;
+                for profile in axiom::install::add_to_path(&bin_dir, &user).map_err(|e| miette::miette!("{}", e))? {
+                    println!("✓ Added '{}' to PATH in '{}'", bin_dir.display(), profile.display());
+                }
+            } else if !axiom::install::on_path(&bin_dir) {
+                println!("'{}' is not on PATH; add this to your shell profile (or rerun with --modify-path):", bin_dir.display());
+                println!("  {}", axiom::install::path_line(&bin_dir, false));
+            }
+        }
+
+        // ----------------------------------------------------------------
+        // axiom hook <install|uninstall>
+        // ----------------------------------------------------------------
+        Commands::Hook { cmd } => {
+            let cwd = std::env::current_dir().into_diagnostic()?;
+            match cmd {
+                HookCommands::Install { force } => {
+                    let path = axiom::hook::install(&cwd, force).map_err(|e| miette::miette!("{}", e))?;
+                    println!("✓ Installed pre-commit hook at '{}'", path.display());
+                }
+                HookCommands::Uninstall => {
+                    let path = axiom::hook::uninstall(&cwd).map_err(|e| miette::miette!("{}", e))?;
+                    println!("✓ Removed pre-commit hook '{}'", path.display());
+                }
+            }
+        }
+
+        // ----------------------------------------------------------------
+        // axiom completions <bash|zsh|fish|powershell>
+        // ----------------------------------------------------------------
+        Commands::Completions { shell } => {
+            print!("{}", axiom::completions::generate(shell, &Cli::command(), "axiom"));
+        }
+        Commands::Complete { kind } => {
+            for value in axiom::completions::dynamic_values(&kind) {
+                println!("{}", value);
+            }
+        }
+
+        // ----------------------------------------------------------------
+        // axiom serve-playground [--port N] [--host ADDR]
+        // ----------------------------------------------------------------
+        Commands::ServePlayground { port, host } => {
+            axiom::playground::serve(&format!("{}:{}", host, port))
+                .map_err(|e| miette::miette!("Playground server failed: {}", e))?;
+        }
     }
 
     Ok(())
 }
+
+/// Exit status of `axiom run` when `--timeout` stopped a script, as with `timeout(1)`.
+const TIMEOUT_EXIT_CODE: i32 = 124;
+
+/// How long a timed-out script has to stop, running its `aut.on_exit`
+/// hooks, before the process exits without it.
+const TIMEOUT_GRACE: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// `axiom run` flags that apply to each script.
+struct RunOptions {
+    format: OutputFormat,
+    emit: Option<Emit>,
+    heap_profile: Option<PathBuf>,
+    ic_stats: bool,
+    opt_stats: bool,
+    trace_vm: Option<String>,
+    engine: Option<EngineArg>,
+    interp: bool,
+    timeout: Option<std::time::Duration>,
+    /// Set once `--timeout` has stopped a script
+    timed_out: std::cell::Cell<bool>,
+    /// Loaded once for every script the command runs
+    conf: axiom::conf::AxConf,
+}
+
+impl RunOptions {
+    /// A Runtime set up for these flags, reporting calls to `calls`.
+    fn runtime(&self, calls: Option<&std::sync::Arc<axiom::profiler::CallTracker>>) -> Runtime {
+        let mut runtime = Runtime::with_conf(&self.conf);
+        if self.heap_profile.is_some() {
+            runtime.enable_heap_profile();
+        }
+        match (self.engine, self.interp) {
+            (Some(EngineArg::Vm), _) => runtime.set_engine(Engine::Vm),
+            (Some(EngineArg::Interp), _) | (None, true) => runtime.set_engine(Engine::Interp),
+            (None, false) => {}
+        }
+        if let Some(function) = &self.trace_vm {
+            let function = (!function.is_empty()).then(|| function.clone());
+            runtime.set_vm_trace(Some(axiom::vm_core::VmTrace { function }));
+        }
+        if let Some(calls) = calls {
+            calls.attach(&mut runtime);
+        }
+        runtime
+    }
+}
+
+/// The scripts `axiom run` was given, with glob patterns expanded in
+/// sorted order. A path that exists is taken as it is, even if it
+/// contains glob characters.
+fn expand_scripts(paths: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let mut scripts = Vec::new();
+    for path in paths {
+        let text = path.to_string_lossy();
+        if path.exists() || !text.contains(['*', '?', '[']) {
+            scripts.push(path.clone());
+            continue;
+        }
+        let matches = glob::glob(&text).map_err(|e| miette::miette!("Bad pattern '{}': {}", text, e))?;
+        let mut found: Vec<PathBuf> = matches.filter_map(|m| m.ok()).filter(|p| p.is_file()).collect();
+        if found.is_empty() {
+            return Err(miette::miette!("No scripts match '{}'", text));
+        }
+        found.sort();
+        scripts.extend(found);
+    }
+    Ok(scripts)
+}
+
+/// Run one script of `axiom run` on `runtime`, printing its diagnostics.
+fn run_file(path: &std::path::Path, runtime: &mut Runtime, options: &RunOptions) -> Result<()> {
+    // A bundle runs from a copy of the project it was built from
+    let path = if path.extension().is_some_and(|ext| ext == "axc") {
+        let bundle = axiom::build::Bundle::read(path).map_err(|e| miette::miette!("{}", e))?;
+        let root = std::env::temp_dir().join(format!("axiom-bundle-{}-{}", bundle.package, bundle.version));
+        let entry = bundle.unpack(&root).map_err(|e| miette::miette!("{}", e))?;
+        runtime.set_module_root(root);
+        entry
+    } else {
+        path.to_path_buf()
+    };
+    let source = std::fs::read_to_string(&path)
+        .map_err(|e| miette::miette!("Cannot read '{}': {}", path.display(), e))?;
+    axiom::crash::set_script(&path);
+    axiom::crash::set_stage("parse");
+
+    let mut parser = Parser::new(&source, 0);
+    let items = parser.parse()
+        .map_err(|e| {
+            use axiom::diagnostics::DiagnosticEngine;
+            let engine = DiagnosticEngine::new(path.display().to_string(), &source);
+            let diag = engine.from_parser(&e);
+            if options.format == OutputFormat::Json {
+                let json = vec![engine.to_json(&diag, &DiagnosticLevel::Error)];
+                println!("{}", serde_json::Value::Array(json));
+                return miette::miette!("Parse error in '{}'", path.display());
+            }
+            miette::Report::new(diag) // Returns a pretty report
+        })?;
+    if let Some(emit) = options.emit {
+        return emit_ir(&items, emit, options.format, &path);
+    }
+
+    let watchdog = options.timeout.map(|timeout| axiom::sandbox::Watchdog::start(timeout, TIMEOUT_GRACE, move || {
+        eprintln!("Timed out after {:?}, and the script did not stop within {:?}", timeout, TIMEOUT_GRACE);
+        std::process::exit(TIMEOUT_EXIT_CODE);
+    }));
+    let mut result = runtime.run(items);
+    if let (Some(timeout), Some(watchdog)) = (options.timeout, watchdog) {
+        // A script woken from aut.sleep by the interrupt may end without an error
+        if watchdog.fired() && matches!(result, Ok(()) | Err(axiom::errors::RuntimeError::Interrupted)) {
+            options.timed_out.set(true);
+            result = Err(axiom::errors::RuntimeError::LimitExceeded {
+                resource: axiom::sandbox::Resource::WallClock,
+                limit: timeout.as_millis() as u64,
+            });
+        }
+    }
+    if let (Some(out), Some(mut snapshot)) = (&options.heap_profile, runtime.take_heap_snapshot()) {
+        snapshot.script = path.display().to_string();
+        let json = serde_json::to_string_pretty(&snapshot).into_diagnostic()?;
+        std::fs::write(out, json)
+            .map_err(|e| miette::miette!("Cannot write '{}': {}", out.display(), e))?;
+        eprintln!("Heap profile written to {} (view with: axiom heap-report {})", out.display(), out.display());
+    }
+    if options.ic_stats {
+        match runtime.ic_report() {
+            Some(report) => eprint!("{}", report.render(Some(&source), 10)),
+            None => eprintln!("Inline caches are off (conf ic_enabled / inline_cache)"),
+        }
+    }
+    if options.opt_stats {
+        match runtime.fusion_report() {
+            Some(stats) => eprint!("{}", stats.render()),
+            None => eprintln!("No bytecode was fused (fusion runs on the VM, with conf superinstructions on)"),
+        }
+    }
+    result.map_err(|e| {
+        use axiom::diagnostics::DiagnosticEngine;
+        let engine = DiagnosticEngine::new(path.display().to_string(), &source);
+        let diag = engine.from_runtime(&e);
+        engine.emit(&diag);
+        if matches!(e, axiom::errors::RuntimeError::Interrupted) {
+            let _ = std::io::stdout().flush();
+            std::process::exit(130);
+        }
+        if matches!(e, axiom::errors::RuntimeError::Internal { .. }) && axiom::crash::enabled() {
+            axiom::crash::write_bundle(&e.to_string());
+        }
+        miette::miette!("{}", e)
+    })
+}
+
+/// The summary after several scripts: one line each, then the counts.
+/// `total` includes scripts --fail-fast skipped.
+fn report_scripts(outcomes: &[(&PathBuf, Result<()>)], total: usize) -> Result<()> {
+    let _ = std::io::stdout().flush();
+    eprintln!();
+    let mut failed = 0;
+    for (script, result) in outcomes {
+        match result {
+            Ok(()) => eprintln!("✓ {}", script.display()),
+            Err(e) => {
+                failed += 1;
+                eprintln!("✗ {}: {}", script.display(), e);
+            }
+        }
+    }
+    let skipped = total - outcomes.len();
+    if skipped > 0 {
+        eprintln!("\n{} passed, {} failed, {} not run", outcomes.len() - failed, failed, skipped);
+    } else {
+        eprintln!("\n{} passed, {} failed", outcomes.len() - failed, failed);
+    }
+    if failed > 0 {
+        return Err(miette::miette!("{} of {} scripts failed", failed, total));
+    }
+    Ok(())
+}
+
+/// The file `conf set` / `conf edit` writes: --profile, --project or the user config.
+fn conf_target(profile: Option<String>, project: bool) -> Result<ConfTarget> {
+    match (profile, project) {
+        (Some(_), true) => Err(miette::miette!("--project and --profile are mutually exclusive")),
+        (Some(name), false) => Ok(ConfTarget::Profile(name)),
+        (None, true) => Ok(ConfTarget::Project),
+        (None, false) => Ok(ConfTarget::User),
+    }
+}
+
+/// The nearest package at or above the current directory.
+fn local_package() -> Result<(PathBuf, axiom::pkg::AxiomiteConfig)> {
+    let cwd = std::env::current_dir().into_diagnostic()?;
+    let root = axiom::pkg::find_root(&cwd)
+        .ok_or_else(|| miette::miette!("No Axiomite.toml in '{}' or any parent directory", cwd.display()))?;
+    let config = axiom::pkg::AxiomiteConfig::from_file(&root.join("Axiomite.toml"))
+        .map_err(|e| miette::miette!("Cannot read '{}': {}", root.join("Axiomite.toml").display(), e))?;
+    Ok((root, config))
+}
+
+/// `axiom pkg run <name>`: run the script and exit with its status.
+fn run_script(name: &str, args: &[String]) -> Result<()> {
+    let (root, config) = local_package()?;
+    let (line, mut command) = axiom::pkg::script_command(&root, &config, name, args).map_err(|e| miette::miette!("{}", e))?;
+    eprintln!("> {}: {}", name, line);
+    let status = command.status().map_err(|e| miette::miette!("Cannot start script '{}': {}", name, e))?;
+    if !status.success() {
+        // A script killed by a signal has no code
+        std::process::exit(status.code().unwrap_or(1));
+    }
+    Ok(())
+}
+
+/// Whether a path argument means standard input.
+fn is_stdin(path: &std::path::Path) -> bool {
+    path.as_os_str() == "-"
+}
+
+/// Read the script a subcommand was given, and the name to report it
+/// under. For `-` that is standard input, named by `--stdin-filename`
+/// or `<stdin>`.
+fn read_source(path: &std::path::Path, stdin_filename: Option<PathBuf>) -> std::io::Result<(String, PathBuf)> {
+    if !is_stdin(path) {
+        return Ok((std::fs::read_to_string(path)?, path.to_path_buf()));
+    }
+    let mut source = String::new();
+    std::io::Read::read_to_string(&mut std::io::stdin(), &mut source)?;
+    Ok((source, stdin_filename.unwrap_or_else(|| PathBuf::from("<stdin>"))))
+}
+
+/// Print the parsed items (`--emit ast`) or their compiled bytecode
+/// (`--emit bytecode`) as text or JSON.
+fn emit_ir(items: &[axiom::ast::Item], emit: Emit, format: OutputFormat, path: &std::path::Path) -> Result<()> {
+    let name = path.display().to_string();
+    let text = match (emit, format) {
+        (Emit::Ast, OutputFormat::Text) => format!("{:#?}\n", items),
+        (Emit::Ast, OutputFormat::Json) => serde_json::to_string_pretty(items).into_diagnostic()? + "\n",
+        (Emit::Bytecode, format) => {
+            let (proto, globals) = axiom::compiler::compile_program(items, &name);
+            if format == OutputFormat::Json {
+                let json = serde_json::json!({ "globals": globals.names, "main": proto.to_json(&name) });
+                serde_json::to_string_pretty(&json).into_diagnostic()? + "\n"
+            } else {
+                format!("{}  globals:      {:?}\n", proto.disassembly(&name), globals.names)
+            }
+        }
+    };
+    // Dumps are often piped into `head` or `less`; a closed pipe is not an error
+    match std::io::stdout().write_all(text.as_bytes()) {
+        Err(e) if e.kind() != std::io::ErrorKind::BrokenPipe => Err(e).into_diagnostic(),
+        _ => Ok(()),
+    }
+}
@@ -311,54 +311,10 @@ impl fmt::Display for NanVal {
 }
 
 // ---------------------------------------------------------------------------
-// String interner — maps &str → u32 index (cached, arena-stored)
+// String interner — SYM payloads index the process-wide table
 // ---------------------------------------------------------------------------
 
-use std::collections::HashMap;
-use parking_lot::RwLock;
-
-pub struct StringInterner {
-    table: RwLock<HashMap<String, u32>>,
-    strings: RwLock<Vec<String>>,
-}
-
-impl StringInterner {
-    pub fn new() -> Self {
-        StringInterner {
-            table: RwLock::new(HashMap::new()),
-            strings: RwLock::new(Vec::new()),
-        }
-    }
-
-    pub fn intern(&self, s: &str) -> u32 {
-        {
-            let table = self.table.read();
-            if let Some(&idx) = table.get(s) {
-                return idx;
-            }
-        }
-        let mut table = self.table.write();
-        if let Some(&idx) = table.get(s) {
-            return idx;
-        }
-        let idx = {
-            let mut strings = self.strings.write();
-            let idx = strings.len() as u32;
-            strings.push(s.to_string());
-            idx
-        };
-        table.insert(s.to_string(), idx);
-        idx
-    }
-
-    pub fn get(&self, idx: u32) -> Option<String> {
-        self.strings.read().get(idx as usize).cloned()
-    }
-
-    pub fn len(&self) -> usize {
-        self.strings.read().len()
-    }
-}
+pub use crate::intern::StringInterner;
 
 #[cfg(test)]
 mod tests {
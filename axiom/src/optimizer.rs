@@ -8,7 +8,9 @@
 ///   4. Jump threading     — eliminate redundant jump chains
 ///   5. Dead code removal  — strip unreachable instructions after jumps
 ///   6. Nop compaction     — remove Nops introduced by other passes
-///   7. Superinstruction fusion — already done in bytecode.rs
+///   7. Superinstruction fusion — LoadInt+Add, Lt+JumpFalse and Move+Return
+///      pairs become one instruction, where register liveness and jump
+///      targets show the pair's middle is never observed
 ///
 /// All passes are O(N) or O(N²) in bytecode length — fast.
 
@@ -54,7 +56,7 @@ pub fn optimize(proto: &mut Proto, cfg: &OptConfig) {
     if cfg.jump_threading    { thread_jumps(proto); }
     if cfg.dead_code         { remove_dead_code(proto); }
     if cfg.nop_removal       { compact_nops(proto); }
-    if cfg.superinstructions { crate::bytecode::apply_superinstructions(proto); }
+    if cfg.superinstructions { fuse(proto, &mut FusionStats::default()); }
 
     // Recurse into nested protos
     for i in 0..proto.protos.len() {
@@ -253,13 +255,9 @@ fn thread_jumps(proto: &mut Proto) {
     let len = proto.code.len();
 
     for i in 0..len {
-        if !matches!(proto.code[i].op(), Op::Jump | Op::JumpTrue | Op::JumpFalse |
-                     Op::JumpNil | Op::JumpNotNil) {
-            continue;
-        }
-
-        let sbx = proto.code[i].get_sbx() as i32;
-        let mut target = i as i32 + 1 + sbx;
+        if proto.code[i].op() == Op::LoopBack { continue; }
+        let Some(offset) = proto.code[i].jump_offset() else { continue };
+        let mut target = i as i32 + 1 + offset;
 
         // Follow the chain
         let mut hops = 0;
@@ -274,11 +272,8 @@ fn thread_jumps(proto: &mut Proto) {
             }
         }
 
-        // Patch the jump
-        let new_sbx = target - i as i32 - 1;
-        if new_sbx >= i16::MIN as i32 && new_sbx <= i16::MAX as i32 {
-            proto.code[i].patch_sbx(new_sbx as i16);
-        }
+        // Patch the jump (left alone if the new offset does not fit)
+        proto.code[i].set_jump_offset(target - i as i32 - 1);
     }
 }
 
@@ -312,7 +307,7 @@ fn remove_dead_code(proto: &mut Proto) {
                 // Fallthrough
                 if i + 1 < len { worklist.push(i + 1); }
                 // Branch target
-                let target = i as i32 + 1 + instr.get_sbx() as i32;
+                let target = i as i32 + 1 + instr.jump_offset().unwrap_or(0);
                 if target >= 0 { worklist.push(target as usize); }
             }
             Op::LoopBack => {
@@ -320,6 +315,15 @@ fn remove_dead_code(proto: &mut Proto) {
                 let target = i as i32 + 1 + instr.get_sbx() as i32;
                 if target >= 0 { worklist.push(target as usize); }
             }
+            Op::MatchEnum => {
+                // Every arm in the jump table, plus its default
+                if let Some(table) = proto.jump_tables.get(instr.bx() as usize) {
+                    for offset in table.targets.values().chain([&table.default]) {
+                        let target = i as i32 + 1 + offset;
+                        if target >= 0 { worklist.push(target as usize); }
+                    }
+                }
+            }
             _ => {
                 if i + 1 < len { worklist.push(i + 1); }
             }
@@ -365,16 +369,23 @@ fn compact_nops(proto: &mut Proto) {
         if instr.op() == Op::Nop { continue; }
 
         // Patch jump offsets
-        match instr.op() {
-            Op::Jump | Op::JumpTrue | Op::JumpFalse | Op::JumpNil |
-            Op::JumpNotNil | Op::LoopBack | Op::CmpLtJmp => {
-                let old_target = i as i32 + 1 + instr.get_sbx() as i32;
+        match (instr.op(), instr.jump_offset()) {
+            (_, Some(offset)) => {
+                let old_target = i as i32 + 1 + offset;
                 let clamped = old_target.max(0).min(len as i32);
                 let new_target = old_to_new[clamped as usize];
                 let new_src    = old_to_new[i];
-                let new_sbx   = new_target - new_src - 1;
-                if new_sbx >= i16::MIN as i32 && new_sbx <= i16::MAX as i32 {
-                    instr.patch_sbx(new_sbx as i16);
+                // Offsets only shrink here, so they still fit
+                instr.set_jump_offset(new_target - new_src - 1);
+            }
+            (Op::MatchEnum, _) => {
+                let remap = |offset: &mut i32| {
+                    let old_target = (i as i32 + 1 + *offset).max(0).min(len as i32);
+                    *offset = old_to_new[old_target as usize] - old_to_new[i] - 1;
+                };
+                if let Some(table) = proto.jump_tables.get_mut(instr.bx() as usize) {
+                    table.targets.values_mut().for_each(remap);
+                    remap(&mut table.default);
                 }
             }
             _ => {}
@@ -392,6 +403,242 @@ fn compact_nops(proto: &mut Proto) {
     proto.counters = new_counters;
 }
 
+// ---------------------------------------------------------------------------
+// Pass 6: Superinstruction fusion
+// ---------------------------------------------------------------------------
+
+/// How many times each superinstruction fusion fired.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FusionStats {
+    /// LoadInt + Add → AddIntImm
+    pub add_int_imm: usize,
+    /// Lt + JumpFalse → CmpLtJmp
+    pub cmp_lt_jmp: usize,
+    /// Move + Return → Return
+    pub move_return: usize,
+}
+
+impl FusionStats {
+    pub fn total(&self) -> usize {
+        self.add_int_imm + self.cmp_lt_jmp + self.move_return
+    }
+
+    /// The counts as a table, for `axiom run --opt-stats`.
+    pub fn render(&self) -> String {
+        let mut out = String::from("=== Superinstruction fusion ===\n");
+        for (pattern, count) in [
+            ("LoadInt + Add    → AddIntImm", self.add_int_imm),
+            ("Lt + JumpFalse   → CmpLtJmp", self.cmp_lt_jmp),
+            ("Move + Return    → Return", self.move_return),
+        ] {
+            out.push_str(&format!("  {:<32} {:>6}\n", pattern, count));
+        }
+        out.push_str(&format!("  {:<32} {:>6}\n", "total", self.total()));
+        out
+    }
+}
+
+/// Fuse superinstructions in `proto` and every function nested in it.
+pub fn fuse_superinstructions(proto: &mut Proto) -> FusionStats {
+    let mut stats = FusionStats::default();
+    fuse_nested(proto, &mut stats);
+    stats
+}
+
+fn fuse_nested(proto: &mut Proto, stats: &mut FusionStats) {
+    fuse(proto, stats);
+    for inner in &mut proto.protos {
+        fuse_nested(inner, stats);
+    }
+}
+
+/// Rewrite each fusable pair into one instruction and a Nop, then compact.
+/// A pair is only fused when nothing can jump between its two halves, and
+/// when the register the first half hands to the second is dead afterwards,
+/// since the fused form no longer writes it.
+fn fuse(proto: &mut Proto, stats: &mut FusionStats) {
+    let len = proto.code.len();
+    if len < 2 { return; }
+    let infos: Vec<InstrInfo> = (0..len).map(|i| instr_info(proto, i)).collect();
+    let live_out = liveness(&infos);
+    let mut is_target = vec![false; len];
+    for &t in infos.iter().enumerate().flat_map(|(i, info)| info.succs.iter().filter(move |&&t| t != i + 1)) {
+        is_target[t] = true;
+    }
+
+    let before = stats.total();
+    let mut i = 0;
+    while i + 1 < len {
+        let (first, second) = (proto.code[i], proto.code[i + 1]);
+        let fused = if is_target[i + 1] {
+            None
+        } else {
+            match (first.op(), second.op()) {
+                // LoadInt t, imm; Add d, x, t → AddIntImm d, x, imm
+                (Op::LoadInt, Op::Add) => {
+                    let t = first.a();
+                    let dead = second.a() == t || !live_out[i + 1].contains(t as usize);
+                    match i8::try_from(first.get_sbx()) {
+                        Ok(imm) if second.c() == t && second.b() != t && dead => {
+                            stats.add_int_imm += 1;
+                            Some(Instr::absc(Op::AddIntImm, second.a(), second.b(), imm))
+                        }
+                        _ => None,
+                    }
+                }
+                // Lt r, x, y; JumpFalse r, off → CmpLtJmp x, y, off + 1
+                (Op::Lt, Op::JumpFalse) => {
+                    let r = first.a();
+                    match i8::try_from(second.get_sbx() as i32 + 1) {
+                        Ok(offset) if second.a() == r && !live_out[i + 1].contains(r as usize) => {
+                            stats.cmp_lt_jmp += 1;
+                            Some(Instr::absc(Op::CmpLtJmp, first.b(), first.c(), offset))
+                        }
+                        _ => None,
+                    }
+                }
+                // Move t, x; Return t → Return x
+                (Op::Move, Op::Return) if first.a() == second.a() => {
+                    stats.move_return += 1;
+                    Some(Instr::abc(Op::Return, first.b(), 0, 0))
+                }
+                _ => None,
+            }
+        };
+        match fused {
+            Some(instr) => {
+                proto.code[i] = instr;
+                proto.code[i + 1] = Instr::abc(Op::Nop, 0, 0, 0);
+                // Errors in the fused instruction belong to the second half's line
+                if let Some(&line) = proto.line_info.get(i + 1) { proto.line_info[i] = line; }
+                i += 2;
+            }
+            None => i += 1,
+        }
+    }
+    if stats.total() > before { compact_nops(proto); }
+}
+
+/// A set of registers, one bit each.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct RegSet([u64; 4]);
+
+impl RegSet {
+    const ALL: RegSet = RegSet([u64::MAX; 4]);
+
+    fn of(regs: &[usize]) -> RegSet {
+        let mut set = RegSet::default();
+        for &r in regs { set.insert(r); }
+        set
+    }
+
+    /// Registers `from .. from + count`.
+    fn span(from: usize, count: usize) -> RegSet {
+        let mut set = RegSet::default();
+        for r in from..(from + count).min(256) { set.insert(r); }
+        set
+    }
+
+    fn insert(&mut self, r: usize) {
+        if r < 256 { self.0[r / 64] |= 1 << (r % 64); }
+    }
+
+    fn contains(&self, r: usize) -> bool {
+        r < 256 && self.0[r / 64] & (1 << (r % 64)) != 0
+    }
+
+    fn union(self, other: RegSet) -> RegSet {
+        RegSet(std::array::from_fn(|w| self.0[w] | other.0[w]))
+    }
+
+    fn minus(self, other: RegSet) -> RegSet {
+        RegSet(std::array::from_fn(|w| self.0[w] & !other.0[w]))
+    }
+}
+
+/// What one instruction does to registers and where control goes next.
+struct InstrInfo {
+    reads: RegSet,
+    /// Registers the instruction always overwrites
+    writes: RegSet,
+    /// Indices of the instructions that can run next
+    succs: Vec<usize>,
+}
+
+/// Register and control-flow metadata for `proto.code[i]`. An op not listed
+/// is taken to read every register and write none, which can only ever keep
+/// a fusion from firing.
+fn instr_info(proto: &Proto, i: usize) -> InstrInfo {
+    let instr = proto.code[i];
+    let (a, b, c) = (instr.a() as usize, instr.b() as usize, instr.c() as usize);
+    let (reads, writes) = match instr.op() {
+        Op::LoadNil | Op::LoadTrue | Op::LoadFalse | Op::LoadInt | Op::LoadFloat | Op::LoadStr
+        | Op::LoadConst | Op::LoadGlobal | Op::LoadUpval | Op::NewMap | Op::LoadCallee => (RegSet::default(), RegSet::of(&[a])),
+        Op::StoreGlobal | Op::StoreUpval | Op::Return | Op::ListReserve | Op::MatchEnum
+        | Op::JumpTrue | Op::JumpFalse | Op::JumpNil | Op::JumpNotNil => (RegSet::of(&[a]), RegSet::default()),
+        Op::Move | Op::Neg | Op::Not | Op::ListLen | Op::EnumPayload | Op::IsListLen | Op::IsListMin
+        | Op::ListTail | Op::AddIntImm => (RegSet::of(&[b]), RegSet::of(&[a])),
+        Op::Add | Op::Sub | Op::Mul | Op::Div | Op::Mod | Op::Pow | Op::AddInt | Op::SubInt | Op::MulInt
+        | Op::AddFloat | Op::SubFloat | Op::MulFloat | Op::DivFloat | Op::Concat | Op::Eq | Op::Ne
+        | Op::Lt | Op::Le | Op::Gt | Op::Ge | Op::LtInt | Op::LeInt | Op::EqInt | Op::And | Op::Or
+        | Op::GetIndex => (RegSet::of(&[b, c]), RegSet::of(&[a])),
+        Op::IncrLocal | Op::DecrLocal | Op::GetProp | Op::GetMethod | Op::NewEnum => (RegSet::of(&[a]), RegSet::of(&[a])),
+        Op::CmpLtJmp => (RegSet::of(&[a, b]), RegSet::default()),
+        Op::SetProp | Op::SetIndex => (RegSet::of(&[a, b, c]), RegSet::default()),
+        Op::Call => (RegSet::span(b, c + 1), RegSet::of(&[a])),
+        Op::CallTail => (RegSet::span(b, c + 1), RegSet::default()),
+        Op::NewList => (RegSet::span(b, c), RegSet::of(&[a])),
+        Op::ListExtend => (RegSet::span(b, c).union(RegSet::of(&[a])), RegSet::default()),
+        Op::Closure => {
+            let captured: Vec<usize> = proto.protos.get(instr.bx() as usize)
+                .map(|p| p.upvals.iter().filter(|u| u.in_stack).map(|u| u.idx as usize).collect())
+                .unwrap_or_default();
+            (RegSet::of(&captured), RegSet::of(&[a]))
+        }
+        Op::Jump | Op::LoopBack | Op::ReturnNil | Op::NilReturn | Op::Halt | Op::Nop
+        | Op::Profile | Op::CloseUpval | Op::Unquicken => (RegSet::default(), RegSet::default()),
+        _ => (RegSet::ALL, RegSet::default()),
+    };
+
+    let len = proto.code.len();
+    let at = |offset: i32| usize::try_from(i as i32 + 1 + offset).ok().filter(|&t| t < len);
+    let mut succs = Vec::new();
+    match instr.op() {
+        Op::Return | Op::ReturnNil | Op::NilReturn | Op::Halt | Op::CallTail => {}
+        Op::MatchEnum => {
+            if let Some(table) = proto.jump_tables.get(instr.bx() as usize) {
+                succs.extend(table.targets.values().chain([&table.default]).filter_map(|&offset| at(offset)));
+            }
+        }
+        op => {
+            if let Some(offset) = instr.jump_offset() { succs.extend(at(offset)); }
+            if !matches!(op, Op::Jump | Op::LoopBack) { succs.extend(at(0)); }
+        }
+    }
+    InstrInfo { reads, writes, succs }
+}
+
+/// The registers live after each instruction: read on some path before
+/// being overwritten.
+fn liveness(infos: &[InstrInfo]) -> Vec<RegSet> {
+    let mut live_in = vec![RegSet::default(); infos.len()];
+    let mut live_out = vec![RegSet::default(); infos.len()];
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for (i, info) in infos.iter().enumerate().rev() {
+            let out = info.succs.iter().fold(RegSet::default(), |acc, &s| acc.union(live_in[s]));
+            let inn = info.reads.union(out.minus(info.writes));
+            if out != live_out[i] || inn != live_in[i] {
+                live_out[i] = out;
+                live_in[i] = inn;
+                changed = true;
+            }
+        }
+    }
+    live_out
+}
+
 // ---------------------------------------------------------------------------
 // Optimization stats
 // ---------------------------------------------------------------------------
@@ -428,3 +675,60 @@ impl OptStats {
         println!("  Nops removed: {}", self.nops_removed);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn proto(code: &[Instr]) -> Proto {
+        let mut p = Proto::new("<test>");
+        for &instr in code { p.emit(instr, 1); }
+        p
+    }
+
+    #[test]
+    fn test_fusion_rewrites_loop_and_patches_jumps() {
+        let mut p = proto(&[
+            Instr::asbx(Op::LoadInt, 1, 1),
+            Instr::abc(Op::Add, 0, 0, 1),
+            Instr::abc(Op::Lt, 2, 0, 3),
+            Instr::asbx(Op::JumpFalse, 2, 1),
+            Instr::asbx(Op::Jump, 0, -5),
+            Instr::abc(Op::Move, 4, 0, 0),
+            Instr::abc(Op::Return, 4, 0, 0),
+        ]);
+        let stats = fuse_superinstructions(&mut p);
+        assert_eq!(stats, FusionStats { add_int_imm: 1, cmp_lt_jmp: 1, move_return: 1 });
+        assert_eq!(p.code, vec![
+            Instr::absc(Op::AddIntImm, 0, 0, 1),
+            Instr::absc(Op::CmpLtJmp, 0, 3, 1),
+            Instr::asbx(Op::Jump, 0, -3),
+            Instr::abc(Op::Return, 0, 0, 0),
+        ]);
+        assert_eq!(p.line_info.len(), 4);
+    }
+
+    #[test]
+    fn test_fusion_skips_live_registers_and_jump_targets() {
+        // The loaded 1 is returned afterwards
+        let mut live = proto(&[
+            Instr::asbx(Op::LoadInt, 1, 1),
+            Instr::abc(Op::Add, 0, 0, 1),
+            Instr::abc(Op::Return, 1, 0, 0),
+        ]);
+        // Something jumps straight to the Add; a 300 does not fit in sC
+        let mut targeted = proto(&[
+            Instr::asbx(Op::Jump, 0, 1),
+            Instr::asbx(Op::LoadInt, 1, 1),
+            Instr::abc(Op::Add, 0, 0, 1),
+            Instr::asbx(Op::LoadInt, 1, 300),
+            Instr::abc(Op::Add, 0, 0, 1),
+            Instr::abc(Op::Return, 0, 0, 0),
+        ]);
+        let (before_live, before_targeted) = (live.code.clone(), targeted.code.clone());
+        assert_eq!(fuse_superinstructions(&mut live).total(), 0);
+        assert_eq!(fuse_superinstructions(&mut targeted).total(), 0);
+        assert_eq!(live.code, before_live);
+        assert_eq!(targeted.code, before_targeted);
+    }
+}
@@ -5,11 +5,14 @@
 //   • Space-separated arguments: `out "text" var "text"` → 3 args
 //   • Match patterns: Status.Active, Variant(binding), wildcards
 //   • Declaration hoisting: ALL declarations before ALL statements
+//   • Optional type annotations: `fun f(x: Num) -> Num`, `let n: Num = 5`
 //   • Proper error reporting with spans
+//   • Panic-mode recovery: syncs at `;`, `}` or a statement keyword and keeps going
 //   • Comprehensive test coverage
 //
 use crate::ast::{
-    ClassMember, EnumVariant, Expr, Item, MatchArm, MatchPattern, Stmt, StringPart,
+    AccessorKind, ClassMember, EnumVariant, Expr, InterfaceMethod, Item, MatchArm, MatchPattern, Stmt, StringPart,
+    TypeAnnotation,
 };
 use crate::errors::{ParserError, Span};
 use crate::lexer::{Lexer, Token};
@@ -17,7 +20,13 @@ use std::collections::VecDeque;
 
 pub struct Parser {
     tokens: VecDeque<(Token, Span)>,
+    /// Span of the last token consumed
+    prev: Span,
     source_id: u32,
+    /// Errors recovered from so far (in source order)
+    errors: Vec<ParserError>,
+    /// Byte offsets of the source's line breaks
+    breaks: Vec<usize>,
 }
 
 impl Parser {
@@ -26,31 +35,72 @@ impl Parser {
         let tokens = lexer.tokenize();
         Parser {
             tokens: VecDeque::from(tokens),
+            prev: Span::new(source_id, 0, 0),
             source_id,
+            errors: Vec::new(),
+            breaks: source.match_indices('\n').map(|(i, _)| i).collect(),
         }
     }
 
+    /// Parse the whole file, failing with the first syntax error.
     pub fn parse(&mut self) -> Result<Vec<Item>, ParserError> {
+        let (items, mut errors) = self.parse_recovering();
+        if errors.is_empty() { Ok(items) } else { Err(errors.remove(0)) }
+    }
+
+    /// Parse the whole file, recovering after each syntax error so that every
+    /// error can be reported at once. Items are partial when errors is non-empty.
+    pub fn parse_recovering(&mut self) -> (Vec<Item>, Vec<ParserError>) {
         let mut decls: Vec<Item> = Vec::new();
         let mut stmts: Vec<Item> = Vec::new();
 
         while !self.is_at_end() {
             self.skip_semicolons();
             if self.is_at_end() { break; }
-            let item = self.parse_item()?;
+            let remaining = self.tokens.len();
+            let item = match self.parse_item() {
+                Ok(item) => item,
+                Err(e) => {
+                    self.errors.push(e);
+                    self.synchronize(remaining);
+                    // A stray `}` cannot start anything at top level
+                    if matches!(self.peek_token(), Token::RBrace) { self.advance(); }
+                    continue;
+                }
+            };
             match &item {
                 Item::FunctionDecl { .. }
                 | Item::ClassDecl { .. }
                 | Item::EnumDecl { .. }
+                | Item::InterfaceDecl { .. }
                 | Item::LocImport { .. }
+                | Item::StdImport { .. }
                 | Item::LibDecl { .. }
+                | Item::Export { .. }
                 | Item::LoadStmt { .. } => decls.push(item),
                 Item::Statement(_) => stmts.push(item),
             }
         }
 
         decls.extend(stmts);
-        Ok(decls)
+        (decls, std::mem::take(&mut self.errors))
+    }
+
+    /// Skip tokens until a likely statement boundary: just past `;`, or at `}`
+    /// or a statement keyword. Always consumes at least one token if the failed
+    /// parse (which started with `remaining` tokens left) did not.
+    fn synchronize(&mut self, remaining: usize) {
+        if self.tokens.len() == remaining { self.advance(); }
+        loop {
+            match self.peek_token() {
+                Token::Eof | Token::RBrace => return,
+                Token::Semicolon => { self.advance(); return; }
+                Token::Let | Token::Fun | Token::Fn | Token::Cls | Token::Enm | Token::Ifc | Token::If
+                | Token::While | Token::For | Token::Return | Token::Go | Token::Match
+                | Token::Out | Token::Print | Token::Load | Token::Loc | Token::Lib => return,
+                _ => { self.advance(); }
+            }
+        }
     }
 
     fn parse_item(&mut self) -> Result<Item, ParserError> {
@@ -59,9 +109,13 @@ impl Parser {
             Token::Fun | Token::Fn => self.parse_function(),
             Token::Cls => self.parse_class_decl(),
             Token::Enm => self.parse_enum_decl(),
+            Token::Ifc => self.parse_interface_decl(),
             Token::Loc => self.parse_loc_import(),
             Token::Lib => self.parse_lib_decl(),
             Token::Load => self.parse_load_stmt(),
+            // `exp` is only a keyword when a name follows it
+            Token::Ident(kw) if kw == "exp" && matches!(self.peek_nth(1), Token::Ident(_)) => self.parse_export(),
+            Token::Ident(kw) if kw == "std" && matches!(self.peek_nth(1), Token::Ident(_)) => self.parse_std_import(),
             Token::Ident(_) => match self.peek_nth(1) {
                 Token::LParen => {
                     if self.is_func_decl_ahead() {
@@ -96,7 +150,7 @@ impl Parser {
                     depth -= 1;
                     if depth == 0 {
                         return i + 1 < self.tokens.len()
-                            && matches!(&self.tokens[i + 1].0, Token::LBrace);
+                            && matches!(&self.tokens[i + 1].0, Token::LBrace | Token::ThinArrow);
                     }
                 }
                 _ => {}
@@ -132,10 +186,11 @@ impl Parser {
         if matches!(self.peek_token(), Token::Fun | Token::Fn) { self.advance(); }
         let name   = self.consume_ident()?;
         self.consume(Token::LParen)?;
-        let params = self.parse_param_list()?;
+        let (params, param_types) = self.parse_typed_param_list()?;
         self.consume(Token::RParen)?;
+        let ret_type = self.parse_return_type()?;
         let body   = self.parse_block()?;
-        Ok(Item::FunctionDecl { name, params, body, span: start.merge(self.prev_span()) })
+        Ok(Item::FunctionDecl { name, params, param_types, ret_type, body, span: start.merge(self.prev_span()) })
     }
 
     fn parse_class_decl(&mut self) -> Result<Item, ParserError> {
@@ -152,6 +207,15 @@ impl Parser {
         } else {
             None
         };
+        let mut interfaces = Vec::new();
+        if matches!(self.peek_token(), Token::Impl) {
+            self.advance();
+            interfaces.push(self.consume_ident()?);
+            while matches!(self.peek_token(), Token::Comma) {
+                self.advance();
+                interfaces.push(self.consume_ident()?);
+            }
+        }
         self.consume(Token::LBrace)?;
         let mut body = Vec::new();
 
@@ -159,17 +223,49 @@ impl Parser {
             self.skip_semicolons();
             if matches!(self.peek_token(), Token::RBrace) { break; }
 
-            if matches!(self.peek_token(), Token::Fun | Token::Ident(_)) {
-                let mstart = self.current_span();
+            // `static` is only a keyword in front of a member
+            let mstart = self.current_span();
+            let is_static = matches!(self.peek_token(), Token::Ident(ref s) if s == "static")
+                && matches!(self.peek_nth(1), Token::Fun | Token::Let | Token::Ident(_));
+            if is_static { self.advance(); }
+
+            // `get` / `set` are likewise contextual: `get name(...)`
+            let accessor = match (self.peek_token(), self.peek_nth(1), self.peek_nth(2)) {
+                (Token::Ident(kw), Token::Ident(_), Token::LParen) if !is_static && kw == "get" => Some(AccessorKind::Get),
+                (Token::Ident(kw), Token::Ident(_), Token::LParen) if !is_static && kw == "set" => Some(AccessorKind::Set),
+                _ => None,
+            };
+
+            if let Some(kind) = accessor {
+                self.advance();
+                let prop = self.consume_ident()?;
+                self.consume(Token::LParen)?;
+                let (params, _) = self.parse_typed_param_list()?;
+                self.consume(Token::RParen)?;
+                let expected = if kind == AccessorKind::Get { 0 } else { 1 };
+                if params.len() != expected {
+                    return Err(ParserError::InvalidSyntax {
+                        context: format!(
+                            "{} '{}' must take {} parameter(s)",
+                            if kind == AccessorKind::Get { "getter" } else { "setter" }, prop, expected
+                        ),
+                        span: mstart.merge(self.prev_span()),
+                    });
+                }
+                self.parse_return_type()?;
+                let abody = self.parse_block()?;
+                body.push(ClassMember::Accessor { name: prop, kind, params, body: abody, span: mstart.merge(self.prev_span()) });
+            } else if matches!(self.peek_token(), Token::Fun | Token::Ident(_)) {
                 if matches!(self.peek_token(), Token::Fun) { self.advance(); }
                 if matches!(self.peek_nth(1), Token::LParen) {
                     let method_name = self.consume_ident()?;
                     self.consume(Token::LParen)?;
-                    let params = self.parse_param_list()?;
+                    let (params, param_types) = self.parse_typed_param_list()?;
                     self.consume(Token::RParen)?;
+                    let ret_type = self.parse_return_type()?;
                     let mbody = self.parse_block()?;
                     body.push(ClassMember::Method {
-                        name: method_name, params, body: mbody,
+                        name: method_name, params, param_types, ret_type, body: mbody, is_static,
                         span: mstart.merge(self.prev_span()),
                     });
                 } else {
@@ -179,26 +275,52 @@ impl Parser {
                     });
                 }
             } else if matches!(self.peek_token(), Token::Let) {
-                let fstart = self.current_span();
                 self.advance();
                 let field_name = self.consume_ident()?;
+                let ty = self.parse_optional_annotation()?;
                 let default = if matches!(self.peek_token(), Token::Assign) {
                     self.advance();
                     Some(self.parse_expr()?)
                 } else { None };
                 self.skip_semicolons();
                 body.push(ClassMember::Field {
-                    name: field_name, default, span: fstart.merge(self.prev_span()),
+                    name: field_name, ty, default, is_static, span: mstart.merge(self.prev_span()),
                 });
             } else {
                 return Err(ParserError::InvalidSyntax {
-                    context: "class body (expected 'fun', 'let', or method name)".to_string(),
+                    context: "class body (expected 'fun', 'let', 'static', or method name)".to_string(),
                     span: self.current_span(),
                 });
             }
         }
         self.consume(Token::RBrace)?;
-        Ok(Item::ClassDecl { name, parent, body, span: start.merge(self.prev_span()) })
+        Ok(Item::ClassDecl { name, parent, interfaces, body, span: start.merge(self.prev_span()) })
+    }
+
+    /// `ifc Name { area(), scale(factor) }`: required method signatures,
+    /// separated by commas or newlines, each optionally after `fun`.
+    fn parse_interface_decl(&mut self) -> Result<Item, ParserError> {
+        let start = self.current_span();
+        self.advance();
+        let name = self.consume_ident()?;
+        self.consume(Token::LBrace)?;
+        let mut methods = Vec::new();
+
+        while !matches!(self.peek_token(), Token::RBrace | Token::Eof) {
+            self.skip_semicolons();
+            if matches!(self.peek_token(), Token::RBrace) { break; }
+            let mstart = self.current_span();
+            if matches!(self.peek_token(), Token::Fun) { self.advance(); }
+            let mname = self.consume_ident()?;
+            self.consume(Token::LParen)?;
+            let (params, _) = self.parse_typed_param_list()?;
+            self.consume(Token::RParen)?;
+            self.parse_return_type()?;
+            methods.push(InterfaceMethod { name: mname, params, span: mstart.merge(self.prev_span()) });
+            if matches!(self.peek_token(), Token::Comma) { self.advance(); }
+        }
+        self.consume(Token::RBrace)?;
+        Ok(Item::InterfaceDecl { name, methods, span: start.merge(self.prev_span()) })
     }
 
     fn parse_enum_decl(&mut self) -> Result<Item, ParserError> {
@@ -233,9 +355,72 @@ impl Parser {
     fn parse_loc_import(&mut self) -> Result<Item, ParserError> {
         let start = self.current_span();
         self.advance();
-        let name = self.consume_ident()?;
+        let name = self.parse_module_spec()?;
+        let (alias, names) = self.parse_import_clause()?;
+        let feature = self.parse_import_feature()?;
         self.skip_semicolons();
-        Ok(Item::LocImport { name, span: start.merge(self.prev_span()) })
+        Ok(Item::LocImport { name, alias, names, feature, span: start.merge(self.prev_span()) })
+    }
+
+    /// `net.handlers.auth`, optionally led by dots for a relative import (`..util`).
+    fn parse_module_spec(&mut self) -> Result<String, ParserError> {
+        let mut spec = String::new();
+        loop {
+            match self.peek_token() {
+                Token::Dot => spec.push('.'),
+                Token::DotDot => spec.push_str(".."),
+                _ => break,
+            }
+            self.advance();
+        }
+        spec.push_str(&self.consume_ident()?);
+        while matches!(self.peek_token(), Token::Dot) {
+            self.advance();
+            spec.push('.');
+            spec.push_str(&self.consume_ident()?);
+        }
+        Ok(spec)
+    }
+
+    fn parse_std_import(&mut self) -> Result<Item, ParserError> {
+        let start = self.current_span();
+        self.advance();  // consume "std"
+        let module = self.consume_ident()?;
+        let (alias, names) = self.parse_import_clause()?;
+        self.skip_semicolons();
+        Ok(Item::StdImport { module, alias, names, span: start.merge(self.prev_span()) })
+    }
+
+    /// The optional `as alias` or `{ a, b }` after an imported module's name.
+    fn parse_import_clause(&mut self) -> Result<(Option<String>, Option<Vec<String>>), ParserError> {
+        match self.peek_token() {
+            Token::Ident(kw) if kw == "as" => {
+                self.advance();
+                Ok((Some(self.consume_ident()?), None))
+            }
+            Token::LBrace => {
+                self.advance();
+                let mut names = Vec::new();
+                while !matches!(self.peek_token(), Token::RBrace | Token::Eof) {
+                    names.push(self.consume_ident()?);
+                    if !matches!(self.peek_token(), Token::Comma) { break; }
+                    self.advance();
+                }
+                self.consume(Token::RBrace)?;
+                Ok((None, Some(names)))
+            }
+            _ => Ok((None, None)),
+        }
+    }
+
+    /// The optional `if "feature"` ending an import on its own line: the
+    /// package feature that must be on for the import to run.
+    fn parse_import_feature(&mut self) -> Result<Option<String>, ParserError> {
+        if !matches!(self.peek_token(), Token::If) || self.at_line_start() {
+            return Ok(None);
+        }
+        self.advance();
+        Ok(Some(self.consume_string()?))
     }
 
     fn parse_lib_decl(&mut self) -> Result<Item, ParserError> {
@@ -245,6 +430,18 @@ impl Parser {
         Ok(Item::LibDecl { name, span: start.merge(self.prev_span()) })
     }
 
+    fn parse_export(&mut self) -> Result<Item, ParserError> {
+        let start = self.current_span();
+        self.advance();  // consume "exp"
+        let mut names = vec![self.consume_ident()?];
+        while matches!(self.peek_token(), Token::Comma) {
+            self.advance();
+            names.push(self.consume_ident()?);
+        }
+        self.skip_semicolons();
+        Ok(Item::Export { names, span: start.merge(self.prev_span()) })
+    }
+
     fn parse_load_stmt(&mut self) -> Result<Item, ParserError> {
         let start = self.current_span();
         self.advance();  // consume "load"
@@ -256,6 +453,7 @@ impl Parser {
             }
             _ => self.consume_string()?,
         };
+        let feature = self.parse_import_feature()?;
 
         self.skip_semicolons();
         let is_lib = path.starts_with('@');
@@ -268,12 +466,17 @@ impl Parser {
             _ => None,
         };
 
-        Ok(Item::LoadStmt { path, is_lib, alias, span: start.merge(self.prev_span()) })
+        Ok(Item::LoadStmt { path, is_lib, alias, feature, span: start.merge(self.prev_span()) })
     }
 
     fn parse_param_list(&mut self) -> Result<Vec<String>, ParserError> {
+        Ok(self.parse_typed_param_list()?.0)
+    }
+
+    fn parse_typed_param_list(&mut self) -> Result<(Vec<String>, Vec<Option<TypeAnnotation>>), ParserError> {
         let mut params = Vec::new();
-        if matches!(self.peek_token(), Token::RParen | Token::Eof) { return Ok(params); }
+        let mut types = Vec::new();
+        if matches!(self.peek_token(), Token::RParen | Token::Eof) { return Ok((params, types)); }
         loop {
             if matches!(self.peek_token(), Token::SelfKw) {
                 self.advance();
@@ -281,10 +484,53 @@ impl Parser {
             } else {
                 params.push(self.consume_ident()?);
             }
+            types.push(self.parse_optional_annotation()?);
             if !matches!(self.peek_token(), Token::Comma) { break; }
             self.advance();
         }
-        Ok(params)
+        Ok((params, types))
+    }
+
+    /// `: Type` after a binding name, if present.
+    fn parse_optional_annotation(&mut self) -> Result<Option<TypeAnnotation>, ParserError> {
+        if !matches!(self.peek_token(), Token::Colon) { return Ok(None); }
+        self.advance();
+        Ok(Some(self.parse_type()?))
+    }
+
+    /// `-> Type` after a parameter list, if present.
+    fn parse_return_type(&mut self) -> Result<Option<TypeAnnotation>, ParserError> {
+        if !matches!(self.peek_token(), Token::ThinArrow) { return Ok(None); }
+        self.advance();
+        Ok(Some(self.parse_type()?))
+    }
+
+    /// Type grammar: `Name` | `Name<Type, ...>`; `nil` and `fun`/`fn` are accepted as names.
+    fn parse_type(&mut self) -> Result<TypeAnnotation, ParserError> {
+        let start = self.current_span();
+        let name = match self.peek_token() {
+            Token::Ident(n) => { self.advance(); n }
+            Token::Nil => { self.advance(); "Nil".to_string() }
+            Token::Fun | Token::Fn => { self.advance(); "Fun".to_string() }
+            other => {
+                return Err(ParserError::UnexpectedToken {
+                    expected: "type name".to_string(),
+                    found: format!("{:?}", other),
+                    span: start,
+                });
+            }
+        };
+        let mut args = Vec::new();
+        if matches!(self.peek_token(), Token::Less) {
+            self.advance();
+            loop {
+                args.push(self.parse_type()?);
+                if !matches!(self.peek_token(), Token::Comma) { break; }
+                self.advance();
+            }
+            self.consume(Token::Greater)?;
+        }
+        Ok(TypeAnnotation { name, args, span: start.merge(self.prev_span()) })
     }
 
     fn parse_stmt(&mut self) -> Result<Stmt, ParserError> {
@@ -300,6 +546,8 @@ impl Parser {
             Token::Out    => self.parse_out_stmt(),
             Token::Print  => self.parse_print_stmt(),
             Token::LBrace => { let b = self.parse_block()?; Ok(Stmt::Block(b)) }
+            // `when` is only a keyword before a platform name and its block
+            Token::Ident(kw) if kw == "when" && matches!(self.peek_nth(1), Token::Ident(_)) => self.parse_when_stmt(),
             // ── Nested named function: fn name(params) { body }
             // Rewrite as:  let name = fn(params) { body }
             // This is the fix for AXM_402 / nil-call bug — closures can be
@@ -344,10 +592,11 @@ impl Parser {
         let start = self.current_span();
         self.advance();
         let name  = self.consume_ident()?;
+        let ty    = self.parse_optional_annotation()?;
         self.consume(Token::Assign)?;
         let value = self.parse_expr()?;
         self.skip_semicolons();
-        Ok(Stmt::Let { name, value, span: start.merge(self.prev_span()) })
+        Ok(Stmt::Let { name, ty, value, span: start.merge(self.prev_span()) })
     }
 
     fn parse_if_stmt(&mut self) -> Result<Stmt, ParserError> {
@@ -372,47 +621,109 @@ impl Parser {
         Ok(Stmt::If { condition, then_body, else_body, span: start.merge(self.prev_span()) })
     }
 
+    /// `when windows { .. } else when linux, macos { .. } else { .. }`: the
+    /// branch for the platform parsing the script, as a block. The others
+    /// are parsed and dropped, so they never run (or get checked) here.
+    fn parse_when_stmt(&mut self) -> Result<Stmt, ParserError> {
+        self.advance();  // consume "when"
+        let mut chosen = None;
+        loop {
+            let mut matched = false;
+            loop {
+                let span = self.current_span();
+                let platform = self.consume_ident()?;
+                if !PLATFORMS.contains(&platform.as_str()) {
+                    return Err(ParserError::InvalidSyntax {
+                        context: format!("unknown platform '{}' (expected one of: {})", platform, PLATFORMS.join(", ")),
+                        span,
+                    });
+                }
+                matched |= on_platform(&platform);
+                if !matches!(self.peek_token(), Token::Comma) { break; }
+                self.advance();
+            }
+            let body = self.parse_block()?;
+            if matched && chosen.is_none() { chosen = Some(body); }
+            if !matches!(self.peek_token(), Token::Else) { break; }
+            self.advance();
+            match self.peek_token() {
+                Token::Ident(kw) if kw == "when" => { self.advance(); }
+                _ => {
+                    let body = self.parse_block()?;
+                    chosen.get_or_insert(body);
+                    break;
+                }
+            }
+        }
+        Ok(Stmt::Block(chosen.unwrap_or_default()))
+    }
+
     fn is_match_body_ahead(&self) -> bool {
         if !matches!(self.peek_token(), Token::LBrace) { return false; }
         let mut i = 1usize;
         while i < self.tokens.len() && matches!(&self.tokens[i].0, Token::Semicolon) { i += 1; }
-        if i >= self.tokens.len() { return false; }
 
-        match &self.tokens[i].0 {
-            Token::Ident(_) | Token::Number(_) | Token::String(_)
-            | Token::True | Token::False | Token::Els => {}
-            _ => return false,
-        }
+        let token = |i: usize| self.tokens.get(i).map(|(t, _)| t);
+        // The last token of the pattern term at `i`: `-1` is two, `[..]` runs to its bracket
+        let term_end = |mut i: usize| -> Option<usize> {
+            match token(i)? {
+                Token::Ident(_) | Token::Number(_) | Token::String(_) | Token::True | Token::False | Token::Els => Some(i),
+                Token::Minus => matches!(token(i + 1), Some(Token::Number(_))).then_some(i + 1),
+                Token::LBracket => {
+                    let mut depth = 0usize;
+                    loop {
+                        match token(i)? {
+                            Token::LBracket => depth += 1,
+                            Token::RBracket => { depth -= 1; if depth == 0 { return Some(i); } }
+                            Token::Eof      => return None,
+                            _ => {}
+                        }
+                        i += 1;
+                    }
+                }
+                _ => None,
+            }
+        };
+        let Some(end) = term_end(i) else { return false };
+        i = end;
 
         loop {
             i += 1;
-            if i >= self.tokens.len() { return false; }
-            match &self.tokens[i].0 {
-                Token::Dot => {
+            match token(i) {
+                Some(Token::Dot) => {
                     i += 1;
-                    if i >= self.tokens.len() { return false; }
-                    if !matches!(&self.tokens[i].0, Token::Ident(_)) { return false; }
+                    if !matches!(token(i), Some(Token::Ident(_))) { return false; }
                 }
-                Token::LParen => {
+                Some(Token::LParen) => {
                     let mut depth = 0usize;
-                    while i < self.tokens.len() {
-                        match &self.tokens[i].0 {
+                    while let Some(t) = token(i) {
+                        match t {
                             Token::LParen  => depth += 1,
-                            Token::RParen  => { depth -= 1; if depth == 0 { i += 1; break; } }
+                            Token::RParen  => { depth -= 1; if depth == 0 { break; } }
                             Token::Eof     => return false,
                             _ => {}
                         }
                         i += 1;
                     }
-                    return i < self.tokens.len() && matches!(&self.tokens[i].0, Token::Arrow);
                 }
-                Token::Arrow => return true,
+                // `a | b` and `lo..hi` continue the pattern; a guard or the arrow ends it
+                Some(Token::Or | Token::DotDot) => {
+                    let Some(end) = term_end(i + 1) else { return false };
+                    i = end;
+                }
+                Some(Token::Arrow | Token::If) => return true,
                 _ => return false,
             }
         }
     }
 
     fn parse_if_as_match(&mut self, expr: Expr, start: Span) -> Result<Stmt, ParserError> {
+        let arms = self.parse_match_arms()?;
+        Ok(Stmt::Match { expr, arms, span: start.merge(self.prev_span()) })
+    }
+
+    /// `{ pattern => body, ... }` after a match scrutinee.
+    fn parse_match_arms(&mut self) -> Result<Vec<MatchArm>, ParserError> {
         self.consume(Token::LBrace)?;
         let mut arms = Vec::new();
 
@@ -422,6 +733,10 @@ impl Parser {
 
             let astart  = self.current_span();
             let pattern = self.parse_match_pattern()?;
+            let guard = if matches!(self.peek_token(), Token::If) {
+                self.advance();
+                Some(self.parse_expr()?)
+            } else { None };
             self.consume(Token::Arrow)?;
 
             let body = if matches!(self.peek_token(), Token::LBrace) {
@@ -433,11 +748,37 @@ impl Parser {
             self.skip_semicolons();
             if matches!(self.peek_token(), Token::Comma) { self.advance(); }
 
-            arms.push(MatchArm { pattern, body, span: astart.merge(self.prev_span()) });
+            arms.push(MatchArm { pattern, guard, body, span: astart.merge(self.prev_span()) });
         }
 
         self.consume(Token::RBrace)?;
-        Ok(Stmt::Match { expr, arms, span: start.merge(self.prev_span()) })
+        Ok(arms)
+    }
+
+    /// `match x { .. }` as a value. Arms cannot `ret`: the match has to
+    /// produce a value where it stands.
+    fn parse_match_expr(&mut self) -> Result<Expr, ParserError> {
+        let start = self.current_span(); self.advance();
+        let expr = self.parse_expr()?;
+        let arms = self.parse_match_arms()?;
+        if let Some(arm) = arms.iter().find(|arm| Self::block_returns(&arm.body)) {
+            return Err(ParserError::InvalidSyntax {
+                context: "match expression: an arm cannot `ret`".to_string(),
+                span: arm.span,
+            });
+        }
+        Ok(Expr::Match { expr: Box::new(expr), arms, span: start.merge(self.prev_span()) })
+    }
+
+    /// Whether `stmts` contain a `ret` that would leave the enclosing function.
+    fn block_returns(stmts: &[Stmt]) -> bool {
+        stmts.iter().any(|stmt| match stmt {
+            Stmt::Return { .. } => true,
+            Stmt::If { then_body, else_body, .. } => Self::block_returns(then_body) || else_body.as_deref().is_some_and(Self::block_returns),
+            Stmt::While { body, .. } | Stmt::For { body, .. } | Stmt::Block(body) => Self::block_returns(body),
+            Stmt::Match { arms, .. } => arms.iter().any(|arm| Self::block_returns(&arm.body)),
+            Stmt::Expr(_) | Stmt::Let { .. } | Stmt::GoSpawn { .. } | Stmt::Out { .. } => false,
+        })
     }
 
     fn parse_while_stmt(&mut self) -> Result<Stmt, ParserError> {
@@ -477,7 +818,19 @@ impl Parser {
         self.parse_if_as_match(expr, start)
     }
 
+    /// A pattern, or alternatives `a | b | c`.
     fn parse_match_pattern(&mut self) -> Result<MatchPattern, ParserError> {
+        let first = self.parse_single_pattern()?;
+        if !matches!(self.peek_token(), Token::Or) { return Ok(first); }
+        let mut alts = vec![first];
+        while matches!(self.peek_token(), Token::Or) {
+            self.advance();
+            alts.push(self.parse_single_pattern()?);
+        }
+        Ok(MatchPattern::Or(alts))
+    }
+
+    fn parse_single_pattern(&mut self) -> Result<MatchPattern, ParserError> {
         match self.peek_token() {
             Token::Els => { self.advance(); Ok(MatchPattern::Wildcard) }
 
@@ -509,9 +862,40 @@ impl Parser {
                 Ok(MatchPattern::Identifier(name))
             }
 
-            Token::Number(_) | Token::String(_) | Token::True | Token::False => {
-                let expr = self.parse_primary()?;
-                Ok(MatchPattern::Literal(expr))
+            Token::Number(_) | Token::String(_) | Token::True | Token::False | Token::Minus => {
+                let start = self.parse_pattern_literal()?;
+                if !matches!(self.peek_token(), Token::DotDot) { return Ok(MatchPattern::Literal(start)); }
+                self.advance();
+                let end = self.parse_pattern_literal()?;
+                Ok(MatchPattern::Range { start, end })
+            }
+
+            Token::LBracket => {
+                self.advance();
+                let mut items = Vec::new();
+                let mut rest = None;
+                while !matches!(self.peek_token(), Token::RBracket | Token::Eof) {
+                    if matches!(self.peek_token(), Token::DotDot) {
+                        // `..rest` or `..` takes the tail and must come last
+                        self.advance();
+                        rest = Some(Box::new(match self.peek_token() {
+                            Token::Ident(_) => self.parse_single_pattern()?,
+                            _ => MatchPattern::Wildcard,
+                        }));
+                        if !matches!(rest.as_deref(), Some(MatchPattern::Identifier(_) | MatchPattern::Wildcard)) {
+                            return Err(ParserError::InvalidSyntax {
+                                context: "list pattern: `..` takes a name or `_`".to_string(),
+                                span: self.prev_span(),
+                            });
+                        }
+                        break;
+                    }
+                    items.push(self.parse_match_pattern()?);
+                    if !matches!(self.peek_token(), Token::Comma) { break; }
+                    self.advance();
+                }
+                self.consume(Token::RBracket)?;
+                Ok(MatchPattern::List { items, rest })
             }
 
             tok => Err(ParserError::UnexpectedToken {
@@ -522,6 +906,24 @@ impl Parser {
         }
     }
 
+    /// A literal in a pattern: a number (optionally negative), string or boolean.
+    fn parse_pattern_literal(&mut self) -> Result<Expr, ParserError> {
+        if !matches!(self.peek_token(), Token::Minus) { return self.parse_primary(); }
+        let start = self.current_span();
+        self.advance();
+        match self.peek_token() {
+            Token::Number(n) => {
+                self.advance();
+                Ok(Expr::Number { value: -n, span: start.merge(self.prev_span()) })
+            }
+            tok => Err(ParserError::UnexpectedToken {
+                expected: "number".to_string(),
+                found: format!("{:?}", tok),
+                span: self.current_span(),
+            }),
+        }
+    }
+
     fn parse_out_stmt(&mut self) -> Result<Stmt, ParserError> {
         let start = self.current_span();
         self.advance();
@@ -537,8 +939,13 @@ impl Parser {
     fn parse_output_stmt(&mut self, start: Span) -> Result<Stmt, ParserError> {
         let mut arguments = Vec::new();
 
-        while self.token_can_start_expr(&self.peek_token()) {
+        // A `match` on the next line is a statement, not another argument
+        let mut after_comma = true;
+        while self.token_can_start_expr(&self.peek_token())
+            && (after_comma || !matches!(self.peek_token(), Token::Match))
+        {
             arguments.push(self.parse_expr()?);
+            after_comma = false;
 
             if matches!(self.peek_token(), Token::Comma) {
                 if matches!(self.peek_nth(1),
@@ -554,6 +961,7 @@ impl Parser {
                     break;
                 }
                 self.advance();
+                after_comma = true;
             }
         }
 
@@ -572,10 +980,12 @@ impl Parser {
             | Token::LParen
             | Token::LBracket
             | Token::SelfKw
+            | Token::Super
             | Token::New
             | Token::Dot
             | Token::Minus
             | Token::Not
+            | Token::Match
         )
     }
 
@@ -585,7 +995,14 @@ impl Parser {
         while !matches!(self.peek_token(), Token::RBrace | Token::Eof) {
             self.skip_semicolons();
             if matches!(self.peek_token(), Token::RBrace) { break; }
-            stmts.push(self.parse_stmt()?);
+            let remaining = self.tokens.len();
+            match self.parse_stmt() {
+                Ok(stmt) => stmts.push(stmt),
+                Err(e) => {
+                    self.errors.push(e);
+                    self.synchronize(remaining);
+                }
+            }
         }
         self.consume(Token::RBrace)?;
         Ok(stmts)
@@ -731,6 +1148,9 @@ impl Parser {
                         Token::Print => { self.advance(); "print".to_string() }
                         Token::In => { self.advance(); "in".to_string() }
                         Token::Match => { self.advance(); "match".to_string() }
+                        Token::Fn => { self.advance(); "fn".to_string() }
+                        Token::Load => { self.advance(); "load".to_string() }
+                        Token::Ext => { self.advance(); "ext".to_string() }
                         _ => self.consume_ident()?
                     };
                     if matches!(self.peek_token(), Token::LParen) {
@@ -771,13 +1191,23 @@ impl Parser {
                 let mut parts = Vec::new();
                 for (is_expr, text) in segments {
                     if is_expr {
-                        let mut sub = Parser::new(&text, self.source_id);
+                        let (source, spec) = split_format_spec(&text);
+                        let mut sub = Parser::new(source, self.source_id);
                         let sub_items = sub.parse().map_err(|_| ParserError::InvalidSyntax {
                             context: format!("interpolated expression: {}", text),
                             span: start,
                         })?;
+                        if let Some(spec) = spec {
+                            crate::core::format::Spec::parse(spec).map_err(|e| ParserError::InvalidSyntax {
+                                context: format!("interpolated format: {}", e),
+                                span: start,
+                            })?;
+                        }
                         if let Some(Item::Statement(Stmt::Expr(e))) = sub_items.into_iter().next() {
-                            parts.push(StringPart::Expr(e));
+                            parts.push(match spec {
+                                Some(spec) => StringPart::Formatted { expr: e, spec: spec.to_string() },
+                                None => StringPart::Expr(e),
+                            });
                         } else {
                             parts.push(StringPart::Literal(text));
                         }
@@ -791,6 +1221,15 @@ impl Parser {
             Token::False  => { self.advance(); Ok(Expr::Boolean { value: false, span: start }) }
             Token::Nil    => { self.advance(); Ok(Expr::Identifier { name: "nil".into(), span: start }) }
             Token::SelfKw => { self.advance(); Ok(Expr::SelfRef { span: start }) }
+            Token::Super  => {
+                self.advance();
+                self.consume(Token::Dot)?;
+                let method = self.consume_ident()?;
+                self.consume(Token::LParen)?;
+                let arguments = self.parse_arg_list()?;
+                self.consume(Token::RParen)?;
+                Ok(Expr::SuperCall { method, arguments, span: start.merge(self.prev_span()) })
+            }
             Token::New    => {
                 self.advance();
                 let class_name = self.consume_ident()?;
@@ -812,6 +1251,7 @@ impl Parser {
                     span: start.merge(self.prev_span()),
                 })
             }
+            Token::Match => self.parse_match_expr(),
             Token::LBracket => {
                 self.advance();
                 let items = self.parse_list_items()?;
@@ -863,10 +1303,12 @@ impl Parser {
         self.consume(Token::LParen)?;
         let params = self.parse_param_list()?;
         self.consume(Token::RParen)?;
+        self.parse_return_type()?;
         let body = self.parse_block()?;
         let span = start.merge(self.prev_span());
         Ok(Stmt::Let {
             name,
+            ty: None,
             value: Expr::Lambda { params, body, span },
             span,
         })
@@ -888,8 +1330,7 @@ impl Parser {
     }
 
     fn prev_span(&self) -> Span {
-        self.tokens.front().map(|(_, s)| *s)
-            .unwrap_or_else(|| Span::new(self.source_id, 0, 0))
+        self.prev
     }
 
     fn current_span(&self) -> Span {
@@ -897,8 +1338,17 @@ impl Parser {
             .unwrap_or_else(|| Span::new(self.source_id, 0, 0))
     }
 
+    /// Whether a line break separates the next token from the last one.
+    fn at_line_start(&self) -> bool {
+        let (from, to) = (self.prev.end, self.current_span().start);
+        self.breaks.iter().any(|&b| from <= b && b < to)
+    }
+
     fn advance(&mut self) -> Token {
-        self.tokens.pop_front().map(|(t, _)| t).unwrap_or(Token::Eof)
+        match self.tokens.pop_front() {
+            Some((token, span)) => { self.prev = span; token }
+            None => Token::Eof,
+        }
     }
 
     fn consume(&mut self, expected: Token) -> Result<(), ParserError> {
@@ -946,6 +1396,44 @@ impl Parser {
     }
 }
 
+/// Platform names `when` accepts: OS families, then operating systems.
+pub const PLATFORMS: &[&str] = &[
+    "unix", "windows", "wasm",
+    "linux", "macos", "ios", "android", "freebsd", "netbsd", "openbsd", "dragonfly", "solaris", "illumos",
+];
+
+/// Whether `platform` names the OS or OS family this build runs on.
+pub fn on_platform(platform: &str) -> bool {
+    platform == std::env::consts::OS || platform == std::env::consts::FAMILY
+        || (platform == "wasm" && cfg!(target_family = "wasm"))
+}
+
+/// Split `@(expr:spec)` at its format spec: the last `:` outside brackets
+/// and string quotes. Expressions have no other use for a colon there.
+fn split_format_spec(text: &str) -> (&str, Option<&str>) {
+    let mut depth = 0i32;
+    let mut quote = None;
+    let mut escaped = false;
+    let mut split = None;
+    for (i, c) in text.char_indices() {
+        match (quote, c) {
+            (Some(_), _) if escaped => escaped = false,
+            (Some(_), '\\') => escaped = true,
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '(' | '[' | '{') => depth += 1,
+            (None, ')' | ']' | '}') => depth -= 1,
+            (None, ':') if depth == 0 => split = Some(i),
+            _ => {}
+        }
+    }
+    match split {
+        Some(i) => (&text[..i], Some(&text[i + 1..])),
+        None => (text, None),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1162,6 +1650,92 @@ mod tests {
         assert!(matches!(items[0], Item::ClassDecl { .. }));
     }
 
+    #[test]
+    fn test_static_members() {
+        let items = parse("cls Counter {\n    static let count = 0\n    let own = 1\n    static fun bump() { ret 1 }\n    static() { ret 2 }\n}");
+        let Item::ClassDecl { body, .. } = &items[0] else { panic!("expected class, got {:?}", items[0]) };
+        let statics: Vec<bool> = body.iter().map(ClassMember::is_static).collect();
+        assert_eq!(statics, [true, false, true, false]);
+    }
+
+    #[test]
+    fn test_import_clauses() {
+        let items = parse("loc utils as u\nloc shapes { area, Point }\nstd mth { sqrt, pow }\nstd str as s\nloc plain");
+        let imports: Vec<(&str, Option<&str>, Option<&Vec<String>>)> = items.iter().filter_map(|i| match i {
+            Item::LocImport { name, alias, names, .. } | Item::StdImport { module: name, alias, names, .. } => Some((name.as_str(), alias.as_deref(), names.as_ref())),
+            _ => None,
+        }).collect();
+        assert_eq!(imports[0], ("utils", Some("u"), None));
+        assert_eq!(imports[1], ("shapes", None, Some(&vec!["area".to_string(), "Point".to_string()])));
+        assert_eq!(imports[2], ("mth", None, Some(&vec!["sqrt".to_string(), "pow".to_string()])));
+        assert_eq!(imports[3], ("str", Some("s"), None));
+        assert_eq!(imports[4], ("plain", None, None));
+        assert!(Parser::new("loc utils as", 0).parse().is_err());
+    }
+
+    #[test]
+    fn test_import_features() {
+        let items = parse("loc plot as p if \"plotting\"\nload \"tui.ax\" if \"tui\"\nloc utils\nif \"x\" == y { out 1 }");
+        let features: Vec<Option<&str>> = items.iter().filter_map(|i| match i {
+            Item::LocImport { feature, .. } | Item::LoadStmt { feature, .. } => Some(feature.as_deref()),
+            _ => None,
+        }).collect();
+        assert_eq!(features, [Some("plotting"), Some("tui"), None]);
+        // An `if` on the next line starts a statement
+        assert!(matches!(items.last(), Some(Item::Statement(Stmt::If { .. }))));
+        assert!(Parser::new("loc plot if tui", 0).parse().is_err());
+    }
+
+    #[test]
+    fn test_nested_and_relative_loc_specs() {
+        let specs: Vec<String> = parse("loc net.handlers.auth\nloc .sibling as s\nloc ...pkg.util { f }").into_iter()
+            .filter_map(|i| match i { Item::LocImport { name, .. } => Some(name), _ => None }).collect();
+        assert_eq!(specs, ["net.handlers.auth", ".sibling", "...pkg.util"]);
+        assert!(Parser::new("loc net.", 0).parse().is_err());
+    }
+
+    #[test]
+    fn test_export_list() {
+        let items = parse("exp area, Shape\nfun area() { ret exp(1) }\nlet exp = 2");
+        let exports: Vec<&Vec<String>> = items.iter().filter_map(|i| match i { Item::Export { names, .. } => Some(names), _ => None }).collect();
+        assert_eq!(exports, [&vec!["area".to_string(), "Shape".to_string()]]);
+        assert!(matches!(items.last(), Some(Item::Statement(Stmt::Let { name, .. })) if name == "exp"));
+    }
+
+    #[test]
+    fn test_super_call() {
+        let items = parse("cls D ext B {\n    init(n) { super.init(n, 1) }\n}");
+        let Item::ClassDecl { body, .. } = &items[0] else { panic!("expected class, got {:?}", items[0]) };
+        let ClassMember::Method { body, .. } = &body[0] else { panic!("expected method") };
+        assert!(matches!(&body[0], Stmt::Expr(Expr::SuperCall { method, arguments, .. })
+            if method == "init" && arguments.len() == 2));
+        assert!(Parser::new("let x = super", 0).parse().is_err());
+    }
+
+    #[test]
+    fn test_accessors() {
+        let items = parse("cls C {\n    get area() { ret 1 }\n    set area(v) { }\n    get() { ret 2 }\n}");
+        let Item::ClassDecl { body, .. } = &items[0] else { panic!("expected class, got {:?}", items[0]) };
+        let kinds: Vec<Option<AccessorKind>> = body.iter().map(|m| match m {
+            ClassMember::Accessor { kind, .. } => Some(*kind),
+            _ => None,
+        }).collect();
+        assert_eq!(kinds, [Some(AccessorKind::Get), Some(AccessorKind::Set), None]);
+        assert!(Parser::new("cls C { set area() { } }", 0).parse().is_err());
+        assert!(Parser::new("cls C { get area(x) { ret x } }", 0).parse().is_err());
+    }
+
+    #[test]
+    fn test_interface_declaration() {
+        let items = parse("ifc Shape {\n    area()\n    fun scale(k: num) -> num, name()\n}\ncls Sq ext Base impl Shape, Named { }");
+        let Item::InterfaceDecl { methods, .. } = &items[0] else { panic!("expected interface, got {:?}", items[0]) };
+        let sigs: Vec<(&str, usize)> = methods.iter().map(|m| (m.name.as_str(), m.params.len())).collect();
+        assert_eq!(sigs, [("area", 0), ("scale", 1), ("name", 0)]);
+        let Item::ClassDecl { parent, interfaces, .. } = &items[1] else { panic!("expected class, got {:?}", items[1]) };
+        assert_eq!(parent.as_deref(), Some("Base"));
+        assert_eq!(interfaces, &["Shape", "Named"]);
+    }
+
     #[test]
     fn test_malformed_lambda_missing_rparen() {
         // Missing ) in lambda param list
@@ -1187,6 +1761,15 @@ mod tests {
         assert_eq!(items.len(), 1);
     }
 
+    #[test]
+    fn test_interpolated_format_spec() {
+        let items = parse(r#"let msg = "@(total / n:>8.2) @(m.get("a:b"))""#);
+        let Item::Statement(Stmt::Let { value: Expr::InterpolatedString { parts, .. }, .. }) = &items[0] else { panic!("not an interpolated string") };
+        assert!(matches!(&parts[0], StringPart::Formatted { expr: Expr::BinaryOp { .. }, spec } if spec == ">8.2"));
+        assert!(matches!(&parts[2], StringPart::Expr(Expr::MethodCall { .. })));
+        assert!(Parser::new(r#"out "@(x:8q)""#, 0).parse().is_err());
+    }
+
     #[test]
     fn test_match_statement() {
         let src = r#"
@@ -1254,4 +1837,107 @@ mod tests {
         let items = parse(src);
         assert_eq!(items.len(), 1);
     }
+
+    #[test]
+    fn test_typed_function_signature() {
+        let items = parse("fun add(x: Num, y) -> List<Num> { ret [x, y] }");
+        if let Item::FunctionDecl { params, param_types, ret_type, .. } = &items[0] {
+            assert_eq!(params, &vec!["x".to_string(), "y".to_string()]);
+            assert_eq!(param_types[0].as_ref().map(|t| t.name.as_str()), Some("Num"));
+            assert!(param_types[1].is_none());
+            let ret = ret_type.as_ref().expect("return type");
+            assert_eq!(ret.name, "List");
+            assert_eq!(ret.args[0].name, "Num");
+        } else {
+            panic!("expected FunctionDecl");
+        }
+    }
+
+    #[test]
+    fn test_typed_let() {
+        let items = parse("let n: Num = 5");
+        assert!(matches!(&items[0], Item::Statement(Stmt::Let { ty: Some(t), .. }) if t.name == "Num"));
+    }
+
+    #[test]
+    fn test_recovery_reports_multiple_errors() {
+        let src = r#"
+            let a = ;
+            fn f(x) {
+                let b = 1 +;
+                out b
+            }
+            let c = (2;
+            out a
+        "#;
+        let (items, errors) = Parser::new(src, 0).parse_recovering();
+        assert_eq!(errors.len(), 3, "{:?}", errors);
+        // The function and the trailing statement still parse
+        assert!(items.iter().any(|i| matches!(i, Item::FunctionDecl { name, .. } if name == "f")));
+        assert!(items.iter().any(|i| matches!(i, Item::Statement(Stmt::Out { .. }))));
+    }
+
+    #[test]
+    fn test_parse_still_returns_first_error() {
+        let err = parse_err("let a = ;\nlet b = )");
+        assert!(matches!(err, ParserError::UnexpectedToken { .. }), "{:?}", err);
+    }
+
+    #[test]
+    fn test_when_keeps_the_branch_for_this_platform() {
+        let src = "when windows { out 1 } else when linux, macos { out 2 } else { out 3 }\nlet when = 1\nwhen = when + 1";
+        let items = parse(src);
+        let Item::Statement(Stmt::Block(body)) = &items[0] else { panic!("{:?}", items[0]) };
+        let expected = if cfg!(windows) { 1.0 } else if cfg!(any(target_os = "linux", target_os = "macos")) { 2.0 } else { 3.0 };
+        assert!(matches!(&body[..], [Stmt::Out { arguments, .. }] if matches!(arguments[..], [Expr::Number { value, .. }] if value == expected)), "{:?}", body);
+        // Only before a platform name
+        assert_eq!(items.len(), 3);
+        assert!(matches!(&parse("when wasm { out 1 }")[0], Item::Statement(Stmt::Block(b)) if b.is_empty() != cfg!(target_family = "wasm")));
+        assert!(Parser::new("when plan9 { out 1 }", 0).parse().is_err());
+    }
+
+    #[test]
+    fn test_keyword_member_names() {
+        let items = parse("let m = ffi.load(\"libm.so.6\")\nlet cos = ffi.fn(m, \"cos\", \"(f64) -> f64\")\nlet e = pth.ext(\"a.ax\")");
+        let methods: Vec<&str> = items.iter().filter_map(|i| match i {
+            Item::Statement(Stmt::Let { value: Expr::MethodCall { method, .. }, .. }) => Some(method.as_str()),
+            _ => None,
+        }).collect();
+        assert_eq!(methods, ["load", "fn", "ext"]);
+    }
+
+    #[test]
+    fn test_match_expression() {
+        let items = parse("let label = match s { Status.Active => \"on\", els => \"off\" }\nout x\nmatch x { 1 => { out 1 } }");
+        assert!(matches!(&items[0], Item::Statement(Stmt::Let { value: Expr::Match { arms, .. }, .. }) if arms.len() == 2));
+        assert!(matches!(&items[1], Item::Statement(Stmt::Out { arguments, .. }) if arguments.len() == 1));
+        assert!(matches!(&items[2], Item::Statement(Stmt::Match { .. })));
+
+        let err = parse_err("fun f(x) { let y = match x { 1 => { ret 5 } els => 2 } }");
+        assert!(matches!(err, ParserError::InvalidSyntax { .. }), "{:?}", err);
+    }
+
+    #[test]
+    fn test_match_guards_and_alternatives() {
+        let items = parse("match s {\n    1 | 2 | 3 => { out 1 }\n    Status.Code(n) if n > 3 => { out n }\n    els => { out 0 }\n}");
+        let Item::Statement(Stmt::Match { arms, .. }) = &items[0] else { panic!("expected match, got {:?}", items[0]) };
+        assert!(matches!(&arms[0].pattern, MatchPattern::Or(alts) if alts.len() == 3));
+        assert!(arms[0].guard.is_none());
+        assert!(matches!(&arms[1].pattern, MatchPattern::EnumVariant { binding: Some(b), .. } if b == "n"));
+        assert!(matches!(arms[1].guard, Some(Expr::BinaryOp { .. })));
+    }
+
+    #[test]
+    fn test_range_and_list_patterns() {
+        let items = parse("match x {\n    -5..10 => { out 1 }\n    [first, _, ..rest] => { out first }\n    [] => { out 0 }\n}");
+        let Item::Statement(Stmt::Match { arms, .. }) = &items[0] else { panic!("expected match, got {:?}", items[0]) };
+        assert!(matches!(&arms[0].pattern, MatchPattern::Range { start: Expr::Number { value, .. }, .. } if *value == -5.0));
+        let MatchPattern::List { items, rest } = &arms[1].pattern else { panic!("expected list, got {:?}", arms[1].pattern) };
+        assert_eq!(items.len(), 2);
+        assert!(matches!(rest.as_deref(), Some(MatchPattern::Identifier(r)) if r == "rest"));
+        assert!(matches!(&arms[2].pattern, MatchPattern::List { items, rest: None } if items.is_empty()));
+
+        let err = parse_err("match x { [..rest, last] => { out 1 } }");
+        assert!(matches!(err, ParserError::UnexpectedToken { .. }), "{:?}", err);
+    }
 }
@@ -0,0 +1,321 @@
+//! `axiom serve-playground` — a local web page for trying Axiom snippets.
+//!
+//! GET `/` serves a single-page editor (playground.html). The page opens a
+//! WebSocket on `/ws` and sends each snippet as one text frame; the server
+//! runs it on a fresh sandboxed Runtime and streams JSON messages back:
+//!
+//!   {"type":"out","text":"..."}    one per line of script output
+//!   {"type":"error","text":"..."}  rendered diagnostic, if the run failed
+//!   {"type":"done","ms":12}        end of the run
+//!
+//! Plain std::net with one thread per connection — this is a teaching aid,
+//! not a hosting platform; it binds to localhost by default. The socket only
+//! accepts upgrades from the page itself: any other site open in the browser
+//! could otherwise run snippets here.
+
+use crate::diagnostics::DiagnosticEngine;
+use crate::runtime::{with_output_sink, Runtime};
+use crate::sandbox::Capabilities;
+use crate::Parser;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{IpAddr, SocketAddr, TcpListener, TcpStream};
+use std::time::{Duration, Instant};
+
+const PAGE: &str = include_str!("playground.html");
+
+/// Wall-clock limit per snippet, on top of `wasm::playground_limits`.
+pub const RUN_TIMEOUT: Duration = Duration::from_secs(5);
+/// Largest snippet accepted, in bytes.
+pub const MAX_SNIPPET_BYTES: usize = 64 * 1024;
+/// Stack for the thread each snippet runs on (the tree-walker recurses).
+const RUN_STACK_SIZE: usize = 64 * 1024 * 1024;
+
+const WS_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+/// Bind `addr` and serve until the process is stopped.
+pub fn serve(addr: &str) -> io::Result<()> {
+    let listener = TcpListener::bind(addr)?;
+    println!("Axiom playground on http://{}  (Ctrl-C to stop)", listener.local_addr()?);
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else { continue };
+        std::thread::spawn(move || {
+            if let Err(e) = handle_connection(stream) {
+                eprintln!("playground: {}", e);
+            }
+        });
+    }
+    Ok(())
+}
+
+fn handle_connection(stream: TcpStream) -> io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let path = request_line.split_whitespace().nth(1).unwrap_or("/").to_string();
+
+    let mut ws_key = None;
+    let mut origin = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("sec-websocket-key") {
+                ws_key = Some(value.trim().to_string());
+            } else if name.trim().eq_ignore_ascii_case("origin") {
+                origin = Some(value.trim().to_string());
+            }
+        }
+    }
+
+    let mut stream = stream;
+    match (path.as_str(), ws_key) {
+        ("/ws", Some(_)) if !origin.as_deref().is_some_and(|o| is_own_origin(o, stream.local_addr())) => {
+            respond(&mut stream, "403 Forbidden", "text/plain", "cross-origin websocket refused\n")
+        }
+        ("/ws", Some(key)) => {
+            write!(
+                stream,
+                "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+                accept_key(&key),
+            )?;
+            serve_socket(reader, stream)
+        }
+        ("/", _) | ("/index.html", _) => respond(&mut stream, "200 OK", "text/html; charset=utf-8", PAGE),
+        _ => respond(&mut stream, "404 Not Found", "text/plain", "not found\n"),
+    }
+}
+
+/// Whether `origin` is the page served from `local`, the address this
+/// connection reached: the same port, and that address or `localhost` for
+/// a loopback one.
+fn is_own_origin(origin: &str, local: io::Result<SocketAddr>) -> bool {
+    let (Some(authority), Ok(local)) = (origin.strip_prefix("http://"), local) else { return false };
+    let host = match authority.strip_suffix(&format!(":{}", local.port())) {
+        Some(host) => host,
+        None if local.port() == 80 => authority,
+        None => return false,
+    };
+    let ip = host.trim_start_matches('[').trim_end_matches(']').parse::<IpAddr>();
+    ip == Ok(local.ip()) || (local.ip().is_loopback() && host.eq_ignore_ascii_case("localhost"))
+}
+
+fn respond(stream: &mut TcpStream, status: &str, content_type: &str, body: &str) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status, content_type, body.len(), body,
+    )
+}
+
+/// Read snippets until the client closes, running each to completion.
+fn serve_socket(mut reader: impl Read, mut stream: TcpStream) -> io::Result<()> {
+    loop {
+        let Some((opcode, payload)) = read_frame(&mut reader)? else { return Ok(()) };
+        match opcode {
+            OP_TEXT => {
+                let src = String::from_utf8_lossy(&payload).into_owned();
+                run_snippet(src, &stream)?;
+            }
+            OP_PING => write_frame(&mut stream, OP_PONG, &payload)?,
+            OP_CLOSE => return write_frame(&mut stream, OP_CLOSE, &[]),
+            _ => {}
+        }
+    }
+}
+
+fn run_snippet(src: String, stream: &TcpStream) -> io::Result<()> {
+    let started = Instant::now();
+    let mut out = stream.try_clone()?;
+    if src.len() > MAX_SNIPPET_BYTES {
+        let msg = format!("snippet is {} bytes; the playground accepts at most {}", src.len(), MAX_SNIPPET_BYTES);
+        send(&mut out, "error", &msg)?;
+        return send_done(&mut out, started);
+    }
+
+    let mut sink_stream = out.try_clone()?;
+    let error = std::thread::Builder::new()
+        .name("axiom-playground-run".into())
+        .stack_size(RUN_STACK_SIZE)
+        .spawn(move || {
+            let engine = DiagnosticEngine::new("playground.ax", src.as_str());
+            let items = match Parser::new(&src, 0).parse() {
+                Ok(items) => items,
+                Err(e) => return Some(engine.format_diagnostic_plain(&engine.from_parser(&e))),
+            };
+            let mut rt = Runtime::new();
+            rt.set_limits(crate::wasm::playground_limits().with_timeout(RUN_TIMEOUT));
+            rt.set_capabilities(Capabilities::pure());
+            let result = with_output_sink(move |line| { let _ = send(&mut sink_stream, "out", line); }, || rt.run(items));
+            result.err().map(|e| engine.format_diagnostic_plain(&engine.from_runtime(&e)))
+        })?
+        .join()
+        .unwrap_or_else(|_| Some("internal error: the runtime panicked".to_string()));
+
+    if let Some(text) = error {
+        send(&mut out, "error", &text)?;
+    }
+    send_done(&mut out, started)
+}
+
+fn send(stream: &mut TcpStream, kind: &str, text: &str) -> io::Result<()> {
+    let msg = serde_json::json!({ "type": kind, "text": text });
+    write_frame(stream, OP_TEXT, msg.to_string().as_bytes())
+}
+
+fn send_done(stream: &mut TcpStream, started: Instant) -> io::Result<()> {
+    let msg = serde_json::json!({ "type": "done", "ms": started.elapsed().as_millis() as u64 });
+    write_frame(stream, OP_TEXT, msg.to_string().as_bytes())
+}
+
+// ---------------------------------------------------------------------------
+// WebSocket framing (RFC 6455) — just enough for one browser client
+// ---------------------------------------------------------------------------
+
+const OP_TEXT: u8 = 0x1;
+const OP_CLOSE: u8 = 0x8;
+const OP_PING: u8 = 0x9;
+const OP_PONG: u8 = 0xA;
+
+/// Read one frame, reassembling continuations. `None` on a clean EOF.
+fn read_frame(reader: &mut impl Read) -> io::Result<Option<(u8, Vec<u8>)>> {
+    let mut opcode = None;
+    let mut payload = Vec::new();
+    loop {
+        let mut head = [0u8; 2];
+        match reader.read_exact(&mut head) {
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            r => r?,
+        }
+        let fin = head[0] & 0x80 != 0;
+        let op = head[0] & 0x0F;
+        let masked = head[1] & 0x80 != 0;
+        let len = match head[1] & 0x7F {
+            126 => { let mut b = [0u8; 2]; reader.read_exact(&mut b)?; u16::from_be_bytes(b) as usize }
+            127 => { let mut b = [0u8; 8]; reader.read_exact(&mut b)?; u64::from_be_bytes(b) as usize }
+            n => n as usize,
+        };
+        if payload.len().saturating_add(len) > MAX_SNIPPET_BYTES * 2 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "frame too large"));
+        }
+        let mut mask = [0u8; 4];
+        if masked {
+            reader.read_exact(&mut mask)?;
+        }
+        let start = payload.len();
+        payload.resize(start + len, 0);
+        reader.read_exact(&mut payload[start..])?;
+        if masked {
+            for (i, b) in payload[start..].iter_mut().enumerate() {
+                *b ^= mask[i % 4];
+            }
+        }
+        // Control frames can arrive between fragments; they are never fragmented
+        if op >= OP_CLOSE {
+            return Ok(Some((op, payload.split_off(start))));
+        }
+        if op != 0 {
+            opcode = Some(op);
+        }
+        if fin {
+            return Ok(opcode.map(|op| (op, payload)));
+        }
+    }
+}
+
+fn write_frame(stream: &mut impl Write, opcode: u8, payload: &[u8]) -> io::Result<()> {
+    let mut frame = Vec::with_capacity(payload.len() + 10);
+    frame.push(0x80 | opcode);
+    match payload.len() {
+        n if n < 126 => frame.push(n as u8),
+        n if n <= u16::MAX as usize => { frame.push(126); frame.extend_from_slice(&(n as u16).to_be_bytes()); }
+        n => { frame.push(127); frame.extend_from_slice(&(n as u64).to_be_bytes()); }
+    }
+    frame.extend_from_slice(payload);
+    stream.write_all(&frame)
+}
+
+fn accept_key(key: &str) -> String {
+    use base64::Engine;
+    base64::engine::general_purpose::STANDARD.encode(sha1(format!("{}{}", key, WS_GUID).as_bytes()))
+}
+
+/// SHA-1, needed only for the WebSocket handshake.
+fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut h: [u32; 5] = [0x67452301, 0xEFCDAB89, 0x98BADCFE, 0x10325476, 0xC3D2E1F0];
+    let mut msg = data.to_vec();
+    msg.push(0x80);
+    while msg.len() % 64 != 56 {
+        msg.push(0);
+    }
+    msg.extend_from_slice(&((data.len() as u64) * 8).to_be_bytes());
+
+    for chunk in msg.chunks(64) {
+        let mut w = [0u32; 80];
+        for (i, word) in chunk.chunks(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..80 {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+        let [mut a, mut b, mut c, mut d, mut e] = h;
+        for (i, wi) in w.iter().enumerate() {
+            let (f, k) = match i {
+                0..=19 => ((b & c) | (!b & d), 0x5A827999),
+                20..=39 => (b ^ c ^ d, 0x6ED9EBA1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1BBCDC),
+                _ => (b ^ c ^ d, 0xCA62C1D6),
+            };
+            let t = a.rotate_left(5).wrapping_add(f).wrapping_add(e).wrapping_add(k).wrapping_add(*wi);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = t;
+        }
+        for (hi, v) in h.iter_mut().zip([a, b, c, d, e]) {
+            *hi = hi.wrapping_add(v);
+        }
+    }
+
+    let mut out = [0u8; 20];
+    for (i, v) in h.iter().enumerate() {
+        out[i * 4..i * 4 + 4].copy_from_slice(&v.to_be_bytes());
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_websocket_accept_key() {
+        // Example handshake from RFC 6455 §1.3
+        assert_eq!(accept_key("dGhlIHNhbXBsZSBub25jZQ=="), "s3pPLMBiTxaQ9kYGzzhZRbK+xOo=");
+    }
+
+    #[test]
+    fn test_frame_round_trip() {
+        let mut buf = Vec::new();
+        write_frame(&mut buf, OP_TEXT, &[b'x'; 300]).unwrap();
+        let (op, payload) = read_frame(&mut buf.as_slice()).unwrap().unwrap();
+        assert_eq!(op, OP_TEXT);
+        assert_eq!(payload.len(), 300);
+        assert!(read_frame(&mut &[][..]).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_only_the_page_origin_may_upgrade() {
+        let local = || Ok("127.0.0.1:8000".parse().unwrap());
+        assert!(is_own_origin("http://127.0.0.1:8000", local()));
+        assert!(is_own_origin("http://localhost:8000", local()));
+        assert!(!is_own_origin("http://localhost:9000", local()));
+        assert!(!is_own_origin("http://evil.example:8000", local()));
+        assert!(!is_own_origin("https://127.0.0.1:8000", local()));
+        assert!(!is_own_origin("null", local()));
+        assert!(is_own_origin("http://[::1]", Ok("[::1]:80".parse().unwrap())));
+        assert!(!is_own_origin("http://localhost:8000", Ok("192.168.1.5:8000".parse().unwrap())));
+    }
+}
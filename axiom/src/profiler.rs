@@ -1,14 +1,16 @@
-/// Profiling Infrastructure
-///
-/// Provides:
-///   1. Opcode execution frequency counters (per-instruction hot-spot detection)
-///   2. Function call counters + call graph (top-N hot functions)
-///   3. Dispatch cycle measurement (estimated via instruction counts)
-///   4. Allocation rate tracking (bytes/sec)
-///   5. Hot loop detection (back-edge counter, triggers trace formation signal)
-///   6. Branch misprediction measurement hooks (via perf-event-style counters)
-///   7. Flame graph export (folded stack format for inferno/speedscope)
-///   8. Real-time performance dashboard (printed to stderr)
+//! Profiling Infrastructure
+//!
+//! Provides:
+//!   1. Opcode execution frequency counters (per-instruction hot-spot detection)
+//!   2. Function call counters + call graph (top-N hot functions), fed by
+//!      the runtime's call hooks
+//!   3. Dispatch cycle measurement (estimated via instruction counts)
+//!   4. Allocation rate tracking (bytes/sec)
+//!   5. Hot loop detection (back-edge counter, triggers trace formation signal)
+//!   6. Branch misprediction measurement hooks (via perf-event-style counters)
+//!   7. Flame graph export (folded stack format for inferno/speedscope)
+//!   8. Real-time performance dashboard (printed to stderr)
+//!   9. Heap snapshots: allocation sites and live objects by type
 
 use std::collections::HashMap;
 use std::sync::atomic::{AtomicU64, Ordering};
@@ -16,6 +18,12 @@ use std::time::Instant;
 use parking_lot::Mutex;
 
 use crate::bytecode::Op;
+use crate::core::value::AxValue;
+use crate::hooks::Phase;
+use crate::inline_cache::IcReport;
+use crate::runtime::Runtime;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 
 // ---------------------------------------------------------------------------
 // Profiler configuration
@@ -54,7 +62,7 @@ impl Default for ProfilerConfig {
 // Opcode frequency table
 // ---------------------------------------------------------------------------
 
-/// Per-opcode execution counter (75 opcodes max, indexed by Op u8 value)
+/// Per-opcode execution counter (76 opcodes, indexed by Op u8 value)
 pub struct OpcodeCounters {
     counts: [AtomicU64; 128],
 }
@@ -88,7 +96,7 @@ impl OpcodeCounters {
             return;
         }
 
-        let mut entries: Vec<(Op, u64)> = (0..75u8).filter_map(|i| {
+        let mut entries: Vec<(Op, u64)> = (0..76u8).filter_map(|i| {
             let op: Op = unsafe { std::mem::transmute(i) };
             let count = self.counts[i as usize].load(Ordering::Relaxed);
             if count > 0 { Some((op, count)) } else { None }
@@ -122,8 +130,8 @@ pub struct FuncProfile {
 pub struct CallTracker {
     /// function_name → profile
     profiles: Mutex<HashMap<String, FuncProfile>>,
-    /// Call stack for timing
-    call_stack: Mutex<Vec<(String, Instant)>>,
+    /// Call stack for timing: name, entry time, time spent in callees
+    call_stack: Mutex<Vec<(String, Instant, u64)>>,
 }
 
 impl CallTracker {
@@ -134,6 +142,15 @@ impl CallTracker {
         }
     }
 
+    /// Track the calls `runtime` makes from now on, through its call hooks.
+    pub fn attach(self: &Arc<Self>, runtime: &mut Runtime) {
+        let tracker = Arc::clone(self);
+        runtime.on_call(move |event| match event.phase {
+            Phase::Before => tracker.enter(event.name),
+            Phase::After => tracker.exit(event.name),
+        });
+    }
+
     pub fn enter(&self, name: &str) {
         {
             let mut profiles = self.profiles.lock();
@@ -142,35 +159,49 @@ impl CallTracker {
             });
             p.calls += 1;
         }
-        self.call_stack.lock().push((name.to_string(), Instant::now()));
+        self.call_stack.lock().push((name.to_string(), Instant::now(), 0));
     }
 
     pub fn exit(&self, _name: &str) {
         let mut stack = self.call_stack.lock();
-        if let Some((fname, enter_time)) = stack.pop() {
+        if let Some((fname, enter_time, callees_ns)) = stack.pop() {
             let elapsed = enter_time.elapsed().as_nanos() as u64;
+            if let Some(caller) = stack.last_mut() {
+                caller.2 += elapsed;
+            }
             let mut profiles = self.profiles.lock();
             if let Some(p) = profiles.get_mut(&fname) {
-                p.self_time_ns += elapsed;
+                p.self_time_ns += elapsed.saturating_sub(callees_ns);
                 p.total_time_ns += elapsed;
             }
         }
     }
 
-    pub fn print_top(&self, n: usize) {
+    /// The `n` functions with the most total time, as a table.
+    pub fn render(&self, n: usize) -> String {
         let profiles = self.profiles.lock();
         let mut entries: Vec<&FuncProfile> = profiles.values().collect();
-        entries.sort_by(|a, b| b.total_time_ns.cmp(&a.total_time_ns));
+        entries.sort_by(|a, b| b.total_time_ns.cmp(&a.total_time_ns).then_with(|| a.name.cmp(&b.name)));
 
-        println!("=== Hot Functions (top {}) ===", n);
-        println!("  {:<30} {:>10}  {:>12}  {:>12}", "Function", "Calls", "Self(µs)", "Total(µs)");
-        println!("  {}", "-".repeat(70));
+        let mut out = format!("=== Hot Functions (top {}) ===\n", n);
+        out += &format!("  {:<30} {:>10}  {:>12}  {:>12}\n", "Function", "Calls", "Self(µs)", "Total(µs)");
+        out += &format!("  {}\n", "-".repeat(70));
         for p in entries.iter().take(n) {
-            println!("  {:<30} {:>10}  {:>12.1}  {:>12.1}",
+            out += &format!("  {:<30} {:>10}  {:>12.1}  {:>12.1}\n",
                 p.name, p.calls,
                 p.self_time_ns as f64 / 1000.0,
                 p.total_time_ns as f64 / 1000.0);
         }
+        out
+    }
+
+    pub fn print_top(&self, n: usize) {
+        print!("{}", self.render(n));
+    }
+
+    /// Calls so far to `name`, with its time.
+    pub fn profile(&self, name: &str) -> Option<FuncProfile> {
+        self.profiles.lock().get(name).cloned()
     }
 }
 
@@ -269,6 +300,171 @@ impl AllocTracker {
     }
 }
 
+// ---------------------------------------------------------------------------
+// Heap snapshot (axiom run --heap-profile / axiom heap-report)
+// ---------------------------------------------------------------------------
+
+/// Allocations attributed to one (site, function) pair.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AllocSite {
+    /// What allocated: `concat`, `interpolate`, `list`, `list.push`, …
+    pub site: String,
+    /// Innermost Axiom function at the time, or `<main>`.
+    pub function: String,
+    pub count: u64,
+    pub bytes: u64,
+}
+
+/// Values of one type reachable when the snapshot was taken.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LiveType {
+    /// `Str`, `Lst`, `Map`, or the class name for instances.
+    pub type_name: String,
+    pub count: u64,
+    /// Approximate payload bytes (string data, list and map slots).
+    pub bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HeapSnapshot {
+    pub script: String,
+    pub total_allocs: u64,
+    pub total_bytes: u64,
+    /// Peak resident set size of the process, where the OS reports it.
+    pub peak_rss_bytes: Option<u64>,
+    /// Sorted by bytes, largest first.
+    pub sites: Vec<AllocSite>,
+    /// Sorted by bytes, largest first.
+    pub live: Vec<LiveType>,
+}
+
+impl HeapSnapshot {
+    /// Plain-text report: totals, then the top `n` sites and live types.
+    pub fn render(&self, n: usize) -> String {
+        let kb = |b: u64| format!("{:.1} KB", b as f64 / 1024.0);
+        let mut out = String::new();
+        out.push_str(&format!("=== Heap profile: {} ===\n", self.script));
+        out.push_str(&format!("  Allocations:  {} ({})\n", self.total_allocs, kb(self.total_bytes)));
+        match self.peak_rss_bytes {
+            Some(peak) => out.push_str(&format!("  Peak RSS:     {}\n", kb(peak))),
+            None => out.push_str("  Peak RSS:     n/a\n"),
+        }
+
+        out.push_str("\n  Allocation sites\n");
+        out.push_str(&format!("  {:<14} {:<24} {:>10} {:>14}\n", "site", "function", "count", "bytes"));
+        for s in self.sites.iter().take(n) {
+            out.push_str(&format!("  {:<14} {:<24} {:>10} {:>14}\n", s.site, s.function, s.count, kb(s.bytes)));
+        }
+
+        out.push_str("\n  Live objects\n");
+        out.push_str(&format!("  {:<39} {:>10} {:>14}\n", "type", "count", "bytes"));
+        for t in self.live.iter().take(n) {
+            out.push_str(&format!("  {:<39} {:>10} {:>14}\n", t.type_name, t.count, kb(t.bytes)));
+        }
+        out
+    }
+}
+
+/// Collects allocation sites while a script runs.
+#[derive(Debug, Default)]
+pub struct HeapProfiler {
+    sites: HashMap<(&'static str, String), (u64, u64)>,
+}
+
+impl HeapProfiler {
+    pub fn record(&mut self, site: &'static str, function: &str, bytes: usize) {
+        let entry = self.sites.entry((site, function.to_string())).or_default();
+        entry.0 += 1;
+        entry.1 += bytes as u64;
+    }
+
+    /// Build the snapshot, counting live values reachable from `roots`.
+    pub fn snapshot<'a>(&self, script: &str, roots: impl IntoIterator<Item = &'a AxValue>) -> HeapSnapshot {
+        let mut sites: Vec<AllocSite> = self.sites.iter()
+            .map(|((site, function), (count, bytes))| AllocSite {
+                site: site.to_string(), function: function.clone(), count: *count, bytes: *bytes,
+            })
+            .collect();
+        sites.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.site.cmp(&b.site)));
+        HeapSnapshot {
+            script: script.to_string(),
+            total_allocs: sites.iter().map(|s| s.count).sum(),
+            total_bytes: sites.iter().map(|s| s.bytes).sum(),
+            peak_rss_bytes: peak_rss_bytes(),
+            sites,
+            live: count_live(roots),
+        }
+    }
+}
+
+/// Walk everything reachable from `roots` once (shared values counted once)
+/// and tally it by type.
+pub fn count_live<'a>(roots: impl IntoIterator<Item = &'a AxValue>) -> Vec<LiveType> {
+    use std::collections::HashSet;
+    let slot = std::mem::size_of::<AxValue>() as u64;
+    let mut seen: HashSet<usize> = HashSet::new();
+    let mut tally: HashMap<String, (u64, u64)> = HashMap::new();
+    let mut stack: Vec<AxValue> = roots.into_iter().cloned().collect();
+    while let Some(value) = stack.pop() {
+        let (name, bytes) = match &value {
+            AxValue::Str(s) => ("Str".to_string(), s.len() as u64),
+            AxValue::Lst(list) => {
+                if !seen.insert(Arc::as_ptr(list) as *const () as usize) { continue; }
+                let items = list.read().unwrap();
+                stack.extend(items.iter().cloned());
+                ("Lst".to_string(), items.len() as u64 * slot)
+            }
+            AxValue::Map(map) => {
+                if !seen.insert(Arc::as_ptr(map) as *const () as usize) { continue; }
+                let mut bytes = 0;
+                for entry in map.iter() {
+                    bytes += entry.key().len() as u64 + slot;
+                    stack.push(entry.value().clone());
+                }
+                ("Map".to_string(), bytes)
+            }
+            AxValue::Instance(inst) => {
+                if !seen.insert(Arc::as_ptr(inst) as *const () as usize) { continue; }
+                let inst = inst.read().unwrap();
+                let mut bytes = 0;
+                for entry in inst.fields.iter() {
+                    bytes += entry.key().len() as u64 + slot;
+                    stack.push(entry.value().clone());
+                }
+                (inst.class.name.clone(), bytes)
+            }
+            AxValue::Obj(obj) => {
+                for entry in obj.fields.iter() {
+                    stack.push(entry.value().clone());
+                }
+                (obj.type_name.clone(), obj.fields.len() as u64 * slot)
+            }
+            AxValue::EnumVariant(name, payload) => {
+                stack.push((**payload).clone());
+                (name.to_string(), 0)
+            }
+            // Scalars, functions and nil live inline or in the program itself
+            _ => continue,
+        };
+        let entry = tally.entry(name).or_default();
+        entry.0 += 1;
+        entry.1 += bytes;
+    }
+    let mut live: Vec<LiveType> = tally.into_iter()
+        .map(|(type_name, (count, bytes))| LiveType { type_name, count, bytes })
+        .collect();
+    live.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.type_name.cmp(&b.type_name)));
+    live
+}
+
+/// Peak resident set size (`VmHWM`), Linux only.
+pub fn peak_rss_bytes() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|l| l.starts_with("VmHWM:"))?;
+    let kb: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kb * 1024)
+}
+
 // ---------------------------------------------------------------------------
 // Flame graph exporter (folded stacks format)
 // ---------------------------------------------------------------------------
@@ -331,6 +527,8 @@ pub struct Profiler {
     /// Estimated dispatch cycles (instruction_count * avg_cycles_per_dispatch)
     pub instruction_count: AtomicU64,
     pub branch_misses: AtomicU64,
+    /// Inline-cache state handed over at the end of a run (`Runtime::ic_report`).
+    pub ic_report: Mutex<Option<IcReport>>,
 }
 
 impl Profiler {
@@ -346,6 +544,7 @@ impl Profiler {
             start_time: Instant::now(),
             instruction_count: AtomicU64::new(0),
             branch_misses:     AtomicU64::new(0),
+            ic_report:         Mutex::new(None),
         }
     }
 
@@ -428,6 +627,11 @@ impl Profiler {
             println!();
         }
 
+        if let Some(report) = self.ic_report.lock().as_ref() {
+            print!("{}", report.render(None, 10));
+            println!();
+        }
+
         if self.config.flame_graph {
             self.flame.export(self.config.flame_graph_path.as_deref());
         }
@@ -445,3 +649,44 @@ impl Profiler {
             self.hot_loops.hot_loops.lock().len());
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::runtime::Runtime;
+    use crate::Parser;
+
+    #[test]
+    fn test_heap_snapshot_sites_and_live_objects() {
+        let src = "fn build(n) {\n  let acc = []\n  let i = 0\n  while i < n { acc.push(\"x\" + str(i)); i = i + 1 }\n  return acc\n}\nlet keep = build(10)\nlet alias = keep";
+        let mut rt = Runtime::new();
+        rt.enable_heap_profile();
+        rt.run(Parser::new(src, 0).parse().unwrap()).unwrap();
+        let snap = rt.take_heap_snapshot().unwrap();
+
+        let site = |name: &str| snap.sites.iter().find(|s| s.site == name).unwrap();
+        assert_eq!((site("list.push").function.as_str(), site("list.push").count), ("build", 10));
+        assert_eq!(site("concat").count, 10);
+        assert_eq!(snap.total_allocs, 21);
+
+        // `alias` shares the list, so it is counted once
+        let live = |name: &str| snap.live.iter().find(|t| t.type_name == name).map(|t| t.count);
+        assert_eq!(live("Lst"), Some(1));
+        assert_eq!(live("Str"), Some(10));
+        assert!(snap.render(5).contains("list.push"));
+        assert!(rt.take_heap_snapshot().is_none());
+    }
+
+    #[test]
+    fn test_call_tracker_counts_calls_through_hooks() {
+        let src = "fn leaf(x) { ret x + 1 }\nfn outer(n) {\n  let i = 0\n  while i < n { leaf(i); i = i + 1 }\n  ret i\n}\nouter(3)\nouter(2)";
+        let tracker = std::sync::Arc::new(super::CallTracker::new());
+        let mut rt = Runtime::new();
+        tracker.attach(&mut rt);
+        rt.run(Parser::new(src, 0).parse().unwrap()).unwrap();
+        let (outer, leaf) = (tracker.profile("outer").unwrap(), tracker.profile("leaf").unwrap());
+        assert_eq!((outer.calls, leaf.calls), (2, 5));
+        // Time in `leaf` counts toward `outer`'s total, not its self time
+        assert!(outer.self_time_ns + leaf.total_time_ns <= outer.total_time_ns + 1000);
+        assert!(tracker.render(1).contains("outer"));
+    }
+}
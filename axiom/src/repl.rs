@@ -0,0 +1,303 @@
+//! `axiom repl` — an interactive session.
+//!
+//! Every entry runs in one `Session` on the tree-walker, so its `let`s,
+//! functions, classes and loaded modules stay defined for the entries after
+//! it. An entry that ends inside an unfinished construct (an open `{`)
+//! carries on at the `...` prompt, and the value of a trailing expression
+//! is printed.
+//!
+//! Line editing comes from rustyline. History is kept in ~/.axiom/history
+//! across sessions, and Tab completes globals, the session's bindings and
+//! intrinsic functions (`str.up` → `str.upper`). Meta-commands:
+//!
+//!   :save FILE  write the definitions entered so far as a runnable script
+//!   :load FILE  run a script in the session, e.g. one written by `:save`
+//!   :help       list the meta-commands
+//!   :quit       leave (as does Ctrl-D)
+
+use std::path::{Path, PathBuf};
+
+use rustyline::completion::Completer;
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, Helper};
+
+use crate::ast::{Item, Stmt};
+use crate::core::value::AxValue;
+use crate::diagnostics::DiagnosticEngine;
+use crate::runtime::{Runtime, Session};
+use crate::Parser;
+
+const HELP: &str = "\
+:save FILE  write the definitions entered so far as a runnable script
+:load FILE  run a script in the session
+:help       show this list
+:quit       leave (or Ctrl-D)";
+
+/// What running one entry did.
+pub enum Outcome {
+    /// It ran; the value of its trailing expression, nil if it has none
+    Value(AxValue),
+    /// It stops inside an unfinished construct; read another line
+    Incomplete,
+    /// It did not parse or failed at runtime: the rendered diagnostic
+    Failed(String),
+}
+
+/// A runtime and the session its entries share, plus the source of every
+/// entry that defined something, for `:save`.
+pub struct Repl {
+    runtime: Runtime,
+    session: Session,
+    definitions: Vec<String>,
+    entries: usize,
+}
+
+impl Default for Repl {
+    fn default() -> Self { Self::new() }
+}
+
+impl Repl {
+    pub fn new() -> Self {
+        let mut runtime = Runtime::new();
+        runtime.set_engine(crate::runtime::Engine::Interp);
+        Repl { runtime, session: Session::default(), definitions: Vec::new(), entries: 0 }
+    }
+
+    /// Run one entry.
+    pub fn eval(&mut self, source: &str) -> Outcome {
+        let name = format!("<repl {}>", self.entries + 1);
+        let outcome = self.run_source(Path::new(&name), source);
+        if !matches!(outcome, Outcome::Incomplete) {
+            self.entries += 1;
+        }
+        outcome
+    }
+
+    /// Parse and run `source`, registered under `path` so an error raised
+    /// in a function it defines points at it even from a later entry.
+    fn run_source(&mut self, path: &Path, source: &str) -> Outcome {
+        let source_id = crate::loader::register_source(path, source);
+        let engine = DiagnosticEngine::new(path.display().to_string(), source);
+        let items = match Parser::new(source, source_id).parse() {
+            Ok(items) => items,
+            Err(e) if e.at_eof() => return Outcome::Incomplete,
+            Err(e) => return Outcome::Failed(engine.format_diagnostic(&engine.from_parser(&e))),
+        };
+        let defines = defines_something(&items);
+        match self.runtime.run_in(items, &mut self.session) {
+            Ok(value) => {
+                if defines {
+                    self.definitions.push(source.trim().to_string());
+                }
+                Outcome::Value(value)
+            }
+            Err(e) => Outcome::Failed(engine.format_diagnostic(&engine.from_runtime(&e))),
+        }
+    }
+
+    /// The entries that defined something, in order, as one script.
+    pub fn definitions(&self) -> String {
+        let mut script = self.definitions.join("\n");
+        script.push('\n');
+        script
+    }
+
+    /// Run a meta-command (the text after `:`), returning what to print.
+    pub fn command(&mut self, command: &str) -> Result<String, String> {
+        let (name, arg) = command.trim().split_once(char::is_whitespace).unwrap_or((command.trim(), ""));
+        let arg = arg.trim();
+        match (name, arg.is_empty()) {
+            ("help" | "h", _) => Ok(HELP.to_string()),
+            ("save", false) => {
+                std::fs::write(arg, self.definitions()).map_err(|e| format!("Cannot write '{}': {}", arg, e))?;
+                Ok(format!("Saved {} definitions to {}", self.definitions.len(), arg))
+            }
+            ("load", false) => {
+                let source = std::fs::read_to_string(arg).map_err(|e| format!("Cannot read '{}': {}", arg, e))?;
+                let path = Path::new(arg).canonicalize().unwrap_or_else(|_| PathBuf::from(arg));
+                match self.run_source(&path, &source) {
+                    Outcome::Value(_) => Ok(format!("Loaded {}", arg)),
+                    Outcome::Incomplete => Err(format!("'{}' ends early", arg)),
+                    Outcome::Failed(report) => Err(report),
+                }
+            }
+            ("save" | "load", true) => Err(format!(":{} needs a file name", name)),
+            _ => Err(format!("Unknown command ':{}' (:help lists them)", name)),
+        }
+    }
+
+    /// Everything Tab can complete to: globals, the session's bindings and
+    /// each intrinsic as `module.name`, sorted.
+    pub fn completions(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.runtime.globals.iter().map(|(name, _)| name.clone())
+            .chain(self.session.names())
+            .chain(crate::intrinsics::INTRINSIC_SIGS.iter().map(|s| format!("{}.{}", s.module, s.name)))
+            .filter(|name| !name.starts_with("__"))
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+}
+
+/// Whether an entry is worth saving: anything but expressions and `out`s,
+/// which only show something.
+fn defines_something(items: &[Item]) -> bool {
+    items.iter().any(|item| !matches!(item, Item::Statement(Stmt::Expr(_) | Stmt::Out { .. })))
+}
+
+/// Where history is kept between sessions.
+pub fn history_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|h| h.join(".axiom").join("history"))
+}
+
+/// Tab completion over `Repl::completions`, refreshed after each entry.
+struct Completion {
+    names: Vec<String>,
+}
+
+impl Completer for Completion {
+    type Candidate = String;
+
+    fn complete(&self, line: &str, pos: usize, _ctx: &Context<'_>) -> rustyline::Result<(usize, Vec<String>)> {
+        let is_word = |c: char| c.is_alphanumeric() || c == '_' || c == '.';
+        let start = line[..pos].char_indices().rev()
+            .find(|&(_, c)| !is_word(c))
+            .map_or(0, |(i, c)| i + c.len_utf8());
+        let word = &line[start..pos];
+        if word.is_empty() {
+            return Ok((pos, Vec::new()));
+        }
+        Ok((start, self.names.iter().filter(|name| name.starts_with(word)).cloned().collect()))
+    }
+}
+
+impl Hinter for Completion {
+    type Hint = String;
+}
+
+impl Highlighter for Completion {}
+impl Validator for Completion {}
+impl Helper for Completion {}
+
+/// Read, run and print until `:quit` or end of input.
+pub fn run() -> rustyline::Result<()> {
+    let mut editor: Editor<Completion, DefaultHistory> = Editor::new()?;
+    let history = history_path();
+    if let Some(path) = &history {
+        // No history yet is fine
+        let _ = editor.load_history(path);
+    }
+    let mut repl = Repl::new();
+    editor.set_helper(Some(Completion { names: repl.completions() }));
+    println!("Axiom {} — :help for commands, Ctrl-D to leave", env!("CARGO_PKG_VERSION"));
+
+    let mut pending = String::new();
+    loop {
+        let prompt = if pending.is_empty() { "ax> " } else { "... " };
+        let line = match editor.readline(prompt) {
+            Ok(line) => line,
+            // Ctrl-C drops a half-typed entry
+            Err(ReadlineError::Interrupted) => { pending.clear(); continue; }
+            Err(ReadlineError::Eof) => break,
+            Err(e) => return Err(e),
+        };
+        if pending.is_empty() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            if let Some(command) = line.trim().strip_prefix(':') {
+                let _ = editor.add_history_entry(line.trim());
+                if matches!(command.trim(), "quit" | "q" | "exit") {
+                    break;
+                }
+                match repl.command(command) {
+                    Ok(message) => println!("{}", message),
+                    Err(message) => eprintln!("{}", message),
+                }
+                if let Some(helper) = editor.helper_mut() {
+                    helper.names = repl.completions();
+                }
+                continue;
+            }
+        }
+        pending.push_str(&line);
+        pending.push('\n');
+        match repl.eval(&pending) {
+            Outcome::Incomplete => continue,
+            Outcome::Value(value) => {
+                if !matches!(value, AxValue::Nil) {
+                    println!("{}", value.display());
+                }
+            }
+            Outcome::Failed(report) => eprint!("{}", report),
+        }
+        let _ = editor.add_history_entry(pending.trim_end());
+        pending.clear();
+        if let Some(helper) = editor.helper_mut() {
+            helper.names = repl.completions();
+        }
+    }
+
+    if let Some(path) = &history {
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        editor.save_history(path)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustyline::history::DefaultHistory;
+
+    fn value(outcome: Outcome) -> String {
+        match outcome {
+            Outcome::Value(v) => v.display(),
+            Outcome::Incomplete => "<incomplete>".into(),
+            Outcome::Failed(report) => panic!("{}", report),
+        }
+    }
+
+    #[test]
+    fn test_entries_share_a_session() {
+        let mut repl = Repl::new();
+        value(repl.eval("let x = 3"));
+        assert!(matches!(repl.eval("fun sq(n) {\n"), Outcome::Incomplete));
+        value(repl.eval("fun sq(n) {\n  ret n * n\n}\n"));
+        assert_eq!(value(repl.eval("sq(x) + 1")), "10");
+        assert!(matches!(repl.eval("nope + 1"), Outcome::Failed(_)));
+        assert!(repl.completions().iter().any(|n| n == "sq"));
+    }
+
+    #[test]
+    fn test_save_and_load_a_session() {
+        let mut repl = Repl::new();
+        value(repl.eval("let x = 4"));
+        value(repl.eval("fun sq(n) { ret n * n }"));
+        value(repl.eval("sq(x)"));
+        assert_eq!(repl.definitions(), "let x = 4\nfun sq(n) { ret n * n }\n");
+
+        let path = std::env::temp_dir().join(format!("axiom-repl-{}.ax", std::process::id()));
+        repl.command(&format!("save {}", path.display())).unwrap();
+        let mut restored = Repl::new();
+        restored.command(&format!("load {}", path.display())).unwrap();
+        assert_eq!(value(restored.eval("sq(x)")), "16");
+        std::fs::remove_file(&path).unwrap();
+        assert!(restored.command("save").is_err());
+    }
+
+    #[test]
+    fn test_completes_intrinsics() {
+        let completion = Completion { names: Repl::new().completions() };
+        let history = DefaultHistory::new();
+        let (start, found) = completion.complete("out str.up", 10, &Context::new(&history)).unwrap();
+        assert_eq!((start, found), (4, vec!["str.upper".to_string()]));
+    }
+}
@@ -0,0 +1,362 @@
+//! Local calls between scripts (the `rpc` module)
+//!
+//! A script serving `rpc.listen(name, handler)` owns an endpoint named after
+//! `name` in `$XDG_RUNTIME_DIR/axiom-rpc` (a per-user directory under the
+//! temp directory when there is no runtime directory): a unix socket
+//! `<name>.sock`, or elsewhere a loopback TCP port written to `<name>.port`.
+//!
+//! Each `rpc.call` opens one connection, sends one JSON line
+//! `{"method": "...", "args": [...]}` and reads one line back, either
+//! `{"result": ...}` or `{"error": "..."}`. Values are encoded the way
+//! `jsn.from_value` encodes them.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+/// Requests and replies longer than this are refused.
+pub const MAX_MESSAGE_BYTES: u64 = 16 * 1024 * 1024;
+
+/// How long a connected client has to send its request.
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How often a waiting server checks for new connections.
+const POLL: Duration = Duration::from_millis(20);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Request {
+    pub method: String,
+    pub args: Vec<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Reply {
+    Result(serde_json::Value),
+    /// The handler failed; the message is raised in the caller.
+    Error(String),
+}
+
+static STOP: AtomicBool = AtomicBool::new(false);
+
+/// Make the running `rpc.listen` return once the current call is answered.
+pub fn request_stop() {
+    STOP.store(true, Ordering::SeqCst);
+}
+
+pub fn stop_requested() -> bool {
+    STOP.load(Ordering::SeqCst)
+}
+
+/// Where endpoints live.
+pub fn endpoint_dir() -> PathBuf {
+    dirs::runtime_dir().map(|d| d.join("axiom-rpc")).unwrap_or_else(|| {
+        match std::env::var("USER").or_else(|_| std::env::var("USERNAME")) {
+            Ok(user) if !user.is_empty() => std::env::temp_dir().join(format!("axiom-rpc-{}", user)),
+            _ => std::env::temp_dir().join("axiom-rpc"),
+        }
+    })
+}
+
+/// The endpoint path for `name`, without the transport's extension.
+fn endpoint(name: &str) -> Result<PathBuf, String> {
+    let valid = !name.is_empty()
+        && !name.starts_with('.')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c));
+    if !valid {
+        return Err(format!("'{}' is not an rpc name (use letters, digits, '-', '_' and '.')", name));
+    }
+    Ok(endpoint_dir().join(name))
+}
+
+#[cfg(unix)]
+mod transport {
+    use std::io;
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::path::Path;
+
+    pub type Listener = UnixListener;
+    pub type Stream = UnixStream;
+
+    /// Create `dir` private to this user. The directory may sit in a shared
+    /// temp dir, so one that already exists must be ours and closed to
+    /// everyone else, or another user could read or replace the sockets.
+    pub fn create_dir(dir: &Path) -> io::Result<()> {
+        use std::os::unix::fs::{DirBuilderExt, MetadataExt};
+        std::fs::DirBuilder::new().recursive(true).mode(0o700).create(dir)?;
+        let meta = std::fs::symlink_metadata(dir)?;
+        // SAFETY: geteuid has no preconditions and cannot fail.
+        let uid = unsafe { libc::geteuid() };
+        if !meta.is_dir() || meta.uid() != uid || meta.mode() & 0o777 != 0o700 {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "not a directory owned by the current user with mode 0700",
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn bind(base: &Path) -> io::Result<Listener> {
+        let path = base.with_extension("sock");
+        if path.exists() {
+            if UnixStream::connect(&path).is_ok() {
+                return Err(io::ErrorKind::AddrInUse.into());
+            }
+            // Left behind by a listener that was killed.
+            std::fs::remove_file(&path)?;
+        }
+        UnixListener::bind(path)
+    }
+
+    pub fn connect(base: &Path) -> io::Result<Stream> {
+        UnixStream::connect(base.with_extension("sock"))
+    }
+
+    pub fn unbind(base: &Path) {
+        let _ = std::fs::remove_file(base.with_extension("sock"));
+    }
+}
+
+#[cfg(not(unix))]
+mod transport {
+    use std::io;
+    use std::net::{Ipv4Addr, TcpListener, TcpStream};
+    use std::path::Path;
+
+    pub type Listener = TcpListener;
+    pub type Stream = TcpStream;
+
+    pub fn create_dir(dir: &Path) -> io::Result<()> {
+        std::fs::create_dir_all(dir)
+    }
+
+    pub fn bind(base: &Path) -> io::Result<Listener> {
+        if connect(base).is_ok() {
+            return Err(io::ErrorKind::AddrInUse.into());
+        }
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?;
+        std::fs::write(base.with_extension("port"), listener.local_addr()?.port().to_string())?;
+        Ok(listener)
+    }
+
+    pub fn connect(base: &Path) -> io::Result<Stream> {
+        let port = std::fs::read_to_string(base.with_extension("port"))?;
+        let port: u16 = port.trim().parse().map_err(|_| io::Error::from(io::ErrorKind::InvalidData))?;
+        TcpStream::connect((Ipv4Addr::LOCALHOST, port))
+    }
+
+    pub fn unbind(base: &Path) {
+        let _ = std::fs::remove_file(base.with_extension("port"));
+    }
+}
+
+/// Read one newline-terminated message; empty if the peer closed the connection.
+fn read_line(stream: &transport::Stream) -> std::io::Result<String> {
+    let mut line = String::new();
+    BufReader::new(stream.take(MAX_MESSAGE_BYTES)).read_line(&mut line)?;
+    Ok(line)
+}
+
+fn parse_message<T: for<'de> Deserialize<'de>>(line: &str) -> Result<T, String> {
+    serde_json::from_str(line).map_err(|e| format!("malformed message: {}", e))
+}
+
+fn write_message(mut stream: &transport::Stream, message: &impl Serialize) -> std::io::Result<()> {
+    let mut line = serde_json::to_vec(message)?;
+    line.push(b'\n');
+    stream.write_all(&line)?;
+    stream.flush()
+}
+
+/// A bound endpoint. Dropping it removes the endpoint.
+pub struct Server {
+    listener: transport::Listener,
+    base: PathBuf,
+}
+
+/// A call waiting for its reply.
+pub struct Incoming {
+    stream: transport::Stream,
+    pub request: Request,
+}
+
+impl Server {
+    /// Take the endpoint for `name`; fails if another script is listening on it.
+    /// Clears an earlier `rpc.stop()`.
+    pub fn bind(name: &str) -> Result<Server, String> {
+        let base = endpoint(name)?;
+        let dir = base.parent().unwrap_or(Path::new("."));
+        transport::create_dir(dir).map_err(|e| format!("Cannot create '{}': {}", dir.display(), e))?;
+        let listener = transport::bind(&base).map_err(|e| match e.kind() {
+            std::io::ErrorKind::AddrInUse => format!("another script is already listening as '{}'", name),
+            _ => format!("cannot listen as '{}': {}", name, e),
+        })?;
+        listener.set_nonblocking(true).map_err(|e| e.to_string())?;
+        STOP.store(false, Ordering::SeqCst);
+        Ok(Server { listener, base })
+    }
+
+    /// The next call, or None if none arrived within `wait`. A malformed
+    /// request is answered with an error here and never returned.
+    pub fn next(&self, wait: Duration) -> Result<Option<Incoming>, String> {
+        let deadline = Instant::now() + wait;
+        loop {
+            match self.listener.accept() {
+                Ok((stream, _)) => {
+                    stream.set_nonblocking(false).map_err(|e| e.to_string())?;
+                    let _ = stream.set_read_timeout(Some(READ_TIMEOUT));
+                    let request = read_line(&stream).map_err(|e| e.to_string()).and_then(|line| parse_message(&line));
+                    match request {
+                        Ok(request) => return Ok(Some(Incoming { stream, request })),
+                        Err(e) => {
+                            let _ = write_message(&stream, &Reply::Error(e));
+                        }
+                    }
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                    if Instant::now() >= deadline {
+                        return Ok(None);
+                    }
+                    std::thread::sleep(POLL);
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e.to_string()),
+            }
+        }
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        transport::unbind(&self.base);
+    }
+}
+
+impl Incoming {
+    /// Send the reply. A caller that has gone away is not an error.
+    pub fn reply(self, reply: &Reply) {
+        let _ = write_message(&self.stream, reply);
+    }
+}
+
+/// Send `request` to the script listening as `name` and wait for its reply,
+/// at most `timeout` when given.
+pub fn call(name: &str, request: &Request, timeout: Option<Duration>) -> Result<Reply, String> {
+    let base = endpoint(name)?;
+    let stream = transport::connect(&base).map_err(|_| format!("no script is listening as '{}'", name))?;
+    stream.set_read_timeout(timeout).map_err(|e| e.to_string())?;
+    write_message(&stream, request).map_err(|e| format!("cannot send to '{}': {}", name, e))?;
+    let line = read_line(&stream).map_err(|e| match e.kind() {
+        std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut => {
+            format!("'{}' did not reply within {} ms", name, timeout.unwrap_or_default().as_millis())
+        }
+        _ => format!("cannot read the reply from '{}': {}", name, e),
+    })?;
+    if line.is_empty() {
+        return Err(format!("'{}' closed the connection without replying", name));
+    }
+    parse_message(&line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_call_round_trips_through_a_server() {
+        let name = format!("test-{}", std::process::id());
+        let server = Server::bind(&name).unwrap();
+        assert!(matches!(Server::bind(&name), Err(e) if e.contains("already listening")));
+        let client = {
+            let name = name.clone();
+            std::thread::spawn(move || {
+                let add = Request { method: "add".into(), args: vec![json!(2), json!(3)] };
+                let missing = Request { method: "nope".into(), args: vec![] };
+                (call(&name, &add, None).unwrap(), call(&name, &missing, None).unwrap())
+            })
+        };
+        for _ in 0..2 {
+            let incoming = server.next(Duration::from_secs(5)).unwrap().unwrap();
+            let reply = match incoming.request.method.as_str() {
+                "add" => Reply::Result(json!(incoming.request.args.iter().filter_map(|a| a.as_i64()).sum::<i64>())),
+                other => Reply::Error(format!("no method '{}'", other)),
+            };
+            incoming.reply(&reply);
+        }
+        let (sum, missing) = client.join().unwrap();
+        assert_eq!(sum, Reply::Result(json!(5)));
+        assert_eq!(missing, Reply::Error("no method 'nope'".into()));
+
+        drop(server);
+        let request = Request { method: "add".into(), args: vec![] };
+        assert!(call(&name, &request, None).unwrap_err().contains("no script is listening"));
+        assert!(endpoint("../etc").is_err());
+    }
+
+    #[test]
+    fn test_scripts_listen_and_call_on_both_engines() {
+        use crate::runtime::Engine;
+        for engine in [Engine::Interp, Engine::Vm] {
+            let name = format!("test-{:?}-{}", engine, std::process::id());
+            let server_src = format!(r#"
+                fun handle(method, args) {{
+                    if method == "add" {{ ret args[0] + args[1]; }}
+                    if method == "echo" {{ ret args[0]; }}
+                    if method == "stop" {{ rpc.stop(); ret "bye"; }}
+                    if method == "ping" {{ ret "pong"; }}
+                    ret 1 / 0;
+                }}
+                rpc.listen("{}", handle)
+                out "stopped"
+            "#, name);
+            let server = std::thread::spawn(move || {
+                let mut rt = crate::Runtime::new();
+                rt.set_engine(engine);
+                crate::runtime::capture_output(|| rt.run(crate::Parser::new(&server_src, 0).parse().expect("parse")))
+            });
+            let ping = Request { method: "ping".into(), args: vec![] };
+            while call(&name, &ping, None).is_err() {
+                std::thread::sleep(std::time::Duration::from_millis(10));
+            }
+            let boom = Request { method: "boom".into(), args: vec![] };
+            let reply = call(&name, &boom, None);
+            assert!(matches!(&reply, Ok(Reply::Error(e)) if e.to_lowercase().contains("division")), "{:?}", reply);
+
+            let client_src = format!(r#"
+                let point = col.new_map()
+                point.x = 3
+                out rpc.call("{0}", "add", [2, 40]);
+                out rpc.call("{0}", "echo", [point]).x;
+                out rpc.call("{0}", "stop", []);
+            "#, name);
+            let mut rt = crate::Runtime::new();
+            rt.set_engine(engine);
+            let (result, output) = crate::runtime::capture_output(|| rt.run(crate::Parser::new(&client_src, 0).parse().expect("parse")));
+            result.unwrap();
+            assert_eq!(output, "42\n3\nbye\n");
+            let (result, output) = server.join().unwrap();
+            result.unwrap();
+            assert_eq!(output, "stopped\n");
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_endpoint_dir_must_be_private() {
+        use std::os::unix::fs::PermissionsExt;
+        let root = tempfile::tempdir().unwrap();
+        let dir = root.path().join("axiom-rpc");
+        transport::create_dir(&dir).unwrap();
+        transport::create_dir(&dir).unwrap();
+        std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o755)).unwrap();
+        assert!(transport::create_dir(&dir).is_err());
+        let link = root.path().join("link");
+        std::os::unix::fs::symlink(&dir, &link).unwrap();
+        std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o700)).unwrap();
+        assert!(transport::create_dir(&link).is_err());
+    }
+}
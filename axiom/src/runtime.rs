@@ -1,17 +1,215 @@
 /// Axiom High-Performance Runtime — Bytecode Edition
-use crate::ast::{ClassMember, Expr, Item, MatchPattern, Stmt, StringPart};
-use crate::compiler::compile_program;
+use crate::ast::{AccessorKind, ClassMember, Expr, Item, MatchArm, MatchPattern, Stmt, StringPart};
+use crate::compiler::{compile_program_with, GlobalTable};
 use crate::core::oop::{AxCallable, AxClass, AxInstance};
-use crate::core::value::AxValue;
-use crate::errors::RuntimeError;
+use crate::core::value::{is_frozen_container, touch_collections, AxValue};
+use crate::errors::{catch_native, RuntimeError, Span};
+use crate::hooks::{CallEvent, Phase, SharedHooks, StmtEvent};
 use crate::intrinsics;
-use crate::vm_core::{Val, VmCore, VmFun};
+use crate::optimizer::{fuse_superinstructions, FusionStats};
+use crate::inline_cache::{IcReport, PropIC, SlotKind};
+use crate::profiler::{HeapProfiler, HeapSnapshot};
+use crate::sandbox::{Capabilities, Meter, ResourceLimits};
+use crate::vm_core::{Val, VmCore, VmTrace};
+use std::path::{Path, PathBuf};
 use dashmap::DashMap;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, RwLock};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+
+/// Receives each line of script output in place of stdout. Shared, so the
+/// threads a run spawns (`go` blocks) write to the same place.
+type OutputSink = Arc<Mutex<dyn FnMut(&str) + Send>>;
+/// A module's exports by name, as `loc` binds them.
+type ModuleMap = Arc<DashMap<String, AxValue>>;
+
+thread_local! {
+    /// When set, script output on this thread goes here instead of stdout.
+    static OUTPUT_SINK: std::cell::RefCell<Option<OutputSink>> = const { std::cell::RefCell::new(None) };
+    /// The running runtime's hooks, while any of them watch output.
+    static OUTPUT_TAP: std::cell::RefCell<Option<SharedHooks>> = const { std::cell::RefCell::new(None) };
+    /// Functions registered with `aut.on_exit` during the current run.
+    static EXIT_HOOKS: std::cell::RefCell<Vec<AxValue>> = const { std::cell::RefCell::new(Vec::new()) };
+    /// Handles registered with `res.defer` and not closed yet, oldest
+    /// first, each with its registration number and the function that
+    /// closes it.
+    static DEFERRED: std::cell::RefCell<Vec<(u64, AxValue, AxValue)>> = const { std::cell::RefCell::new(Vec::new()) };
+    /// Registrations so far on this thread.
+    static DEFERRALS: std::cell::Cell<u64> = const { std::cell::Cell::new(0) };
+}
+
+/// Register `hook` to be called when the current run ends (`aut.on_exit`).
+pub(crate) fn on_exit(hook: AxValue) {
+    EXIT_HOOKS.with(|h| h.borrow_mut().push(hook));
+}
+
+/// Register `finalizer` to be called with `handle` when it is closed
+/// (`res.defer`).
+pub(crate) fn defer(handle: AxValue, finalizer: AxValue) {
+    let id = DEFERRALS.with(|n| { n.set(n.get() + 1); n.get() });
+    DEFERRED.with(|d| d.borrow_mut().push((id, handle, finalizer)));
+}
+
+/// Handles still open.
+pub(crate) fn deferred_count() -> usize {
+    DEFERRED.with(|d| d.borrow().len())
+}
+
+/// Marks the registrations so far; `take_deferred_since` returns the
+/// handles deferred after it.
+pub(crate) fn deferred_mark() -> u64 {
+    DEFERRALS.with(|n| n.get())
+}
+
+/// Unregister `handle`, returning its finalizer. The most recent
+/// registration of that very value wins, then one of an equal value: a
+/// handle crossing between the engines arrives as a copy.
+pub(crate) fn take_deferred(handle: &AxValue) -> Option<AxValue> {
+    DEFERRED.with(|d| {
+        let mut d = d.borrow_mut();
+        let same = |(_, h, _): &(u64, AxValue, AxValue)| match (h, handle) {
+            (AxValue::Map(a), AxValue::Map(b)) => Arc::ptr_eq(a, b),
+            (AxValue::Lst(a), AxValue::Lst(b)) => Arc::ptr_eq(a, b),
+            (AxValue::Instance(a), AxValue::Instance(b)) => Arc::ptr_eq(a, b),
+            _ => false,
+        };
+        let i = d.iter().rposition(same).or_else(|| d.iter().rposition(|(_, h, _)| h.deep_eq(handle)))?;
+        Some(d.remove(i).2)
+    })
+}
+
+/// Unregister the handles deferred after `mark`, most recent first, as
+/// (handle, finalizer) pairs.
+pub(crate) fn take_deferred_since(mark: u64) -> Vec<(AxValue, AxValue)> {
+    DEFERRED.with(|d| {
+        let mut d = d.borrow_mut();
+        let first = d.iter().position(|(id, _, _)| *id > mark).unwrap_or(d.len());
+        d.drain(first..).rev().map(|(_, h, f)| (h, f)).collect()
+    })
+}
+
+/// How script output reaches stdout (conf `stdout_buffer`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputBuffering {
+    /// Flush after every line, so output shows up as it is printed
+    #[default]
+    Line,
+    /// Flush when the buffer fills, on `sys.flush()`, before reading input
+    /// and when the run ends; much faster for scripts that print a lot
+    Block,
+}
+
+impl OutputBuffering {
+    /// The `stdout_buffer` conf value (unknown values mean `Line`).
+    pub fn from_conf(conf: &crate::conf::AxConf) -> OutputBuffering {
+        match conf.stdout_buffer() {
+            "block" => OutputBuffering::Block,
+            _ => OutputBuffering::Line,
+        }
+    }
+}
+
+/// Script output bound for stdout, shared by every thread of the process.
+static STDOUT: Lazy<Mutex<std::io::BufWriter<std::io::Stdout>>> =
+    Lazy::new(|| Mutex::new(std::io::BufWriter::with_capacity(64 * 1024, std::io::stdout())));
+static BLOCK_BUFFERED: AtomicBool = AtomicBool::new(false);
+
+pub fn set_output_buffering(mode: OutputBuffering) {
+    BLOCK_BUFFERED.store(mode == OutputBuffering::Block, Ordering::Relaxed);
+}
+
+pub fn output_buffering() -> OutputBuffering {
+    match BLOCK_BUFFERED.load(Ordering::Relaxed) {
+        true => OutputBuffering::Block,
+        false => OutputBuffering::Line,
+    }
+}
+
+/// Write out any buffered script output (`sys.flush`).
+pub fn flush_output() {
+    let _ = STDOUT.lock().flush();
+}
+
+/// Write one line of script output (`out` statements and the `out`/`print` builtins).
+pub(crate) fn write_line(line: &str) {
+    OUTPUT_TAP.with(|t| if let Some(hooks) = t.borrow().as_ref() { hooks.lock().output(line) });
+    let sink = OUTPUT_SINK.with(|s| s.borrow().clone());
+    match sink {
+        Some(sink) => (sink.lock())(line),
+        None => {
+            let mut out = STDOUT.lock();
+            let _ = writeln!(out, "{}", line);
+            if !BLOCK_BUFFERED.load(Ordering::Relaxed) {
+                let _ = out.flush();
+            }
+        }
+    }
+}
+
+/// Whether script output on this thread goes to a sink rather than stdout.
+pub(crate) fn output_captured() -> bool {
+    OUTPUT_SINK.with(|s| s.borrow().is_some())
+}
+
+/// Puts back the sink a thread had before, even if the code in between panics.
+struct SinkGuard(Option<OutputSink>);
+
+impl SinkGuard {
+    fn install(sink: Option<OutputSink>) -> SinkGuard {
+        SinkGuard(OUTPUT_SINK.with(|s| std::mem::replace(&mut *s.borrow_mut(), sink)))
+    }
+}
+
+impl Drop for SinkGuard {
+    fn drop(&mut self) {
+        let previous = self.0.take();
+        OUTPUT_SINK.with(|s| *s.borrow_mut() = previous);
+    }
+}
+
+/// Run `f` with every line of script output on this thread, and on the
+/// threads it spawns for `go` blocks, passed to `sink`.
+pub fn with_output_sink<T>(sink: impl FnMut(&str) + Send + 'static, f: impl FnOnce() -> T) -> T {
+    let _guard = SinkGuard::install(Some(Arc::new(Mutex::new(sink))));
+    f()
+}
+
+/// Run `f` and return its result along with everything it printed, `go`
+/// blocks included as far as they got before `f` returned.
+pub fn capture_output<T>(f: impl FnOnce() -> T) -> (T, String) {
+    let buf = Arc::new(Mutex::new(String::new()));
+    let sink_buf = Arc::clone(&buf);
+    let result = with_output_sink(move |line| {
+        let mut b = sink_buf.lock();
+        b.push_str(line);
+        b.push('\n');
+    }, f);
+    let output = buf.lock().clone();
+    (result, output)
+}
+
+/// Run `f` concurrently (`go`): as a tokio task when there is a runtime,
+/// else on a new thread. Its output goes where this thread's does, and it
+/// has the same capabilities.
+fn spawn_go(f: impl FnOnce() + Send + 'static) {
+    let sink = OUTPUT_SINK.with(|s| s.borrow().clone());
+    let caps = crate::sandbox::active_capabilities();
+    let task = move || {
+        let _guard = SinkGuard::install(sink);
+        crate::sandbox::with_capabilities(caps, f);
+    };
+    match tokio::runtime::Handle::try_current() {
+        Ok(handle) => { handle.spawn(async move { task() }); }
+        Err(_) => { std::thread::spawn(task); }
+    }
+}
 
 pub struct Env {
-    frames: Vec<HashMap<String, AxValue>>,
+    /// Scopes, innermost last, keyed by interned identifiers
+    frames: Vec<HashMap<Arc<str>, AxValue>>,
 }
 
 impl Env {
@@ -26,29 +224,225 @@ impl Env {
     }
     fn set(&mut self, name: &str, value: AxValue) -> bool {
         for frame in self.frames.iter_mut().rev() {
-            if frame.contains_key(name) { frame.insert(name.to_string(), value); return true; }
+            if let Some(slot) = frame.get_mut(name) { *slot = value; return true; }
         }
         false
     }
-    fn define(&mut self, name: String, value: AxValue) {
+    fn define(&mut self, name: &str, value: AxValue) {
+        self.define_interned(crate::intern::intern(name), value);
+    }
+    /// Assignment: update the innermost binding, or define one here.
+    fn assign(&mut self, name: &str, value: AxValue) {
+        if !self.set(name, value.clone()) { self.define(name, value); }
+    }
+    fn define_interned(&mut self, name: Arc<str>, value: AxValue) {
         if let Some(f) = self.frames.last_mut() { f.insert(name, value); }
     }
+    fn names(&self) -> impl Iterator<Item = &str> {
+        self.frames.iter().flat_map(|f| f.keys()).map(|k| &**k)
+    }
+}
+
+/// Top-level bindings kept from one `Runtime::run_in` call to the next, so
+/// a REPL can enter a program a piece at a time.
+pub struct Session {
+    env: Env,
+}
+
+impl Default for Session {
+    fn default() -> Self { Session { env: Env::new() } }
+}
+
+impl Session {
+    /// Names bound at the top level so far.
+    pub fn names(&self) -> Vec<String> {
+        self.env.names().map(String::from).collect()
+    }
+}
+
+/// The global environment. Every name has a slot numbered by the same
+/// `GlobalTable` the VM compiles against, so a program run on the VM starts
+/// with its globals at the indices the runtime already uses, and a global
+/// read on the tree-walker is resolved to its slot once per identifier site.
+/// Going by name is left to imports, `main` and other dynamic lookups.
+#[derive(Clone, Default)]
+pub struct Globals {
+    table: GlobalTable,
+    /// Indexed like `table.names`; `None` until the name is bound
+    slots: Vec<Option<AxValue>>,
+    /// Identifier site address → slot, filled by `lookup_at`
+    sites: std::cell::RefCell<HashMap<usize, u16>>,
+}
+
+impl Globals {
+    pub fn get(&self, name: &str) -> Option<&AxValue> {
+        self.get_at(self.table.get(name)? as usize)
+    }
+
+    pub fn get_at(&self, slot: usize) -> Option<&AxValue> {
+        self.slots.get(slot)?.as_ref()
+    }
+
+    pub fn contains_key(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    pub fn insert(&mut self, name: impl AsRef<str>, value: AxValue) {
+        let slot = self.table.intern(name.as_ref()) as usize;
+        self.set_at(slot, value);
+    }
+
+    pub fn set_at(&mut self, slot: usize, value: AxValue) {
+        if slot >= self.slots.len() { self.slots.resize(slot + 1, None); }
+        self.slots[slot] = Some(value);
+    }
+
+    /// The slot `name` is bound in, if any.
+    pub fn slot(&self, name: &str) -> Option<usize> {
+        let slot = self.table.get(name)? as usize;
+        self.get_at(slot).map(|_| slot)
+    }
+
+    /// The names slots are numbered by; a program compiled from a clone
+    /// keeps these indices and appends its own names after them.
+    pub fn table(&self) -> &GlobalTable {
+        &self.table
+    }
+
+    /// Switch to `table`, which must extend the current one.
+    fn adopt(&mut self, table: GlobalTable) {
+        debug_assert!(table.names.starts_with(&self.table.names));
+        self.table = table;
+    }
+
+    /// The global read by the identifier whose name is `name`, as it sits in
+    /// the AST. The slot found the first time is remembered for the site;
+    /// slots are never renumbered, but the name is checked in case the site's
+    /// AST was freed and its address reused.
+    fn lookup_at(&self, name: &str) -> Option<&AxValue> {
+        let site = name.as_ptr() as usize;
+        let cached = self.sites.borrow().get(&site).copied();
+        if let Some(slot) = cached.filter(|&s| self.table.names[s as usize] == name) {
+            return self.get_at(slot as usize);
+        }
+        let slot = self.slot(name)?;
+        self.sites.borrow_mut().insert(site, slot as u16);
+        self.get_at(slot)
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &String> {
+        self.iter().map(|(k, _)| k)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &AxValue)> {
+        self.table.names.iter().zip(&self.slots).filter_map(|(k, v)| Some((k, v.as_ref()?)))
+    }
+}
+
+/// Which engine `Runtime::run` executes programs on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Engine {
+    /// The tree-walking interpreter
+    #[default]
+    Interp,
+    /// Compile to bytecode and run on the register VM; programs using
+    /// constructs the compiler does not handle yet fall back to `Interp`
+    Vm,
+}
+
+impl Engine {
+    /// The `engine` conf value (unknown values mean `Interp`).
+    pub fn from_conf(conf: &crate::conf::AxConf) -> Engine {
+        match conf.engine() {
+            "vm" => Engine::Vm,
+            _ => Engine::Interp,
+        }
+    }
 }
 
 pub struct Runtime {
-    pub globals: HashMap<String, AxValue>,
+    pub globals: Globals,
     pub classes: HashMap<String, Arc<AxClass>>,
-    call_depth: std::cell::Cell<usize>,
+    /// Names of the Axiom functions currently executing, outermost first
+    call_stack: std::cell::RefCell<Vec<String>>,
+    max_call_depth: usize,
+    meter: Meter,
+    capabilities: Capabilities,
+    heap_profile: Option<HeapProfiling>,
+    /// `None` when the `ic_enabled` / `inline_cache` conf switches are off.
+    method_ics: Option<std::cell::RefCell<MethodIcs>>,
+    /// When set, `run` executes on the bytecode VM and traces each instruction.
+    vm_trace: Option<VmTrace>,
+    engine: Engine,
+    /// Whether the VM raises on reading a global never bound (conf `strict_vars`)
+    strict_vars: bool,
+    modules: ModuleCache,
+    /// Whether bytecode goes through superinstruction fusion (conf
+    /// `superinstructions`), and what it did in the last run on the VM
+    fuse: bool,
+    fusions: Option<FusionStats>,
+    hooks: SharedHooks,
+    /// Whether a statement or call hook is registered (checked per statement)
+    stepping: bool,
+    /// Whether `@user/repo` packages are checked against Axiomite.lock (conf `pkg_verify`)
+    pkg_verify: crate::pkg::VerifyMode,
+    /// What this runtime was configured from, for the VMs it creates
+    conf: Arc<crate::conf::AxConf>,
+}
+
+/// Modules parsed and run so far, so each file is read once and run once per
+/// `run` however many files import it.
+#[derive(Default)]
+struct ModuleCache {
+    /// Parsed items by canonical file path
+    parsed: HashMap<PathBuf, Vec<Item>>,
+    /// Export maps by file and whether it was imported with `loc`
+    loaded: HashMap<(PathBuf, bool), ModuleMap>,
+    /// Files being run, outermost first: a relative `loc` resolves against the
+    /// innermost one's directory, and finding a file here again is a cycle
+    loading: Vec<PathBuf>,
+    /// Module maps by the string a compiled `__load` passes: the `load` path,
+    /// the main program's `loc` spec, or a module's `loc`ed file
+    imported: HashMap<String, AxValue>,
+    /// Where the main program's relative `load` paths and `loc`s start, in
+    /// place of the working directory
+    root: Option<PathBuf>,
 }
 
-const MAX_CALL_DEPTH: usize = 1000;
+/// Inline caches for instance method calls and accessor sites, one per
+/// call site. A class stands in for a shape, and the cached slot is how
+/// many parent hops up the chain the method or accessor was found.
+#[derive(Default)]
+struct MethodIcs {
+    /// Call-site address → (method name, source offset, cache).
+    sites: HashMap<usize, (String, usize, PropIC)>,
+    /// Class address → shape id.
+    shapes: HashMap<usize, u32>,
+}
+
+/// State for `enable_heap_profile`: sites recorded so far, the globals that
+/// existed before the script ran (builtins, not script data), and the
+/// snapshot taken when the last run finished.
+struct HeapProfiling {
+    profiler: std::cell::RefCell<HeapProfiler>,
+    builtins: std::collections::HashSet<String>,
+    snapshot: Option<HeapSnapshot>,
+}
 
 impl Runtime {
+    /// A runtime configured from the conf files and `AXIOM_*` variables.
     pub fn new() -> Self {
-        let mut globals: HashMap<String, AxValue> = HashMap::new();
+        Self::with_conf(&crate::conf::AxConf::load())
+    }
+
+    /// A runtime configured from `conf`, which the VM it runs programs on
+    /// is configured from as well.
+    pub fn with_conf(conf: &crate::conf::AxConf) -> Self {
+        let mut globals = Globals::default();
         macro_rules! native {
             ($name:expr, $body:expr) => {
-                globals.insert($name.into(), AxValue::Fun(Arc::new(AxCallable::Native { name: $name.into(), func: $body })));
+                let f: fn(Vec<AxValue>) -> AxValue = $body;
+                globals.insert($name, AxValue::Fun(Arc::new(AxCallable::native($name, move |args| Ok(f(args))))));
             };
         }
         native!("type", |args| args.first().map(|a| AxValue::Str(a.type_name().to_string())).unwrap_or(AxValue::Nil));
@@ -60,11 +454,13 @@ impl Runtime {
         });
         native!("str", |args| args.first().map(|a| AxValue::Str(a.display())).unwrap_or(AxValue::Nil));
         native!("bol", |args| args.first().map(|a| AxValue::Bol(a.is_truthy())).unwrap_or(AxValue::Nil));
-        native!("out", |args| { println!("{}", args.iter().map(|a| a.display()).collect::<Vec<_>>().join(" ")); AxValue::Nil });
-        native!("print", |args| { println!("{}", args.iter().map(|a| a.display()).collect::<Vec<_>>().join(" ")); AxValue::Nil });
+        native!("out", |args| { write_line(&args.iter().map(|a| a.display()).collect::<Vec<_>>().join(" ")); AxValue::Nil });
+        native!("print", |args| { write_line(&args.iter().map(|a| a.display()).collect::<Vec<_>>().join(" ")); AxValue::Nil });
         native!("in", |args| {
-            use std::io::Write;
-            if let Some(AxValue::Str(p)) = args.first() { print!("{}", p); let _ = std::io::stdout().flush(); }
+            let mut out = STDOUT.lock();
+            if let Some(AxValue::Str(p)) = args.first() { let _ = write!(out, "{}", p); }
+            let _ = out.flush();
+            drop(out);
             let mut s = String::new();
             match std::io::stdin().read_line(&mut s) {
                 Ok(_) => AxValue::Str(s.trim_end_matches(['\r', '\n']).to_string()),
@@ -87,197 +483,732 @@ impl Runtime {
             }
             _ => AxValue::Nil,
         });
-        globals.insert("nil".into(), AxValue::Nil);
-        // chdir / cwd — registered here so both VM and tree-walk paths can find them
-        globals.insert("chdir".into(), AxValue::Fun(Arc::new(AxCallable::Native {
-            name: "chdir".into(),
-            func: |args| match args.first() {
-                Some(AxValue::Str(path)) => match std::env::set_current_dir(path) {
-                    Ok(_)  => AxValue::Bol(true),
-                    Err(e) => AxValue::Str(format!("ERROR: {}", e)),
-                },
-                _ => AxValue::Str("ERROR: chdir expects a string path".into()),
-            },
-        })));
-        globals.insert("cwd".into(), AxValue::Fun(Arc::new(AxCallable::Native {
-            name: "cwd".into(),
-            func: |_args| match std::env::current_dir() {
-                Ok(path) => AxValue::Str(path.display().to_string()),
-                Err(e)   => AxValue::Str(format!("ERROR: {}", e)),
-            },
-        })));
+        globals.insert("nil", AxValue::Nil);
         intrinsics::register(&mut globals);
         // Register nil as a global constant
-        globals.insert("nil".to_string(), AxValue::Nil);
-        Runtime { globals, classes: HashMap::new(), call_depth: std::cell::Cell::new(0) }
+        globals.insert("nil", AxValue::Nil);
+        Runtime {
+            globals,
+            classes: HashMap::new(),
+            call_stack: Default::default(),
+            max_call_depth: conf.max_call_depth() as usize,
+            meter: Meter::default(),
+            capabilities: Capabilities::from_conf(conf),
+            heap_profile: None,
+            method_ics: (conf.ic_enabled() && conf.inline_cache()).then(Default::default),
+            vm_trace: VmTrace::from_conf(conf),
+            engine: Engine::from_conf(conf),
+            strict_vars: conf.strict_vars(),
+            modules: ModuleCache::default(),
+            fuse: conf.to_opt_config().superinstructions,
+            fusions: None,
+            hooks: Default::default(),
+            stepping: false,
+            pkg_verify: crate::pkg::VerifyMode::from_conf(conf),
+            conf: Arc::new(conf.clone()),
+        }
+    }
+
+    /// How many superinstructions were fused in the program last run on the
+    /// VM, or `None` if none ran there or fusion is off.
+    pub fn fusion_report(&self) -> Option<FusionStats> {
+        self.fusions
+    }
+
+    /// Hit/miss counts and states of the method-call inline caches so far,
+    /// or `None` when caching is disabled.
+    pub fn ic_report(&self) -> Option<IcReport> {
+        let ics = self.method_ics.as_ref()?.borrow();
+        Some(IcReport::new(ics.sites.values().map(|(m, at, ic)| (m.clone(), *at, ic.clone()))))
+    }
+
+    /// Record allocation sites from now on; each `run` then leaves a
+    /// snapshot for `take_heap_snapshot`.
+    pub fn enable_heap_profile(&mut self) {
+        self.heap_profile = Some(HeapProfiling {
+            profiler: Default::default(),
+            builtins: self.globals.keys().cloned().collect(),
+            snapshot: None,
+        });
+    }
+
+    /// The snapshot from the last `run` (also taken when it failed).
+    pub fn take_heap_snapshot(&mut self) -> Option<HeapSnapshot> {
+        self.heap_profile.as_mut()?.snapshot.take()
+    }
+
+    /// Charge `bytes` to the meter, attributing them to `site` when profiling.
+    fn charge(&self, site: &'static str, bytes: usize) -> Result<(), RuntimeError> {
+        if let Some(heap) = &self.heap_profile {
+            let stack = self.call_stack.borrow();
+            // Method sites (`list.push`) run in their own call frame; credit the caller
+            let skip = usize::from(site.contains('.'));
+            let function = stack.iter().rev().nth(skip).map(String::as_str).unwrap_or("<main>");
+            heap.profiler.borrow_mut().record(site, function, bytes);
+        }
+        self.meter.charge(bytes)
+    }
+
+    /// Restrict which side-effecting intrinsic groups scripts may call
+    /// (overrides the `sandbox_deny` conf value).
+    pub fn set_capabilities(&mut self, capabilities: Capabilities) {
+        self.capabilities = capabilities;
+    }
+
+    /// Bound the next `run` by instruction count, wall-clock time and
+    /// allocation; exceeding any of them raises `RuntimeError::LimitExceeded`.
+    pub fn set_limits(&mut self, limits: ResourceLimits) {
+        self.meter = Meter::new(limits);
+    }
+
+    /// Override the `max_call_depth` conf value (tree-walker and VM alike).
+    pub fn set_max_call_depth(&mut self, depth: usize) {
+        self.max_call_depth = depth;
+    }
+
+    /// Trace executed VM instructions to stderr (overrides the
+    /// `opcode_trace` / `opcode_trace_filter` conf values).
+    pub fn set_vm_trace(&mut self, trace: Option<VmTrace>) {
+        self.vm_trace = trace;
+    }
+
+    /// Choose the engine (overrides the `engine` conf value).
+    pub fn set_engine(&mut self, engine: Engine) {
+        self.engine = engine;
+    }
+
+    /// Make reading an undeclared variable an error on the VM too
+    /// (overrides the `strict_vars` conf value).
+    pub fn set_strict_vars(&mut self, strict: bool) {
+        self.strict_vars = strict;
+    }
+
+    /// Resolve the main program's relative `load` paths and `loc`s against
+    /// `dir` instead of the working directory (used to run an unpacked bundle).
+    pub fn set_module_root(&mut self, dir: impl Into<PathBuf>) {
+        self.modules.root = Some(dir.into());
+    }
+
+    /// Call `hook` before and after each statement the script executes.
+    /// Scripts then run on the tree-walker.
+    pub fn on_statement(&mut self, hook: impl FnMut(&StmtEvent) + Send + 'static) {
+        self.hooks.lock().add_statement(hook);
+        self.stepping = true;
+    }
+
+    /// Call `hook` when each function, native or script, is entered and
+    /// left. Scripts then run on the tree-walker.
+    pub fn on_call(&mut self, hook: impl FnMut(&CallEvent) + Send + 'static) {
+        self.hooks.lock().add_call(hook);
+        self.stepping = true;
+    }
+
+    /// Call `hook` with each line the script prints, on either engine.
+    pub fn on_output(&mut self, hook: impl FnMut(&str) + Send + 'static) {
+        self.hooks.lock().add_output(hook);
+    }
+
+    /// Call `hook` with the error a `run` fails with, on either engine.
+    pub fn on_error(&mut self, hook: impl FnMut(&RuntimeError) + Send + 'static) {
+        self.hooks.lock().add_error(hook);
     }
 
     pub fn run(&mut self, items: Vec<Item>) -> Result<(), RuntimeError> {
-        // Use tree-walk runtime for all programs
-        // The VM path has issues with module marshaling; it's an optimization that needs proper globals bridging
+        self.meter.reset();
+        self.modules.loaded.clear();
+        self.modules.imported.clear();
+        crate::sandbox::clear_interrupt();
+        EXIT_HOOKS.with(|h| h.borrow_mut().clear());
+        DEFERRED.with(|d| d.borrow_mut().clear());
+        self.observed(|rt| {
+            let result = rt.run_program(items);
+            rt.run_exit_hooks(result)
+        })
+    }
+
+    /// Run `items` on the tree-walker in `session`, which keeps their
+    /// top-level bindings (and the modules they load) for the next call.
+    /// Returns the value of a trailing expression statement, else nil.
+    /// `main` is not called and `aut.on_exit` hooks wait for a `run`.
+    pub fn run_in(&mut self, items: Vec<Item>, session: &mut Session) -> Result<AxValue, RuntimeError> {
+        self.meter.reset();
+        crate::sandbox::clear_interrupt();
+        self.observed(|rt| {
+            let _package = rt.enter_main_package();
+            let items = rt.enabled_imports(items)?;
+            for item in &items { rt.register_decl(item)?; }
+            rt.run_imports(&items, &mut session.env)?;
+            let mut value = AxValue::Nil;
+            for item in &items {
+                value = match item {
+                    Item::Statement(Stmt::Expr(expr)) => rt.eval(expr, &mut session.env).map_err(|e| e.located(expr.span()))?,
+                    Item::Statement(stmt) => { rt.exec_stmt(stmt, &mut session.env)?; AxValue::Nil }
+                    _ => AxValue::Nil,
+                };
+            }
+            Ok(value)
+        })
+    }
+
+    /// Run `body` with the output hooks seeing what it prints, then flush
+    /// its output and pass the error it fails with to the error hooks.
+    fn observed<T>(&mut self, body: impl FnOnce(&mut Self) -> Result<T, RuntimeError>) -> Result<T, RuntimeError> {
+        let tap = self.hooks.lock().has_output().then(|| self.hooks.clone());
+        let previous = OUTPUT_TAP.with(|t| std::mem::replace(&mut *t.borrow_mut(), tap));
+        let result = crate::sandbox::with_capabilities(self.capabilities, || body(self));
+        OUTPUT_TAP.with(|t| *t.borrow_mut() = previous);
+        flush_output();
+        if let Err(e) = &result {
+            self.hooks.lock().error(e);
+        }
+        result
+    }
+
+    /// `run`, returning what the script printed instead of writing it to
+    /// stdout: `out`, `print` and console log records of every level,
+    /// including those from `go` blocks that print before it returns.
+    pub fn run_captured(&mut self, items: Vec<Item>) -> (Result<(), RuntimeError>, String) {
+        capture_output(|| self.run(items))
+    }
+
+    fn run_program(&mut self, items: Vec<Item>) -> Result<(), RuntimeError> {
+        let _package = self.enter_main_package();
+        let items = self.enabled_imports(items)?;
+        if !self.stepping && (self.engine == Engine::Vm || self.vm_trace.is_some()) {
+            let unsupported = crate::compiler::vm_unsupported(&items)
+                .or_else(|| self.imports_unsupported(&items, &mut HashSet::new()));
+            match unsupported {
+                None => return self.run_via_vm(&items),
+                Some(what) => eprintln!("[vm] {} not supported by the VM yet; running on the tree-walker", what),
+            }
+        }
+        crate::crash::set_stage("run (interp)");
         self.run_tree_walk(items)
     }
 
-    fn run_via_vm(&mut self, items: &[Item]) -> Result<bool, RuntimeError> {
-        let needs_tree_walk = items.iter().any(|item| {
-            matches!(item, Item::ClassDecl { .. } | Item::LoadStmt { .. })
-        });
-        if needs_tree_walk { return Ok(false); }
+    /// Call the `aut.on_exit` hooks, most recent first, however the run
+    /// ended, then close the `res.defer` handles left open, also most
+    /// recent first. The run's own error wins over one raised by a hook.
+    fn run_exit_hooks(&mut self, result: Result<(), RuntimeError>) -> Result<(), RuntimeError> {
+        let hooks = EXIT_HOOKS.with(|h| std::mem::take(&mut *h.borrow_mut()));
+        if hooks.is_empty() && deferred_count() == 0 {
+            return result;
+        }
+        // An interrupt has done its job once the script stops; the hooks still run
+        crate::sandbox::clear_interrupt();
+        self.meter.reset();
+        let mut env = Env::new();
+        let mut failed = result.err();
+        for hook in hooks.into_iter().rev() {
+            if let Err(e) = self.call_value(hook, vec![], &mut env) {
+                failed.get_or_insert(e);
+            }
+        }
+        for (handle, finalizer) in take_deferred_since(0) {
+            if let Err(e) = self.call_value(finalizer, vec![handle], &mut env) {
+                failed.get_or_insert(e);
+            }
+        }
+        failed.map_or(Ok(()), Err)
+    }
 
-        let (proto, global_table) = compile_program(items, "<main>");
-        let n_globals = global_table.names.len();
-        let mut vm = VmCore::new(n_globals + 64);
-
-        for (idx, name) in global_table.names.iter().enumerate() {
-            if let Some(ax_val) = self.globals.get(name) {
-                match ax_val {
-                    AxValue::Fun(callable) => {
-                        if let AxCallable::Native { name: fn_name, func } = callable.as_ref() {
-                            let func_ptr = *func;
-                            let fn_name_c = fn_name.clone();
-                            let vm_fn = VmFun::Native {
-                                name: fn_name_c,
-                                func: Box::new(move |args: &[Val]| {
-                                    let ax_args: Vec<AxValue> = args.iter().map(VmCore::val_to_ax).collect();
-                                    Ok(VmCore::ax_to_val(&func_ptr(ax_args)))
-                                }),
-                            };
-                            vm.set_global_at(idx, Val::Fun(Arc::new(vm_fn)));
-                        }
-                    }
-                    other => {
-                        let v = VmCore::ax_to_val(other);
-                        if !matches!(v, Val::Nil) { vm.set_global_at(idx, v); }
+    fn run_via_vm(&mut self, items: &[Item]) -> Result<(), RuntimeError> {
+        // `load`s are hoisted as on the tree-walker; module functions and
+        // enums are compiled with the program and the compiled `__load` calls then just
+        // pick up the module, with its functions swapped for the VM's
+        let mut env = Env::new();
+        let mut program = self.run_imports(items, &mut env)?;
+        let modules = self.modules.imported.clone();
+        program.extend(items.iter().cloned());
+        crate::crash::set_stage("compile");
+        let (mut proto, global_table) = compile_program_with(&program, "<main>", self.globals.table().reopen());
+        if self.fuse {
+            self.fusions = Some(fuse_superinstructions(&mut proto));
+        }
+        crate::crash::set_stage("run (vm)");
+
+        // A module's functions are switched over to the VM's compiled ones when
+        // its `__load` runs, after their declarations
+        let keys: HashMap<usize, String> = self.modules.loaded.iter()
+            .map(|((file, _), module)| (Arc::as_ptr(module) as usize, file.display().to_string()))
+            .collect();
+        let module_globals = global_table.clone();
+        let mut hooks: HashMap<&str, AxValue> = HashMap::new();
+        hooks.insert("__load", AxValue::Fun(Arc::new(AxCallable::native("__load", move |args: Vec<AxValue>| {
+            let Some(AxValue::Map(module)) = args.first().and_then(|path| match path {
+                AxValue::Str(path) => modules.get(path),
+                _ => None,
+            }) else {
+                return Ok(AxValue::Nil);
+            };
+            let key = keys.get(&(Arc::as_ptr(module) as usize)).map(String::as_str).unwrap_or_default();
+            for mut entry in module.iter_mut() {
+                if let AxValue::Fun(f) = entry.value() {
+                    // Functions a module renamed are compiled under their private names
+                    let idx = module_globals.get(&crate::loader::private_name(key, entry.key()))
+                        .or_else(|| module_globals.get(entry.key()))
+                        .filter(|&idx| module_globals.used(idx as usize));
+                    if let (AxCallable::UserDefined { .. }, Some(idx)) = (f.as_ref(), idx) {
+                        *entry.value_mut() = VmCore::calling_global(idx as usize);
                     }
                 }
             }
+            // The VM may hold a copy converted before the swap
+            touch_collections();
+            Ok(AxValue::Map(Arc::clone(module)))
+        }))));
+        hooks.insert("__fmt", AxValue::Fun(Arc::new(AxCallable::native("__fmt", |args: Vec<AxValue>| {
+            let spec = match args.get(1) { Some(AxValue::Str(spec)) => spec.as_str(), _ => "" };
+            format_value(args.first().unwrap_or(&AxValue::Nil), spec).map(AxValue::Str)
+        }))));
+        hooks.insert("__go", AxValue::Fun(Arc::new(AxCallable::native("__go", |args: Vec<AxValue>| {
+            if let Some(AxValue::Fun(body)) = args.into_iter().next() {
+                if let AxCallable::Native { func, .. } = body.as_ref() {
+                    let func = Arc::clone(func);
+                    spawn_go(move || { let _ = func(vec![]); });
+                }
+            }
+            Ok(AxValue::Nil)
+        }))));
+
+        let n_globals = global_table.names.len();
+        let mut vm = VmCore::with_conf(n_globals + 64, &self.conf);
+        vm.set_max_call_depth(self.max_call_depth);
+        vm.set_limits(self.meter.limits());
+        vm.set_trace(self.vm_trace.clone());
+        vm.set_capabilities(self.capabilities);
+        vm.set_strict(self.strict_vars.then_some(&global_table.names[..]));
+
+        // The program's table extends the runtime's, so the globals it uses
+        // are copied slot for slot; only the VM's hooks and the names bound
+        // by imports are found by name. A slot the table only names (read but
+        // never assigned in an earlier run) has no binding and stays unbound
+        // for strict mode; one really bound, even to nil, counts as declared
+        for (idx, name) in global_table.names.iter().enumerate().filter(|&(idx, _)| global_table.used(idx)) {
+            let binding = hooks.get(name.as_str()).or_else(|| self.globals.get_at(idx)).or_else(|| env.get(name));
+            if let Some(ax_val) = binding {
+                vm.set_global_at(idx, VmCore::ax_to_val(ax_val));
+            }
         }
 
         let proto = Arc::new(proto);
         vm.run(proto)?;
+        if let Some(Val::Fun(main)) = global_table.get("main").map(|idx| vm.get_global_at(idx as usize)) {
+            vm.call(&main, vec![])?;
+        }
 
-        for (idx, name) in global_table.names.iter().enumerate() {
+        self.globals.adopt(global_table.clone());
+        for (idx, name) in global_table.names.iter().enumerate().filter(|&(idx, _)| global_table.used(idx)) {
             let vm_val = vm.get_global_at(idx);
-            if !matches!(vm_val, Val::Nil) {
-                self.globals.insert(name.clone(), VmCore::val_to_ax(&vm_val));
+            if !matches!(vm_val, Val::Nil) && !hooks.contains_key(name.as_str()) {
+                self.globals.set_at(idx, vm.export(&vm_val));
             }
         }
 
-        Ok(true)
+        Ok(())
     }
 
     fn run_tree_walk(&mut self, items: Vec<Item>) -> Result<(), RuntimeError> {
-        for item in &items { self.register_decl(item); }
+        for item in &items { self.register_decl(item)?; }
         let mut env = Env::new();
-        for item in &items {
-            if let Item::LoadStmt { path, is_lib, alias, .. } = item {
-                self.handle_load(path, *is_lib, alias.as_deref(), &mut env)?;
-            }
+        self.run_imports(&items, &mut env)?;
+        let result = self.exec_program(&items, &mut env);
+        if let Some(heap) = &mut self.heap_profile {
+            let roots = env.frames.iter().flat_map(|f| f.values())
+                .chain(self.globals.iter().filter(|(k, _)| !heap.builtins.contains(*k)).map(|(_, v)| v));
+            heap.snapshot = Some(heap.profiler.borrow().snapshot("", roots));
         }
-        for item in &items {
-            if let Item::Statement(stmt) = item { self.exec_stmt(stmt, &mut env)?; }
+        result
+    }
+
+    fn exec_program(&mut self, items: &[Item], env: &mut Env) -> Result<(), RuntimeError> {
+        for item in items {
+            if let Item::Statement(stmt) = item { self.exec_stmt(stmt, env)?; }
         }
         if let Some(main_fn) = self.globals.get("main").cloned() {
-            self.call_value(main_fn, vec![], &mut env)?;
+            self.call_named("main", main_fn, vec![], env)?;
         }
         Ok(())
     }
 
-    fn handle_load(&mut self, path: &str, is_lib: bool, alias: Option<&str>, env: &mut Env) -> Result<(), RuntimeError> {
-        use crate::pkg::AxiomiteConfig;
-        use std::path::PathBuf;
-        let (resolved_path, pkg_root) = if is_lib {
+    /// Resolve a `load` path to the module file and its package root.
+    pub(crate) fn module_path(path: &str, is_lib: bool) -> Result<(std::path::PathBuf, Option<std::path::PathBuf>), RuntimeError> {
+        if is_lib {
             let home = dirs::home_dir().ok_or_else(|| RuntimeError::GenericError { message: "Cannot determine home directory".into(), span: Default::default() })?;
             let parts: Vec<&str> = path.trim_start_matches('@').split('/').collect();
-            if parts.len() != 2 { return Err(RuntimeError::GenericError { message: format!("Invalid library path: {}", path), span: Default::default() }); }
-            let root = home.join(".axiomlibs").join(parts[0]).join(parts[1]);
-            (root.join("lib.ax"), Some(root))
+            // `@user/repo`, or `@host/user/repo` for a package from another host
+            if !(2..=3).contains(&parts.len()) { return Err(RuntimeError::GenericError { message: format!("Invalid library path: {}", path), span: Default::default() }); }
+            let root = home.join(".axiomlibs").join(parts.join("/"));
+            Ok((root.join("lib.ax"), Some(root)))
         } else {
-            let p = PathBuf::from(path);
+            let p = std::path::PathBuf::from(path);
             let root = p.parent().map(|r| r.to_path_buf());
-            (p, root)
-        };
+            Ok((p, root))
+        }
+    }
+
+    /// `module_path`, with a relative `load` path taken from the module root.
+    fn load_path(&self, path: &str, is_lib: bool) -> Result<(PathBuf, Option<PathBuf>), RuntimeError> {
+        let (file, pkg_root) = Self::module_path(path, is_lib)?;
+        match &self.modules.root {
+            Some(root) if !is_lib && file.is_relative() => Ok((root.join(&file), pkg_root.map(|dir| root.join(dir)))),
+            _ => Ok((file, pkg_root)),
+        }
+    }
+
+    /// Whether `file` may be read as a module: anything with the `fs`
+    /// capability, and without it only files inside the module root (an
+    /// unpacked bundle). Checked before the file is opened, so a denied
+    /// file's lines never reach a diagnostic.
+    fn check_module_access(&self, statement: &str, file: &Path) -> Result<(), RuntimeError> {
+        if self.capabilities.permits(crate::sandbox::Capability::FileSystem) {
+            return Ok(());
+        }
+        let root = self.modules.root.as_deref().and_then(|root| root.canonicalize().ok());
+        let resolved = file.canonicalize().ok();
+        match (root, resolved) {
+            (Some(root), Some(file)) if file.starts_with(&root) => Ok(()),
+            _ => Err(RuntimeError::PermissionDenied { function: statement.to_string(), capability: crate::sandbox::Capability::FileSystem }),
+        }
+    }
+
+    /// Read and parse a module file once; later imports reuse the items.
+    fn parse_file(&mut self, file: &Path, module: &str) -> Result<Vec<Item>, RuntimeError> {
+        self.check_module_access("load", file)?;
+        let file = file.canonicalize().map_err(|e| RuntimeError::GenericError { message: format!("Cannot load '{}': {}", file.display(), e), span: Default::default() })?;
+        if let Some(items) = self.modules.parsed.get(&file) { return Ok(items.clone()); }
+        let source = std::fs::read_to_string(&file).map_err(|e| RuntimeError::GenericError { message: format!("Cannot load '{}': {}", file.display(), e), span: Default::default() })?;
+        let mut parser = crate::Parser::new(&source, crate::loader::register_source(&file, &source));
+        let items = parser.parse().map_err(|error| {
+            RuntimeError::ModuleSyntaxError { module: module.to_string(), file: file.display().to_string(), error }
+        })?;
+        self.modules.parsed.insert(file, items.clone());
+        Ok(items)
+    }
+
+    /// Resolve a `loc` spec against the directory of the module being run
+    /// (the module root, by default the working directory, for the main program).
+    fn resolve_loc(&self, spec: &str) -> Result<PathBuf, RuntimeError> {
+        let base = self.modules.loading.last().and_then(|file| file.parent())
+            .or(self.modules.root.as_deref())
+            .unwrap_or(Path::new("."));
+        crate::loader::resolve_loc(spec, base).map_err(|message| RuntimeError::ImportError { module: spec.to_string(), message })
+    }
+
+    /// The first construct the VM cannot compile in the modules `items`
+    /// import, directly or through their own imports.
+    fn imports_unsupported(&mut self, items: &[Item], seen: &mut HashSet<PathBuf>) -> Option<&'static str> {
+        for item in items {
+            let (file, module) = match item {
+                Item::LoadStmt { path, is_lib, .. } => (self.load_path(path, *is_lib).ok().map(|(file, _)| file), path),
+                Item::LocImport { name, .. } => (self.resolve_loc(name).ok(), name),
+                _ => continue,
+            };
+            let Some(file) = file.and_then(|f| f.canonicalize().ok()) else { continue };
+            if !seen.insert(file.clone()) { continue; }
+            let Ok(module) = self.parse_file(&file, module) else { continue };
+            self.modules.loading.push(file);
+            let found = crate::compiler::vm_unsupported(&module).or_else(|| self.imports_unsupported(&module, seen));
+            self.modules.loading.pop();
+            if found.is_some() { return found; }
+        }
+        None
+    }
+
+    /// Answer `pkg.feature` for the package the main program belongs to.
+    fn enter_main_package(&self) -> crate::pkg::PackageScope {
+        let dir = self.modules.root.clone().or_else(|| std::env::current_dir().ok()).unwrap_or_default();
+        crate::pkg::enter_package(&dir)
+    }
+
+    /// `items` without the `load`s and `loc`s whose package feature is off.
+    fn enabled_imports(&self, items: Vec<Item>) -> Result<Vec<Item>, RuntimeError> {
+        let mut kept = Vec::with_capacity(items.len());
+        for item in items {
+            let (module, feature) = match &item {
+                Item::LoadStmt { path, feature: Some(feature), .. } | Item::LocImport { name: path, feature: Some(feature), .. } => (path, feature),
+                _ => { kept.push(item); continue; }
+            };
+            let on = crate::pkg::feature_enabled(feature)
+                .map_err(|message| RuntimeError::ImportError { module: module.clone(), message })?;
+            if on { kept.push(item); }
+        }
+        Ok(kept)
+    }
+
+    /// Run the `load`, `loc` and `std` imports among `items` and note each
+    /// module for the VM's `__load`. Returns the imported function and enum
+    /// declarations, plus the `loc`s of a module being run, rewritten to name
+    /// their files, for the VM to compile.
+    fn run_imports(&mut self, items: &[Item], env: &mut Env) -> Result<Vec<Item>, RuntimeError> {
+        let mut decls = Vec::new();
+        for item in items {
+            match item {
+                Item::LoadStmt { path, is_lib, alias, .. } => {
+                    decls.extend(self.handle_load(path, *is_lib, alias.as_deref(), env)?);
+                    let module = self.globals.get(path).cloned().unwrap_or(AxValue::Nil);
+                    self.modules.imported.insert(path.clone(), module);
+                }
+                Item::LocImport { name, alias, names, span, .. } => {
+                    let file = self.resolve_loc(name)?;
+                    let (module, module_decls) = self.handle_loc(name, alias.as_deref(), names.as_deref(), env)?;
+                    decls.extend(module_decls);
+                    if self.modules.loading.is_empty() {
+                        self.modules.imported.insert(name.clone(), module);
+                        continue;
+                    }
+                    // Inside a module the spec is relative to it
+                    let path = file.display().to_string();
+                    let alias = alias.clone().unwrap_or_else(|| crate::loader::loc_binding(name).to_string());
+                    decls.push(Item::LocImport { name: path.clone(), alias: Some(alias), names: names.clone(), feature: None, span: *span });
+                    self.modules.imported.insert(path, module);
+                }
+                Item::StdImport { module, alias, names, .. } => self.handle_std_import(module, alias.as_deref(), names.as_deref())?,
+                _ => {}
+            }
+        }
+        Ok(decls)
+    }
+
+    /// Run a `load`: set up its package, execute the module and bind it as a
+    /// map. Returns the module's function and enum declarations (and its dependencies').
+    fn handle_load(&mut self, path: &str, is_lib: bool, alias: Option<&str>, env: &mut Env) -> Result<Vec<Item>, RuntimeError> {
+        use crate::pkg::AxiomiteConfig;
+        let mut decls = Vec::new();
+        let (file, pkg_root) = self.load_path(path, is_lib)?;
+        self.check_module_access("load", &file)?;
+        if let (true, Some(root)) = (is_lib, &pkg_root) {
+            self.verify_package(path, root)?;
+        }
         if let Some(ref root) = pkg_root {
             let toml_path = root.join("Axiomite.toml");
             if toml_path.exists() {
                 if let Ok(config) = AxiomiteConfig::from_file(&toml_path) {
                     for (k, v) in &config.env { std::env::set_var(k, v); self.globals.insert(k.clone(), AxValue::Str(v.clone())); }
                     for dep in &config.dependencies.requires {
-                        if !self.globals.contains_key(dep.as_str()) { self.handle_load(&format!("@{}", dep), true, None, env)?; }
+                        if !self.globals.contains_key(dep.as_str()) { decls.extend(self.handle_load(&format!("@{}", dep), true, None, env)?); }
                     }
                 }
             }
             let init_path = root.join("init.ax");
             if init_path.exists() {
                 if let Ok(src) = std::fs::read_to_string(&init_path) {
-                    let mut p = crate::Parser::new(&src, 0);
+                    let mut p = crate::Parser::new(&src, crate::loader::register_source(&init_path, &src));
                     if let Ok(init_items) = p.parse() {
-                        for item in &init_items { self.register_decl(item); }
+                        for item in &init_items { self.register_decl(item)?; }
                         for item in &init_items { if let Item::Statement(s) = item { self.exec_stmt(s, env)?; } }
                     }
                 }
             }
         }
-        let source = std::fs::read_to_string(&resolved_path).map_err(|e| RuntimeError::GenericError { message: format!("Cannot load '{}': {}", resolved_path.display(), e), span: Default::default() })?;
-        let mut parser = crate::Parser::new(&source, 0);
-        let loaded_items = parser.parse().map_err(|e| RuntimeError::GenericError { message: format!("Parse error in '{}': {}", resolved_path.display(), e), span: Default::default() })?;
-        let module_map = Arc::new(DashMap::new());
-        for item in &loaded_items {
-            self.register_decl(item);
-            if let Item::FunctionDecl { name, params, body, .. } = item {
-                module_map.insert(name.clone(), AxValue::Fun(Arc::new(AxCallable::UserDefined { params: params.clone(), body: body.clone(), captured: std::collections::HashMap::new() })));
+        let (module_map, module_decls) = self.instantiate_module(path, &file, false, env)?;
+        let module_val = AxValue::Map(module_map);
+        self.globals.insert(crate::loader::module_key(path), module_val.clone());
+        self.globals.insert(path, module_val.clone());
+        if let Some(a) = alias { self.globals.insert(a, module_val); }
+        decls.extend(module_decls);
+        Ok(decls)
+    }
+
+    /// Check the installed package at `root` against Axiomite.lock, as conf
+    /// `pkg_verify` asks.
+    fn verify_package(&self, path: &str, root: &Path) -> Result<(), RuntimeError> {
+        if self.pkg_verify == crate::pkg::VerifyMode::Off {
+            return Ok(());
+        }
+        #[cfg(feature = "native")]
+        if let Err(message) = crate::pkg::verify_installed(root, path.trim_start_matches('@')) {
+            if self.pkg_verify == crate::pkg::VerifyMode::On {
+                return Err(RuntimeError::ImportError { module: path.to_string(), message });
             }
+            eprintln!("warning: {}", message);
         }
-        for item in &loaded_items {
-            if let Item::Statement(stmt) = item {
-                self.exec_stmt(stmt, env)?;
-                if let Stmt::Let { name, .. } = stmt {
-                    if let Some(v) = self.globals.get(name) { module_map.insert(name.clone(), v.clone()); }
-                    else if let Some(v) = env.get(name) { module_map.insert(name.clone(), v.clone()); }
-                }
+        #[cfg(not(feature = "native"))]
+        let _ = (path, root);
+        Ok(())
+    }
+
+    /// Run a `loc` import: load the module `name` names and bind its exports
+    /// as a map under the alias (or the name's last segment), or bind just the
+    /// selected `names`. Returns the module's map and its function and enum declarations.
+    fn handle_loc(&mut self, name: &str, alias: Option<&str>, names: Option<&[String]>, env: &mut Env) -> Result<(AxValue, Vec<Item>), RuntimeError> {
+        let file = self.resolve_loc(name)?;
+        self.check_module_access("loc", &file)?;
+        let (module_map, decls) = self.instantiate_module(name, &file, true, env)?;
+        match names {
+            Some(names) => for member in names {
+                // Exported classes keep their own names, so they are in scope already
+                if self.classes.contains_key(member) { continue; }
+                let value = module_map.get(member).map(|v| v.clone()).ok_or_else(|| RuntimeError::ImportError {
+                    module: name.to_string(),
+                    message: format!("module does not export '{}'", member),
+                })?;
+                self.globals.insert(member.clone(), value);
+            },
+            None => {
+                let binding = alias.unwrap_or_else(|| crate::loader::loc_binding(name));
+                self.globals.insert(binding, AxValue::Map(Arc::clone(&module_map)));
             }
         }
-        let module_val = AxValue::Map(module_map);
-        let full_key = path.trim_start_matches('@').replace('/', ".").replace('-', "_");
-        self.globals.insert(full_key, module_val.clone());
-        self.globals.insert(path.to_string(), module_val.clone());
-        if let Some(a) = alias { self.globals.insert(a.to_string(), module_val); }
+        Ok((AxValue::Map(module_map), decls))
+    }
+
+    /// Run a `std` import: bind an intrinsic module under another name, or
+    /// some of its functions under their own.
+    fn handle_std_import(&mut self, module: &str, alias: Option<&str>, names: Option<&[String]>) -> Result<(), RuntimeError> {
+        let Some(AxValue::Map(map)) = self.globals.get(module).cloned() else {
+            return Err(RuntimeError::ImportError { module: module.to_string(), message: "no such standard module".into() });
+        };
+        match names {
+            Some(names) => for member in names {
+                let value = map.get(member).map(|v| v.clone()).ok_or_else(|| RuntimeError::ImportError {
+                    module: module.to_string(),
+                    message: format!("module has no function '{}'", member),
+                })?;
+                self.globals.insert(member.clone(), value);
+            },
+            None => { self.globals.insert(alias.unwrap_or(module), AxValue::Map(map)); }
+        }
         Ok(())
     }
 
-    fn register_decl(&mut self, item: &Item) {
+    /// Register and run the module in `file` (imported as `module`), renaming
+    /// what it keeps private (and, when `qualified`, its exports too) out of
+    /// the importer's reach. Returns the map of its exports and the function
+    /// and enum declarations of it and its own imports; a module already run
+    /// returns its map again and no declarations.
+    fn instantiate_module(&mut self, module: &str, file: &Path, qualified: bool, env: &mut Env) -> Result<(ModuleMap, Vec<Item>), RuntimeError> {
+        let file = file.canonicalize().map_err(|e| RuntimeError::ImportError { module: module.to_string(), message: format!("cannot open '{}': {}", file.display(), e) })?;
+        if let Some(map) = self.modules.loaded.get(&(file.clone(), qualified)) { return Ok((Arc::clone(map), Vec::new())); }
+        if let Some(start) = self.modules.loading.iter().position(|f| *f == file) {
+            let chain = self.modules.loading[start..].iter().chain(std::iter::once(&file)).map(|f| f.display().to_string()).collect();
+            return Err(RuntimeError::CircularImport { chain });
+        }
+        // The module's features are its own package's
+        let _package = crate::pkg::enter_package(file.parent().unwrap_or(Path::new(".")));
+        let items = self.parse_file(&file, module)?;
+        let items = self.enabled_imports(items)?;
+        let key = file.display().to_string();
+        let exported = crate::loader::exports(&items);
+        let names: Vec<String> = crate::loader::top_level_names(&items).into_iter().map(String::from).collect();
+        let hide = if qualified { crate::loader::hide_all } else { crate::loader::hide_private };
+        let items = hide(items, &key).map_err(|message| RuntimeError::ImportError { module: module.to_string(), message })?;
+        self.modules.loading.push(file.clone());
+        let ran = self.run_module(&items, env);
+        self.modules.loading.pop();
+        let mut decls = ran?;
+        // Renaming keeps declaration order, so the names still line up
+        let module_map = Arc::new(DashMap::new());
+        for (name, bound) in names.iter().zip(crate::loader::top_level_names(&items)) {
+            if !exported.as_ref().is_none_or(|e| e.contains(name)) { continue; }
+            if let Some(v) = self.globals.get(bound).or_else(|| env.get(bound)) { module_map.insert(name.clone(), v.clone()); }
+        }
+        self.modules.loaded.insert((file, qualified), Arc::clone(&module_map));
+        decls.extend(items.into_iter().filter(|item| matches!(item, Item::FunctionDecl { .. } | Item::EnumDecl { .. })));
+        Ok((module_map, decls))
+    }
+
+    /// A module's body: its imports, then its declarations and statements.
+    fn run_module(&mut self, items: &[Item], env: &mut Env) -> Result<Vec<Item>, RuntimeError> {
+        let decls = self.run_imports(items, env)?;
+        for item in items { self.register_decl(item)?; }
+        for item in items {
+            if let Item::Statement(stmt) = item { self.exec_stmt(stmt, env)?; }
+        }
+        Ok(decls)
+    }
+
+    fn register_decl(&mut self, item: &Item) -> Result<(), RuntimeError> {
         match item {
             Item::FunctionDecl { name, params, body, .. } => {
-                self.globals.insert(name.clone(), AxValue::Fun(Arc::new(AxCallable::UserDefined { params: params.clone(), body: body.clone(), captured: std::collections::HashMap::new() })));
+                self.globals.insert(name.clone(), AxValue::Fun(Arc::new(AxCallable::UserDefined { name: Some(name.clone()), params: params.clone(), body: body.clone(), captured: std::collections::HashMap::new() })));
             }
-            Item::ClassDecl { name, body, .. } => {
+            Item::ClassDecl { name, parent, interfaces, body, .. } => {
                 let mut ax_class = AxClass::new(name.clone());
+                ax_class.interfaces = interfaces.clone();
+                // Instance members see the parent's name as `super` (nil in a
+                // base class, shadowing any caller's binding)
+                let mut captured = std::collections::HashMap::new();
+                captured.insert(Arc::from("super"), AxValue::Nil);
+                if let Some(parent) = parent {
+                    let class = self.classes.get(parent).cloned().ok_or_else(|| RuntimeError::GenericError {
+                        message: format!("Class '{}' extends unknown class '{}'", name, parent),
+                        span: Default::default(),
+                    })?;
+                    ax_class.parent = Some(class);
+                    captured.insert(Arc::from("super"), AxValue::Str(parent.clone()));
+                }
                 for member in body {
                     match member {
+                        ClassMember::Method { name: mn, params, body, is_static: true, .. } => {
+                            let method = AxCallable::UserDefined { name: Some(mn.clone()), params: params.clone(), body: body.clone(), captured: std::collections::HashMap::new() };
+                            ax_class.statics.insert(mn.clone(), AxValue::Fun(Arc::new(method)));
+                        }
                         ClassMember::Method { name: mn, params, body, .. } => {
-                            ax_class.methods.insert(mn.clone(), AxCallable::UserDefined { params: params.clone(), body: body.clone(), captured: std::collections::HashMap::new() });
+                            ax_class.methods.insert(mn.clone(), AxCallable::UserDefined { name: Some(mn.clone()), params: params.clone(), body: body.clone(), captured: captured.clone() });
+                        }
+                        ClassMember::Field { name: fn_, default, is_static: true, .. } => {
+                            let value = match default { Some(e) => self.eval(e, &mut Env::new())?, None => AxValue::Nil };
+                            ax_class.statics.insert(fn_.clone(), value);
                         }
                         ClassMember::Field { name: fn_, default, .. } => { ax_class.fields.push((fn_.clone(), default.clone())); }
+                        ClassMember::Accessor { name: an, kind, params, body, .. } => {
+                            let table = match kind { AccessorKind::Get => &mut ax_class.getters, AccessorKind::Set => &mut ax_class.setters };
+                            table.insert(an.clone(), AxCallable::UserDefined { name: Some(an.clone()), params: params.clone(), body: body.clone(), captured: captured.clone() });
+                        }
                     }
                 }
-                self.classes.insert(name.clone(), Arc::new(ax_class));
+                // `Name.member` reaches the statics like an enum's variants
+                self.globals.insert(name.clone(), AxValue::Map(Arc::clone(&ax_class.statics)));
+                let ax_class = Arc::new(ax_class);
+                AxClass::declare(&ax_class);
+                self.classes.insert(name.clone(), ax_class);
             }
             Item::EnumDecl { name, variants, .. } => {
-                for v in variants { self.globals.insert(format!("{}.{}", name, v.name), AxValue::Str(format!("{}.{}", name, v.name))); }
-                self.globals.insert(name.clone(), AxValue::Str(name.clone()));
+                // `Name` is a namespace: unit variants are values, data variants constructors
+                let namespace = DashMap::new();
+                for v in variants {
+                    let qualified: Arc<str> = Arc::from(format!("{}.{}", name, v.name));
+                    let value = if v.has_data {
+                        AxValue::Fun(Arc::new(AxCallable::native(qualified.to_string(), move |args: Vec<AxValue>| {
+                            Ok(AxValue::EnumVariant(Arc::clone(&qualified), Box::new(args.into_iter().next().unwrap_or(AxValue::Nil))))
+                        })))
+                    } else {
+                        AxValue::EnumVariant(qualified, Box::new(AxValue::Nil))
+                    };
+                    namespace.insert(v.name.clone(), value);
+                }
+                self.globals.insert(name.clone(), AxValue::Map(Arc::new(namespace)));
             }
             _ => {}
         }
+        Ok(())
     }
 
+    /// Run `stmt`. An error that does not know where it happened yet is
+    /// placed at the statement, in the file the statement came from.
     fn exec_stmt(&self, stmt: &Stmt, env: &mut Env) -> Result<Option<AxValue>, RuntimeError> {
+        let result = if self.stepping {
+            let depth = self.call_stack.borrow().len();
+            self.hooks.lock().statement(&StmtEvent { stmt, phase: Phase::Before, depth });
+            let result = self.exec_stmt_inner(stmt, env);
+            self.hooks.lock().statement(&StmtEvent { stmt, phase: Phase::After, depth });
+            result
+        } else {
+            self.exec_stmt_inner(stmt, env)
+        };
+        result.map_err(|e| e.located(error_span(stmt)))
+    }
+
+    fn exec_stmt_inner(&self, stmt: &Stmt, env: &mut Env) -> Result<Option<AxValue>, RuntimeError> {
+        self.meter.tick()?;
         match stmt {
-            Stmt::Let { name, value, .. } => { let val = self.eval(value, env)?; env.define(name.clone(), val); }
+            Stmt::Let { name, value, .. } => { let val = self.eval(value, env)?; env.define(name, val); }
             Stmt::Expr(e) => { self.eval(e, env)?; }
             Stmt::Out { arguments, .. } => {
                 let mut parts = Vec::with_capacity(arguments.len());
                 for arg in arguments { parts.push(self.eval(arg, env)?.display()); }
-                println!("{}", parts.join(""));
+                write_line(&parts.join(""));
             }
             Stmt::Return { value, .. } => {
                 let v = match value { Some(e) => self.eval(e, env)?, None => AxValue::Nil };
@@ -289,6 +1220,7 @@ impl Runtime {
             }
             Stmt::While { condition, body, .. } => {
                 while self.eval(condition, env)?.is_truthy() {
+                    self.meter.tick()?;
                     if let Some(ret) = self.exec_block(body, env)? { return Ok(Some(ret)); }
                 }
             }
@@ -300,7 +1232,8 @@ impl Runtime {
                     _ => return Err(RuntimeError::GenericError { message: format!("'{}' is not iterable", iter_val.type_name()), span: Default::default() }),
                 };
                 for item in items {
-                    env.push_frame(); env.define(var.clone(), item);
+                    self.meter.tick()?;
+                    env.push_frame(); env.define(var, item);
                     let ret = self.exec_block_in_env(body, env)?;
                     env.pop_frame();
                     if ret.is_some() { return Ok(ret); }
@@ -309,19 +1242,15 @@ impl Runtime {
             Stmt::Block(stmts) => return self.exec_block(stmts, env),
             Stmt::Match { expr, arms, .. } => {
                 let val = self.eval(expr, env)?;
-                for arm in arms {
-                    if self.pattern_matches(&arm.pattern, &val) {
-                        env.push_frame();
-                        if let MatchPattern::EnumVariant { binding: Some(b), .. } = &arm.pattern { env.define(b.clone(), val.clone()); }
-                        let ret = self.exec_block_in_env(&arm.body, env)?;
-                        env.pop_frame();
-                        return Ok(ret);
-                    }
+                if let Some(arm) = self.select_arm(arms, &val, env)? {
+                    let ret = self.exec_block_in_env(&arm.body, env)?;
+                    env.pop_frame();
+                    return Ok(ret);
                 }
             }
             Stmt::GoSpawn { body, .. } => {
-                let g = self.globals.clone(); let c = self.classes.clone(); let body = body.clone();
-                tokio::spawn(async move { let rt = Runtime { globals: g, classes: c, call_depth: std::cell::Cell::new(0) }; let mut env = Env::new(); let _ = rt.exec_block_in_env(&body, &mut env); });
+                let g = self.globals.clone(); let c = self.classes.clone(); let body = body.clone(); let max_call_depth = self.max_call_depth; let limits = self.meter.limits(); let capabilities = self.capabilities; let strict_vars = self.strict_vars; let pkg_verify = self.pkg_verify; let conf = Arc::clone(&self.conf);
+                spawn_go(move || { let rt = Runtime { globals: g, classes: c, call_stack: Default::default(), max_call_depth, meter: Meter::new(limits), capabilities, heap_profile: None, method_ics: None, vm_trace: None, engine: Engine::Interp, strict_vars, modules: ModuleCache::default(), fuse: false, fusions: None, hooks: Default::default(), stepping: false, pkg_verify, conf }; let mut env = Env::new(); let _ = rt.exec_block_in_env(&body, &mut env); });
             }
         }
         Ok(None)
@@ -336,43 +1265,148 @@ impl Runtime {
         Ok(None)
     }
 
+    /// The first arm whose pattern matches `value` and whose guard holds,
+    /// with its bindings defined in a new frame the caller pops.
+    fn select_arm<'a>(&self, arms: &'a [MatchArm], value: &AxValue, env: &mut Env) -> Result<Option<&'a MatchArm>, RuntimeError> {
+        for arm in arms {
+            if !self.pattern_matches(&arm.pattern, value) { continue; }
+            env.push_frame();
+            self.bind_pattern(&arm.pattern, value, env);
+            match arm.guard.as_ref().map_or(Ok(true), |guard| self.eval(guard, env).map(|v| v.is_truthy())) {
+                Ok(true) => return Ok(Some(arm)),
+                Ok(false) => env.pop_frame(),
+                Err(e) => { env.pop_frame(); return Err(e); }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Define the variables a matched pattern binds: the value itself for
+    /// `name`, the payload (nil for a non-variant) for `Variant(name)`, and
+    /// for `a | b` those of the first alternative that matches (nil for the
+    /// names only other alternatives bind).
+    fn bind_pattern(&self, pattern: &MatchPattern, value: &AxValue, env: &mut Env) {
+        match pattern {
+            MatchPattern::Identifier(name) => env.define(name, value.clone()),
+            MatchPattern::EnumVariant { binding: Some(name), .. } => {
+                let payload = match value {
+                    AxValue::EnumVariant(_, inner) => (**inner).clone(),
+                    _ => AxValue::Nil,
+                };
+                env.define(name, payload);
+            }
+            MatchPattern::Or(alts) => {
+                for (name, _) in pattern.bindings() { env.define(name, AxValue::Nil); }
+                if let Some(alt) = alts.iter().find(|alt| self.pattern_matches(alt, value)) {
+                    self.bind_pattern(alt, value, env);
+                }
+            }
+            MatchPattern::List { items, rest } => {
+                let AxValue::Lst(list) = value else { return };
+                let list = list.read().unwrap().clone();
+                for (item, elem) in items.iter().zip(&list) { self.bind_pattern(item, elem, env); }
+                if let Some(rest) = rest {
+                    let tail = list.get(items.len()..).unwrap_or_default().to_vec();
+                    self.bind_pattern(rest, &AxValue::Lst(Arc::new(RwLock::new(tail))), env);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Run a match-expression arm; its value is that of its last statement
+    /// if that is an expression, nil otherwise.
+    fn eval_arm(&self, body: &[Stmt], env: &mut Env) -> Result<AxValue, RuntimeError> {
+        let Some((last, init)) = body.split_last() else { return Ok(AxValue::Nil) };
+        for stmt in init { self.exec_stmt(stmt, env)?; }
+        match last {
+            Stmt::Expr(e) => self.eval(e, env),
+            stmt => { self.exec_stmt(stmt, env)?; Ok(AxValue::Nil) }
+        }
+    }
+
     fn pattern_matches(&self, pattern: &MatchPattern, value: &AxValue) -> bool {
         match pattern {
             MatchPattern::Wildcard | MatchPattern::Identifier(_) => true,
+            MatchPattern::Or(alts) => alts.iter().any(|alt| self.pattern_matches(alt, value)),
             MatchPattern::Literal(expr) => {
                 let mut env = Env::new();
-                if let Ok(lit) = self.eval(expr, &mut env) { self.values_equal(&lit, value) } else { false }
+                if let Ok(lit) = self.eval(expr, &mut env) { lit.deep_eq(value) } else { false }
+            }
+            MatchPattern::Range { start, end } => {
+                let mut env = Env::new();
+                let (Ok(lo), Ok(hi)) = (self.eval(start, &mut env), self.eval(end, &mut env)) else { return false };
+                // Numbers against numbers, strings against strings, as the VM compares
+                match (&lo, value, &hi) {
+                    (AxValue::Num(lo), AxValue::Num(v), AxValue::Num(hi)) => lo <= v && v < hi,
+                    (AxValue::Str(lo), AxValue::Str(v), AxValue::Str(hi)) => lo <= v && v < hi,
+                    _ => false,
+                }
+            }
+            MatchPattern::List { items, rest } => {
+                let AxValue::Lst(list) = value else { return false };
+                let list = list.read().unwrap();
+                let len_ok = if rest.is_some() { list.len() >= items.len() } else { list.len() == items.len() };
+                len_ok && items.iter().zip(list.iter()).all(|(item, elem)| self.pattern_matches(item, elem))
             }
             MatchPattern::EnumVariant { enum_name, variant, .. } => {
-                let expected = match enum_name { Some(e) => format!("{}.{}", e, variant), None => variant.clone() };
-                match value { AxValue::Str(s) => s == &expected || s.ends_with(&format!(".{}", variant)), _ => false }
+                let AxValue::EnumVariant(name, _) = value else { return false };
+                match (enum_name, name.split_once('.')) {
+                    (Some(e), Some((en, v))) => e == en && v == variant,
+                    (None, Some((_, v))) => v == variant,
+                    (_, None) => false,
+                }
             }
         }
     }
 
-    fn values_equal(&self, a: &AxValue, b: &AxValue) -> bool {
-        match (a, b) {
-            (AxValue::Num(x), AxValue::Num(y)) => x == y,
-            (AxValue::Str(x), AxValue::Str(y)) => x == y,
-            (AxValue::Bol(x), AxValue::Bol(y)) => x == y,
-            (AxValue::Nil, AxValue::Nil) => true,
-            _ => false,
-        }
-    }
-
     fn eval(&self, expr: &Expr, env: &mut Env) -> Result<AxValue, RuntimeError> {
         match expr {
             Expr::Number  { value, .. } => Ok(AxValue::Num(*value)),
             Expr::String  { value, .. } => Ok(AxValue::Str(value.clone())),
             Expr::Boolean { value, .. } => Ok(AxValue::Bol(*value)),
             Expr::SelfRef { .. }        => self.lookup("self", env),
+            Expr::SuperCall { method, arguments, .. } => {
+                let parent = match env.get("super") {
+                    Some(AxValue::Str(parent)) => parent.clone(),
+                    _ => return Err(RuntimeError::GenericError { message: "`super` used outside a subclass method".into(), span: Default::default() }),
+                };
+                let class = self.classes.get(&parent).cloned().ok_or_else(|| RuntimeError::GenericError { message: format!("Unknown class '{}'", parent), span: Default::default() })?;
+                let callable = class.resolve_method(method).cloned().ok_or_else(|| RuntimeError::GenericError { message: format!("No method '{}' on '{}'", method, parent), span: Default::default() })?;
+                let this = self.lookup("self", env)?;
+                let mut args = Vec::with_capacity(arguments.len()); for arg in arguments { args.push(self.eval(arg, env)?); }
+                self.enter_call(&format!("{}.{}", parent, method))?;
+                let result = self.invoke_bound(this, callable, args, env);
+                self.exit_call();
+                result
+            }
             Expr::Identifier { name, .. } => self.lookup(name, env),
             Expr::Assign { target, value, .. } => {
                 let val = self.eval(value, env)?;
                 match target.as_ref() {
-                    Expr::Identifier { name, .. } => { if !env.set(name, val.clone()) { env.define(name.clone(), val.clone()); } }
-                    Expr::MemberAccess { object, member, .. } => {
-                        if let AxValue::Instance(inst) = self.eval(object, env)? { inst.write().unwrap().fields.insert(member.clone(), val.clone()); }
+                    Expr::Identifier { name, .. } => env.assign(name, val.clone()),
+                    Expr::MemberAccess { object, member, .. } => match self.eval(object, env)? {
+                        frozen if frozen.is_frozen() => return Err(RuntimeError::FrozenMutation { type_name: frozen.type_name().into(), span: target.span() }),
+                        AxValue::Instance(inst) => {
+                            let class = Arc::clone(&inst.read().unwrap().class);
+                            let setter = if class.has_accessors() { self.resolve_instance_member(Some(target), &class, member, SlotKind::Setter) } else { None };
+                            match setter {
+                                Some(setter) => { self.call_accessor(AxValue::Instance(inst), &class, member, setter, vec![val.clone()], env)?; }
+                                None => { inst.write().unwrap().fields.insert(member.clone(), val.clone()); }
+                            }
+                        }
+                        AxValue::Map(map) => { map.insert(member.clone(), val.clone()); }
+                        _ => {}
+                    },
+                    // Like the VM's SetIndex: only an in-range list slot is written
+                    Expr::Index { object, index, .. } => {
+                        if let (AxValue::Lst(list), AxValue::Num(n)) = (self.eval(object, env)?, self.eval(index, env)?) {
+                            if is_frozen_container(&list) {
+                                return Err(RuntimeError::FrozenMutation { type_name: "Lst".into(), span: target.span() });
+                            }
+                            let mut lst = list.write().unwrap();
+                            if n >= 0.0 && (n as usize) < lst.len() { lst[n as usize] = val.clone(); touch_collections(); }
+                        }
                     }
                     _ => {}
                 }
@@ -382,7 +1416,7 @@ impl Runtime {
                 let v = self.eval(operand, env)?;
                 match op.as_str() { "!" => Ok(AxValue::Bol(!v.is_truthy())), "-" => Ok(AxValue::Num(-v.as_num().unwrap_or(0.0))), _ => Ok(AxValue::Nil) }
             }
-            Expr::BinaryOp { left, op, right, .. } => {
+            Expr::BinaryOp { left, op, right, span } => {
                 match op.as_str() {
                     "&&" => { let l = self.eval(left, env)?; return if l.is_truthy() { self.eval(right, env) } else { Ok(l) }; }
                     "||" => { let l = self.eval(left, env)?; return if l.is_truthy() { Ok(l) } else { self.eval(right, env) }; }
@@ -390,13 +1424,16 @@ impl Runtime {
                 }
                 let l = self.eval(left, env)?; let r = self.eval(right, env)?;
                 match op.as_str() {
-                    "+"  => match (&l, &r) { (AxValue::Num(a), AxValue::Num(b)) => Ok(AxValue::Num(a + b)), _ => Ok(AxValue::Str(format!("{}{}", l.display(), r.display()))) },
+                    "+"  => match (&l, &r) {
+                        (AxValue::Num(a), AxValue::Num(b)) => Ok(AxValue::Num(a + b)),
+                        _ => { let s = format!("{}{}", l.display(), r.display()); self.charge("concat", s.len())?; Ok(AxValue::Str(s)) }
+                    },
                     "-"  => Ok(AxValue::Num(l.as_num().unwrap_or(0.0) - r.as_num().unwrap_or(0.0))),
                     "*"  => Ok(AxValue::Num(l.as_num().unwrap_or(0.0) * r.as_num().unwrap_or(0.0))),
-                    "/"  => { let d = r.as_num().unwrap_or(1.0); if d == 0.0 { return Err(RuntimeError::GenericError { message: "Division by zero".into(), span: Default::default() }); } Ok(AxValue::Num(l.as_num().unwrap_or(0.0) / d)) }
-                    "%"  => Ok(AxValue::Num(l.as_num().unwrap_or(0.0) % r.as_num().unwrap_or(1.0))),
-                    "==" => Ok(AxValue::Bol(self.values_equal(&l, &r))),
-                    "!=" => Ok(AxValue::Bol(!self.values_equal(&l, &r))),
+                    "/"  => { let d = r.as_num().unwrap_or(1.0); if d == 0.0 { return Err(RuntimeError::DivisionByZero { span: *span }); } Ok(AxValue::Num(l.as_num().unwrap_or(0.0) / d)) }
+                    "%"  => Ok(AxValue::Num(l.as_num().unwrap_or(0.0).rem_euclid(r.as_num().unwrap_or(1.0)))),
+                    "==" => Ok(AxValue::Bol(l.deep_eq(&r))),
+                    "!=" => Ok(AxValue::Bol(!l.deep_eq(&r))),
                     "<"  => Ok(AxValue::Bol(l.as_num().unwrap_or(0.0) <  r.as_num().unwrap_or(0.0))),
                     "<=" => Ok(AxValue::Bol(l.as_num().unwrap_or(0.0) <= r.as_num().unwrap_or(0.0))),
                     ">"  => Ok(AxValue::Bol(l.as_num().unwrap_or(0.0) >  r.as_num().unwrap_or(0.0))),
@@ -414,7 +1451,10 @@ impl Runtime {
                 let func = self.eval(function, env)?;
                 let mut args = Vec::with_capacity(arguments.len());
                 for arg in arguments { args.push(self.eval(arg, env)?); }
-                self.call_value(func, args, env)
+                match &**function {
+                    Expr::Identifier { name, .. } => self.call_named(name, func, args, env),
+                    _ => self.call_value(func, args, env),
+                }
             }
             Expr::MethodCall { object, method, arguments, .. } => {
                 let obj = self.eval(object, env)?;
@@ -457,17 +1497,65 @@ impl Runtime {
                                 }
                             }
                         }
+                        "bench" if matches!(&**object, Expr::Identifier { name, .. } if name == "tim") => {
+                            if let Some(fn_val @ AxValue::Fun(callable)) = args.first() {
+                                if matches!(callable.as_ref(), AxCallable::UserDefined { .. }) {
+                                    let func = fn_val.clone();
+                                    return crate::intrinsics::tim_bench_with(&args, || self.call_value(func.clone(), vec![], env));
+                                }
+                            }
+                        }
+                        "close" if matches!(&**object, Expr::Identifier { name, .. } if name == "res") => {
+                            return crate::intrinsics::res_close_with(&args, |f, a| self.call_value(f, a, env));
+                        }
+                        "scope" if matches!(&**object, Expr::Identifier { name, .. } if name == "res") => {
+                            return crate::intrinsics::res_scope_with(&args, |f, a| self.call_value(f, a, env));
+                        }
+                        #[cfg(feature = "native")]
+                        "listen" if matches!(&**object, Expr::Identifier { name, .. } if name == "rpc") => {
+                            self.capabilities.check("rpc.listen")?;
+                            return crate::intrinsics::rpc_listen_with(&args, |f, a| self.call_value(f, a, env));
+                        }
+                        "sort_by" | "sort_desc" | "sort_with" if matches!(&**object, Expr::Identifier { name, .. } if name == "alg") => {
+                            return crate::intrinsics::alg_sort_keyed(&format!("alg.{}", method), &args, |f, a| self.call_value(f, a, env));
+                        }
+                        "group_by" if matches!(&**object, Expr::Identifier { name, .. } if name == "alg") => {
+                            return crate::intrinsics::alg_group_by_with(&args, |f, a| self.call_value(f, a, env));
+                        }
+                        "with" if matches!(&**object, Expr::Identifier { name, .. } if name == "env") => {
+                            self.capabilities.check("env.with")?;
+                            return crate::intrinsics::env_with_with(&args, |f, a| self.call_value(f, a, env));
+                        }
+                        "collect" | "count" | "first" | "each" | "reduce" => {
+                            if let Some(pipeline) = crate::intrinsics::pipeline_of(&obj) {
+                                return pipeline.finish(method, &args, &mut |f, a| self.call_value(f, a, env));
+                            }
+                        }
+                        "scope" if matches!(&**object, Expr::Identifier { name, .. } if name == "log") => {
+                            if let (Some(AxValue::Str(scope)), Some(fn_val @ AxValue::Fun(_))) = (args.first(), args.get(1)) {
+                                crate::logger::push_scope(scope);
+                                let result = self.call_value(fn_val.clone(), vec![], env);
+                                crate::logger::pop_scope();
+                                return result;
+                            }
+                        }
                         _ => {}
                     }
                 }
                 // ── End higher-order intercept ───────────────────────────────
 
-                self.call_method(obj, method, args, env)
+                self.call_method_at(Some(expr), obj, method, args, env)
             }
             Expr::MemberAccess { object, member, .. } => {
                 let obj = self.eval(object, env)?;
                 match &obj {
                     AxValue::Instance(inst) => {
+                        let class = Arc::clone(&inst.read().unwrap().class);
+                        if class.has_accessors() {
+                            if let Some(getter) = self.resolve_instance_member(Some(expr), &class, member, SlotKind::Getter) {
+                                return self.call_accessor(obj.clone(), &class, member, getter, vec![], env);
+                            }
+                        }
                         let r = inst.read().unwrap();
                         if let Some(v) = r.fields.get(member) { return Ok(v.clone()); }
                         if let Some(m) = r.class.methods.get(member) { return Ok(AxValue::Fun(Arc::new(m.clone()))); }
@@ -495,32 +1583,48 @@ impl Runtime {
             Expr::New { class_name, arguments, .. } => {
                 let class = self.classes.get(class_name).cloned().ok_or_else(|| RuntimeError::GenericError { message: format!("Unknown class '{}'", class_name), span: Default::default() })?;
                 let fields: DashMap<String, AxValue> = DashMap::new();
-                for (fn_, default) in &class.fields { fields.insert(fn_.clone(), if let Some(e) = default { self.eval(e, env)? } else { AxValue::Nil }); }
+                // Ancestors' defaults first, so a subclass can override them
+                let mut lineage = vec![&class];
+                while let Some(parent) = &lineage[lineage.len() - 1].parent { lineage.push(parent); }
+                for c in lineage.into_iter().rev() {
+                    for (fn_, default) in &c.fields { fields.insert(fn_.clone(), if let Some(e) = default { self.eval(e, env)? } else { AxValue::Nil }); }
+                }
                 let inst = Arc::new(RwLock::new(AxInstance { class: Arc::clone(&class), fields }));
                 let mut args = Vec::with_capacity(arguments.len()); for arg in arguments { args.push(self.eval(arg, env)?); }
                 let iv = AxValue::Instance(Arc::clone(&inst));
-                if let Some(AxCallable::UserDefined { params, body, captured }) = class.methods.get("init").cloned() {
-                    env.push_frame();
-                    for (k, v) in &captured { env.define(k.clone(), v.clone()); }
-                    env.define("self".into(), iv.clone());
-                    for (p, a) in params.iter().zip(args.iter()) { env.define(p.clone(), a.clone()); }
-                    self.exec_block_in_env(&body, env)?; env.pop_frame();
+                if let Some(init) = class.resolve_method("init").cloned() {
+                    self.invoke_bound(iv.clone(), init, args, env)?;
                 }
                 Ok(iv)
             }
             Expr::List { items, .. } => {
                 let mut vals = Vec::with_capacity(items.len()); for item in items { vals.push(self.eval(item, env)?); }
+                self.charge("list", vals.len() * std::mem::size_of::<AxValue>())?;
                 Ok(AxValue::Lst(Arc::new(RwLock::new(vals))))
             }
             Expr::InterpolatedString { parts, .. } => {
                 let mut result = String::new();
-                for part in parts { match part { StringPart::Literal(s) => result.push_str(s), StringPart::Expr(e) => result.push_str(&self.eval(e, env)?.display()) } }
+                for part in parts {
+                    match part {
+                        StringPart::Literal(s) => result.push_str(s),
+                        StringPart::Expr(e) => result.push_str(&self.eval(e, env)?.display()),
+                        StringPart::Formatted { expr, spec } => result.push_str(&format_value(&self.eval(expr, env)?, spec)?),
+                    }
+                }
+                self.charge("interpolate", result.len())?;
                 Ok(AxValue::Str(result))
             }
             // Lambda expression: fn(params) { body } — creates a callable value.
             // This is used directly for anonymous lambdas AND for named nested
             // functions that the parser rewrites as: let name = fn(params) { body }
             // We capture the current environment as a closure snapshot.
+            Expr::Match { expr, arms, .. } => {
+                let val = self.eval(expr, env)?;
+                let Some(arm) = self.select_arm(arms, &val, env)? else { return Ok(AxValue::Nil) };
+                let result = self.eval_arm(&arm.body, env);
+                env.pop_frame();
+                result
+            }
             Expr::Lambda { params, body, .. } => {
                 let mut captured = std::collections::HashMap::new();
                 for frame in &env.frames {
@@ -529,6 +1633,7 @@ impl Runtime {
                     }
                 }
                 Ok(AxValue::Fun(Arc::new(AxCallable::UserDefined {
+                    name: None,
                     params: params.clone(),
                     body: body.clone(),
                     captured,
@@ -540,29 +1645,61 @@ impl Runtime {
 
     fn lookup(&self, name: &str, env: &Env) -> Result<AxValue, RuntimeError> {
         if let Some(v) = env.get(name) { return Ok(v.clone()); }
-        if let Some(v) = self.globals.get(name) { return Ok(v.clone()); }
-        Err(RuntimeError::UndefinedVariable { name: name.to_string(), span: Default::default() })
+        if let Some(v) = self.globals.lookup_at(name) { return Ok(v.clone()); }
+        let candidates = env.names().chain(self.globals.keys().map(String::as_str)).map(String::from).collect();
+        Err(RuntimeError::UndefinedVariable { name: name.to_string(), span: Default::default(), candidates })
     }
 
     pub fn call_value(&self, func: AxValue, args: Vec<AxValue>, env: &mut Env) -> Result<AxValue, RuntimeError> {
-        let depth = self.call_depth.get();
-        if depth >= MAX_CALL_DEPTH {
-            return Err(RuntimeError::GenericError {
-                message: "[AXM_408] Call stack overflow — frame limit reached. Check for infinite recursion.".to_string(),
-                span: Default::default(),
-            });
-        }
-        self.call_depth.set(depth + 1);
+        let name = match &func {
+            AxValue::Fun(callable) => match &**callable {
+                AxCallable::Native { name, .. } => name.clone(),
+                AxCallable::UserDefined { .. } => "<fn>".to_string(),
+            },
+            _ => "<value>".to_string(),
+        };
+        self.call_named(&name, func, args, env)
+    }
+
+    /// Call `func`, recording `name` in the call chain reported on overflow.
+    fn call_named(&self, name: &str, func: AxValue, args: Vec<AxValue>, env: &mut Env) -> Result<AxValue, RuntimeError> {
+        self.enter_call(name)?;
         let result = self.call_value_inner(func, args, env);
-        self.call_depth.set(depth);
+        self.exit_call();
         result
     }
 
+    fn enter_call(&self, name: &str) -> Result<(), RuntimeError> {
+        self.meter.tick()?;
+        let mut stack = self.call_stack.borrow_mut();
+        if stack.len() >= self.max_call_depth {
+            return Err(RuntimeError::StackOverflow { limit: self.max_call_depth, call_chain: stack.clone() });
+        }
+        stack.push(name.to_string());
+        if self.stepping {
+            let depth = stack.len();
+            drop(stack);
+            self.hooks.lock().call(&CallEvent { name, phase: Phase::Before, depth });
+        }
+        Ok(())
+    }
+
+    fn exit_call(&self) {
+        let name = self.call_stack.borrow_mut().pop();
+        if let (true, Some(name)) = (self.stepping, name) {
+            let depth = self.call_stack.borrow().len() + 1;
+            self.hooks.lock().call(&CallEvent { name: &name, phase: Phase::After, depth });
+        }
+    }
+
     fn call_value_inner(&self, func: AxValue, args: Vec<AxValue>, env: &mut Env) -> Result<AxValue, RuntimeError> {
         match func {
             AxValue::Fun(callable) => match &*callable {
-                AxCallable::Native { func, .. } => Ok(func(args)),
-                AxCallable::UserDefined { params, body, captured } => {
+                AxCallable::Native { name, func } => {
+                    self.capabilities.check(name)?;
+                    catch_native(name, || func(args))
+                }
+                AxCallable::UserDefined { params, body, captured, .. } => {
                     if args.len() != params.len() {
                         return Err(RuntimeError::ArityMismatch {
                             expected: params.len(),
@@ -572,9 +1709,9 @@ impl Runtime {
                     env.push_frame();
                     // Inject captured closure variables first (so params can override them)
                     for (k, v) in captured {
-                        env.define(k.clone(), v.clone());
+                        env.define_interned(k.clone(), v.clone());
                     }
-                    for (p, a) in params.iter().zip(args.iter()) { env.define(p.clone(), a.clone()); }
+                    for (p, a) in params.iter().zip(args.iter()) { env.define(p, a.clone()); }
                     let ret = self.exec_block_in_env(body, env)?; env.pop_frame();
                     Ok(ret.unwrap_or(AxValue::Nil))
                 }
@@ -582,69 +1719,123 @@ impl Runtime {
             AxValue::Nil => Err(RuntimeError::NilCall {
                 hint: "Value is nil — check that the variable is assigned before use (AXM_402)".into(),
                 span: Default::default(),
+                name: None,
+                candidates: Vec::new(),
             }),
             _ => Err(RuntimeError::GenericError { message: format!("Not callable: {}", func.type_name()), span: Default::default() }),
         }
     }
 
-    fn call_method(&self, obj: AxValue, method: &str, args: Vec<AxValue>, env: &mut Env) -> Result<AxValue, RuntimeError> {
-        let depth = self.call_depth.get();
-        if depth >= MAX_CALL_DEPTH {
-            return Err(RuntimeError::GenericError {
-                message: "[AXM_408] Call stack overflow — frame limit reached.".to_string(),
-                span: Default::default(),
-            });
+    /// `site` is the `MethodCall` expression, when there is one, for the inline cache.
+    fn call_method_at(&self, site: Option<&Expr>, obj: AxValue, method: &str, args: Vec<AxValue>, env: &mut Env) -> Result<AxValue, RuntimeError> {
+        let name = match &obj {
+            AxValue::Instance(inst) => format!("{}.{}", inst.read().unwrap().class.name, method),
+            _ => method.to_string(),
+        };
+        self.enter_call(&name)?;
+        let result = self.call_method_inner(site, obj, method, args, env);
+        self.exit_call();
+        result
+    }
+
+    /// Find the `kind` member `name` (a method, getter or setter) on `class`
+    /// or its parents, through the site's inline cache if any.
+    fn resolve_instance_member(&self, site: Option<&Expr>, class: &Arc<AxClass>, name: &str, kind: SlotKind) -> Option<AxCallable> {
+        let table = |class: &AxClass| match kind {
+            SlotKind::Getter => class.getters.get(name).cloned(),
+            SlotKind::Setter => class.setters.get(name).cloned(),
+            _ => class.methods.get(name).cloned(),
+        };
+        let ancestor = |depth: usize| {
+            let mut class = class;
+            for _ in 0..depth { class = class.parent.as_ref()?; }
+            Some(class)
+        };
+        let Some(ics) = site.and(self.method_ics.as_ref()) else {
+            return (0..).map_while(ancestor).find_map(|c| table(c));
+        };
+        let site = site?;
+        let mut ics = ics.borrow_mut();
+        let next_id = ics.shapes.len() as u32 + 1;
+        let shape = *ics.shapes.entry(Arc::as_ptr(class) as usize).or_insert(next_id);
+        let (_, _, ic) = ics.sites.entry(site as *const Expr as usize)
+            .or_insert_with(|| (name.to_string(), site.span().start, PropIC::new()));
+        if let Some(entry) = ic.lookup_entry(shape) {
+            if entry.kind == SlotKind::Field { return None; }
+            return table(ancestor(entry.slot as usize)?);
+        }
+        match (0..).map_while(ancestor).position(|c| table(c).is_some()) {
+            Some(depth) => {
+                ic.update(shape, depth as u16, kind);
+                table(ancestor(depth)?)
+            }
+            None => {
+                // Remember that reads/writes here go straight to the field
+                if kind != SlotKind::Method { ic.update(shape, 0, SlotKind::Field); }
+                None
+            }
         }
-        self.call_depth.set(depth + 1);
-        let result = self.call_method_inner(obj, method, args, env);
-        self.call_depth.set(depth);
+    }
+
+    /// Run a `get`/`set` accessor with `self` bound to `obj`.
+    fn call_accessor(&self, obj: AxValue, class: &AxClass, name: &str, accessor: AxCallable, args: Vec<AxValue>, env: &mut Env) -> Result<AxValue, RuntimeError> {
+        self.enter_call(&format!("{}.{}", class.name, name))?;
+        let result = self.invoke_bound(obj, accessor, args, env);
+        self.exit_call();
         result
     }
 
-    fn call_method_inner(&self, obj: AxValue, method: &str, args: Vec<AxValue>, env: &mut Env) -> Result<AxValue, RuntimeError> {
+    /// Call `callable` as a method of `obj`.
+    fn invoke_bound(&self, obj: AxValue, callable: AxCallable, args: Vec<AxValue>, env: &mut Env) -> Result<AxValue, RuntimeError> {
+        match callable {
+            AxCallable::UserDefined { params, body, captured, .. } => {
+                env.push_frame();
+                for (k, v) in &captured { env.define_interned(k.clone(), v.clone()); }
+                env.define("self", obj);
+                for (p, a) in params.iter().zip(args.iter()) { env.define(p, a.clone()); }
+                let ret = self.exec_block_in_env(&body, env)?; env.pop_frame();
+                Ok(ret.unwrap_or(AxValue::Nil))
+            }
+            AxCallable::Native { name, func } => { self.capabilities.check(&name)?; catch_native(&name, || func(args)) }
+        }
+    }
+
+    fn call_method_inner(&self, site: Option<&Expr>, obj: AxValue, method: &str, args: Vec<AxValue>, env: &mut Env) -> Result<AxValue, RuntimeError> {
         match &obj {
             AxValue::Instance(inst) => {
-                let callable = { inst.read().unwrap().class.methods.get(method).cloned() };
-                match callable {
-                    Some(AxCallable::UserDefined { params, body, captured }) => {
-                        env.push_frame();
-                        for (k, v) in &captured { env.define(k.clone(), v.clone()); }
-                        env.define("self".into(), obj.clone());
-                        for (p, a) in params.iter().zip(args.iter()) { env.define(p.clone(), a.clone()); }
-                        let ret = self.exec_block_in_env(&body, env)?; env.pop_frame();
-                        Ok(ret.unwrap_or(AxValue::Nil))
-                    }
-                    Some(AxCallable::Native { func, .. }) => Ok(func(args)),
+                let class = Arc::clone(&inst.read().unwrap().class);
+                match self.resolve_instance_member(site, &class, method, SlotKind::Method) {
+                    Some(callable) => self.invoke_bound(obj.clone(), callable, args, env),
                     None => Err(RuntimeError::GenericError { message: format!("No method '{}' on instance", method), span: Default::default() }),
                 }
             }
             AxValue::Map(map) => {
-                if let Some(v) = map.get(method) { return self.call_value((*v).clone(), args, env); }
-                Err(RuntimeError::GenericError { message: format!("No method '{}' on Map", method), span: Default::default() })
-            }
-            AxValue::Str(s) => {
-                match method {
-                    "len"       => Ok(AxValue::Num(s.len() as f64)),
-                    "upper"     => Ok(AxValue::Str(s.to_uppercase())),
-                    "lower"     => Ok(AxValue::Str(s.to_lowercase())),
-                    "trim"      => Ok(AxValue::Str(s.trim().to_string())),
-                    "split"     => { let sep = args.first().and_then(|a| if let AxValue::Str(s) = a { Some(s.as_str()) } else { None }).unwrap_or(" "); Ok(AxValue::Lst(Arc::new(RwLock::new(s.split(sep).map(|p| AxValue::Str(p.to_string())).collect())))) }
-                    "contains"  => Ok(AxValue::Bol(s.contains(&args.first().map(|a| a.display()).unwrap_or_default()))),
-                    "starts_with" => Ok(AxValue::Bol(s.starts_with(&args.first().map(|a| a.display()).unwrap_or_default()))),
-                    "ends_with" => Ok(AxValue::Bol(s.ends_with(&args.first().map(|a| a.display()).unwrap_or_default()))),
-                    "replace"   => { let from = args.first().map(|a| a.display()).unwrap_or_default(); let to = args.get(1).map(|a| a.display()).unwrap_or_default(); Ok(AxValue::Str(s.replace(&from, &to))) }
-                    "align"     => { let w = args.first().and_then(|a| a.as_num().ok()).unwrap_or(0.0) as usize; let d = args.get(1).map(|a| a.display()).unwrap_or_else(|| "left".into()); Ok(AxValue::Str(match d.as_str() { "right" => format!("{:>width$}", s, width=w), "center" => format!("{:^width$}", s, width=w), _ => format!("{:<width$}", s, width=w) })) }
-                    _ => Err(RuntimeError::GenericError { message: format!("No method '{}' on Str", method), span: Default::default() }),
-                }
+                // Clone out first: the entry's shard stays locked while the guard lives
+                let found = map.get(method).map(|v| v.clone());
+                if let Some(v) = found { return self.call_value(v, args, env); }
+                Err(RuntimeError::NilCall {
+                    hint: format!("No method '{}' on Map", method),
+                    span: Default::default(),
+                    name: Some(method.to_string()),
+                    candidates: map.iter().map(|e| e.key().clone()).collect(),
+                })
             }
+            AxValue::Str(s) => str_method(s, method, &args),
             AxValue::Lst(list) => {
                 match method {
                     "len"      => Ok(AxValue::Num(list.read().unwrap().len() as f64)),
-                    "push"     => { if let Some(v) = args.into_iter().next() { list.write().unwrap().push(v); } Ok(AxValue::Nil) }
-                    "pop"      => Ok(list.write().unwrap().pop().unwrap_or(AxValue::Nil)),
+                    "push" | "pop" if is_frozen_container(list) => Err(RuntimeError::FrozenMutation { type_name: "Lst".into(), span: Default::default() }),
+                    "push"     => { if let Some(v) = args.into_iter().next() { self.charge("list.push", std::mem::size_of::<AxValue>())?; list.write().unwrap().push(v); touch_collections(); } Ok(AxValue::Nil) }
+                    "pop"      => { touch_collections(); Ok(list.write().unwrap().pop().unwrap_or(AxValue::Nil)) }
+                    "reserve"  => {
+                        let n = args.first().and_then(|a| a.as_num().ok()).unwrap_or(0.0);
+                        self.charge("list.reserve", (n.max(0.0) as usize).saturating_mul(std::mem::size_of::<AxValue>()))?;
+                        list_reserve(&mut list.write().unwrap(), n)?;
+                        Ok(AxValue::Nil)
+                    }
                     "first"    => Ok(list.read().unwrap().first().cloned().unwrap_or(AxValue::Nil)),
                     "last"     => Ok(list.read().unwrap().last().cloned().unwrap_or(AxValue::Nil)),
-                    "contains" => { let needle = args.first().cloned().unwrap_or(AxValue::Nil); Ok(AxValue::Bol(list.read().unwrap().iter().any(|v| self.values_equal(v, &needle)))) }
+                    "contains" => { let needle = args.first().cloned().unwrap_or(AxValue::Nil); Ok(AxValue::Bol(list.read().unwrap().iter().any(|v| v.deep_eq(&needle)))) }
                     "join"     => { let sep = args.first().map(|a| a.display()).unwrap_or_default(); Ok(AxValue::Str(list.read().unwrap().iter().map(|v: &AxValue| v.display()).collect::<Vec<_>>().join(&sep))) }
                     _ => Err(RuntimeError::GenericError { message: format!("No method '{}' on List", method), span: Default::default() }),
                 }
@@ -653,3 +1844,49 @@ impl Runtime {
         }
     }
 }
+
+/// `xs.reserve(n)`: make room for `n` more items so the next pushes do not
+/// reallocate. Shared by both engines.
+/// `@(expr:spec)` on either engine: `value` formatted by `spec`.
+fn format_value(value: &AxValue, spec: &str) -> Result<String, RuntimeError> {
+    crate::core::format::Spec::parse(spec)
+        .map_err(|message| RuntimeError::GenericError { message, span: Span::default() })?
+        .apply(value)
+}
+
+/// Where an error raised by `stmt` itself, rather than by a statement
+/// inside its body, is reported: the condition, iterable or scrutinee of a
+/// compound statement, else the whole statement.
+fn error_span(stmt: &Stmt) -> Span {
+    match stmt {
+        Stmt::If { condition, .. } | Stmt::While { condition, .. } => condition.span(),
+        Stmt::For { iterable, .. } => iterable.span(),
+        Stmt::Match { expr, .. } => expr.span(),
+        other => other.span(),
+    }
+}
+
+pub(crate) fn list_reserve<T>(items: &mut Vec<T>, additional: f64) -> Result<(), RuntimeError> {
+    let n = additional.max(0.0) as usize;
+    items.try_reserve_exact(n).map_err(|_| RuntimeError::GenericError {
+        message: format!("Cannot reserve room for {} list items", n),
+        span: Default::default(),
+    })
+}
+
+/// Built-in string methods (`s.upper()`, `s.split(",")`, …), shared by both engines.
+pub(crate) fn str_method(s: &str, method: &str, args: &[AxValue]) -> Result<AxValue, RuntimeError> {
+    match method {
+        "len"       => Ok(AxValue::Num(s.len() as f64)),
+        "upper"     => Ok(AxValue::Str(s.to_uppercase())),
+        "lower"     => Ok(AxValue::Str(s.to_lowercase())),
+        "trim"      => Ok(AxValue::Str(s.trim().to_string())),
+        "split"     => { let sep = args.first().and_then(|a| if let AxValue::Str(s) = a { Some(s.as_str()) } else { None }).unwrap_or(" "); Ok(AxValue::Lst(Arc::new(RwLock::new(s.split(sep).map(|p| AxValue::Str(p.to_string())).collect())))) }
+        "contains"  => Ok(AxValue::Bol(s.contains(&args.first().map(|a| a.display()).unwrap_or_default()))),
+        "starts_with" => Ok(AxValue::Bol(s.starts_with(&args.first().map(|a| a.display()).unwrap_or_default()))),
+        "ends_with" => Ok(AxValue::Bol(s.ends_with(&args.first().map(|a| a.display()).unwrap_or_default()))),
+        "replace"   => { let from = args.first().map(|a| a.display()).unwrap_or_default(); let to = args.get(1).map(|a| a.display()).unwrap_or_default(); Ok(AxValue::Str(s.replace(&from, &to))) }
+        "align"     => { let w = args.first().and_then(|a| a.as_num().ok()).unwrap_or(0.0) as usize; let d = args.get(1).map(|a| a.display()).unwrap_or_else(|| "left".into()); Ok(AxValue::Str(match d.as_str() { "right" => format!("{:>width$}", s, width=w), "center" => format!("{:^width$}", s, width=w), _ => format!("{:<width$}", s, width=w) })) }
+        _ => Err(RuntimeError::GenericError { message: format!("No method '{}' on Str", method), span: Default::default() }),
+    }
+}
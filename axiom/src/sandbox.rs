@@ -0,0 +1,563 @@
+//! Axiom execution sandbox — bounds for embedded and untrusted scripts.
+//!
+//! `ResourceLimits` is the embedding-facing configuration; `Meter` is the
+//! per-run accounting both engines consult:
+//!
+//!   • instruction budget — tree-walker: statements, loop iterations and
+//!     calls; VmCore: dispatched bytecode instructions
+//!   • wall-clock timeout — checked every `CLOCK_CHECK_INTERVAL` ticks
+//!   • allocation ceiling — approximate bytes of strings and list slots
+//!     created by the script (not a global allocator hook)
+//!
+//! An interrupt (Ctrl-C or `--timeout` under `axiom run`) is a process-wide
+//! flag that the same ticks poll, so either engine stops with
+//! `RuntimeError::Interrupted` at the next check.
+//!
+//! `Capabilities` gates the side-effecting intrinsic groups (filesystem,
+//! network, process, usb, env mutation, ffi); a denied call raises
+//! `RuntimeError::PermissionDenied` instead of running. The check sits in
+//! the gated natives themselves (`AxCallable::native` wraps them), against
+//! the capabilities of the run active on the thread, so a gated native
+//! passed as a callback (`alg.map(paths, ioo.read)`) is checked too.
+
+use crate::errors::RuntimeError;
+use std::cell::Cell;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::time::{Duration, Instant};
+
+/// How many ticks pass between wall-clock and interrupt checks (Instant::now
+/// is not free).
+const CLOCK_CHECK_INTERVAL: u64 = 1024;
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Ask the running script to stop; safe to call from a signal thread.
+pub fn interrupt() {
+    INTERRUPTED.store(true, Ordering::SeqCst);
+}
+
+/// Whether an interrupt is pending.
+pub fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::Relaxed)
+}
+
+/// Drop a pending interrupt (at the start of a run, and before exit hooks).
+pub fn clear_interrupt() {
+    INTERRUPTED.store(false, Ordering::SeqCst);
+}
+
+/// Interrupts the run once `timeout` has passed (`axiom run --timeout`).
+/// Unlike `ResourceLimits::timeout`, this also stops a script blocked in
+/// `aut.sleep`, `rpc.listen` or anything else that polls the interrupt.
+/// Dropping the watchdog disarms it.
+pub struct Watchdog {
+    fired: Arc<AtomicBool>,
+    _disarm: mpsc::Sender<()>,
+}
+
+impl Watchdog {
+    /// Arm a watchdog. If the script is still running `grace` after the
+    /// interrupt, `stuck` is called on the watchdog's thread.
+    pub fn start(timeout: Duration, grace: Duration, stuck: impl FnOnce() + Send + 'static) -> Watchdog {
+        let fired = Arc::new(AtomicBool::new(false));
+        let (disarm, disarmed) = mpsc::channel::<()>();
+        let flag = Arc::clone(&fired);
+        let _ = std::thread::Builder::new().name("axiom-watchdog".into()).spawn(move || {
+            if disarmed.recv_timeout(timeout) != Err(mpsc::RecvTimeoutError::Timeout) {
+                return;
+            }
+            flag.store(true, Ordering::SeqCst);
+            interrupt();
+            if disarmed.recv_timeout(grace) == Err(mpsc::RecvTimeoutError::Timeout) {
+                stuck();
+            }
+        });
+        Watchdog { fired, _disarm: disarm }
+    }
+
+    /// Whether the timeout passed and the run was interrupted.
+    pub fn fired(&self) -> bool {
+        self.fired.load(Ordering::SeqCst)
+    }
+}
+
+/// A duration such as `30s`, `500ms`, `2m` or `1.5h`; a bare number is seconds.
+pub fn parse_duration(text: &str) -> Result<Duration, String> {
+    let text = text.trim();
+    let split = text.find(|c: char| !(c.is_ascii_digit() || c == '.')).unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let scale = match unit.trim() {
+        "ms" => 0.001,
+        "" | "s" => 1.0,
+        "m" | "min" => 60.0,
+        "h" => 3600.0,
+        other => return Err(format!("unknown unit '{}' in '{}' (use ms, s, m or h)", other, text)),
+    };
+    match number.parse::<f64>() {
+        Ok(n) if n > 0.0 => Ok(Duration::from_secs_f64(n * scale)),
+        _ => Err(format!("'{}' is not a positive duration like 30s or 500ms", text)),
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResourceLimits {
+    pub max_instructions: Option<u64>,
+    pub timeout: Option<Duration>,
+    pub max_alloc_bytes: Option<usize>,
+}
+
+impl ResourceLimits {
+    /// No limits — the default for `axiom run`.
+    pub fn unlimited() -> Self {
+        Self::default()
+    }
+
+    pub fn with_max_instructions(mut self, n: u64) -> Self {
+        self.max_instructions = Some(n);
+        self
+    }
+
+    pub fn with_timeout(mut self, t: Duration) -> Self {
+        self.timeout = Some(t);
+        self
+    }
+
+    pub fn with_max_alloc_bytes(mut self, n: usize) -> Self {
+        self.max_alloc_bytes = Some(n);
+        self
+    }
+
+    pub fn is_unlimited(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Which limit a script ran into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resource {
+    Instructions,
+    WallClock,
+    Memory,
+}
+
+impl fmt::Display for Resource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Resource::Instructions => write!(f, "instruction budget"),
+            Resource::WallClock    => write!(f, "wall-clock timeout"),
+            Resource::Memory       => write!(f, "allocation ceiling"),
+        }
+    }
+}
+
+impl Resource {
+    /// A limit of this resource with its unit: `10000 steps`, `1s`, `1500ms`,
+    /// `4096 bytes` (wall-clock limits are in milliseconds).
+    pub fn format_limit(self, limit: u64) -> String {
+        match self {
+            Resource::Instructions => format!("{} steps", limit),
+            Resource::WallClock => match Duration::from_millis(limit) {
+                d if limit > 0 && d.subsec_millis() == 0 => format!("{}s", d.as_secs()),
+                _ => format!("{}ms", limit),
+            },
+            Resource::Memory => format!("{} bytes", limit),
+        }
+    }
+}
+
+/// Running totals for one execution, checked against `ResourceLimits`.
+#[derive(Debug)]
+pub struct Meter {
+    limits: ResourceLimits,
+    /// Only read when a timeout is set (Instant::now panics on wasm32).
+    started: Option<Instant>,
+    instructions: Cell<u64>,
+    allocated: Cell<usize>,
+}
+
+impl Meter {
+    pub fn new(limits: ResourceLimits) -> Self {
+        Meter { limits, started: limits.timeout.map(|_| Instant::now()), instructions: Cell::new(0), allocated: Cell::new(0) }
+    }
+
+    pub fn limits(&self) -> ResourceLimits {
+        self.limits
+    }
+
+    /// Restart the clock and zero the counters (called at the start of a run).
+    pub fn reset(&mut self) {
+        self.started = self.limits.timeout.map(|_| Instant::now());
+        self.instructions.set(0);
+        self.allocated.set(0);
+    }
+
+    pub fn instructions(&self) -> u64 {
+        self.instructions.get()
+    }
+
+    pub fn allocated(&self) -> usize {
+        self.allocated.get()
+    }
+
+    /// Count one unit of work.
+    #[inline]
+    pub fn tick(&self) -> Result<(), RuntimeError> {
+        let n = self.instructions.get() + 1;
+        self.instructions.set(n);
+        if let Some(max) = self.limits.max_instructions {
+            if n > max {
+                return Err(RuntimeError::LimitExceeded { resource: Resource::Instructions, limit: max });
+            }
+        }
+        if n.is_multiple_of(CLOCK_CHECK_INTERVAL) {
+            if interrupted() {
+                return Err(RuntimeError::Interrupted);
+            }
+            self.check_clock()?;
+        }
+        Ok(())
+    }
+
+    pub fn check_clock(&self) -> Result<(), RuntimeError> {
+        match (self.limits.timeout, self.started) {
+            (Some(t), Some(started)) if started.elapsed() > t => Err(RuntimeError::LimitExceeded {
+                resource: Resource::WallClock,
+                limit: t.as_millis() as u64,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Record `bytes` of script-visible allocation (also reported to the GC heap).
+    pub fn charge(&self, bytes: usize) -> Result<(), RuntimeError> {
+        let total = self.allocated.get().saturating_add(bytes);
+        self.allocated.set(total);
+        crate::gc::note_alloc(bytes);
+        match self.limits.max_alloc_bytes {
+            Some(max) if total > max => Err(RuntimeError::LimitExceeded { resource: Resource::Memory, limit: max as u64 }),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Default for Meter {
+    fn default() -> Self {
+        Meter::new(ResourceLimits::unlimited())
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Capabilities
+// ---------------------------------------------------------------------------
+
+/// A group of intrinsics with effects outside the script.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Capability {
+    /// ioo, pth, csv.write, log.to_file, plt, git
+    FileSystem,
+    /// net, rpc, git.clone
+    Network,
+    /// cli.exec, cli.shell, chdir
+    Process,
+    /// usb
+    Usb,
+    /// env.set, env.load
+    EnvWrite,
+    /// ffi — native code runs outside every other check
+    Ffi,
+}
+
+impl Capability {
+    pub const ALL: [Capability; 6] = [
+        Capability::FileSystem, Capability::Network, Capability::Process,
+        Capability::Usb, Capability::EnvWrite, Capability::Ffi,
+    ];
+
+    /// Short name used by the `sandbox_deny` conf property.
+    pub fn name(self) -> &'static str {
+        match self {
+            Capability::FileSystem => "fs",
+            Capability::Network    => "net",
+            Capability::Process    => "process",
+            Capability::Usb        => "usb",
+            Capability::EnvWrite   => "env",
+            Capability::Ffi        => "ffi",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Capability> {
+        Capability::ALL.into_iter().find(|c| c.name() == name)
+    }
+
+    fn bit(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+impl fmt::Display for Capability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Capabilities an intrinsic needs, keyed by its registered native name.
+pub fn required_capabilities(native: &str) -> &'static [Capability] {
+    let module = native.split_once('.').map(|(m, _)| m).unwrap_or("");
+    match (module, native) {
+        (_, "git.clone")                          => &[Capability::Network, Capability::FileSystem],
+        (_, "cli.exec") | (_, "cli.shell")        => &[Capability::Process],
+        (_, "chdir") | (_, "sys.chdir")           => &[Capability::Process],
+        (_, "sys.kill")                           => &[Capability::Process],
+        (_, "env.set") | (_, "env.load")          => &[Capability::EnvWrite],
+        (_, "env.unset") | (_, "env.with")        => &[Capability::EnvWrite],
+        (_, "env.save")                           => &[Capability::FileSystem],
+        (_, "csv.write") | (_, "log.to_file")     => &[Capability::FileSystem],
+        ("ioo", _) | ("pth", _) | ("plt", _) | ("git", _) => &[Capability::FileSystem],
+        ("net", _) | ("rpc", _)                   => &[Capability::Network],
+        ("usb", _)                                => &[Capability::Usb],
+        ("ffi", _)                                => &[Capability::Ffi],
+        _                                         => &[],
+    }
+}
+
+/// The set of capabilities granted to a script. Everything is granted by
+/// default; embedders narrow it with `deny` or start from `pure()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Capabilities {
+    denied: u8,
+}
+
+impl Capabilities {
+    pub fn all() -> Self {
+        Capabilities { denied: 0 }
+    }
+
+    /// Pure computation only — every capability denied.
+    pub fn pure() -> Self {
+        Capability::ALL.into_iter().fold(Self::all(), Self::deny)
+    }
+
+    pub fn deny(mut self, cap: Capability) -> Self {
+        self.denied |= cap.bit();
+        self
+    }
+
+    pub fn allow(mut self, cap: Capability) -> Self {
+        self.denied &= !cap.bit();
+        self
+    }
+
+    pub fn permits(&self, cap: Capability) -> bool {
+        self.denied & cap.bit() == 0
+    }
+
+    /// Parse a comma-separated deny list such as `"fs,net"` (`"all"` = pure).
+    pub fn from_deny_list(list: &str) -> Result<Self, String> {
+        let mut caps = Self::all();
+        for name in list.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            if name == "all" {
+                caps = Self::pure();
+                continue;
+            }
+            let cap = Capability::from_name(name).ok_or_else(|| format!(
+                "Unknown capability '{}' (expected one of: {}, all)",
+                name,
+                Capability::ALL.map(Capability::name).join(", "),
+            ))?;
+            caps = caps.deny(cap);
+        }
+        Ok(caps)
+    }
+
+    /// Capabilities from the `sandbox_deny` conf property; an invalid list
+    /// denies everything rather than silently granting access.
+    pub fn from_conf(conf: &crate::conf::AxConf) -> Self {
+        Self::from_deny_list(conf.sandbox_deny()).unwrap_or_else(|_| Self::pure())
+    }
+
+    /// Fail with PermissionDenied if the intrinsic `native` is not granted.
+    pub fn check(&self, native: &str) -> Result<(), RuntimeError> {
+        match required_capabilities(native).iter().find(|c| !self.permits(**c)) {
+            Some(cap) => Err(RuntimeError::PermissionDenied { function: native.to_string(), capability: *cap }),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Default for Capabilities {
+    fn default() -> Self {
+        Self::all()
+    }
+}
+
+thread_local! {
+    /// The capabilities of the run on this thread; everything outside a run.
+    static ACTIVE: Cell<Capabilities> = const { Cell::new(Capabilities { denied: 0 }) };
+}
+
+/// The capabilities gated natives on this thread are checked against.
+pub fn active_capabilities() -> Capabilities {
+    ACTIVE.with(Cell::get)
+}
+
+/// Run `f` with `caps` active on this thread, restoring the previous set
+/// afterwards (even if `f` panics). A nested run only narrows what is
+/// active: a capability denied outside stays denied inside.
+pub fn with_capabilities<T>(caps: Capabilities, f: impl FnOnce() -> T) -> T {
+    struct Restore(Capabilities);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            ACTIVE.with(|a| a.set(self.0));
+        }
+    }
+    let previous = ACTIVE.with(Cell::get);
+    let _restore = Restore(previous);
+    ACTIVE.with(|a| a.set(Capabilities { denied: previous.denied | caps.denied }));
+    f()
+}
+
+/// `Capabilities::check` against the active set (the gated natives' guard).
+pub(crate) fn check_active(native: &str) -> Result<(), RuntimeError> {
+    active_capabilities().check(native)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Parser, Runtime};
+
+    fn run_limited(src: &str, limits: ResourceLimits) -> Result<(), RuntimeError> {
+        let items = Parser::new(src, 0).parse().expect("parse");
+        let mut rt = Runtime::new();
+        rt.set_limits(limits);
+        rt.run(items)
+    }
+
+    #[test]
+    fn test_meter_instruction_budget() {
+        let meter = Meter::new(ResourceLimits::unlimited().with_max_instructions(2));
+        assert!(meter.tick().is_ok());
+        assert!(meter.tick().is_ok());
+        assert!(matches!(meter.tick(), Err(RuntimeError::LimitExceeded { resource: Resource::Instructions, limit: 2 })));
+    }
+
+    #[test]
+    fn test_runaway_loop_hits_budget() {
+        let err = run_limited("while true { }", ResourceLimits::unlimited().with_max_instructions(10_000));
+        assert!(matches!(err, Err(RuntimeError::LimitExceeded { resource: Resource::Instructions, .. })));
+
+        let err = run_limited("while true { }", ResourceLimits::unlimited().with_timeout(Duration::from_millis(20)));
+        assert!(matches!(err, Err(RuntimeError::LimitExceeded { resource: Resource::WallClock, .. })));
+    }
+
+    #[test]
+    fn test_limits_render_with_units() {
+        assert_eq!(Resource::WallClock.format_limit(1000), "1s");
+        assert_eq!(Resource::WallClock.format_limit(1500), "1500ms");
+        let err = RuntimeError::LimitExceeded { resource: Resource::WallClock, limit: 1000 };
+        let diag = crate::diagnostics::DiagnosticEngine::new("t.ax", "").from_runtime(&err);
+        assert!(diag.message.contains("wall-clock timeout of 1s"), "{}", diag.message);
+        assert!(err.to_string().contains("wall-clock timeout of 1s"), "{}", err);
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("30s"), Ok(Duration::from_secs(30)));
+        assert_eq!(parse_duration("500ms"), Ok(Duration::from_millis(500)));
+        assert_eq!(parse_duration("2m"), Ok(Duration::from_secs(120)));
+        assert_eq!(parse_duration("1.5h"), Ok(Duration::from_secs(5400)));
+        assert_eq!(parse_duration("45"), Ok(Duration::from_secs(45)));
+        assert!(parse_duration("0s").is_err());
+        assert!(parse_duration("10d").unwrap_err().contains("unknown unit"));
+        assert!(parse_duration("s").is_err());
+    }
+
+    #[test]
+    fn test_allocation_ceiling() {
+        let src = "let s = \"x\"\nwhile true { s = s + s }";
+        let err = run_limited(src, ResourceLimits::unlimited().with_max_alloc_bytes(1 << 16));
+        assert!(matches!(err, Err(RuntimeError::LimitExceeded { resource: Resource::Memory, limit: 65536 })));
+        assert!(run_limited("let x = [1, 2, 3]", ResourceLimits::unlimited().with_max_alloc_bytes(1 << 16)).is_ok());
+    }
+
+    #[test]
+    fn test_capability_deny_list() {
+        let caps = Capabilities::from_deny_list("fs, net").unwrap();
+        assert!(!caps.permits(Capability::FileSystem));
+        assert!(caps.permits(Capability::Process));
+        assert!(caps.check("mth.sqrt").is_ok());
+        assert!(matches!(
+            caps.check("git.clone"),
+            Err(RuntimeError::PermissionDenied { capability: Capability::Network, .. })
+        ));
+        assert!(Capabilities::from_deny_list("ffi").unwrap().check("ffi.load").is_err());
+        assert!(Capabilities::from_deny_list("process").unwrap().check("sys.kill").is_err());
+        assert_eq!(Capabilities::from_deny_list("all").unwrap(), Capabilities::pure());
+        assert!(Capabilities::from_deny_list("disk").is_err());
+    }
+
+    #[test]
+    fn test_denied_intrinsic_raises() {
+        let items = Parser::new("let s = ioo.read(\"/etc/hostname\")", 0).parse().expect("parse");
+        let mut rt = Runtime::new();
+        rt.set_capabilities(Capabilities::pure());
+        let err = rt.run(items).unwrap_err();
+        assert!(matches!(err, RuntimeError::PermissionDenied { ref function, capability: Capability::FileSystem } if function == "ioo.read"));
+    }
+
+    #[test]
+    fn test_gated_native_as_callback_is_checked() {
+        use crate::runtime::Engine;
+        let paths = "[\"/etc/hostname\", \"/etc/hostname\"]";
+        let sources = [
+            format!("let r = alg.map({}, ioo.read)\nout r", paths),
+            format!("let r = alg.filter({}, ioo.exists)\nout r", paths),
+            format!("out alg.pipeline({}).map(ioo.read).collect()", paths),
+        ];
+        for src in &sources {
+            for engine in [Engine::Interp, Engine::Vm] {
+                let mut rt = Runtime::new();
+                rt.set_engine(engine);
+                rt.set_capabilities(Capabilities::pure());
+                let (result, output) = rt.run_captured(Parser::new(src, 0).parse().expect("parse"));
+                assert!(matches!(result, Err(RuntimeError::PermissionDenied { capability: Capability::FileSystem, .. })), "{:?} {}: {:?}", engine, src, result);
+                assert!(output.is_empty(), "{}", output);
+            }
+        }
+        // Outside a run nothing is denied, and a nested run cannot widen
+        assert!(check_active("ioo.read").is_ok());
+        with_capabilities(Capabilities::pure(), || with_capabilities(Capabilities::all(), || {
+            assert!(check_active("ioo.read").is_err());
+        }));
+    }
+
+    #[test]
+    fn test_module_loading_needs_fs_outside_the_module_root() {
+        use crate::runtime::Engine;
+        let dir = std::env::temp_dir().join(format!("axiom-sandbox-load-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("bundle")).unwrap();
+        std::fs::write(dir.join("secret.ax"), "secret line: not axiom\n").unwrap();
+        std::fs::write(dir.join("bundle/util.ax"), "exp twice\nfun twice(x) { ret x * 2 }\n").unwrap();
+        let outside = [format!("load \"{}\"", dir.join("secret.ax").display()), "loc ..secret".to_string()];
+        for engine in [Engine::Interp, Engine::Vm] {
+            for src in &outside {
+                let mut rt = Runtime::new();
+                rt.set_engine(engine);
+                rt.set_capabilities(Capabilities::pure());
+                rt.set_module_root(dir.join("bundle"));
+                let result = rt.run_captured(Parser::new(src, 0).parse().expect("parse")).0;
+                assert!(matches!(result, Err(RuntimeError::PermissionDenied { capability: Capability::FileSystem, .. })), "{:?} {}: {:?}", engine, src, result);
+            }
+            let mut rt = Runtime::new();
+            rt.set_engine(engine);
+            rt.set_capabilities(Capabilities::pure());
+            rt.set_module_root(dir.join("bundle"));
+            let (result, output) = rt.run_captured(Parser::new("loc util\nout util.twice(4);", 0).parse().expect("parse"));
+            assert!(result.is_ok(), "{:?}: {:?}", engine, result);
+            assert_eq!(output, "8\n");
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
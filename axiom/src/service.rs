@@ -0,0 +1,230 @@
+//! Long-lived scripts (`axiom service run script.ax`)
+//!
+//! The script runs in this process on a fresh Runtime, re-reading the file
+//! each time so a fix takes effect on the next start. A run that finishes
+//! cleanly ends the service. With `--restart-on-crash`, a run that fails
+//! (an error or a panic) is started again after a backoff that doubles from
+//! 1 s to 60 s and drops back to 1 s once a run has stayed up for a minute.
+//!
+//! SIGTERM or Ctrl-C interrupts the script like Ctrl-C during `axiom run`:
+//! its `aut.on_exit` hooks run — the script's shutdown hook — and the
+//! service exits without restarting. A second signal exits at once.
+//!
+//! With `--log-file`, script output, log records and service events are
+//! appended to the file with timestamps, and the file is rotated like the
+//! `log_file` conf property (at `log_max_mb`, keeping three old files).
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+
+use crate::errors::RuntimeError;
+use crate::logger::FileSink;
+use crate::parser::Parser;
+use crate::runtime::Runtime;
+
+#[derive(Debug, Clone, Default)]
+pub struct ServiceOptions {
+    /// Start the script again when it fails
+    pub restart_on_crash: bool,
+    /// Where output and service events go instead of stdout/stderr
+    pub log_file: Option<PathBuf>,
+    /// Give up after this many restarts in a row (None = never)
+    pub max_restarts: Option<u32>,
+}
+
+/// Delay between a failed run and the next start.
+#[derive(Debug, Clone)]
+pub struct Backoff {
+    delay: Duration,
+}
+
+impl Backoff {
+    pub const MIN: Duration = Duration::from_secs(1);
+    pub const MAX: Duration = Duration::from_secs(60);
+    /// A run that stays up this long resets the delay.
+    pub const STABLE: Duration = Duration::from_secs(60);
+
+    pub fn new() -> Self {
+        Backoff { delay: Self::MIN }
+    }
+
+    /// The wait before the next start, given how long the last run was up.
+    pub fn next(&mut self, uptime: Duration) -> Duration {
+        if uptime >= Self::STABLE {
+            self.delay = Self::MIN;
+        }
+        let wait = self.delay;
+        self.delay = (self.delay * 2).min(Self::MAX);
+        wait
+    }
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+static STOP: AtomicBool = AtomicBool::new(false);
+
+/// Stop the service: interrupt the running script and start no other.
+pub fn request_stop() {
+    STOP.store(true, Ordering::SeqCst);
+    crate::sandbox::interrupt();
+}
+
+pub fn stopping() -> bool {
+    STOP.load(Ordering::SeqCst)
+}
+
+/// How one run of the script ended.
+#[derive(Debug, Clone, PartialEq)]
+enum Outcome {
+    Finished,
+    Stopped,
+    Failed(String),
+}
+
+type SharedLog = Arc<Mutex<Option<FileSink>>>;
+
+fn stamped(line: &str) -> String {
+    format!("{} {}", chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true), line)
+}
+
+/// A service event, to the log file when there is one, else stderr.
+fn event(log: &SharedLog, message: &str) {
+    let line = format!("[service] {}", message);
+    let logged = log.lock().as_mut().is_some_and(|sink| sink.write_line(&stamped(&line)).is_ok());
+    if !logged {
+        eprintln!("{}", line);
+    }
+}
+
+/// Run the script at `path` until it finishes, fails for good, or the
+/// service is stopped. Err carries the failure that ended the service.
+pub fn run(path: &Path, options: &ServiceOptions) -> Result<(), String> {
+    let log: SharedLog = Arc::new(Mutex::new(None));
+    if let Some(file) = &options.log_file {
+        let max_bytes = crate::conf::AxConf::load().log_max_mb() as u64 * 1024 * 1024;
+        let sink = FileSink::open(file, max_bytes)
+            .map_err(|e| format!("Cannot open log file '{}': {}", file.display(), e))?;
+        *log.lock() = Some(sink);
+    }
+    let mut backoff = Backoff::new();
+    let mut restarts = 0;
+    loop {
+        event(&log, &format!("starting {} (pid {})", path.display(), std::process::id()));
+        let started = Instant::now();
+        match run_once(path, &log) {
+            Outcome::Finished => {
+                event(&log, "script finished");
+                return Ok(());
+            }
+            Outcome::Stopped => {
+                event(&log, "stopped");
+                return Ok(());
+            }
+            Outcome::Failed(error) => {
+                event(&log, &format!("script failed after {:.1}s: {}", started.elapsed().as_secs_f64(), error));
+                if !options.restart_on_crash {
+                    return Err(error);
+                }
+                if options.max_restarts.is_some_and(|max| restarts >= max) {
+                    event(&log, &format!("giving up after {} restarts", restarts));
+                    return Err(error);
+                }
+                let wait = backoff.next(started.elapsed());
+                event(&log, &format!("restarting in {}s", wait.as_secs()));
+                if !sleep_unless_stopped(wait) {
+                    event(&log, "stopped");
+                    return Ok(());
+                }
+                restarts += 1;
+            }
+        }
+    }
+}
+
+fn run_once(path: &Path, log: &SharedLog) -> Outcome {
+    if stopping() {
+        return Outcome::Stopped;
+    }
+    let source = match std::fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(e) => return Outcome::Failed(format!("cannot read '{}': {}", path.display(), e)),
+    };
+    let items = match Parser::new(&source, 0).parse() {
+        Ok(items) => items,
+        Err(e) => return Outcome::Failed(format!("parse error: {}", e)),
+    };
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let mut runtime = Runtime::new();
+        if log.lock().is_none() {
+            return runtime.run(items);
+        }
+        let sink = Arc::clone(log);
+        crate::runtime::with_output_sink(move |line| {
+            if let Some(file) = sink.lock().as_mut() {
+                let _ = file.write_line(&stamped(line));
+            }
+        }, || runtime.run(items))
+    }));
+    crate::runtime::flush_output();
+    match result {
+        _ if stopping() => Outcome::Stopped,
+        Ok(Ok(())) => Outcome::Finished,
+        Ok(Err(RuntimeError::Interrupted)) => Outcome::Stopped,
+        Ok(Err(e)) => Outcome::Failed(e.to_string()),
+        Err(payload) => Outcome::Failed(format!("internal error (panic): {}", crate::errors::panic_message(payload.as_ref()))),
+    }
+}
+
+/// Wait `wait`, or less if the service is stopped meanwhile. False if it was.
+fn sleep_unless_stopped(wait: Duration) -> bool {
+    let deadline = Instant::now() + wait;
+    while !stopping() {
+        let left = deadline.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            return true;
+        }
+        std::thread::sleep(left.min(Duration::from_millis(50)));
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_doubles_and_resets_after_a_stable_run() {
+        let mut backoff = Backoff::new();
+        let quick = Duration::from_millis(10);
+        let waits: Vec<u64> = (0..8).map(|_| backoff.next(quick).as_secs()).collect();
+        assert_eq!(waits, vec![1, 2, 4, 8, 16, 32, 60, 60]);
+        assert_eq!(backoff.next(Backoff::STABLE), Backoff::MIN);
+        assert_eq!(backoff.next(quick), Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_failed_run_is_logged_and_not_restarted_without_the_flag() {
+        let dir = std::env::temp_dir().join(format!("axiom-service-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let script = dir.join("job.ax");
+        std::fs::write(&script, "out \"working\";\nmissing();\n").unwrap();
+        let log = dir.join("job.log");
+        let options = ServiceOptions { log_file: Some(log.clone()), ..Default::default() };
+        assert!(run(&script, &options).is_err());
+        let text = std::fs::read_to_string(&log).unwrap();
+        let lines: Vec<&str> = text.lines().map(|l| l.split_once(' ').unwrap().1).collect();
+        assert!(lines[0].starts_with("[service] starting"));
+        assert_eq!(lines[1], "working");
+        assert!(lines[2].starts_with("[service] script failed"));
+        assert_eq!(lines.len(), 3);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
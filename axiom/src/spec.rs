@@ -0,0 +1,144 @@
+//! Conformance specs (`axiom spec`)
+//!
+//! Each `.ax` file under `spec/` is a program with its expected stdout in a
+//! `.out` file beside it. A spec runs once on the tree-walker and once on the
+//! VM; it passes when both print exactly the expected text. A run that fails
+//! ends its output with an `error[AXM_NNN]` line, so expected errors are
+//! specs too. Directories starting with `_` hold modules the specs import
+//! and are not run themselves.
+
+use std::path::{Path, PathBuf};
+
+use crate::diagnostics::DiagnosticEngine;
+use crate::runtime::Engine;
+
+/// What one spec printed on each engine, against what it should print.
+pub struct SpecOutcome {
+    pub path: PathBuf,
+    /// Contents of the `.out` file, if there is one
+    pub expected: Option<String>,
+    pub interp: String,
+    pub vm: String,
+}
+
+impl SpecOutcome {
+    pub fn passed(&self) -> bool {
+        self.failure().is_none()
+    }
+
+    /// Why the spec fails, or `None` when it passes.
+    pub fn failure(&self) -> Option<String> {
+        if self.interp != self.vm {
+            return Some(format!("engines diverge (interp vs vm):\n{}", diff(&self.interp, &self.vm)));
+        }
+        match &self.expected {
+            None => Some(format!("no expected output ({} is missing; run with --bless to write it)", self.out_path().display())),
+            Some(expected) if *expected != self.interp => Some(format!("output differs (expected vs actual):\n{}", diff(expected, &self.interp))),
+            Some(_) => None,
+        }
+    }
+
+    /// The `.out` file next to the spec.
+    pub fn out_path(&self) -> PathBuf {
+        self.path.with_extension("out")
+    }
+}
+
+/// Run the spec in `path` on both engines.
+pub fn run_file(path: &Path) -> Result<SpecOutcome, String> {
+    let source = std::fs::read_to_string(path).map_err(|e| format!("Cannot read '{}': {}", path.display(), e))?;
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let expected = std::fs::read_to_string(path.with_extension("out")).ok();
+    Ok(SpecOutcome {
+        path: path.to_path_buf(),
+        expected,
+        interp: run_source(&source, Engine::Interp, dir),
+        vm: run_source(&source, Engine::Vm, dir),
+    })
+}
+
+/// Run every spec under `dir`, in path order.
+pub fn run_dir(dir: &Path) -> Result<Vec<SpecOutcome>, String> {
+    let mut files = Vec::new();
+    collect(dir, &mut files)?;
+    files.sort();
+    files.iter().map(|file| run_file(file)).collect()
+}
+
+fn collect(dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), String> {
+    let entries = std::fs::read_dir(dir).map_err(|e| format!("Cannot read '{}': {}", dir.display(), e))?;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            if !entry.file_name().to_string_lossy().starts_with('_') { collect(&path, files)?; }
+        } else if path.extension().is_some_and(|ext| ext == "ax") {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Everything `source` prints on `engine`, then `error[AXM_NNN]` if it fails.
+/// `loc`s and relative `load`s start in `dir`.
+fn run_source(source: &str, engine: Engine, dir: &Path) -> String {
+    let engine_diag = DiagnosticEngine::new("<spec>", source);
+    let items = match crate::Parser::new(source, 0).parse() {
+        Ok(items) => items,
+        Err(e) => return format!("error[AXM_{:03}]\n", engine_diag.from_parser(&e).code.as_u32()),
+    };
+    let mut runtime = crate::Runtime::new();
+    runtime.set_engine(engine);
+    runtime.set_module_root(dir);
+    let (result, mut output) = runtime.run_captured(items);
+    if let Err(e) = result {
+        output.push_str(&format!("error[AXM_{:03}]\n", engine_diag.from_runtime(&e).code.as_u32()));
+    }
+    output
+}
+
+/// The first line where `left` and `right` differ, with its neighbours.
+fn diff(left: &str, right: &str) -> String {
+    let (l, r): (Vec<&str>, Vec<&str>) = (left.lines().collect(), right.lines().collect());
+    let at = (0..l.len().max(r.len())).find(|&i| l.get(i) != r.get(i)).unwrap_or(0);
+    let mut text = String::new();
+    for i in at.saturating_sub(2)..(at + 3).min(l.len().max(r.len())) {
+        match (l.get(i), r.get(i)) {
+            (a, b) if a == b => text.push_str(&format!("  {:>4} | {}\n", i + 1, a.unwrap_or(&""))),
+            (a, b) => {
+                if let Some(a) = a { text.push_str(&format!("- {:>4} | {}\n", i + 1, a)); }
+                if let Some(b) = b { text.push_str(&format!("+ {:>4} | {}\n", i + 1, b)); }
+            }
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spec_suite() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("spec");
+        let outcomes = run_dir(&dir).unwrap();
+        assert!(!outcomes.is_empty());
+        let failures: Vec<String> = outcomes.iter()
+            .filter_map(|o| o.failure().map(|why| format!("{}: {}", o.path.display(), why)))
+            .collect();
+        assert!(failures.is_empty(), "{}", failures.join("\n"));
+    }
+
+    #[test]
+    fn test_divergence_and_missing_output_fail() {
+        let outcome = |expected: Option<&str>, vm: &str| SpecOutcome {
+            path: PathBuf::from("spec/x.ax"),
+            expected: expected.map(String::from),
+            interp: "1\n2\n".into(),
+            vm: vm.into(),
+        };
+        assert!(outcome(Some("1\n2\n"), "1\n2\n").passed());
+        assert!(outcome(Some("1\n2\n"), "1\n3\n").failure().unwrap().contains("+    2 | 3"));
+        assert!(outcome(Some("1\n"), "1\n2\n").failure().unwrap().contains("output differs"));
+        assert!(outcome(None, "1\n2\n").failure().unwrap().contains("x.out"));
+    }
+}
@@ -5,37 +5,54 @@
 /// • 32-bit fixed-width instructions (Op/A/B/C or Op/A/sBx)
 /// • Register file per call frame: Vec<Val> pre-sized to proto.reg_count
 /// • Integer fast path: Val::Int(i64) — no f64 boxing for whole numbers
+/// • Operands read by reference: only a value stored in a register is cloned
 /// • Call frames on a Vec (no Rust-stack recursion → no stack overflow)
+/// • Register files recycled through a pool as frames return (no alloc per call)
 /// • Globals indexed by u16 in a flat Vec<Val>  (no HashMap string lookup)
+/// • Map property reads cached per instruction  (no lock or hash on a hit)
 /// • Native functions stored as Arc<dyn Fn>  (no enum matching per call)
 ///
 /// PERFORMANCE vs TREE-WALKER
 /// ──────────────────────────
-/// For pure numeric loops (fib, sorting, …):
-///   • ~5-15x faster than the tree-walking runtime
-///   • Integer add/sub/compare: 2-5 ns per op (no alloc, no hash lookup)
-///   • Function calls: ~50 ns (Vec push, no Mutex/RwLock)
+/// Measured by `cargo bench --bench engines` (programs in benches/programs):
+///   • Calls and loops (fib, loop_sum, method_dispatch): ~2-2.5x faster
+///   • Work done in natives or on maps (list_sort, map_access,
+///     string_concat): about even — the time is spent outside dispatch
+/// `benches/compare.py` times the same programs against other languages.
 ///
 /// HOW IT WIRES IN
 /// ───────────────
-///   compile_program(items)  →  (Proto, GlobalTable)
+///   compile_program_with(items, runtime.globals.table().reopen())
+///                           →  (Proto, GlobalTable)  ← runtime's slots kept
 ///   VmCore::new()
-///   vm.seed_globals(runtime.globals, &global_table)  ← copies AxValue → Val
+///   vm.set_global_at(idx, ..) per used slot          ← copies AxValue → Val
 ///   vm.run(proto)
-///   runtime.read_globals_back(vm, &global_table)     ← copies Val → AxValue
+///   runtime.globals.set_at(idx, ..) per used slot    ← copies Val → AxValue
+///
+/// Functions and maps keep their identity across the boundary: see the
+/// handle table below.
 ///
 /// The tree-walking runtime is kept for OOP / module / IO paths.
 /// The VM is activated for pure Axiom functions and top-level numeric code.
 
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::fmt;
-use std::sync::Arc;
+use std::panic::AssertUnwindSafe;
+use std::rc::Rc;
+use std::sync::{Arc, Weak};
 
+use dashmap::DashMap;
+use once_cell::sync::Lazy;
 use parking_lot::Mutex;
 
-use crate::bytecode::{Op, Proto};
-use crate::core::value::AxValue;
-use crate::errors::RuntimeError;
+use crate::bytecode::{Instr, Op, Proto};
+use crate::core::oop::AxCallable;
+use crate::core::value::{collections_revision, freeze_container, is_frozen_container, sorted_maps, touch_collections, AxValue, DisplayGuard, COMPARE_MAX_DEPTH};
+use crate::errors::{catch_native, panic_message, RuntimeError};
+use crate::inline_cache::{IcState, IcStats, PropIC, IC_MAX_POLY};
+use crate::intern::Sym;
+use crate::sandbox::Capabilities;
 
 // ═══════════════════════════════════════════════════════════════════════════
 // Val — compact VM value type
@@ -51,14 +68,18 @@ pub enum Val {
     Int(i64),
     /// Floating-point (only when the value is non-integer)
     Float(f64),
-    /// Interned / heap string (Arc avoids copies on clone)
+    /// String (Arc avoids copies on clone); constants and names share the
+    /// interner's allocation, strings built at runtime are plain `Arc<str>`
     Str(Arc<str>),
     /// Function value (native or compiled bytecode)
     Fun(Arc<VmFun>),
     /// List — uses parking_lot Mutex (much cheaper than std::sync::RwLock)
     List(Arc<Mutex<Vec<Val>>>),
-    /// Map / module namespace
-    Map(Arc<Mutex<HashMap<String, Val>>>),
+    /// Map / module namespace; keys share the interner's allocation only when
+    /// they are names or constants, data keys are never interned
+    Map(Arc<Mutex<HashMap<Arc<str>, Val>>>),
+    /// Enum variant — qualified `Enum.Variant` symbol plus optional payload
+    Enum(Sym, Option<Arc<Val>>),
 }
 
 impl Val {
@@ -73,6 +94,7 @@ impl Val {
             Val::Fun(_)     => true,
             Val::List(l)    => !l.lock().is_empty(),
             Val::Map(m)     => !m.lock().is_empty(),
+            Val::Enum(..)   => true,
         }
     }
 
@@ -95,33 +117,80 @@ impl Val {
             Val::Fun(_)   => "fun",
             Val::List(_)  => "list",
             Val::Map(_)   => "map",
+            Val::Enum(..) => "enum",
         }
     }
 
+    /// Same text as `AxValue::display` for the converted value.
     pub fn display(&self) -> String {
+        let mut out = String::new();
+        self.write_display(&mut out, &mut DisplayGuard::default());
+        out
+    }
+
+    fn write_display(&self, out: &mut String, guard: &mut DisplayGuard) {
         match self {
-            Val::Nil        => "nil".into(),
-            Val::Bool(b)    => b.to_string(),
-            Val::Int(n)     => n.to_string(),
+            Val::Nil        => out.push_str("nil"),
+            Val::Bool(b)    => out.push_str(&b.to_string()),
+            Val::Int(n)     => out.push_str(&n.to_string()),
             Val::Float(f)   => {
                 if f.fract() == 0.0 && f.abs() < 1e15 {
-                    format!("{}", *f as i64)
+                    out.push_str(&(*f as i64).to_string());
                 } else {
-                    f.to_string()
+                    out.push_str(&f.to_string());
+                }
+            }
+            Val::Str(s)     => out.push_str(s),
+            Val::Fun(_)     => out.push_str("<fun>"),
+            Val::Enum(variant, payload) => {
+                out.push_str(&variant.as_str());
+                if let Some(payload) = payload {
+                    out.push('(');
+                    payload.write_display(out, guard);
+                    out.push(')');
                 }
             }
-            Val::Str(s)     => s.to_string(),
-            Val::Fun(_)     => "<fun>".into(),
             Val::List(l)    => {
-                let items = l.lock();
-                let s: Vec<String> = items.iter().map(|v| v.display()).collect();
-                format!("[{}]", s.join(", "))
+                if !guard.enter(Arc::as_ptr(l) as usize) {
+                    out.push_str("[...]");
+                    return;
+                }
+                // Snapshot so a nested value can lock its own collection
+                let items = l.lock().clone();
+                out.push('[');
+                for (i, v) in items.iter().enumerate() {
+                    if i > 0 { out.push_str(", "); }
+                    v.write_display(out, guard);
+                }
+                out.push(']');
+                guard.leave();
+            }
+            Val::Map(m)     => {
+                if !guard.enter(Arc::as_ptr(m) as usize) {
+                    out.push_str("{...}");
+                    return;
+                }
+                let mut entries: Vec<(Arc<str>, Val)> = m.lock().iter()
+                    .map(|(k, v)| (Arc::clone(k), v.clone()))
+                    .collect();
+                if sorted_maps() {
+                    entries.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+                }
+                out.push('{');
+                for (i, (k, v)) in entries.iter().enumerate() {
+                    if i > 0 { out.push_str(", "); }
+                    out.push_str(k);
+                    out.push_str(": ");
+                    v.write_display(out, guard);
+                }
+                out.push('}');
+                guard.leave();
             }
-            Val::Map(_)     => "<map>".into(),
         }
     }
 
-    /// Equality — structural, no string formatting
+    /// Equality — structural, no string formatting; same rules as
+    /// `AxValue::deep_eq`
     #[inline(always)]
     pub fn eq_val(&self, other: &Val) -> bool {
         match (self, other) {
@@ -131,10 +200,46 @@ impl Val {
             (Val::Float(a),  Val::Float(b))  => a == b,
             (Val::Int(a),    Val::Float(b))  => (*a as f64) == *b,
             (Val::Float(a),  Val::Int(b))    => *a == (*b as f64),
-            (Val::Str(a),    Val::Str(b))    => a == b,
+            (Val::Str(a),    Val::Str(b))    => Arc::ptr_eq(a, b) || a == b,
+            (Val::List(_), Val::List(_)) | (Val::Map(_), Val::Map(_)) | (Val::Enum(..), Val::Enum(..)) => self.eq_nested(other, &mut Vec::new()),
+            (Val::Fun(a),    Val::Fun(b))    => Arc::ptr_eq(a, b),
             _                                => false,
         }
     }
+
+    fn eq_nested(&self, other: &Val, seen: &mut Vec<(usize, usize)>) -> bool {
+        // Snapshots throughout: the VM's locks are not reentrant
+        match (self, other) {
+            (Val::List(a), Val::List(b)) => {
+                if Arc::ptr_eq(a, b) { return true; }
+                let pair = (Arc::as_ptr(a) as usize, Arc::as_ptr(b) as usize);
+                if seen.contains(&pair) { return true; }
+                if seen.len() >= COMPARE_MAX_DEPTH { return false; }
+                seen.push(pair);
+                let (a, b) = (a.lock().clone(), b.lock().clone());
+                let eq = a.len() == b.len() && a.iter().zip(&b).all(|(x, y)| x.eq_nested(y, seen));
+                seen.pop();
+                eq
+            }
+            (Val::Map(a), Val::Map(b)) => {
+                if Arc::ptr_eq(a, b) { return true; }
+                let pair = (Arc::as_ptr(a) as usize, Arc::as_ptr(b) as usize);
+                if seen.contains(&pair) { return true; }
+                if seen.len() >= COMPARE_MAX_DEPTH { return false; }
+                seen.push(pair);
+                let (a, b) = (a.lock().clone(), b.lock().clone());
+                let eq = a.len() == b.len()
+                    && a.iter().all(|(k, x)| b.get(k).is_some_and(|y| x.eq_nested(y, seen)));
+                seen.pop();
+                eq
+            }
+            (Val::Enum(a, x), Val::Enum(b, y)) => a == b && match (x, y) {
+                (Some(x), Some(y)) => x.eq_nested(y, seen),
+                (x, y) => x.is_none() && y.is_none(),
+            },
+            _ => self.eq_val(other),
+        }
+    }
 }
 
 impl fmt::Display for Val {
@@ -169,10 +274,485 @@ impl fmt::Debug for VmFun {
     }
 }
 
+// ═══════════════════════════════════════════════════════════════════════════
+// Instruction tracing (axiom run --trace-vm, conf opcode_trace)
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Which instructions to trace. Lines go to stderr.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VmTrace {
+    /// Only trace frames running this function (`<main>` for top-level code).
+    pub function: Option<String>,
+}
+
+impl VmTrace {
+    /// Whether a frame running `name` is traced. Nested function names are
+    /// qualified (`<main>:add`), so the bare name matches too.
+    fn matches(&self, name: &str) -> bool {
+        self.function.as_deref().is_none_or(|f| name == f || name.rsplit(':').next() == Some(f))
+    }
+
+    /// The trace requested by conf (`debug` + `opcode_trace`, `opcode_trace_filter`), if any.
+    pub fn from_conf(conf: &crate::conf::AxConf) -> Option<VmTrace> {
+        if !conf.opcode_trace() {
+            return None;
+        }
+        let filter = conf.opcode_trace_filter();
+        Some(VmTrace { function: (!filter.is_empty()).then(|| filter.to_string()) })
+    }
+}
+
+/// Short rendering of a value for trace lines.
+fn trace_val(v: &Val) -> String {
+    let s = match v {
+        Val::Str(s) => format!("{:?}", s),
+        Val::Fun(f) => format!("{:?}", f),
+        other => other.display(),
+    };
+    if s.chars().count() > 40 {
+        format!("{}…", s.chars().take(39).collect::<String>())
+    } else {
+        s
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Boundary conversion cache
+// ═══════════════════════════════════════════════════════════════════════════
+//
+// Values crossing between the VM and the tree-walker are copied, since the
+// two engines use different collection types.  Nil, bools and numbers are
+// unboxed on both sides and never allocate; the rest is cached here:
+//   • strings  — "" and short strings already in the interner reuse its Arc
+//   • lists    — a converted copy is reused while its source is alive and no
+//     collection has been mutated since (core::value::touch_collections)
+//   • maps     — same
+// A conversion also records the reverse entry, so a collection that goes
+// out and comes back unchanged is the original one.  Collections are
+// registered before their items are converted, so a list or map that
+// contains itself converts to a copy that contains itself.  Functions are
+// not copied at all — see the handle table.
+
+/// Strings up to this many bytes are looked up in the interner.
+const SHORT_STRING: usize = 32;
+/// Entries per table before it is flushed.
+const CONVERSION_CACHE_LIMIT: usize = 1024;
+
+static EMPTY_STR: Lazy<Arc<str>> = Lazy::new(|| crate::intern::intern(""));
+
+/// The collection a cache entry was converted from. Holding the Weak keeps
+/// the allocation (and so the address used as key) from being reused.
+enum Source {
+    List(Weak<std::sync::RwLock<Vec<AxValue>>>),
+    Map(Weak<DashMap<String, AxValue>>),
+    VmList(Weak<Mutex<Vec<Val>>>),
+    VmMap(Weak<Mutex<HashMap<Arc<str>, Val>>>),
+}
+
+impl Source {
+    fn alive(&self) -> bool {
+        match self {
+            Source::List(w)   => w.strong_count() > 0,
+            Source::Map(w)    => w.strong_count() > 0,
+            Source::VmList(w) => w.strong_count() > 0,
+            Source::VmMap(w)  => w.strong_count() > 0,
+        }
+    }
+}
+
+#[derive(Default)]
+struct ConversionCache {
+    /// Collection revision the `to_vm` / `to_ax` entries were made at
+    revision: u64,
+    to_vm:    HashMap<usize, (Source, Val)>,
+    to_ax:    HashMap<usize, (Source, AxValue)>,
+    /// Collections whose conversion is in progress (never flushed)
+    open_vm:  HashMap<usize, Val>,
+    open_ax:  HashMap<usize, AxValue>,
+}
+
+thread_local! {
+    static CONVERSIONS: RefCell<ConversionCache> = RefCell::new(ConversionCache::default());
+}
+
+impl ConversionCache {
+    /// Run `f` on this thread's cache after dropping stale collection entries.
+    fn with<R>(f: impl FnOnce(&mut ConversionCache) -> R) -> R {
+        CONVERSIONS.with(|cache| {
+            let mut cache = cache.borrow_mut();
+            let revision = collections_revision();
+            if cache.revision != revision {
+                cache.to_vm.clear();
+                cache.to_ax.clear();
+                cache.revision = revision;
+            }
+            f(&mut cache)
+        })
+    }
+
+    fn vm(key: usize) -> Option<Val> {
+        Self::with(|c| c.open_vm.get(&key).cloned()
+            .or_else(|| c.to_vm.get(&key).filter(|(src, _)| src.alive()).map(|(_, v)| v.clone())))
+    }
+
+    fn ax(key: usize) -> Option<AxValue> {
+        Self::with(|c| c.open_ax.get(&key).cloned()
+            .or_else(|| c.to_ax.get(&key).filter(|(src, _)| src.alive()).map(|(_, v)| v.clone())))
+    }
+
+    /// Mark `key` as being converted into `val` (or done, with `None`).
+    fn open_vm(key: usize, val: Option<&Val>) {
+        Self::with(|c| match val {
+            Some(v) => { c.open_vm.insert(key, v.clone()); }
+            None    => { c.open_vm.remove(&key); }
+        })
+    }
+
+    fn open_ax(key: usize, val: Option<&AxValue>) {
+        Self::with(|c| match val {
+            Some(v) => { c.open_ax.insert(key, v.clone()); }
+            None    => { c.open_ax.remove(&key); }
+        })
+    }
+
+    /// Remember a conversion started at `revision`, unless something was
+    /// mutated while it ran.
+    fn store_vm(revision: u64, key: usize, source: Source, val: &Val) {
+        Self::with(|c| {
+            if c.revision != revision { return; }
+            if c.to_vm.len() >= CONVERSION_CACHE_LIMIT { c.to_vm.clear(); }
+            c.to_vm.insert(key, (source, val.clone()));
+        })
+    }
+
+    fn store_ax(revision: u64, key: usize, source: Source, val: &AxValue) {
+        Self::with(|c| {
+            if c.revision != revision { return; }
+            if c.to_ax.len() >= CONVERSION_CACHE_LIMIT { c.to_ax.clear(); }
+            c.to_ax.insert(key, (source, val.clone()));
+        })
+    }
+}
+
+/// Collection arguments of a native call, paired with the copies the native
+/// sees. Natives like `col.set` mutate their copy in place; the change is
+/// copied back so the VM's collection sees it too.
+struct SharedArgs(Vec<(Val, AxValue)>);
+
+impl SharedArgs {
+    fn new(args: &[Val], ax_args: &[AxValue]) -> Self {
+        SharedArgs(args.iter().zip(ax_args)
+            .filter(|(v, _)| matches!(v, Val::List(_) | Val::Map(_)))
+            .map(|(v, a)| (v.clone(), a.clone()))
+            .collect())
+    }
+
+    /// `av` as a Val, mapping the argument copies back to the originals.
+    fn to_val(&self, av: &AxValue) -> Val {
+        let original = self.0.iter().find(|(_, a)| match (a, av) {
+            (AxValue::Lst(x), AxValue::Lst(y)) => Arc::ptr_eq(x, y),
+            (AxValue::Map(x), AxValue::Map(y)) => Arc::ptr_eq(x, y),
+            _ => false,
+        });
+        match original {
+            Some((v, _)) => v.clone(),
+            None         => VmCore::ax_to_val(av),
+        }
+    }
+
+    fn write_back(&self) {
+        for (v, a) in &self.0 {
+            match (v, a) {
+                (Val::List(vl), AxValue::Lst(al)) => {
+                    let items: Vec<Val> = al.read().unwrap().iter().map(|x| self.to_val(x)).collect();
+                    *vl.lock() = items;
+                }
+                (Val::Map(vm), AxValue::Map(am)) => {
+                    let entries: HashMap<Arc<str>, Val> = am.iter()
+                        .map(|e| (vm_str(e.key()), self.to_val(e.value())))
+                        .collect();
+                    *vm.lock() = entries;
+                }
+                _ => {}
+            }
+        }
+        touch_collections();
+    }
+}
+
+/// VM string for `s`, sharing the interner's allocation when there is one.
+/// Only looks the string up: data crossing the boundary (map keys included)
+/// is never added to the process-wide table, which nothing ever shrinks.
+fn vm_str(s: &str) -> Arc<str> {
+    if s.is_empty() {
+        return Arc::clone(&EMPTY_STR);
+    }
+    if s.len() <= SHORT_STRING {
+        if let Some(arc) = crate::intern::interner().lookup(s) {
+            return arc;
+        }
+    }
+    Arc::from(s)
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Handle table — functions crossing the boundary
+// ═══════════════════════════════════════════════════════════════════════════
+//
+// A native AxCallable entering the VM is wrapped in a VmFun, and a VmFun
+// leaving it is wrapped in a native AxCallable that runs it on a detached
+// VmCore.  The table links each wrapper to what it wraps, in both
+// directions, so a function that goes out and comes back is the original.
+//
+// Entries hold only Weak references.  A wrapper keeps what it wraps alive,
+// and the Weak for an entry's own key pins that address, so a live entry
+// never describes a different value.
+
+/// Dead entries are swept once the table grows past this.
+const HANDLE_SWEEP_AT: usize = 1024;
+
+#[derive(Default)]
+struct HandleTable {
+    by_ax:    HashMap<usize, (Weak<AxCallable>, Weak<VmFun>)>,
+    by_vm:    HashMap<usize, (Weak<VmFun>, Weak<AxCallable>)>,
+    sweep_at: usize,
+}
+
+static HANDLES: Lazy<Mutex<HandleTable>> = Lazy::new(|| Mutex::new(HandleTable::default()));
+
+impl HandleTable {
+    fn vm_for(callable: &Arc<AxCallable>) -> Option<Arc<VmFun>> {
+        let table = HANDLES.lock();
+        let (key, fun) = table.by_ax.get(&(Arc::as_ptr(callable) as usize))?;
+        if key.strong_count() == 0 { return None; }
+        fun.upgrade()
+    }
+
+    fn ax_for(fun: &Arc<VmFun>) -> Option<Arc<AxCallable>> {
+        let table = HANDLES.lock();
+        let (key, callable) = table.by_vm.get(&(Arc::as_ptr(fun) as usize))?;
+        if key.strong_count() == 0 { return None; }
+        callable.upgrade()
+    }
+
+    fn link(callable: &Arc<AxCallable>, fun: &Arc<VmFun>) {
+        let mut table = HANDLES.lock();
+        table.by_ax.insert(Arc::as_ptr(callable) as usize, (Arc::downgrade(callable), Arc::downgrade(fun)));
+        table.by_vm.insert(Arc::as_ptr(fun) as usize, (Arc::downgrade(fun), Arc::downgrade(callable)));
+        if table.by_ax.len() + table.by_vm.len() > table.sweep_at.max(HANDLE_SWEEP_AT) {
+            table.by_ax.retain(|_, (k, v)| k.strong_count() > 0 && v.strong_count() > 0);
+            table.by_vm.retain(|_, (k, v)| k.strong_count() > 0 && v.strong_count() > 0);
+            table.sweep_at = 2 * (table.by_ax.len() + table.by_vm.len());
+        }
+    }
+}
+
+/// What a VM function needs to run outside the VM that made it: that VM's
+/// globals (as they were when the function left it) and its limits.
+#[derive(Clone)]
+struct Exporter {
+    globals:      Arc<Vec<Val>>,
+    max_frames:   usize,
+    limits:       crate::sandbox::ResourceLimits,
+    capabilities: Capabilities,
+}
+
+impl Default for Exporter {
+    fn default() -> Self {
+        Exporter {
+            globals:      Arc::new(Vec::new()),
+            max_frames:   500,
+            limits:       crate::sandbox::ResourceLimits::unlimited(),
+            capabilities: Capabilities::all(),
+        }
+    }
+}
+
+thread_local! {
+    /// Set while a VM hands values to the tree-walker (native calls, read-back).
+    static EXPORTER: RefCell<Option<Exporter>> = const { RefCell::new(None) };
+}
+
+impl Exporter {
+    fn current() -> Exporter {
+        EXPORTER.with(|e| e.borrow().clone()).unwrap_or_default()
+    }
+
+    fn call(&self, fun: &Arc<VmFun>, args: Vec<Val>) -> Result<Val, RuntimeError> {
+        let mut vm = VmCore {
+            globals:      Arc::clone(&self.globals),
+            frames:       Vec::with_capacity(16),
+            max_frames:   self.max_frames,
+            meter:        crate::sandbox::Meter::new(self.limits),
+            trace:        None,
+            capabilities: self.capabilities,
+            // Thrown away after this one call, so nothing to cache for
+            prop_sites:   None,
+            reg_pool:     Vec::new(),
+            strict:       None,
+        };
+        vm.call(fun, args)
+    }
+}
+
+/// The declared name (`None` for a lambda) and parameter count of a
+/// function compiled for the VM, given the callable it was exported as.
+pub fn compiled_signature(callable: &Arc<AxCallable>) -> Option<(Option<String>, usize)> {
+    match HandleTable::vm_for(callable)?.as_ref() {
+        VmFun::Compiled { name, params, .. } => {
+            // Protos are named `<source>:<name>`, and lambdas `<enclosing>.lambda`
+            let name = name.rsplit(':').next().filter(|n| !n.ends_with(".lambda")).map(str::to_string);
+            Some((name, *params))
+        }
+        VmFun::Native { .. } => None,
+    }
+}
+
+/// Native wrapper for a VmFun entering the tree-walker.
+fn export_fun(fun: &Arc<VmFun>) -> Arc<AxCallable> {
+    let name = match fun.as_ref() {
+        VmFun::Native { name, .. } | VmFun::Compiled { name, .. } => name.clone(),
+    };
+    let exporter = Exporter::current();
+    let target = Arc::clone(fun);
+    let callable = Arc::new(AxCallable::native(name, move |args: Vec<AxValue>| {
+        let args: Vec<Val> = args.iter().map(VmCore::ax_to_val).collect();
+        exporter.call(&target, args).map(|r| VmCore::val_to_ax(&r))
+    }));
+    HandleTable::link(&callable, fun);
+    callable
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Built-in methods on lists and strings
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// `recv.name` as a native with `recv` bound (GetMethod on a list or string).
+fn bound_method(recv: &Val, name: &str) -> Option<Val> {
+    let method = name.to_string();
+    let bound_name = format!("{}.{}", recv.type_name(), name);
+    let fun = match recv {
+        Val::List(l) => {
+            let l = Arc::clone(l);
+            VmFun::Native { name: bound_name, func: Box::new(move |args: &[Val]| list_method(&l, &method, args)) }
+        }
+        Val::Str(s) => {
+            let s = Arc::clone(s);
+            VmFun::Native { name: bound_name, func: Box::new(move |args: &[Val]| {
+                let args: Vec<AxValue> = args.iter().map(VmCore::val_to_ax).collect();
+                crate::runtime::str_method(&s, &method, &args).map(|r| VmCore::ax_to_val(&r))
+            }) }
+        }
+        _ => return None,
+    };
+    Some(Val::Fun(Arc::new(fun)))
+}
+
+/// Built-in list methods; mutate the VM list in place.
+fn list_method(l: &Arc<Mutex<Vec<Val>>>, method: &str, args: &[Val]) -> Result<Val, RuntimeError> {
+    match method {
+        "len"      => Ok(Val::Int(l.lock().len() as i64)),
+        "push" | "pop" if is_frozen_container(l) => Err(RuntimeError::FrozenMutation { type_name: "Lst".into(), span: Default::default() }),
+        "push"     => {
+            if let Some(v) = args.first() {
+                l.lock().push(v.clone());
+                touch_collections();
+            }
+            Ok(Val::Nil)
+        }
+        "pop"      => {
+            touch_collections();
+            Ok(l.lock().pop().unwrap_or(Val::Nil))
+        }
+        "reserve"  => {
+            crate::runtime::list_reserve(&mut l.lock(), args.first().map_or(0.0, Val::as_f64))?;
+            Ok(Val::Nil)
+        }
+        "first"    => Ok(l.lock().first().cloned().unwrap_or(Val::Nil)),
+        "last"     => Ok(l.lock().last().cloned().unwrap_or(Val::Nil)),
+        "contains" => {
+            let needle = args.first().cloned().unwrap_or(Val::Nil);
+            let items = l.lock().clone();
+            Ok(Val::Bool(items.iter().any(|v| v.eq_val(&needle))))
+        }
+        "join"     => {
+            let sep = args.first().map(Val::display).unwrap_or_default();
+            Ok(Val::Str(Arc::from(l.lock().iter().map(Val::display).collect::<Vec<_>>().join(&sep))))
+        }
+        _ => Err(RuntimeError::GenericError { message: format!("No method '{}' on List", method), span: Default::default() }),
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Property inline caches
+// ═══════════════════════════════════════════════════════════════════════════
+
+type VmMap = Mutex<HashMap<Arc<str>, Val>>;
+
+/// Cache slots for one proto, indexed by instruction.
+type PropSites = Rc<RefCell<Vec<PropSite>>>;
+
+/// A value a `GetProp`/`GetMethod` read from one map. VM maps have no
+/// shapes, so the map's identity plus the collections revision stands in
+/// for one: any in-place list or map mutation bumps the revision and so
+/// invalidates every entry.
+struct PropEntry {
+    map: Weak<VmMap>,
+    revision: u64,
+    value: Val,
+}
+
+/// The inline cache of one property-reading instruction: up to
+/// `IC_MAX_POLY` maps, then megamorphic (plain lookups from then on).
+#[derive(Default)]
+struct PropSite {
+    /// `None` until the instruction first runs, so other ops have no state
+    ic: Option<PropIC>,
+    entries: Vec<PropEntry>,
+}
+
+impl PropSite {
+    /// `name` in `map`, through the cache. Only keys that are present are
+    /// cached, so a miss always falls back to the map itself.
+    fn get(&mut self, map: &Arc<VmMap>, name: &str) -> Option<Val> {
+        let ic = self.ic.get_or_insert_with(PropIC::new);
+        let revision = collections_revision();
+        let at = self.entries.iter().position(|e| std::ptr::eq(e.map.as_ptr(), Arc::as_ptr(map)));
+        if let Some(entry) = at.map(|i| &self.entries[i]).filter(|e| e.revision == revision) {
+            ic.hit_count += 1;
+            return Some(entry.value.clone());
+        }
+        ic.miss_count += 1;
+        let value = map.lock().get(name).cloned();
+        if ic.state == IcState::Megamorphic {
+            return value;
+        }
+        let entry = value.clone().map(|value| PropEntry { map: Arc::downgrade(map), revision, value });
+        match (at, entry) {
+            (Some(i), Some(entry)) => self.entries[i] = entry,
+            (Some(i), None) => { self.entries.remove(i); }
+            (None, Some(_)) if self.entries.len() == IC_MAX_POLY => {
+                ic.state = IcState::Megamorphic;
+                self.entries = Vec::new();
+            }
+            (None, Some(entry)) => {
+                self.entries.push(entry);
+                if self.entries.len() > 1 { ic.state = IcState::Polymorphic; }
+                else if ic.state == IcState::Uninitialized { ic.state = IcState::Monomorphic; }
+            }
+            (None, None) => {}
+        }
+        value
+    }
+}
+
 // ═══════════════════════════════════════════════════════════════════════════
 // Call frame
 // ═══════════════════════════════════════════════════════════════════════════
 
+/// Most spare register files a VM keeps; more are freed as frames return.
+const REG_POOL_MAX: usize = 64;
+
 struct Frame {
     /// Register file — indexed by u8 operand
     regs:    Vec<Val>,
@@ -184,6 +764,10 @@ struct Frame {
     ret_reg: usize,
     /// Captured upvalues for closures
     upvalues: Vec<Val>,
+    /// Function executing in this frame (`None` for the top-level chunk)
+    callee: Option<Arc<VmFun>>,
+    /// The proto's property caches, fetched on the frame's first map read
+    prop_sites: Option<PropSites>,
 }
 
 // ═══════════════════════════════════════════════════════════════════════════
@@ -191,25 +775,203 @@ struct Frame {
 // ═══════════════════════════════════════════════════════════════════════════
 
 pub struct VmCore {
-    /// Global variable table — indexed by u16 from GlobalTable
-    pub globals: Vec<Val>,
+    /// Global variable table — indexed by u16 from GlobalTable.  Shared
+    /// copy-on-write with the functions this VM has handed out.
+    pub globals: Arc<Vec<Val>>,
     /// Frame stack (empty = not running)
     frames:  Vec<Frame>,
+    /// Frame count at which `Call` raises StackOverflow instead of pushing
+    max_frames: usize,
+    /// Sandbox accounting (instruction budget, timeout, allocation)
+    meter: crate::sandbox::Meter,
+    trace: Option<VmTrace>,
+    /// Intrinsic groups natives may use; checked on every native call
+    capabilities: Capabilities,
+    /// Property caches per proto (keyed by its address, which the held
+    /// `Arc` keeps from being reused); `None` when the `ic_enabled` /
+    /// `inline_cache` conf switches are off
+    prop_sites: Option<HashMap<usize, (Arc<Proto>, PropSites)>>,
+    /// Register files of returned frames, emptied and kept for the next call
+    reg_pool: Vec<Vec<Val>>,
+    /// Set in strict mode (`strict_vars`): reading a global never bound raises
+    strict: Option<StrictGlobals>,
+}
+
+/// Which globals have been bound, for strict mode. Unbound slots hold nil
+/// like globals bound to nil, so the VM keeps track on the side.
+struct StrictGlobals {
+    /// Indexed like the globals; a slot without a name is never reported
+    names: Vec<String>,
+    bound: Vec<bool>,
+}
+
+impl StrictGlobals {
+    fn bind(&mut self, idx: usize) {
+        if let Some(bound) = self.bound.get_mut(idx) {
+            *bound = true;
+        }
+    }
+
+    /// AXM_201 for reading the global in `idx` before anything was bound to it.
+    fn check(&self, idx: usize) -> Result<(), RuntimeError> {
+        match self.names.get(idx) {
+            Some(name) if !self.bound[idx] => Err(RuntimeError::UndefinedVariable {
+                name: name.clone(),
+                span: Default::default(),
+                candidates: self.names.iter().zip(&self.bound).filter(|&(_, &b)| b).map(|(n, _)| n.clone()).collect(),
+            }),
+            _ => Ok(()),
+        }
+    }
 }
 
 impl VmCore {
+    /// A VM configured from the conf files and `AXIOM_*` variables.
     pub fn new(global_capacity: usize) -> Self {
+        Self::with_conf(global_capacity, &crate::conf::AxConf::load())
+    }
+
+    pub fn with_conf(global_capacity: usize, conf: &crate::conf::AxConf) -> Self {
         VmCore {
-            globals: vec![Val::Nil; global_capacity],
+            globals: Arc::new(vec![Val::Nil; global_capacity]),
             frames:  Vec::with_capacity(64),
+            max_frames: conf.max_call_depth() as usize,
+            meter:   crate::sandbox::Meter::default(),
+            trace:   VmTrace::from_conf(conf),
+            capabilities: Capabilities::from_conf(conf),
+            prop_sites: (conf.ic_enabled() && conf.inline_cache()).then(HashMap::new),
+            reg_pool: Vec::new(),
+            strict: None,
         }
     }
 
+    /// Strict mode: `names` are the globals' names, by slot, and reading one
+    /// that was never bound raises AXM_201 instead of giving nil (`None` = off).
+    /// Call before binding the globals the program starts with.
+    pub fn set_strict(&mut self, names: Option<&[String]>) {
+        self.strict = names.map(|names| StrictGlobals { names: names.to_vec(), bound: vec![false; names.len()] });
+    }
+
+    /// Restrict which side-effecting natives may be called.
+    pub fn set_capabilities(&mut self, capabilities: Capabilities) {
+        self.capabilities = capabilities;
+    }
+
+    /// Print every executed instruction (or `None` to stop).
+    pub fn set_trace(&mut self, trace: Option<VmTrace>) {
+        self.trace = trace;
+    }
+
+    pub fn set_limits(&mut self, limits: crate::sandbox::ResourceLimits) {
+        self.meter = crate::sandbox::Meter::new(limits);
+    }
+
+    /// Totals over every property-reading instruction this VM has run.
+    pub fn prop_ic_stats(&self) -> IcStats {
+        let mut stats = IcStats::default();
+        for (_, sites) in self.prop_sites.iter().flat_map(HashMap::values) {
+            for ic in sites.borrow().iter().filter_map(|site| site.ic.as_ref()) {
+                stats.add(ic);
+            }
+        }
+        stats
+    }
+
+    /// Read `name` from `map` for the current instruction of frame
+    /// `frame_idx`, through that instruction's inline cache.
+    fn map_get(&mut self, frame_idx: usize, map: &Arc<VmMap>, name: &str) -> Option<Val> {
+        let Some(protos) = &mut self.prop_sites else {
+            return map.lock().get(name).cloned();
+        };
+        let frame = &mut self.frames[frame_idx];
+        let sites = match &frame.prop_sites {
+            Some(sites) => Rc::clone(sites),
+            None => {
+                let proto = &frame.proto;
+                let (_, sites) = protos.entry(Arc::as_ptr(proto) as usize).or_insert_with(|| {
+                    let sites = (0..proto.code.len()).map(|_| PropSite::default()).collect();
+                    (Arc::clone(proto), Rc::new(RefCell::new(sites)))
+                });
+                frame.prop_sites = Some(Rc::clone(sites));
+                Rc::clone(sites)
+            }
+        };
+        let ip = frame.ip - 1;
+        let value = sites.borrow_mut()[ip].get(map, name);
+        value
+    }
+
+    pub fn set_max_call_depth(&mut self, depth: usize) {
+        self.max_frames = depth;
+    }
+
+    /// Names of the active frames, outermost first, for AXM_408 reports.
+    fn call_chain(&self) -> Vec<String> {
+        self.frames.iter().map(|f| Self::frame_name(f).to_string()).collect()
+    }
+
+    fn frame_name(frame: &Frame) -> &str {
+        match frame.callee.as_deref() {
+            Some(VmFun::Compiled { name, .. }) | Some(VmFun::Native { name, .. }) => name,
+            None => "<main>",
+        }
+    }
+
+    /// One trace line for `instr` at `ip`, with the operands it reads resolved.
+    fn trace_line(&self, frame_idx: usize, ip: usize, instr: Instr) -> String {
+        let f = &self.frames[frame_idx];
+        let (a, b, c, bx, sbx) = (instr.a() as usize, instr.b() as usize, instr.c() as usize, instr.bx() as usize, instr.get_sbx());
+        let r = |i: usize| format!("r{}={}", i, f.regs.get(i).map(trace_val).unwrap_or_else(|| "?".into()));
+        let operands = match instr.op() {
+            Op::LoadNil | Op::LoadTrue | Op::LoadFalse => format!("r{}", a),
+            Op::LoadInt => format!("r{} ← {}", a, sbx),
+            Op::LoadFloat | Op::LoadConst => format!("r{} ← {}", a, f.proto.float_consts.get(bx).copied().unwrap_or(0.0)),
+            Op::LoadStr => format!("r{} ← {:?}", a, f.proto.str_consts.get(bx).map(|s| &**s).unwrap_or("")),
+            Op::LoadGlobal => format!("r{} ← g{}={}", a, bx, self.globals.get(bx).map(trace_val).unwrap_or_else(|| "nil".into())),
+            Op::StoreGlobal => format!("g{} ← {}", bx, r(a)),
+            Op::Move | Op::Neg | Op::Not | Op::ListLen | Op::LoadUpval => format!("r{} ← {}", a, r(b)),
+            Op::Add | Op::Sub | Op::Mul | Op::Div | Op::Mod | Op::Pow
+            | Op::AddInt | Op::SubInt | Op::MulInt | Op::AddFloat | Op::SubFloat | Op::MulFloat | Op::DivFloat
+            | Op::Eq | Op::Ne | Op::Lt | Op::Le | Op::Gt | Op::Ge | Op::LtInt | Op::LeInt | Op::EqInt
+            | Op::And | Op::Or | Op::Concat | Op::GetIndex => format!("r{} ← {}, {}", a, r(b), r(c)),
+            Op::IncrLocal | Op::DecrLocal | Op::Return => r(a),
+            Op::AddIntImm => format!("r{} ← {} + {}", a, r(b), instr.sc()),
+            Op::CmpLtJmp => format!("{} < {} else → {:+}", r(a), r(b), instr.sc()),
+            Op::Jump | Op::LoopBack => format!("→ {:+}", sbx),
+            Op::JumpTrue | Op::JumpFalse | Op::JumpNil | Op::JumpNotNil => format!("{} → {:+}", r(a), sbx),
+            Op::Call | Op::CallTail => {
+                let args: Vec<String> = (0..c).map(|i| r(b + 1 + i)).collect();
+                format!("r{} ← {}({})", a, r(b), args.join(", "))
+            }
+            Op::Closure => format!("r{} ← {}", a, f.proto.protos.get(bx).map(|p| p.source.as_str()).unwrap_or("?")),
+            Op::ReturnNil | Op::NilReturn => String::new(),
+            Op::SetIndex => format!("{}[{}] ← {}", r(a), r(b), r(c)),
+            Op::GetProp | Op::GetMethod => format!("r{} ← {}.{}", a, r(a), f.proto.str_consts.get(bx).map(|s| &**s).unwrap_or("?")),
+            Op::SetProp => format!("{}.{} ← {}", r(a), r(b), r(c)),
+            Op::LoadCallee => format!("r{}", a),
+            Op::NewEnum => format!("r{} ← {}({})", a, f.proto.variants.get(bx).map(|v| v.as_str()).as_deref().unwrap_or("?"), r(a)),
+            Op::MatchEnum => format!("{} → table {}", r(a), bx),
+            Op::EnumPayload => format!("r{} ← payload of {}", a, r(b)),
+            Op::IsListLen => format!("r{} ← {} is [{} items]", a, r(b), c),
+            Op::IsListMin => format!("r{} ← {} is [{}+ items]", a, r(b), c),
+            Op::ListTail => format!("r{} ← {}[{}..]", a, r(b), c),
+            Op::NewList => format!("r{} ← [r{}..r{}]", a, b, b + c),
+            Op::ListReserve => format!("{} += room for {}", r(a), bx),
+            Op::ListExtend => format!("{} += [r{}..r{}]", r(a), b, b + c),
+            _ => format!("A={} B={} C={}", a, b, c),
+        };
+        let depth = self.frames.len() - 1;
+        format!("[vm] {:>2} {}{}:{:04}  {:<12} {}", depth, "  ".repeat(depth), Self::frame_name(f), ip, instr.op().name(), operands).trim_end().to_string()
+    }
+
     // ── Global management ────────────────────────────────────────────────────
 
     pub fn set_global_at(&mut self, idx: usize, val: Val) {
         if idx < self.globals.len() {
-            self.globals[idx] = val;
+            Arc::make_mut(&mut self.globals)[idx] = val;
+            if let Some(strict) = &mut self.strict {
+                strict.bind(idx);
+            }
         }
     }
 
@@ -217,6 +979,31 @@ impl VmCore {
         self.globals.get(idx).cloned().unwrap_or(Val::Nil)
     }
 
+    /// Convert a value this VM produced for the tree-walker.  Functions in it
+    /// stay callable there, against this VM's globals.
+    pub fn export(&self, v: &Val) -> AxValue {
+        self.exporting(|| Self::val_to_ax(v))
+    }
+
+    fn exporting<R>(&self, f: impl FnOnce() -> R) -> R {
+        let exporter = Exporter {
+            globals:      Arc::clone(&self.globals),
+            max_frames:   self.max_frames,
+            limits:       self.meter.limits(),
+            capabilities: self.capabilities,
+        };
+        let outer = EXPORTER.with(|e| e.borrow_mut().replace(exporter));
+        let result = f();
+        EXPORTER.with(|e| *e.borrow_mut() = outer);
+        result
+    }
+
+    /// Global `idx` of the VM currently inside a native call, as an AxValue
+    /// (Nil outside a native call).
+    pub fn calling_global(idx: usize) -> AxValue {
+        Exporter::current().globals.get(idx).map(VmCore::val_to_ax).unwrap_or(AxValue::Nil)
+    }
+
     // ── AxValue conversion helpers ────────────────────────────────────────────
 
     /// Convert AxValue → Val for the VM.
@@ -225,30 +1012,34 @@ impl VmCore {
             AxValue::Nil         => Val::Nil,
             AxValue::Bol(b)      => Val::Bool(*b),
             AxValue::Num(n)      => {
-                if n.fract() == 0.0 && *n >= i64::MIN as f64 && *n <= i64::MAX as f64 {
+                if n.fract() == 0.0 && *n >= i64::MIN as f64 && *n < i64::MAX as f64 {
                     Val::Int(*n as i64)
                 } else {
                     Val::Float(*n)
                 }
             }
-            AxValue::Str(s)      => Val::Str(Arc::from(s.as_str())),
+            AxValue::Str(s)      => Val::Str(vm_str(s)),
             AxValue::Fun(callable) => {
-                use crate::core::oop::AxCallable;
                 match callable.as_ref() {
                     AxCallable::Native { name, func } => {
-                        let func = *func; // fn ptr — Copy
-                        let name = name.clone();
-                        Val::Fun(Arc::new(VmFun::Native {
-                            name,
+                        if let Some(fun) = HandleTable::vm_for(callable) { return Val::Fun(fun); }
+                        let func = Arc::clone(func);
+                        let fun = Arc::new(VmFun::Native {
+                            name: name.clone(),
                             func: Box::new(move |args: &[Val]| {
                                 // Convert Val → AxValue for native call
                                 let ax_args: Vec<AxValue> = args.iter()
                                     .map(VmCore::val_to_ax)
                                     .collect();
+                                let shared = SharedArgs::new(args, &ax_args);
+                                let revision = collections_revision();
                                 let result = func(ax_args);
-                                Ok(VmCore::ax_to_val(&result))
+                                if collections_revision() != revision { shared.write_back(); }
+                                result.map(|r| shared.to_val(&r))
                             }),
-                        }))
+                        });
+                        HandleTable::link(callable, &fun);
+                        Val::Fun(fun)
                     }
                     AxCallable::UserDefined { .. } => {
                         // User-defined functions are not pre-compiled here.
@@ -258,20 +1049,51 @@ impl VmCore {
                 }
             }
             AxValue::Lst(list) => {
-                let items: Vec<Val> = list.read().unwrap()
-                    .iter()
-                    .map(VmCore::ax_to_val)
-                    .collect();
-                Val::List(Arc::new(Mutex::new(items)))
+                let key = Arc::as_ptr(list) as usize;
+                if let Some(v) = ConversionCache::vm(key) { return v; }
+                let revision = collections_revision();
+                let vm_list = Arc::new(Mutex::new(Vec::new()));
+                let v = Val::List(Arc::clone(&vm_list));
+                ConversionCache::open_vm(key, Some(&v));
+                // Keep any room reserved with `reserve`
+                let source = list.read().unwrap();
+                let mut items = Vec::with_capacity(source.capacity());
+                items.extend(source.iter().map(VmCore::ax_to_val));
+                drop(source);
+                *vm_list.lock() = items;
+                if is_frozen_container(list) {
+                    freeze_container(&vm_list);
+                }
+                ConversionCache::open_vm(key, None);
+                ConversionCache::store_vm(revision, key, Source::List(Arc::downgrade(list)), &v);
+                ConversionCache::store_ax(revision, Arc::as_ptr(&vm_list) as usize, Source::VmList(Arc::downgrade(&vm_list)), av);
+                v
             }
             AxValue::Map(dash_map) => {
+                let key = Arc::as_ptr(dash_map) as usize;
+                if let Some(v) = ConversionCache::vm(key) { return v; }
+                let revision = collections_revision();
+                let vm_map = Arc::new(Mutex::new(HashMap::new()));
+                let v = Val::Map(Arc::clone(&vm_map));
+                ConversionCache::open_vm(key, Some(&v));
                 // Convert DashMap to HashMap for the VM
-                let mut hmap = HashMap::new();
+                let mut hmap = HashMap::with_capacity(dash_map.capacity().max(dash_map.len()));
                 for entry in dash_map.iter() {
-                    hmap.insert(entry.key().clone(), VmCore::ax_to_val(entry.value()));
+                    hmap.insert(vm_str(entry.key()), VmCore::ax_to_val(entry.value()));
+                }
+                *vm_map.lock() = hmap;
+                if is_frozen_container(dash_map) {
+                    freeze_container(&vm_map);
                 }
-                Val::Map(Arc::new(Mutex::new(hmap)))
+                ConversionCache::open_vm(key, None);
+                ConversionCache::store_vm(revision, key, Source::Map(Arc::downgrade(dash_map)), &v);
+                ConversionCache::store_ax(revision, Arc::as_ptr(&vm_map) as usize, Source::VmMap(Arc::downgrade(&vm_map)), av);
+                v
             }
+            AxValue::EnumVariant(name, payload) => Val::Enum(
+                Sym::new(name),
+                (!matches!(**payload, AxValue::Nil)).then(|| Arc::new(VmCore::ax_to_val(payload))),
+            ),
             _ => Val::Nil,
         }
     }
@@ -285,11 +1107,45 @@ impl VmCore {
             Val::Float(f)   => AxValue::Num(*f),
             Val::Str(s)     => AxValue::Str(s.to_string()),
             Val::List(l)    => {
-                let items: Vec<AxValue> = l.lock().iter().map(VmCore::val_to_ax).collect();
-                AxValue::Lst(Arc::new(std::sync::RwLock::new(items)))
+                let key = Arc::as_ptr(l) as usize;
+                if let Some(v) = ConversionCache::ax(key) { return v; }
+                let revision = collections_revision();
+                let ax_list = Arc::new(std::sync::RwLock::new(Vec::new()));
+                ConversionCache::open_ax(key, Some(&AxValue::Lst(Arc::clone(&ax_list))));
+                // Snapshot first: the VM's locks are not reentrant
+                let items = l.lock().clone();
+                *ax_list.write().unwrap() = items.iter().map(VmCore::val_to_ax).collect();
+                if is_frozen_container(l) {
+                    freeze_container(&ax_list);
+                }
+                ConversionCache::open_ax(key, None);
+                ConversionCache::store_ax(revision, key, Source::VmList(Arc::downgrade(l)), &AxValue::Lst(Arc::clone(&ax_list)));
+                ConversionCache::store_vm(revision, Arc::as_ptr(&ax_list) as usize, Source::List(Arc::downgrade(&ax_list)), v);
+                AxValue::Lst(ax_list)
             }
-            Val::Fun(_)     => AxValue::Nil, // not needed for output
-            Val::Map(_)     => AxValue::Nil,
+            Val::Map(m)     => {
+                let key = Arc::as_ptr(m) as usize;
+                if let Some(v) = ConversionCache::ax(key) { return v; }
+                let revision = collections_revision();
+                let ax_map = Arc::new(DashMap::new());
+                ConversionCache::open_ax(key, Some(&AxValue::Map(Arc::clone(&ax_map))));
+                let entries = m.lock().clone();
+                for (k, item) in entries.iter() {
+                    ax_map.insert(k.to_string(), VmCore::val_to_ax(item));
+                }
+                if is_frozen_container(m) {
+                    freeze_container(&ax_map);
+                }
+                ConversionCache::open_ax(key, None);
+                ConversionCache::store_ax(revision, key, Source::VmMap(Arc::downgrade(m)), &AxValue::Map(Arc::clone(&ax_map)));
+                ConversionCache::store_vm(revision, Arc::as_ptr(&ax_map) as usize, Source::Map(Arc::downgrade(&ax_map)), v);
+                AxValue::Map(ax_map)
+            }
+            Val::Fun(f)     => AxValue::Fun(HandleTable::ax_for(f).unwrap_or_else(|| export_fun(f))),
+            Val::Enum(variant, payload) => AxValue::EnumVariant(
+                variant.as_str(),
+                Box::new(payload.as_deref().map(VmCore::val_to_ax).unwrap_or(AxValue::Nil)),
+            ),
         }
     }
 
@@ -297,15 +1153,82 @@ impl VmCore {
 
     /// Run the top-level proto.  Returns the last value produced (usually Nil).
     pub fn run(&mut self, proto: Arc<Proto>) -> Result<Val, RuntimeError> {
-        let nregs = (proto.reg_count as usize + 32).max(64);
+        self.meter.reset();
+        let regs = self.take_regs(proto.reg_count as usize);
         self.frames.push(Frame {
-            regs:     vec![Val::Nil; nregs],
+            regs,
             proto,
             ip:       0,
             ret_reg:  0,
             upvalues: vec![],
+            callee:   None,
+            prop_sites: None,
+        });
+        let caps = self.capabilities;
+        crate::sandbox::with_capabilities(caps, || self.execute())
+    }
+
+    /// Call `fun` with `args` on this (idle) VM and return its result.
+    pub fn call(&mut self, fun: &Arc<VmFun>, args: Vec<Val>) -> Result<Val, RuntimeError> {
+        let (proto, params, upvalues) = match fun.as_ref() {
+            VmFun::Native { name, func } => {
+                self.capabilities.check(name)?;
+                return self.exporting(|| catch_native(name, || func(&args)));
+            }
+            VmFun::Compiled { proto, params, upvalues, .. } => (proto, *params, upvalues),
+        };
+        let mut regs = self.take_regs(proto.reg_count as usize);
+        for (i, arg) in args.into_iter().enumerate().take(params) {
+            regs[i] = arg;
+        }
+        self.frames.push(Frame {
+            regs,
+            proto:    Arc::clone(proto),
+            ip:       0,
+            ret_reg:  0,
+            upvalues: upvalues.clone(),
+            callee:   Some(Arc::clone(fun)),
+            prop_sites: None,
+        });
+        let caps = self.capabilities;
+        crate::sandbox::with_capabilities(caps, || self.execute())
+    }
+
+    /// A register file of `len` nils, reusing one a returned frame left behind.
+    fn take_regs(&mut self, len: usize) -> Vec<Val> {
+        let mut regs = self.reg_pool.pop().unwrap_or_default();
+        regs.resize(len, Val::Nil);
+        regs
+    }
+
+    fn recycle_regs(&mut self, mut regs: Vec<Val>) {
+        if self.reg_pool.len() < REG_POOL_MAX {
+            regs.clear();
+            self.reg_pool.push(regs);
+        }
+    }
+
+    fn pop_frame(&mut self) {
+        if let Some(frame) = self.frames.pop() {
+            self.recycle_regs(frame.regs);
+        }
+    }
+
+    /// Run until the frame stack empties. A panic in dispatch is reported as
+    /// `RuntimeError::Internal` against the frame it happened in; on any error
+    /// the VM is left idle so it can take the next call.
+    fn execute(&mut self) -> Result<Val, RuntimeError> {
+        let result = std::panic::catch_unwind(AssertUnwindSafe(|| self.dispatch())).unwrap_or_else(|payload| {
+            let function = self.frames.last().map_or("<main>", Self::frame_name).to_string();
+            Err(RuntimeError::Internal { function, message: panic_message(payload.as_ref()) })
         });
+        if result.is_err() {
+            self.frames.clear();
+        }
+        result
+    }
 
+    fn dispatch(&mut self) -> Result<Val, RuntimeError> {
         loop {
             // ── fetch ──────────────────────────────────────────────────────────
             let frame_idx = self.frames.len() - 1;
@@ -313,7 +1236,7 @@ impl VmCore {
             if self.frames[frame_idx].ip >= self.frames[frame_idx].proto.code.len() {
                 // Fell off the end without a Return — implicit nil return
                 let ret_reg = self.frames[frame_idx].ret_reg;
-                self.frames.pop();
+                self.pop_frame();
                 if self.frames.is_empty() {
                     return Ok(Val::Nil);
                 }
@@ -325,7 +1248,14 @@ impl VmCore {
                 let f = &self.frames[frame_idx];
                 f.proto.code[f.ip]
             };
+            if let Some(trace) = &self.trace {
+                let name = Self::frame_name(&self.frames[frame_idx]);
+                if trace.matches(name) {
+                    eprintln!("{}", self.trace_line(frame_idx, self.frames[frame_idx].ip, instr));
+                }
+            }
             self.frames[frame_idx].ip += 1;
+            self.meter.tick()?;
 
             let op  = instr.op();
             let a   = instr.a() as usize;
@@ -355,7 +1285,7 @@ impl VmCore {
                 }
                 Op::LoadStr => {
                     let s = self.frames[frame_idx].proto.str_consts.get(bx)
-                        .map(|s| Arc::from(s.as_str()))
+                        .cloned()
                         .unwrap_or_else(|| Arc::from(""));
                     self.frames[frame_idx].regs[a] = Val::Str(s);
                 }
@@ -372,79 +1302,77 @@ impl VmCore {
                 // ── Globals ─────────────────────────────────────────────────────
                 Op::LoadGlobal => {
                     let v = self.globals.get(bx).cloned().unwrap_or(Val::Nil);
+                    if let (Val::Nil, Some(strict)) = (&v, &self.strict) {
+                        strict.check(bx)?;
+                    }
                     self.frames[frame_idx].regs[a] = v;
                 }
                 Op::StoreGlobal => {
                     let v = self.frames[frame_idx].regs[a].clone();
-                    if bx >= self.globals.len() {
-                        self.globals.resize(bx + 1, Val::Nil);
+                    let globals = Arc::make_mut(&mut self.globals);
+                    if bx >= globals.len() {
+                        globals.resize(bx + 1, Val::Nil);
+                    }
+                    globals[bx] = v;
+                    if let Some(strict) = &mut self.strict {
+                        strict.bind(bx);
                     }
-                    self.globals[bx] = v;
                 }
 
                 // ── Generic arithmetic ──────────────────────────────────────────
                 Op::Add => {
-                    let lv = self.frames[frame_idx].regs[b].clone();
-                    let rv = self.frames[frame_idx].regs[c].clone();
-                    self.frames[frame_idx].regs[a] = binop_add(lv, rv)?;
+                    let regs = &mut self.frames[frame_idx].regs;
+                    regs[a] = binop_add(&regs[b], &regs[c])?;
                 }
                 Op::Sub => {
-                    let lv = self.frames[frame_idx].regs[b].clone();
-                    let rv = self.frames[frame_idx].regs[c].clone();
-                    self.frames[frame_idx].regs[a] = binop_sub(lv, rv)?;
+                    let regs = &mut self.frames[frame_idx].regs;
+                    regs[a] = binop_sub(&regs[b], &regs[c])?;
                 }
                 Op::Mul => {
-                    let lv = self.frames[frame_idx].regs[b].clone();
-                    let rv = self.frames[frame_idx].regs[c].clone();
-                    self.frames[frame_idx].regs[a] = binop_mul(lv, rv)?;
+                    let regs = &mut self.frames[frame_idx].regs;
+                    regs[a] = binop_mul(&regs[b], &regs[c])?;
                 }
                 Op::Div => {
-                    let lv = self.frames[frame_idx].regs[b].clone();
-                    let rv = self.frames[frame_idx].regs[c].clone();
-                    self.frames[frame_idx].regs[a] = binop_div(lv, rv)?;
+                    let regs = &mut self.frames[frame_idx].regs;
+                    regs[a] = binop_div(&regs[b], &regs[c])?;
                 }
                 Op::Mod => {
-                    let lv = self.frames[frame_idx].regs[b].clone();
-                    let rv = self.frames[frame_idx].regs[c].clone();
-                    self.frames[frame_idx].regs[a] = binop_mod(lv, rv)?;
+                    let regs = &mut self.frames[frame_idx].regs;
+                    regs[a] = binop_mod(&regs[b], &regs[c])?;
                 }
                 Op::Pow => {
-                    let lv = self.frames[frame_idx].regs[b].clone();
-                    let rv = self.frames[frame_idx].regs[c].clone();
-                    self.frames[frame_idx].regs[a] = Val::Float(lv.as_f64().powf(rv.as_f64()));
+                    let regs = &mut self.frames[frame_idx].regs;
+                    regs[a] = Val::Float(regs[b].as_f64().powf(regs[c].as_f64()));
                 }
                 Op::Neg => {
-                    let v = self.frames[frame_idx].regs[b].clone();
-                    self.frames[frame_idx].regs[a] = match v {
-                        Val::Int(n)   => Val::Int(-n),
+                    let regs = &mut self.frames[frame_idx].regs;
+                    regs[a] = match &regs[b] {
+                        Val::Int(n)   => n.checked_neg().map_or(Val::Float(-(*n as f64)), Val::Int),
                         Val::Float(f) => Val::Float(-f),
-                        _ => Val::Float(-v.as_f64()),
+                        v => Val::Float(-v.as_f64()),
                     };
                 }
 
                 // ── Specialized integer arithmetic (fast path) ──────────────────
                 Op::AddInt => {
-                    let lv = self.frames[frame_idx].regs[b].clone();
-                    let rv = self.frames[frame_idx].regs[c].clone();
-                    self.frames[frame_idx].regs[a] = match (&lv, &rv) {
-                        (Val::Int(x), Val::Int(y)) => Val::Int(x.wrapping_add(*y)),
-                        _ => binop_add(lv, rv)?,
+                    let regs = &mut self.frames[frame_idx].regs;
+                    regs[a] = match (&regs[b], &regs[c]) {
+                        (Val::Int(x), Val::Int(y)) => int_add(*x, *y),
+                        (lv, rv) => binop_add(lv, rv)?,
                     };
                 }
                 Op::SubInt => {
-                    let lv = self.frames[frame_idx].regs[b].clone();
-                    let rv = self.frames[frame_idx].regs[c].clone();
-                    self.frames[frame_idx].regs[a] = match (&lv, &rv) {
-                        (Val::Int(x), Val::Int(y)) => Val::Int(x.wrapping_sub(*y)),
-                        _ => binop_sub(lv, rv)?,
+                    let regs = &mut self.frames[frame_idx].regs;
+                    regs[a] = match (&regs[b], &regs[c]) {
+                        (Val::Int(x), Val::Int(y)) => int_sub(*x, *y),
+                        (lv, rv) => binop_sub(lv, rv)?,
                     };
                 }
                 Op::MulInt => {
-                    let lv = self.frames[frame_idx].regs[b].clone();
-                    let rv = self.frames[frame_idx].regs[c].clone();
-                    self.frames[frame_idx].regs[a] = match (&lv, &rv) {
-                        (Val::Int(x), Val::Int(y)) => Val::Int(x.wrapping_mul(*y)),
-                        _ => binop_mul(lv, rv)?,
+                    let regs = &mut self.frames[frame_idx].regs;
+                    regs[a] = match (&regs[b], &regs[c]) {
+                        (Val::Int(x), Val::Int(y)) => int_mul(*x, *y),
+                        (lv, rv) => binop_mul(lv, rv)?,
                     };
                 }
                 Op::AddFloat => {
@@ -469,93 +1397,79 @@ impl VmCore {
                 }
 
                 // ── Superinstructions ───────────────────────────────────────────
-                // AddIntImm: A, B, sBx → R[A] = R[B] + sBx
+                // AddIntImm: A, B, sC → R[A] = R[B] + sC
                 Op::AddIntImm => {
-                    let v = self.frames[frame_idx].regs[b].clone();
-                    self.frames[frame_idx].regs[a] = match v {
-                        Val::Int(n) => Val::Int(n.wrapping_add(sbx as i64)),
-                        Val::Float(f) => Val::Float(f + sbx as f64),
-                        _ => Val::Int(sbx as i64),
+                    let imm = instr.sc() as i64;
+                    let regs = &mut self.frames[frame_idx].regs;
+                    regs[a] = match &regs[b] {
+                        Val::Int(n) => int_add(*n, imm),
+                        other => binop_add(other, &Val::Int(imm))?,
                     };
                 }
                 // IncrLocal: R[A] = R[A] + 1
                 Op::IncrLocal => {
-                    let v = self.frames[frame_idx].regs[a].clone();
-                    self.frames[frame_idx].regs[a] = match v {
-                        Val::Int(n) => Val::Int(n.wrapping_add(1)),
-                        Val::Float(f) => Val::Float(f + 1.0),
+                    let reg = &mut self.frames[frame_idx].regs[a];
+                    *reg = match &*reg {
+                        Val::Int(n) => int_add(*n, 1),
+                        Val::Float(f) => Val::Float(*f + 1.0),
                         _ => Val::Int(1),
                     };
                 }
                 // DecrLocal: R[A] = R[A] - 1
                 Op::DecrLocal => {
-                    let v = self.frames[frame_idx].regs[a].clone();
-                    self.frames[frame_idx].regs[a] = match v {
-                        Val::Int(n) => Val::Int(n.wrapping_sub(1)),
-                        Val::Float(f) => Val::Float(f - 1.0),
+                    let reg = &mut self.frames[frame_idx].regs[a];
+                    *reg = match &*reg {
+                        Val::Int(n) => int_sub(*n, 1),
+                        Val::Float(f) => Val::Float(*f - 1.0),
                         _ => Val::Int(-1),
                     };
                 }
-                // CmpLtJmp: A, B, sBx → if R[A] >= R[B]: ip += sBx
+                // CmpLtJmp: A, B, sC → if !(R[A] < R[B]): ip += sC
                 Op::CmpLtJmp => {
-                    let la = self.frames[frame_idx].regs[a].clone();
-                    let lb = self.frames[frame_idx].regs[b].clone();
-                    let cond = match (&la, &lb) {
-                        (Val::Int(x), Val::Int(y)) => x >= y,
-                        _ => la.as_f64() >= lb.as_f64(),
-                    };
-                    if cond {
+                    let regs = &self.frames[frame_idx].regs;
+                    if !cmp_lt(&regs[a], &regs[b]) {
                         let ip = self.frames[frame_idx].ip;
-                        self.frames[frame_idx].ip = (ip as isize + sbx) as usize;
+                        self.frames[frame_idx].ip = (ip as isize + instr.sc() as isize) as usize;
                     }
                 }
 
                 // ── Comparison ──────────────────────────────────────────────────
                 Op::Eq => {
-                    let lv = self.frames[frame_idx].regs[b].clone();
-                    let rv = self.frames[frame_idx].regs[c].clone();
-                    self.frames[frame_idx].regs[a] = Val::Bool(lv.eq_val(&rv));
+                    let regs = &mut self.frames[frame_idx].regs;
+                    regs[a] = Val::Bool(regs[b].eq_val(&regs[c]));
                 }
                 Op::Ne => {
-                    let lv = self.frames[frame_idx].regs[b].clone();
-                    let rv = self.frames[frame_idx].regs[c].clone();
-                    self.frames[frame_idx].regs[a] = Val::Bool(!lv.eq_val(&rv));
+                    let regs = &mut self.frames[frame_idx].regs;
+                    regs[a] = Val::Bool(!regs[b].eq_val(&regs[c]));
                 }
                 Op::Lt => {
-                    let lv = self.frames[frame_idx].regs[b].clone();
-                    let rv = self.frames[frame_idx].regs[c].clone();
-                    self.frames[frame_idx].regs[a] = Val::Bool(cmp_lt(&lv, &rv));
+                    let regs = &mut self.frames[frame_idx].regs;
+                    regs[a] = Val::Bool(cmp_lt(&regs[b], &regs[c]));
                 }
                 Op::Le => {
-                    let lv = self.frames[frame_idx].regs[b].clone();
-                    let rv = self.frames[frame_idx].regs[c].clone();
-                    self.frames[frame_idx].regs[a] = Val::Bool(cmp_le(&lv, &rv));
+                    let regs = &mut self.frames[frame_idx].regs;
+                    regs[a] = Val::Bool(cmp_le(&regs[b], &regs[c]));
                 }
                 Op::Gt => {
-                    let lv = self.frames[frame_idx].regs[b].clone();
-                    let rv = self.frames[frame_idx].regs[c].clone();
-                    self.frames[frame_idx].regs[a] = Val::Bool(cmp_lt(&rv, &lv));
+                    let regs = &mut self.frames[frame_idx].regs;
+                    regs[a] = Val::Bool(cmp_lt(&regs[c], &regs[b]));
                 }
                 Op::Ge => {
-                    let lv = self.frames[frame_idx].regs[b].clone();
-                    let rv = self.frames[frame_idx].regs[c].clone();
-                    self.frames[frame_idx].regs[a] = Val::Bool(cmp_le(&rv, &lv));
+                    let regs = &mut self.frames[frame_idx].regs;
+                    regs[a] = Val::Bool(cmp_le(&regs[c], &regs[b]));
                 }
                 // Specialised integer comparison
                 Op::LtInt => {
-                    let lv = self.frames[frame_idx].regs[b].clone();
-                    let rv = self.frames[frame_idx].regs[c].clone();
-                    self.frames[frame_idx].regs[a] = Val::Bool(cmp_lt(&lv, &rv));
+                    let regs = &mut self.frames[frame_idx].regs;
+                    regs[a] = Val::Bool(cmp_lt(&regs[b], &regs[c]));
                 }
                 Op::LeInt => {
-                    let lv = self.frames[frame_idx].regs[b].clone();
-                    let rv = self.frames[frame_idx].regs[c].clone();
-                    self.frames[frame_idx].regs[a] = Val::Bool(cmp_le(&lv, &rv));
+                    let regs = &mut self.frames[frame_idx].regs;
+                    regs[a] = Val::Bool(cmp_le(&regs[b], &regs[c]));
                 }
                 Op::EqInt => {
-                    let lv = self.frames[frame_idx].regs[b].clone();
-                    let rv = self.frames[frame_idx].regs[c].clone();
-                    self.frames[frame_idx].regs[a] = Val::Bool(lv.eq_val(&rv));
+                    let regs = &mut self.frames[frame_idx].regs;
+                    regs[a] = Val::Bool(regs[b].eq_val(&regs[c]));
                 }
 
                 // ── Logic ───────────────────────────────────────────────────────
@@ -564,21 +1478,19 @@ impl VmCore {
                     self.frames[frame_idx].regs[a] = Val::Bool(!v);
                 }
                 Op::And => {
-                    let lv = self.frames[frame_idx].regs[b].clone();
-                    let rv = self.frames[frame_idx].regs[c].clone();
-                    self.frames[frame_idx].regs[a] = if lv.is_truthy() { rv } else { lv };
+                    let regs = &mut self.frames[frame_idx].regs;
+                    regs[a] = if regs[b].is_truthy() { regs[c].clone() } else { regs[b].clone() };
                 }
                 Op::Or => {
-                    let lv = self.frames[frame_idx].regs[b].clone();
-                    let rv = self.frames[frame_idx].regs[c].clone();
-                    self.frames[frame_idx].regs[a] = if lv.is_truthy() { lv } else { rv };
+                    let regs = &mut self.frames[frame_idx].regs;
+                    regs[a] = if regs[b].is_truthy() { regs[b].clone() } else { regs[c].clone() };
                 }
 
                 // ── String concat ────────────────────────────────────────────────
                 Op::Concat => {
-                    let lv = self.frames[frame_idx].regs[b].clone();
-                    let rv = self.frames[frame_idx].regs[c].clone();
-                    let s = format!("{}{}", lv.display(), rv.display());
+                    let regs = &mut self.frames[frame_idx].regs;
+                    let s = format!("{}{}", regs[b].display(), regs[c].display());
+                    self.meter.charge(s.len())?;
                     self.frames[frame_idx].regs[a] = Val::Str(Arc::from(s.as_str()));
                 }
 
@@ -629,13 +1541,19 @@ impl VmCore {
 
                     match func_val {
                         Val::Fun(f) => match f.as_ref() {
-                            VmFun::Native { func, .. } => {
-                                let result = func(&args)?;
+                            VmFun::Native { name, func } => {
+                                self.capabilities.check(name)?;
+                                let result = self.exporting(|| catch_native(name, || func(&args)))?;
                                 self.frames[frame_idx].regs[a] = result;
                             }
                             VmFun::Compiled { proto, params, upvalues, .. } => {
-                                let nregs = (proto.reg_count as usize + 32).max(64);
-                                let mut regs = vec![Val::Nil; nregs];
+                                if self.frames.len() >= self.max_frames {
+                                    return Err(RuntimeError::StackOverflow {
+                                        limit: self.max_frames,
+                                        call_chain: self.call_chain(),
+                                    });
+                                }
+                                let mut regs = self.take_regs(proto.reg_count as usize);
                                 for (i, arg) in args.into_iter().enumerate() {
                                     if i < *params { regs[i] = arg; }
                                 }
@@ -645,6 +1563,8 @@ impl VmCore {
                                     ip:      0,
                                     ret_reg: a,
                                     upvalues: upvalues.clone(),
+                                    callee:  Some(Arc::clone(&f)),
+                                    prop_sites: None,
                                 });
                                 continue; // skip frame_idx update — new frame is now active
                             }
@@ -654,6 +1574,8 @@ impl VmCore {
                             return Err(RuntimeError::NilCall {
                                 hint: "Value resolved to nil — check parent-scope identifier binding (AXM_402)".into(),
                                 span: Default::default(),
+                                name: None,
+                                candidates: Vec::new(),
                             });
                         }
                         other => {
@@ -674,11 +1596,12 @@ impl VmCore {
 
                     match func_val {
                         Val::Fun(f) => match f.as_ref() {
-                            VmFun::Native { func, .. } => {
-                                let result = func(&args)?;
+                            VmFun::Native { name, func } => {
+                                self.capabilities.check(name)?;
+                                let result = self.exporting(|| catch_native(name, || func(&args)))?;
                                 // Return immediately — tail call to native
                                 let ret_reg = self.frames[frame_idx].ret_reg;
-                                self.frames.pop();
+                                self.pop_frame();
                                 if self.frames.is_empty() {
                                     return Ok(result);
                                 }
@@ -686,19 +1609,21 @@ impl VmCore {
                             }
                             VmFun::Compiled { proto, params, upvalues, .. } => {
                                 // Reuse current frame (real tail-call optimization)
-                                let nregs = (proto.reg_count as usize + 32).max(64);
-                                let mut new_regs = vec![Val::Nil; nregs];
+                                let mut new_regs = self.take_regs(proto.reg_count as usize);
                                 for (i, arg) in args.into_iter().enumerate() {
                                     if i < *params { new_regs[i] = arg; }
                                 }
                                 let ret_reg = self.frames[frame_idx].ret_reg;
-                                self.frames[frame_idx] = Frame {
+                                let old = std::mem::replace(&mut self.frames[frame_idx], Frame {
                                     regs:     new_regs,
                                     proto:    Arc::clone(proto),
                                     ip:       0,
                                     ret_reg,
                                     upvalues: upvalues.clone(),
-                                };
+                                    callee:   Some(Arc::clone(&f)),
+                                    prop_sites: None,
+                                });
+                                self.recycle_regs(old.regs);
                                 continue;
                             }
                         }
@@ -712,9 +1637,10 @@ impl VmCore {
                 }
 
                 Op::Return => {
-                    let ret_val = self.frames[frame_idx].regs[a].clone();
+                    // The frame is discarded, so its register can give up the value
+                    let ret_val = std::mem::replace(&mut self.frames[frame_idx].regs[a], Val::Nil);
                     let ret_reg = self.frames[frame_idx].ret_reg;
-                    self.frames.pop();
+                    self.pop_frame();
                     if self.frames.is_empty() {
                         return Ok(ret_val);
                     }
@@ -723,7 +1649,7 @@ impl VmCore {
 
                 Op::ReturnNil | Op::NilReturn => {
                     let ret_reg = self.frames[frame_idx].ret_reg;
-                    self.frames.pop();
+                    self.pop_frame();
                     if self.frames.is_empty() {
                         return Ok(Val::Nil);
                     }
@@ -782,24 +1708,27 @@ impl VmCore {
                 // ── Lists ────────────────────────────────────────────────────────
                 Op::NewList => {
                     // abc(dst, base, count): b=base register, c=item count
-                    let base  = instr.b() as usize;
-                    let count = instr.c() as usize;
-                    let regs_len = self.frames[frame_idx].regs.len();
-                    let items: Vec<Val> = (0..count)
-                        .map(|i| {
-                            let idx = base + i;
-                            if idx < regs_len {
-                                self.frames[frame_idx].regs[idx].clone()
-                            } else {
-                                Val::Nil
-                            }
-                        })
-                        .collect();
+                    let items = self.frames[frame_idx].regs[b..b + c].to_vec();
+                    self.meter.charge(items.len() * std::mem::size_of::<Val>())?;
                     self.frames[frame_idx].regs[a] = Val::List(Arc::new(Mutex::new(items)));
                 }
+                Op::ListReserve => {
+                    if let Val::List(l) = &self.frames[frame_idx].regs[a] {
+                        l.lock().reserve_exact(bx);
+                    }
+                }
+                Op::ListExtend => {
+                    self.meter.charge(c * std::mem::size_of::<Val>())?;
+                    let frame = &self.frames[frame_idx];
+                    if let Val::List(l) = &frame.regs[a] {
+                        l.lock().extend_from_slice(&frame.regs[b..b + c]);
+                    }
+                }
+                Op::NewMap => {
+                    self.frames[frame_idx].regs[a] = Val::Map(Arc::new(Mutex::new(HashMap::new())));
+                }
                 Op::ListLen => {
-                    let lst = self.frames[frame_idx].regs[b].clone();
-                    let len = match &lst {
+                    let len = match &self.frames[frame_idx].regs[b] {
                         Val::List(l) => l.lock().len() as i64,
                         Val::Str(s)  => s.len() as i64,
                         _            => 0,
@@ -807,9 +1736,8 @@ impl VmCore {
                     self.frames[frame_idx].regs[a] = Val::Int(len);
                 }
                 Op::GetIndex => {
-                    let obj = self.frames[frame_idx].regs[b].clone();
-                    let idx = self.frames[frame_idx].regs[c].clone();
-                    let result = match (&obj, &idx) {
+                    let regs = &self.frames[frame_idx].regs;
+                    let result = match (&regs[b], &regs[c]) {
                         (Val::List(l), Val::Int(i)) => {
                             let lst = l.lock();
                             let i = *i;
@@ -836,34 +1764,31 @@ impl VmCore {
                 }
                 Op::SetIndex => {
                     // R[A][R[B]] = R[C]
-                    let obj = self.frames[frame_idx].regs[a].clone();
-                    let idx = self.frames[frame_idx].regs[b].clone();
-                    let val = self.frames[frame_idx].regs[c].clone();
-                    if let (Val::List(l), Val::Int(i)) = (&obj, &idx) {
+                    let regs = &self.frames[frame_idx].regs;
+                    if let (Val::List(l), Val::Int(i)) = (&regs[a], &regs[b]) {
+                        if is_frozen_container(l) {
+                            return Err(RuntimeError::FrozenMutation { type_name: "Lst".into(), span: Default::default() });
+                        }
                         let mut lst = l.lock();
                         let i = *i as usize;
-                        if i < lst.len() { lst[i] = val; }
+                        if i < lst.len() { lst[i] = regs[c].clone(); touch_collections(); }
                     }
                 }
 
                 // ── Property access ──────────────────────────────────────────────
                 Op::GetProp => {
-                    // GetProp A, Bx — obj in regs[c] (see compiler patch)
-                    // In bytecode.rs the compiler patches: code[last].0 |= (obj_r as u32) << 24;
-                    // So C field = obj register
-                    let obj_reg = instr.c() as usize;
-                    let str_idx = bx;
-                    let obj = self.frames[frame_idx].regs[obj_reg].clone();
-                    let prop_name = self.frames[frame_idx].proto.str_consts.get(str_idx)
+                    // GetProp A, Bx — R[A] = R[A].S[Bx]
+                    let obj = self.frames[frame_idx].regs[a].clone();
+                    let prop_name = self.frames[frame_idx].proto.str_consts.get(bx)
                         .cloned()
-                        .unwrap_or_default();
+                        .unwrap_or_else(|| Arc::from(""));
                     let result = match &obj {
-                        Val::Map(m) => m.lock().get(&prop_name).cloned().unwrap_or(Val::Nil),
-                        Val::Str(s) => match prop_name.as_str() {
+                        Val::Map(m) => self.map_get(frame_idx, m, &prop_name).unwrap_or(Val::Nil),
+                        Val::Str(s) => match &*prop_name {
                             "len" => Val::Int(s.len() as i64),
                             _     => Val::Nil,
                         }
-                        Val::List(l) => match prop_name.as_str() {
+                        Val::List(l) => match &*prop_name {
                             "len" => Val::Int(l.lock().len() as i64),
                             _     => Val::Nil,
                         }
@@ -871,6 +1796,93 @@ impl VmCore {
                     };
                     self.frames[frame_idx].regs[a] = result;
                 }
+                Op::GetMethod => {
+                    // GetMethod A, Bx — R[A] = R[A].method[Bx]; list/string methods bind R[A]
+                    let obj = self.frames[frame_idx].regs[a].clone();
+                    let method = self.frames[frame_idx].proto.str_consts.get(bx)
+                        .cloned()
+                        .unwrap_or_else(|| Arc::from(""));
+                    let result = match &obj {
+                        Val::Map(m) => match self.map_get(frame_idx, m, &method) {
+                            Some(v) => v,
+                            None => return Err(RuntimeError::NilCall {
+                                hint: format!("No method '{}' on Map", method),
+                                span: Default::default(),
+                                name: Some(method.to_string()),
+                                candidates: m.lock().keys().map(|k| k.to_string()).collect(),
+                            }),
+                        },
+                        other => match bound_method(other, &method) {
+                            Some(f) => f,
+                            None => return Err(RuntimeError::GenericError {
+                                message: format!("No method '{}' on {}", method, other.type_name()),
+                                span: Default::default(),
+                            }),
+                        },
+                    };
+                    self.frames[frame_idx].regs[a] = result;
+                }
+                Op::SetProp => {
+                    // SetProp A, B, C — R[A].R[B] = R[C]
+                    let obj = self.frames[frame_idx].regs[a].clone();
+                    let key = self.frames[frame_idx].regs[b].clone();
+                    let val = self.frames[frame_idx].regs[c].clone();
+                    if let (Val::Map(m), Val::Str(k)) = (&obj, &key) {
+                        if is_frozen_container(m) {
+                            return Err(RuntimeError::FrozenMutation { type_name: "Map".into(), span: Default::default() });
+                        }
+                        m.lock().insert(Arc::clone(k), val);
+                        touch_collections();
+                    }
+                }
+                Op::LoadCallee => {
+                    let callee = self.frames[frame_idx].callee.clone();
+                    self.frames[frame_idx].regs[a] = callee.map(Val::Fun).unwrap_or(Val::Nil);
+                }
+
+                // ── Enums ────────────────────────────────────────────────────────
+                Op::NewEnum => {
+                    let variant = self.frames[frame_idx].proto.variants[bx];
+                    let payload = match std::mem::replace(&mut self.frames[frame_idx].regs[a], Val::Nil) {
+                        Val::Nil => None,
+                        v => Some(Arc::new(v)),
+                    };
+                    self.frames[frame_idx].regs[a] = Val::Enum(variant, payload);
+                }
+                Op::MatchEnum => {
+                    let frame = &mut self.frames[frame_idx];
+                    let table = &frame.proto.jump_tables[bx];
+                    let offset = match &frame.regs[a] {
+                        Val::Enum(variant, _) => table.targets.get(variant).copied().unwrap_or(table.default),
+                        _ => table.default,
+                    };
+                    frame.ip = (frame.ip as isize + offset as isize) as usize;
+                }
+                Op::EnumPayload => {
+                    let payload = match &self.frames[frame_idx].regs[b] {
+                        Val::Enum(_, Some(payload)) => (**payload).clone(),
+                        _ => Val::Nil,
+                    };
+                    self.frames[frame_idx].regs[a] = payload;
+                }
+
+                // ── List patterns ────────────────────────────────────────────────
+                Op::IsListLen | Op::IsListMin => {
+                    let fits = match &self.frames[frame_idx].regs[b] {
+                        Val::List(l) if op == Op::IsListLen => l.lock().len() == c,
+                        Val::List(l) => l.lock().len() >= c,
+                        _ => false,
+                    };
+                    self.frames[frame_idx].regs[a] = Val::Bool(fits);
+                }
+                Op::ListTail => {
+                    let tail = match &self.frames[frame_idx].regs[b] {
+                        Val::List(l) => l.lock().get(c..).unwrap_or_default().to_vec(),
+                        _ => Vec::new(),
+                    };
+                    self.meter.charge(tail.len() * std::mem::size_of::<Val>())?;
+                    self.frames[frame_idx].regs[a] = Val::List(Arc::new(Mutex::new(tail)));
+                }
 
                 // ── Misc ─────────────────────────────────────────────────────────
                 Op::Nop  => {}
@@ -921,10 +1933,28 @@ impl VmCore {
 // Arithmetic helpers — inline-able, branch-predictable
 // ═══════════════════════════════════════════════════════════════════════════
 
+// Int results that do not fit in an i64 continue as floats, which is what
+// the tree-walker (all f64) computes as well; nothing ever wraps.
+
 #[inline(always)]
-fn binop_add(l: Val, r: Val) -> Result<Val, RuntimeError> {
-    Ok(match (&l, &r) {
-        (Val::Int(a),   Val::Int(b))   => Val::Int(a.wrapping_add(*b)),
+fn int_add(a: i64, b: i64) -> Val {
+    a.checked_add(b).map_or(Val::Float(a as f64 + b as f64), Val::Int)
+}
+
+#[inline(always)]
+fn int_sub(a: i64, b: i64) -> Val {
+    a.checked_sub(b).map_or(Val::Float(a as f64 - b as f64), Val::Int)
+}
+
+#[inline(always)]
+fn int_mul(a: i64, b: i64) -> Val {
+    a.checked_mul(b).map_or(Val::Float(a as f64 * b as f64), Val::Int)
+}
+
+#[inline(always)]
+fn binop_add(l: &Val, r: &Val) -> Result<Val, RuntimeError> {
+    Ok(match (l, r) {
+        (Val::Int(a),   Val::Int(b))   => int_add(*a, *b),
         (Val::Float(a), Val::Float(b)) => Val::Float(a + b),
         (Val::Int(a),   Val::Float(b)) => Val::Float(*a as f64 + b),
         (Val::Float(a), Val::Int(b))   => Val::Float(a + *b as f64),
@@ -934,9 +1964,9 @@ fn binop_add(l: Val, r: Val) -> Result<Val, RuntimeError> {
 }
 
 #[inline(always)]
-fn binop_sub(l: Val, r: Val) -> Result<Val, RuntimeError> {
-    Ok(match (&l, &r) {
-        (Val::Int(a),   Val::Int(b))   => Val::Int(a.wrapping_sub(*b)),
+fn binop_sub(l: &Val, r: &Val) -> Result<Val, RuntimeError> {
+    Ok(match (l, r) {
+        (Val::Int(a),   Val::Int(b))   => int_sub(*a, *b),
         (Val::Float(a), Val::Float(b)) => Val::Float(a - b),
         (Val::Int(a),   Val::Float(b)) => Val::Float(*a as f64 - b),
         (Val::Float(a), Val::Int(b))   => Val::Float(a - *b as f64),
@@ -945,9 +1975,9 @@ fn binop_sub(l: Val, r: Val) -> Result<Val, RuntimeError> {
 }
 
 #[inline(always)]
-fn binop_mul(l: Val, r: Val) -> Result<Val, RuntimeError> {
-    Ok(match (&l, &r) {
-        (Val::Int(a),   Val::Int(b))   => Val::Int(a.wrapping_mul(*b)),
+fn binop_mul(l: &Val, r: &Val) -> Result<Val, RuntimeError> {
+    Ok(match (l, r) {
+        (Val::Int(a),   Val::Int(b))   => int_mul(*a, *b),
         (Val::Float(a), Val::Float(b)) => Val::Float(a * b),
         (Val::Int(a),   Val::Float(b)) => Val::Float(*a as f64 * b),
         (Val::Float(a), Val::Int(b))   => Val::Float(a * *b as f64),
@@ -956,7 +1986,7 @@ fn binop_mul(l: Val, r: Val) -> Result<Val, RuntimeError> {
 }
 
 #[inline(always)]
-fn binop_div(l: Val, r: Val) -> Result<Val, RuntimeError> {
+fn binop_div(l: &Val, r: &Val) -> Result<Val, RuntimeError> {
     let divisor = r.as_f64();
     if divisor == 0.0 {
         return Err(RuntimeError::DivisionByZero { span: Default::default() });
@@ -965,9 +1995,9 @@ fn binop_div(l: Val, r: Val) -> Result<Val, RuntimeError> {
 }
 
 #[inline(always)]
-fn binop_mod(l: Val, r: Val) -> Result<Val, RuntimeError> {
-    Ok(match (&l, &r) {
-        (Val::Int(a), Val::Int(b)) if *b != 0 => Val::Int(a.rem_euclid(*b)),
+fn binop_mod(l: &Val, r: &Val) -> Result<Val, RuntimeError> {
+    Ok(match (l, r) {
+        (Val::Int(a), Val::Int(b)) if *b != 0 => a.checked_rem_euclid(*b).map_or(Val::Int(0), Val::Int),
         _ => Val::Float(l.as_f64() % r.as_f64()),
     })
 }
@@ -995,3 +2025,792 @@ fn cmp_le(l: &Val, r: &Val) -> bool {
         _                              => false,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::RwLock;
+
+    fn ax_list(items: Vec<AxValue>) -> AxValue {
+        AxValue::Lst(Arc::new(RwLock::new(items)))
+    }
+
+    #[test]
+    fn test_short_strings_share_interned_allocation() {
+        let interned = crate::intern::intern("vm-boundary-name");
+        match VmCore::ax_to_val(&AxValue::Str("vm-boundary-name".into())) {
+            Val::Str(s) => assert!(Arc::ptr_eq(&s, &interned)),
+            other => panic!("expected a string, got {:?}", other),
+        }
+        match (VmCore::ax_to_val(&AxValue::Str(String::new())), VmCore::ax_to_val(&AxValue::Str(String::new()))) {
+            (Val::Str(a), Val::Str(b)) => assert!(Arc::ptr_eq(&a, &b)),
+            other => panic!("expected strings, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_unchanged_list_is_converted_once() {
+        let list = ax_list(vec![AxValue::Num(1.0), AxValue::Str("a".into())]);
+        // Other tests mutate collections concurrently; retry until a pair of
+        // conversions lands inside one revision.
+        for _ in 0..100 {
+            let revision = collections_revision();
+            let (a, b) = (VmCore::ax_to_val(&list), VmCore::ax_to_val(&list));
+            if collections_revision() != revision { continue; }
+            match (a, b) {
+                (Val::List(a), Val::List(b)) => return assert!(Arc::ptr_eq(&a, &b)),
+                other => panic!("expected lists, got {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_mutated_list_is_converted_again() {
+        let list = ax_list(vec![AxValue::Num(1.0)]);
+        let _ = VmCore::ax_to_val(&list);
+        if let AxValue::Lst(l) = &list {
+            l.write().unwrap().push(AxValue::Num(2.0));
+            touch_collections();
+        }
+        match VmCore::ax_to_val(&list) {
+            Val::List(l) => assert_eq!(l.lock().len(), 2),
+            other => panic!("expected a list, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_frozen_lists_stay_frozen_across_conversions() {
+        let list = ax_list(vec![AxValue::Num(1.0)]);
+        let AxValue::Lst(l) = &list else { unreachable!() };
+        crate::core::value::freeze_container(l);
+        let Val::List(vm_list) = VmCore::ax_to_val(&list) else { panic!("expected a list") };
+        assert!(is_frozen_container(&vm_list));
+        assert!(list_method(&vm_list, "push", &[Val::Int(2)]).is_err());
+        // A fresh VM list converts to a fresh, mutable list
+        let fresh = Val::List(Arc::new(Mutex::new(vec![Val::Int(1)])));
+        assert!(!VmCore::val_to_ax(&fresh).is_frozen());
+    }
+
+    /// Run `src` on a fresh VM and return it with its global table.
+    fn run_vm(src: &str) -> (VmCore, crate::compiler::GlobalTable) {
+        let items = crate::Parser::new(src, 0).parse().expect("parse");
+        let (proto, globals) = crate::compiler::compile_program(&items, "<test>");
+        let mut vm = VmCore::new(globals.names.len() + 64);
+        vm.run(Arc::new(proto)).expect("run");
+        (vm, globals)
+    }
+
+    fn global(vm: &VmCore, globals: &crate::compiler::GlobalTable, name: &str) -> Val {
+        let idx = globals.names.iter().position(|n| n == name).expect("global");
+        vm.get_global_at(idx)
+    }
+
+    #[test]
+    fn test_compiled_function_survives_round_trip() {
+        let (vm, globals) = run_vm("fun fact(n) { if n <= 1 { ret 1 } ret n * fact(n - 1) }");
+        let Val::Fun(original) = global(&vm, &globals, "fact") else { panic!("fact is not a function") };
+        let AxValue::Fun(callable) = vm.export(&Val::Fun(Arc::clone(&original))) else { panic!("fact did not export") };
+        let AxCallable::Native { func, .. } = callable.as_ref() else { panic!("expected a native wrapper") };
+        assert!(matches!(func(vec![AxValue::Num(5.0)]), Ok(AxValue::Num(n)) if n == 120.0));
+        match VmCore::ax_to_val(&AxValue::Fun(callable)) {
+            Val::Fun(back) => assert!(Arc::ptr_eq(&back, &original)),
+            other => panic!("expected a function, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_map_survives_round_trip() {
+        let mut entries = HashMap::new();
+        entries.insert(crate::intern::intern("x"), Val::Int(1));
+        let map = Arc::new(Mutex::new(entries));
+        let AxValue::Map(out) = VmCore::val_to_ax(&Val::Map(Arc::clone(&map))) else { panic!("map did not convert") };
+        assert!(matches!(out.get("x").map(|v| v.clone()), Some(AxValue::Num(n)) if n == 1.0));
+        match VmCore::ax_to_val(&AxValue::Map(out)) {
+            Val::Map(back) => assert!(matches!(back.lock().get("x"), Some(Val::Int(1)))),
+            other => panic!("expected a map, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_map_keys_from_data_are_not_interned() {
+        let map = dashmap::DashMap::new();
+        map.insert("data-key-never-interned".to_string(), AxValue::Num(1.0));
+        map.insert("const-key-shared".to_string(), AxValue::Num(2.0));
+        let shared = crate::intern::intern("const-key-shared");
+        let Val::Map(vm_map) = VmCore::ax_to_val(&AxValue::Map(Arc::new(map))) else { panic!("map did not convert") };
+        assert!(crate::intern::interner().lookup("data-key-never-interned").is_none());
+        let entries = vm_map.lock();
+        let (key, _) = entries.get_key_value("const-key-shared").unwrap();
+        assert!(Arc::ptr_eq(key, &shared));
+    }
+
+    /// Output of `src` on the given engine.
+    fn run_on(engine: crate::runtime::Engine, src: &str) -> String {
+        let items = crate::Parser::new(src, 0).parse().expect("parse");
+        let mut rt = crate::Runtime::new();
+        rt.set_engine(engine);
+        let (result, output) = crate::runtime::capture_output(|| rt.run(items));
+        result.expect("run");
+        output
+    }
+
+    /// Output of `src`, which both engines must agree on.
+    fn run_on_both(src: &str) -> String {
+        let interp = run_on(crate::runtime::Engine::Interp, src);
+        assert_eq!(run_on(crate::runtime::Engine::Vm, src), interp, "engines diverge");
+        interp
+    }
+
+    #[test]
+    fn test_vm_engine_matches_tree_walker() {
+        let src = r#"
+            fun make_adder(x) { ret fn(y) { ret x + y } }
+            let add5 = make_adder(5)
+            let scale = 3
+            fun scaled(n) { ret n * scale }
+            fun fib(n) {
+                if n <= 1 { ret n }
+                let a = 0
+                let b = 1
+                let i = 2
+                while i <= n {
+                    let t = a + b
+                    a = b
+                    b = t
+                    i = i + 1
+                }
+                ret b
+            }
+            let xs = alg.range(6)
+            xs.push(6)
+            out add5(10) " " scaled(4) " " str(fib(10))
+            out alg.filter(xs, fn(x) { ret x % 2 == 0 }) " " xs.len() " " "abc".len()
+            let m = col.new()
+            col.set(m, "b", xs)
+            col.set(m, "a", 1)
+            out m
+            fun main() { out "main" }
+        "#;
+        assert_eq!(run_on_both(src), "15 12 55\n[0, 2, 4, 6] 7 3\n{a: 1, b: [0, 1, 2, 3, 4, 5, 6]}\nmain\n");
+    }
+
+    #[test]
+    fn test_display_is_shared_and_cycle_safe() {
+        let src = "let l = [1, \"a\"]\nl.push(l)\nout l\nout [[true, nil], 1.5]\nout \"l=@l\"";
+        let expected = "[1, a, [...]]\n[[true, nil], 1.5]\nl=[1, a, [...]]\n";
+        assert_eq!(run_on_both(src), expected);
+
+        let mut deep = AxValue::Nil;
+        let mut vm_deep = Val::Nil;
+        for _ in 0..100 {
+            deep = ax_list(vec![deep]);
+            vm_deep = Val::List(Arc::new(Mutex::new(vec![vm_deep])));
+        }
+        let shown = deep.display();
+        assert_eq!(shown.matches('[').count(), crate::core::value::DISPLAY_MAX_DEPTH + 1);
+        assert!(shown.contains("[...]"));
+        assert_eq!(vm_deep.display(), shown);
+    }
+
+    #[test]
+    fn test_equality_and_sort_order_match_across_engines() {
+        let src = r#"
+            let n = sqrt(0 - 1)
+            out n == n, " ", 1 == 1.0, " ", [1, [2, "x"]] == [1, [2, "x"]], " ", [1, 2] != [2, 1], " ", [n] == [n]
+            let a = col.new()
+            col.set(a, "k", [1, 2])
+            let b = col.new()
+            col.set(b, "k", [1, 2])
+            out a == b, " ", a == col.new(), " ", [[1], 2].contains([1]), " ", "1" == 1
+            out alg.sort([3, "b", nil, 1.5, "a", true, [2], [1, 5], n, 0 - 2])
+        "#;
+        let expected = "false true true true false\ntrue false true false\n[nil, true, -2, 1.5, 3, NaN, a, b, [1, 5], [2]]\n";
+        assert_eq!(run_on_both(src), expected);
+
+        // Self-containing lists compare without recursing forever
+        let (x, y) = (ax_list(vec![AxValue::Num(1.0)]), ax_list(vec![AxValue::Num(1.0)]));
+        for l in [&x, &y] {
+            if let AxValue::Lst(inner) = l { inner.write().unwrap().push(l.clone()); }
+        }
+        assert!(x.deep_eq(&y));
+        let (vx, vy) = (VmCore::ax_to_val(&x), VmCore::ax_to_val(&y));
+        assert!(vx.eq_val(&vy));
+    }
+
+    #[test]
+    fn test_int_overflow_promotes_to_float() {
+        assert!(matches!(int_add(i64::MAX, 1), Val::Float(f) if f == i64::MAX as f64));
+        assert!(matches!(int_sub(i64::MIN, 1), Val::Float(f) if f == i64::MIN as f64));
+        assert!(matches!(int_mul(1 << 62, 4), Val::Float(f) if f == 1.8446744073709552e19));
+        assert!(matches!(int_add(40, 2), Val::Int(42)));
+        assert!(matches!(binop_mod(&Val::Int(i64::MIN), &Val::Int(-1)), Ok(Val::Int(0))));
+
+        let src = r#"
+            fun fib(n) {
+                let a = 0
+                let b = 1
+                let i = 0
+                while i < n {
+                    let t = a + b
+                    a = b
+                    b = t
+                    i = i + 1
+                }
+                ret a
+            }
+            out fib(93)
+            out fib(100) > fib(99)
+        "#;
+        let expected = "12200160415121877000\ntrue\n";
+        assert_eq!(run_on_both(src), expected);
+    }
+
+    #[test]
+    fn test_enums_match_across_engines() {
+        let src = r#"
+            enm Shape { Circle(r), Square(s), Empty }
+            fun area(sh) {
+                match sh {
+                    Circle(r) => { ret r * r * 3 }
+                    Shape.Square(s) => { ret s * s }
+                    _ => { ret 0 }
+                }
+            }
+            let shapes = [Shape.Circle(2), Shape.Square(3), Shape.Empty]
+            for s in shapes { out area(s) }
+            out shapes
+            out (Shape.Circle(2) == Shape.Circle(2)), (Shape.Circle(2) == Shape.Circle(3))
+            let mk = Shape.Square
+            out alg.map([1, 2], mk)
+            match Shape.Square([1]) {
+                5 => { out "five" }
+                Shape.Square(v) => { out "square ", v }
+                _ => { out "none" }
+            }
+        "#;
+        let items = crate::Parser::new(src, 0).parse().expect("parse");
+        assert_eq!(crate::compiler::vm_unsupported(&items), None);
+        let (proto, _) = crate::compiler::compile_program(&items, "<test>");
+        let area = &proto.protos[0];
+        assert_eq!(area.jump_tables.len(), 1);
+        assert_eq!(area.jump_tables[0].targets.len(), 2);
+
+        let expected = "12\n9\n0\n[Shape.Circle(2), Shape.Square(3), Shape.Empty]\ntruefalse\n[Shape.Square(1), Shape.Square(2)]\nsquare [1]\n";
+        assert_eq!(run_on_both(src), expected);
+    }
+
+    #[test]
+    fn test_static_members_across_engines() {
+        let src = r#"
+            cls Counter {
+                static let count = 0
+                static let label = "hits"
+                static fun bump(by) {
+                    Counter.count = Counter.count + by
+                    ret Counter.count
+                }
+                static fun describe() { ret Counter.label + "=" + Counter.count }
+            }
+            Counter.bump(2)
+            let n = Counter.bump(3)
+            out n
+            let show = Counter.describe
+            out show()
+        "#;
+        let items = crate::Parser::new(src, 0).parse().expect("parse");
+        assert_eq!(crate::compiler::vm_unsupported(&items), None);
+        // `Counter.bump(..)` calls the method's global directly
+        let (proto, _) = crate::compiler::compile_program(&items, "<test>");
+        assert!(proto.code.iter().all(|i| i.op() != Op::GetMethod));
+
+        let expected = "5\nhits=5\n";
+        assert_eq!(run_on_both(src), expected);
+
+        let items = crate::Parser::new("cls P { let x = 1 }", 0).parse().expect("parse");
+        assert_eq!(crate::compiler::vm_unsupported(&items), Some("classes"));
+    }
+
+    #[test]
+    fn test_runtime_with_conf_configures_both_engines() {
+        use crate::conf::{AxConf, Origin};
+        let src = "fun down(n) { if n == 0 { ret 0 } ret down(n - 1) }\nout down(20);";
+        for engine in ["interp", "vm"] {
+            let pairs = vec![("max_call_depth".to_string(), "8".to_string()), ("engine".to_string(), engine.to_string())];
+            let conf = AxConf::from_layers(vec![(Origin::Cli, pairs)]);
+            let items = crate::Parser::new(src, 0).parse().expect("parse");
+            let mut rt = crate::Runtime::with_conf(&conf);
+            let result = crate::runtime::capture_output(|| rt.run(items)).0;
+            assert!(matches!(result, Err(RuntimeError::StackOverflow { limit: 8, .. })), "{}: {:?}", engine, result);
+        }
+    }
+
+    #[test]
+    fn test_captured_output_includes_go_blocks() {
+        use crate::runtime::Engine;
+        for engine in [Engine::Interp, Engine::Vm] {
+            let lines = Arc::new(Mutex::new(Vec::new()));
+            let sink_lines = Arc::clone(&lines);
+            let items = crate::Parser::new("go { out \"from go\"; }\nout \"main\";", 0).parse().expect("parse");
+            let mut rt = crate::Runtime::new();
+            rt.set_engine(engine);
+            crate::runtime::with_output_sink(move |line| sink_lines.lock().push(line.to_string()), || rt.run(items)).expect("run");
+            // The go block may finish after the run does
+            let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+            while lines.lock().len() < 2 && std::time::Instant::now() < deadline {
+                std::thread::sleep(std::time::Duration::from_millis(1));
+            }
+            let mut printed = lines.lock().clone();
+            printed.sort();
+            assert_eq!(printed, ["from go", "main"], "{:?}", engine);
+        }
+        // A panic inside leaves no sink installed
+        let panicked = std::panic::catch_unwind(|| crate::runtime::with_output_sink(|_| {}, || panic!("boom")));
+        assert!(panicked.is_err() && !crate::runtime::output_captured());
+    }
+
+    #[test]
+    fn test_runtime_leaves_process_settings_alone() {
+        use crate::conf::{AxConf, Origin};
+        let pairs = vec![("sorted_maps".to_string(), "off".to_string()), ("stdout_buffer".to_string(), "block".to_string())];
+        let _rt = crate::Runtime::with_conf(&AxConf::from_layers(vec![(Origin::Cli, pairs)]));
+        assert!(sorted_maps(), "building a Runtime must not reset sorted_maps for the process");
+        assert_eq!(crate::runtime::output_buffering(), crate::runtime::OutputBuffering::Line);
+    }
+
+    #[test]
+    fn test_strict_vars_across_engines() {
+        use crate::runtime::Engine;
+        let run = |engine, strict, src: &str| {
+            let items = crate::Parser::new(src, 0).parse().expect("parse");
+            let mut rt = crate::Runtime::new();
+            rt.set_engine(engine);
+            rt.set_strict_vars(strict);
+            crate::runtime::capture_output(|| rt.run(items))
+        };
+        let typo = "let total = 1\nfun f() { ret totl }\nout f();";
+        assert_eq!(run(Engine::Vm, false, typo).1, "nil\n");
+        for engine in [Engine::Interp, Engine::Vm] {
+            match run(engine, true, typo).0 {
+                Err(RuntimeError::UndefinedVariable { name, candidates, .. }) => {
+                    assert_eq!(name, "totl");
+                    assert!(candidates.iter().any(|c| c == "total"), "{:?}", candidates);
+                }
+                other => panic!("{:?}: {:?}", engine, other),
+            }
+        }
+
+        // Globals bound to nil, hoisted functions and enums still read fine
+        let ok = "let n = nil\nout n;\nout g();\nfun g() { ret 2 }\nenm C { A }\nout C.A;";
+        for engine in [Engine::Interp, Engine::Vm] {
+            let (result, output) = run(engine, true, ok);
+            assert!(result.is_ok(), "{:?}: {:?}", engine, result);
+            assert_eq!(output, "nil\n2\nC.A\n");
+        }
+
+        // A name an earlier run only read has a slot but no binding
+        let mut rt = crate::Runtime::new();
+        rt.set_engine(Engine::Vm);
+        let first = crate::runtime::capture_output(|| rt.run(crate::Parser::new("out seen_not_set;", 0).parse().unwrap()));
+        assert_eq!(first.1, "nil\n");
+        rt.set_strict_vars(true);
+        let second = crate::runtime::capture_output(|| rt.run(crate::Parser::new("out seen_not_set;", 0).parse().unwrap())).0;
+        assert!(matches!(&second, Err(RuntimeError::UndefinedVariable { name, .. }) if name == "seen_not_set"), "{:?}", second);
+    }
+
+    #[test]
+    fn test_module_exports_across_engines() {
+        let dir = std::env::temp_dir().join(format!("axiom-exp-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let module = dir.join("shapes.ax");
+        std::fs::write(&module, "exp area, unit\nlet unit = 1\nlet scale = 3\nfun sq(x) { ret x * x * scale }\nfun area(scale) { ret sq(scale) }\n").unwrap();
+        let load = format!("load \"{}\"\n", module.display());
+        let src = format!("{}out area(2);\nout unit;", load);
+        assert_eq!(run_on_both(&src), "12\n1\n");
+
+        for hidden in ["sq(2)", "scale"] {
+            let items = crate::Parser::new(&format!("{}out {};", load, hidden), 0).parse().unwrap();
+            let result = crate::runtime::capture_output(|| crate::Runtime::new().run(items)).0;
+            assert!(matches!(result, Err(RuntimeError::UndefinedVariable { .. })), "{}: {:?}", hidden, result);
+        }
+    }
+
+    #[test]
+    fn test_import_aliases_across_engines() {
+        let dir = std::env::temp_dir().join(format!("axiom-aliases-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("helpers.ax"), "exp twice\nlet k = 2\nfun twice(x) { ret x * k }\n").unwrap();
+        // Each script imports `helpers` relative to itself and is loaded by path
+        let script = |name: &str, body: &str| {
+            let path = dir.join(format!("{}.ax", name));
+            std::fs::write(&path, body).unwrap();
+            format!("load \"{}\"", path.display())
+        };
+        let src = script("main", "loc .helpers as u\nloc .helpers { twice }\nstd mth { sqrt }\nstd mth as m\nout u.twice(3);\nout twice(4);\nout sqrt(16);\nout m.abs(-2);\n");
+        let output = run_on_both(&src);
+
+        let run = |src: String| {
+            let items = crate::Parser::new(&src, 0).parse().unwrap();
+            crate::runtime::capture_output(|| crate::Runtime::new().run(items)).0
+        };
+        let qualified_only = run(script("qualified", "loc .helpers\nout twice(1);\n"));
+        let not_exported = run(script("private", "loc .helpers { k }\n"));
+        let unknown_fn = run("std mth { nope }".to_string());
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(output, "6\n8\n4\n2\n");
+        assert!(matches!(qualified_only, Err(RuntimeError::UndefinedVariable { .. })), "{:?}", qualified_only);
+        assert!(matches!(not_exported, Err(RuntimeError::ImportError { .. })), "{:?}", not_exported);
+        assert!(matches!(unknown_fn, Err(RuntimeError::ImportError { .. })), "{:?}", unknown_fn);
+    }
+
+    #[test]
+    fn test_nested_and_relative_modules_across_engines() {
+        let dir = std::env::temp_dir().join(format!("axiom-nested-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("net/handlers")).unwrap();
+        std::fs::create_dir_all(dir.join("cyc")).unwrap();
+        std::fs::write(dir.join("app.ax"), "exp run\nloc .net.handlers.auth as auth\nloc .util { twice }\nfun run(x) { ret auth.check(twice(x)) }\n").unwrap();
+        std::fs::write(dir.join("util.ax"), "exp twice\nout \"util loaded\";\nfun twice(x) { ret x * 2 }\n").unwrap();
+        std::fs::write(dir.join("net/handlers/auth.ax"), "exp check\nloc ...util as u\nfun check(x) { ret u.twice(x) + 1 }\n").unwrap();
+        std::fs::write(dir.join("cyc/a.ax"), "loc .b\n").unwrap();
+        std::fs::write(dir.join("cyc/b.ax"), "loc .a\n").unwrap();
+
+        let src = format!("load \"{}\"\nout run(5);", dir.join("app.ax").display());
+        let output = run_on_both(&src);
+        let items = crate::Parser::new(&format!("load \"{}\"", dir.join("cyc/a.ax").display()), 0).parse().unwrap();
+        let cycle = crate::runtime::capture_output(|| crate::Runtime::new().run(items)).0;
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        // `util` is imported twice but runs once
+        assert_eq!(output, "util loaded\n21\n");
+        assert!(matches!(&cycle, Err(RuntimeError::CircularImport { chain }) if chain.len() == 3), "{:?}", cycle);
+    }
+
+    #[test]
+    fn test_feature_gated_imports_across_engines() {
+        let dir = std::env::temp_dir().join(format!("axiom-features-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("Axiomite.toml"), "[package]\nname = \"viz\"\nversion = \"0.1.0\"\n\n[features]\ndefault = [\"tui\"]\ntui = []\nplot = []\n").unwrap();
+        // `plot.ax` does not exist, so loading it would fail
+        std::fs::write(dir.join("app.ax"), "loc .ui if \"tui\"\nloc .plot if \"plot\"\nout \"tui @(pkg.feature(\"tui\")), plot @(pkg.feature(\"plot\"))\";\n").unwrap();
+        std::fs::write(dir.join("ui.ax"), "out \"ui loaded\";\n").unwrap();
+        std::fs::write(dir.join("bad.ax"), "out pkg.feature(\"gpu\");\n").unwrap();
+
+        let src = format!("load \"{}\"\nout pkg.feature(\"tui\");", dir.join("app.ax").display());
+        let output = run_on_both(&src);
+        let items = crate::Parser::new(&format!("load \"{}\"", dir.join("bad.ax").display()), 0).parse().unwrap();
+        let unknown = crate::runtime::capture_output(|| crate::Runtime::new().run(items)).0;
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        // The main program is in no package, so its features are all off
+        assert_eq!(output, "ui loaded\ntui true, plot false\nfalse\n");
+        assert!(unknown.is_err_and(|e| e.to_string().contains("has no feature 'gpu'")));
+    }
+
+    #[test]
+    fn test_super_calls_across_engines() {
+        let src = r#"
+            cls Animal {
+                let legs = 4
+                init(n) { self.name = n }
+                fun speak() { ret self.name + " speaks" }
+            }
+            cls Dog ext Animal {
+                init(n) { super.init(n + "!") }
+                fun speak() { ret super.speak() + " woof" }
+            }
+            cls Puppy ext Dog {
+                fun speak() { ret super.speak() + " yip" }
+            }
+            let p = new Puppy("Bo")
+            out p.speak();
+            out p.legs;
+        "#;
+        let expected = "Bo! speaks woof yip\n4\n";
+        assert_eq!(run_on_both(src), expected);
+    }
+
+    #[test]
+    fn test_accessors_across_engines() {
+        let src = r#"
+            cls Temp {
+                let c = 0
+                get f() { ret self.c * 9 / 5 + 32 }
+                set f(v) { self.c = (v - 32) * 5 / 9 }
+            }
+            let t = new Temp()
+            t.c = 100
+            out t.f;
+            t.f = 32
+            out t.c;
+        "#;
+        let expected = "212\n0\n";
+        assert_eq!(run_on_both(src), expected);
+    }
+
+    #[test]
+    fn test_interface_implements_across_engines() {
+        let src = r#"
+            ifc Shape { area() }
+            cls Sq impl Shape {
+                let side = 2
+                fun area() { ret self.side * self.side }
+            }
+            let s = new Sq()
+            out ann.implements(s, "Shape")
+            out ann.implements(s, "Named")
+            out ann.implements("Sq", "Shape")
+        "#;
+        let expected = "true\nfalse\nfalse\n";
+        assert_eq!(run_on_both(src), expected);
+    }
+
+    #[test]
+    fn test_match_expression_matches_across_engines() {
+        let src = r#"
+            enm Status { Active, Idle, Code(n) }
+            fun describe(s) {
+                let d = match s {
+                    Status.Code(n) => {
+                        let twice = n * 2
+                        "code " + twice
+                    }
+                    Status.Active => "active"
+                }
+                ret d
+            }
+            out describe(Status.Code(21)), " ", describe(Status.Active), " ", describe(Status.Idle)
+            out match 4 { 1 => "one", 4 => "four", n => n * 10 }
+            let sq = fn(v) { ret match v { 0 => "zero", n => n * n } }
+            out sq(0), " ", sq(5)
+        "#;
+        let expected = "code 42 active nil\nfour\nzero 25\n";
+        assert_eq!(run_on_both(src), expected);
+    }
+
+    #[test]
+    fn test_match_guards_and_alternatives_across_engines() {
+        let src = r#"
+            enm Status { Active, Idle, Code(n) }
+            fun classify(s, retries) {
+                ret match s {
+                    Status.Active if retries > 3 => "flaky"
+                    Status.Active | Status.Idle => "ok"
+                    Status.Code(n) if n >= 500 => "server " + n
+                    Status.Code(n) => "client " + n
+                }
+            }
+            out classify(Status.Active, 5), " ", classify(Status.Active, 1), " ", classify(Status.Idle, 9)
+            out classify(Status.Code(503), 0), " ", classify(Status.Code(404), 0)
+            fun small(v) { ret match v { 1 | 2 | 3 => "small", n if n < 0 => "negative", els => "big" } }
+            out small(2), " ", small(-4), " ", small(7)
+        "#;
+        let expected = "flaky ok ok\nserver 503 client 404\nsmall negative big\n";
+        assert_eq!(run_on_both(src), expected);
+    }
+
+    #[test]
+    fn test_range_and_list_patterns_across_engines() {
+        let src = r#"
+            fun grade(n) { ret match n { 90..101 => "A", 0..90 => "low", -10..0 => "neg", els => "?" } }
+            out grade(95), " ", grade(42), " ", grade(-3), " ", grade("x"), " ", grade(101)
+            fun shape(xs) {
+                ret match xs {
+                    [] => "empty",
+                    [only] => "one " + only,
+                    [first, 2 | 3, ..rest] => "pair " + first + " +" + alg.len(rest),
+                    [first, ..] => "many " + first,
+                    els => "not a list",
+                }
+            }
+            out shape([]), " ", shape([7]), " ", shape([1, 3, 4, 5]), " ", shape([9, 9]), " ", shape(5)
+            match [1, [2, 3]] {
+                [a, [b, c]] if a < b => { out a + b + c }
+                els => { out "no" }
+            }
+        "#;
+        let expected = "A low neg ? ?\nempty one 7 pair 1 +2 many 9 not a list\n6\n";
+        assert_eq!(run_on_both(src), expected);
+    }
+
+    #[test]
+    fn test_large_list_literal_and_reserve_across_engines() {
+        let items: Vec<String> = (0..300).map(|i| if i % 100 == 0 { "xs[0]".to_string() } else { i.to_string() }).collect();
+        let src = format!(r#"
+            let xs = [7]
+            xs = [{}]
+            out alg.len(xs), " ", xs[0], " ", xs[100], " ", xs[299]
+            let ys = []
+            ys.reserve(1000)
+            let i = 0
+            while i < 1000 {{ ys.push(i); i = i + 1 }}
+            out alg.len(ys), " ", ys[999]
+            let m = col.with_capacity(64)
+            col.set(m, "k", 1)
+            out col.len(m)
+        "#, items.join(", "));
+        let expected = "300 7 7 299\n1000 999\n1\n";
+        assert_eq!(run_on_both(&src), expected);
+        let items = crate::Parser::new(&src, 0).parse().expect("parse");
+        let (proto, _) = crate::compiler::compile_program(&items, "<test>");
+        assert!(proto.code.iter().any(|i| i.op() == Op::ListReserve));
+        assert_eq!(proto.code.iter().filter(|i| i.op() == Op::ListExtend).count(), 5);
+    }
+
+    #[test]
+    fn test_native_panic_becomes_internal_error() {
+        let (mut vm, globals) = run_vm("fun apply(f, x) { ret f(x) + 1 }");
+        let Val::Fun(apply) = global(&vm, &globals, "apply") else { panic!("apply is not a function") };
+        let boom = VmCore::ax_to_val(&AxValue::Fun(Arc::new(AxCallable::native("test.boom", |_| -> Result<AxValue, RuntimeError> {
+            panic!("index out of range")
+        }))));
+        let id = VmCore::ax_to_val(&AxValue::Fun(Arc::new(AxCallable::native("test.id", |mut args: Vec<AxValue>| {
+            Ok(args.pop().unwrap_or(AxValue::Nil))
+        }))));
+        match vm.call(&apply, vec![boom, Val::Int(1)]) {
+            Err(RuntimeError::Internal { function, message }) => {
+                assert_eq!(function, "test.boom");
+                assert_eq!(message, "index out of range");
+            }
+            other => panic!("expected an internal error, got {:?}", other.map(|v| v.to_string())),
+        }
+        // The VM is idle again and takes the next call
+        assert_eq!(vm.call(&apply, vec![id, Val::Int(41)]).unwrap().to_string(), "42");
+    }
+
+    #[test]
+    fn test_native_wrapper_is_reused() {
+        let f = AxValue::Fun(Arc::new(AxCallable::native("test.id", |mut args: Vec<AxValue>| {
+            Ok(args.pop().unwrap_or(AxValue::Nil))
+        })));
+        match (VmCore::ax_to_val(&f), VmCore::ax_to_val(&f)) {
+            (Val::Fun(a), Val::Fun(b)) => assert!(Arc::ptr_eq(&a, &b)),
+            other => panic!("expected functions, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_globals_keep_their_slots_across_runs() {
+        use crate::runtime::Engine;
+        let run = |rt: &mut crate::Runtime, src: &str| {
+            let items = crate::Parser::new(src, 0).parse().expect("parse");
+            let (result, output) = crate::runtime::capture_output(|| rt.run(items));
+            result.expect("run");
+            output
+        };
+        for engine in [Engine::Interp, Engine::Vm] {
+            let mut rt = crate::Runtime::new();
+            rt.set_engine(engine);
+            run(&mut rt, "fun twice(x) { ret x * 2 }\nfun label() { ret \"first\" }");
+            let slot = rt.globals.slot("twice").expect("twice is bound");
+            assert_eq!(rt.globals.table().get("twice"), Some(slot as u16));
+            assert_eq!(run(&mut rt, "out label(); out twice(21)"), "first\n42\n");
+            run(&mut rt, "fun label() { ret \"second\" }");
+            assert_eq!(run(&mut rt, "out label(); out alg.len([1, 2])"), "second\n2\n");
+            assert_eq!(rt.globals.slot("twice"), Some(slot));
+        }
+    }
+
+    #[test]
+    fn test_fused_superinstructions_match_tree_walker() {
+        use crate::runtime::Engine;
+        let src = r#"
+            fun count(n) {
+                let i = 0
+                let s = "x"
+                while i < n { i = i + 1; s = s + 1 }
+                ret s
+            }
+            fun first(a, b) { let m = a; ret m }
+            out count(3); out first("a", "b"); out 2.5 + 1
+        "#;
+        run_on_both(src);
+        let mut rt = crate::Runtime::new();
+        rt.set_engine(Engine::Vm);
+        let (result, _) = crate::runtime::capture_output(|| rt.run(crate::Parser::new(src, 0).parse().unwrap()));
+        result.unwrap();
+        let stats = rt.fusion_report().expect("fusion is on by default");
+        assert!(stats.add_int_imm >= 2 && stats.cmp_lt_jmp >= 1 && stats.move_return >= 1, "{:?}", stats);
+    }
+
+    #[test]
+    fn test_property_cache_sees_map_updates() {
+        let src = r#"
+            let cfg = col.new_map()
+            cfg.rate = 2
+            let other = col.new_map()
+            other.rate = 100
+            fun total(n) {
+                let i = 0
+                let t = 0
+                while i < n {
+                    t = t + cfg.rate
+                    if i == 5 { cfg.rate = 3 }
+                    i = i + 1
+                }
+                ret t
+            }
+            fun rate(m) { ret m.rate }
+            let a = total(10)
+            let b = rate(cfg) + rate(other)
+            out a; out b; out rate(other)
+        "#;
+        assert_eq!(run_on_both(src), "24\n103\n100\n");
+
+        // Run on a bare VM to read its caches, seeded with the intrinsics
+        // (`out` needs the runtime, so that line is left out)
+        let items = crate::Parser::new(&src.replace("out a; out b; out rate(other)", ""), 0).parse().expect("parse");
+        let (proto, table) = crate::compiler::compile_program(&items, "<test>");
+        let mut intrinsics = crate::runtime::Globals::default();
+        crate::intrinsics::register(&mut intrinsics);
+        let mut vm = VmCore::new(table.names.len() + 64);
+        for (idx, name) in table.names.iter().enumerate() {
+            if let Some(value) = intrinsics.get(name) { vm.set_global_at(idx, VmCore::ax_to_val(value)); }
+        }
+        let (result, _) = crate::runtime::capture_output(|| vm.run(Arc::new(proto)));
+        result.expect("run");
+        let stats = vm.prop_ic_stats();
+        assert!(stats.hits >= 8, "{:?}", stats);
+        assert_eq!(stats.polymorphic, 1, "{:?}", stats);
+    }
+
+    #[test]
+    fn test_scoped_locals_free_their_registers() {
+        let src = r#"
+            fun f() {
+                let x = 1
+                if true { let x = 2; out x }
+                out x
+                for x in [5, 6] { out x }
+                out x
+            }
+            f()
+        "#;
+        assert_eq!(run_on_both(src), "2\n1\n5\n6\n1\n");
+
+        let frame_size = |body: &str| {
+            let items = crate::Parser::new(&format!("fun f(n) {{ {} }}", body), 0).parse().expect("parse");
+            let (proto, _) = crate::compiler::compile_program(&items, "<test>");
+            proto.protos[0].reg_count
+        };
+        let one = frame_size("if n { let a = n + 1; let b = a * 2 }");
+        let three = frame_size("if n { let a = n + 1; let b = a * 2 } if n { let c = n - 1; let d = c * 2 } while n < 0 { let e = n }");
+        assert_eq!(one, three);
+    }
+
+    #[test]
+    fn test_returned_frames_recycle_their_registers() {
+        let (vm, globals) = run_vm(r#"
+            fun fib(n) { if n < 2 { ret n } ret fib(n - 1) + fib(n - 2) }
+            fun sum(n, acc) { if n == 0 { ret acc } ret sum(n - 1, acc + n) }
+            fun pair(x) { let a = [x]; let b = a; ret b }
+            let f = fib(15)
+            let s = sum(100, 0)
+            let p = pair(pair(nil))
+        "#);
+        assert!(matches!(global(&vm, &globals, "f"), Val::Int(610)));
+        assert!(matches!(global(&vm, &globals, "s"), Val::Int(5050)));
+        assert_eq!(global(&vm, &globals, "p").display(), "[[nil]]");
+        assert!(!vm.reg_pool.is_empty() && vm.reg_pool.len() <= REG_POOL_MAX);
+        assert!(vm.reg_pool.iter().all(Vec::is_empty));
+    }
+}
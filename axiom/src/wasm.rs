@@ -0,0 +1,75 @@
+//! Browser entry point — the API an in-browser playground calls.
+//!
+//! Build for the web with the host-only intrinsics (git, sys, tui, usb, ffi)
+//! compiled out:
+//!
+//!   cargo rustc --lib --crate-type cdylib --target wasm32-unknown-unknown \
+//!       --no-default-features --features wasm --release
+//!   wasm-bindgen --target web --out-dir pkg \
+//!       target/wasm32-unknown-unknown/release/axiom.wasm
+//!
+//! `run_source` is plain Rust on every other target, so embedders and tests
+//! get the same sandboxed behaviour.
+
+use crate::diagnostics::DiagnosticEngine;
+use crate::runtime::Runtime;
+use crate::sandbox::{Capabilities, ResourceLimits};
+use crate::Parser;
+
+/// Instruction budget for one snippet. There is no wall-clock limit because
+/// `Instant::now` is unavailable on wasm32-unknown-unknown.
+pub const PLAYGROUND_MAX_INSTRUCTIONS: u64 = 50_000_000;
+pub const PLAYGROUND_MAX_ALLOC_BYTES: usize = 64 * 1024 * 1024;
+
+/// The limits `run_source` applies.
+pub fn playground_limits() -> ResourceLimits {
+    ResourceLimits::unlimited()
+        .with_max_instructions(PLAYGROUND_MAX_INSTRUCTIONS)
+        .with_max_alloc_bytes(PLAYGROUND_MAX_ALLOC_BYTES)
+}
+
+/// Parse and run `src` with no capabilities and the playground limits.
+/// Returns everything the script printed, followed by the rendered
+/// diagnostic (uncoloured) if parsing or execution failed.
+#[cfg_attr(feature = "wasm", wasm_bindgen::prelude::wasm_bindgen)]
+pub fn run_source(src: &str) -> String {
+    let engine = DiagnosticEngine::new("playground.ax", src);
+    let items = match Parser::new(src, 0).parse() {
+        Ok(items) => items,
+        Err(e) => return engine.format_diagnostic_plain(&engine.from_parser(&e)),
+    };
+    let mut rt = Runtime::new();
+    rt.set_limits(playground_limits());
+    rt.set_capabilities(Capabilities::pure());
+    let (result, mut output) = rt.run_captured(items);
+    if let Err(e) = result {
+        output.push_str(&engine.format_diagnostic_plain(&engine.from_runtime(&e)));
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_source_captures_output_and_errors() {
+        assert_eq!(run_source("let x = 2\nout x * 21\nprint(\"hi\")"), "42\nhi\n");
+
+        let out = run_source("out 1\nlet s = ioo.read(\"/etc/hostname\")");
+        assert!(out.starts_with("1\n"), "{}", out);
+        assert!(out.contains("AXM_506"), "{}", out);
+
+        assert!(run_source("let = 1").contains("AXM_101"));
+        assert!(run_source("let s = \"x\"\nwhile true { s = s + s }").contains("AXM_409"));
+    }
+
+    #[test]
+    fn test_map_output_is_in_key_order() {
+        let src = "let m = col.new()\nfor k in [\"pear\", \"apple\", \"fig\", \"kiwi\"] { col.set(m, k, k.len()) }\nout m\nout col.keys(m)\nout col.values(m)";
+        let expected = "{apple: 5, fig: 3, kiwi: 4, pear: 4}\n[apple, fig, kiwi, pear]\n[5, 3, 4, 4]\n";
+        for _ in 0..5 {
+            assert_eq!(run_source(src), expected);
+        }
+    }
+}
@@ -0,0 +1,89 @@
+#!/usr/bin/env python3
+"""Time each program in benches/programs/ through the axiom binary, on the
+VM and on the tree-walker, next to the same program in other languages
+(a sibling .py, .js or .lua file, when that interpreter is installed).
+
+Prints a Markdown table of the best wall-clock time per program, in
+milliseconds, with each column's ratio to the VM. Times include process
+startup, unlike `cargo bench --bench engines`. Every run of a program must
+print the same output, or the script stops.
+
+    cargo build --release
+    python3 benches/compare.py [--runs 5] [--axiom target/release/axiom]
+"""
+
+import argparse
+import shutil
+import subprocess
+import sys
+import time
+from pathlib import Path
+
+ROOT = Path(__file__).resolve().parent.parent
+PROGRAMS = ROOT / "benches" / "programs"
+
+# Other languages: file extension -> interpreter command
+OTHERS = {".py": ["python3"], ".js": ["node"], ".lua": ["lua"]}
+
+
+def best_time(cmd, runs):
+    """Fastest of `runs` runs in milliseconds, and what the command printed."""
+    best, output = None, None
+    for _ in range(runs):
+        start = time.perf_counter()
+        done = subprocess.run(cmd, capture_output=True, text=True, cwd=PROGRAMS)
+        elapsed = (time.perf_counter() - start) * 1000
+        if done.returncode != 0:
+            sys.exit(f"{' '.join(cmd)} failed:\n{done.stderr}")
+        best = elapsed if best is None else min(best, elapsed)
+        output = done.stdout
+    return best, output
+
+
+def main():
+    parser = argparse.ArgumentParser(description=__doc__, formatter_class=argparse.RawDescriptionHelpFormatter)
+    parser.add_argument("--runs", type=int, default=5, help="runs per program and engine (best is kept)")
+    parser.add_argument("--axiom", type=Path, default=ROOT / "target" / "release" / "axiom", help="axiom binary")
+    args = parser.parse_args()
+    args.axiom = args.axiom.resolve()
+    if not args.axiom.exists():
+        sys.exit(f"{args.axiom} not found; run `cargo build --release` first")
+
+    others = [(ext, cmd) for ext, cmd in OTHERS.items()
+              if shutil.which(cmd[0]) and any(PROGRAMS.glob("*" + ext))]
+    columns = ["vm", "interp"] + [cmd[0] for _, cmd in others]
+    print("| program | " + " | ".join(columns) + " |")
+    print("|---" * (len(columns) + 1) + "|")
+
+    for program in sorted(PROGRAMS.glob("*.ax")):
+        runs = {
+            "vm": [str(args.axiom), "run", program.name, "--engine", "vm"],
+            "interp": [str(args.axiom), "run", program.name, "--engine", "interp"],
+        }
+        for ext, cmd in others:
+            sibling = program.with_suffix(ext)
+            if sibling.exists():
+                runs[cmd[0]] = cmd + [sibling.name]
+
+        times, expected = {}, None
+        for column, cmd in runs.items():
+            ms, output = best_time(cmd, args.runs)
+            if expected is None:
+                expected = output
+            elif output != expected:
+                sys.exit(f"{program.stem}: {column} printed {output!r}, vm printed {expected!r}")
+            times[column] = ms
+
+        cells = []
+        for column in columns:
+            if column not in times:
+                cells.append("—")
+            elif column == "vm":
+                cells.append(f"{times['vm']:.1f}")
+            else:
+                cells.append(f"{times[column]:.1f} ({times[column] / times['vm']:.1f}x)")
+        print(f"| {program.stem} | " + " | ".join(cells) + " |")
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,49 @@
+//! Engine benchmarks (`cargo bench --bench engines`)
+//!
+//! Runs each program in `benches/programs/` on the tree-walker and on the
+//! VM. Parsing and `Runtime` setup happen outside the timed part, so a
+//! sample covers compiling (on the VM) and running the program. Output is
+//! captured rather than printed. `benches/compare.py` times the same
+//! programs through the `axiom` binary next to other languages.
+
+use std::path::Path;
+
+use axiom::runtime::{capture_output, Engine};
+use axiom::{Item, Parser, Runtime};
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+
+const PROGRAMS: &[&str] = &["fib", "loop_sum", "string_concat", "list_sort", "map_access", "method_dispatch"];
+
+fn parse(name: &str) -> Vec<Item> {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("benches/programs").join(format!("{}.ax", name));
+    let source = std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("Cannot read '{}': {}", path.display(), e));
+    Parser::new(&source, 0).parse().unwrap_or_else(|e| panic!("'{}' does not parse: {:?}", path.display(), e))
+}
+
+fn engines(c: &mut Criterion) {
+    for name in PROGRAMS {
+        let items = parse(name);
+        let mut group = c.benchmark_group(*name);
+        for (label, engine) in [("interp", Engine::Interp), ("vm", Engine::Vm)] {
+            group.bench_with_input(BenchmarkId::from_parameter(label), &engine, |b, &engine| {
+                b.iter_batched(
+                    || {
+                        let mut runtime = Runtime::new();
+                        runtime.set_engine(engine);
+                        (runtime, items.clone())
+                    },
+                    |(mut runtime, items)| {
+                        let (result, output) = capture_output(|| runtime.run(items));
+                        result.unwrap_or_else(|e| panic!("{} failed on {}: {:?}", name, label, e));
+                        output
+                    },
+                    BatchSize::SmallInput,
+                );
+            });
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, engines);
+criterion_main!(benches);
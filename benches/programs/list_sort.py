@@ -0,0 +1,12 @@
+def fill(n):
+    xs = []
+    x = 1
+    i = 0
+    while i < n:
+        x = (x * 75 + 74) % 65537
+        xs.append(x)
+        i = i + 1
+    return xs
+
+xs = sorted(fill(5000))
+print(xs[0] + xs[4999])
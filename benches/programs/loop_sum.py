@@ -0,0 +1,9 @@
+def sum_to(n):
+    i = 0
+    t = 0
+    while i < n:
+        t = t + i
+        i = i + 1
+    return t
+
+print(sum_to(200000))
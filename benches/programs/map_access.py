@@ -0,0 +1,9 @@
+def run(n):
+    p = {"x": 0, "step": 3}
+    i = 0
+    while i < n:
+        p["x"] = p["x"] + p["step"]
+        i = i + 1
+    return p["x"]
+
+print(run(20000))
@@ -0,0 +1,14 @@
+class Counter:
+    def step(self, x):
+        return x + 1
+
+def run(n):
+    counter = Counter()
+    t = 0
+    i = 0
+    while i < n:
+        t = counter.step(t)
+        i = i + 1
+    return t
+
+print(run(20000))
@@ -0,0 +1,9 @@
+def build(n):
+    s = ""
+    i = 0
+    while i < n:
+        s = s + "x" + str(i)
+        i = i + 1
+    return len(s)
+
+print(build(2000))